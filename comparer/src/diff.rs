@@ -0,0 +1,147 @@
+//! Модуль вывода различающихся транзакций.
+//!
+//! По умолчанию для каждой пары различающихся транзакций выводятся только различающиеся поля.
+//! С параметром `--color` пара выводится в виде унифицированного diff всех полей, выровненных
+//! по ширине названий: значения из первого файла отмечаются `-` (красным), из второго — `+`
+//! (зеленым), совпадающие поля выводятся без отметки.
+
+use clap::ValueEnum;
+use parser::{FieldDiff, Record, RecordKey};
+use std::io::{self, IsTerminal, Write};
+
+/// Начало выделения красным цветом.
+const RED: &str = "\x1b[31m";
+
+/// Начало выделения зеленым цветом.
+const GREEN: &str = "\x1b[32m";
+
+/// Начало выделения полужирным шрифтом.
+const BOLD: &str = "\x1b[1m";
+
+/// Окончание выделения.
+const RESET: &str = "\x1b[0m";
+
+/// Когда выделять различия цветом.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ColorWhen {
+    /// Only when printing to a terminal and `NO_COLOR` is not set
+    Auto,
+
+    /// Always
+    Always,
+
+    /// Never (the diff layout is kept)
+    Never,
+}
+
+/// Транзакция одного из сравниваемых файлов.
+pub struct Side<'a> {
+    /// Название файла.
+    pub file: &'a str,
+
+    /// Порядковый номер транзакции в файле (начиная с 1).
+    pub number: usize,
+
+    /// Транзакция.
+    pub record: &'a Record,
+}
+
+/// Способ вывода различающихся транзакций.
+pub struct DiffPrinter {
+    /// Признак вывода в виде унифицированного diff всех полей.
+    unified: bool,
+
+    /// Признак выделения различий цветом.
+    color: bool,
+}
+
+impl DiffPrinter {
+    /// Создание способа вывода по значению параметра `--color` (`None`, если он не указан).
+    pub fn new(color: Option<ColorWhen>) -> Self {
+        Self {
+            unified: color.is_some(),
+            color: match color {
+                Some(ColorWhen::Always) => true,
+                Some(ColorWhen::Auto) => {
+                    std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+                }
+                Some(ColorWhen::Never) | None => false,
+            },
+        }
+    }
+
+    /// Вывести заголовок и различия пары транзакций в указанное назначение.
+    pub fn print<W: Write>(
+        &self,
+        header: &str,
+        expected: Side,
+        actual: Side,
+        diffs: &[FieldDiff],
+        out: &mut W,
+    ) -> io::Result<()> {
+        if !self.unified {
+            writeln!(out, "{header}")?;
+
+            for diff in diffs {
+                writeln!(out, "    {diff}")?;
+            }
+
+            return Ok(());
+        }
+
+        writeln!(out, "{}", self.paint(BOLD, header))?;
+        writeln!(
+            out,
+            "{}",
+            self.paint(RED, &format!("--- {} #{}", expected.file, expected.number))
+        )?;
+        writeln!(
+            out,
+            "{}",
+            self.paint(GREEN, &format!("+++ {} #{}", actual.file, actual.number))
+        )?;
+
+        let width = Record::EXPECTED_KEYS
+            .iter()
+            .chain(&Record::OPTIONAL_KEYS)
+            .map(|key| key.to_string().len())
+            .max()
+            .unwrap_or(0);
+
+        for key in Record::EXPECTED_KEYS
+            .into_iter()
+            .chain(Record::OPTIONAL_KEYS)
+        {
+            // Отображение ключа не учитывает ширину поля форматирования.
+            let name = key.to_string();
+
+            match diffs.iter().find(|diff| diff.key == key) {
+                Some(diff) => {
+                    let line = format!("- {name:<width$}  {}", diff.expected);
+                    writeln!(out, "{}", self.paint(RED, &line))?;
+
+                    let line = format!("+ {name:<width$}  {}", diff.actual);
+                    writeln!(out, "{}", self.paint(GREEN, &line))?;
+                }
+                // Отсутствующая в обеих транзакциях валюта не выводится.
+                None if key == RecordKey::Currency && expected.record.currency().is_none() => {}
+                None => writeln!(
+                    out,
+                    "  {name:<width$}  {}",
+                    expected.record.field_to_string(key)
+                )?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Выделить строку указанным стилем, если выделение цветом включено.
+    fn paint(&self, style: &str, line: &str) -> String {
+        if self.color {
+            format!("{style}{line}{RESET}")
+        } else {
+            line.to_string()
+        }
+    }
+}
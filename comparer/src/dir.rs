@@ -0,0 +1,121 @@
+//! Модуль сравнения файлов двух каталогов, сопоставленных по имени.
+
+use super::report::{DirSummary, FileComparison, FileStatus};
+use super::{Args, CliError, ReportFormat, compare_files, print_report};
+use std::collections::BTreeSet;
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+
+/// Имена файлов каталога (кроме скрытых, начинающихся с `.`), отсортированные по возрастанию.
+fn file_names(dir: &Path) -> Result<BTreeSet<String>, CliError> {
+    let mut names = BTreeSet::new();
+
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+
+        if !name.starts_with('.') && entry.path().is_file() {
+            names.insert(name);
+        }
+    }
+
+    Ok(names)
+}
+
+/// Сравнить файлы с одинаковыми именами из двух каталогов, определяя их форматы по содержимому,
+/// и вывести результаты сравнения каждой пары файлов и сводку в указанное назначение.
+///
+/// Ошибка сравнения одной пары файлов не прерывает сравнение остальных; если какую-либо пару
+/// не удалось сравнить, возвращается ошибка [`CliError::DirFailed`].
+pub fn compare_dirs<W: Write>(
+    args: &Args,
+    dir1: &Path,
+    dir2: &Path,
+    out: &mut W,
+) -> Result<(), CliError> {
+    let names1 = file_names(dir1)?;
+    let names2 = file_names(dir2)?;
+
+    let text = matches!(args.report, ReportFormat::Text);
+    let mut files = vec![];
+
+    for name in names1.union(&names2) {
+        let status = match (names1.contains(name), names2.contains(name)) {
+            (true, false) => Some(FileStatus::OnlyInDir1),
+            (false, true) => Some(FileStatus::OnlyInDir2),
+            _ => None,
+        };
+
+        if let Some(status) = status {
+            files.push(FileComparison {
+                name: name.clone(),
+                status,
+                report: None,
+                error: None,
+            });
+            continue;
+        }
+
+        let input1 = (dir1.join(name), "auto".to_string());
+        let input2 = (dir2.join(name), "auto".to_string());
+        let file1 = display(&input1.0);
+        let file2 = display(&input2.0);
+
+        if text {
+            writeln!(out, "Comparing `{file1}` with `{file2}`:")?;
+        }
+
+        let comparison = match compare_files(args, (&file1, &input1), (&file2, &input2), out) {
+            Ok(report) => FileComparison {
+                name: name.clone(),
+                status: if report.identical {
+                    FileStatus::Identical
+                } else {
+                    FileStatus::Different
+                },
+                report: Some(report),
+                error: None,
+            },
+            // Ошибка вывода прерывает сравнение остальных пар файлов.
+            Err(CliError::Io(e)) if e.kind() == ErrorKind::BrokenPipe => return Err(e.into()),
+            Err(e) => {
+                if text {
+                    writeln!(out, "{e}")?;
+                }
+
+                FileComparison {
+                    name: name.clone(),
+                    status: FileStatus::Failed,
+                    report: None,
+                    error: Some(e.to_string()),
+                }
+            }
+        };
+
+        if text {
+            writeln!(out)?;
+        }
+
+        files.push(comparison);
+    }
+
+    let summary = DirSummary::new(display(dir1), display(dir2), files);
+
+    if text {
+        write!(out, "{summary}")?;
+    } else {
+        print_report(&summary, out)?;
+    }
+
+    out.flush()?;
+
+    match summary.count(FileStatus::Failed) {
+        0 => Ok(()),
+        failed => Err(CliError::DirFailed(failed)),
+    }
+}
+
+/// Путь в виде строки для вывода.
+fn display(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
@@ -0,0 +1,79 @@
+//! Модуль журналирования в stderr при помощи `tracing`.
+//!
+//! Журнал выводится в виде текста или, для сбора журналов в Kubernetes и подобных средах,
+//! в виде JSON-объектов по одному на строку.
+
+use clap::ValueEnum;
+use std::io::IsTerminal;
+use tracing::Level;
+
+/// Минимальный уровень журналируемых событий.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum LogLevel {
+    /// Errors only
+    Error,
+
+    /// Errors and skipped or invalid records
+    Warn,
+
+    /// Per-file summaries with records/sec
+    Info,
+
+    /// Detailed diagnostics
+    Debug,
+
+    /// Everything
+    Trace,
+}
+
+/// Реализация трейта [`From<LogLevel>`] для [`Level`].
+impl From<LogLevel> for Level {
+    /// Реализация метода [`From<LogLevel>::from`] для [`Level`].
+    fn from(value: LogLevel) -> Self {
+        match value {
+            LogLevel::Error => Level::ERROR,
+            LogLevel::Warn => Level::WARN,
+            LogLevel::Info => Level::INFO,
+            LogLevel::Debug => Level::DEBUG,
+            LogLevel::Trace => Level::TRACE,
+        }
+    }
+}
+
+// Параметры журналирования, общие для всех подкоманд.
+#[derive(clap::Args, Debug)]
+pub struct LogArgs {
+    /// Log events of this or a more severe level to stderr
+    #[arg(long, global = true, value_enum, value_name = "LEVEL")]
+    log_level: Option<LogLevel>,
+
+    /// Log events to stderr as JSON lines (at the `info` level unless `--log-level` is given)
+    #[arg(long, global = true)]
+    log_json: bool,
+}
+
+impl LogArgs {
+    /// Установить глобальный обработчик событий, если журналирование включено.
+    ///
+    /// Возвращает признак включения журналирования.
+    pub fn init(&self) -> bool {
+        let level = match (self.log_level, self.log_json) {
+            (Some(level), _) => level,
+            (None, true) => LogLevel::Info,
+            (None, false) => return false,
+        };
+
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(Level::from(level))
+            .with_writer(std::io::stderr)
+            .with_ansi(std::io::stderr().is_terminal());
+
+        if self.log_json {
+            subscriber.json().flatten_event(true).init();
+        } else {
+            subscriber.init();
+        }
+
+        true
+    }
+}
@@ -1,28 +1,146 @@
-use clap::Parser;
+use clap::{CommandFactory, Parser, ValueEnum, error::ErrorKind};
+use diff::{ColorWhen, DiffPrinter, Side};
 use parser::{
-    YPBankImpl,
+    FieldDiff, Record, RecordComparator, RecordMask, YPBankImpl,
+    compression::decompress,
     errors::{FormatError, ReadError, WriteError},
+    reconcile::{FuzzyMatch, MatchKind, ReconcileOptions, Reconciliation, reconcile_records},
 };
+use report::{FieldReport, MismatchReport, Report, Summary};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
+mod diff;
+mod dir;
+#[cfg(feature = "tracing")]
+mod logging;
+mod report;
+
+/// Способ сопоставления транзакций при сравнении.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum CompareBy {
+    /// Compare transactions by their position in the files
+    Position,
+
+    /// Compare transactions with the same TX_ID regardless of their order
+    #[value(name = "tx_id")]
+    TxId,
+}
+
+/// Формат вывода результата сравнения.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ReportFormat {
+    /// Human-readable text
+    Text,
+
+    /// Machine-readable JSON report
+    Json,
+}
+
+/// Способ выбора пар файлов для сравнения.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Against {
+    /// Compare every file with the first one
+    First,
+
+    /// Compare every pair of files
+    Pairwise,
+}
+
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(
+    version,
+    about = "Compare YPBank transaction data of two or more files or directories",
+    long_about = None
+)]
 struct Args {
-    /// First file to read
-    #[arg(long, value_name = "FILE")]
-    file1: std::path::PathBuf,
+    /// First file to read (`-` to read from stdin)
+    #[arg(long, value_name = "FILE", conflicts_with = "dir1")]
+    file1: Option<std::path::PathBuf>,
+
+    /// Data format in the first file to read (detected by the file content if omitted or `auto`)
+    #[clap(long, value_name = "FORMAT", requires = "file1")]
+    format1: Option<String>,
+
+    /// Second file to read (`-` to read from stdin)
+    #[arg(long, value_name = "FILE", conflicts_with = "dir1")]
+    file2: Option<std::path::PathBuf>,
+
+    /// Data format in the second file to read (detected by the file content if omitted or `auto`)
+    #[clap(long, value_name = "FORMAT", requires = "file2")]
+    format2: Option<String>,
+
+    /// Additional file to read (`-` to read from stdin); can be repeated
+    ///
+    /// Either each one needs its own `--format`, or all formats are detected by the file content
+    #[arg(long = "file", value_name = "FILE", conflicts_with = "dir1")]
+    files: Vec<std::path::PathBuf>,
+
+    /// Data format in the additional file to read (in the same order as `--file`)
+    #[clap(long = "format", value_name = "FORMAT")]
+    formats: Vec<String>,
+
+    /// First directory whose files are compared with the files of the same name in `--dir2`
+    /// (formats are detected by the file content)
+    #[arg(long, value_name = "DIR", requires = "dir2")]
+    dir1: Option<PathBuf>,
+
+    /// Second directory to compare with `--dir1`
+    #[arg(long, value_name = "DIR", requires = "dir1")]
+    dir2: Option<PathBuf>,
+
+    /// Which pairs of files to compare when more than two files are given
+    #[arg(long, value_enum, default_value_t = Against::First)]
+    against: Against,
+
+    /// How to match transactions of the two files
+    #[arg(long, value_enum, default_value_t = CompareBy::Position, conflicts_with = "reconcile")]
+    by: CompareBy,
+
+    /// Comma-separated fields to exclude from comparison (e.g. `timestamp,description`)
+    #[arg(long, value_name = "FIELDS", value_parser = |s: &str| RecordMask::try_from(s))]
+    ignore_fields: Option<RecordMask>,
+
+    /// Maximum number of different transactions to print
+    #[arg(long, value_name = "N")]
+    max_diffs: Option<usize>,
 
-    /// Data format in the first file to read
-    #[clap(long, value_name = "FORMAT")]
-    format1: String,
+    /// Output format of the comparison result
+    #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+    report: ReportFormat,
 
-    /// Second file to read
-    #[arg(long, value_name = "FILE")]
-    file2: std::path::PathBuf,
+    /// Match transactions by TX_ID instead of comparing them by position
+    #[arg(long)]
+    reconcile: bool,
 
-    /// Data format in the second file to read
-    #[clap(long, value_name = "FORMAT")]
-    format2: String,
+    /// Maximum AMOUNT difference of transactions compared as equal (with `--reconcile`, also
+    /// to match transactions with different TX_ID)
+    #[arg(long, value_name = "AMOUNT")]
+    amount_tolerance: Option<u64>,
+
+    /// Maximum TIMESTAMP difference (ms) of transactions compared as equal, e.g. clock skew between
+    /// systems (with `--reconcile`, also to match transactions with different TX_ID)
+    #[arg(long, visible_alias = "timestamp-tolerance-ms", value_name = "MS")]
+    timestamp_tolerance: Option<u64>,
+
+    /// Print different transactions as a unified diff of all fields aligned by name, highlighting
+    /// expected and actual values in color (`always` if WHEN is omitted)
+    #[arg(long, value_enum, value_name = "WHEN", num_args = 0..=1, require_equals = true, default_missing_value = "always")]
+    color: Option<ColorWhen>,
+
+    #[cfg(feature = "tracing")]
+    #[command(flatten)]
+    logging: logging::LogArgs,
+}
+
+impl Args {
+    /// Сравнение транзакций без исключенных полей с допусками сумм и меток времени.
+    fn comparator(&self) -> RecordComparator {
+        RecordComparator::from(self.ignore_fields.unwrap_or_default())
+            .amount_tolerance(self.amount_tolerance.unwrap_or(0))
+            .timestamp_tolerance(self.timestamp_tolerance.unwrap_or(0))
+    }
 }
 
 /// Ошибка парсинга данных.
@@ -45,61 +163,605 @@ enum CliError {
 
     #[error("File is too big!")]
     TooBigFile,
+
+    #[error("Report serialization error: {0}")]
+    Report(#[from] serde_json::Error),
+
+    #[error("Failed to compare {0} pair(s) of files!")]
+    DirFailed(usize),
+}
+
+/// Путь, указывающий на чтение из stdin.
+const STDIN: &str = "-";
+
+macro_rules! convert_format {
+    ($input:expr) => {
+        YPBankImpl::try_from($input)?
+    };
+}
+
+macro_rules! open_with_format {
+    ($file:expr, $format:expr) => {{
+        let file: Box<dyn std::io::Read> = if $file == Path::new(STDIN) {
+            Box::new(std::io::stdin().lock())
+        } else {
+            Box::new(std::fs::File::open($file)?)
+        };
+        let file = decompress(file)?;
+        let (file, format): (Box<dyn std::io::Read>, YPBankImpl) = match $format {
+            "auto" => {
+                let (format, file) = YPBankImpl::detect_format_from_stream(file)?;
+                (Box::new(file), format)
+            }
+            format => (file, convert_format!(format)),
+        };
+        (file, format)
+    }};
+}
+
+/// Область трассировки чтения записей файла.
+#[cfg(feature = "tracing")]
+macro_rules! file_span {
+    ($file:expr, $format:expr) => {
+        tracing::info_span!("file", path = %$file.display(), format = ?$format)
+    };
 }
 
 macro_rules! open_and_read {
     ($file:expr, $format:expr) => {{
-        if std::fs::metadata(&$file)?.len() > 1024 * 1024 * 1024 {
+        if $file != Path::new(STDIN) && std::fs::metadata(&$file)?.len() > 1024 * 1024 * 1024 {
             return Err(CliError::TooBigFile);
         }
 
-        let mut file = std::fs::File::open($file)?;
-        $format.read_from(&mut file)?
+        let (mut file, format) = open_with_format!($file, $format);
+        #[cfg(feature = "tracing")]
+        let _span = file_span!($file, format).entered();
+        format.read_from(&mut file)?
     }};
 }
 
-macro_rules! convert_format {
-    ($input:expr) => {
-        YPBankImpl::try_from($input)?
+macro_rules! open_and_iter {
+    ($file:expr, $format:expr) => {{
+        let (file, format) = open_with_format!($file, $format);
+        let records = format.iter_from(file);
+        #[cfg(feature = "tracing")]
+        let records = records.instrument(file_span!($file, format));
+        records
+    }};
+}
+
+fn print_skipped_diffs<W: Write>(
+    total: usize,
+    max_diffs: Option<usize>,
+    out: &mut W,
+) -> std::io::Result<()> {
+    if let Some(max_diffs) = max_diffs
+        && total > max_diffs
+    {
+        writeln!(
+            out,
+            "... and {} more different transactions",
+            total - max_diffs
+        )?;
+    }
+
+    Ok(())
+}
+
+fn print_reconciliation<W: Write>(
+    reconciliation: &Reconciliation,
+    (file1, records1): (&str, &[Record]),
+    (file2, records2): (&str, &[Record]),
+    printer: &DiffPrinter,
+    max_diffs: Option<usize>,
+    out: &mut W,
+) -> std::io::Result<()> {
+    if reconciliation.is_clean() {
+        writeln!(
+            out,
+            "Transactions in files `{file1}` and `{file2}` are reconciled without differences!"
+        )?;
+        return Ok(());
+    }
+
+    let fuzzy = reconciliation
+        .matched
+        .iter()
+        .filter(|pair| pair.kind == MatchKind::Fuzzy)
+        .count();
+    writeln!(
+        out,
+        "Matched transactions: {} ({fuzzy} fuzzy)",
+        reconciliation.matched.len()
+    )?;
+
+    let join = |numbers: &[usize]| {
+        numbers
+            .iter()
+            .map(|number| number.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
     };
+
+    if !reconciliation.only_in_left.is_empty() {
+        writeln!(
+            out,
+            "Transactions only in `{file1}`: {}",
+            join(&reconciliation.only_in_left)
+        )?;
+    }
+
+    if !reconciliation.only_in_right.is_empty() {
+        writeln!(
+            out,
+            "Transactions only in `{file2}`: {}",
+            join(&reconciliation.only_in_right)
+        )?;
+    }
+
+    let mismatched = &reconciliation.mismatched;
+
+    for mismatch in mismatched
+        .iter()
+        .take(max_diffs.unwrap_or(mismatched.len()))
+    {
+        printer.print(
+            &format!(
+                "Transactions with TX_ID {} (numbered {} and {}) are different:",
+                mismatch.tx_id, mismatch.left, mismatch.right
+            ),
+            Side {
+                file: file1,
+                number: mismatch.left,
+                record: &records1[mismatch.left - 1],
+            },
+            Side {
+                file: file2,
+                number: mismatch.right,
+                record: &records2[mismatch.right - 1],
+            },
+            &mismatch.fields,
+            out,
+        )?;
+    }
+
+    print_skipped_diffs(mismatched.len(), max_diffs, out)
 }
 
-fn run() -> Result<(), CliError> {
-    let args = Args::parse();
+/// Сравнить транзакции двух файлов по их порядковым номерам, считывая их по одной.
+///
+/// Для каждой пары различающихся транзакций вызывает `on_diff` с их порядковым номером,
+/// самими транзакциями и различиями полей; ошибка `on_diff` прерывает сравнение.
+/// Возвращает количество транзакций в файлах.
+fn compare_by_position(
+    mut records1: impl Iterator<Item = Result<Record, ReadError>>,
+    mut records2: impl Iterator<Item = Result<Record, ReadError>>,
+    comparator: &RecordComparator,
+    mut on_diff: impl FnMut(usize, &Record, &Record, Vec<FieldDiff>) -> std::io::Result<()>,
+) -> Result<usize, CliError> {
+    let mut count = 0;
 
-    let file1 = args.file1;
-    let file2 = args.file2;
-    let format1 = convert_format!(args.format1.as_str());
-    let format2 = convert_format!(args.format2.as_str());
+    loop {
+        match (records1.next().transpose()?, records2.next().transpose()?) {
+            (Some(r1), Some(r2)) => {
+                count += 1;
 
-    let records1 = open_and_read!(file1.clone(), format1);
-    let records2 = open_and_read!(file2.clone(), format2);
+                let diffs = comparator.diff(&r1, &r2);
+                if !diffs.is_empty() {
+                    on_diff(count, &r1, &r2, diffs)?;
+                }
+            }
+            (None, None) => return Ok(count),
+            (Some(_), None) => {
+                return Err(CliError::UnequalData {
+                    len1: count + 1 + records1.try_fold(0, |n, r| r.map(|_| n + 1))?,
+                    len2: count,
+                });
+            }
+            (None, Some(_)) => {
+                return Err(CliError::UnequalData {
+                    len1: count,
+                    len2: count + 1 + records2.try_fold(0, |n, r| r.map(|_| n + 1))?,
+                });
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn print_tx_id_comparison<W: Write>(
+    reconciliation: &Reconciliation,
+    records1: &[Record],
+    records2: &[Record],
+    file1: &str,
+    file2: &str,
+    printer: &DiffPrinter,
+    max_diffs: Option<usize>,
+    out: &mut W,
+) -> std::io::Result<()> {
+    if reconciliation.is_clean() {
+        writeln!(
+            out,
+            "Transactions in files `{file1}` and `{file2}` are identical by TX_ID!"
+        )?;
+        return Ok(());
+    }
 
-    if records1.len() != records2.len() {
-        return Err(CliError::UnequalData {
-            len1: records1.len(),
-            len2: records2.len(),
-        });
+    let join_tx_ids = |records: &[Record], numbers: &[usize]| {
+        numbers
+            .iter()
+            .map(|&number| records[number - 1].tx_id().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    if !reconciliation.only_in_left.is_empty() {
+        writeln!(
+            out,
+            "TX_IDs missing in `{file2}`: {}",
+            join_tx_ids(records1, &reconciliation.only_in_left)
+        )?;
     }
 
-    match records1
+    if !reconciliation.only_in_right.is_empty() {
+        writeln!(
+            out,
+            "Extra TX_IDs in `{file2}`: {}",
+            join_tx_ids(records2, &reconciliation.only_in_right)
+        )?;
+    }
+
+    let mismatched = &reconciliation.mismatched;
+
+    for mismatch in mismatched
         .iter()
-        .zip(records2.iter())
-        .position(|(r1, r2)| r1 != r2)
+        .take(max_diffs.unwrap_or(mismatched.len()))
     {
-        Some(idx) => println!("Transactions numbered {} are different!", idx + 1),
-        None => println!(
-            "Transactions in files `{}` and `{}` are completely identical!",
-            file1.to_str().unwrap_or("file1"),
-            file2.to_str().unwrap_or("file2")
+        printer.print(
+            &format!("Transactions with TX_ID {} are different:", mismatch.tx_id),
+            Side {
+                file: file1,
+                number: mismatch.left,
+                record: &records1[mismatch.left - 1],
+            },
+            Side {
+                file: file2,
+                number: mismatch.right,
+                record: &records2[mismatch.right - 1],
+            },
+            &mismatch.fields,
+            out,
+        )?;
+    }
+
+    print_skipped_diffs(mismatched.len(), max_diffs, out)
+}
+
+fn print_report<T: serde::Serialize, W: Write>(report: &T, out: &mut W) -> Result<(), CliError> {
+    writeln!(out, "{}", serde_json::to_string_pretty(report)?)?;
+
+    Ok(())
+}
+
+/// Сравнить транзакции двух файлов, выводя результат в текстовом виде в указанное назначение,
+/// если это требуется.
+fn compare<W: Write>(
+    args: &Args,
+    (file1, records1): (&str, &[Record]),
+    (file2, records2): (&str, &[Record]),
+    out: &mut W,
+) -> Result<Report, CliError> {
+    let comparator = args.comparator();
+    let text = matches!(args.report, ReportFormat::Text);
+
+    if args.reconcile {
+        let fuzzy =
+            (args.amount_tolerance.is_some() || args.timestamp_tolerance.is_some()).then(|| {
+                FuzzyMatch {
+                    amount_tolerance: args.amount_tolerance.unwrap_or(0),
+                    timestamp_tolerance: args.timestamp_tolerance.unwrap_or(0),
+                }
+            });
+
+        let reconciliation =
+            reconcile_records(records1, records2, &ReconcileOptions { fuzzy, comparator });
+
+        if text {
+            print_reconciliation(
+                &reconciliation,
+                (file1, records1),
+                (file2, records2),
+                &DiffPrinter::new(args.color),
+                args.max_diffs,
+                out,
+            )?;
+        }
+
+        return Ok(Report::from_reconciliation(
+            "reconcile",
+            file1,
+            file2,
+            records1,
+            records2,
+            &reconciliation,
+        ));
+    }
+
+    match args.by {
+        CompareBy::Position => compare_positional(
+            args,
+            (file1, records1.iter().cloned().map(Ok)),
+            (file2, records2.iter().cloned().map(Ok)),
+            out,
         ),
+        CompareBy::TxId => {
+            let options = ReconcileOptions {
+                comparator,
+                ..Default::default()
+            };
+            let reconciliation = reconcile_records(records1, records2, &options);
+
+            if text {
+                print_tx_id_comparison(
+                    &reconciliation,
+                    records1,
+                    records2,
+                    file1,
+                    file2,
+                    &DiffPrinter::new(args.color),
+                    args.max_diffs,
+                    out,
+                )?;
+            }
+
+            Ok(Report::from_reconciliation(
+                "tx_id",
+                file1,
+                file2,
+                records1,
+                records2,
+                &reconciliation,
+            ))
+        }
+    }
+}
+
+/// Сравнить транзакции двух файлов по их порядковым номерам, выводя результат в текстовом виде
+/// в указанное назначение, если это требуется.
+fn compare_positional<W: Write>(
+    args: &Args,
+    (file1, records1): (&str, impl Iterator<Item = Result<Record, ReadError>>),
+    (file2, records2): (&str, impl Iterator<Item = Result<Record, ReadError>>),
+    out: &mut W,
+) -> Result<Report, CliError> {
+    let comparator = args.comparator();
+    let text = matches!(args.report, ReportFormat::Text);
+    let max_diffs = args.max_diffs.unwrap_or(usize::MAX);
+
+    let printer = DiffPrinter::new(args.color);
+    let mut different = 0;
+    let mut mismatched = vec![];
+
+    let records = compare_by_position(records1, records2, &comparator, |number, r1, r2, diffs| {
+        different += 1;
+
+        if !text {
+            mismatched.push(MismatchReport {
+                tx_id: r1.tx_id(),
+                record1: number,
+                record2: number,
+                fields: diffs.iter().map(FieldReport::from).collect(),
+            });
+        } else if different <= max_diffs {
+            return printer.print(
+                &format!("Transactions numbered {number} are different:"),
+                Side {
+                    file: file1,
+                    number,
+                    record: r1,
+                },
+                Side {
+                    file: file2,
+                    number,
+                    record: r2,
+                },
+                &diffs,
+                out,
+            );
+        }
+
+        Ok(())
+    })?;
+
+    if text {
+        if different == 0 {
+            writeln!(
+                out,
+                "Transactions in files `{file1}` and `{file2}` are completely identical!"
+            )?;
+        }
+
+        print_skipped_diffs(different, args.max_diffs, out)?;
+    }
+
+    Ok(Report::from_positional(
+        file1, file2, records, different, mismatched,
+    ))
+}
+
+/// Сравнить транзакции двух файлов способом, указанным в аргументах командной строки, выводя
+/// результат в текстовом виде в указанное назначение, если это требуется.
+///
+/// Сравнение по порядковым номерам выполняется потоково, не загружая файлы в память.
+fn compare_files<W: Write>(
+    args: &Args,
+    (file1, input1): (&str, &(PathBuf, String)),
+    (file2, input2): (&str, &(PathBuf, String)),
+    out: &mut W,
+) -> Result<Report, CliError> {
+    if !args.reconcile && matches!(args.by, CompareBy::Position) {
+        let records1 = open_and_iter!(&input1.0, input1.1.as_str());
+        let records2 = open_and_iter!(&input2.0, input2.1.as_str());
+
+        return compare_positional(args, (file1, records1), (file2, records2), out);
+    }
+
+    let records1 = open_and_read!(&input1.0, input1.1.as_str());
+    let records2 = open_and_read!(&input2.0, input2.1.as_str());
+
+    compare(args, (file1, &records1), (file2, &records2), out)
+}
+
+/// Собрать пары (файл, формат) из аргументов командной строки в порядке их указания.
+fn inputs(args: &Args) -> Vec<(PathBuf, String)> {
+    if !args.formats.is_empty() && args.files.len() != args.formats.len() {
+        Args::command()
+            .error(
+                ErrorKind::WrongNumberOfValues,
+                "each `--file` must have its own `--format`",
+            )
+            .exit();
+    }
+
+    let formats = args.formats.iter().map(Some).chain(std::iter::repeat(None));
+
+    let inputs = args
+        .file1
+        .iter()
+        .zip(std::iter::once(args.format1.as_ref()))
+        .chain(
+            args.file2
+                .iter()
+                .zip(std::iter::once(args.format2.as_ref())),
+        )
+        .chain(args.files.iter().zip(formats))
+        .map(|(file, format)| {
+            let format = format.map_or("auto", String::as_str);
+            (file.clone(), format.to_string())
+        })
+        .collect::<Vec<_>>();
+
+    if inputs.len() < 2 {
+        Args::command()
+            .error(
+                ErrorKind::TooFewValues,
+                "at least two files must be given to compare",
+            )
+            .exit();
+    }
+
+    if inputs
+        .iter()
+        .filter(|(file, _)| file == Path::new(STDIN))
+        .count()
+        > 1
+    {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "only one file can be read from stdin",
+            )
+            .exit();
+    }
+
+    inputs
+}
+
+fn run(args: Args) -> Result<(), CliError> {
+    let mut out = std::io::stdout().lock();
+
+    if let (Some(dir1), Some(dir2)) = (&args.dir1, &args.dir2) {
+        return dir::compare_dirs(&args, dir1, dir2, &mut out);
+    }
+
+    let inputs = inputs(&args);
+    let names = inputs
+        .iter()
+        .map(|(file, _)| match file.to_str() {
+            Some(STDIN) => "<stdin>".to_string(),
+            _ => file.to_string_lossy().into_owned(),
+        })
+        .collect::<Vec<_>>();
+
+    // Сравнение по порядковым номерам выполняется потоково, остальные режимы требуют
+    // загрузки всех транзакций в память. Данные stdin можно прочитать только один раз,
+    // поэтому при сравнении нескольких пар файлов они также загружаются в память.
+    let stdin = inputs.iter().any(|(file, _)| file == Path::new(STDIN));
+    let streaming =
+        !args.reconcile && matches!(args.by, CompareBy::Position) && !(stdin && inputs.len() > 2);
+
+    let mut files = vec![];
+
+    if !streaming {
+        for (file, format) in &inputs {
+            files.push(open_and_read!(file, format.as_str()));
+        }
+    }
+
+    let pairs = match args.against {
+        Against::First => (1..inputs.len()).map(|j| (0, j)).collect::<Vec<_>>(),
+        Against::Pairwise => (0..inputs.len())
+            .flat_map(|i| (i + 1..inputs.len()).map(move |j| (i, j)))
+            .collect(),
     };
 
+    let text = matches!(args.report, ReportFormat::Text);
+    let mut reports = vec![];
+
+    for (i, j) in pairs {
+        let (file1, file2) = (names[i].as_str(), names[j].as_str());
+
+        if text && inputs.len() > 2 {
+            if !reports.is_empty() {
+                writeln!(out)?;
+            }
+            writeln!(out, "Comparing `{file1}` with `{file2}`:")?;
+        }
+
+        let report = if streaming {
+            compare_files(&args, (file1, &inputs[i]), (file2, &inputs[j]), &mut out)?
+        } else {
+            compare(&args, (file1, &files[i]), (file2, &files[j]), &mut out)?
+        };
+
+        reports.push(report);
+    }
+
+    if inputs.len() == 2 {
+        if !text {
+            print_report(&reports[0], &mut out)?;
+        }
+    } else {
+        let summary = Summary::new(reports);
+
+        if text {
+            writeln!(out)?;
+            write!(out, "{summary}")?;
+        } else {
+            print_report(&summary, &mut out)?;
+        }
+    }
+
+    out.flush()?;
+
     Ok(())
 }
 
 fn main() {
-    if let Err(err) = run() {
+    let args = Args::parse();
+
+    #[cfg(feature = "tracing")]
+    let logging = args.logging.init();
+
+    if let Err(err) = run(args) {
+        // Закрытие читающей стороны канала (например, `comparer ... | head`) не считается
+        // ошибкой.
+        if let CliError::Io(e) = &err
+            && e.kind() == std::io::ErrorKind::BrokenPipe
+        {
+            return;
+        }
+
         let exit_code = match err {
             CliError::UnknownFormat(_) => -1,
             CliError::Io(_) => -2,
@@ -107,8 +769,16 @@ fn main() {
             CliError::WriteData(_) => -4,
             CliError::UnequalData { .. } => -5,
             CliError::TooBigFile => -6,
+            CliError::Report(_) => -7,
+            CliError::DirFailed(_) => -8,
         };
 
+        #[cfg(feature = "tracing")]
+        if logging {
+            tracing::error!(exit_code, error = %err, "failed");
+            std::process::exit(exit_code);
+        }
+
         eprintln!("{}", err);
         std::process::exit(exit_code);
     }
@@ -0,0 +1,382 @@
+//! Модуль машиночитаемого отчета о сравнении файлов.
+
+use parser::reconcile::{MatchKind, Reconciliation};
+use parser::{FieldDiff, Record};
+use serde::Serialize;
+use std::fmt;
+
+/// Различие значений поля двух транзакций.
+#[derive(Serialize, Debug)]
+pub struct FieldReport {
+    /// Ключ различающегося поля.
+    pub field: String,
+
+    /// Значение поля в транзакции из первого файла.
+    pub expected: String,
+
+    /// Значение поля в транзакции из второго файла.
+    pub actual: String,
+}
+
+/// Реализация трейта [`From<&FieldDiff>`] для [`FieldReport`].
+impl From<&FieldDiff> for FieldReport {
+    /// Реализация метода [`From<&FieldDiff>::from`] для [`FieldReport`].
+    fn from(diff: &FieldDiff) -> Self {
+        Self {
+            field: diff.key.to_string(),
+            expected: diff.expected.clone(),
+            actual: diff.actual.clone(),
+        }
+    }
+}
+
+/// Пара различающихся транзакций.
+#[derive(Serialize, Debug)]
+pub struct MismatchReport {
+    /// Идентификатор транзакции из первого файла.
+    pub tx_id: u64,
+
+    /// Порядковый номер транзакции в первом файле (начиная с 1).
+    pub record1: usize,
+
+    /// Порядковый номер транзакции во втором файле (начиная с 1).
+    pub record2: usize,
+
+    /// Различия значений полей.
+    pub fields: Vec<FieldReport>,
+}
+
+/// Количественные итоги сравнения.
+#[derive(Serialize, Debug)]
+pub struct Counts {
+    /// Количество транзакций в первом файле.
+    pub records1: usize,
+
+    /// Количество транзакций во втором файле.
+    pub records2: usize,
+
+    /// Количество совпавших транзакций.
+    pub matched: usize,
+
+    /// Количество транзакций, сопоставленных нечетко.
+    pub fuzzy_matched: usize,
+
+    /// Количество пар различающихся транзакций.
+    pub mismatched: usize,
+
+    /// Количество транзакций, присутствующих только в первом файле.
+    pub only_in_file1: usize,
+
+    /// Количество транзакций, присутствующих только во втором файле.
+    pub only_in_file2: usize,
+}
+
+/// Отчет о сравнении двух файлов.
+#[derive(Serialize, Debug)]
+pub struct Report {
+    /// Режим сравнения (`position`, `tx_id` или `reconcile`).
+    pub mode: &'static str,
+
+    /// Путь к первому файлу.
+    pub file1: String,
+
+    /// Путь ко второму файлу.
+    pub file2: String,
+
+    /// Признак полного совпадения файлов.
+    pub identical: bool,
+
+    /// Количественные итоги сравнения.
+    pub counts: Counts,
+
+    /// Пары различающихся транзакций.
+    pub mismatched: Vec<MismatchReport>,
+
+    /// Порядковые номера транзакций, присутствующих только в первом файле.
+    pub only_in_file1: Vec<usize>,
+
+    /// Порядковые номера транзакций, присутствующих только во втором файле.
+    pub only_in_file2: Vec<usize>,
+}
+
+impl Report {
+    /// Построить отчет о сравнении транзакций по их порядковым номерам.
+    ///
+    /// `records` — количество транзакций в каждом из файлов, `different` — количество пар
+    /// различающихся транзакций, `mismatched` — их описания (могут быть не собраны).
+    pub fn from_positional(
+        file1: &str,
+        file2: &str,
+        records: usize,
+        different: usize,
+        mismatched: Vec<MismatchReport>,
+    ) -> Self {
+        Self {
+            mode: "position",
+            file1: file1.to_string(),
+            file2: file2.to_string(),
+            identical: different == 0,
+            counts: Counts {
+                records1: records,
+                records2: records,
+                matched: records - different,
+                fuzzy_matched: 0,
+                mismatched: different,
+                only_in_file1: 0,
+                only_in_file2: 0,
+            },
+            mismatched,
+            only_in_file1: vec![],
+            only_in_file2: vec![],
+        }
+    }
+
+    /// Построить отчет по результату сверки транзакций.
+    pub fn from_reconciliation(
+        mode: &'static str,
+        file1: &str,
+        file2: &str,
+        records1: &[Record],
+        records2: &[Record],
+        reconciliation: &Reconciliation,
+    ) -> Self {
+        let mismatched = reconciliation
+            .mismatched
+            .iter()
+            .map(|mismatch| MismatchReport {
+                tx_id: mismatch.tx_id,
+                record1: mismatch.left,
+                record2: mismatch.right,
+                fields: mismatch.fields.iter().map(FieldReport::from).collect(),
+            })
+            .collect();
+
+        Self {
+            mode,
+            file1: file1.to_string(),
+            file2: file2.to_string(),
+            identical: reconciliation.is_clean(),
+            counts: Counts {
+                records1: records1.len(),
+                records2: records2.len(),
+                matched: reconciliation.matched.len(),
+                fuzzy_matched: reconciliation
+                    .matched
+                    .iter()
+                    .filter(|pair| pair.kind == MatchKind::Fuzzy)
+                    .count(),
+                mismatched: reconciliation.mismatched.len(),
+                only_in_file1: reconciliation.only_in_left.len(),
+                only_in_file2: reconciliation.only_in_right.len(),
+            },
+            mismatched,
+            only_in_file1: reconciliation.only_in_left.clone(),
+            only_in_file2: reconciliation.only_in_right.clone(),
+        }
+    }
+}
+
+/// Сводный отчет о попарном сравнении нескольких файлов.
+#[derive(Serialize, Debug)]
+pub struct Summary {
+    /// Признак полного совпадения всех сравненных пар файлов.
+    pub identical: bool,
+
+    /// Отчеты о сравнении каждой пары файлов.
+    pub comparisons: Vec<Report>,
+}
+
+impl Summary {
+    /// Построить сводный отчет по отчетам о сравнении пар файлов.
+    pub fn new(comparisons: Vec<Report>) -> Self {
+        Self {
+            identical: comparisons.iter().all(|report| report.identical),
+            comparisons,
+        }
+    }
+}
+
+/// Реализация трейта [`fmt::Display`] для [`Summary`].
+impl fmt::Display for Summary {
+    /// Реализация метода [`fmt::Display::fmt`] для [`Summary`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let different = self
+            .comparisons
+            .iter()
+            .filter(|report| !report.identical)
+            .collect::<Vec<_>>();
+
+        writeln!(
+            f,
+            "Summary: {} of {} comparisons found differences",
+            different.len(),
+            self.comparisons.len()
+        )?;
+
+        for report in different {
+            writeln!(
+                f,
+                "    `{}` and `{}`: {} different, {} only in the first, {} only in the second",
+                report.file1,
+                report.file2,
+                report.counts.mismatched,
+                report.counts.only_in_file1,
+                report.counts.only_in_file2
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Результат сравнения файла из двух каталогов.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileStatus {
+    /// Транзакции файлов совпадают.
+    Identical,
+
+    /// Транзакции файлов различаются.
+    Different,
+
+    /// Файлы не удалось сравнить.
+    Failed,
+
+    /// Файл присутствует только в первом каталоге.
+    OnlyInDir1,
+
+    /// Файл присутствует только во втором каталоге.
+    OnlyInDir2,
+}
+
+/// Реализация трейта [`fmt::Display`] для [`FileStatus`].
+impl fmt::Display for FileStatus {
+    /// Реализация метода [`fmt::Display::fmt`] для [`FileStatus`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Self::Identical => "identical",
+            Self::Different => "different",
+            Self::Failed => "failed",
+            Self::OnlyInDir1 => "only_in_dir1",
+            Self::OnlyInDir2 => "only_in_dir2",
+        })
+    }
+}
+
+/// Сравнение файла с одинаковым именем из двух каталогов.
+#[derive(Serialize, Debug)]
+pub struct FileComparison {
+    /// Имя файла.
+    pub name: String,
+
+    /// Результат сравнения.
+    pub status: FileStatus,
+
+    /// Отчет о сравнении, если файлы удалось сравнить.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<Report>,
+
+    /// Ошибка сравнения, если файлы не удалось сравнить.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Сводный отчет о сравнении файлов двух каталогов.
+#[derive(Serialize, Debug)]
+pub struct DirSummary {
+    /// Путь к первому каталогу.
+    pub dir1: String,
+
+    /// Путь ко второму каталогу.
+    pub dir2: String,
+
+    /// Признак совпадения всех файлов каталогов.
+    pub identical: bool,
+
+    /// Результаты сравнения файлов в порядке их имен.
+    pub files: Vec<FileComparison>,
+}
+
+impl DirSummary {
+    /// Построить сводный отчет по результатам сравнения файлов.
+    pub fn new(dir1: String, dir2: String, files: Vec<FileComparison>) -> Self {
+        Self {
+            dir1,
+            dir2,
+            identical: files
+                .iter()
+                .all(|file| file.status == FileStatus::Identical),
+            files,
+        }
+    }
+
+    /// Количество файлов с указанным результатом сравнения.
+    pub fn count(&self, status: FileStatus) -> usize {
+        self.files
+            .iter()
+            .filter(|file| file.status == status)
+            .count()
+    }
+}
+
+/// Реализация трейта [`fmt::Display`] для [`DirSummary`].
+///
+/// Выводит таблицу результатов сравнения файлов и итоговое количество файлов по результатам.
+impl fmt::Display for DirSummary {
+    /// Реализация метода [`fmt::Display::fmt`] для [`DirSummary`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .files
+            .iter()
+            .map(|file| file.name.len())
+            .max()
+            .unwrap_or(0)
+            .max(4);
+
+        writeln!(
+            f,
+            "{:<12}  {:>9}  {:<width$}  DETAILS",
+            "STATUS", "RECORDS", "FILE"
+        )?;
+
+        for file in &self.files {
+            let records = file
+                .report
+                .as_ref()
+                .map(|report| report.counts.records1.to_string())
+                .unwrap_or_else(|| "-".to_string());
+
+            let details = match (&file.report, &file.error, file.status) {
+                (_, Some(error), _) => error.clone(),
+                (Some(report), _, FileStatus::Different) => format!(
+                    "{} different, {} only in the first, {} only in the second",
+                    report.counts.mismatched,
+                    report.counts.only_in_file1,
+                    report.counts.only_in_file2
+                ),
+                (_, _, FileStatus::OnlyInDir1) => format!("missing in `{}`", self.dir2),
+                (_, _, FileStatus::OnlyInDir2) => format!("missing in `{}`", self.dir1),
+                _ => String::new(),
+            };
+
+            let line = format!(
+                "{:<12}  {records:>9}  {:<width$}  {details}",
+                file.status, file.name
+            );
+            writeln!(f, "{}", line.trim_end())?;
+        }
+
+        writeln!(
+            f,
+            "Summary: {} files, {} identical, {} different, {} failed, {} only in `{}`, {} only in `{}`",
+            self.files.len(),
+            self.count(FileStatus::Identical),
+            self.count(FileStatus::Different),
+            self.count(FileStatus::Failed),
+            self.count(FileStatus::OnlyInDir1),
+            self.dir1,
+            self.count(FileStatus::OnlyInDir2),
+            self.dir2
+        )
+    }
+}
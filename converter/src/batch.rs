@@ -0,0 +1,262 @@
+//! Модуль пакетной конвертации файлов, заданных шаблонами путей.
+
+use super::{Args, BatchArgs, CliError, DataFormat, OutputFormat, convert_file};
+use clap::{CommandFactory, error::ErrorKind};
+use parser::SqlDialect;
+use std::collections::{HashMap, HashSet};
+use std::num::NonZero;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Входные файлы, соответствующие шаблонам путей, в порядке указания шаблонов без повторов.
+///
+/// Завершает работу с ошибкой, если шаблон некорректен или ему не соответствует ни один файл.
+fn expand_inputs(patterns: &[String]) -> Vec<PathBuf> {
+    let mut inputs = vec![];
+    let mut seen = HashSet::new();
+
+    for pattern in patterns {
+        let paths = glob::glob(pattern).unwrap_or_else(|e| {
+            Args::command()
+                .error(
+                    ErrorKind::ValueValidation,
+                    format!("invalid `--input` pattern `{pattern}`: {e}"),
+                )
+                .exit()
+        });
+
+        let mut matched = false;
+
+        for path in paths {
+            match path {
+                Ok(path) if path.is_file() => {
+                    matched = true;
+
+                    if seen.insert(path.clone()) {
+                        inputs.push(path);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("{e}"),
+            }
+        }
+
+        if !matched {
+            Args::command()
+                .error(
+                    ErrorKind::ValueValidation,
+                    format!("no files match `--input {pattern}`"),
+                )
+                .exit();
+        }
+    }
+
+    inputs
+}
+
+/// Путь к выходному файлу: имя входного файла с названием выходного формата в качестве
+/// расширения в каталоге `out_dir` либо в каталоге входного файла.
+fn output_path(input: &Path, out_dir: Option<&Path>, output_format: &str) -> PathBuf {
+    let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+    let file_name = format!("{stem}.{output_format}");
+
+    match out_dir {
+        Some(dir) => dir.join(file_name),
+        None => input.with_file_name(file_name),
+    }
+}
+
+/// Описание первого конфликта выходных файлов: выходного файла, совпадающего с входным
+/// или с выходным файлом другого входного файла.
+pub(super) fn output_conflict(inputs: &[PathBuf], outputs: &[PathBuf]) -> Option<String> {
+    let inputs_set = inputs.iter().collect::<HashSet<_>>();
+    let mut sources = HashMap::new();
+
+    for (input, output) in inputs.iter().zip(outputs) {
+        if inputs_set.contains(output) {
+            return Some(format!(
+                "converting `{}` would overwrite the input file `{}`",
+                input.display(),
+                output.display()
+            ));
+        }
+
+        if let Some(other) = sources.insert(output, input) {
+            return Some(format!(
+                "`{}` and `{}` would be converted to the same file `{}`",
+                other.display(),
+                input.display(),
+                output.display()
+            ));
+        }
+    }
+
+    None
+}
+
+/// Завершить работу с ошибкой, если выходной файл совпадает с входным или с выходным файлом
+/// другого входного файла.
+fn check_outputs(inputs: &[PathBuf], outputs: &[PathBuf]) {
+    if let Some(message) = output_conflict(inputs, outputs) {
+        Args::command()
+            .error(ErrorKind::ArgumentConflict, message)
+            .exit();
+    }
+}
+
+/// Вывести таблицу результатов конвертации файлов и итоговое количество успешно
+/// сконвертированных и не сконвертированных файлов.
+fn print_summary(inputs: &[PathBuf], outputs: &[PathBuf], results: &[Result<usize, CliError>]) {
+    let inputs = inputs
+        .iter()
+        .map(|input| input.display().to_string())
+        .collect::<Vec<_>>();
+    let width = inputs.iter().map(String::len).max().unwrap_or(0).max(5);
+
+    println!(
+        "{:<6}  {:>9}  {:<width$}  OUTPUT",
+        "STATUS", "RECORDS", "INPUT"
+    );
+
+    for ((input, output), result) in inputs.iter().zip(outputs).zip(results) {
+        match result {
+            Ok(count) => println!(
+                "{:<6}  {count:>9}  {input:<width$}  {}",
+                "ok",
+                output.display()
+            ),
+            Err(e) => println!("{:<6}  {:>9}  {input:<width$}  {e}", "failed", "-"),
+        }
+    }
+
+    let failed = results.iter().filter(|result| result.is_err()).count();
+
+    println!(
+        "{} files: {} converted, {failed} failed",
+        results.len(),
+        results.len() - failed
+    );
+}
+
+/// Конвертировать входные файлы, заданные шаблонами путей, параллельно в `--jobs` потоках,
+/// записывая каждый в собственный выходной файл, и вывести таблицу результатов.
+///
+/// Ошибка конвертации одного файла не прерывает конвертацию остальных.
+pub fn convert_batch(args: BatchArgs) -> Result<(), CliError> {
+    let inputs = expand_inputs(&args.input);
+    let outputs = inputs
+        .iter()
+        .map(|input| output_path(input, args.out_dir.as_deref(), &args.output_format))
+        .collect::<Vec<_>>();
+
+    check_outputs(&inputs, &outputs);
+
+    if let Some(out_dir) = &args.out_dir {
+        std::fs::create_dir_all(out_dir)?;
+    }
+
+    let options = args.formats.options();
+    let output_format = OutputFormat::plain(
+        DataFormat::new(
+            &args.output_format,
+            args.formats.options(),
+            true,
+            SqlDialect::default(),
+        )?,
+        args.schema_version,
+    );
+
+    let jobs = args
+        .jobs
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, NonZero::get))
+        .clamp(1, inputs.len());
+    let next = AtomicUsize::new(0);
+
+    let mut results = std::thread::scope(|scope| {
+        let workers = (0..jobs)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = vec![];
+
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some((input, output)) = inputs.get(index).zip(outputs.get(index))
+                        else {
+                            break results;
+                        };
+
+                        let result = convert_file(
+                            input,
+                            &args.input_format,
+                            &options,
+                            &output_format,
+                            output,
+                        );
+                        results.push((index, result));
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("conversion thread panicked"))
+            .collect::<Vec<_>>()
+    });
+
+    results.sort_by_key(|(index, _)| *index);
+
+    let results = results
+        .into_iter()
+        .map(|(_, result)| result)
+        .collect::<Vec<_>>();
+
+    print_summary(&inputs, &outputs, &results);
+
+    match results.iter().filter(|result| result.is_err()).count() {
+        0 => Ok(()),
+        failed => Err(CliError::BatchFailed(failed)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::input_dir("data/a.csv", None, "bin", "data/a.bin")]
+    #[case::out_dir("data/a.csv", Some("out"), "bin", "out/a.bin")]
+    #[case::no_extension("data/a", Some("out"), "json", "out/a.json")]
+    #[case::several_extensions("data/a.tar.csv", None, "bin", "data/a.tar.bin")]
+    fn test_output_path(
+        #[case] input: &str,
+        #[case] out_dir: Option<&str>,
+        #[case] output_format: &str,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(
+            output_path(input.as_ref(), out_dir.map(Path::new), output_format),
+            PathBuf::from(expected)
+        );
+    }
+
+    #[rstest]
+    #[case::distinct(&["a.csv", "b.csv"], &["a.bin", "b.bin"], None)]
+    #[case::overwrites_input(&["a.csv", "a.bin"], &["a.bin", "a.json"], Some("would overwrite the input file `a.bin`"))]
+    #[case::same_output(&["a.csv", "a.txt"], &["a.bin", "a.bin"], Some("`a.csv` and `a.txt` would be converted to the same file `a.bin`"))]
+    fn test_output_conflict(
+        #[case] inputs: &[&str],
+        #[case] outputs: &[&str],
+        #[case] expected: Option<&str>,
+    ) {
+        let inputs = inputs.iter().map(PathBuf::from).collect::<Vec<_>>();
+        let outputs = outputs.iter().map(PathBuf::from).collect::<Vec<_>>();
+        let conflict = output_conflict(&inputs, &outputs);
+
+        match expected {
+            Some(expected) => assert!(conflict.unwrap().contains(expected)),
+            None => assert_eq!(conflict, None),
+        }
+    }
+}
@@ -0,0 +1,622 @@
+//! HTTP-сервис конвертации, проверки и статистики данных о транзакциях.
+//!
+//! Позволяет использовать возможности крейта Parser без запуска конвертера из других программ.
+//! Сборка включается признаком `server`.
+
+use clap::Parser;
+use parser::{
+    MetricsRegistry, Record, RecordIter, SchemaVersion, Severity, Stats, StatsReport,
+    ValidationConfig, Validator, YPBankImpl,
+    compression::decompress_detected,
+    errors::{FormatError, ReadError, WriteError},
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::sync::{Arc, LazyLock};
+use thiserror::Error;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Метрики чтения и записи записей всеми запросами, отдаваемые по `GET /metrics`.
+static METRICS: LazyLock<Arc<MetricsRegistry>> = LazyLock::new(Default::default);
+
+#[derive(Parser, Debug)]
+#[command(version, about = "HTTP service converting, validating and summarizing transaction data", long_about = None)]
+struct Args {
+    /// Address to listen on
+    #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:8080")]
+    listen: String,
+
+    /// Number of requests handled concurrently
+    #[arg(long, value_name = "N", default_value_t = 4)]
+    workers: usize,
+
+    /// Maximum size of a request body in bytes, both as received and decompressed
+    #[arg(long, value_name = "BYTES", default_value_t = 64 * 1024 * 1024)]
+    max_body_size: usize,
+}
+
+#[derive(Debug, Error)]
+enum ServerError {
+    #[error("{0}")]
+    BadRequest(String),
+
+    #[error("Not found")]
+    NotFound,
+
+    #[error("Method not allowed")]
+    MethodNotAllowed,
+
+    #[error("Request body exceeds {0} bytes")]
+    PayloadTooLarge(usize),
+
+    #[error(transparent)]
+    UnknownFormat(#[from] FormatError),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    ReadData(#[from] ReadError),
+
+    #[error(transparent)]
+    WriteData(#[from] WriteError),
+
+    #[error("Report error: {0}")]
+    Report(#[from] serde_json::Error),
+}
+
+impl ServerError {
+    /// Код состояния HTTP-ответа с ошибкой.
+    fn status_code(&self) -> u16 {
+        match self {
+            Self::BadRequest(_) | Self::UnknownFormat(_) | Self::ReadData(_) => 400,
+            Self::NotFound => 404,
+            Self::MethodNotAllowed => 405,
+            Self::PayloadTooLarge(_) => 413,
+            Self::WriteData(_) => 422,
+            Self::Io(_) | Self::Report(_) => 500,
+        }
+    }
+}
+
+/// Тело HTTP-ответа с ошибкой.
+#[derive(Serialize, Debug)]
+struct ErrorReport {
+    /// Описание ошибки.
+    error: String,
+}
+
+/// Проблема, найденная при проверке данных.
+#[derive(Serialize, Debug)]
+struct IssueReport {
+    /// Позиция записи в данных (отсутствует, если она неизвестна).
+    position: Option<String>,
+
+    /// Важность проблемы: `error` или `warning`.
+    severity: String,
+
+    /// Идентификатор транзакции (отсутствует для некорректных записей).
+    tx_id: Option<u64>,
+
+    /// Описание проблемы.
+    message: String,
+}
+
+/// Отчет о проверке данных.
+#[derive(Serialize, Debug)]
+struct ValidationReport {
+    /// Количество корректно считанных записей.
+    records: usize,
+
+    /// Количество ошибок.
+    errors: usize,
+
+    /// Количество предупреждений.
+    warnings: usize,
+
+    /// Найденные проблемы в порядке следования записей.
+    issues: Vec<IssueReport>,
+}
+
+/// Ответ на HTTP-запрос: код состояния, тип содержимого и тело.
+struct Reply {
+    /// Код состояния.
+    status_code: u16,
+
+    /// Тип содержимого.
+    content_type: &'static str,
+
+    /// Тело ответа.
+    body: Vec<u8>,
+}
+
+impl Reply {
+    /// Успешный ответ с указанным телом.
+    fn ok(content_type: &'static str, body: Vec<u8>) -> Self {
+        Self {
+            status_code: 200,
+            content_type,
+            body,
+        }
+    }
+
+    /// Успешный ответ с телом в формате JSON.
+    fn json<T: Serialize>(value: &T) -> Result<Self, ServerError> {
+        Ok(Self::ok(
+            "application/json",
+            serde_json::to_vec_pretty(value)?,
+        ))
+    }
+}
+
+/// Реализация трейта [`From<ServerError>`] для [`Reply`].
+impl From<ServerError> for Reply {
+    /// Реализация метода [`From<ServerError>::from`] для [`Reply`].
+    fn from(err: ServerError) -> Self {
+        let report = ErrorReport {
+            error: err.to_string(),
+        };
+
+        Self {
+            status_code: err.status_code(),
+            content_type: "application/json",
+            body: serde_json::to_vec(&report).expect("error report is always serializable"),
+        }
+    }
+}
+
+/// Параметры запроса из строки запроса URL.
+struct Query(HashMap<String, String>);
+
+impl Query {
+    /// Разобрать строку запроса вида `key=value&key=value`, декодируя ключи и значения
+    /// (см. [`percent_decode`]).
+    fn parse(query: &str) -> Self {
+        Self(
+            query
+                .split('&')
+                .filter(|pair| !pair.is_empty())
+                .map(|pair| {
+                    let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                    (percent_decode(key), percent_decode(value))
+                })
+                .collect(),
+        )
+    }
+
+    /// Значение обязательного параметра.
+    fn required(&self, key: &str) -> Result<&str, ServerError> {
+        self.0
+            .get(key)
+            .map(String::as_str)
+            .ok_or_else(|| ServerError::BadRequest(format!("missing query parameter `{key}`")))
+    }
+
+    /// Значение флага: `true`, `1` или пустое значение включают его.
+    fn flag(&self, key: &str) -> bool {
+        matches!(self.0.get(key).map(String::as_str), Some("" | "1" | "true"))
+    }
+
+    /// Считать записи из распакованного тела запроса во входном формате из параметра `from`.
+    ///
+    /// Значение `auto` определяет формат по содержимому.
+    fn records(&self, body: Vec<u8>) -> Result<RecordIter<Box<dyn Read>>, ServerError> {
+        let input: Box<dyn Read> = Box::new(Cursor::new(body));
+
+        let records = match self.required("from")? {
+            "auto" => {
+                let (format, input) = YPBankImpl::detect_format_from_stream(input)?;
+                format.iter_from(Box::new(input) as Box<dyn Read>)
+            }
+            format => YPBankImpl::try_from(format)?.iter_from(input),
+        }
+        .metrics(METRICS.clone());
+
+        Ok(if self.flag("lenient") {
+            records.lenient()
+        } else {
+            records
+        })
+    }
+}
+
+/// Декодировать компонент строки запроса: `+` заменяется пробелом, а последовательности
+/// `%XX` — байтами с указанным шестнадцатеричным кодом. Некорректные последовательности
+/// остаются как есть.
+fn percent_decode(component: &str) -> String {
+    let bytes = component.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let byte = match bytes[i] {
+            b'+' => b' ',
+            b'%' => match bytes
+                .get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(byte) => {
+                    i += 2;
+                    byte
+                }
+                None => b'%',
+            },
+            byte => byte,
+        };
+
+        decoded.push(byte);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Тип содержимого данных в указанном формате.
+fn content_type(format: YPBankImpl) -> &'static str {
+    match format {
+        YPBankImpl::Text => "text/plain; charset=utf-8",
+        YPBankImpl::Csv => "text/csv; charset=utf-8",
+        YPBankImpl::Json => "application/json",
+        YPBankImpl::Jsonl => "application/x-ndjson",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Найти позицию первого вхождения последовательности байтов.
+fn find(data: &[u8], needle: &[u8]) -> Option<usize> {
+    data.windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Получить содержимое первой части тела запроса в формате `multipart/form-data`.
+fn multipart_content(body: &[u8], boundary: &str) -> Option<Vec<u8>> {
+    let delimiter = format!("--{boundary}");
+    let part = &body[find(body, delimiter.as_bytes())? + delimiter.len()..];
+    let content = &part[find(part, b"\r\n\r\n")? + 4..];
+    let end = find(content, format!("\r\n{delimiter}").as_bytes())?;
+
+    Some(content[..end].to_vec())
+}
+
+/// Считать тело запроса, извлекая из тела в формате `multipart/form-data` первую часть.
+fn read_body(
+    reader: impl Read,
+    content_type: Option<&str>,
+    max_body_size: usize,
+) -> Result<Vec<u8>, ServerError> {
+    let mut body = vec![];
+    reader
+        .take(max_body_size as u64 + 1)
+        .read_to_end(&mut body)?;
+
+    if body.len() > max_body_size {
+        return Err(ServerError::PayloadTooLarge(max_body_size));
+    }
+
+    let Some(boundary) = content_type.and_then(|content_type| {
+        content_type
+            .strip_prefix("multipart/form-data")?
+            .split(';')
+            .find_map(|param| param.trim().strip_prefix("boundary="))
+            .map(|boundary| boundary.trim_matches('"').to_string())
+    }) else {
+        return Ok(body);
+    };
+
+    multipart_content(&body, &boundary)
+        .ok_or_else(|| ServerError::BadRequest("malformed multipart body".to_string()))
+}
+
+/// Распаковать тело запроса, если оно сжато поддерживаемым способом.
+///
+/// Размер распакованных данных ограничен так же, как размер тела запроса, чтобы небольшое
+/// сжатое тело не распаковывалось в неограниченный объем данных.
+fn decompress_body(body: Vec<u8>, max_body_size: usize) -> Result<Vec<u8>, ServerError> {
+    let invalid =
+        |e: std::io::Error| ServerError::BadRequest(format!("invalid compressed body: {e}"));
+
+    let (_, input) = decompress_detected(Cursor::new(body)).map_err(invalid)?;
+    let mut data = vec![];
+    input
+        .take(max_body_size as u64 + 1)
+        .read_to_end(&mut data)
+        .map_err(invalid)?;
+
+    if data.len() > max_body_size {
+        return Err(ServerError::PayloadTooLarge(max_body_size));
+    }
+
+    Ok(data)
+}
+
+/// Конвертировать записи из формата `from` в формат `to`.
+///
+/// Параметр `schema_version` (`1` или `2`) задает версию схемы записываемых записей.
+fn convert(query: &Query, body: Vec<u8>) -> Result<Reply, ServerError> {
+    let output_format = YPBankImpl::try_from(query.required("to")?)?;
+    let schema_version = match query.0.get("schema_version") {
+        Some(version) => SchemaVersion::try_from(version.as_str())
+            .map_err(|e| ServerError::BadRequest(e.to_string()))?,
+        None => SchemaVersion::default(),
+    };
+
+    let mut writer = output_format
+        .writer_to(vec![])
+        .schema_version(schema_version)
+        .metrics(METRICS.clone());
+
+    for record in query.records(body)? {
+        writer.write_record(&record?)?;
+    }
+
+    Ok(Reply::ok(content_type(output_format), writer.finish()?))
+}
+
+/// Проверить записи, продолжая чтение после некорректных записей, если формат это допускает.
+fn validate(query: &Query, body: Vec<u8>) -> Result<Reply, ServerError> {
+    let mut records = query.records(body)?.continue_on_error();
+    let mut validator = Validator::new(ValidationConfig::default());
+    let mut report = ValidationReport {
+        records: 0,
+        errors: 0,
+        warnings: 0,
+        issues: vec![],
+    };
+
+    while let Some(result) = records.next_with_position() {
+        match result {
+            Ok((position, record)) => {
+                report.records += 1;
+
+                for issue in validator.check(position.record, &record) {
+                    match issue.severity() {
+                        Severity::Error => report.errors += 1,
+                        Severity::Warning => report.warnings += 1,
+                    }
+
+                    report.issues.push(IssueReport {
+                        position: Some(position.to_string()),
+                        severity: issue.severity().to_string(),
+                        tx_id: Some(issue.tx_id),
+                        message: issue.kind.to_string(),
+                    });
+                }
+            }
+            Err(e) => {
+                report.errors += 1;
+                report.issues.push(IssueReport {
+                    position: e.position().map(|position| position.to_string()),
+                    severity: Severity::Error.to_string(),
+                    tx_id: None,
+                    message: e.inner().to_string(),
+                });
+            }
+        }
+    }
+
+    Reply::json(&report)
+}
+
+/// Собрать сводную статистику по записям.
+///
+/// Параметр `top` задает количество участников с наибольшей общей суммой транзакций (10 по умолчанию).
+fn stats(query: &Query, body: Vec<u8>) -> Result<Reply, ServerError> {
+    let top = match query.0.get("top") {
+        Some(top) => top
+            .parse()
+            .map_err(|_| ServerError::BadRequest(format!("invalid `top` value `{top}`")))?,
+        None => 10,
+    };
+
+    let stats = query
+        .records(body)?
+        .collect::<Result<Vec<Record>, _>>()?
+        .iter()
+        .collect::<Stats>();
+
+    Reply::json(&StatsReport::new(&stats, top))
+}
+
+/// Обработать HTTP-запрос с указанными методом, URL, типом содержимого и телом.
+fn route(
+    method: &Method,
+    url: &str,
+    content_type: Option<&str>,
+    body: impl Read,
+    max_body_size: usize,
+) -> Result<Reply, ServerError> {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+
+    if path == "/metrics" {
+        if *method != Method::Get {
+            return Err(ServerError::MethodNotAllowed);
+        }
+
+        return Ok(Reply::ok(
+            "text/plain; version=0.0.4",
+            METRICS.to_prometheus().into_bytes(),
+        ));
+    }
+
+    let handler = match path {
+        "/convert" => convert,
+        "/validate" => validate,
+        "/stats" => stats,
+        _ => return Err(ServerError::NotFound),
+    };
+
+    if *method != Method::Post {
+        return Err(ServerError::MethodNotAllowed);
+    }
+
+    let body = read_body(body, content_type, max_body_size)?;
+    let body = decompress_body(body, max_body_size)?;
+
+    handler(&Query::parse(query), body)
+}
+
+/// Обработать HTTP-запрос.
+fn handle(request: &mut Request, max_body_size: usize) -> Result<Reply, ServerError> {
+    let method = request.method().clone();
+    let url = request.url().to_string();
+    let content_type = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Content-Type"))
+        .map(|header| header.value.as_str().to_string());
+
+    route(
+        &method,
+        &url,
+        content_type.as_deref(),
+        request.as_reader(),
+        max_body_size,
+    )
+}
+
+/// Отправить ответ на HTTP-запрос.
+fn respond(request: Request, reply: Reply) -> std::io::Result<()> {
+    let header = Header::from_bytes(&b"Content-Type"[..], reply.content_type.as_bytes())
+        .expect("content type is a valid header value");
+
+    request.respond(
+        Response::from_data(reply.body)
+            .with_status_code(reply.status_code)
+            .with_header(header),
+    )
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let server = match Server::http(&args.listen) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("Failed to listen on {}: {e}", args.listen);
+            std::process::exit(-2);
+        }
+    };
+
+    eprintln!("Listening on {}", args.listen);
+
+    std::thread::scope(|scope| {
+        for _ in 0..args.workers.max(1) {
+            scope.spawn(|| {
+                for mut request in server.incoming_requests() {
+                    let reply =
+                        handle(&mut request, args.max_body_size).unwrap_or_else(Reply::from);
+                    let status_code = reply.status_code;
+                    let summary =
+                        format!("{} {} -> {status_code}", request.method(), request.url());
+
+                    if let Err(e) = respond(request, reply) {
+                        eprintln!("{summary}: failed to respond: {e}");
+                    } else if status_code >= 500 {
+                        eprintln!("{summary}");
+                    }
+                }
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::compression::Compression;
+    use rstest::rstest;
+    use std::io::Write;
+
+    /// Данные в формате CSV.
+    const CSV: &str = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n\
+        1,DEPOSIT,0,772,29,1734172695334,SUCCESS,\"Deposit\"\n";
+
+    /// Код состояния ответа на запрос.
+    fn status_code(method: Method, url: &str, body: &[u8], max_body_size: usize) -> u16 {
+        route(&method, url, None, body, max_body_size)
+            .unwrap_or_else(Reply::from)
+            .status_code
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = Compression::Gzip.encoder(vec![]).unwrap();
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[rstest]
+    #[case::plain("a=1&b=x", &[("a", "1"), ("b", "x")])]
+    #[case::encoded("from=a%75to&to=j%73on", &[("from", "auto"), ("to", "json")])]
+    #[case::plus("q=a+b%2Bc", &[("q", "a b+c")])]
+    #[case::invalid("q=100%&r=%zz", &[("q", "100%"), ("r", "%zz")])]
+    #[case::utf8("q=%D0%AF", &[("q", "Я")])]
+    fn test_query_parse(#[case] query: &str, #[case] expected: &[(&str, &str)]) {
+        let query = Query::parse(query);
+
+        for (key, value) in expected {
+            assert_eq!(query.0[*key], *value);
+        }
+    }
+
+    #[rstest]
+    #[case::convert(Method::Post, "/convert?from=csv&to=json", 200)]
+    #[case::encoded_query(Method::Post, "/convert?from=%61uto&to=json", 200)]
+    #[case::stats(Method::Post, "/stats?from=auto", 200)]
+    #[case::validate(Method::Post, "/validate?from=csv", 200)]
+    #[case::metrics(Method::Get, "/metrics", 200)]
+    #[case::missing_parameter(Method::Post, "/convert?from=csv", 400)]
+    #[case::unknown_format(Method::Post, "/convert?from=csv&to=unknown", 400)]
+    #[case::not_found(Method::Post, "/unknown", 404)]
+    #[case::method_not_allowed(Method::Get, "/convert?from=csv&to=json", 405)]
+    fn test_route_status_code(#[case] method: Method, #[case] url: &str, #[case] expected: u16) {
+        assert_eq!(status_code(method, url, CSV.as_bytes(), 1024), expected);
+    }
+
+    #[test]
+    fn test_body_too_large() {
+        let url = "/convert?from=csv&to=json";
+
+        assert_eq!(
+            status_code(Method::Post, url, CSV.as_bytes(), CSV.len()),
+            200
+        );
+        assert_eq!(
+            status_code(Method::Post, url, CSV.as_bytes(), CSV.len() - 1),
+            413
+        );
+    }
+
+    #[test]
+    fn test_decompressed_body_too_large() {
+        let url = "/convert?from=csv&to=json";
+        let body = gzip(CSV.as_bytes());
+        let max_body_size = body.len().max(CSV.len());
+
+        assert_eq!(status_code(Method::Post, url, &body, max_body_size), 200);
+
+        let bomb = gzip(&vec![b'0'; 1024 * 1024]);
+
+        assert_eq!(status_code(Method::Post, url, &bomb, 64 * 1024), 413);
+    }
+
+    #[test]
+    fn test_multipart_body() {
+        let body = format!(
+            "--xyz\r\nContent-Disposition: form-data; name=\"file\"\r\n\r\n{CSV}\r\n--xyz--\r\n"
+        );
+        let reply = route(
+            &Method::Post,
+            "/convert?from=csv&to=jsonl",
+            Some("multipart/form-data; boundary=xyz"),
+            body.as_bytes(),
+            1024,
+        )
+        .unwrap();
+
+        assert_eq!(reply.status_code, 200);
+        assert_eq!(reply.content_type, "application/x-ndjson");
+        assert!(String::from_utf8(reply.body).unwrap().contains("Deposit"));
+    }
+}
@@ -1,92 +1,2255 @@
-use clap::Parser;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum, error::ErrorKind};
+#[cfg(feature = "parquet")]
+use parser::YPBankParquet;
+#[cfg(feature = "sqlite")]
+use parser::YPBankSqlite;
+#[cfg(feature = "encoding")]
+use parser::encoding::{EncodeWriter, Encoding};
 use parser::{
-    YPBankImpl,
-    errors::{FormatError, ReadError, WriteError},
+    AnonymizePolicy, Anonymizer, CsvColumnMapping, CsvOptions, DescriptionPolicy, ExternalSort,
+    Filter, Fingerprint, FixedWidthLayout, MarkdownOptions, MaskDescriptions, MergeSorted,
+    PartitionedWriter, Record, RecordIter, RecordKey, RecordWriter, RecoveryGap, RemapUserIds,
+    SchemaVersion, Severity, ShiftTimestamps, SizeLimits, SortOrder, SqlDialect, Stats,
+    StatsReport, Status, Tagger, TimestampCheck, Transform, TxType, UserIdPolicy, ValidationConfig,
+    Validator, WriteOptions, YPBankBin, YPBankImpl,
+    compression::{Compression, Encoder, decompress_detected},
+    diff::{self, ChangeSet},
+    errors::{
+        ApplyError, BinStructureError, DiffError, FormatError, ReadError, SortError, WriteError,
+    },
+    fingerprint::to_hex,
+    inspect::{BinInspector, InspectedItem},
+    line_ending::{LineEnding, LineEndingWriter},
+    statement::{self, Period, StatementFormat},
+    transform_records,
 };
-use std::io::Write;
+#[cfg(feature = "encryption")]
+use parser::{
+    YPBank,
+    encryption::{EncryptionKey, decrypt, write_to_bin_encrypted},
+    errors::EncryptionError,
+};
+#[cfg(feature = "signing")]
+use parser::{
+    errors::SigningError,
+    signing::{Signature, SigningKey, VerifyingKey},
+};
+use split::{SplitBy, part_path};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
-#[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
-struct Args {
-    /// File to read
-    #[arg(long, value_name = "FILE")]
-    input: std::path::PathBuf,
+mod batch;
+#[cfg(feature = "tracing")]
+mod logging;
+mod progress;
+mod split;
+#[cfg(feature = "watch")]
+mod watch;
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Convert YPBank transaction data between formats", long_about = None, args_conflicts_with_subcommands = true)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    convert: Option<ConvertArgs>,
+
+    #[cfg(feature = "tracing")]
+    #[command(flatten)]
+    logging: logging::LogArgs,
+}
+
+/// Подкоманды конвертера; без подкоманды данные конвертируются.
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print summary statistics of transactions
+    Stats(StatsArgs),
+
+    /// Check input files and print every problem found, without converting them
+    Validate(ValidateArgs),
+
+    /// Print a format-independent SHA-256 fingerprint of transactions in each input file
+    Fingerprint(FingerprintArgs),
+
+    /// Print an account statement of a user for a period: balances, transactions and totals
+    Statement(StatementArgs),
+
+    /// Recover intact records from a corrupted or truncated `bin` file, skipping damaged data
+    Repair(RepairArgs),
+
+    /// Print each record of a `bin` file as an annotated hex dump, stopping at the first
+    /// structural error
+    Inspect(InspectArgs),
+
+    /// Sort transactions by field values using temporary files, for inputs larger than memory
+    Sort(SortArgs),
+
+    /// Write the changes (added, removed and modified transactions by TX_ID) between a base
+    /// and a current file, to ship them instead of the whole current file
+    Delta(DeltaArgs),
+
+    /// Apply changes written by `delta` to the base file, restoring the current file
+    ApplyDelta(ApplyDeltaArgs),
+
+    /// Convert many files concurrently, each into its own output file, and print a summary
+    Batch(BatchArgs),
+
+    /// Sign the fingerprint of transactions in the input file with an Ed25519 private key
+    #[cfg(feature = "signing")]
+    Sign(SignArgs),
+
+    /// Verify the signature of the fingerprint of transactions in the input file
+    #[cfg(feature = "signing")]
+    Verify(VerifyArgs),
+
+    /// Monitor a directory and convert files as they appear in it, quarantining failed ones
+    #[cfg(feature = "watch")]
+    Watch(WatchArgs),
+}
+
+/// Формат вывода отчета подкоманды.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ReportFormat {
+    /// Human-readable text
+    Text,
+
+    /// Machine-readable JSON report
+    Json,
+}
+
+/// Формат набора изменений.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum DeltaFormat {
+    /// Compact binary format
+    Bin,
+
+    /// Human-readable text format
+    Text,
+}
+
+/// Формат вывода выписки по счету.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum StatementOutput {
+    /// Human-readable text
+    Text,
+
+    /// CSV table of statement entries
+    Csv,
+
+    /// HTML page
+    Html,
+}
+
+/// Реализация трейта [`From<StatementOutput>`] для [`StatementFormat`].
+impl From<StatementOutput> for StatementFormat {
+    /// Реализация метода [`From<StatementOutput>::from`] для [`StatementFormat`].
+    fn from(value: StatementOutput) -> Self {
+        match value {
+            StatementOutput::Text => StatementFormat::Text,
+            StatementOutput::Csv => StatementFormat::Csv,
+            StatementOutput::Html => StatementFormat::Html,
+        }
+    }
+}
+
+/// Важность нарушений смысловых правил операций при проверке.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum RuleSeverity {
+    /// Report violations as warnings
+    Warning,
+
+    /// Report violations as errors
+    Error,
+}
+
+/// Реализация трейта [`From<RuleSeverity>`] для [`Severity`].
+impl From<RuleSeverity> for Severity {
+    /// Реализация метода [`From<RuleSeverity>::from`] для [`Severity`].
+    fn from(value: RuleSeverity) -> Self {
+        match value {
+            RuleSeverity::Warning => Severity::Warning,
+            RuleSeverity::Error => Severity::Error,
+        }
+    }
+}
+
+/// Аргументы параметров форматов входных данных.
+#[derive(clap::Args, Debug)]
+struct FormatArgs {
+    /// Field layout for the `fixed` format: comma-separated `KEY:WIDTH[:ALIGN[:PADDING]]`
+    #[arg(long, value_name = "SPEC", value_parser = |s: &str| FixedWidthLayout::try_from(s))]
+    fixed_layout: Option<FixedWidthLayout>,
+
+    /// Comma-separated fields written as columns of the `markdown` format (all fields except CURRENCY by default)
+    #[arg(long, value_name = "KEYS", value_delimiter = ',', value_parser = parse_record_key)]
+    markdown_columns: Vec<RecordKey>,
+
+    /// Maximum number of rows of the `markdown` format table
+    #[arg(long, value_name = "N")]
+    markdown_max_rows: Option<usize>,
+
+    /// Field delimiter for the `csv` format (`\t` or `tab` for a tab character)
+    #[arg(long, value_name = "CHAR", default_value = ",", value_parser = parse_char)]
+    csv_delimiter: char,
+
+    /// Quote character for the `csv` and `tsv` formats
+    #[arg(long, value_name = "CHAR", default_value = "\"", value_parser = parse_char)]
+    csv_quote: char,
+
+    /// Accept CSV header columns in any order and case
+    #[arg(long)]
+    csv_tolerant_header: bool,
+
+    /// File mapping CSV header columns to fields, one `COLUMN=KEY` per line (implies `--csv-tolerant-header`)
+    #[arg(long, value_name = "FILE", value_parser = read_column_mapping)]
+    csv_mapping: Option<CsvColumnMapping>,
+
+    /// Comma-separated keys of metadata fields read and written as extra columns in the `csv` and `tsv` formats
+    #[arg(long, value_name = "KEYS", value_delimiter = ',', value_parser = parse_metadata_key)]
+    csv_metadata_columns: Vec<String>,
+
+    /// Read unknown TX_TYPE and STATUS values (e.g. from newer exports) as is instead of failing
+    #[arg(long)]
+    lenient: bool,
+
+    /// Maximum size of a record in bytes: a `bin` record body or the lines of a `text` or `csv` record
+    #[arg(long, value_name = "BYTES", default_value_t = SizeLimits::DEFAULT.max_record_size)]
+    max_record_size: u32,
+
+    /// Maximum length of a `bin` record description in bytes
+    #[arg(long, value_name = "BYTES", default_value_t = SizeLimits::DEFAULT.max_description_len)]
+    max_description_len: u32,
+
+    /// Encoding of text input files, e.g. `cp1251` or `utf-16le`
+    #[cfg(feature = "encoding")]
+    #[arg(long, value_name = "ENCODING", default_value = "utf-8", value_parser = |s: &str| Encoding::try_from(s))]
+    input_encoding: Encoding,
+
+    /// File with the AES-256 key (64 hexadecimal digits) for the `bin-encrypted` format
+    #[cfg(feature = "encryption")]
+    #[arg(long, value_name = "FILE", value_parser = read_encryption_key, conflicts_with = "encryption_key_env")]
+    encryption_key: Option<EncryptionKey>,
+
+    /// Environment variable with the AES-256 key (64 hexadecimal digits) for the `bin-encrypted` format
+    #[cfg(feature = "encryption")]
+    #[arg(long, value_name = "VAR", value_parser = |s: &str| EncryptionKey::from_env(s))]
+    encryption_key_env: Option<EncryptionKey>,
+}
+
+impl FormatArgs {
+    /// Параметры форматов данных, заданные аргументами командной строки.
+    fn options(&self) -> FormatOptions {
+        if self.csv_delimiter == self.csv_quote {
+            Args::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "`--csv-delimiter` and `--csv-quote` must differ",
+                )
+                .exit();
+        }
+
+        FormatOptions {
+            csv: CsvOptions {
+                delimiter: self.csv_delimiter,
+                quote_char: self.csv_quote,
+                tolerant_header: self.csv_tolerant_header || self.csv_mapping.is_some(),
+                column_mapping: self.csv_mapping.clone().unwrap_or_default(),
+                metadata_columns: self.csv_metadata_columns.clone(),
+                ..CsvOptions::default()
+            },
+            fixed_layout: self.fixed_layout.clone().unwrap_or_default(),
+            markdown: MarkdownOptions {
+                columns: if self.markdown_columns.is_empty() {
+                    MarkdownOptions::default().columns
+                } else {
+                    self.markdown_columns.clone()
+                },
+                max_rows: self.markdown_max_rows,
+            },
+            lenient: self.lenient,
+            limits: SizeLimits {
+                max_record_size: self.max_record_size,
+                max_description_len: self.max_description_len,
+            },
+            #[cfg(feature = "encoding")]
+            encoding: self.input_encoding,
+            #[cfg(feature = "encryption")]
+            encryption_key: self
+                .encryption_key
+                .clone()
+                .or_else(|| self.encryption_key_env.clone()),
+        }
+    }
+}
+
+/// Считать файл соответствия имен столбцов CSV-таблицы полям записи.
+fn read_column_mapping(path: &str) -> Result<CsvColumnMapping, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+
+    CsvColumnMapping::try_from(content.as_str()).map_err(|e| format!("{path}: {e}"))
+}
+
+/// Считать файл правил присвоения категорий транзакциям.
+fn read_tag_rules(path: &str) -> Result<Tagger, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+
+    Tagger::try_from(content.as_str()).map_err(|e| format!("{path}: {e}"))
+}
+
+/// Считать ключ шифрования из файла.
+#[cfg(feature = "encryption")]
+fn read_encryption_key(path: &str) -> Result<EncryptionKey, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+
+    EncryptionKey::try_from(content.as_str()).map_err(|e| format!("{path}: {e}"))
+}
+
+/// Разобрать ключ дополнительного поля записи.
+fn parse_metadata_key(s: &str) -> Result<String, String> {
+    let key = s.trim();
+
+    if !Record::is_metadata_key(key) {
+        return Err(format!(
+            "invalid metadata key `{key}`: expected letters, digits, `_`, `-` or `.` and not a field key"
+        ));
+    }
+
+    Ok(key.to_string())
+}
+
+/// Считать файл со списком TX_ID, по одному в строке; пустые строки и строки,
+/// начинающиеся с `#`, пропускаются.
+fn read_tx_ids(path: &str) -> Result<HashSet<u64>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+
+    content
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            line.parse()
+                .map_err(|_| format!("{path}: line {number}: invalid TX_ID `{line}`"))
+        })
+        .collect()
+}
+
+/// Считать из файла пары `OLD=NEW` исходных и новых идентификаторов участников, по одной
+/// в строке. Пустые строки и строки, начинающиеся с `#`, пропускаются.
+fn read_user_id_mapping(path: &str) -> Result<RemapUserIds, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+
+    content
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            line.split_once('=')
+                .and_then(|(old, new)| Some((old.trim().parse().ok()?, new.trim().parse().ok()?)))
+                .ok_or_else(|| {
+                    format!("{path}: line {number}: expected `OLD=NEW` user IDs, got `{line}`")
+                })
+        })
+        .collect()
+}
+
+/// Разобрать ключ поля записи без учета регистра.
+fn parse_record_key(s: &str) -> Result<RecordKey, String> {
+    RecordKey::try_from(s.trim().to_uppercase().as_str()).map_err(|e| e.to_string())
+}
+
+/// Разобрать ключ поля, значение которого удаляется, без учета регистра.
+fn parse_redact_field(s: &str) -> Result<RecordKey, String> {
+    let field = parse_record_key(s)?;
+
+    Record::check_redactable(&[field]).map_err(|e| e.to_string())?;
+
+    Ok(field)
+}
+
+/// Аргументы подкоманды `stats`.
+#[derive(clap::Args, Debug)]
+struct StatsArgs {
+    /// File to read (`-` to read from stdin); can be repeated to gather statistics over several files
+    #[arg(long, value_name = "FILE", required = true)]
+    input: Vec<PathBuf>,
+
+    /// Data format in the file to read (`auto` to detect it by the file content);
+    /// one for each `--input`
+    #[clap(long, value_name = "FORMAT", required = true)]
+    input_format: Vec<String>,
+
+    #[command(flatten)]
+    formats: FormatArgs,
+
+    /// Number of users with the largest total AMOUNT to print
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    top: usize,
+
+    /// File with rules assigning categories to transactions before gathering statistics
+    #[arg(long, value_name = "FILE", value_parser = read_tag_rules)]
+    tag_rules: Option<Tagger>,
+
+    /// Output format of the statistics
+    #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+    report: ReportFormat,
+}
+
+/// Аргументы подкоманды `validate`.
+#[derive(clap::Args, Debug)]
+struct ValidateArgs {
+    /// File to read (`-` to read from stdin); can be repeated to check several files
+    #[arg(long, value_name = "FILE", required = true)]
+    input: Vec<PathBuf>,
+
+    /// Data format in the file to read (`auto` to detect it by the file content);
+    /// one for each `--input`
+    #[clap(long, value_name = "FORMAT", required = true)]
+    input_format: Vec<String>,
+
+    /// Report implausible timestamps as errors: negative when read as signed 64-bit numbers,
+    /// later than now plus `--max-future-skew`, or outside `--since`..`--until`
+    #[arg(long)]
+    check_timestamps: bool,
+
+    /// Allowed clock skew (seconds) of timestamps in the future for `--check-timestamps`
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value = "300",
+        requires = "check_timestamps"
+    )]
+    max_future_skew: u64,
+
+    /// With `--check-timestamps`, report TIMESTAMP (ms) less than the given one
+    #[arg(long, value_name = "MS", requires = "check_timestamps")]
+    since: Option<u64>,
+
+    /// With `--check-timestamps`, report TIMESTAMP (ms) not less than the given one
+    #[arg(long, value_name = "MS", requires = "check_timestamps")]
+    until: Option<u64>,
+
+    /// Check transfers to the sender, zero amounts and deposits / withdrawals with
+    /// a counterparty, reporting violations with the given severity
+    #[arg(long, value_name = "SEVERITY")]
+    semantic_rules: Option<RuleSeverity>,
+
+    #[command(flatten)]
+    formats: FormatArgs,
+}
+
+impl ValidateArgs {
+    /// Параметры проверки записей.
+    fn config(&self) -> ValidationConfig {
+        let window = (self.since.is_some() || self.until.is_some())
+            .then(|| (self.since.unwrap_or(0), self.until.unwrap_or(u64::MAX)));
+
+        ValidationConfig {
+            timestamp_check: self.check_timestamps.then_some(TimestampCheck {
+                max_future_skew: self.max_future_skew.saturating_mul(1000),
+                window,
+            }),
+            semantic_rules: self.semantic_rules.map(Severity::from),
+            ..Default::default()
+        }
+    }
+}
+
+/// Аргументы подкоманды `fingerprint`.
+#[derive(clap::Args, Debug)]
+struct FingerprintArgs {
+    /// File to read (`-` to read from stdin); can be repeated to fingerprint several files
+    #[arg(long, value_name = "FILE", required = true)]
+    input: Vec<PathBuf>,
+
+    /// Data format in the file to read (`auto` to detect it by the file content);
+    /// one for each `--input`
+    #[clap(long, value_name = "FORMAT", required = true)]
+    input_format: Vec<String>,
+
+    #[command(flatten)]
+    formats: FormatArgs,
+}
+
+/// Аргументы подкоманды `statement`.
+#[derive(clap::Args, Debug)]
+struct StatementArgs {
+    /// File to read (`-` to read from stdin); can be repeated to build a statement over several files
+    #[arg(long, value_name = "FILE", required = true)]
+    input: Vec<PathBuf>,
+
+    /// Data format in the file to read (`auto` to detect it by the file content);
+    /// one for each `--input`
+    #[clap(long, value_name = "FORMAT", required = true)]
+    input_format: Vec<String>,
+
+    #[command(flatten)]
+    formats: FormatArgs,
+
+    /// User ID to build the statement for
+    #[arg(long, value_name = "USER_ID")]
+    user: u64,
+
+    /// Statement period: a calendar month `YYYY-MM` (UTC) or a TIMESTAMP range `SINCE..UNTIL` (ms, UNTIL excluded)
+    #[arg(long, value_name = "PERIOD", value_parser = |s: &str| Period::try_from(s))]
+    period: Period,
+
+    /// Output format of the statement
+    #[arg(long, value_enum, default_value_t = StatementOutput::Text)]
+    format: StatementOutput,
+
+    /// File to write the statement to (stdout if not specified)
+    #[arg(long, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+/// Аргументы подкоманды `repair`.
+#[derive(clap::Args, Debug)]
+struct RepairArgs {
+    /// Corrupted `bin` file to read (`-` to read from stdin)
+    #[arg(long, value_name = "FILE")]
+    input: PathBuf,
+
+    /// File to write recovered records to in the `bin` format (stdout if not specified)
+    #[arg(long, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+/// Аргументы подкоманды `inspect`.
+#[derive(clap::Args, Debug)]
+struct InspectArgs {
+    /// `bin` file to inspect (`-` to read from stdin)
+    #[arg(long, value_name = "FILE")]
+    input: PathBuf,
+
+    /// Maximum number of records to print
+    #[arg(long, value_name = "N")]
+    max_records: Option<usize>,
+}
+
+/// Аргументы подкоманды `sort`.
+#[derive(clap::Args, Debug)]
+struct SortArgs {
+    /// File to read (`-` to read from stdin); can be repeated to sort several files together
+    #[arg(long, value_name = "FILE", required = true)]
+    input: Vec<PathBuf>,
+
+    /// Data format in the file to read (`auto` to detect it by the file content);
+    /// one for each `--input`
+    #[clap(long, value_name = "FORMAT", required = true)]
+    input_format: Vec<String>,
+
+    #[command(flatten)]
+    formats: FormatArgs,
+
+    /// Show reading progress in stderr
+    #[arg(long)]
+    progress: bool,
+
+    /// Comma-separated sort keys `KEY[:asc|:desc]`; each next key orders records with equal
+    /// values of the previous ones
+    #[arg(
+        long,
+        value_name = "KEYS",
+        value_delimiter = ',',
+        default_value = "TIMESTAMP",
+        value_parser = parse_sort_key
+    )]
+    key: Vec<(RecordKey, SortOrder)>,
+
+    /// Directory for temporary files (the system temporary directory if not specified)
+    #[arg(long, value_name = "DIR")]
+    tmp_dir: Option<PathBuf>,
+
+    /// Number of records sorted in memory at a time; lower it to reduce memory usage
+    #[arg(long, value_name = "N", default_value_t = ExternalSort::DEFAULT_CHUNK_RECORDS)]
+    chunk_records: usize,
+
+    /// Output data format
+    #[clap(long, value_name = "FORMAT")]
+    output_format: String,
+
+    /// File to write (stdout if not specified)
+    #[arg(long, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+/// Аргументы подкоманды `delta`.
+#[derive(clap::Args, Debug)]
+struct DeltaArgs {
+    /// Base (previous) file to read (`-` to read from stdin)
+    #[arg(long, value_name = "FILE")]
+    base: PathBuf,
+
+    /// Data format in the base file (`auto` to detect it by the file content)
+    #[clap(long, value_name = "FORMAT", default_value = "auto")]
+    base_format: String,
+
+    /// Current file to read (`-` to read from stdin)
+    #[arg(long, value_name = "FILE")]
+    current: PathBuf,
+
+    /// Data format in the current file (`auto` to detect it by the file content)
+    #[clap(long, value_name = "FORMAT", default_value = "auto")]
+    current_format: String,
+
+    #[command(flatten)]
+    formats: FormatArgs,
+
+    /// Format of the changes to write
+    #[arg(long, value_enum, default_value_t = DeltaFormat::Bin)]
+    delta_format: DeltaFormat,
+
+    /// File to write the changes to (stdout if not specified)
+    #[arg(long, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+/// Аргументы подкоманды `apply-delta`.
+#[derive(clap::Args, Debug)]
+struct ApplyDeltaArgs {
+    /// Base file the changes were computed against (`-` to read from stdin)
+    #[arg(long, value_name = "FILE")]
+    base: PathBuf,
+
+    /// Data format in the base file (`auto` to detect it by the file content)
+    #[clap(long, value_name = "FORMAT", default_value = "auto")]
+    base_format: String,
+
+    #[command(flatten)]
+    formats: FormatArgs,
+
+    /// File with the changes written by `delta` in any format (`-` to read from stdin)
+    #[arg(long, value_name = "FILE")]
+    delta: PathBuf,
+
+    /// Output data format
+    #[clap(long, value_name = "FORMAT")]
+    output_format: String,
+
+    /// File to write (stdout if not specified)
+    #[arg(long, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+/// Аргументы подкоманды `batch`.
+#[derive(clap::Args, Debug)]
+struct BatchArgs {
+    /// File or glob pattern (e.g. `exports/*.csv`) of files to convert; can be repeated
+    #[arg(long, value_name = "PATTERN", required = true)]
+    input: Vec<String>,
+
+    /// Data format of the input files (`auto` to detect it by the content of each file)
+    #[clap(long, value_name = "FORMAT", default_value = "auto")]
+    input_format: String,
+
+    #[command(flatten)]
+    formats: FormatArgs,
+
+    /// Output data format
+    #[clap(long, value_name = "FORMAT")]
+    output_format: String,
+
+    /// Directory to write converted files to, named after input files with the output format
+    /// as the extension (the directory of each input file if not specified)
+    #[arg(long, value_name = "DIR")]
+    out_dir: Option<PathBuf>,
+
+    /// Schema version of written records: `1` (AMOUNT within u64) or `2` (negative
+    /// and wider amounts)
+    #[arg(long, value_name = "VERSION", default_value = "1", value_parser = |s: &str| SchemaVersion::try_from(s))]
+    schema_version: SchemaVersion,
+
+    /// Number of files converted concurrently (the number of CPUs if not specified)
+    #[arg(long, value_name = "N")]
+    jobs: Option<usize>,
+}
+
+/// Разобрать ключ сортировки `KEY[:asc|:desc]` без учета регистра.
+fn parse_sort_key(s: &str) -> Result<(RecordKey, SortOrder), String> {
+    let (key, order) = s.trim().split_once(':').unwrap_or((s.trim(), "asc"));
+    let key = RecordKey::try_from(key.to_uppercase().as_str()).map_err(|e| e.to_string())?;
+
+    let order = match order.to_lowercase().as_str() {
+        "asc" => SortOrder::Ascending,
+        "desc" => SortOrder::Descending,
+        order => {
+            return Err(format!(
+                "invalid sort order `{order}`: expected `asc` or `desc`"
+            ));
+        }
+    };
+
+    Ok((key, order))
+}
+
+/// Аргументы подкоманды `watch`.
+#[cfg(feature = "watch")]
+#[derive(clap::Args, Debug)]
+struct WatchArgs {
+    /// Directory to monitor for new files
+    #[arg(long, value_name = "DIR")]
+    in_dir: PathBuf,
+
+    /// Directory to write converted files to, named after input files with the output format
+    /// as the extension (must not be `--in-dir` or inside it)
+    #[arg(long, value_name = "DIR")]
+    out_dir: PathBuf,
+
+    /// Directory to move files failed to convert to, each with a `<FILE>.error.txt` report
+    /// (`quarantine` in `--out-dir` if not specified; must not be `--in-dir` or inside it)
+    #[arg(long, value_name = "DIR")]
+    quarantine_dir: Option<PathBuf>,
+
+    /// Data format of incoming files (`auto` to detect it by the file content)
+    #[clap(long, value_name = "FORMAT", default_value = "auto")]
+    input_format: String,
+
+    #[command(flatten)]
+    formats: FormatArgs,
+
+    /// Output data format
+    #[clap(long, value_name = "FORMAT")]
+    output_format: String,
+
+    /// Schema version of written records: `1` (AMOUNT within u64) or `2` (negative
+    /// and wider amounts)
+    #[arg(long, value_name = "VERSION", default_value = "1", value_parser = |s: &str| SchemaVersion::try_from(s))]
+    schema_version: SchemaVersion,
+
+    /// Time in milliseconds a new file must stay unchanged before it is converted
+    #[arg(long, value_name = "MS", default_value_t = 1000)]
+    settle_time: u64,
+
+    /// Convert files already present in `--in-dir` and exit instead of monitoring it
+    #[arg(long)]
+    once: bool,
+}
+
+/// Аргументы подкоманды `sign`.
+#[cfg(feature = "signing")]
+#[derive(clap::Args, Debug)]
+struct SignArgs {
+    /// File to sign (`-` to read from stdin)
+    #[arg(long, value_name = "FILE")]
+    input: PathBuf,
+
+    /// Data format in the file to sign (`auto` to detect it by the file content)
+    #[clap(long, value_name = "FORMAT")]
+    input_format: String,
+
+    #[command(flatten)]
+    formats: FormatArgs,
+
+    /// Ed25519 private key in PKCS#8 PEM format
+    #[arg(long, value_name = "FILE", value_parser = read_signing_key)]
+    key: SigningKey,
+
+    /// File to write the signature to (stdout if not specified)
+    #[arg(long, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+/// Аргументы подкоманды `verify`.
+#[cfg(feature = "signing")]
+#[derive(clap::Args, Debug)]
+struct VerifyArgs {
+    /// File to verify (`-` to read from stdin)
+    #[arg(long, value_name = "FILE")]
+    input: PathBuf,
+
+    /// Data format in the file to verify (`auto` to detect it by the file content)
+    #[clap(long, value_name = "FORMAT")]
+    input_format: String,
+
+    #[command(flatten)]
+    formats: FormatArgs,
+
+    /// Ed25519 public key in SPKI PEM format
+    #[arg(long, value_name = "FILE", value_parser = read_verifying_key)]
+    public_key: VerifyingKey,
+
+    /// File with the signature written by the `sign` subcommand
+    #[arg(long, value_name = "FILE", value_parser = read_signature)]
+    signature: Signature,
+}
+
+/// Считать закрытый ключ подписи из файла.
+#[cfg(feature = "signing")]
+fn read_signing_key(path: &str) -> Result<SigningKey, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+
+    SigningKey::from_pem(&content).map_err(|e| format!("{path}: {e}"))
+}
+
+/// Считать открытый ключ проверки подписи из файла.
+#[cfg(feature = "signing")]
+fn read_verifying_key(path: &str) -> Result<VerifyingKey, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+
+    VerifyingKey::from_pem(&content).map_err(|e| format!("{path}: {e}"))
+}
+
+/// Считать подпись из файла.
+#[cfg(feature = "signing")]
+fn read_signature(path: &str) -> Result<Signature, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+
+    Signature::try_from(content.as_str()).map_err(|e| format!("{path}: {e}"))
+}
+
+// Аргументы конвертации данных.
+#[derive(clap::Args, Debug)]
+struct ConvertArgs {
+    // Группа аргументов с вложенными аргументами не заполняется clap автоматически, а по ее
+    // наличию определяется, что заданы аргументы конвертации.
+    /// File to read (`-` to read from stdin); can be repeated to merge several files
+    #[arg(long, value_name = "FILE", required = true, group = "ConvertArgs")]
+    input: Vec<PathBuf>,
+
+    /// Data format in the file to read (`auto` to detect it by the file content);
+    /// one for each `--input`
+    #[clap(long, value_name = "FORMAT", required = true)]
+    input_format: Vec<String>,
+
+    #[command(flatten)]
+    formats: FormatArgs,
+
+    /// Show conversion progress in stderr
+    #[arg(long)]
+    progress: bool,
+
+    /// Sort merged transactions by TIMESTAMP (requires reading all of them into memory)
+    #[arg(long)]
+    sort_by_timestamp: bool,
+
+    /// Merge input files already sorted by TIMESTAMP into sorted output without reading them
+    /// into memory
+    #[arg(long, conflicts_with = "sort_by_timestamp")]
+    merge_sorted: bool,
+
+    /// Output data format
+    #[clap(long, value_name = "FORMAT", group = "ConvertArgs")]
+    output_format: String,
+
+    /// File to write (stdout if not specified)
+    #[arg(long, value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// SQL dialect for `--output-format sql`: `postgres`, `mysql` or `sqlite`
+    #[arg(long, value_name = "DIALECT", default_value = "postgres", value_parser = |s: &str| SqlDialect::try_from(s))]
+    sql_dialect: SqlDialect,
+
+    /// Do not write the header line in the `csv` and `tsv` output formats
+    #[arg(long)]
+    no_csv_header: bool,
+
+    /// Line ending of text output formats: `lf` or `crlf`
+    #[arg(long, value_name = "LINE_ENDING", default_value = "lf", value_parser = |s: &str| LineEnding::try_from(s))]
+    line_ending: LineEnding,
+
+    /// Write a UTF-8 byte order mark at the beginning of text output formats
+    #[arg(long)]
+    bom: bool,
+
+    /// Encoding of text output formats, e.g. `cp1251` or `utf-16le`
+    #[cfg(feature = "encoding")]
+    #[arg(long, value_name = "ENCODING", default_value = "utf-8", value_parser = |s: &str| Encoding::try_from(s))]
+    output_encoding: Encoding,
+
+    /// Output compression: `none`, `gzip` or `zstd`
+    #[arg(long, value_name = "COMPRESSION", default_value = "none", value_parser = |s: &str| Compression::try_from(s))]
+    compress: Compression,
+
+    /// Schema version of written records: `1` (AMOUNT within u64) or `2` (negative
+    /// and wider amounts)
+    #[arg(long, value_name = "VERSION", default_value = "1", value_parser = |s: &str| SchemaVersion::try_from(s))]
+    schema_version: SchemaVersion,
+
+    /// Capacity of the output write buffer in bytes; larger buffers reduce system calls when
+    /// writing many small records to a pipe
+    #[arg(long, value_name = "BYTES", default_value_t = WriteOptions::DEFAULT_BUFFER_CAPACITY)]
+    write_buffer_size: usize,
+
+    /// Split output into several files (partitions): `count:N` records per file, `day`,
+    /// `month` or `user:N` buckets of user ids
+    #[arg(long, visible_alias = "partition-by", value_name = "SPLIT", requires = "output", value_parser = |s: &str| SplitBy::try_from(s))]
+    split_by: Option<SplitBy>,
+
+    /// Convert only transactions with the given STATUS
+    #[arg(long, value_name = "STATUS", value_parser = |s: &str| Status::try_from(s.to_uppercase().as_str()))]
+    status: Option<Status>,
+
+    /// Convert only transactions with the given TX_TYPE
+    #[arg(long, value_name = "TX_TYPE", value_parser = |s: &str| TxType::try_from(s.to_uppercase().as_str()))]
+    tx_type: Option<TxType>,
+
+    /// Convert only transactions with the given FROM_USER_ID
+    #[arg(long, value_name = "USER_ID")]
+    from_user: Option<u64>,
+
+    /// Convert only transactions with the given TO_USER_ID
+    #[arg(long, value_name = "USER_ID")]
+    to_user: Option<u64>,
+
+    /// Convert only transactions with AMOUNT not less than the given one
+    #[arg(long, value_name = "AMOUNT")]
+    min_amount: Option<u64>,
+
+    /// Convert only transactions with TIMESTAMP (ms) not less than the given one
+    #[arg(long, value_name = "MS")]
+    since: Option<u64>,
+
+    /// Convert only transactions with TIMESTAMP (ms) less than the given one
+    #[arg(long, value_name = "MS")]
+    until: Option<u64>,
+
+    /// File with TX_IDs of transactions to redact, one per line
+    #[arg(long, value_name = "FILE", value_parser = read_tx_ids)]
+    redact_file: Option<HashSet<u64>>,
+
+    /// Comma-separated fields to blank in transactions listed in `--redact-file`
+    #[arg(long, value_name = "KEYS", value_delimiter = ',', default_value = "from_user_id,to_user_id,description", requires = "redact_file", value_parser = parse_redact_field)]
+    redact_fields: Vec<RecordKey>,
+
+    /// File with rules assigning categories to transactions (stored in the `category` metadata field)
+    #[arg(long, value_name = "FILE", value_parser = read_tag_rules)]
+    tag_rules: Option<Tagger>,
+
+    /// Replace digits in descriptions with `*`
+    #[arg(long)]
+    mask_descriptions: bool,
+
+    /// Add MS milliseconds (negative to subtract) to TIMESTAMP of transactions
+    #[arg(long, value_name = "MS", allow_negative_numbers = true)]
+    shift_timestamps: Option<i64>,
+
+    /// File with `OLD=NEW` pairs of user IDs, one per line, replacing FROM_USER_ID and TO_USER_ID
+    #[arg(long, value_name = "FILE", value_parser = read_user_id_mapping)]
+    remap_users: Option<RemapUserIds>,
+
+    /// Anonymize transactions: replace user IDs with pseudonyms and redact descriptions
+    #[arg(long)]
+    anonymize: bool,
+
+    /// Salt for hashing user IDs with `--anonymize`, so that pseudonyms match across files
+    /// anonymized with the same salt (users are numbered consecutively if not specified)
+    #[arg(long, value_name = "SALT", requires = "anonymize")]
+    anonymize_salt: Option<String>,
+
+    /// Descriptions with `--anonymize`: `redact`, `mask` (replace digits with `*`) or `keep`
+    #[arg(long, value_name = "POLICY", default_value = "redact", requires = "anonymize", value_parser = |s: &str| DescriptionPolicy::try_from(s))]
+    anonymize_description: DescriptionPolicy,
+}
+
+impl ConvertArgs {
+    /// Признак того, что заданы параметры записи текстовых данных, отличные от умолчаний.
+    fn has_text_output_options(&self) -> bool {
+        #[cfg(feature = "encoding")]
+        if !self.output_encoding.is_utf8() {
+            return true;
+        }
+
+        self.line_ending != LineEnding::Lf || self.bom
+    }
+
+    /// Параметры обезличивания записей, если оно требуется.
+    fn anonymize_policy(&self) -> Option<AnonymizePolicy> {
+        if !self.anonymize {
+            return None;
+        }
+
+        Some(AnonymizePolicy {
+            user_ids: match &self.anonymize_salt {
+                Some(salt) => UserIdPolicy::Hash { salt: salt.clone() },
+                None => UserIdPolicy::Map,
+            },
+            description: self.anonymize_description,
+        })
+    }
+
+    /// Условия отбора записей, заданные аргументами командной строки.
+    fn filter(&self) -> Filter {
+        Filter {
+            status: self.status.clone(),
+            tx_type: self.tx_type.clone(),
+            from_user_id: self.from_user,
+            to_user_id: self.to_user,
+            min_amount: self.min_amount,
+            since: self.since,
+            until: self.until,
+        }
+    }
+
+    /// Преобразования записей, заданные аргументами командной строки, в порядке применения.
+    fn transforms(&self) -> Vec<Box<dyn Transform>> {
+        let mut transforms: Vec<Box<dyn Transform>> = vec![];
+
+        if let Some(tagger) = &self.tag_rules {
+            transforms.push(Box::new(tagger.clone()));
+        }
+
+        if let Some(remap) = &self.remap_users {
+            transforms.push(Box::new(remap.clone()));
+        }
+
+        if let Some(shift) = self.shift_timestamps {
+            transforms.push(Box::new(ShiftTimestamps(shift)));
+        }
+
+        if self.mask_descriptions {
+            transforms.push(Box::new(MaskDescriptions));
+        }
+
+        transforms
+    }
+}
+
+/// Ошибка парсинга данных.
+#[derive(Error, Debug)]
+enum CliError {
+    #[error(transparent)]
+    UnknownFormat(#[from] FormatError),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    ReadData(#[from] ReadError),
+
+    #[error(transparent)]
+    WriteData(#[from] WriteError),
+
+    #[error("Report error: {0}")]
+    Report(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Sort(#[from] SortError),
+
+    #[error("Validation failed: {0} errors found")]
+    Invalid(usize),
+
+    #[cfg(feature = "signing")]
+    #[error(transparent)]
+    Signing(#[from] SigningError),
+
+    #[cfg(feature = "encryption")]
+    #[error(transparent)]
+    Encryption(#[from] EncryptionError),
+
+    #[cfg(feature = "watch")]
+    #[error("Watch error: {0}")]
+    Watch(#[from] notify::Error),
+
+    #[error("Conversion failed for {0} files")]
+    BatchFailed(usize),
+
+    #[error(transparent)]
+    Diff(#[from] DiffError),
+
+    #[error(transparent)]
+    Apply(#[from] ApplyError),
+
+    #[error(transparent)]
+    Structure(#[from] BinStructureError),
+}
+
+macro_rules! convert_format {
+    ($input:expr) => {
+        YPBankImpl::try_from($input)?
+    };
+}
+
+macro_rules! open_and_read {
+    ($file:expr, $format:expr, $options:expr) => {{
+        let (input, size): (Box<dyn Read>, Option<u64>) = if $file == Path::new("-") {
+            (Box::new(std::io::stdin().lock()), None)
+        } else {
+            let file = File::open($file)?;
+            let size = file.metadata()?.len();
+            (Box::new(file), Some(size))
+        };
+        let (compression, input) = decompress_detected(input)?;
+
+        // Размер сжатого или зашифрованного файла не соответствует объему считываемых из него
+        // данных.
+        let size = size.filter(|_| compression == Compression::None && $format != "bin-encrypted");
+
+        // Перекодированные данные бинарных форматов были бы повреждены.
+        #[cfg(feature = "encoding")]
+        let (input, size): (Box<dyn Read>, Option<u64>) = match $format {
+            "bin" | "bin-encrypted" | "msgpack" | "proto" | "protobuf" => (input, size),
+            _ if $options.encoding.is_utf8() => (input, size),
+            _ => (Box::new($options.encoding.decoder(input)), None),
+        };
+
+        let records = match $format {
+            "auto" => {
+                let (format, input) = YPBankImpl::detect_format_from_stream(input)?;
+                format.iter_from(Box::new(input) as Box<dyn Read>)
+            }
+            "csv" => $options.csv.iter_from(input),
+            "tsv" => $options.tsv().iter_from(input),
+            "fixed" => $options.fixed_layout.iter_from(input),
+            #[cfg(feature = "encryption")]
+            "bin-encrypted" => {
+                let plaintext = decrypt(&mut { input }, $options.encryption_key())?;
+                YPBankBin::iter_from(Box::new(std::io::Cursor::new(plaintext)) as Box<dyn Read>)
+            }
+            format => convert_format!(format).iter_from(input),
+        };
+        let records = if $options.lenient {
+            records.lenient()
+        } else {
+            records
+        }
+        .limits($options.limits);
+        #[cfg(feature = "tracing")]
+        let records = records.instrument(tracing::info_span!(
+            "file",
+            path = %$file.display(),
+            format = $format
+        ));
+        (records, size)
+    }};
+}
+
+/// Разобрать символ, заданный аргументом командной строки (`\t` или `tab` — символ табуляции).
+fn parse_char(s: &str) -> Result<char, String> {
+    let mut chars = s.chars();
+
+    match (s, chars.next(), chars.next()) {
+        ("\\t" | "tab", _, _) => Ok('\t'),
+        (_, Some('\n' | '\r'), _) => Err("line breaks are not allowed".to_string()),
+        (_, Some(c), None) => Ok(c),
+        _ => Err(format!("expected a single character, got `{s}`")),
+    }
+}
+
+/// Параметры форматов данных, заданные аргументами командной строки.
+struct FormatOptions {
+    /// Параметры формата `csv`.
+    csv: CsvOptions,
+
+    /// Разметка полей формата `fixed`.
+    fixed_layout: FixedWidthLayout,
+
+    /// Параметры формата `markdown`.
+    markdown: MarkdownOptions,
+
+    /// Признак нестрогого чтения значений (см. [`parser::ParseOptions::lenient`]).
+    lenient: bool,
+
+    /// Ограничения размеров записей (см. [`parser::ParseOptions::limits`]).
+    limits: SizeLimits,
+
+    /// Кодировка входных файлов текстовых форматов.
+    #[cfg(feature = "encoding")]
+    encoding: Encoding,
+
+    /// Ключ шифрования формата `bin-encrypted`.
+    #[cfg(feature = "encryption")]
+    encryption_key: Option<EncryptionKey>,
+}
+
+impl FormatOptions {
+    /// Ключ шифрования формата `bin-encrypted`; если он не задан, приложение завершается
+    /// с ошибкой.
+    #[cfg(feature = "encryption")]
+    fn encryption_key(&self) -> &EncryptionKey {
+        self.encryption_key.as_ref().unwrap_or_else(|| {
+            Args::command()
+                .error(
+                    ErrorKind::MissingRequiredArgument,
+                    "the `bin-encrypted` format requires `--encryption-key` or `--encryption-key-env`",
+                )
+                .exit()
+        })
+    }
+
+    /// Параметры формата `tsv`.
+    fn tsv(&self) -> CsvOptions {
+        CsvOptions {
+            delimiter: '\t',
+            ..self.csv.clone()
+        }
+    }
+}
+
+/// Формат выходных данных.
+enum DataFormat {
+    /// Формат записей о транзакциях.
+    Records(YPBankImpl),
+
+    /// CSV-формат с заданными параметрами.
+    Csv(CsvOptions),
+
+    /// SQL-запросы добавления записей в заданном диалекте.
+    Sql(SqlDialect),
+
+    /// Формат с фиксированной шириной полей с заданной разметкой.
+    FixedWidth(FixedWidthLayout),
+
+    /// Таблица Markdown с заданными параметрами.
+    Markdown(MarkdownOptions),
+}
+
+impl DataFormat {
+    /// Формат выходных данных с указанным названием и параметрами форматов.
+    fn new(
+        format: &str,
+        options: FormatOptions,
+        write_header: bool,
+        sql_dialect: SqlDialect,
+    ) -> Result<Self, CliError> {
+        Ok(match format {
+            "sql" => Self::Sql(sql_dialect),
+            "csv" => Self::Csv(CsvOptions {
+                write_header,
+                ..options.csv
+            }),
+            "tsv" => Self::Csv(CsvOptions {
+                write_header,
+                ..options.tsv()
+            }),
+            "fixed" => Self::FixedWidth(options.fixed_layout),
+            "markdown" | "md" => Self::Markdown(options.markdown),
+            format => Self::Records(convert_format!(format)),
+        })
+    }
+
+    /// Признак текстового формата, допускающего замену окончаний строк.
+    fn is_text(&self) -> bool {
+        match self {
+            Self::Records(format) => matches!(
+                format,
+                YPBankImpl::Text | YPBankImpl::Csv | YPBankImpl::Json | YPBankImpl::Jsonl
+            ),
+            Self::Csv(_) | Self::Sql(_) | Self::FixedWidth(_) | Self::Markdown(_) => true,
+        }
+    }
+}
+
+/// Назначение записи выходных данных текстовых форматов до их сжатия.
+#[cfg(feature = "encoding")]
+type TextWriter<W> = EncodeWriter<Encoder<W>>;
+
+/// Назначение записи выходных данных текстовых форматов до их сжатия.
+#[cfg(not(feature = "encoding"))]
+type TextWriter<W> = Encoder<W>;
+
+/// Назначение записи выходных данных.
+type OutputWriter<W> = RecordWriter<LineEndingWriter<TextWriter<W>>>;
+
+/// Формат и способ сжатия выходных данных.
+struct OutputFormat {
+    /// Формат данных.
+    format: DataFormat,
+
+    /// Способ сжатия.
+    compression: Compression,
+
+    /// Окончание строк текстовых форматов.
+    line_ending: LineEnding,
+
+    /// Признак записи метки порядка байтов UTF-8 в начало данных текстовых форматов.
+    bom: bool,
+
+    /// Кодировка данных текстовых форматов.
+    #[cfg(feature = "encoding")]
+    encoding: Encoding,
+
+    /// Параметры записи: емкость буфера и версия схемы записываемых записей.
+    write_options: WriteOptions,
+}
+
+impl OutputFormat {
+    /// Формат выходных данных без сжатия и преобразования текста.
+    fn plain(format: DataFormat, schema_version: SchemaVersion) -> Self {
+        Self {
+            format,
+            compression: Compression::None,
+            line_ending: LineEnding::default(),
+            bom: false,
+            #[cfg(feature = "encoding")]
+            encoding: Encoding::default(),
+            write_options: WriteOptions {
+                schema_version,
+                ..WriteOptions::default()
+            },
+        }
+    }
+
+    /// Последовательно записывать данные в назначение с учетом формата, окончания строк,
+    /// кодировки и способа сжатия.
+    fn writer_to<W: Write>(&self, w: W) -> std::io::Result<OutputWriter<W>> {
+        let w = self.compression.encoder(w)?;
+
+        #[cfg(feature = "encoding")]
+        let w = self.encoding.encoder(w);
+
+        let w = LineEndingWriter::new(w, self.line_ending, self.bom);
+
+        let writer = match &self.format {
+            DataFormat::Records(format) => format.writer_to(w),
+            DataFormat::Csv(options) => options.writer_to(w),
+            DataFormat::Sql(dialect) => dialect.writer_to(w),
+            DataFormat::FixedWidth(layout) => layout.writer_to(w),
+            DataFormat::Markdown(options) => options.writer_to(w),
+        };
+
+        Ok(writer.with_options(self.write_options))
+    }
+}
+
+/// Завершить запись выходных данных и вернуть исходное назначение.
+fn finish<W: Write>(writer: OutputWriter<W>) -> Result<W, CliError> {
+    finish_text(writer.finish()?)
+}
+
+/// Завершить преобразование текста и сжатие уже записанных выходных данных и вернуть
+/// исходное назначение.
+fn finish_text<W: Write>(w: LineEndingWriter<TextWriter<W>>) -> Result<W, CliError> {
+    let w = w.into_inner()?;
+
+    #[cfg(feature = "encoding")]
+    let w = w.finish()?;
+
+    Ok(w.finish()?)
+}
+
+fn convert<W: Write>(
+    records: impl IntoIterator<Item = Result<Record, ReadError>>,
+    output_format: &OutputFormat,
+    w: W,
+) -> Result<W, CliError> {
+    let mut writer = output_format.writer_to(w)?;
+
+    for record in records {
+        writer.write_record(&record?)?;
+    }
+
+    finish(writer)
+}
+
+/// Конвертировать данные входного файла в выходной формат, атомарно записав их в `output`.
+///
+/// Возвращает количество записанных записей.
+fn convert_file(
+    input: &Path,
+    input_format: &str,
+    options: &FormatOptions,
+    output_format: &OutputFormat,
+    output: &Path,
+) -> Result<usize, CliError> {
+    let (records, _) = open_and_read!(input, input_format, options);
+
+    let mut count = 0;
+    let records = records.inspect(|record| count += usize::from(record.is_ok()));
+
+    write_atomically(output, |w| convert(records, output_format, w))?;
+
+    Ok(count)
+}
+
+/// Путь к временному файлу, в который записываются данные перед переименованием в `output`.
+fn temp_path(output: &Path) -> PathBuf {
+    let file_name = output
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    output.with_file_name(format!(".{file_name}.{}.tmp", std::process::id()))
+}
+
+/// Сбросить буфер записи в файл и дождаться записи данных на диск.
+fn sync(writer: BufWriter<File>) -> Result<(), CliError> {
+    let file = writer.into_inner().map_err(|e| e.into_error())?;
+    file.sync_all()?;
+
+    Ok(())
+}
 
-    /// Data format in the file to read
-    #[clap(long, value_name = "FORMAT")]
-    input_format: String,
+/// Записать данные во временный файл рядом с `output` и по успешном завершении атомарно
+/// переименовать его в `output`. В случае ошибки временный файл удаляется, а `output` остается
+/// нетронутым.
+fn write_atomically(
+    output: &Path,
+    write: impl FnOnce(BufWriter<File>) -> Result<BufWriter<File>, CliError>,
+) -> Result<(), CliError> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!("output", path = %output.display()).entered();
 
-    /// Output data format
-    #[clap(long, value_name = "FORMAT")]
-    output_format: String,
+    let temp_path = temp_path(output);
+
+    let result = File::create(&temp_path)
+        .map_err(CliError::from)
+        .and_then(|file| write(BufWriter::new(file)))
+        .and_then(|writer| {
+            sync(writer)?;
+
+            Ok(std::fs::rename(&temp_path, output)?)
+        });
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+
+    result
 }
 
-/// Ошибка парсинга данных.
-#[derive(Error, Debug)]
-enum CliError {
-    #[error(transparent)]
-    UnknownFormat(#[from] FormatError),
+/// Записать отобранные записи в части выходных данных, создавая их по мере необходимости.
+fn write_parts(
+    records: impl IntoIterator<Item = Result<Record, ReadError>>,
+    split_by: SplitBy,
+    writer: &mut PartitionedWriter<String, LineEndingWriter<TextWriter<BufWriter<File>>>>,
+) -> Result<(), CliError> {
+    for record in records {
+        let opened = writer.len();
+        writer.write_record(&record?)?;
 
-    #[error("IO error: {0}")]
-    Io(#[from] std::io::Error),
+        // При последовательном разбиении предыдущая часть завершается, как только
+        // открывается следующая.
+        if split_by.is_sequential()
+            && writer.len() > opened
+            && let Some(previous) = opened
+                .checked_sub(1)
+                .and_then(|index| writer.keys().nth(index).cloned())
+            && let Some(w) = writer.finish_partition(&previous)?
+        {
+            sync(finish_text(w)?)?;
+        }
+    }
 
-    #[error(transparent)]
-    ReadData(#[from] ReadError),
+    Ok(())
+}
 
-    #[error(transparent)]
-    WriteData(#[from] WriteError),
+/// Записать отобранные записи в несколько файлов согласно способу разбиения.
+///
+/// Части записываются во временные файлы и переименовываются только после успешного
+/// завершения всей конвертации.
+fn convert_split(
+    records: impl IntoIterator<Item = Result<Record, ReadError>>,
+    output_format: &OutputFormat,
+    output: &Path,
+    split_by: SplitBy,
+) -> Result<(), CliError> {
+    let mut number = 0;
+    let mut writer = PartitionedWriter::new(
+        |record| {
+            let key = split_by.part_key(number, record);
+            number += 1;
+
+            key
+        },
+        |key: &String| {
+            let file = File::create(temp_path(&part_path(output, key)))?;
+
+            Ok(output_format.writer_to(BufWriter::new(file))?)
+        },
+    );
 
-    #[error("File is too big!")]
-    TooBigFile,
+    let result = write_parts(records, split_by, &mut writer);
+    let paths = writer
+        .keys()
+        .map(|key| part_path(output, key))
+        .collect::<Vec<_>>();
+
+    let result = result.and_then(|()| {
+        for (_, w) in writer.finish()? {
+            sync(finish_text(w)?)?;
+        }
+
+        paths
+            .iter()
+            .try_for_each(|path| Ok(std::fs::rename(temp_path(path), path)?))
+    });
+
+    if result.is_err() {
+        for path in &paths {
+            let _ = std::fs::remove_file(temp_path(path));
+        }
+    }
+
+    result
 }
 
-macro_rules! open_and_read {
-    ($file:expr, $format:expr) => {{
-        if std::fs::metadata(&$file)?.len() > 1024 * 1024 * 1024 {
-            return Err(CliError::TooBigFile);
+/// Открыть входные файлы для последовательного чтения записей, отображая ход чтения,
+/// если это требуется.
+fn open_inputs(
+    inputs: &[PathBuf],
+    formats: &[String],
+    options: &FormatOptions,
+    show_progress: bool,
+) -> Result<Vec<RecordIter<Box<dyn Read>>>, CliError> {
+    if inputs.len() != formats.len() {
+        Args::command()
+            .error(
+                ErrorKind::WrongNumberOfValues,
+                "each `--input` must have its own `--input-format`",
+            )
+            .exit();
+    }
+
+    let mut sources = vec![];
+
+    for (input, format) in inputs.iter().zip(formats) {
+        let (records, size) = open_and_read!(input, format.as_str(), options);
+
+        sources.push(if show_progress {
+            records.on_progress(progress::reporter(input.display().to_string(), size))
+        } else {
+            records
+        });
+    }
+
+    Ok(sources)
+}
+
+/// Вывести сводную статистику по транзакциям входных файлов.
+fn print_stats(args: StatsArgs) -> Result<(), CliError> {
+    let mut stats = Stats::new();
+
+    for record in open_inputs(
+        &args.input,
+        &args.input_format,
+        &args.formats.options(),
+        false,
+    )?
+    .into_iter()
+    .flatten()
+    {
+        let mut record = record?;
+
+        if let Some(tagger) = &args.tag_rules {
+            tagger.tag(&mut record);
         }
 
-        let mut file = std::fs::File::open($file)?;
-        $format.read_from(&mut file)?
-    }};
+        stats.add(&record);
+    }
+
+    let report = StatsReport::new(&stats, args.top);
+
+    match args.report {
+        ReportFormat::Text => print!("{report}"),
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+
+    Ok(())
 }
 
-macro_rules! convert_format {
-    ($input:expr) => {
-        YPBankImpl::try_from($input)?
+/// Проверить входные файлы, выводя каждую некорректную запись и каждое смысловое нарушение
+/// с указанием позиции в файле.
+///
+/// Чтение продолжается после некорректных записей, если формат это допускает.
+fn validate_files(args: ValidateArgs) -> Result<(), CliError> {
+    let sources = open_inputs(
+        &args.input,
+        &args.input_format,
+        &args.formats.options(),
+        false,
+    )?;
+
+    let mut errors = 0;
+    let mut warnings = 0;
+
+    for (input, records) in args.input.iter().zip(sources) {
+        let input = input.display();
+        let mut records = records.continue_on_error();
+        let mut validator = Validator::new(args.config());
+
+        while let Some(result) = records.next_with_position() {
+            match result {
+                Ok((position, record)) => {
+                    for issue in validator.check(position.record, &record) {
+                        match issue.severity() {
+                            Severity::Error => errors += 1,
+                            Severity::Warning => warnings += 1,
+                        }
+
+                        println!(
+                            "{input}: {position}: {}: TX_ID {}: {}",
+                            issue.severity(),
+                            issue.tx_id,
+                            issue.kind
+                        );
+                    }
+                }
+                Err(e) => {
+                    errors += 1;
+
+                    match e.position() {
+                        Some(position) => println!("{input}: {position}: error: {}", e.inner()),
+                        None => println!("{input}: error: {e}"),
+                    }
+                }
+            }
+        }
+    }
+
+    println!("{errors} errors, {warnings} warnings");
+
+    if errors > 0 {
+        return Err(CliError::Invalid(errors));
+    }
+
+    Ok(())
+}
+
+/// Вычислить отпечаток записей входного файла.
+fn fingerprint_file(
+    input: &Path,
+    format: &str,
+    options: &FormatOptions,
+) -> Result<[u8; 32], CliError> {
+    let (records, _) = open_and_read!(input, format, options);
+    let mut fingerprint = Fingerprint::new();
+
+    for record in records {
+        fingerprint.update(&record?);
+    }
+
+    Ok(fingerprint.finish())
+}
+
+/// Вывести отпечаток записей каждого входного файла в виде `<отпечаток>  <файл>`.
+fn print_fingerprints(args: FingerprintArgs) -> Result<(), CliError> {
+    if args.input.len() != args.input_format.len() {
+        Args::command()
+            .error(
+                ErrorKind::WrongNumberOfValues,
+                "each `--input` must have its own `--input-format`",
+            )
+            .exit();
+    }
+
+    let options = args.formats.options();
+
+    for (input, format) in args.input.iter().zip(&args.input_format) {
+        let fingerprint = fingerprint_file(input, format, &options)?;
+
+        println!("{}  {}", to_hex(&fingerprint), input.display());
+    }
+
+    Ok(())
+}
+
+/// Сформировать выписку по счету участника за период по транзакциям входных файлов и записать ее.
+fn print_statement(args: StatementArgs) -> Result<(), CliError> {
+    let mut records = vec![];
+
+    for record in open_inputs(
+        &args.input,
+        &args.input_format,
+        &args.formats.options(),
+        false,
+    )?
+    .into_iter()
+    .flatten()
+    {
+        let record = record?;
+
+        if statement::involves(args.user, &record) {
+            records.push(record);
+        }
+    }
+
+    let statement = statement::generate_from_records(args.user, args.period, &records);
+
+    match &args.output {
+        Some(path) => {
+            let mut output = BufWriter::new(File::create(path)?);
+            statement.write_to(&mut output, args.format.into())?;
+            output.flush()?;
+        }
+        None => statement.write_to(&mut std::io::stdout().lock(), args.format.into())?,
+    }
+
+    Ok(())
+}
+
+/// Восстановить корректные записи поврежденного файла в бинарном формате и записать их,
+/// выводя в stderr диапазоны пропущенных данных.
+fn repair_file(args: RepairArgs) -> Result<(), CliError> {
+    let input: Box<dyn Read> = if args.input == Path::new("-") {
+        Box::new(std::io::stdin().lock())
+    } else {
+        Box::new(File::open(&args.input)?)
     };
+    let (_, input) = decompress_detected(input)?;
+
+    let (records, gaps) = YPBankBin::recover(input);
+
+    for gap in &gaps {
+        eprintln!("{}: skipped {gap}", args.input.display());
+    }
+
+    eprintln!(
+        "Recovered {} records, skipped {} bytes in {} ranges",
+        records.len(),
+        gaps.iter().map(RecoveryGap::len).sum::<u64>(),
+        gaps.len()
+    );
+
+    match args.output {
+        Some(output) => write_atomically(&output, |w| write_repaired(&records, w)),
+        None => {
+            write_repaired(&records, std::io::stdout().lock())?.flush()?;
+
+            Ok(())
+        }
+    }
 }
 
-fn run() -> Result<(), CliError> {
-    let args = Args::parse();
+/// Вывести записи файла в бинарном формате в виде шестнадцатеричного дампа с описанием полей.
+///
+/// Разбор останавливается на первой структурной ошибке, которая возвращается с ее смещением.
+fn inspect_file(args: InspectArgs) -> Result<(), CliError> {
+    let input: Box<dyn Read> = if args.input == Path::new("-") {
+        Box::new(std::io::stdin().lock())
+    } else {
+        Box::new(File::open(&args.input)?)
+    };
+    let (_, mut input) = decompress_detected(input)?;
+
+    let mut data = vec![];
+    input.read_to_end(&mut data)?;
+
+    let mut stdout = std::io::stdout().lock();
+    let mut records = 0;
+
+    for item in BinInspector::new(&data) {
+        let item = item?;
+
+        if matches!(item, InspectedItem::Record { .. }) {
+            if args
+                .max_records
+                .is_some_and(|max_records| records >= max_records)
+            {
+                break;
+            }
+
+            records += 1;
+        }
+
+        writeln!(stdout, "{item}")?;
+    }
+
+    Ok(())
+}
 
-    let input_filename = args.input;
-    let input_format = convert_format!(args.input_format.as_str());
+/// Отсортировать записи входных файлов по ключам `--key` и записать их в выходном формате.
+///
+/// В памяти одновременно находится не более `--chunk-records` записей: отсортированные части
+/// сохраняются во временные файлы в `--tmp-dir`, которые затем сливаются. Записи переписываются
+/// как есть, в том числе с широкими суммами.
+fn sort_files(args: SortArgs) -> Result<(), CliError> {
     let output_format = convert_format!(args.output_format.as_str());
 
-    let records = open_and_read!(input_filename, input_format);
+    let records = open_inputs(
+        &args.input,
+        &args.input_format,
+        &args.formats.options(),
+        args.progress,
+    )?
+    .into_iter()
+    .flatten();
 
-    let mut stdout = std::io::stdout().lock();
+    let sort = ExternalSort::new(&args.key, args.tmp_dir.unwrap_or_else(std::env::temp_dir))
+        .chunk_records(args.chunk_records);
+
+    match args.output {
+        Some(output) => write_atomically(&output, |w| {
+            Ok(sort.sort(records, sorted_writer(output_format, w))?)
+        }),
+        None => {
+            sort.sort(
+                records,
+                sorted_writer(output_format, std::io::stdout().lock()),
+            )?
+            .flush()?;
+
+            Ok(())
+        }
+    }
+}
 
-    output_format.write_to(records, &mut stdout)?;
+/// Создание объекта записи отсортированных записей в указанном формате.
+fn sorted_writer<W: Write>(format: YPBankImpl, w: W) -> RecordWriter<W> {
+    format.writer_to(w).schema_version(SchemaVersion::V2)
+}
+
+/// Записать восстановленные записи в бинарном формате.
+///
+/// Записи переписываются как есть, в том числе с широкими суммами.
+fn write_repaired<W: Write>(records: &[Record], w: W) -> Result<W, CliError> {
+    let mut writer = YPBankImpl::Bin
+        .writer_to(w)
+        .schema_version(SchemaVersion::V2);
+
+    for record in records {
+        writer.write_record(record)?;
+    }
+
+    Ok(writer.finish()?)
+}
+
+/// Считать все записи входного файла.
+fn read_records(
+    input: &Path,
+    format: &str,
+    options: &FormatOptions,
+) -> Result<Vec<Record>, CliError> {
+    let (records, _) = open_and_read!(input, format, options);
+
+    Ok(records.collect::<Result<_, _>>()?)
+}
+
+/// Вывести в stderr количество изменений каждого вида.
+fn print_delta_summary(changeset: &ChangeSet) {
+    eprintln!(
+        "Delta: {} added, {} removed, {} modified{}",
+        changeset.added.len(),
+        changeset.removed.len(),
+        changeset.modified.len(),
+        if changeset.order.is_empty() {
+            ""
+        } else {
+            ", order changed"
+        }
+    );
+}
+
+/// Записать изменения между базовым и текущим файлами.
+fn write_delta(args: DeltaArgs) -> Result<(), CliError> {
+    let options = args.formats.options();
+    let base = read_records(&args.base, &args.base_format, &options)?;
+    let current = read_records(&args.current, &args.current_format, &options)?;
+
+    let changeset = diff::compute(&base, &current)?;
+    print_delta_summary(&changeset);
+
+    match args.output {
+        Some(output) => write_atomically(&output, |w| {
+            write_changeset(&changeset, args.delta_format, w)
+        }),
+        None => {
+            let stdout = BufWriter::new(std::io::stdout().lock());
+            write_changeset(&changeset, args.delta_format, stdout)?.flush()?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Записать изменения в указанном формате.
+fn write_changeset<W: Write>(
+    changeset: &ChangeSet,
+    format: DeltaFormat,
+    mut w: W,
+) -> Result<W, CliError> {
+    match format {
+        DeltaFormat::Bin => changeset.write_bin_to(&mut w)?,
+        DeltaFormat::Text => changeset.write_text_to(&mut w)?,
+    }
+
+    Ok(w)
+}
+
+/// Применить изменения к базовому файлу и записать полученные записи.
+fn apply_delta(args: ApplyDeltaArgs) -> Result<(), CliError> {
+    let output_format = convert_format!(args.output_format.as_str());
+    let base = read_records(&args.base, &args.base_format, &args.formats.options())?;
+
+    let input: Box<dyn Read> = if args.delta == Path::new("-") {
+        Box::new(std::io::stdin().lock())
+    } else {
+        Box::new(File::open(&args.delta)?)
+    };
+    let (_, mut input) = decompress_detected(input)?;
+
+    let changeset = ChangeSet::read_from(&mut input)?;
+    print_delta_summary(&changeset);
+
+    let records = diff::apply(base, &changeset)?;
+
+    match args.output {
+        Some(output) => write_atomically(&output, |w| write_patched(&records, output_format, w)),
+        None => {
+            write_patched(&records, output_format, std::io::stdout().lock())?.flush()?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Записать записи, полученные применением изменений, в указанном формате.
+///
+/// Записи переписываются как есть, в том числе с широкими суммами.
+fn write_patched<W: Write>(records: &[Record], format: YPBankImpl, w: W) -> Result<W, CliError> {
+    let mut writer = format.writer_to(w).schema_version(SchemaVersion::V2);
+
+    for record in records {
+        writer.write_record(record)?;
+    }
+
+    Ok(writer.finish()?)
+}
+
+/// Подписать отпечаток записей входного файла и записать подпись.
+#[cfg(feature = "signing")]
+fn sign_file(args: SignArgs) -> Result<(), CliError> {
+    let fingerprint = fingerprint_file(&args.input, &args.input_format, &args.formats.options())?;
+    let signature = args.key.sign(&fingerprint);
+
+    match args.output {
+        Some(output) => std::fs::write(output, format!("{signature}\n"))?,
+        None => println!("{signature}"),
+    }
+
+    Ok(())
+}
+
+/// Проверить подпись отпечатка записей входного файла.
+#[cfg(feature = "signing")]
+fn verify_file(args: VerifyArgs) -> Result<(), CliError> {
+    let fingerprint = fingerprint_file(&args.input, &args.input_format, &args.formats.options())?;
+
+    args.public_key.verify(&fingerprint, &args.signature)?;
+    println!("{}: signature is valid", args.input.display());
+
+    Ok(())
+}
+
+fn run(args: Args) -> Result<(), CliError> {
+    match (args.command, args.convert) {
+        (Some(Command::Stats(args)), _) => print_stats(args),
+        (Some(Command::Validate(args)), _) => validate_files(args),
+        (Some(Command::Fingerprint(args)), _) => print_fingerprints(args),
+        (Some(Command::Statement(args)), _) => print_statement(args),
+        (Some(Command::Repair(args)), _) => repair_file(args),
+        (Some(Command::Inspect(args)), _) => inspect_file(args),
+        (Some(Command::Sort(args)), _) => sort_files(args),
+        (Some(Command::Delta(args)), _) => write_delta(args),
+        (Some(Command::ApplyDelta(args)), _) => apply_delta(args),
+        (Some(Command::Batch(args)), _) => batch::convert_batch(args),
+        #[cfg(feature = "signing")]
+        (Some(Command::Sign(args)), _) => sign_file(args),
+        #[cfg(feature = "signing")]
+        (Some(Command::Verify(args)), _) => verify_file(args),
+        #[cfg(feature = "watch")]
+        (Some(Command::Watch(args)), _) => watch::watch_dir(args),
+        (None, Some(args)) => convert_files(args),
+        (None, None) => Args::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "either a subcommand or conversion arguments must be provided",
+            )
+            .exit(),
+    }
+}
+
+/// Конвертировать данные входных файлов согласно аргументам командной строки.
+fn convert_files(args: ConvertArgs) -> Result<(), CliError> {
+    #[cfg(feature = "sqlite")]
+    if args.output_format == "sqlite" {
+        return convert_to_sqlite(&args);
+    }
+
+    #[cfg(feature = "parquet")]
+    if args.output_format == "parquet" {
+        return convert_to_parquet(&args);
+    }
+
+    #[cfg(feature = "encryption")]
+    if args.output_format == "bin-encrypted" {
+        return convert_to_encrypted(&args);
+    }
+
+    let options = args.formats.options();
+
+    let output_format = OutputFormat {
+        format: DataFormat::new(
+            &args.output_format,
+            options,
+            !args.no_csv_header,
+            args.sql_dialect,
+        )?,
+        compression: args.compress,
+        line_ending: args.line_ending,
+        bom: args.bom,
+        #[cfg(feature = "encoding")]
+        encoding: args.output_encoding,
+        write_options: WriteOptions {
+            buffer_capacity: args.write_buffer_size,
+            schema_version: args.schema_version,
+        },
+    };
+
+    check_text_output(&args, &output_format);
+
+    let records = merged_records(&args)?;
 
-    stdout.flush()?;
+    match args.output {
+        Some(output) if let Some(split_by) = args.split_by => {
+            convert_split(records, &output_format, &output, split_by)
+        }
+        Some(output) => write_atomically(&output, |w| convert(records, &output_format, w)),
+        None => {
+            let mut stdout = convert(records, &output_format, std::io::stdout().lock())?;
+            stdout.flush()?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Завершить работу с ошибкой, если параметры записи текстовых данных не применимы
+/// к выходному формату.
+fn check_text_output(args: &ConvertArgs, output_format: &OutputFormat) {
+    if !output_format.format.is_text() && args.has_text_output_options() {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                format!(
+                    "`--output-format {}` cannot be used with `--line-ending`, `--bom` \
+                     or `--output-encoding`",
+                    args.output_format
+                ),
+            )
+            .exit();
+    }
+
+    #[cfg(feature = "encoding")]
+    if args.bom && !args.output_encoding.is_utf8() && !args.output_encoding.is_utf16() {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                format!(
+                    "`--bom` cannot be used with `--output-encoding {}`",
+                    args.output_encoding.name()
+                ),
+            )
+            .exit();
+    }
+}
+
+/// Отобранные записи о транзакциях из всех входных файлов в порядке указания файлов либо,
+/// если это требуется, отсортированные или слитые по метке времени, с удаленными значениями полей
+/// транзакций из `--redact-file`, преобразованные параметрами `--tag-rules`, `--remap-users`,
+/// `--shift-timestamps` и `--mask-descriptions` и обезличенные при указании `--anonymize`.
+fn merged_records(
+    args: &ConvertArgs,
+) -> Result<Box<dyn Iterator<Item = Result<Record, ReadError>>>, CliError> {
+    let sources = open_inputs(
+        &args.input,
+        &args.input_format,
+        &args.formats.options(),
+        args.progress,
+    )?;
+
+    let records: Box<dyn Iterator<Item = Result<Record, ReadError>>> = if args.sort_by_timestamp {
+        let mut records = sources
+            .into_iter()
+            .flatten()
+            .collect::<Result<Vec<_>, _>>()?;
+        records.sort_by(|a, b| a.compare_by(b, RecordKey::Timestamp));
+
+        Box::new(records.into_iter().map(Ok))
+    } else if args.merge_sorted {
+        Box::new(MergeSorted::new(
+            sources,
+            &[(RecordKey::Timestamp, SortOrder::Ascending)],
+        ))
+    } else {
+        Box::new(sources.into_iter().flatten())
+    };
+
+    let mut transforms: Vec<Box<dyn Transform>> = vec![Box::new(args.filter())];
+
+    if let Some(tx_ids) = &args.redact_file {
+        let tx_ids = tx_ids.clone();
+        let fields = args.redact_fields.clone();
+
+        transforms.push(Box::new(move |mut record: Record| {
+            if tx_ids.contains(&record.tx_id()) {
+                record
+                    .redact(&fields)
+                    .expect("redacted fields are checked when parsing arguments");
+            }
+
+            Some(record)
+        }));
+    }
+
+    transforms.extend(args.transforms());
+
+    if let Some(policy) = args.anonymize_policy() {
+        transforms.push(Box::new(Anonymizer::new(policy)));
+    }
+
+    Ok(Box::new(transform_records(records, transforms)))
+}
+
+/// Завершить работу с ошибкой, если для выходного формата, не поддерживающего разбиение,
+/// внешнее сжатие и параметры записи текстовых данных, указаны `--split-by`, `--compress`,
+/// `--line-ending`, `--bom` или `--output-encoding`.
+#[cfg(any(feature = "sqlite", feature = "parquet", feature = "encryption"))]
+fn check_no_split_or_compress(args: &ConvertArgs) {
+    if args.split_by.is_some()
+        || args.compress != Compression::None
+        || args.has_text_output_options()
+    {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                format!(
+                    "`--output-format {}` cannot be used with `--split-by`, `--compress`, \
+                     `--line-ending`, `--bom` or `--output-encoding`",
+                    args.output_format
+                ),
+            )
+            .exit();
+    }
+}
+
+/// Добавить отобранные записи в таблицу базы данных SQLite, указанной в `--output`.
+///
+/// Записи добавляются в одной транзакции базы данных: в случае ошибки база данных остается
+/// нетронутой.
+#[cfg(feature = "sqlite")]
+fn convert_to_sqlite(args: &ConvertArgs) -> Result<(), CliError> {
+    let Some(output) = &args.output else {
+        Args::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "`--output-format sqlite` requires `--output`",
+            )
+            .exit();
+    };
+
+    check_no_split_or_compress(args);
+
+    let records = merged_records(args)?;
+
+    let mut database = YPBankSqlite::open(output).map_err(WriteError::from)?;
+    let mut writer = database.writer()?;
+
+    for record in records {
+        writer.write_record(&record?)?;
+    }
+
+    writer.finish()?;
 
     Ok(())
 }
 
+/// Записать отобранные записи в формате Apache Parquet в файл, указанный в `--output`
+/// (атомарно), либо в stdout.
+#[cfg(feature = "parquet")]
+fn convert_to_parquet(args: &ConvertArgs) -> Result<(), CliError> {
+    check_no_split_or_compress(args);
+
+    let records = merged_records(args)?;
+
+    match &args.output {
+        Some(output) => write_atomically(output, |w| write_parquet(records, w)),
+        None => {
+            write_parquet(records, std::io::stdout())?.flush()?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Записать отобранные записи в назначение в формате Apache Parquet.
+#[cfg(feature = "parquet")]
+fn write_parquet<W: Write + Send>(
+    records: impl IntoIterator<Item = Result<Record, ReadError>>,
+    w: W,
+) -> Result<W, CliError> {
+    let mut writer = YPBankParquet::writer_to(w)?;
+
+    for record in records {
+        writer.write_record(&record?)?;
+    }
+
+    Ok(writer.finish()?)
+}
+
+/// Записать отобранные записи в зашифрованный контейнер бинарного формата.
+///
+/// Данные шифруются целиком, поэтому записи предварительно считываются в память.
+#[cfg(feature = "encryption")]
+fn convert_to_encrypted(args: &ConvertArgs) -> Result<(), CliError> {
+    check_no_split_or_compress(args);
+
+    let options = args.formats.options();
+    let key = options.encryption_key();
+
+    let data = merged_records(args)?.collect::<Result<YPBankBin, _>>()?;
+
+    match &args.output {
+        Some(output) => write_atomically(output, |mut w| {
+            write_to_bin_encrypted(&data, &mut w, key)?;
+
+            Ok(w)
+        }),
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            write_to_bin_encrypted(&data, &mut stdout, key)?;
+            stdout.flush()?;
+
+            Ok(())
+        }
+    }
+}
+
 fn main() {
-    if let Err(err) = run() {
+    let args = Args::parse();
+
+    #[cfg(feature = "tracing")]
+    let logging = args.logging.init();
+
+    if let Err(err) = run(args) {
         let exit_code = match err {
             CliError::UnknownFormat(_) => -1,
-            CliError::Io(_) => -2,
-            CliError::ReadData(_) => -3,
-            CliError::WriteData(_) => -4,
-            CliError::TooBigFile => -5,
+            CliError::Io(_) | CliError::Sort(SortError::Io(_)) => -2,
+            CliError::ReadData(_) | CliError::Sort(SortError::Read(_)) | CliError::Structure(_) => {
+                -3
+            }
+            CliError::WriteData(_) | CliError::Sort(SortError::Write(_)) => -4,
+            CliError::Report(_) => -5,
+            CliError::Invalid(_) => -6,
+            #[cfg(feature = "signing")]
+            CliError::Signing(_) => -7,
+            #[cfg(feature = "encryption")]
+            CliError::Encryption(_) => -8,
+            #[cfg(feature = "watch")]
+            CliError::Watch(_) => -9,
+            CliError::BatchFailed(_) => -10,
+            CliError::Diff(_) | CliError::Apply(_) => -11,
         };
 
+        #[cfg(feature = "tracing")]
+        if logging {
+            tracing::error!(exit_code, error = %err, "failed");
+            std::process::exit(exit_code);
+        }
+
         eprintln!("{}", err);
+
+        if let CliError::ReadData(e) | CliError::Sort(SortError::Read(e)) = &err
+            && e.is_truncated()
+        {
+            eprintln!("The input data is incomplete, try to download it again");
+        }
+
         std::process::exit(exit_code);
     }
 }
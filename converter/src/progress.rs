@@ -0,0 +1,82 @@
+//! Модуль отображения хода конвертации в stderr.
+
+use parser::Progress;
+use std::time::{Duration, Instant};
+
+/// Минимальный интервал между обновлениями строки прогресса.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Ширина полосы прогресса в символах.
+const BAR_WIDTH: u64 = 30;
+
+/// Представить количество байт в удобочитаемом виде.
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// Создать функцию отображения хода чтения файла с указанным именем.
+///
+/// `total` — размер считываемых данных в байтах, если он известен заранее.
+pub fn reporter(name: String, total: Option<u64>) -> impl FnMut(Progress) + Send + 'static {
+    let start = Instant::now();
+    let mut last_redraw: Option<Instant> = None;
+
+    move |progress| {
+        let now = Instant::now();
+
+        if !progress.finished
+            && last_redraw.is_some_and(|last| now.duration_since(last) < REDRAW_INTERVAL)
+        {
+            return;
+        }
+
+        last_redraw = Some(now);
+
+        let elapsed = now.duration_since(start).as_secs_f64();
+        let rate = if elapsed > 0.0 {
+            progress.records as f64 / elapsed
+        } else {
+            0.0
+        };
+
+        let bytes = match total {
+            Some(total) if total > 0 => {
+                let done = progress.bytes.min(total);
+                let filled = (done * BAR_WIDTH / total) as usize;
+
+                format!(
+                    "[{}{}] {:>3}% {} / {}",
+                    "#".repeat(filled),
+                    " ".repeat(BAR_WIDTH as usize - filled),
+                    done * 100 / total,
+                    format_bytes(done),
+                    format_bytes(total)
+                )
+            }
+            _ => format_bytes(progress.bytes),
+        };
+
+        eprint!(
+            "\r{name}: {bytes}, {} records, {rate:.0} records/s\x1b[K",
+            progress.records
+        );
+
+        if progress.finished {
+            eprintln!();
+        }
+    }
+}
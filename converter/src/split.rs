@@ -0,0 +1,67 @@
+//! Модуль разбиения выходных данных конвертера на несколько файлов.
+
+use parser::{PartitionBy, Record};
+use std::path::{Path, PathBuf};
+
+/// Способ разбиения выходных данных на части.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitBy {
+    /// Не более указанного количества записей в каждой части.
+    Count(usize),
+
+    /// По разделам (дню или месяцу метки времени, группе пользователей) записи.
+    Partition(PartitionBy),
+}
+
+impl SplitBy {
+    /// Ключ части, в которую попадает запись с указанным порядковым номером (начиная с 0)
+    /// среди записываемых.
+    pub fn part_key(&self, number: usize, record: &Record) -> String {
+        match self {
+            Self::Count(count) => format!("{:04}", number / count + 1),
+            Self::Partition(partition_by) => partition_by.key(record),
+        }
+    }
+
+    /// Признак того, что записи каждой части следуют подряд, и предыдущие части можно
+    /// завершать при переходе к следующей.
+    pub fn is_sequential(&self) -> bool {
+        matches!(self, Self::Count(_))
+    }
+}
+
+/// Реализация трейта [`TryFrom<&str>`] для [`SplitBy`].
+impl TryFrom<&str> for SplitBy {
+    /// Описание ошибки разбора способа разбиения.
+    type Error = String;
+
+    /// Реализация метода [`TryFrom<&str>::try_from`] для [`SplitBy`].
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        if let Ok(partition_by) = PartitionBy::try_from(s) {
+            return Ok(Self::Partition(partition_by));
+        }
+
+        match s.strip_prefix("count:").map(str::parse::<usize>) {
+            Some(Ok(count)) if count > 0 => Ok(Self::Count(count)),
+            _ => Err(format!(
+                "expected `count:N` with positive N, `day`, `month` or `user:N` with positive N, got `{s}`"
+            )),
+        }
+    }
+}
+
+/// Путь к части выходных данных: ключ части вставляется перед расширением файла
+/// (`out.csv` -> `out.0001.csv`).
+pub fn part_path(output: &Path, key: &str) -> PathBuf {
+    let stem = output
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let file_name = match output.extension() {
+        Some(extension) => format!("{stem}.{key}.{}", extension.to_string_lossy()),
+        None => format!("{stem}.{key}"),
+    };
+
+    output.with_file_name(file_name)
+}
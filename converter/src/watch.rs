@@ -0,0 +1,517 @@
+//! Модуль наблюдения за каталогом и конвертации появляющихся в нем файлов.
+
+use super::{
+    Args, CliError, DataFormat, FormatOptions, OutputFormat, WatchArgs, batch, convert_file,
+};
+use clap::{CommandFactory, error::ErrorKind};
+use notify::{EventKind, RecursiveMode, Watcher};
+use parser::SqlDialect;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+/// Конвертер файлов каталога.
+struct DirConverter {
+    /// Аргументы подкоманды с каноническими путями к каталогам.
+    args: WatchArgs,
+
+    /// Параметры форматов входных данных.
+    options: FormatOptions,
+
+    /// Формат выходных данных.
+    output_format: OutputFormat,
+
+    /// Канонический путь к каталогу файлов, которые не удалось сконвертировать.
+    quarantine_dir: PathBuf,
+
+    /// Файлы, которые не удалось ни удалить после конвертации, ни переместить в каталог
+    /// карантина. Они не обрабатываются повторно, пока не будут созданы заново.
+    failed: HashSet<PathBuf>,
+}
+
+impl DirConverter {
+    /// Создать конвертер, создав выходной каталог и каталог карантина, если они отсутствуют.
+    fn new(args: WatchArgs, quarantine_dir: PathBuf) -> Result<Self, CliError> {
+        std::fs::create_dir_all(&args.out_dir)?;
+        std::fs::create_dir_all(&quarantine_dir)?;
+
+        let output_format = OutputFormat::plain(
+            DataFormat::new(
+                &args.output_format,
+                args.formats.options(),
+                true,
+                SqlDialect::default(),
+            )?,
+            args.schema_version,
+        );
+
+        Ok(Self {
+            options: args.formats.options(),
+            output_format,
+            quarantine_dir,
+            failed: HashSet::new(),
+            args,
+        })
+    }
+
+    /// Признак файла, подлежащего конвертации: скрытые файлы (например, временные файлы
+    /// копирующих программ) и каталоги пропускаются.
+    fn is_candidate(path: &Path) -> bool {
+        path.file_name()
+            .is_some_and(|name| !name.to_string_lossy().starts_with('.'))
+            && path.is_file()
+    }
+
+    /// Файлы, уже находящиеся в наблюдаемом каталоге.
+    fn existing_files(&self) -> Result<Vec<PathBuf>, CliError> {
+        let mut files = vec![];
+
+        for entry in std::fs::read_dir(&self.args.in_dir)? {
+            let path = entry?.path();
+
+            if Self::is_candidate(&path) {
+                files.push(path);
+            }
+        }
+
+        files.sort();
+
+        Ok(files)
+    }
+
+    /// Путь к выходному файлу: имя входного файла с названием выходного формата в качестве
+    /// расширения.
+    fn output_path(&self, input: &Path) -> PathBuf {
+        let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+
+        self.args
+            .out_dir
+            .join(format!("{stem}.{}", self.args.output_format))
+    }
+
+    /// Сконвертировать файл, удалив его после успешной конвертации либо переместив
+    /// в каталог карантина в случае ошибки.
+    ///
+    /// Файл, выходной файл которого совпадает с ним или уже существует (например, выходной
+    /// файл другого входного файла с тем же именем без расширения), не конвертируется,
+    /// а перемещается в каталог карантина.
+    fn process(&mut self, input: &Path) {
+        if self.failed.contains(input) {
+            return;
+        }
+
+        let output = self.output_path(input);
+        let conflict =
+            batch::output_conflict(&[input.to_path_buf()], std::slice::from_ref(&output)).or_else(
+                || {
+                    output.exists().then(|| {
+                        format!(
+                            "converting `{}` would overwrite the existing file `{}`",
+                            input.display(),
+                            output.display()
+                        )
+                    })
+                },
+            );
+
+        if let Some(message) = conflict {
+            eprintln!("{}: {message}", input.display());
+            self.quarantine(input, &message);
+            return;
+        }
+
+        match convert_file(
+            input,
+            &self.args.input_format,
+            &self.options,
+            &self.output_format,
+            &output,
+        ) {
+            Ok(_) => {
+                eprintln!("{} -> {}", input.display(), output.display());
+
+                // Входной файл удаляется, только если он не оказался самим выходным файлом.
+                let removed = if same_file(input, &output) {
+                    Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "it is the output file",
+                    ))
+                } else {
+                    std::fs::remove_file(input)
+                };
+
+                if let Err(e) = removed {
+                    eprintln!("{}: failed to remove converted file: {e}", input.display());
+                    self.failed.insert(input.to_path_buf());
+                }
+            }
+            Err(e) => {
+                eprintln!("{}: {e}", input.display());
+                self.quarantine(input, &e);
+            }
+        }
+    }
+
+    /// Переместить файл в каталог карантина и записать туда отчет об ошибке `<файл>.error.txt`.
+    ///
+    /// Отчет записывается и тогда, когда файл не удалось переместить; такой файл остается
+    /// на месте и больше не обрабатывается.
+    fn quarantine(&mut self, input: &Path, error: &dyn Display) {
+        let name = input.file_name().unwrap_or_default().to_string_lossy();
+        let moved = move_file(input, &self.quarantine_dir.join(name.as_ref()));
+
+        let mut report = format!(
+            "File: {}\nInput format: {}\nError: {error}\n",
+            input.display(),
+            self.args.input_format
+        );
+
+        if let Err(e) = &moved {
+            report.push_str(&format!("Not moved to quarantine: {e}\n"));
+        }
+
+        if let Err(e) = std::fs::write(
+            self.quarantine_dir.join(format!("{name}.error.txt")),
+            report,
+        ) {
+            eprintln!("{}: failed to write error report: {e}", input.display());
+        }
+
+        if let Err(e) = moved {
+            eprintln!("{}: failed to quarantine: {e}", input.display());
+            self.failed.insert(input.to_path_buf());
+        }
+    }
+}
+
+/// Признак того, что пути указывают на один и тот же существующий файл.
+fn same_file(path1: &Path, path2: &Path) -> bool {
+    matches!(
+        (path1.canonicalize(), path2.canonicalize()),
+        (Ok(path1), Ok(path2)) if path1 == path2
+    )
+}
+
+/// Переместить файл, копируя его с удалением исходного файла, если переименование невозможно
+/// (например, между разными файловыми системами).
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    std::fs::copy(from, to)?;
+    std::fs::remove_file(from)
+}
+
+/// Канонический путь к каталогу, который может еще не существовать: канонический путь
+/// ближайшего существующего каталога с присоединенными к нему остальными компонентами.
+fn canonical_dir(path: &Path) -> io::Result<PathBuf> {
+    match path.canonicalize() {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+                return Err(e);
+            };
+            let parent = if parent.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                parent
+            };
+
+            Ok(canonical_dir(parent)?.join(name))
+        }
+        result => result,
+    }
+}
+
+/// Описание конфликта канонических путей к каталогам: выходной каталог и каталог карантина
+/// не должны совпадать с наблюдаемым каталогом или находиться в нем, а также совпадать
+/// друг с другом.
+fn dir_conflict(in_dir: &Path, out_dir: &Path, quarantine_dir: &Path) -> Option<String> {
+    if out_dir.starts_with(in_dir) {
+        Some(format!(
+            "`--out-dir` `{}` must not be `--in-dir` `{}` or inside it",
+            out_dir.display(),
+            in_dir.display()
+        ))
+    } else if quarantine_dir.starts_with(in_dir) {
+        Some(format!(
+            "`--quarantine-dir` `{}` must not be `--in-dir` `{}` or inside it",
+            quarantine_dir.display(),
+            in_dir.display()
+        ))
+    } else if quarantine_dir == out_dir {
+        Some(format!(
+            "`--quarantine-dir` `{}` must differ from `--out-dir`",
+            quarantine_dir.display()
+        ))
+    } else {
+        None
+    }
+}
+
+/// Наблюдать за каталогом `--in-dir`, конвертируя появляющиеся в нем файлы в `--out-dir`.
+///
+/// Файл конвертируется, когда он не изменяется в течение `--settle-time`, чтобы не считывать
+/// еще копируемые файлы. Файлы, уже находящиеся в каталоге, конвертируются при запуске.
+/// Успешно сконвертированный файл удаляется из `--in-dir`, а файл, который не удалось
+/// сконвертировать, перемещается в каталог карантина вместе с отчетом об ошибке.
+///
+/// Завершает работу с ошибкой, если выходной каталог или каталог карантина совпадает
+/// с `--in-dir` или находится в нем.
+pub fn watch_dir(mut args: WatchArgs) -> Result<(), CliError> {
+    args.in_dir = args.in_dir.canonicalize()?;
+    args.out_dir = canonical_dir(&args.out_dir)?;
+
+    let quarantine_dir = match &args.quarantine_dir {
+        Some(dir) => canonical_dir(dir)?,
+        None => args.out_dir.join("quarantine"),
+    };
+
+    if let Some(message) = dir_conflict(&args.in_dir, &args.out_dir, &quarantine_dir) {
+        Args::command()
+            .error(ErrorKind::ArgumentConflict, message)
+            .exit();
+    }
+
+    let mut converter = DirConverter::new(args, quarantine_dir)?;
+
+    if converter.args.once {
+        for path in converter.existing_files()? {
+            converter.process(&path);
+        }
+
+        return Ok(());
+    }
+
+    // Наблюдение начинается до просмотра каталога, чтобы не пропустить файлы, появившиеся
+    // в промежутке.
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(&converter.args.in_dir, RecursiveMode::NonRecursive)?;
+
+    eprintln!("Watching {}", converter.args.in_dir.display());
+
+    let settle_time = Duration::from_millis(converter.args.settle_time);
+    let mut pending: HashMap<PathBuf, Instant> = converter
+        .existing_files()?
+        .into_iter()
+        .map(|path| (path, Instant::now()))
+        .collect();
+
+    loop {
+        let event = match pending.values().min() {
+            Some(changed) => {
+                rx.recv_timeout((*changed + settle_time).saturating_duration_since(Instant::now()))
+            }
+            None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+
+        match event {
+            Ok(Ok(event)) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                for path in event.paths {
+                    // Заново созданный файл обрабатывается, даже если прежний не удалось
+                    // ни удалить, ни переместить в каталог карантина.
+                    if matches!(event.kind, EventKind::Create(_)) {
+                        converter.failed.remove(&path);
+                    }
+
+                    pending.insert(path, Instant::now());
+                }
+            }
+            Ok(Ok(_)) | Err(RecvTimeoutError::Timeout) => {}
+            Ok(Err(e)) => eprintln!("{}: {e}", converter.args.in_dir.display()),
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+
+        let now = Instant::now();
+        let mut settled = pending
+            .iter()
+            .filter(|&(_, &changed)| now.duration_since(changed) >= settle_time)
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+        settled.sort();
+
+        for path in settled {
+            pending.remove(&path);
+
+            if DirConverter::is_candidate(&path) {
+                converter.process(&path);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Command;
+    use clap::Parser;
+    use rstest::rstest;
+
+    /// Данные входного файла в формате CSV.
+    const CSV: &str = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n\
+        1,DEPOSIT,0,772,29,1734172695334,SUCCESS,\"Deposit\"\n";
+
+    /// Временный каталог с наблюдаемым каталогом `in`, удаляемый по окончании теста.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir()
+                .canonicalize()
+                .unwrap()
+                .join(format!("converter-watch-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(dir.join("in")).unwrap();
+
+            Self(dir)
+        }
+
+        fn input(&self, name: &str) -> PathBuf {
+            self.0.join("in").join(name)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Конвертер файлов каталога `in` в формат JSON в каталог `out` с каталогом карантина
+    /// `quarantine`.
+    fn converter(dir: &TempDir) -> DirConverter {
+        let in_dir = dir.0.join("in");
+        let out_dir = dir.0.join("out");
+        let args = Args::parse_from([
+            "converter".as_ref(),
+            "watch".as_ref(),
+            "--in-dir".as_ref(),
+            in_dir.as_os_str(),
+            "--out-dir".as_ref(),
+            out_dir.as_os_str(),
+            "--output-format".as_ref(),
+            "json".as_ref(),
+            "--once".as_ref(),
+        ]);
+        let Some(Command::Watch(args)) = args.command else {
+            unreachable!()
+        };
+
+        DirConverter::new(args, dir.0.join("quarantine")).unwrap()
+    }
+
+    #[rstest]
+    #[case::same_out_dir("/data/in", "/data/in", "/data/quarantine", true)]
+    #[case::nested_out_dir("/data/in", "/data/in/out", "/data/quarantine", true)]
+    #[case::nested_quarantine_dir("/data/in", "/data/out", "/data/in/quarantine", true)]
+    #[case::same_quarantine_dir("/data/in", "/data/out", "/data/out", true)]
+    #[case::sibling_dirs("/data/in", "/data/input", "/data/out/quarantine", false)]
+    fn test_dir_conflict(
+        #[case] in_dir: &str,
+        #[case] out_dir: &str,
+        #[case] quarantine_dir: &str,
+        #[case] conflict: bool,
+    ) {
+        assert_eq!(
+            dir_conflict(in_dir.as_ref(), out_dir.as_ref(), quarantine_dir.as_ref()).is_some(),
+            conflict
+        );
+    }
+
+    #[test]
+    fn test_canonical_dir_missing() {
+        let dir = TempDir::new("canonical");
+
+        assert_eq!(
+            canonical_dir(&dir.0.join("in/../missing/out")).unwrap(),
+            dir.0.join("missing/out")
+        );
+    }
+
+    #[test]
+    fn test_process() {
+        let dir = TempDir::new("process");
+        let mut converter = converter(&dir);
+        std::fs::write(dir.input("a.csv"), CSV).unwrap();
+
+        converter.process(&dir.input("a.csv"));
+
+        assert!(!dir.input("a.csv").exists());
+        assert!(dir.0.join("out/a.json").exists());
+    }
+
+    #[test]
+    fn test_process_invalid() {
+        let dir = TempDir::new("invalid");
+        let mut converter = converter(&dir);
+        std::fs::write(dir.input("a.csv"), "garbage").unwrap();
+
+        converter.process(&dir.input("a.csv"));
+
+        assert!(!dir.input("a.csv").exists());
+        assert!(!dir.0.join("out/a.json").exists());
+        assert!(dir.0.join("quarantine/a.csv").exists());
+        assert!(dir.0.join("quarantine/a.csv.error.txt").exists());
+    }
+
+    #[test]
+    fn test_process_same_stem() {
+        let dir = TempDir::new("stem");
+        let mut converter = converter(&dir);
+        std::fs::write(dir.input("a.csv"), CSV).unwrap();
+        std::fs::write(dir.input("a.txt"), CSV.replace("Deposit", "Other")).unwrap();
+
+        for path in converter.existing_files().unwrap() {
+            converter.process(&path);
+        }
+
+        let output = std::fs::read_to_string(dir.0.join("out/a.json")).unwrap();
+        let report = std::fs::read_to_string(dir.0.join("quarantine/a.txt.error.txt")).unwrap();
+
+        assert!(output.contains("Deposit"));
+        assert!(dir.0.join("quarantine/a.txt").exists());
+        assert!(report.contains("would overwrite the existing file"));
+    }
+
+    #[test]
+    fn test_process_existing_output() {
+        let dir = TempDir::new("existing");
+        let mut converter = converter(&dir);
+        std::fs::write(dir.input("a.csv"), CSV).unwrap();
+        std::fs::write(dir.0.join("out/a.json"), "[]").unwrap();
+
+        converter.process(&dir.input("a.csv"));
+
+        assert_eq!(
+            std::fs::read_to_string(dir.0.join("out/a.json")).unwrap(),
+            "[]"
+        );
+        assert!(dir.0.join("quarantine/a.csv").exists());
+    }
+
+    #[test]
+    fn test_quarantine_not_moved() {
+        let dir = TempDir::new("not-moved");
+        let mut converter = converter(&dir);
+        std::fs::write(dir.input("a.csv"), "garbage").unwrap();
+        // Каталог с именем файла в каталоге карантина не дает переместить файл.
+        std::fs::create_dir_all(dir.0.join("quarantine/a.csv/occupied")).unwrap();
+
+        converter.process(&dir.input("a.csv"));
+
+        let report = std::fs::read_to_string(dir.0.join("quarantine/a.csv.error.txt")).unwrap();
+
+        assert!(dir.input("a.csv").exists());
+        assert!(report.contains("Not moved to quarantine"));
+
+        // Файл, оставшийся на месте, больше не обрабатывается.
+        std::fs::remove_file(dir.0.join("quarantine/a.csv.error.txt")).unwrap();
+        converter.process(&dir.input("a.csv"));
+
+        assert!(!dir.0.join("quarantine/a.csv.error.txt").exists());
+    }
+}
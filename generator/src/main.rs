@@ -0,0 +1,162 @@
+use clap::Parser;
+use parser::{
+    YPBankImpl,
+    compression::Compression,
+    errors::{FormatError, GenerateError, WriteError},
+    generator::{AmountDistribution, Generator, GeneratorConfig},
+};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Number of transactions to generate
+    #[arg(long, value_name = "N")]
+    count: usize,
+
+    /// Seed of the pseudo-random generator: the same seed produces the same transactions
+    #[arg(long, value_name = "SEED", default_value_t = 0)]
+    seed: u64,
+
+    /// TX_ID of the first transaction; the following ones are consecutive
+    #[arg(long, value_name = "TX_ID", default_value_t = 1)]
+    first_tx_id: u64,
+
+    /// Range of FROM_USER_ID and TO_USER_ID values: `MIN-MAX`
+    #[arg(long, value_name = "MIN-MAX", default_value = "1-1000", value_parser = parse_range)]
+    user_ids: (u64, u64),
+
+    /// AMOUNT distribution: `uniform:MIN-MAX` or `log:MIN-MAX` (small amounts are more frequent)
+    #[arg(long, value_name = "DISTRIBUTION", default_value = "log:1-1000000", value_parser = parse_amount)]
+    amount: AmountDistribution,
+
+    /// Minimum TIMESTAMP (ms) of the generated transactions (2024-01-01 by default)
+    #[arg(long, value_name = "MS")]
+    since: Option<u64>,
+
+    /// TIMESTAMP (ms) all generated transactions are less than (2025-01-01 by default)
+    #[arg(long, value_name = "MS")]
+    until: Option<u64>,
+
+    /// Output data format
+    #[clap(long, value_name = "FORMAT")]
+    output_format: String,
+
+    /// File to write (stdout if not specified)
+    #[arg(long, value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Output compression: `none`, `gzip` or `zstd`
+    #[arg(long, value_name = "COMPRESSION", default_value = "none", value_parser = |s: &str| Compression::try_from(s))]
+    compress: Compression,
+}
+
+impl Args {
+    /// Параметры генерации, заданные аргументами командной строки.
+    fn config(&self) -> GeneratorConfig {
+        let default = GeneratorConfig::default();
+
+        GeneratorConfig {
+            seed: self.seed,
+            first_tx_id: self.first_tx_id,
+            user_ids: self.user_ids.0..=self.user_ids.1,
+            amount: self.amount,
+            since: self.since.unwrap_or(default.since),
+            until: self.until.unwrap_or(default.until),
+        }
+    }
+}
+
+/// Разобрать диапазон значений вида `MIN-MAX`.
+fn parse_range(s: &str) -> Result<(u64, u64), String> {
+    s.split_once('-')
+        .and_then(|(min, max)| Some((min.parse().ok()?, max.parse().ok()?)))
+        .ok_or_else(|| format!("expected `MIN-MAX`, got `{s}`"))
+}
+
+/// Разобрать распределение сумм вида `uniform:MIN-MAX` или `log:MIN-MAX`.
+fn parse_amount(s: &str) -> Result<AmountDistribution, String> {
+    match s.split_once(':') {
+        Some(("uniform", range)) => {
+            let (min, max) = parse_range(range)?;
+            Ok(AmountDistribution::Uniform { min, max })
+        }
+        Some(("log", range)) => {
+            let (min, max) = parse_range(range)?;
+            Ok(AmountDistribution::LogUniform { min, max })
+        }
+        _ => Err(format!(
+            "expected `uniform:MIN-MAX` or `log:MIN-MAX`, got `{s}`"
+        )),
+    }
+}
+
+/// Ошибка генерации данных.
+#[derive(Error, Debug)]
+enum CliError {
+    #[error(transparent)]
+    UnknownFormat(#[from] FormatError),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Generate(#[from] GenerateError),
+
+    #[error(transparent)]
+    WriteData(#[from] WriteError),
+}
+
+/// Записать сгенерированные записи в назначение в указанном формате со сжатием.
+fn generate<W: Write>(
+    generator: Generator,
+    count: usize,
+    format: YPBankImpl,
+    compression: Compression,
+    w: W,
+) -> Result<W, CliError> {
+    let mut writer = format.writer_to(compression.encoder(w)?);
+
+    for record in generator.take(count) {
+        writer.write_record(&record)?;
+    }
+
+    Ok(writer.finish()?.finish()?)
+}
+
+fn run() -> Result<(), CliError> {
+    let args = Args::parse();
+
+    let format = YPBankImpl::try_from(args.output_format.as_str())?;
+    let generator = Generator::new(args.config())?;
+
+    match &args.output {
+        Some(output) => {
+            let w = BufWriter::new(File::create(output)?);
+            generate(generator, args.count, format, args.compress, w)?.flush()?;
+        }
+        None => {
+            let stdout = std::io::stdout().lock();
+            generate(generator, args.count, format, args.compress, stdout)?.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = run() {
+        let exit_code = match err {
+            CliError::UnknownFormat(_) => -1,
+            CliError::Io(_) => -2,
+            CliError::Generate(_) => -3,
+            CliError::WriteData(_) => -4,
+        };
+
+        eprintln!("{}", err);
+        std::process::exit(exit_code);
+    }
+}
@@ -0,0 +1,93 @@
+/*
+ * Интерфейс C библиотеки чтения и конвертации данных о транзакциях YPBank.
+ *
+ * Библиотека собирается из крейта `ypbank` с признаком `ffi`:
+ *
+ *     cargo build --release -p ypbank --features ffi
+ *
+ * и подключается как динамическая библиотека (libypbank.so, ypbank.dll, libypbank.dylib).
+ *
+ * Форматы указываются строками "text", "csv", "bin", "json", "jsonl". Функции, завершившиеся
+ * ошибкой, возвращают NULL или -1, а текст ошибки возвращает ypbank_last_error() в том же потоке.
+ */
+
+#ifndef YPBANK_H
+#define YPBANK_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Запись о транзакции.
+ *
+ * Строки заканчиваются нулевым символом и действительны до вызова ypbank_records_free().
+ */
+typedef struct YPBankRecord {
+    uint64_t tx_id;
+    /* Тип транзакции, например "DEPOSIT". */
+    const char *tx_type;
+    uint64_t from_user_id;
+    uint64_t to_user_id;
+    /* Сумма транзакции — 128-битное целое со знаком: amount_hi * 2^64 + amount_lo.
+       Для сумм в пределах uint64_t amount_hi равно 0. */
+    uint64_t amount_lo;
+    int64_t amount_hi;
+    /* Unix epoch timestamp в миллисекундах. */
+    uint64_t timestamp;
+    /* Состояние транзакции, например "SUCCESS". */
+    const char *status;
+    /* Описание транзакции; может содержать нулевые символы, длина — description_len. */
+    const char *description;
+    size_t description_len;
+    /* Код валюты (ISO 4217) или NULL, если валюта не указана. */
+    const char *currency;
+} YPBankRecord;
+
+/* Набор записей о транзакциях, считанный ypbank_parse(). */
+typedef struct YPBankRecords YPBankRecords;
+
+/*
+ * Считать записи о транзакциях в формате format из len байтов data.
+ *
+ * Возвращает набор записей, который необходимо освободить ypbank_records_free(),
+ * или NULL при ошибке.
+ */
+YPBankRecords *ypbank_parse(const uint8_t *data, size_t len, const char *format);
+
+/* Количество записей в наборе. */
+size_t ypbank_records_len(const YPBankRecords *records);
+
+/* Запись набора с номером index (начиная с 0) или NULL, если в наборе меньше записей. */
+const YPBankRecord *ypbank_records_get(const YPBankRecords *records, size_t index);
+
+/* Освободить набор записей (NULL допускается). */
+void ypbank_records_free(YPBankRecords *records);
+
+/*
+ * Конвертировать len байтов data из формата from в формат to.
+ *
+ * При успехе записывает в *output и *output_len данные в целевом формате и возвращает 0;
+ * данные необходимо освободить ypbank_free(). При ошибке возвращает -1.
+ */
+int32_t ypbank_convert(const uint8_t *data, size_t len, const char *from, const char *to,
+                       uint8_t **output, size_t *output_len);
+
+/* Освободить данные, полученные от ypbank_convert() (NULL допускается). */
+void ypbank_free(uint8_t *data, size_t len);
+
+/*
+ * Текст ошибки последнего вызова функции библиотеки в текущем потоке или NULL, если вызов
+ * завершился успешно. Строка действительна до следующего вызова функции библиотеки
+ * в том же потоке.
+ */
+const char *ypbank_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* YPBANK_H */
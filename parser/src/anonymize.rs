@@ -0,0 +1,291 @@
+//! Модуль обезличивания записей о транзакциях.
+//!
+//! Позволяет передавать данные промышленной эксплуатации аналитикам и в тестовые среды:
+//! идентификаторы участников согласованно заменяются псевдонимами (одному участнику во всех
+//! записях соответствует один и тот же псевдоним), а описания удаляются или маскируются.
+
+use super::errors::FormatError;
+use super::record::Record;
+use super::transform::mask_digits;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// Способ обезличивания идентификаторов участников (FROM_USER_ID и TO_USER_ID).
+///
+/// Идентификатор 0 (внешний участник депозитов и снятий) не изменяется, а псевдонимы никогда
+/// не равны 0, поэтому обезличенные записи остаются корректными.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserIdPolicy {
+    /// Оставить идентификаторы без изменений.
+    Keep,
+
+    /// Заменить идентификатор первыми 8 байтами хеша SHA-256 соли и идентификатора.
+    ///
+    /// Псевдонимы совпадают в разных выгрузках, обезличенных с одной и той же солью.
+    Hash {
+        /// Соль, без знания которой псевдонимы нельзя сопоставить с идентификаторами перебором.
+        salt: String,
+    },
+
+    /// Пронумеровать участников подряд, начиная с 1, в порядке их первого появления.
+    Map,
+}
+
+/// Способ обезличивания описаний транзакций.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DescriptionPolicy {
+    /// Оставить описания без изменений.
+    Keep,
+
+    /// Удалить описания.
+    #[default]
+    Redact,
+
+    /// Заменить цифры в описаниях (номера счетов, карт, телефонов) символом `*`.
+    Mask,
+}
+
+/// Реализация трейта [`TryFrom<&str>`] для [`DescriptionPolicy`].
+impl TryFrom<&str> for DescriptionPolicy {
+    /// Ошибка определения способа обезличивания описаний.
+    type Error = FormatError;
+
+    /// Реализация метода [`TryFrom<&str>::try_from`] для [`DescriptionPolicy`].
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "keep" => Ok(Self::Keep),
+            "redact" => Ok(Self::Redact),
+            "mask" => Ok(Self::Mask),
+            _ => Err(FormatError::UnsupportedDescriptionPolicy(s.to_string())),
+        }
+    }
+}
+
+/// Параметры обезличивания записей о транзакциях.
+///
+/// По умолчанию участники нумеруются подряд, а описания удаляются.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnonymizePolicy {
+    /// Способ обезличивания идентификаторов участников.
+    pub user_ids: UserIdPolicy,
+
+    /// Способ обезличивания описаний.
+    pub description: DescriptionPolicy,
+}
+
+/// Реализация трейта [`Default`] для [`AnonymizePolicy`].
+impl Default for AnonymizePolicy {
+    /// Реализация метода [`Default::default`] для [`AnonymizePolicy`].
+    fn default() -> Self {
+        Self {
+            user_ids: UserIdPolicy::Map,
+            description: DescriptionPolicy::default(),
+        }
+    }
+}
+
+/// Последовательное обезличивание записей о транзакциях по одной, например, при потоковой
+/// конвертации.
+///
+/// Сохраняет соответствие идентификаторов псевдонимам, поэтому все записи одного набора
+/// следует обезличивать одним объектом.
+#[derive(Debug, Clone)]
+pub struct Anonymizer {
+    /// Параметры обезличивания.
+    policy: AnonymizePolicy,
+
+    /// Псевдонимы уже встреченных участников при нумерации подряд.
+    mapping: HashMap<u64, u64>,
+}
+
+impl Anonymizer {
+    /// Создание объекта обезличивания с указанными параметрами.
+    pub fn new(policy: AnonymizePolicy) -> Self {
+        Self {
+            policy,
+            mapping: HashMap::new(),
+        }
+    }
+
+    /// Обезличить очередную запись о транзакции.
+    pub fn anonymize(&mut self, record: &mut Record) {
+        let from_user_id = self.pseudonym(record.from_user_id());
+        record.set_from_user_id(from_user_id);
+
+        let to_user_id = self.pseudonym(record.to_user_id());
+        record.set_to_user_id(to_user_id);
+
+        let description = match self.policy.description {
+            DescriptionPolicy::Keep => return,
+            DescriptionPolicy::Redact => String::new(),
+            DescriptionPolicy::Mask => mask_digits(record.description()),
+        };
+
+        record.set_description(description);
+    }
+
+    /// Псевдоним участника с указанным идентификатором.
+    fn pseudonym(&mut self, user_id: u64) -> u64 {
+        if user_id == 0 {
+            return 0;
+        }
+
+        match &self.policy.user_ids {
+            UserIdPolicy::Keep => user_id,
+            UserIdPolicy::Hash { salt } => {
+                let mut hasher = Sha256::new();
+                hasher.update(salt.as_bytes());
+                hasher.update(user_id.to_be_bytes());
+
+                let hash = hasher.finalize();
+                let mut bytes = [0u8; 8];
+                bytes.copy_from_slice(&hash[..8]);
+
+                u64::from_be_bytes(bytes).max(1)
+            }
+            UserIdPolicy::Map => {
+                let next = self.mapping.len() as u64 + 1;
+
+                *self.mapping.entry(user_id).or_insert(next)
+            }
+        }
+    }
+}
+
+/// Обезличить записи о транзакциях.
+pub(crate) fn anonymize(records: &mut [Record], policy: &AnonymizePolicy) {
+    let mut anonymizer = Anonymizer::new(policy.clone());
+
+    for record in records {
+        anonymizer.anonymize(record);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::{Status, TxType};
+    use rstest::rstest;
+
+    fn records() -> Vec<Record> {
+        vec![
+            Record::new(
+                1,
+                TxType::Deposit,
+                0,
+                501,
+                100,
+                1,
+                Status::Success,
+                "Card 4276 1234".to_string(),
+            ),
+            Record::new(
+                2,
+                TxType::Transfer,
+                501,
+                777,
+                50,
+                2,
+                Status::Success,
+                "To Ivan".to_string(),
+            ),
+            Record::new(
+                3,
+                TxType::Withdrawal,
+                777,
+                0,
+                10,
+                3,
+                Status::Success,
+                "".to_string(),
+            ),
+        ]
+    }
+
+    fn user_ids(records: &[Record]) -> Vec<(u64, u64)> {
+        records
+            .iter()
+            .map(|record| (record.from_user_id(), record.to_user_id()))
+            .collect()
+    }
+
+    #[test]
+    fn test_anonymize_default() {
+        let mut records = records();
+        anonymize(&mut records, &AnonymizePolicy::default());
+
+        assert_eq!(user_ids(&records), [(0, 1), (1, 2), (2, 0)]);
+        assert!(records.iter().all(|record| record.description().is_empty()));
+    }
+
+    #[test]
+    fn test_anonymize_hash_is_consistent() {
+        let policy = AnonymizePolicy {
+            user_ids: UserIdPolicy::Hash {
+                salt: "secret".to_string(),
+            },
+            description: DescriptionPolicy::Keep,
+        };
+
+        let mut first = records();
+        anonymize(&mut first, &policy);
+
+        // Псевдонимы не зависят от порядка записей и совпадают в разных выгрузках.
+        let mut second = records();
+        second.reverse();
+        anonymize(&mut second, &policy);
+        second.reverse();
+
+        assert_eq!(user_ids(&first), user_ids(&second));
+        assert_eq!(first[0].from_user_id(), 0);
+        assert_eq!(first[0].to_user_id(), first[1].from_user_id());
+        assert_ne!(first[0].to_user_id(), 501);
+        assert_eq!(first[1].description(), "To Ivan");
+    }
+
+    #[test]
+    fn test_anonymize_hash_depends_on_salt() {
+        let hash = |salt: &str| {
+            let mut records = records();
+            anonymize(
+                &mut records,
+                &AnonymizePolicy {
+                    user_ids: UserIdPolicy::Hash {
+                        salt: salt.to_string(),
+                    },
+                    description: DescriptionPolicy::Keep,
+                },
+            );
+
+            user_ids(&records)
+        };
+
+        assert_ne!(hash("a"), hash("b"));
+    }
+
+    #[rstest]
+    #[case(DescriptionPolicy::Keep, "Card 4276 1234")]
+    #[case(DescriptionPolicy::Redact, "")]
+    #[case(DescriptionPolicy::Mask, "Card **** ****")]
+    fn test_anonymize_description(#[case] description: DescriptionPolicy, #[case] expected: &str) {
+        let mut records = records();
+        anonymize(
+            &mut records,
+            &AnonymizePolicy {
+                user_ids: UserIdPolicy::Keep,
+                description,
+            },
+        );
+
+        assert_eq!(records[0].description(), expected);
+        assert_eq!(user_ids(&records), user_ids(&self::records()));
+    }
+
+    #[test]
+    fn test_description_policy_try_from_unsupported() {
+        assert_eq!(
+            DescriptionPolicy::try_from("hide").unwrap_err().to_string(),
+            "Unsupported description policy: hide"
+        );
+    }
+}
@@ -0,0 +1,458 @@
+//! Модуль дозаписи записей о транзакциях в архив файлов в бинарном формате с ротацией по размеру.
+
+use super::YPBank;
+use super::bin_format::YPBankBin;
+use super::errors::{ArchiveError, WriteError};
+use super::record::Record;
+use super::schema::SchemaVersion;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+/// Расширение файлов архива.
+const ARCHIVE_EXTENSION: &str = "ypbn";
+
+/// Архив записей о транзакциях в бинарном формате: последовательность файлов
+/// `<prefix>-0001.ypbn`, `<prefix>-0002.ypbn`, ... в одном каталоге.
+///
+/// Файлы архива содержат записи без заголовка контейнера, поэтому каждый из них читается
+/// как обычный источник в бинарном формате. Новые записи дописываются в последний файл,
+/// а когда его размер превысил бы [`BinArchive::max_file_size`], начинается следующий файл.
+/// Это позволяет долго работающему процессу-сборщику вести один согласованный архив,
+/// не переписывая уже записанные данные.
+#[derive(Debug, Clone)]
+pub struct BinArchive {
+    /// Каталог файлов архива.
+    dir: PathBuf,
+
+    /// Префикс имен файлов архива.
+    prefix: String,
+
+    /// Максимальный размер файла архива в байтах.
+    max_file_size: u64,
+
+    /// Признак усечения некорректного окончания последнего файла архива при открытии.
+    repair_tail: bool,
+
+    /// Версия схемы дописываемых записей.
+    schema_version: SchemaVersion,
+}
+
+impl BinArchive {
+    /// Максимальный размер файла архива по умолчанию (64 МиБ).
+    pub const DEFAULT_MAX_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+    /// Создание архива в указанном каталоге с указанным префиксом имен файлов.
+    pub fn new(dir: impl Into<PathBuf>, prefix: impl Into<String>) -> Self {
+        Self {
+            dir: dir.into(),
+            prefix: prefix.into(),
+            max_file_size: Self::DEFAULT_MAX_FILE_SIZE,
+            repair_tail: false,
+            schema_version: SchemaVersion::default(),
+        }
+    }
+
+    /// Установить максимальный размер файла архива в байтах.
+    ///
+    /// Запись, размер которой превышает его сама по себе, записывается в отдельный файл.
+    pub fn max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = max_file_size.max(1);
+        self
+    }
+
+    /// Усекать последний файл архива перед первой некорректной записью, если его окончание
+    /// некорректно (например, запись оборвалась при аварийном завершении сборщика).
+    /// Все данные, следующие за некорректной записью, отбрасываются.
+    ///
+    /// По умолчанию архив с некорректным окончанием не открывается.
+    pub fn repair_tail(mut self, repair_tail: bool) -> Self {
+        self.repair_tail = repair_tail;
+        self
+    }
+
+    /// Установить версию схемы дописываемых записей (по умолчанию [`SchemaVersion::V1`]).
+    ///
+    /// Запись, требующая более новой версии схемы, не записывается, а возвращается ошибка
+    /// [`WriteError::SchemaVersionRequired`].
+    pub fn schema_version(mut self, schema_version: SchemaVersion) -> Self {
+        self.schema_version = schema_version;
+        self
+    }
+
+    /// Путь к файлу архива с указанным номером (начиная с 1).
+    pub fn file_path(&self, number: u32) -> PathBuf {
+        self.dir
+            .join(format!("{}-{number:04}.{ARCHIVE_EXTENSION}", self.prefix))
+    }
+
+    /// Номера существующих файлов архива в порядке возрастания.
+    pub fn file_numbers(&self) -> Result<Vec<u32>, ArchiveError> {
+        let mut numbers = vec![];
+
+        for entry in std::fs::read_dir(&self.dir)? {
+            let name = entry?.file_name();
+            let number = name
+                .to_str()
+                .and_then(|name| name.strip_prefix(self.prefix.as_str()))
+                .and_then(|name| name.strip_prefix('-'))
+                .and_then(|name| name.strip_suffix(ARCHIVE_EXTENSION))
+                .and_then(|name| name.strip_suffix('.'))
+                .filter(|number| number.bytes().all(|b| b.is_ascii_digit()))
+                .and_then(|number| number.parse::<u32>().ok());
+
+            if let Some(number) = number.filter(|&number| number > 0) {
+                numbers.push(number);
+            }
+        }
+
+        numbers.sort_unstable();
+
+        Ok(numbers)
+    }
+
+    /// Открыть архив для дозаписи, создав каталог архива, если его нет.
+    ///
+    /// Последний файл архива проверяется целиком: если он начинается с заголовка контейнера
+    /// или содержит некорректные записи, возвращается ошибка (см. также
+    /// [`BinArchive::repair_tail`]).
+    pub fn open(self) -> Result<BinArchiveWriter, ArchiveError> {
+        std::fs::create_dir_all(&self.dir)?;
+
+        let (number, size) = match self.file_numbers()?.last() {
+            Some(&number) => (number, self.check_tail(&self.file_path(number))?),
+            None => (1, 0),
+        };
+
+        Ok(BinArchiveWriter {
+            archive: self,
+            number,
+            size,
+            file: None,
+            buffer: vec![],
+            count: 0,
+        })
+    }
+
+    /// Проверить записи файла архива и вернуть размер его корректных данных, при необходимости
+    /// усекая файл до него.
+    fn check_tail(&self, path: &Path) -> Result<u64, ArchiveError> {
+        let mut file = File::open(path)?;
+
+        let mut magic = vec![];
+        (&mut file)
+            .take(YPBankBin::CONTAINER_MAGIC.len() as u64)
+            .read_to_end(&mut magic)?;
+
+        if magic == YPBankBin::CONTAINER_MAGIC {
+            return Err(ArchiveError::Container(path.to_path_buf()));
+        }
+
+        file.rewind()?;
+
+        let mut iter = YPBankBin::iter_from(file);
+        let mut end = 0;
+
+        while let Some(result) = iter.next_with_offset() {
+            match result {
+                Ok(_) => end = iter.offset(),
+                Err(source) if !self.repair_tail => {
+                    return Err(ArchiveError::CorruptedTail {
+                        path: path.to_path_buf(),
+                        source,
+                    });
+                }
+                Err(_) => {
+                    OpenOptions::new().write(true).open(path)?.set_len(end)?;
+                    break;
+                }
+            }
+        }
+
+        Ok(end)
+    }
+}
+
+/// Дозапись записей о транзакциях в архив файлов в бинарном формате (см. [`BinArchive`]).
+///
+/// Записи буферизуются; для записи буфера на диск необходимо вызвать
+/// [`BinArchiveWriter::flush`] или [`BinArchiveWriter::finish`].
+#[derive(Debug)]
+pub struct BinArchiveWriter {
+    /// Параметры архива.
+    archive: BinArchive,
+
+    /// Номер текущего файла архива.
+    number: u32,
+
+    /// Размер данных текущего файла архива в байтах.
+    size: u64,
+
+    /// Назначение записи текущего файла архива (открывается при записи в него первой записи).
+    file: Option<BufWriter<File>>,
+
+    /// Буфер сериализации очередной записи.
+    buffer: Vec<u8>,
+
+    /// Количество дописанных записей.
+    count: usize,
+}
+
+impl BinArchiveWriter {
+    /// Дописать очередную запись о транзакции, начав следующий файл архива, если размер
+    /// текущего превысил бы максимальный.
+    pub fn append(&mut self, record: &Record) -> Result<(), ArchiveError> {
+        if record.schema_version() > self.archive.schema_version {
+            return Err(WriteError::SchemaVersionRequired {
+                tx_id: record.tx_id(),
+                amount: record.wide_amount(),
+                required: record.schema_version(),
+                used: self.archive.schema_version,
+            })?;
+        }
+
+        self.buffer.clear();
+        record.to_bin_with_schema(&mut self.buffer, self.archive.schema_version)?;
+
+        let len = self.buffer.len() as u64;
+
+        if self.size > 0 && self.size + len > self.archive.max_file_size {
+            self.rotate()?;
+        }
+
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => self.file.insert(BufWriter::new(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(self.path())?,
+            )),
+        };
+
+        file.write_all(&self.buffer)?;
+        self.size += len;
+        self.count += 1;
+
+        Ok(())
+    }
+
+    /// Завершить текущий файл архива и перейти к следующему.
+    fn rotate(&mut self) -> Result<(), ArchiveError> {
+        self.flush()?;
+
+        self.file = None;
+        self.number += 1;
+        self.size = 0;
+
+        Ok(())
+    }
+
+    /// Записать буферизованные записи в текущий файл архива и дождаться их записи на диск.
+    pub fn flush(&mut self) -> Result<(), ArchiveError> {
+        if let Some(file) = &mut self.file {
+            file.flush()?;
+            file.get_ref().sync_data()?;
+        }
+
+        Ok(())
+    }
+
+    /// Путь к текущему файлу архива, в который дописываются записи.
+    pub fn path(&self) -> PathBuf {
+        self.archive.file_path(self.number)
+    }
+
+    /// Размер данных текущего файла архива в байтах.
+    pub fn file_size(&self) -> u64 {
+        self.size
+    }
+
+    /// Количество дописанных записей.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Записать буферизованные записи на диск и закрыть архив.
+    pub fn finish(mut self) -> Result<(), ArchiveError> {
+        self.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::YPBankImpl;
+
+    /// Временный каталог архива, удаляемый по окончании теста.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("parser-archive-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn read_file(path: &Path) -> Vec<Record> {
+        YPBankImpl::Bin
+            .read_from(&mut File::open(path).unwrap())
+            .unwrap()
+    }
+
+    fn record_size() -> u64 {
+        let mut data = vec![];
+        crate::tests::get_data_to_write()[0]
+            .to_bin(&mut data)
+            .unwrap();
+
+        data.len() as u64
+    }
+
+    #[test]
+    fn test_append_and_reopen() {
+        let dir = TempDir::new("reopen");
+        let records = crate::tests::get_data_to_write();
+
+        let mut writer = BinArchive::new(&dir.0, "ledger").open().unwrap();
+        writer.append(&records[0]).unwrap();
+        writer.finish().unwrap();
+
+        let mut writer = BinArchive::new(&dir.0, "ledger").open().unwrap();
+        assert_eq!(writer.path(), dir.0.join("ledger-0001.ypbn"));
+        assert_eq!(writer.file_size(), record_size());
+
+        writer.append(&records[1]).unwrap();
+        writer.append(&records[2]).unwrap();
+        assert_eq!(writer.count(), 2);
+        writer.finish().unwrap();
+
+        assert_eq!(read_file(&dir.0.join("ledger-0001.ypbn")), records);
+    }
+
+    #[test]
+    fn test_rotation() {
+        let dir = TempDir::new("rotation");
+        let records = crate::tests::get_data_to_write();
+        let archive = BinArchive::new(&dir.0, "ledger").max_file_size(2 * record_size());
+
+        let mut writer = archive.clone().open().unwrap();
+        for record in &records {
+            writer.append(record).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let mut writer = archive.clone().open().unwrap();
+        writer.append(&records[0]).unwrap();
+        writer.append(&records[1]).unwrap();
+        assert_eq!(writer.path(), dir.0.join("ledger-0003.ypbn"));
+        writer.finish().unwrap();
+
+        assert_eq!(archive.file_numbers().unwrap(), [1, 2, 3]);
+        assert_eq!(read_file(&archive.file_path(1)), records[..2]);
+        assert_eq!(
+            read_file(&archive.file_path(2)),
+            [records[2].clone(), records[0].clone()]
+        );
+        assert_eq!(read_file(&archive.file_path(3)), records[1..2]);
+    }
+
+    #[test]
+    fn test_truncated_tail() {
+        let dir = TempDir::new("truncated");
+        let records = crate::tests::get_data_to_write();
+        let archive = BinArchive::new(&dir.0, "ledger");
+
+        let mut writer = archive.clone().open().unwrap();
+        writer.append(&records[0]).unwrap();
+        writer.append(&records[1]).unwrap();
+        writer.finish().unwrap();
+
+        let path = archive.file_path(1);
+        let len = std::fs::metadata(&path).unwrap().len();
+        OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(len - 5)
+            .unwrap();
+
+        let error = archive.clone().open().unwrap_err();
+        assert!(matches!(error, ArchiveError::CorruptedTail { .. }));
+        assert!(error.to_string().starts_with("Cannot append to "));
+
+        let mut writer = archive.repair_tail(true).open().unwrap();
+        assert_eq!(writer.file_size(), record_size());
+
+        writer.append(&records[2]).unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(read_file(&path), [records[0].clone(), records[2].clone()]);
+    }
+
+    #[test]
+    fn test_container_file() {
+        let dir = TempDir::new("container");
+        let archive = BinArchive::new(&dir.0, "ledger");
+
+        std::fs::create_dir_all(&dir.0).unwrap();
+        YPBankBin::from_records(crate::tests::get_data_to_write())
+            .write_container_to(&mut File::create(archive.file_path(1)).unwrap())
+            .unwrap();
+
+        assert!(matches!(
+            archive.open().unwrap_err(),
+            ArchiveError::Container(_)
+        ));
+    }
+
+    #[test]
+    fn test_schema_version_required() {
+        let dir = TempDir::new("schema");
+        let record = crate::tests::get_wide_data_to_write().remove(0);
+
+        let mut writer = BinArchive::new(&dir.0, "ledger").open().unwrap();
+        assert!(matches!(
+            writer.append(&record).unwrap_err(),
+            ArchiveError::Write(WriteError::SchemaVersionRequired { .. })
+        ));
+        assert!(!writer.path().exists());
+
+        let mut writer = BinArchive::new(&dir.0, "ledger")
+            .schema_version(SchemaVersion::V2)
+            .open()
+            .unwrap();
+        writer.append(&record).unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(read_file(&dir.0.join("ledger-0001.ypbn")), [record]);
+    }
+
+    #[test]
+    fn test_file_numbers_ignores_other_files() {
+        let dir = TempDir::new("numbers");
+        let archive = BinArchive::new(&dir.0, "ledger");
+
+        std::fs::create_dir_all(&dir.0).unwrap();
+        for name in [
+            "ledger-0002.ypbn",
+            "ledger-0010.ypbn",
+            "ledger-x.ypbn",
+            "ledger-0003.bin",
+            "other-0004.ypbn",
+        ] {
+            File::create(dir.0.join(name)).unwrap();
+        }
+
+        assert_eq!(archive.file_numbers().unwrap(), [2, 10]);
+    }
+}
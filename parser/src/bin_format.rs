@@ -1,7 +1,55 @@
 use super::YPBank;
-use super::errors::{ReadError, WriteError};
-use super::record::Record;
-use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use super::errors::{Position, ReadError, WriteError};
+use super::iter::{DeclaredContent, PositionReader, RecordIter};
+use super::options::ParseContext;
+use super::record::errors::ParseRecordFromBinError;
+use super::record::{Record, RecordRef};
+use super::schema::SchemaVersion;
+use super::writer::{RecordWriter, finish_nothing};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::fmt;
+use std::io::{BufRead, BufWriter, Read, Write};
+
+/// Диапазон данных, пропущенных при восстановлении поврежденного источника в бинарном формате
+/// (см. [`YPBankBin::recover`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecoveryGap {
+    /// Смещение начала пропущенных данных в байтах.
+    pub start: u64,
+
+    /// Смещение конца пропущенных данных в байтах (не включительно).
+    pub end: u64,
+
+    /// Ошибка чтения первой из пропущенных записей.
+    pub reason: String,
+}
+
+impl RecoveryGap {
+    /// Количество пропущенных байт.
+    pub fn len(&self) -> u64 {
+        self.end - self.start
+    }
+
+    /// Признак отсутствия пропущенных байт.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// Реализация трейта [`fmt::Display`] для [`RecoveryGap`].
+impl fmt::Display for RecoveryGap {
+    /// Реализация метода [`fmt::Display::fmt`] для [`RecoveryGap`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "bytes {}..{} ({} bytes skipped): {}",
+            self.start,
+            self.end,
+            self.len(),
+            self.reason
+        )
+    }
+}
 
 #[derive(Debug)]
 pub struct YPBankBin {
@@ -9,30 +57,368 @@ pub struct YPBankBin {
     pub records: Vec<Record>,
 }
 
-impl YPBank for YPBankBin {
-    // Считать данные о банковских операциях в бинарном формате.
-    fn read_from<R: Read>(r: &mut R) -> Result<Self, ReadError> {
-        let mut reader = BufReader::new(r);
+impl YPBankBin {
+    /// MAGIC необязательного заголовка контейнера, предшествующего записям.
+    pub(crate) const CONTAINER_MAGIC: [u8; 4] = [0x59, 0x50, 0x42, 0x43];
+
+    /// Версия заголовка контейнера для записей версии схемы [`SchemaVersion::V1`].
+    const CONTAINER_VERSION: u32 = 1;
 
-        let mut records: Vec<Record> = vec![];
+    /// Версия заголовка контейнера, записи в котором могут содержать широкие суммы
+    /// (см. [`SchemaVersion::V2`]).
+    const CONTAINER_VERSION_WIDE: u32 = 2;
 
-        while !reader.fill_buf()?.is_empty() {
-            records.push(Record::from_bin(&mut reader)?);
+    /// Размер заголовка контейнера в байтах.
+    pub(crate) const CONTAINER_HEADER_LEN: usize = 24;
+
+    /// Считать необязательный заголовок контейнера.
+    ///
+    /// Заголовок имеет следующую структуру (все числа в порядке big-endian):
+    ///
+    /// MAGIC (4 байта) | VERSION (4 байта) | RECORD_COUNT (8 байт) | PAYLOAD_LEN (8 байт)
+    ///
+    /// Если источник начинается не с MAGIC контейнера, заголовок считается отсутствующим.
+    fn read_header<R: Read>(
+        reader: &mut PositionReader<R>,
+    ) -> Result<Option<DeclaredContent>, ReadError> {
+        if !reader
+            .peek(Self::CONTAINER_MAGIC.len())?
+            .starts_with(&Self::CONTAINER_MAGIC)
+        {
+            return Ok(None);
+        }
+
+        reader.consume(Self::CONTAINER_MAGIC.len());
+
+        Ok(Some(Self::read_header_fields(reader)?))
+    }
+
+    /// Считать поля заголовка контейнера, следующие за его MAGIC.
+    pub(crate) fn read_header_fields<R: Read>(
+        reader: &mut R,
+    ) -> Result<DeclaredContent, ReadError> {
+        let version = reader
+            .read_u32::<BigEndian>()
+            .map_err(ParseRecordFromBinError::from)?;
+
+        if !(Self::CONTAINER_VERSION..=Self::CONTAINER_VERSION_WIDE).contains(&version) {
+            return Err(ParseRecordFromBinError::UnsupportedContainerVersion(
+                version,
+            ))?;
         }
 
+        let records = reader
+            .read_u64::<BigEndian>()
+            .map_err(ParseRecordFromBinError::from)?;
+        let payload_len = reader
+            .read_u64::<BigEndian>()
+            .map_err(ParseRecordFromBinError::from)?;
+
+        Ok(DeclaredContent {
+            records,
+            payload_len,
+        })
+    }
+
+    /// Записать данные о банковских операциях в бинарном формате с заголовком контейнера,
+    /// содержащим версию формата, количество записей и размер их данных.
+    ///
+    /// Заголовок позволяет при чтении заранее выделить память под записи и выявить
+    /// обрыв источника. Если хотя бы одна запись содержит широкую сумму, записывается
+    /// версия 2, чтобы читатели, не поддерживающие широкие суммы, отказались от чтения,
+    /// а не считали вместо них 0.
+    pub fn write_container_to<W: Write>(&self, w: &mut W) -> Result<(), WriteError> {
+        let mut payload = vec![];
+
+        for record in &self.records {
+            record.to_bin_with_schema(&mut payload, SchemaVersion::V2)?;
+        }
+
+        let mut header = Vec::with_capacity(Self::CONTAINER_HEADER_LEN);
+        header.extend(Self::CONTAINER_MAGIC);
+        let wide = self
+            .records
+            .iter()
+            .any(|record| record.schema_version() > SchemaVersion::V1);
+        header.write_u32::<BigEndian>(if wide {
+            Self::CONTAINER_VERSION_WIDE
+        } else {
+            Self::CONTAINER_VERSION
+        })?;
+        header.write_u64::<BigEndian>(self.records.len() as u64)?;
+        header.write_u64::<BigEndian>(payload.len() as u64)?;
+
+        w.write_all(&header)?;
+        w.write_all(&payload)?;
+
+        Ok(())
+    }
+
+    /// Последовательно разбирать записи о банковских операциях в бинарном формате из среза
+    /// байтов, не копируя их строковые поля (см. [`RecordRef`]).
+    ///
+    /// Подходит для просмотра больших архивов, отображенных в память. Если данные начинаются
+    /// с заголовка контейнера, разбирается объявленное в нем количество записей. Итерация
+    /// прекращается после первой ошибки.
+    pub fn parse_slice(data: &[u8]) -> impl Iterator<Item = Result<RecordRef<'_>, ReadError>> {
+        SliceRecords {
+            data,
+            offset: 0,
+            end: data.len(),
+            remaining: None,
+            index: 0,
+            header_pending: data.starts_with(&Self::CONTAINER_MAGIC),
+            finished: false,
+        }
+    }
+
+    /// Считать все корректные записи из поврежденного источника в бинарном формате
+    /// (например, из оборванной или частично перезаписанной выгрузки).
+    ///
+    /// После некорректной записи чтение продолжается с MAGIC следующей записи, а вместо ошибки
+    /// возвращаются диапазоны пропущенных данных. Подряд идущие некорректные записи образуют
+    /// один диапазон. Если источник не удается прочитать до конца, непрочитанный остаток
+    /// также считается пропущенным.
+    pub fn recover<R: Read>(r: R) -> (Vec<Record>, Vec<RecoveryGap>) {
+        let mut iter = Self::iter_from(r).continue_on_error();
+
+        let mut records = vec![];
+        let mut gaps = vec![];
+
+        // Диапазон, конец которого станет известен при считывании следующей корректной записи.
+        let mut open_gap: Option<RecoveryGap> = None;
+
+        loop {
+            let offset = iter.offset();
+
+            match iter.next_with_offset() {
+                Some(Ok((start, record))) => {
+                    if let Some(mut gap) = open_gap.take() {
+                        gap.end = start;
+                        gaps.push(gap);
+                    }
+
+                    records.push(record);
+                }
+                Some(Err(e)) => {
+                    let start = e.position().map_or(offset, |position| position.offset);
+
+                    open_gap.get_or_insert_with(|| RecoveryGap {
+                        start,
+                        end: start,
+                        reason: e.inner().to_string(),
+                    });
+                }
+                None => break,
+            }
+        }
+
+        if let Some(mut gap) = open_gap {
+            gap.end = iter.skip_rest();
+            gaps.push(gap);
+        }
+
+        (records, gaps)
+    }
+
+    /// Считать очередную запись о банковской операции в бинарном формате.
+    fn read_record<B: BufRead>(
+        reader: &mut B,
+        index: usize,
+        context: &ParseContext,
+    ) -> Result<Option<Record>, ReadError> {
+        if reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Record::from_bin_at(reader, index + 1, context)?))
+    }
+
+    /// Пропустить данные поврежденной записи вплоть до MAGIC следующей записи.
+    fn resync<R: Read>(reader: &mut PositionReader<R>) -> Result<(), ReadError> {
+        let magic = Record::BINARY_MAGIC;
+
+        loop {
+            let buffer = reader.peek(magic.len())?;
+
+            if buffer.len() < magic.len() {
+                let len = buffer.len();
+                reader.consume(len);
+
+                return Ok(());
+            }
+
+            if buffer.starts_with(&magic) {
+                return Ok(());
+            }
+
+            // Пропустить байты до следующего вхождения первого байта MAGIC.
+            let skip = buffer[1..]
+                .iter()
+                .position(|&b| b == magic[0])
+                .map_or(buffer.len(), |pos| pos + 1);
+            reader.consume(skip);
+        }
+    }
+
+    /// Записать очередную запись о банковской операции в бинарном формате.
+    fn write_record<W: Write>(
+        writer: &mut BufWriter<W>,
+        record: &Record,
+        _index: usize,
+    ) -> Result<(), WriteError> {
+        // Версия схемы записи проверяется [`RecordWriter::write_record`].
+        record.to_bin_with_schema(writer, SchemaVersion::V2)?;
+
+        Ok(())
+    }
+}
+
+/// Итератор записей о банковских операциях, разбираемых из среза байтов в бинарном формате
+/// (см. [`YPBankBin::parse_slice`]).
+struct SliceRecords<'a> {
+    /// Разбираемые данные.
+    data: &'a [u8],
+
+    /// Смещение очередной записи.
+    offset: usize,
+
+    /// Конец данных записей.
+    end: usize,
+
+    /// Количество записей, объявленное в заголовке контейнера и еще не разобранное.
+    remaining: Option<u64>,
+
+    /// Количество уже разобранных записей.
+    index: usize,
+
+    /// Признак того, что заголовок контейнера еще предстоит разобрать.
+    header_pending: bool,
+
+    /// Признак завершения итерации.
+    finished: bool,
+}
+
+impl SliceRecords<'_> {
+    /// Разобрать заголовок контейнера и ограничить данные объявленными в нем записями.
+    fn read_header(&mut self) -> Result<(), ReadError> {
+        let mut fields = &self.data[YPBankBin::CONTAINER_MAGIC.len()..];
+        let declared = YPBankBin::read_header_fields(&mut fields)?;
+
+        self.offset = YPBankBin::CONTAINER_HEADER_LEN;
+        self.end = usize::try_from(declared.payload_len)
+            .ok()
+            .and_then(|len| self.offset.checked_add(len))
+            .map_or(self.data.len(), |end| end.min(self.data.len()));
+        self.remaining = Some(declared.records);
+
+        Ok(())
+    }
+}
+
+/// Реализация трейта [`Iterator`] для [`SliceRecords`].
+impl<'a> Iterator for SliceRecords<'a> {
+    type Item = Result<RecordRef<'a>, ReadError>;
+
+    /// Реализация метода [`Iterator::next`] для [`SliceRecords`].
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        if std::mem::take(&mut self.header_pending)
+            && let Err(e) = self.read_header()
+        {
+            self.finished = true;
+            return Some(Err(e));
+        }
+
+        if self.remaining == Some(0) || (self.remaining.is_none() && self.offset == self.end) {
+            self.finished = true;
+            return None;
+        }
+
+        let position = Position {
+            record: self.index + 1,
+            line: None,
+            offset: self.offset as u64,
+        };
+
+        match RecordRef::from_bin_at(
+            &self.data[self.offset..self.end],
+            position.record,
+            &ParseContext::default(),
+        ) {
+            Ok((record, len)) => {
+                self.offset += len;
+                self.index += 1;
+                self.remaining = self.remaining.map(|remaining| remaining - 1);
+
+                Some(Ok(record))
+            }
+            Err(e) => {
+                self.finished = true;
+
+                Some(Err(ReadError::At {
+                    position,
+                    source: Box::new(e.into()),
+                }))
+            }
+        }
+    }
+}
+
+impl YPBank for YPBankBin {
+    /// Считанные записи о банковских операциях.
+    fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    /// Изменяемый список записей о банковских операциях.
+    fn records_mut(&mut self) -> &mut Vec<Record> {
+        &mut self.records
+    }
+
+    /// Создание набора из указанных записей о банковских операциях.
+    fn from_records(records: Vec<Record>) -> Self {
+        Self { records }
+    }
+
+    // Считать данные о банковских операциях в бинарном формате.
+    fn read_from<R: Read>(r: &mut R) -> Result<Self, ReadError> {
+        let records = Self::iter_from(r).read_all()?;
+
         Ok(Self { records })
     }
 
+    /// Последовательно считывать данные о банковских операциях в бинарном формате.
+    ///
+    /// Если источник начинается с заголовка контейнера, считывается объявленное в нем
+    /// количество записей.
+    fn iter_from<R: Read>(r: R) -> RecordIter<R> {
+        RecordIter::new_binary(r, Self::read_record)
+            .with_header(Self::read_header)
+            .with_recovery(Self::resync)
+            .with_rescan()
+    }
+
     /// Записать данные о банковских операциях в бинарном формате.
     fn write_to<W: Write>(&self, w: &mut W) -> Result<(), WriteError> {
-        let mut writer = BufWriter::new(w);
+        let mut writer = Self::writer_to(w);
 
         for record in &self.records {
-            record.to_bin(&mut writer)?;
+            writer.write_record(record)?;
         }
 
+        writer.finish()?;
+
         Ok(())
     }
+
+    /// Последовательно записывать данные о банковских операциях в бинарном формате.
+    fn writer_to<W: Write>(w: W) -> RecordWriter<W> {
+        RecordWriter::new(w, Self::write_record, finish_nothing)
+    }
 }
 
 #[cfg(test)]
@@ -41,7 +427,8 @@ mod tests {
     use crate::record::errors::ParseRecordFromBinError;
     use crate::record::status::Status;
     use crate::record::tx_type::TxType;
-    use std::io::Cursor;
+    use rstest::rstest;
+    use std::io::{BufReader, Cursor};
 
     #[test]
     fn test_read_from_bin_data_specification() {
@@ -114,12 +501,39 @@ mod tests {
 
         let result = result.unwrap_err();
         assert!(matches!(
-            result,
+            result.inner(),
             ReadError::FromBin(ParseRecordFromBinError::InvalidMagicNumber)
         ));
         assert_eq!(
             result.to_string(),
-            "Binary format parsing error: Invalid magic number"
+            "Binary format parsing error: Invalid magic number (at record 1, byte 0)"
+        );
+    }
+
+    #[test]
+    fn test_read_from_bin_truncated() {
+        let (data, offsets) = get_bin_data();
+        let truncated = &data[..offsets[2] as usize + 20];
+
+        let result = YPBankBin::read_from(&mut &truncated[..]).unwrap_err();
+
+        assert!(result.is_truncated());
+        assert!(matches!(
+            result.inner(),
+            ReadError::FromBin(ParseRecordFromBinError::UnexpectedEof {
+                record_index: 3,
+                got: 20,
+                ..
+            })
+        ));
+
+        let mut corrupted = data.clone();
+        corrupted[offsets[2] as usize] = 0x00;
+
+        assert!(
+            !YPBankBin::read_from(&mut corrupted.as_slice())
+                .unwrap_err()
+                .is_truncated()
         );
     }
 
@@ -184,4 +598,333 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_bin_container_round_trip() {
+        let records = crate::tests::get_data_to_write();
+
+        let mut buffer = vec![];
+        YPBankBin {
+            records: records.clone(),
+        }
+        .write_container_to(&mut buffer)
+        .unwrap();
+
+        assert_eq!(
+            buffer[..24],
+            [
+                0x59, 0x50, 0x42, 0x43, // MAGIC "YPBC"
+                0x00, 0x00, 0x00, 0x01, // VERSION
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, // RECORD_COUNT
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xd4, // PAYLOAD_LEN (212)
+            ]
+        );
+
+        // Данные после объявленных записей не считываются.
+        buffer.extend([0x00, 0x01]);
+
+        let data = YPBankBin::read_from(&mut buffer.as_slice()).unwrap();
+
+        assert_eq!(data.records, records);
+    }
+
+    /// Данные записей в бинарном формате и смещения начала каждой записи.
+    fn get_bin_data() -> (Vec<u8>, Vec<u64>) {
+        let mut data = vec![];
+        let mut offsets = vec![];
+
+        for record in crate::tests::get_data_to_write() {
+            offsets.push(data.len() as u64);
+            record.to_bin(&mut data).unwrap();
+        }
+
+        (data, offsets)
+    }
+
+    #[rstest]
+    #[case(
+        1,
+        "Record size 61 is less than the size of fixed fields (46) plus DESCRIPTION_SIZE (16)"
+    )]
+    #[case(
+        256,
+        "Record size 61 is less than the size of fixed fields (46) plus DESCRIPTION_SIZE (271)"
+    )]
+    #[case(
+        -3,
+        "Invalid value: \"User transf (DESCRIPTION must start and end with symbol \")"
+    )]
+    fn test_read_from_bin_inconsistent_description_size(#[case] delta: i64, #[case] error: &str) {
+        let (mut data, offsets) = get_bin_data();
+
+        // Изменить DESCRIPTION_SIZE второй записи, не меняя ее RECORD_SIZE.
+        let position = offsets[1] as usize + 50;
+        let desc_len = u32::from_be_bytes(data[position..position + 4].try_into().unwrap());
+        let desc_len = (i64::from(desc_len) + delta) as u32;
+        data[position..position + 4].copy_from_slice(&desc_len.to_be_bytes());
+
+        let results: Vec<_> = YPBankBin::iter_from(data.as_slice())
+            .continue_on_error()
+            .collect();
+
+        // Ошибка в записи не сдвигает чтение следующей записи.
+        let expected = crate::tests::get_data_to_write();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap(), &expected[0]);
+        assert_eq!(results[2].as_ref().unwrap(), &expected[2]);
+        assert_eq!(
+            results[1].as_ref().unwrap_err().to_string(),
+            format!(
+                "Binary format parsing error: {error} (at record 2, byte {})",
+                offsets[1]
+            )
+        );
+
+        let (_, gaps) = YPBankBin::recover(data.as_slice());
+        assert_eq!(gaps.len(), 1);
+        assert_eq!((gaps[0].start, gaps[0].end), (offsets[1], offsets[2]));
+    }
+
+    #[test]
+    fn test_parse_slice() {
+        let (data, _) = get_bin_data();
+
+        let records = YPBankBin::parse_slice(&data)
+            .map(|result| result.map(Record::from))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(records, crate::tests::get_data_to_write());
+        assert_eq!(YPBankBin::parse_slice(&[]).count(), 0);
+    }
+
+    #[test]
+    fn test_parse_slice_container() {
+        let mut data = vec![];
+        YPBankBin {
+            records: crate::tests::get_wide_data_to_write(),
+        }
+        .write_container_to(&mut data)
+        .unwrap();
+
+        // Данные после объявленных записей не разбираются.
+        data.extend([0x00, 0x01]);
+
+        let records = YPBankBin::parse_slice(&data)
+            .map(|result| result.map(Record::from))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(records, crate::tests::get_wide_data_to_write());
+    }
+
+    #[test]
+    fn test_parse_slice_error() {
+        let (mut data, offsets) = get_bin_data();
+        data[offsets[1] as usize] = 0x00;
+
+        let results: Vec<_> = YPBankBin::parse_slice(&data).collect();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results[0].as_ref().unwrap().description(),
+            "Terminal deposit"
+        );
+        assert_eq!(
+            results[1].as_ref().unwrap_err().to_string(),
+            format!(
+                "Binary format parsing error: Invalid magic number (at record 2, byte {})",
+                offsets[1]
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_slice_container_truncated() {
+        let mut data = vec![];
+        YPBankBin {
+            records: crate::tests::get_data_to_write(),
+        }
+        .write_container_to(&mut data)
+        .unwrap();
+        data.truncate(data.len() - 10);
+
+        let results: Vec<_> = YPBankBin::parse_slice(&data).collect();
+
+        assert_eq!(results.len(), 3);
+        assert!(results[2].as_ref().unwrap_err().is_truncated());
+    }
+
+    #[test]
+    fn test_recover_intact() {
+        let (data, _) = get_bin_data();
+
+        let (records, gaps) = YPBankBin::recover(data.as_slice());
+
+        assert_eq!(records, crate::tests::get_data_to_write());
+        assert!(gaps.is_empty());
+    }
+
+    #[test]
+    fn test_recover_corrupted_record() {
+        let (mut data, offsets) = get_bin_data();
+        // TX_TYPE второй записи.
+        data[offsets[1] as usize + 16] = 0xFF;
+
+        let (records, gaps) = YPBankBin::recover(data.as_slice());
+
+        let mut expected = crate::tests::get_data_to_write();
+        expected.remove(1);
+        assert_eq!(records, expected);
+        assert_eq!(
+            gaps,
+            vec![RecoveryGap {
+                start: offsets[1],
+                end: offsets[2],
+                reason: "Binary format parsing error: Invalid value: 255 (Invalid TX_TYPE: 255)"
+                    .to_string(),
+            }]
+        );
+        assert_eq!(gaps[0].len(), offsets[2] - offsets[1]);
+    }
+
+    #[test]
+    fn test_recover_garbage_and_truncated_tail() {
+        let (data, offsets) = get_bin_data();
+
+        let mut corrupted = data[..offsets[1] as usize].to_vec();
+        corrupted.extend_from_slice(b"garbage");
+        corrupted.extend_from_slice(&data[offsets[1] as usize..data.len() - 10]);
+
+        let (records, gaps) = YPBankBin::recover(corrupted.as_slice());
+
+        assert_eq!(records, crate::tests::get_data_to_write()[..2]);
+        assert_eq!(gaps.len(), 2);
+        assert_eq!((gaps[0].start, gaps[0].end), (offsets[1], offsets[1] + 7));
+        assert_eq!(
+            (gaps[1].start, gaps[1].end),
+            (offsets[2] + 7, corrupted.len() as u64)
+        );
+        assert_eq!(
+            gaps[0].to_string(),
+            format!(
+                "bytes {}..{} (7 bytes skipped): Binary format parsing error: Invalid magic number",
+                offsets[1],
+                offsets[1] + 7
+            )
+        );
+    }
+
+    #[test]
+    fn test_recover_records_inside_truncated_record() {
+        let records = (1..=5)
+            .map(|tx_id| {
+                let mut record = crate::tests::get_data_to_write().remove(0);
+                record.set_tx_id(tx_id);
+                record
+            })
+            .collect::<Vec<_>>();
+        let encoded = records
+            .iter()
+            .map(|record| {
+                let mut buffer = vec![];
+                record.to_bin(&mut buffer).unwrap();
+                buffer
+            })
+            .collect::<Vec<_>>();
+
+        // Запись 1, мусор, запись 2, оборванная запись 3, целая запись 4, оборванная запись 5:
+        // RECORD_SIZE записи 3 захватывает начало записи 4.
+        let mut data = encoded[0].clone();
+        data.extend_from_slice(&[0xAB; 9]);
+        data.extend_from_slice(&encoded[1]);
+        let truncated3 = data.len() as u64;
+        data.extend_from_slice(&encoded[2][..20]);
+        let start4 = data.len() as u64;
+        data.extend_from_slice(&encoded[3]);
+        let truncated5 = data.len() as u64;
+        data.extend_from_slice(&encoded[4][..20]);
+
+        let (recovered, gaps) = YPBankBin::recover(data.as_slice());
+
+        assert_eq!(
+            recovered,
+            vec![records[0].clone(), records[1].clone(), records[3].clone()]
+        );
+        assert_eq!(
+            gaps.iter()
+                .map(|gap| (gap.start, gap.end))
+                .collect::<Vec<_>>(),
+            vec![
+                (encoded[0].len() as u64, encoded[0].len() as u64 + 9),
+                (truncated3, start4),
+                (truncated5, data.len() as u64)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recover_invalid_header() {
+        let data = [
+            0x59, 0x50, 0x42, 0x43, // MAGIC "YPBC"
+            0x00, 0x00, 0x00, 0x09, // VERSION
+            0x00, 0x00,
+        ];
+
+        let (records, gaps) = YPBankBin::recover(data.as_slice());
+
+        assert!(records.is_empty());
+        assert_eq!((gaps[0].start, gaps[0].end), (0, data.len() as u64));
+    }
+
+    #[test]
+    fn test_bin_container_wide_amounts() {
+        let records = crate::tests::get_wide_data_to_write();
+
+        let mut buffer = vec![];
+        YPBankBin {
+            records: records.clone(),
+        }
+        .write_container_to(&mut buffer)
+        .unwrap();
+
+        assert_eq!(buffer[4..8], [0x00, 0x00, 0x00, 0x02]); // VERSION
+
+        let data = YPBankBin::read_from(&mut buffer.as_slice()).unwrap();
+
+        assert_eq!(data.records, records);
+    }
+
+    #[test]
+    fn test_read_from_bin_container_truncated() {
+        let mut buffer = vec![];
+        YPBankBin {
+            records: crate::tests::get_data_to_write(),
+        }
+        .write_container_to(&mut buffer)
+        .unwrap();
+        buffer.truncate(buffer.len() - 10);
+
+        let result = YPBankBin::read_from(&mut buffer.as_slice()).unwrap_err();
+
+        assert_eq!(
+            result.to_string(),
+            "Data is truncated: expected 3 records (212 bytes), found 202 bytes (at record 3, byte 165)"
+        );
+    }
+
+    #[test]
+    fn test_read_from_bin_container_unsupported_version() {
+        let data = [
+            0x59, 0x50, 0x42, 0x43, // MAGIC "YPBC"
+            0x00, 0x00, 0x00, 0x03, // VERSION
+        ];
+
+        let result = YPBankBin::read_from(&mut data.as_slice()).unwrap_err();
+
+        assert!(matches!(
+            result,
+            ReadError::FromBin(ParseRecordFromBinError::UnsupportedContainerVersion(3))
+        ));
+    }
 }
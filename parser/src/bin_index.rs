@@ -0,0 +1,220 @@
+//! Модуль индекса для произвольного доступа к записям в бинарном формате.
+
+use super::YPBank;
+use super::bin_format::YPBankBin;
+use super::errors::{ReadError, WriteError};
+use super::record::Record;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+
+/// Индекс записей в бинарном формате, сопоставляющий TX_ID смещению записи в источнике.
+///
+/// Позволяет считывать отдельные записи из источника, поддерживающего [`Seek`],
+/// без просмотра его целиком.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct YPBankBinIndex {
+    /// Смещения записей в байтах от начала источника по их TX_ID.
+    offsets: BTreeMap<u64, u64>,
+}
+
+impl YPBankBinIndex {
+    /// MAGIC сериализованного индекса.
+    const MAGIC: [u8; 4] = [0x59, 0x50, 0x42, 0x49];
+
+    /// Построить индекс по источнику данных в бинарном формате.
+    ///
+    /// Для повторяющихся TX_ID сохраняется смещение первой записи.
+    pub fn build<R: Read>(r: R) -> Result<Self, ReadError> {
+        let mut offsets = BTreeMap::new();
+        let mut iter = YPBankBin::iter_from(r);
+
+        while let Some(result) = iter.next_with_offset() {
+            let (offset, record) = result?;
+
+            offsets.entry(record.tx_id()).or_insert(offset);
+        }
+
+        Ok(Self { offsets })
+    }
+
+    /// Количество проиндексированных записей.
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Признак пустого индекса.
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    /// Получить смещение записи с указанным TX_ID, если она есть в индексе.
+    pub fn offset_of(&self, tx_id: u64) -> Option<u64> {
+        self.offsets.get(&tx_id).copied()
+    }
+
+    /// Считать запись о транзакции, начинающуюся с указанного смещения в источнике.
+    pub fn read_record_at<R: Read + Seek>(r: &mut R, offset: u64) -> Result<Record, ReadError> {
+        r.seek(SeekFrom::Start(offset))?;
+
+        Ok(Record::from_bin(&mut BufReader::new(r))?)
+    }
+
+    /// Найти запись о транзакции с указанным TX_ID в источнике, по которому построен индекс.
+    ///
+    /// Возвращает `None`, если TX_ID отсутствует в индексе.
+    pub fn find_by_tx_id<R: Read + Seek>(
+        &self,
+        r: &mut R,
+        tx_id: u64,
+    ) -> Result<Option<Record>, ReadError> {
+        let Some(offset) = self.offset_of(tx_id) else {
+            return Ok(None);
+        };
+
+        let record = Self::read_record_at(r, offset)?;
+
+        if record.tx_id() != tx_id {
+            return Err(ReadError::InvalidIndex(format!(
+                "record at byte {offset} has TX_ID {}, expected {tx_id}",
+                record.tx_id()
+            )));
+        }
+
+        Ok(Some(record))
+    }
+
+    /// Считать сериализованный индекс.
+    ///
+    /// Индекс имеет следующую структуру (все числа в порядке big-endian):
+    ///
+    /// MAGIC (4 байта) | COUNT (8 байт) | COUNT пар TX_ID (8 байт) и OFFSET (8 байт)
+    pub fn read_from<R: Read>(r: &mut R) -> Result<Self, ReadError> {
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+
+        if magic != Self::MAGIC {
+            return Err(ReadError::InvalidIndex("invalid magic number".to_string()));
+        }
+
+        let count = r.read_u64::<BigEndian>()?;
+
+        let mut offsets = BTreeMap::new();
+
+        for _ in 0..count {
+            let tx_id = r.read_u64::<BigEndian>()?;
+            let offset = r.read_u64::<BigEndian>()?;
+
+            match offsets.entry(tx_id) {
+                Entry::Vacant(entry) => {
+                    entry.insert(offset);
+                }
+                Entry::Occupied(_) => {
+                    return Err(ReadError::InvalidIndex(format!("duplicate TX_ID {tx_id}")));
+                }
+            }
+        }
+
+        Ok(Self { offsets })
+    }
+
+    /// Записать сериализованный индекс.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> Result<(), WriteError> {
+        w.write_all(&Self::MAGIC)?;
+        w.write_u64::<BigEndian>(self.offsets.len() as u64)?;
+
+        for (&tx_id, &offset) in &self.offsets {
+            w.write_u64::<BigEndian>(tx_id)?;
+            w.write_u64::<BigEndian>(offset)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn get_bin_data(container: bool) -> Vec<u8> {
+        let data = YPBankBin {
+            records: crate::tests::get_data_to_write(),
+        };
+
+        let mut buffer = vec![];
+
+        if container {
+            data.write_container_to(&mut buffer).unwrap();
+        } else {
+            data.write_to(&mut buffer).unwrap();
+        }
+
+        buffer
+    }
+
+    #[test]
+    fn test_build_index() {
+        let index = YPBankBinIndex::build(get_bin_data(false).as_slice()).unwrap();
+
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.offset_of(1234567890123456), Some(0));
+        assert_eq!(index.offset_of(2312321321321321), Some(72));
+        assert_eq!(index.offset_of(3213213213213213), Some(141));
+        assert_eq!(index.offset_of(1), None);
+    }
+
+    #[test]
+    fn test_find_by_tx_id() {
+        for container in [false, true] {
+            let mut cursor = Cursor::new(get_bin_data(container));
+            let index = YPBankBinIndex::build(&mut cursor).unwrap();
+
+            for record in crate::tests::get_data_to_write().into_iter().rev() {
+                let found = index.find_by_tx_id(&mut cursor, record.tx_id()).unwrap();
+
+                assert_eq!(found, Some(record));
+            }
+
+            assert_eq!(index.find_by_tx_id(&mut cursor, 1).unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn test_find_by_tx_id_stale_index() {
+        let index = YPBankBinIndex {
+            offsets: BTreeMap::from([(1, 0)]),
+        };
+
+        let result = index
+            .find_by_tx_id(&mut Cursor::new(get_bin_data(false)), 1)
+            .unwrap_err();
+
+        assert_eq!(
+            result.to_string(),
+            "Invalid index: record at byte 0 has TX_ID 1234567890123456, expected 1"
+        );
+    }
+
+    #[test]
+    fn test_index_round_trip() {
+        let index = YPBankBinIndex::build(get_bin_data(false).as_slice()).unwrap();
+
+        let mut buffer = vec![];
+        index.write_to(&mut buffer).unwrap();
+
+        assert_eq!(buffer.len(), 4 + 8 + 3 * 16);
+
+        let result = YPBankBinIndex::read_from(&mut buffer.as_slice()).unwrap();
+
+        assert_eq!(result, index);
+    }
+
+    #[test]
+    fn test_read_index_invalid_magic() {
+        let result = YPBankBinIndex::read_from(&mut [0u8; 12].as_slice()).unwrap_err();
+
+        assert!(matches!(result, ReadError::InvalidIndex(_)));
+    }
+}
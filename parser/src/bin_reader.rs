@@ -0,0 +1,392 @@
+//! Модуль произвольного доступа к записям в бинарном формате по их порядковому номеру.
+
+use super::bin_format::YPBankBin;
+use super::errors::{Position, ReadError};
+use super::options::{ParseContext, SizeLimits};
+use super::record::Record;
+use super::record::errors::ParseRecordFromBinError;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::ops::Range;
+
+/// Чтение записей в бинарном формате по их порядковому номеру из источника,
+/// поддерживающего [`Seek`].
+///
+/// Записи, предшествующие запрошенной, пропускаются по их RECORD_SIZE без разбора полей.
+/// Смещения каждой [`BinReader::CHECKPOINT_INTERVAL`]-й записи запоминаются, поэтому
+/// повторное обращение к ранее пройденным записям не требует просмотра источника с начала.
+/// Подходит для выборки и постраничного просмотра больших файлов.
+#[derive(Debug)]
+pub struct BinReader<R> {
+    /// Источник данных.
+    inner: R,
+
+    /// Смещение конца данных записей.
+    end: u64,
+
+    /// Смещения записей с номерами, кратными [`BinReader::CHECKPOINT_INTERVAL`].
+    checkpoints: Vec<u64>,
+
+    /// Номер (начиная с 0) и смещение записи, до которой дошел пропуск записей.
+    cursor: (usize, u64),
+
+    /// Количество записей, если источник просмотрен до конца.
+    count: Option<usize>,
+}
+
+impl<R: Read + Seek> BinReader<R> {
+    /// Интервал номеров записей, смещения которых запоминаются.
+    pub const CHECKPOINT_INTERVAL: usize = 1024;
+
+    /// Создание чтения записей из указанного источника.
+    ///
+    /// Если источник начинается с заголовка контейнера, записями считаются только объявленные
+    /// в нем данные.
+    pub fn new(mut inner: R) -> Result<Self, ReadError> {
+        let len = inner.seek(SeekFrom::End(0))?;
+        inner.seek(SeekFrom::Start(0))?;
+
+        let mut magic = vec![];
+        inner
+            .by_ref()
+            .take(YPBankBin::CONTAINER_MAGIC.len() as u64)
+            .read_to_end(&mut magic)?;
+
+        let (start, end) = if magic == YPBankBin::CONTAINER_MAGIC {
+            let declared = YPBankBin::read_header_fields(&mut inner)?;
+            let start = YPBankBin::CONTAINER_HEADER_LEN as u64;
+
+            (start, len.min(start.saturating_add(declared.payload_len)))
+        } else {
+            (0, len)
+        };
+
+        Ok(Self {
+            inner,
+            end,
+            checkpoints: vec![start],
+            cursor: (0, start),
+            count: None,
+        })
+    }
+
+    /// Перейти к записи с указанным номером (начиная с 0), пропустив предшествующие записи.
+    ///
+    /// Возвращает смещение записи или `None`, если в источнике меньше записей.
+    pub fn seek_to_record(&mut self, n: usize) -> Result<Option<u64>, ReadError> {
+        if self.cursor.0 > n {
+            let checkpoint = n / Self::CHECKPOINT_INTERVAL;
+
+            self.cursor = (
+                checkpoint * Self::CHECKPOINT_INTERVAL,
+                self.checkpoints[checkpoint],
+            );
+        }
+
+        while self.cursor.0 < n {
+            if !self.skip_record()? {
+                return Ok(None);
+            }
+        }
+
+        let offset = self.cursor.1;
+
+        if offset >= self.end {
+            self.count = Some(n);
+
+            return Ok(None);
+        }
+
+        self.inner.seek(SeekFrom::Start(offset))?;
+
+        Ok(Some(offset))
+    }
+
+    /// Считать запись с указанным номером (начиная с 0).
+    ///
+    /// Возвращает `None`, если в источнике меньше записей.
+    pub fn nth_record(&mut self, n: usize) -> Result<Option<Record>, ReadError> {
+        Ok(self.read_range(n..n + 1)?.pop())
+    }
+
+    /// Считать записи с номерами (начиная с 0) из указанного диапазона.
+    ///
+    /// Если в источнике меньше записей, возвращаются имеющиеся записи диапазона.
+    pub fn read_range(&mut self, range: Range<usize>) -> Result<Vec<Record>, ReadError> {
+        if range.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let Some(offset) = self.seek_to_record(range.start)? else {
+            return Ok(vec![]);
+        };
+
+        Ok(self.read_records_at(range.start, offset, range.len())?.0)
+    }
+
+    /// Считать не более `limit` записей, начиная с записи с номером `index` (начиная с 0),
+    /// расположенной по смещению `offset`.
+    ///
+    /// Возвращает считанные записи и смещение следующей за ними записи.
+    pub(crate) fn read_records_at(
+        &mut self,
+        index: usize,
+        mut offset: u64,
+        limit: usize,
+    ) -> Result<(Vec<Record>, u64), ReadError> {
+        let start = offset;
+        self.inner.seek(SeekFrom::Start(start))?;
+
+        let mut reader = CountingReader {
+            inner: BufReader::new(self.inner.by_ref().take(self.end.saturating_sub(start))),
+            consumed: 0,
+        };
+        let mut records = Vec::with_capacity(limit.min(Self::CHECKPOINT_INTERVAL));
+
+        for index in index..index + limit {
+            if reader.fill_buf()?.is_empty() {
+                break;
+            }
+
+            let record = Record::from_bin_at(&mut reader, index + 1, &ParseContext::default())
+                .map_err(|e| ReadError::At {
+                    position: Position {
+                        record: index + 1,
+                        line: None,
+                        offset,
+                    },
+                    source: Box::new(e.into()),
+                })?;
+
+            records.push(record);
+            offset = start + reader.consumed;
+        }
+
+        Ok((records, offset))
+    }
+
+    /// Смещение конца данных записей.
+    pub(crate) fn end(&self) -> u64 {
+        self.end
+    }
+
+    /// Количество записей в источнике.
+    ///
+    /// При первом вызове источник просматривается до конца без разбора полей записей.
+    pub fn count(&mut self) -> Result<usize, ReadError> {
+        if let Some(count) = self.count {
+            return Ok(count);
+        }
+
+        while self.skip_record()? {}
+
+        Ok(self.cursor.0)
+    }
+
+    /// Получить источник данных.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Пропустить запись, на которой остановился пропуск записей, по ее RECORD_SIZE.
+    ///
+    /// Возвращает `false`, если записей больше нет.
+    fn skip_record(&mut self) -> Result<bool, ReadError> {
+        let (index, offset) = self.cursor;
+
+        if offset >= self.end {
+            self.count = Some(index);
+
+            return Ok(false);
+        }
+
+        let position = Position {
+            record: index + 1,
+            line: None,
+            offset,
+        };
+        let at = |e: ParseRecordFromBinError| ReadError::At {
+            position,
+            source: Box::new(e.into()),
+        };
+
+        self.inner.seek(SeekFrom::Start(offset))?;
+
+        let mut header = Vec::with_capacity(Record::BINARY_HEADER_SIZE);
+        self.inner
+            .by_ref()
+            .take((Record::BINARY_HEADER_SIZE as u64).min(self.end - offset))
+            .read_to_end(&mut header)?;
+
+        let record_size =
+            Record::check_bin_header(&header, index + 1, SizeLimits::DEFAULT.max_record_size)
+                .map_err(at)?;
+        let len = (Record::BINARY_HEADER_SIZE + record_size as usize) as u64;
+
+        if offset + len > self.end {
+            return Err(at(ParseRecordFromBinError::UnexpectedEof {
+                record_index: index + 1,
+                expected: len as usize,
+                got: (self.end - offset) as usize,
+            }));
+        }
+
+        self.cursor = (index + 1, offset + len);
+
+        if self.cursor.0 == self.checkpoints.len() * Self::CHECKPOINT_INTERVAL {
+            self.checkpoints.push(self.cursor.1);
+        }
+
+        Ok(true)
+    }
+}
+
+/// Буферизованное чтение с подсчетом считанных байтов.
+struct CountingReader<B> {
+    /// Источник данных.
+    inner: B,
+
+    /// Количество считанных байтов.
+    consumed: u64,
+}
+
+/// Реализация трейта [`Read`] для [`CountingReader`].
+impl<B: BufRead> Read for CountingReader<B> {
+    /// Реализация метода [`Read::read`] для [`CountingReader`].
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.consumed += n as u64;
+
+        Ok(n)
+    }
+}
+
+/// Реализация трейта [`BufRead`] для [`CountingReader`].
+impl<B: BufRead> BufRead for CountingReader<B> {
+    /// Реализация метода [`BufRead::fill_buf`] для [`CountingReader`].
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    /// Реализация метода [`BufRead::consume`] для [`CountingReader`].
+    fn consume(&mut self, amount: usize) {
+        self.inner.consume(amount);
+        self.consumed += amount as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::YPBank;
+    use rstest::rstest;
+    use std::io::Cursor;
+
+    fn get_bin_data(records: Vec<Record>, container: bool) -> Cursor<Vec<u8>> {
+        let data = YPBankBin::from_records(records);
+        let mut buffer = vec![];
+
+        if container {
+            data.write_container_to(&mut buffer).unwrap();
+        } else {
+            data.write_to(&mut buffer).unwrap();
+        }
+
+        Cursor::new(buffer)
+    }
+
+    fn get_many_records(count: u64) -> Vec<Record> {
+        let template = crate::tests::get_data_to_write().remove(0);
+
+        (0..count)
+            .map(|tx_id| {
+                let mut record = template.clone();
+                record.set_tx_id(tx_id);
+                record
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case(false)]
+    #[case(true)]
+    fn test_read_range(#[case] container: bool) {
+        let records = crate::tests::get_data_to_write();
+        let mut reader = BinReader::new(get_bin_data(records.clone(), container)).unwrap();
+
+        assert_eq!(reader.read_range(1..3).unwrap(), records[1..3]);
+        assert_eq!(reader.read_range(0..1).unwrap(), records[..1]);
+        assert_eq!(reader.read_range(2..10).unwrap(), records[2..]);
+        assert!(reader.read_range(3..5).unwrap().is_empty());
+        assert_eq!(reader.count().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_seek_to_record() {
+        let mut reader =
+            BinReader::new(get_bin_data(crate::tests::get_data_to_write(), false)).unwrap();
+
+        assert_eq!(reader.seek_to_record(1).unwrap(), Some(72));
+        assert_eq!(reader.seek_to_record(0).unwrap(), Some(0));
+        assert_eq!(reader.seek_to_record(3).unwrap(), None);
+    }
+
+    #[test]
+    fn test_nth_record_checkpoints() {
+        let count = 2 * BinReader::<Cursor<Vec<u8>>>::CHECKPOINT_INTERVAL + 10;
+        let mut reader =
+            BinReader::new(get_bin_data(get_many_records(count as u64), true)).unwrap();
+
+        for n in [
+            count - 1,
+            5,
+            BinReader::<Cursor<Vec<u8>>>::CHECKPOINT_INTERVAL + 3,
+            0,
+        ] {
+            assert_eq!(reader.nth_record(n).unwrap().unwrap().tx_id(), n as u64);
+        }
+
+        assert!(reader.nth_record(count).unwrap().is_none());
+        assert_eq!(reader.count().unwrap(), count);
+        assert_eq!(reader.checkpoints.len(), 3);
+    }
+
+    #[test]
+    fn test_count_truncated() {
+        let mut data = get_bin_data(crate::tests::get_data_to_write(), false).into_inner();
+        data.truncate(data.len() - 1);
+
+        let mut reader = BinReader::new(Cursor::new(data)).unwrap();
+
+        assert_eq!(reader.read_range(0..2).unwrap().len(), 2);
+
+        let error = reader.count().unwrap_err();
+
+        assert!(error.is_truncated());
+        assert_eq!(
+            error.position(),
+            Some(Position {
+                record: 3,
+                line: None,
+                offset: 141
+            })
+        );
+    }
+
+    #[test]
+    fn test_read_range_invalid_record() {
+        let mut data = get_bin_data(crate::tests::get_data_to_write(), false).into_inner();
+        data[88] = 42;
+
+        let mut reader = BinReader::new(Cursor::new(data)).unwrap();
+
+        assert_eq!(reader.count().unwrap(), 3);
+        assert_eq!(
+            reader.read_range(0..3).unwrap_err().position(),
+            Some(Position {
+                record: 2,
+                line: None,
+                offset: 72
+            })
+        );
+    }
+}
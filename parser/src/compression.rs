@@ -0,0 +1,229 @@
+//! Модуль прозрачной распаковки и сжатия потоков данных.
+//!
+//! Поддержка gzip и zstd включается признаками крейта `gzip` и `zstd` соответственно.
+
+use super::PrefixedReader;
+use super::errors::FormatError;
+use std::io::{self, Cursor, Read, Write};
+
+/// MAGIC потока, сжатого gzip.
+#[cfg(feature = "gzip")]
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// MAGIC потока, сжатого zstd.
+#[cfg(feature = "zstd")]
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Способ сжатия данных.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Compression {
+    /// Без сжатия.
+    #[default]
+    None,
+
+    /// Сжатие gzip.
+    #[cfg(feature = "gzip")]
+    Gzip,
+
+    /// Сжатие zstd.
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+/// Реализация трейта [`TryFrom<&str>`] для [`Compression`].
+impl TryFrom<&str> for Compression {
+    /// Ошибка определения способа сжатия.
+    type Error = FormatError;
+
+    /// Реализация метода [`TryFrom<&str>::try_from`] для [`Compression`].
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "none" => Ok(Self::None),
+            #[cfg(feature = "gzip")]
+            "gzip" | "gz" => Ok(Self::Gzip),
+            #[cfg(feature = "zstd")]
+            "zstd" | "zst" => Ok(Self::Zstd),
+            _ => Err(FormatError::UnsupportedCompression(s.to_string())),
+        }
+    }
+}
+
+impl Compression {
+    /// Максимальная длина MAGIC поддерживаемых способов сжатия.
+    const MAGIC_LEN: u64 = 4;
+
+    /// Определить способ сжатия по начальному фрагменту потока.
+    fn detect(prefix: &[u8]) -> Self {
+        #[cfg(feature = "gzip")]
+        if prefix.starts_with(&GZIP_MAGIC) {
+            return Self::Gzip;
+        }
+
+        #[cfg(feature = "zstd")]
+        if prefix.starts_with(&ZSTD_MAGIC) {
+            return Self::Zstd;
+        }
+
+        let _ = prefix;
+
+        Self::None
+    }
+
+    /// Обернуть назначение, сжимающее записываемые в него данные указанным способом.
+    pub fn encoder<W: Write>(self, w: W) -> io::Result<Encoder<W>> {
+        match self {
+            Self::None => Ok(Encoder::None(w)),
+            #[cfg(feature = "gzip")]
+            Self::Gzip => Ok(Encoder::Gzip(flate2::write::GzEncoder::new(
+                w,
+                flate2::Compression::default(),
+            ))),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => Ok(Encoder::Zstd(zstd::stream::write::Encoder::new(w, 0)?)),
+        }
+    }
+}
+
+/// Назначение, сжимающее записываемые данные.
+///
+/// Для записи завершающих данных сжатого потока необходимо вызвать [`Encoder::finish`].
+pub enum Encoder<W: Write> {
+    /// Назначение без сжатия.
+    None(W),
+
+    /// Назначение со сжатием gzip.
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzEncoder<W>),
+
+    /// Назначение со сжатием zstd.
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::write::Encoder<'static, W>),
+}
+
+impl<W: Write> Encoder<W> {
+    /// Завершить сжатый поток и вернуть исходное назначение.
+    pub fn finish(self) -> io::Result<W> {
+        match self {
+            Self::None(mut w) => {
+                w.flush()?;
+                Ok(w)
+            }
+            #[cfg(feature = "gzip")]
+            Self::Gzip(encoder) => encoder.finish(),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(encoder) => encoder.finish(),
+        }
+    }
+}
+
+/// Реализация трейта [`Write`] для [`Encoder`].
+impl<W: Write> Write for Encoder<W> {
+    /// Реализация метода [`Write::write`] для [`Encoder`].
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::None(w) => w.write(buf),
+            #[cfg(feature = "gzip")]
+            Self::Gzip(encoder) => encoder.write(buf),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    /// Реализация метода [`Write::flush`] для [`Encoder`].
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::None(w) => w.flush(),
+            #[cfg(feature = "gzip")]
+            Self::Gzip(encoder) => encoder.flush(),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Обернуть источник, прозрачно распаковывающий данные, если они сжаты поддерживаемым способом.
+///
+/// Способ сжатия определяется по MAGIC в начале потока; несжатые данные возвращаются как есть.
+pub fn decompress<'a, R: Read + 'a>(r: R) -> io::Result<Box<dyn Read + 'a>> {
+    Ok(decompress_detected(r)?.1)
+}
+
+/// Обернуть источник, прозрачно распаковывающий данные (см. [`decompress`]), и вернуть
+/// определенный способ сжатия.
+pub fn decompress_detected<'a, R: Read + 'a>(
+    mut r: R,
+) -> io::Result<(Compression, Box<dyn Read + 'a>)> {
+    let mut prefix = vec![];
+    r.by_ref()
+        .take(Compression::MAGIC_LEN)
+        .read_to_end(&mut prefix)?;
+
+    let compression = Compression::detect(&prefix);
+    let r: PrefixedReader<R> = Cursor::new(prefix).chain(r);
+
+    let r: Box<dyn Read + 'a> = match compression {
+        Compression::None => Box::new(r),
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(r)),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(r)?),
+    };
+
+    Ok((compression, r))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::YPBankImpl;
+    use rstest::rstest;
+
+    fn compress(compression: Compression, data: &[u8]) -> Vec<u8> {
+        let mut encoder = compression.encoder(vec![]).unwrap();
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[rstest]
+    #[case(Compression::None)]
+    #[cfg_attr(feature = "gzip", case(Compression::Gzip))]
+    #[cfg_attr(feature = "zstd", case(Compression::Zstd))]
+    fn test_roundtrip(#[case] compression: Compression) {
+        let format = YPBankImpl::Csv;
+
+        let mut data = vec![];
+        format
+            .write_to(crate::tests::get_data_to_write(), &mut data)
+            .unwrap();
+
+        let compressed = compress(compression, &data);
+        assert_eq!(compressed == data, compression == Compression::None);
+
+        let (detected, mut r) = decompress_detected(compressed.as_slice()).unwrap();
+        assert_eq!(detected, compression);
+
+        assert_eq!(
+            format.read_from(&mut r).unwrap(),
+            crate::tests::get_data_to_write()
+        );
+    }
+
+    #[rstest]
+    #[case(b"")]
+    #[case(b"\x1f")]
+    fn test_decompress_short(#[case] data: &[u8]) {
+        let mut result = vec![];
+        decompress(data).unwrap().read_to_end(&mut result).unwrap();
+
+        assert_eq!(result, data);
+    }
+
+    #[test]
+    fn test_try_from() {
+        assert_eq!(Compression::try_from("none").unwrap(), Compression::None);
+        assert_eq!(
+            Compression::try_from("lz4").unwrap_err().to_string(),
+            "Unsupported compression: lz4"
+        );
+    }
+}
@@ -1,8 +1,13 @@
 use super::YPBank;
-use super::errors::{ReadError, WriteError};
-use super::record::Record;
+use super::errors::{CsvColumnMappingError, ReadError, WriteError};
+use super::iter::{RecordIter, recover_nothing};
+use super::options::ParseContext;
+use super::record::{Record, RecordKey};
+use super::writer::RecordWriter;
 use crate::record::errors::ParseRecordFromCsvError;
-use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::collections::HashMap;
+use std::io::{BufRead, BufWriter, Read, Write};
+use std::sync::{Arc, OnceLock};
 
 #[derive(Debug)]
 pub struct YPBankCsv {
@@ -10,85 +15,471 @@ pub struct YPBankCsv {
     pub records: Vec<Record>,
 }
 
-impl YPBankCsv {
-    /// Подготовить заголовок для CSV-формата с именами полей.
+/// Столбец CSV-таблицы.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum CsvColumn {
+    /// Поле записи с указанным ключом.
+    Field(RecordKey),
+
+    /// Дополнительное поле записи с указанным ключом (см. [`CsvOptions::metadata_columns`]).
+    Metadata(String),
+}
+
+/// Реализация трейта [`std::fmt::Display`] для [`CsvColumn`].
+impl std::fmt::Display for CsvColumn {
+    /// Реализация метода [`std::fmt::Display::fmt`] для [`CsvColumn`].
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Field(key) => write!(f, "{key}"),
+            Self::Metadata(key) => write!(f, "{key}"),
+        }
+    }
+}
+
+/// Соответствие имен столбцов CSV-таблицы полям записи.
+///
+/// Используется при нестрогой проверке заголовка (см. [`CsvOptions::tolerant_header`]) для
+/// столбцов, имена которых отличаются от ключей полей, например, `txid` вместо `TX_ID`.
+/// Имена столбцов сравниваются без учета регистра.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CsvColumnMapping {
+    /// Ключи полей по именам столбцов в нижнем регистре.
+    columns: HashMap<String, RecordKey>,
+}
+
+impl CsvColumnMapping {
+    /// Создание пустого соответствия.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Добавить соответствие столбца с указанным именем полю записи.
+    pub fn insert(&mut self, column: &str, key: RecordKey) -> &mut Self {
+        self.columns.insert(column.trim().to_lowercase(), key);
+        self
+    }
+
+    /// Получить ключ поля записи, соответствующего столбцу с указанным именем.
     ///
-    /// Заголовок соответствует следующей строке:
+    /// Столбец, отсутствующий в соответствии, сопоставляется полю с тем же ключом
+    /// без учета регистра.
+    pub fn key(&self, column: &str) -> Option<RecordKey> {
+        let column = column.trim();
+
+        self.columns
+            .get(&column.to_lowercase())
+            .copied()
+            .or_else(|| {
+                Record::EXPECTED_KEYS
+                    .into_iter()
+                    .chain(Record::OPTIONAL_KEYS)
+                    .find(|key| key.to_string().eq_ignore_ascii_case(column))
+            })
+    }
+
+    /// Признак отсутствия заданных соответствий.
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+}
+
+/// Реализация трейта [`TryFrom<&str>`] для [`CsvColumnMapping`].
+///
+/// Каждая непустая строка описывает соответствие в виде `COLUMN=KEY`, например, `txid=TX_ID`;
+/// строки, начинающиеся с `#`, игнорируются.
+impl TryFrom<&str> for CsvColumnMapping {
+    /// Ошибка описания соответствия.
+    type Error = CsvColumnMappingError;
+
+    /// Реализация метода [`TryFrom<&str>::try_from`] для [`CsvColumnMapping`].
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        let mut mapping = Self::new();
+
+        for (index, line) in s.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (column, key) = line
+                .split_once('=')
+                .filter(|(column, _)| !column.trim().is_empty())
+                .ok_or_else(|| CsvColumnMappingError::InvalidLine {
+                    line: index + 1,
+                    content: line.to_string(),
+                })?;
+
+            mapping.insert(column, RecordKey::try_from(key.trim())?);
+        }
+
+        Ok(mapping)
+    }
+}
+
+/// Параметры CSV-формата.
+///
+/// Разделитель полей и символ кавычки должны различаться и не могут быть символами перевода
+/// строки.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvOptions {
+    /// Разделитель полей.
+    pub delimiter: char,
+
+    /// Символ кавычки, в которую заключается описание транзакции.
+    pub quote_char: char,
+
+    /// Признак записи строки заголовка с именами полей.
     ///
-    /// TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION
-    fn prepare_header() -> String {
-        Record::EXPECTED_KEYS
+    /// При чтении заголовок ожидается всегда.
+    pub write_header: bool,
+
+    /// Признак нестрогой проверки заголовка при чтении.
+    ///
+    /// Столбцы могут следовать в произвольном порядке, а их имена сравниваются с ключами полей
+    /// без учета регистра и с учетом [`CsvOptions::column_mapping`]. Иначе заголовок должен
+    /// в точности совпадать с записываемым.
+    pub tolerant_header: bool,
+
+    /// Соответствие имен столбцов полям записи при нестрогой проверке заголовка.
+    pub column_mapping: CsvColumnMapping,
+
+    /// Ключи дополнительных полей записи (см. [`Record::metadata`]), записываемых
+    /// и считываемых дополнительными столбцами таблицы с теми же именами.
+    ///
+    /// Столбцы следуют за полями записи в указанном порядке; при нестрогой проверке заголовка
+    /// они могут располагаться в произвольном порядке. Дополнительные поля с другими ключами
+    /// в CSV-формате не записываются. По умолчанию дополнительных столбцов нет.
+    pub metadata_columns: Vec<String>,
+}
+
+/// Реализация трейта [`Default`] для [`CsvOptions`].
+impl Default for CsvOptions {
+    /// Параметры по умолчанию: разделитель `,`, кавычка `"`, заголовок записывается
+    /// и проверяется строго.
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            quote_char: '"',
+            write_header: true,
+            tolerant_header: false,
+            column_mapping: CsvColumnMapping::default(),
+            metadata_columns: vec![],
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Параметры формата TSV (разделитель полей — символ табуляции).
+    pub fn tsv() -> Self {
+        Self {
+            delimiter: '\t',
+            ..Self::default()
+        }
+    }
+
+    /// Получить столбцы записываемой таблицы со столбцом валюты или без него.
+    pub(crate) fn columns(&self, currency: bool) -> Vec<CsvColumn> {
+        let mut columns = Record::EXPECTED_KEYS.map(CsvColumn::Field).to_vec();
+
+        if currency {
+            columns.push(CsvColumn::Field(RecordKey::Currency));
+        }
+
+        columns.extend(
+            self.metadata_columns
+                .iter()
+                .cloned()
+                .map(CsvColumn::Metadata),
+        );
+
+        columns
+    }
+
+    /// Подготовить заголовок с именами полей, разделенными разделителем полей,
+    /// со столбцом валюты или без него.
+    pub(crate) fn header(&self, currency: bool) -> String {
+        self.columns(currency)
             .iter()
-            .map(|key| key.to_string())
+            .map(|column| column.to_string())
             .collect::<Vec<_>>()
-            .join(",")
+            .join(&self.delimiter.to_string())
     }
 
-    /// Валидировать переданный заголовок для CSV-формата на соответствие ожидаемой структуре.
-    fn validate_header(header: &str) -> Result<(), ReadError> {
-        let expected_header = Self::prepare_header();
+    /// Валидировать переданный заголовок на соответствие ожидаемой структуре.
+    ///
+    /// Возвращает поля, соответствующие столбцам: столбец валюты необязателен.
+    fn validate_header(&self, header: &str) -> Result<Vec<CsvColumn>, ReadError> {
+        [false, true]
+            .into_iter()
+            .find(|&currency| header == self.header(currency))
+            .map(|currency| self.columns(currency))
+            .ok_or_else(|| {
+                ParseRecordFromCsvError::UnexpectedError("invalid header structure".to_string())
+                    .into()
+            })
+    }
+
+    /// Сопоставить столбцы заголовка полям записи при нестрогой проверке заголовка.
+    ///
+    /// Столбцы, имена которых в точности совпадают с ключами из
+    /// [`CsvOptions::metadata_columns`], соответствуют дополнительным полям.
+    fn map_columns(&self, header: &[String]) -> Result<Vec<CsvColumn>, ParseRecordFromCsvError> {
+        let mut columns = Vec::with_capacity(header.len());
+
+        for name in header {
+            let column = match self.column_mapping.key(name) {
+                Some(key) => CsvColumn::Field(key),
+                None if self.metadata_columns.contains(name) => CsvColumn::Metadata(name.clone()),
+                None => return Err(ParseRecordFromCsvError::UnknownColumn(name.clone())),
+            };
+
+            if columns.contains(&column) {
+                return Err(ParseRecordFromCsvError::DuplicateColumn(column.to_string()));
+            }
 
-        if header != expected_header {
-            Err(ParseRecordFromCsvError::UnexpectedError(
-                "invalid header structure".to_string(),
-            ))?
-        } else {
-            Ok(())
+            columns.push(column);
         }
-    }
-}
 
-impl YPBank for YPBankCsv {
-    /// Считать данные о банковских операциях в CSV формате.
-    fn read_from<R: Read>(r: &mut R) -> Result<Self, ReadError> {
-        let mut reader = BufReader::new(r);
+        if let Some(&key) = Record::EXPECTED_KEYS
+            .iter()
+            .find(|&&key| !columns.contains(&CsvColumn::Field(key)))
+        {
+            return Err(ParseRecordFromCsvError::MissingColumn(key));
+        }
 
-        let mut records: Vec<Record> = vec![];
+        Ok(columns)
+    }
+
+    /// Считать и валидировать заголовок.
+    ///
+    /// Возвращает поля, соответствующие столбцам.
+    fn read_header<B: BufRead>(&self, reader: &mut B) -> Result<Vec<CsvColumn>, ReadError> {
+        if self.tolerant_header {
+            let header = Record::read_csv_fields(reader, self)?
+                .ok_or_else(|| {
+                    ParseRecordFromCsvError::UnexpectedError("missing header".to_string())
+                })?
+                .into_iter()
+                .map(|field| field.value)
+                .collect::<Vec<_>>();
+
+            return Ok(self.map_columns(&header)?);
+        }
 
         let mut header = String::new();
         reader.read_line(&mut header)?;
 
         header = header.trim_end_matches(['\r', '\n']).to_string();
 
-        Self::validate_header(&header)?;
-
-        loop {
-            if reader.fill_buf()?.is_empty() {
-                break;
-            }
+        self.validate_header(&header)
+    }
 
-            records.push(Record::from_csv(&mut reader)?);
+    /// Считать очередную запись о банковской операции.
+    ///
+    /// Строки записи считываются из источника целиком, поэтому после ошибки чтение может быть
+    /// продолжено со следующей строки.
+    fn read_record<B: BufRead>(
+        &self,
+        reader: &mut B,
+        columns: &[CsvColumn],
+        line: &mut String,
+        context: &ParseContext,
+    ) -> Result<Option<Record>, ReadError> {
+        if reader.fill_buf()?.is_empty() {
+            return Ok(None);
         }
 
-        Ok(Self { records })
+        Ok(Some(Record::from_csv_columns(
+            reader, self, columns, line, context,
+        )?))
     }
 
-    /// Записать данные о банковских операциях в CSV формате.
-    fn write_to<W: Write>(&self, w: &mut W) -> Result<(), WriteError> {
-        let mut writer = BufWriter::new(w);
+    /// Записать заголовок с именами полей, если это требуется.
+    fn write_header<W: Write>(
+        &self,
+        writer: &mut BufWriter<W>,
+        currency: bool,
+    ) -> Result<(), WriteError> {
+        if !self.write_header {
+            return Ok(());
+        }
 
-        let header = Self::prepare_header();
         writer
-            .write_all(header.as_bytes())
+            .write_all(self.header(currency).as_bytes())
             .map_err(|e| WriteError::WriteHeaderError(e.to_string()))?;
         writer.write_all(b"\n")?;
 
+        Ok(())
+    }
+
+    /// Записать очередную запись о банковской операции.
+    ///
+    /// Перед первой записью записывается заголовок. Таблица содержит столбец валюты, если
+    /// валюта указана в первой записи: валюта последующих записей без такого столбца не может
+    /// быть записана.
+    fn write_record<W: Write>(
+        &self,
+        writer: &mut BufWriter<W>,
+        record: &Record,
+        index: usize,
+        currency_column: bool,
+    ) -> Result<(), WriteError> {
+        if index == 0 {
+            self.write_header(writer, currency_column)?;
+        }
+
+        if record.currency().is_some() && !currency_column {
+            return Err(WriteError::MissingCurrencyColumn(record.tx_id()));
+        }
+
+        record.to_csv_row(writer, self, currency_column)?;
+
+        Ok(())
+    }
+
+    /// Завершить запись данных.
+    ///
+    /// Если не было записано ни одной записи, записывается только заголовок.
+    fn finish<W: Write>(&self, writer: &mut BufWriter<W>, count: usize) -> Result<(), WriteError> {
+        if count == 0 {
+            self.write_header(writer, false)?;
+        }
+
+        Ok(())
+    }
+
+    /// Считать записи о транзакциях в CSV формате с данными параметрами.
+    pub fn read_from<R: Read>(&self, r: &mut R) -> Result<Vec<Record>, ReadError> {
+        self.iter_from(r).read_all()
+    }
+
+    /// Последовательно считывать записи о транзакциях в CSV формате с данными параметрами.
+    pub fn iter_from<R: Read>(&self, r: R) -> RecordIter<R> {
+        let columns = Arc::new(OnceLock::new());
+        let header_columns = Arc::clone(&columns);
+
+        let options = self.clone();
+        let header_options = self.clone();
+        let default_columns = self.columns(false);
+        let mut line = String::new();
+
+        RecordIter::new_with(r, move |reader, _index, context| {
+            let columns = columns.get().unwrap_or(&default_columns);
+            options.read_record(reader, columns, &mut line, context)
+        })
+        .with_header_with(move |reader| {
+            let columns = header_options.read_header(reader)?;
+            header_columns.get_or_init(|| columns);
+
+            Ok(None)
+        })
+        .with_recovery(recover_nothing)
+    }
+
+    /// Записать записи о транзакциях в CSV формате с данными параметрами.
+    pub fn write_to<W: Write>(&self, records: &[Record], w: &mut W) -> Result<(), WriteError> {
+        let mut writer = self.writer_to(w);
+
+        for record in records {
+            writer.write_record(record)?;
+        }
+
+        writer.finish()?;
+
+        Ok(())
+    }
+
+    /// Последовательно записывать записи о транзакциях в CSV формате с данными параметрами.
+    pub fn writer_to<W: Write>(&self, w: W) -> RecordWriter<W> {
+        let options = self.clone();
+        let finish_options = self.clone();
+
+        let mut currency_column = false;
+
+        RecordWriter::new_with(
+            w,
+            move |writer, record, index| {
+                if index == 0 {
+                    currency_column = record.currency().is_some();
+                }
+
+                options.write_record(writer, record, index, currency_column)
+            },
+            move |writer, count| finish_options.finish(writer, count),
+        )
+    }
+}
+
+impl YPBankCsv {
+    /// Проверить, является ли строка заголовком CSV-формата с именами полей.
+    ///
+    /// Заголовок соответствует следующей строке, к которой может быть добавлен столбец `CURRENCY`:
+    ///
+    /// TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION
+    pub(crate) fn is_header(line: &str) -> bool {
+        let options = CsvOptions::default();
+
+        line == options.header(false) || line == options.header(true)
+    }
+}
+
+impl YPBank for YPBankCsv {
+    /// Считанные записи о банковских операциях.
+    fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    /// Изменяемый список записей о банковских операциях.
+    fn records_mut(&mut self) -> &mut Vec<Record> {
+        &mut self.records
+    }
+
+    /// Создание набора из указанных записей о банковских операциях.
+    fn from_records(records: Vec<Record>) -> Self {
+        Self { records }
+    }
+
+    /// Считать данные о банковских операциях в CSV формате.
+    fn read_from<R: Read>(r: &mut R) -> Result<Self, ReadError> {
+        let records = Self::iter_from(r).collect::<Result<_, _>>()?;
+
+        Ok(Self { records })
+    }
+
+    /// Последовательно считывать данные о банковских операциях в CSV формате.
+    fn iter_from<R: Read>(r: R) -> RecordIter<R> {
+        CsvOptions::default().iter_from(r)
+    }
+
+    /// Записать данные о банковских операциях в CSV формате.
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<(), WriteError> {
+        let mut writer = Self::writer_to(w);
+
         for record in &self.records {
-            record.to_csv(&mut writer)?;
+            writer.write_record(record)?;
         }
 
+        writer.finish()?;
+
         Ok(())
     }
+
+    /// Последовательно записывать данные о банковских операциях в CSV формате.
+    fn writer_to<W: Write>(w: W) -> RecordWriter<W> {
+        CsvOptions::default().writer_to(w)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::record::Currency;
     use crate::record::errors::ParseRecordFromCsvError;
     use crate::record::status::Status;
     use crate::record::tx_type::TxType;
     use rstest::rstest;
-    use std::io::Cursor;
+    use std::io::{BufReader, Cursor};
 
     #[test]
     fn test_read_from_csv_data_specification() {
@@ -151,7 +542,7 @@ mod tests {
 
         let result = result.unwrap_err();
         matches!(
-            result,
+            result.inner(),
             ReadError::FromCsv(ParseRecordFromCsvError::UnexpectedError(_))
         );
         assert_eq!(
@@ -166,7 +557,10 @@ mod tests {
         let result = YPBankCsv::read_from(&mut cursor);
 
         let result = result.unwrap_err();
-        assert!(matches!(result, ReadError::Io(std::io::Error { .. })));
+        assert!(matches!(
+            result.inner(),
+            ReadError::Io(std::io::Error { .. })
+        ));
         assert_eq!(
             result.to_string(),
             "Read data error: stream did not contain valid UTF-8"
@@ -177,22 +571,60 @@ mod tests {
     fn test_read_from_csv_invalid_record() {
         let mut reader = BufReader::new(Cursor::new(
             br#"TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION
-"1001,DEPOSIT,0,501,50000,SUCCESS,\"Initial account funding\"""#,
+1001,DEPOSIT,0,501,50000,SUCCESS,"Initial account funding""#,
         ));
 
         let result = YPBankCsv::read_from(&mut reader);
 
         let result = result.unwrap_err();
         assert!(matches!(
-            result,
+            result.inner(),
             ReadError::FromCsv(ParseRecordFromCsvError::InvalidCountOfColumns(_))
         ));
         assert_eq!(
             result.to_string(),
-            "CSV format parsing error: Invalid count of columns: 7"
+            "CSV format parsing error: Invalid count of columns: 7 (at record 1, line 2, byte 74)"
         );
     }
 
+    #[test]
+    fn test_csv_round_trip_with_special_characters() {
+        let records = vec![
+            Record::new(
+                1,
+                TxType::Transfer,
+                501,
+                502,
+                15000,
+                1672534800000,
+                Status::Success,
+                "Payment, ref \"42\"".to_string(),
+            ),
+            Record::new(
+                2,
+                TxType::Deposit,
+                0,
+                501,
+                100,
+                1672534800001,
+                Status::Pending,
+                "Multi\nline, description".to_string(),
+            ),
+        ];
+
+        let mut cursor = Cursor::new(vec![]);
+        YPBankCsv {
+            records: records.clone(),
+        }
+        .write_to(&mut cursor)
+        .unwrap();
+
+        cursor.set_position(0);
+        let data = YPBankCsv::read_from(&mut cursor).unwrap();
+
+        assert_eq!(data.records, records);
+    }
+
     #[test]
     fn test_write_to_csv_empty_record() {
         let data = YPBankCsv { records: vec![] };
@@ -221,4 +653,355 @@ mod tests {
 "#
         );
     }
+
+    #[test]
+    fn test_csv_currency_column() {
+        let mut records = crate::tests::get_data_to_write();
+        records[0].set_currency(Some(Currency::try_from("USD").unwrap()));
+
+        let mut data = vec![];
+        YPBankCsv {
+            records: records.clone(),
+        }
+        .write_to(&mut data)
+        .unwrap();
+
+        let text = String::from_utf8(data.clone()).unwrap();
+        let mut lines = text.lines();
+        assert_eq!(
+            lines.next().unwrap(),
+            "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION,CURRENCY"
+        );
+        assert!(lines.next().unwrap().ends_with("\"Terminal deposit\",USD"));
+        assert!(lines.next().unwrap().ends_with("\"User transfer\","));
+
+        assert_eq!(
+            YPBankCsv::read_from(&mut data.as_slice()).unwrap().records,
+            records
+        );
+    }
+
+    #[test]
+    fn test_write_to_csv_currency_without_column() {
+        let mut records = crate::tests::get_data_to_write();
+        records[1].set_currency(Some(Currency::try_from("USD").unwrap()));
+
+        let result = YPBankCsv { records }.write_to(&mut vec![]).unwrap_err();
+
+        assert!(matches!(
+            result,
+            WriteError::MissingCurrencyColumn(2312321321321321)
+        ));
+    }
+
+    #[test]
+    fn test_read_from_csv_tolerant_currency() {
+        let data = "currency,tx_id,tx_type,from_user_id,to_user_id,amount,timestamp,status,description\nJPY,1,DEPOSIT,0,2,100,1,SUCCESS,\"a\"\n";
+        let options = CsvOptions {
+            tolerant_header: true,
+            ..CsvOptions::default()
+        };
+
+        let records = options.read_from(&mut data.as_bytes()).unwrap();
+
+        assert_eq!(
+            records[0].currency(),
+            Some(Currency::try_from("JPY").unwrap())
+        );
+    }
+
+    #[rstest]
+    #[case(false)]
+    #[case(true)]
+    fn test_csv_metadata_columns(#[case] tolerant_header: bool) {
+        let mut records = crate::tests::get_data_to_write();
+        records[0]
+            .metadata_mut()
+            .insert("VENDOR".to_string(), "acme".to_string());
+        records[2]
+            .metadata_mut()
+            .insert("VENDOR".to_string(), "".to_string());
+
+        let options = CsvOptions {
+            tolerant_header,
+            metadata_columns: vec!["VENDOR".to_string()],
+            ..CsvOptions::default()
+        };
+
+        let mut data = vec![];
+        options.write_to(&records, &mut data).unwrap();
+
+        let text = String::from_utf8(data.clone()).unwrap();
+        assert!(text.starts_with(
+            "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION,VENDOR\n"
+        ));
+        assert!(text.contains("\"Terminal deposit\",\"acme\"\n"));
+        assert!(text.contains("\"User transfer\",\n"));
+
+        assert_eq!(options.read_from(&mut data.as_slice()).unwrap(), records);
+    }
+
+    #[test]
+    fn test_read_from_csv_metadata_column_without_opt_in() {
+        let data =
+            "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION,VENDOR\n";
+        let options = CsvOptions {
+            tolerant_header: true,
+            ..CsvOptions::default()
+        };
+
+        let result = options.read_from(&mut data.as_bytes()).unwrap_err();
+
+        assert_eq!(
+            result.to_string(),
+            "CSV format parsing error: Unknown column: VENDOR"
+        );
+    }
+
+    #[test]
+    fn test_read_from_tsv() {
+        let data =
+            "TX_ID\tTX_TYPE\tFROM_USER_ID\tTO_USER_ID\tAMOUNT\tTIMESTAMP\tSTATUS\tDESCRIPTION
+1002\tTRANSFER\t501\t502\t15000\t1672534800000\tFAILURE\t\"Payment, invoice\t#123\"
+";
+        let records = CsvOptions::tsv().read_from(&mut data.as_bytes()).unwrap();
+
+        assert_eq!(
+            records,
+            vec![Record::new(
+                1002,
+                TxType::Transfer,
+                501,
+                502,
+                15000,
+                1672534800000,
+                Status::Failure,
+                "Payment, invoice\t#123".to_string(),
+            )]
+        );
+    }
+
+    #[test]
+    fn test_write_to_csv_custom_options() {
+        let options = CsvOptions {
+            delimiter: ';',
+            quote_char: '\'',
+            write_header: false,
+            ..CsvOptions::default()
+        };
+        let records = vec![Record::new(
+            1,
+            TxType::Deposit,
+            0,
+            2,
+            300,
+            1000,
+            Status::Success,
+            "Rent; it's paid".to_string(),
+        )];
+
+        let mut buffer = vec![];
+        options.write_to(&records, &mut buffer).unwrap();
+
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "1;DEPOSIT;0;2;300;1000;SUCCESS;'Rent; it''s paid'\n"
+        );
+    }
+
+    #[test]
+    fn test_write_to_csv_without_header_empty() {
+        let options = CsvOptions {
+            write_header: false,
+            ..CsvOptions::default()
+        };
+
+        let mut buffer = vec![];
+        options.write_to(&[], &mut buffer).unwrap();
+
+        assert!(buffer.is_empty());
+    }
+
+    #[rstest]
+    #[case(CsvOptions::tsv())]
+    #[case(CsvOptions { delimiter: ';', quote_char: '\'', ..CsvOptions::default() })]
+    fn test_csv_options_roundtrip(#[case] options: CsvOptions) {
+        let records = crate::tests::get_data_to_write();
+
+        let mut buffer = vec![];
+        options.write_to(&records, &mut buffer).unwrap();
+
+        assert_eq!(options.read_from(&mut buffer.as_slice()).unwrap(), records);
+    }
+
+    #[test]
+    fn test_read_from_tsv_comma_header() {
+        let data = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n";
+        let result = CsvOptions::tsv()
+            .read_from(&mut data.as_bytes())
+            .unwrap_err();
+
+        assert_eq!(
+            result.to_string(),
+            "CSV format parsing error: Unexpected error: invalid header structure"
+        );
+    }
+
+    fn tolerant() -> CsvOptions {
+        CsvOptions {
+            tolerant_header: true,
+            ..CsvOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_read_from_csv_tolerant_header() {
+        let data = r#"description,Amount,tx_id,TX_TYPE,"status",timestamp,to_user_id,from_user_id
+"Initial account funding",50000,1001,DEPOSIT,SUCCESS,1672531200000,501,0
+"#;
+
+        let result = tolerant().read_from(&mut data.as_bytes()).unwrap();
+
+        assert_eq!(
+            result,
+            vec![Record::new(
+                1001,
+                TxType::Deposit,
+                0,
+                501,
+                50000,
+                1672531200000,
+                Status::Success,
+                "Initial account funding".to_string(),
+            )]
+        );
+    }
+
+    #[test]
+    fn test_read_from_csv_strict_header_reordered() {
+        let data = "TX_TYPE,TX_ID,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n";
+
+        let result = YPBankCsv::read_from(&mut data.as_bytes()).unwrap_err();
+
+        assert_eq!(
+            result.to_string(),
+            "CSV format parsing error: Unexpected error: invalid header structure"
+        );
+    }
+
+    #[test]
+    fn test_read_from_csv_column_mapping() {
+        let mapping = CsvColumnMapping::try_from(
+            "# Выгрузка из учетной системы\ntxid=TX_ID\n  Kind = TX_TYPE\n\nmemo=DESCRIPTION\n",
+        )
+        .unwrap();
+        let options = CsvOptions {
+            column_mapping: mapping,
+            ..tolerant()
+        };
+        let data = "TXID,kind,from_user_id,to_user_id,amount,timestamp,status,memo
+7,TRANSFER,1,2,300,1000,PENDING,\"Mapped\"
+";
+
+        let result = options.read_from(&mut data.as_bytes()).unwrap();
+
+        assert_eq!(
+            result,
+            vec![Record::new(
+                7,
+                TxType::Transfer,
+                1,
+                2,
+                300,
+                1000,
+                Status::Pending,
+                "Mapped".to_string(),
+            )]
+        );
+    }
+
+    #[rstest]
+    #[case(
+        "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,MEMO",
+        "Unknown column: MEMO"
+    )]
+    #[case(
+        "TX_ID,tx_id,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION",
+        "Duplicate column for TX_ID"
+    )]
+    #[case(
+        "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,TIMESTAMP,STATUS,DESCRIPTION",
+        "Missing column for AMOUNT"
+    )]
+    fn test_read_from_csv_tolerant_invalid_header(#[case] header: &str, #[case] error: &str) {
+        let result = tolerant()
+            .read_from(&mut format!("{header}\n").as_bytes())
+            .unwrap_err();
+
+        assert_eq!(
+            result.to_string(),
+            format!("CSV format parsing error: {error}")
+        );
+    }
+
+    #[test]
+    fn test_read_from_csv_tolerant_empty() {
+        let result = tolerant().read_from(&mut "".as_bytes()).unwrap_err();
+
+        assert!(matches!(
+            result.inner(),
+            ReadError::FromCsv(ParseRecordFromCsvError::UnexpectedError(_))
+        ));
+    }
+
+    #[rstest]
+    #[case("txid", "Invalid mapping at line 1: `txid`: expected COLUMN=KEY")]
+    #[case(
+        "TX_ID=TX_ID\n=AMOUNT",
+        "Invalid mapping at line 2: `=AMOUNT`: expected COLUMN=KEY"
+    )]
+    #[case("txid=ID", "Invalid key: ID")]
+    fn test_csv_column_mapping_invalid(#[case] spec: &str, #[case] error: &str) {
+        assert_eq!(
+            CsvColumnMapping::try_from(spec).unwrap_err().to_string(),
+            error
+        );
+    }
+
+    #[rstest]
+    #[case(CsvOptions::default())]
+    #[case(tolerant())]
+    fn test_read_from_csv_bom_crlf(#[case] options: CsvOptions) {
+        let data = "\u{feff}TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\r\n\
+            1,DEPOSIT,0,2,300,1000,SUCCESS,\"Line\r\nbreak\"\r\n\
+            2,WITHDRAWAL,2,0,100,2000,PENDING,\"ATM\"\r\n";
+
+        let result = options.read_from(&mut data.as_bytes()).unwrap();
+
+        assert_eq!(
+            result,
+            vec![
+                Record::new(
+                    1,
+                    TxType::Deposit,
+                    0,
+                    2,
+                    300,
+                    1000,
+                    Status::Success,
+                    "Line\r\nbreak".to_string(),
+                ),
+                Record::new(
+                    2,
+                    TxType::Withdrawal,
+                    2,
+                    0,
+                    100,
+                    2000,
+                    Status::Pending,
+                    "ATM".to_string(),
+                ),
+            ]
+        );
+    }
 }
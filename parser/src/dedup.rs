@@ -0,0 +1,189 @@
+//! Модуль поиска и устранения записей о транзакциях с повторяющимися TX_ID.
+
+use super::errors::DedupError;
+use super::record::Record;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Способ устранения записей с повторяющимися TX_ID.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DedupStrategy {
+    /// Оставить первую запись с данным TX_ID.
+    #[default]
+    KeepFirst,
+
+    /// Оставить последнюю запись с данным TX_ID.
+    KeepLast,
+
+    /// Вернуть ошибку при наличии повторяющихся TX_ID.
+    Error,
+}
+
+/// Группа записей с одинаковым TX_ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Duplicate {
+    /// Повторяющийся идентификатор транзакции.
+    pub tx_id: u64,
+
+    /// Порядковые номера записей с данным TX_ID (начиная с 1).
+    pub records: Vec<usize>,
+}
+
+/// Реализация трейта [`fmt::Display`] для [`Duplicate`].
+impl fmt::Display for Duplicate {
+    /// Реализация метода [`fmt::Display::fmt`] для [`Duplicate`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let records = self
+            .records
+            .iter()
+            .map(|record| record.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        write!(f, "TX_ID {} in records {records}", self.tx_id)
+    }
+}
+
+/// Найти группы записей с одинаковым TX_ID в порядке первого появления TX_ID.
+pub(crate) fn find_duplicates(records: &[Record]) -> Vec<Duplicate> {
+    let mut groups: HashMap<u64, Vec<usize>> = HashMap::new();
+    let mut order = vec![];
+
+    for (index, record) in records.iter().enumerate() {
+        let group = groups.entry(record.tx_id()).or_default();
+
+        if group.is_empty() {
+            order.push(record.tx_id());
+        }
+
+        group.push(index + 1);
+    }
+
+    order
+        .into_iter()
+        .filter_map(|tx_id| {
+            let records = groups.remove(&tx_id)?;
+
+            (records.len() > 1).then_some(Duplicate { tx_id, records })
+        })
+        .collect()
+}
+
+/// Удалить записи с повторяющимися TX_ID согласно указанному способу.
+///
+/// Порядок оставшихся записей сохраняется. Возвращает количество удаленных записей.
+pub(crate) fn dedup_by_tx_id(
+    records: &mut Vec<Record>,
+    strategy: DedupStrategy,
+) -> Result<usize, DedupError> {
+    let duplicates = find_duplicates(records);
+
+    if duplicates.is_empty() {
+        return Ok(0);
+    }
+
+    // Порядковые номера оставляемых записей для каждого повторяющегося TX_ID.
+    let kept = duplicates
+        .into_iter()
+        .map(|duplicate| {
+            let record = match strategy {
+                DedupStrategy::KeepFirst => duplicate.records[0],
+                DedupStrategy::KeepLast => duplicate.records[duplicate.records.len() - 1],
+                DedupStrategy::Error => return Err(DedupError::DuplicateTxId(duplicate)),
+            };
+
+            Ok((duplicate.tx_id, record))
+        })
+        .collect::<Result<HashMap<_, _>, _>>()?;
+
+    let count = records.len();
+    let mut number = 0;
+
+    records.retain(|record| {
+        number += 1;
+
+        kept.get(&record.tx_id())
+            .is_none_or(|&kept_number| kept_number == number)
+    });
+
+    Ok(count - records.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::{Status, TxType};
+    use rstest::rstest;
+
+    fn get_record(tx_id: u64, amount: i128) -> Record {
+        Record::new_wide(
+            tx_id,
+            TxType::Deposit,
+            0,
+            1,
+            amount,
+            1633036800000,
+            Status::Success,
+            "".to_string(),
+        )
+    }
+
+    fn get_records() -> Vec<Record> {
+        vec![
+            get_record(1, 10),
+            get_record(2, 20),
+            get_record(1, 30),
+            get_record(3, 40),
+            get_record(2, 50),
+            get_record(1, 60),
+        ]
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let duplicates = find_duplicates(&get_records());
+
+        assert_eq!(
+            duplicates,
+            vec![
+                Duplicate {
+                    tx_id: 1,
+                    records: vec![1, 3, 6]
+                },
+                Duplicate {
+                    tx_id: 2,
+                    records: vec![2, 5]
+                },
+            ]
+        );
+        assert!(find_duplicates(&crate::tests::get_data_to_write()).is_empty());
+    }
+
+    #[rstest]
+    #[case(DedupStrategy::KeepFirst, vec![10, 20, 40])]
+    #[case(DedupStrategy::KeepLast, vec![40, 50, 60])]
+    fn test_dedup_by_tx_id(#[case] strategy: DedupStrategy, #[case] amounts: Vec<i128>) {
+        let mut records = get_records();
+
+        let removed = dedup_by_tx_id(&mut records, strategy).unwrap();
+
+        assert_eq!(removed, 3);
+        assert_eq!(
+            records.iter().map(Record::wide_amount).collect::<Vec<_>>(),
+            amounts
+        );
+    }
+
+    #[test]
+    fn test_dedup_by_tx_id_error() {
+        let mut records = get_records();
+
+        let result = dedup_by_tx_id(&mut records, DedupStrategy::Error).unwrap_err();
+
+        assert_eq!(
+            result.to_string(),
+            "Duplicate TX_ID found: TX_ID 1 in records 1, 3, 6"
+        );
+        assert_eq!(records, get_records());
+    }
+}
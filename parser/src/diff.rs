@@ -0,0 +1,790 @@
+//! Модуль наборов изменений записей о транзакциях.
+//!
+//! Набор изменений ([`ChangeSet`]) между двумя выгрузками содержит добавленные, удаленные
+//! и измененные записи, сопоставленные по TX_ID. Вместо полной выгрузки можно передать набор
+//! изменений относительно предыдущей и восстановить новую выгрузку функцией [`apply`].
+//!
+//! ```
+//! use parser::diff::{apply, compute};
+//! use parser::{Record, Status, TxType};
+//!
+//! let record = |tx_id, amount| {
+//!     Record::new(tx_id, TxType::Deposit, 0, 2, amount, 1_700_000_000_000, Status::Success, String::new())
+//! };
+//!
+//! let yesterday = vec![record(1, 100), record(2, 200)];
+//! let today = vec![record(3, 300), record(1, 150)];
+//!
+//! let changeset = compute(&yesterday, &today).unwrap();
+//! assert_eq!(changeset.removed, vec![2]);
+//! assert_eq!(changeset.added, vec![(0, record(3, 300))]);
+//! assert_eq!(changeset.len(), 3);
+//!
+//! assert_eq!(apply(yesterday, &changeset).unwrap(), today);
+//! ```
+
+use super::YPBank;
+use super::dedup::find_duplicates;
+use super::errors::{ApplyError, DiffError, ReadError, WriteError};
+use super::fingerprint::{Fingerprint, from_hex, to_hex};
+use super::record::Record;
+use super::schema::SchemaVersion;
+use super::text_format::YPBankText;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufReader, Read, Write};
+
+/// Набор изменений записей о транзакциях, сопоставленных по TX_ID.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChangeSet {
+    /// Добавленные записи (с TX_ID, отсутствовавшими в исходных записях) с их порядковыми
+    /// номерами в новых записях (начиная с 0) по возрастанию номеров.
+    pub added: Vec<(usize, Record)>,
+
+    /// TX_ID удаленных записей.
+    pub removed: Vec<u64>,
+
+    /// Новые значения измененных записей.
+    pub modified: Vec<Record>,
+
+    /// TX_ID всех новых записей в порядке их следования, если порядок сохранившихся записей
+    /// изменился; пуст, если порядок восстанавливается по номерам добавленных записей.
+    pub order: Vec<u64>,
+
+    /// Отпечаток новых записей (см. [`Fingerprint`]), с которым сверяется результат [`apply`].
+    pub fingerprint: Option<[u8; 32]>,
+}
+
+impl ChangeSet {
+    /// MAGIC набора изменений в бинарном формате.
+    const MAGIC: [u8; 4] = [0x59, 0x50, 0x42, 0x44];
+
+    /// Заголовок набора изменений в текстовом формате.
+    const TEXT_HEADER: &str = "# YPBank changeset";
+
+    /// Маркер раздела удаленных записей в текстовом формате.
+    const REMOVED_SECTION: &str = "@@ REMOVED";
+
+    /// Маркер раздела порядковых номеров добавленных записей в текстовом формате.
+    const POSITIONS_SECTION: &str = "@@ ADDED POSITIONS";
+
+    /// Маркер раздела добавленных записей в текстовом формате.
+    const ADDED_SECTION: &str = "@@ ADDED";
+
+    /// Маркер раздела измененных записей в текстовом формате.
+    const MODIFIED_SECTION: &str = "@@ MODIFIED";
+
+    /// Маркер раздела порядка записей в текстовом формате.
+    const ORDER_SECTION: &str = "@@ ORDER";
+
+    /// Маркер раздела отпечатка новых записей в текстовом формате.
+    const FINGERPRINT_SECTION: &str = "@@ FINGERPRINT";
+
+    /// Общее количество изменений.
+    pub fn len(&self) -> usize {
+        self.added.len() + self.removed.len() + self.modified.len()
+    }
+
+    /// Признак отсутствия изменений.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0 && self.order.is_empty()
+    }
+
+    /// Записать набор изменений в текстовом формате.
+    ///
+    /// После заголовка следуют разделы, начинающиеся строками `@@ REMOVED`,
+    /// `@@ ADDED POSITIONS`, `@@ ADDED`, `@@ MODIFIED` и `@@ ORDER`: TX_ID удаленных записей
+    /// (по одному в строке `TX_ID: <значение>`), порядковые номера добавленных записей
+    /// (по одному в строке `POSITION: <значение>`), добавленные и измененные записи в текстовом
+    /// формате, TX_ID новых записей в порядке их следования, а также отпечаток новых записей
+    /// в шестнадцатеричном виде, если он есть.
+    pub fn write_text_to<W: Write>(&self, w: &mut W) -> Result<(), WriteError> {
+        writeln!(w, "{}", Self::TEXT_HEADER)?;
+        writeln!(w, "{}", Self::REMOVED_SECTION)?;
+
+        for tx_id in &self.removed {
+            writeln!(w, "TX_ID: {tx_id}")?;
+        }
+
+        writeln!(w, "\n{}", Self::POSITIONS_SECTION)?;
+
+        for (position, _) in &self.added {
+            writeln!(w, "POSITION: {position}")?;
+        }
+
+        for (section, records) in [
+            (
+                Self::ADDED_SECTION,
+                self.added
+                    .iter()
+                    .map(|(_, record)| record)
+                    .collect::<Vec<_>>(),
+            ),
+            (Self::MODIFIED_SECTION, self.modified.iter().collect()),
+        ] {
+            writeln!(w, "\n{section}")?;
+
+            let mut writer = YPBankText::writer_to(&mut *w);
+
+            for record in records {
+                writer.write_record(record)?;
+            }
+
+            writer.finish()?;
+        }
+
+        writeln!(w, "\n{}", Self::ORDER_SECTION)?;
+
+        for tx_id in &self.order {
+            writeln!(w, "TX_ID: {tx_id}")?;
+        }
+
+        if let Some(fingerprint) = &self.fingerprint {
+            writeln!(
+                w,
+                "\n{}\n{}",
+                Self::FINGERPRINT_SECTION,
+                to_hex(fingerprint)
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Считать набор изменений в текстовом формате (см. [`ChangeSet::write_text_to`]).
+    pub fn read_text_from<R: Read>(r: &mut R) -> Result<Self, ReadError> {
+        let mut data = String::new();
+        r.read_to_string(&mut data)?;
+
+        let mut sections: HashMap<&str, String> = HashMap::new();
+        let mut section = None;
+
+        for line in data.lines() {
+            if matches!(
+                line,
+                Self::REMOVED_SECTION
+                    | Self::POSITIONS_SECTION
+                    | Self::ADDED_SECTION
+                    | Self::MODIFIED_SECTION
+                    | Self::ORDER_SECTION
+                    | Self::FINGERPRINT_SECTION
+            ) {
+                if sections.contains_key(line) {
+                    return Err(invalid(format!("duplicate section `{line}`")));
+                }
+
+                sections.insert(line, String::new());
+                section = Some(line);
+                continue;
+            }
+
+            match section {
+                Some(section) => {
+                    let body = sections.get_mut(section).expect("section is inserted");
+                    body.push_str(line);
+                    body.push('\n');
+                }
+                None if line.trim().is_empty() || line.starts_with('#') => {}
+                None => return Err(invalid(format!("unexpected line `{line}`"))),
+            }
+        }
+
+        let values = |section, key: &str, name: &str| -> Result<Vec<u64>, ReadError> {
+            sections
+                .get(section)
+                .into_iter()
+                .flat_map(|s| s.lines())
+                .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+                .map(|line| {
+                    line.strip_prefix(key)
+                        .and_then(|value| value.strip_prefix(':'))
+                        .and_then(|value| value.trim().parse().ok())
+                        .ok_or_else(|| invalid(format!("invalid {name} `{line}`")))
+                })
+                .collect()
+        };
+
+        let removed = values(Self::REMOVED_SECTION, "TX_ID", "removed TX_ID")?;
+        let positions = values(Self::POSITIONS_SECTION, "POSITION", "added position")?;
+        let order = values(Self::ORDER_SECTION, "TX_ID", "order TX_ID")?;
+
+        // Пустые строки между разделами не относятся к записям.
+        let records = |section| match sections.get(section).map(|body| body.trim()) {
+            Some(body) if !body.is_empty() => {
+                YPBankText::read_from(&mut body.as_bytes()).map(|text| text.records)
+            }
+            _ => Ok(vec![]),
+        };
+
+        let added = records(Self::ADDED_SECTION)?;
+
+        let fingerprint = match sections.get(Self::FINGERPRINT_SECTION).map(|s| s.trim()) {
+            Some(hex) => {
+                Some(from_hex(hex).ok_or_else(|| invalid(format!("invalid fingerprint `{hex}`")))?)
+            }
+            None => None,
+        };
+
+        if positions.len() != added.len() {
+            return Err(invalid(format!(
+                "{} added positions for {} added records",
+                positions.len(),
+                added.len()
+            )));
+        }
+
+        Ok(Self {
+            added: positions
+                .into_iter()
+                .map(position)
+                .zip(added)
+                .map(|(position, record)| Ok((position?, record)))
+                .collect::<Result<_, ReadError>>()?,
+            removed,
+            modified: records(Self::MODIFIED_SECTION)?,
+            order,
+            fingerprint,
+        })
+    }
+
+    /// Записать набор изменений в бинарном формате.
+    ///
+    /// Набор изменений имеет следующую структуру (все числа в порядке big-endian):
+    ///
+    /// MAGIC (4 байта) | REMOVED (8 байт) | REMOVED TX_ID (8 байт) |
+    /// ADDED (8 байт) | ADDED порядковых номеров (8 байт) и записей |
+    /// MODIFIED (8 байт) | MODIFIED записей | ORDER (8 байт) | ORDER TX_ID (8 байт) |
+    /// FINGERPRINT (1 байт: 0 или 1) | отпечаток (32 байта, если FINGERPRINT = 1)
+    ///
+    /// Записи хранятся в бинарном формате.
+    pub fn write_bin_to<W: Write>(&self, w: &mut W) -> Result<(), WriteError> {
+        w.write_all(&Self::MAGIC)?;
+        w.write_u64::<BigEndian>(self.removed.len() as u64)?;
+
+        for &tx_id in &self.removed {
+            w.write_u64::<BigEndian>(tx_id)?;
+        }
+
+        w.write_u64::<BigEndian>(self.added.len() as u64)?;
+
+        for (position, record) in &self.added {
+            w.write_u64::<BigEndian>(*position as u64)?;
+            record.to_bin_with_schema(w, SchemaVersion::V2)?;
+        }
+
+        w.write_u64::<BigEndian>(self.modified.len() as u64)?;
+
+        for record in &self.modified {
+            record.to_bin_with_schema(w, SchemaVersion::V2)?;
+        }
+
+        w.write_u64::<BigEndian>(self.order.len() as u64)?;
+
+        for &tx_id in &self.order {
+            w.write_u64::<BigEndian>(tx_id)?;
+        }
+
+        match &self.fingerprint {
+            Some(fingerprint) => {
+                w.write_u8(1)?;
+                w.write_all(fingerprint)?;
+            }
+            None => w.write_u8(0)?,
+        }
+
+        Ok(())
+    }
+
+    /// Считать набор изменений в бинарном формате (см. [`ChangeSet::write_bin_to`]).
+    pub fn read_bin_from<R: Read>(r: &mut R) -> Result<Self, ReadError> {
+        let mut r = BufReader::new(r);
+
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+
+        if magic != Self::MAGIC {
+            return Err(invalid("invalid magic number".to_string()));
+        }
+
+        let tx_ids = |r: &mut BufReader<&mut R>| -> Result<Vec<u64>, ReadError> {
+            let count = r.read_u64::<BigEndian>()?;
+
+            Ok((0..count)
+                .map(|_| r.read_u64::<BigEndian>())
+                .collect::<Result<_, _>>()?)
+        };
+
+        let removed = tx_ids(&mut r)?;
+
+        let count = r.read_u64::<BigEndian>()?;
+        let added = (0..count)
+            .map(|_| {
+                let position = position(r.read_u64::<BigEndian>()?)?;
+
+                Ok((position, Record::from_bin(&mut r)?))
+            })
+            .collect::<Result<_, ReadError>>()?;
+
+        let count = r.read_u64::<BigEndian>()?;
+        let modified = (0..count)
+            .map(|_| Ok(Record::from_bin(&mut r)?))
+            .collect::<Result<_, ReadError>>()?;
+
+        let order = tx_ids(&mut r)?;
+
+        let fingerprint = match r.read_u8()? {
+            0 => None,
+            1 => {
+                let mut fingerprint = [0u8; 32];
+                r.read_exact(&mut fingerprint)?;
+                Some(fingerprint)
+            }
+            flag => return Err(invalid(format!("invalid fingerprint flag {flag}"))),
+        };
+
+        Ok(Self {
+            added,
+            removed,
+            modified,
+            order,
+            fingerprint,
+        })
+    }
+
+    /// Считать набор изменений, определив его формат (бинарный или текстовый) по MAGIC.
+    pub fn read_from<R: Read>(r: &mut R) -> Result<Self, ReadError> {
+        let mut magic = Vec::with_capacity(Self::MAGIC.len());
+        r.take(Self::MAGIC.len() as u64).read_to_end(&mut magic)?;
+
+        let mut r = magic.as_slice().chain(r);
+
+        if magic == Self::MAGIC {
+            Self::read_bin_from(&mut r)
+        } else {
+            Self::read_text_from(&mut r)
+        }
+    }
+}
+
+/// Ошибка разбора некорректного набора изменений.
+fn invalid(message: String) -> ReadError {
+    ReadError::InvalidChangeSet(message)
+}
+
+/// Порядковый номер добавленной записи из считанного значения.
+fn position(value: u64) -> Result<usize, ReadError> {
+    usize::try_from(value).map_err(|_| invalid(format!("added position {value} is too large")))
+}
+
+/// Вычислить набор изменений, преобразующий записи `old` в записи `new`, вместе с отпечатком
+/// записей `new`.
+///
+/// Записи сопоставляются по TX_ID, поэтому в пределах каждого набора записей TX_ID должны быть
+/// уникальными, иначе возвращается ошибка. Изменения перечисляются в порядке следования записей:
+/// удаленные — в `old`, добавленные и измененные — в `new`. Если порядок сохранившихся записей
+/// в `new` отличается от `old`, набор изменений также содержит порядок всех записей `new`.
+pub fn compute(old: &[Record], new: &[Record]) -> Result<ChangeSet, DiffError> {
+    if let Some(duplicate) = find_duplicates(old).into_iter().next() {
+        return Err(DiffError::DuplicateInOld(duplicate));
+    }
+
+    if let Some(duplicate) = find_duplicates(new).into_iter().next() {
+        return Err(DiffError::DuplicateInNew(duplicate));
+    }
+
+    let old_by_tx_id = old
+        .iter()
+        .map(|record| (record.tx_id(), record))
+        .collect::<HashMap<_, _>>();
+    let new_tx_ids = new.iter().map(Record::tx_id).collect::<HashSet<_>>();
+
+    let mut changeset = ChangeSet {
+        removed: old
+            .iter()
+            .map(Record::tx_id)
+            .filter(|tx_id| !new_tx_ids.contains(tx_id))
+            .collect(),
+        ..Default::default()
+    };
+
+    for (position, record) in new.iter().enumerate() {
+        match old_by_tx_id.get(&record.tx_id()) {
+            None => changeset.added.push((position, record.clone())),
+            Some(&old) if old != record => changeset.modified.push(record.clone()),
+            Some(_) => {}
+        }
+    }
+
+    let retained_old = old
+        .iter()
+        .map(Record::tx_id)
+        .filter(|tx_id| new_tx_ids.contains(tx_id));
+    let retained_new = new
+        .iter()
+        .map(Record::tx_id)
+        .filter(|tx_id| old_by_tx_id.contains_key(tx_id));
+
+    if !retained_old.eq(retained_new) {
+        changeset.order = new.iter().map(Record::tx_id).collect();
+    }
+
+    changeset.fingerprint = Some(fingerprint(new));
+
+    Ok(changeset)
+}
+
+/// Применить набор изменений к записям `base`.
+///
+/// Удаленные записи исключаются, измененные заменяются на месте, а добавленные вставляются
+/// на свои порядковые номера; если набор изменений содержит порядок записей, полученные записи
+/// располагаются в этом порядке. Таким образом, применение к `old` набора изменений, вычисленного
+/// [`compute`], восстанавливает `new` в точности, что проверяется по отпечатку набора изменений.
+///
+/// Если набор изменений не соответствует записям (TX_ID в `base` повторяются, удаляемый или
+/// изменяемый TX_ID отсутствует, добавляемый уже есть, порядковый номер или порядок не подходят
+/// к полученным записям либо отпечаток полученных записей отличается), возвращается ошибка.
+pub fn apply(base: Vec<Record>, changeset: &ChangeSet) -> Result<Vec<Record>, ApplyError> {
+    if let Some(duplicate) = find_duplicates(&base).into_iter().next() {
+        return Err(ApplyError::DuplicateInBase(duplicate));
+    }
+
+    let mut index = base
+        .iter()
+        .enumerate()
+        .map(|(i, record)| (record.tx_id(), i))
+        .collect::<HashMap<_, _>>();
+
+    let mut removed = vec![false; base.len()];
+
+    for tx_id in &changeset.removed {
+        let i = index.remove(tx_id).ok_or(ApplyError::MissingTxId(*tx_id))?;
+        removed[i] = true;
+    }
+
+    let mut modified = HashMap::new();
+
+    for record in &changeset.modified {
+        let i = *index
+            .get(&record.tx_id())
+            .ok_or(ApplyError::MissingTxId(record.tx_id()))?;
+
+        if modified.insert(i, record).is_some() {
+            return Err(ApplyError::RepeatedChange(record.tx_id()));
+        }
+    }
+
+    for (_, record) in &changeset.added {
+        if index.insert(record.tx_id(), usize::MAX).is_some() {
+            return Err(ApplyError::DuplicateTxId(record.tx_id()));
+        }
+    }
+
+    let mut retained = base
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| !removed[*i])
+        .map(|(i, record)| match modified.get(&i) {
+            Some(&record) => record.clone(),
+            None => record,
+        });
+
+    let mut records = Vec::with_capacity(index.len());
+
+    for (position, record) in &changeset.added {
+        while records.len() < *position {
+            match retained.next() {
+                Some(record) => records.push(record),
+                None => break,
+            }
+        }
+
+        if records.len() != *position {
+            return Err(ApplyError::InvalidPosition {
+                tx_id: record.tx_id(),
+                position: *position,
+            });
+        }
+
+        records.push(record.clone());
+    }
+
+    records.extend(retained);
+
+    if !changeset.order.is_empty() {
+        if changeset.order.len() != records.len() {
+            return Err(ApplyError::InvalidOrder);
+        }
+
+        let mut by_tx_id = records
+            .into_iter()
+            .map(|record| (record.tx_id(), record))
+            .collect::<HashMap<_, _>>();
+
+        records = changeset
+            .order
+            .iter()
+            .map(|tx_id| by_tx_id.remove(tx_id).ok_or(ApplyError::InvalidOrder))
+            .collect::<Result<_, _>>()?;
+    }
+
+    if changeset
+        .fingerprint
+        .is_some_and(|expected| expected != fingerprint(&records))
+    {
+        return Err(ApplyError::FingerprintMismatch);
+    }
+
+    Ok(records)
+}
+
+/// Отпечаток записей в порядке их следования.
+fn fingerprint(records: &[Record]) -> [u8; 32] {
+    let mut fingerprint = Fingerprint::new();
+    fingerprint.extend(records);
+    fingerprint.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dedup::Duplicate;
+    use rstest::rstest;
+
+    fn get_changeset() -> (Vec<Record>, Vec<Record>, ChangeSet) {
+        let old = crate::tests::get_data_to_write();
+
+        let mut new = old.clone();
+        let removed = new.remove(0);
+        new[0].set_description("Changed \"description\"".to_string());
+        let mut added = removed.clone();
+        added.set_tx_id(u64::MAX);
+        new.push(added.clone());
+
+        let changeset = ChangeSet {
+            added: vec![(new.len() - 1, added)],
+            removed: vec![removed.tx_id()],
+            modified: vec![new[0].clone()],
+            order: vec![],
+            fingerprint: Some(fingerprint(&new)),
+        };
+
+        (old, new, changeset)
+    }
+
+    #[test]
+    fn test_compute_and_apply() {
+        let (old, new, expected) = get_changeset();
+
+        let changeset = compute(&old, &new).unwrap();
+
+        assert_eq!(changeset, expected);
+        assert_eq!(changeset.len(), 3);
+        assert_eq!(apply(old, &changeset).unwrap(), new);
+        assert!(compute(&new, &new).unwrap().is_empty());
+    }
+
+    #[rstest]
+    #[case::inserted(false)]
+    #[case::reordered(true)]
+    fn test_compute_and_apply_keeps_order(#[case] reorder: bool) {
+        let old = crate::tests::get_data_to_write();
+
+        let mut new = old.clone();
+        if reorder {
+            new.reverse();
+        }
+        let mut added = old[0].clone();
+        added.set_tx_id(u64::MAX);
+        new.insert(1, added.clone());
+
+        let changeset = compute(&old, &new).unwrap();
+
+        assert_eq!(changeset.added, vec![(1, added)]);
+        assert_eq!(changeset.order.is_empty(), !reorder);
+        assert_eq!(apply(old, &changeset).unwrap(), new);
+    }
+
+    #[test]
+    fn test_apply_fingerprint_mismatch() {
+        let (mut old, _, changeset) = get_changeset();
+        old[2].set_description("Another base".to_string());
+
+        assert_eq!(
+            apply(old, &changeset).unwrap_err(),
+            ApplyError::FingerprintMismatch
+        );
+    }
+
+    #[test]
+    fn test_compute_duplicates() {
+        let records = crate::tests::get_data_to_write();
+        let mut duplicated = records.clone();
+        duplicated.push(records[0].clone());
+
+        let duplicate = Duplicate {
+            tx_id: records[0].tx_id(),
+            records: vec![1, duplicated.len()],
+        };
+
+        assert_eq!(
+            compute(&duplicated, &records).unwrap_err(),
+            DiffError::DuplicateInOld(duplicate.clone())
+        );
+        assert_eq!(
+            compute(&records, &duplicated).unwrap_err(),
+            DiffError::DuplicateInNew(duplicate)
+        );
+    }
+
+    #[test]
+    fn test_apply_mismatched() {
+        let mut records = crate::tests::get_data_to_write();
+        let base = vec![records.remove(0)];
+        let missing = records.remove(0);
+        let missing_tx_id = missing.tx_id();
+
+        let changesets = [
+            (
+                ChangeSet {
+                    removed: vec![missing_tx_id],
+                    ..Default::default()
+                },
+                ApplyError::MissingTxId(missing_tx_id),
+            ),
+            (
+                ChangeSet {
+                    modified: vec![missing.clone()],
+                    ..Default::default()
+                },
+                ApplyError::MissingTxId(missing_tx_id),
+            ),
+            (
+                ChangeSet {
+                    added: vec![(0, base[0].clone())],
+                    ..Default::default()
+                },
+                ApplyError::DuplicateTxId(base[0].tx_id()),
+            ),
+            (
+                ChangeSet {
+                    modified: vec![base[0].clone(), base[0].clone()],
+                    ..Default::default()
+                },
+                ApplyError::RepeatedChange(base[0].tx_id()),
+            ),
+            (
+                ChangeSet {
+                    added: vec![(2, missing.clone())],
+                    ..Default::default()
+                },
+                ApplyError::InvalidPosition {
+                    tx_id: missing_tx_id,
+                    position: 2,
+                },
+            ),
+            (
+                ChangeSet {
+                    order: vec![missing_tx_id],
+                    ..Default::default()
+                },
+                ApplyError::InvalidOrder,
+            ),
+        ];
+
+        for (changeset, expected) in changesets {
+            assert_eq!(apply(base.clone(), &changeset).unwrap_err(), expected);
+        }
+
+        assert_eq!(
+            apply(
+                vec![base[0].clone(), base[0].clone()],
+                &ChangeSet::default()
+            )
+            .unwrap_err(),
+            ApplyError::DuplicateInBase(Duplicate {
+                tx_id: base[0].tx_id(),
+                records: vec![1, 2],
+            })
+        );
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let (_, new, mut changeset) = get_changeset();
+        changeset.order = new.iter().rev().map(Record::tx_id).collect();
+
+        let mut buffer = vec![];
+        changeset.write_text_to(&mut buffer).unwrap();
+
+        let text = String::from_utf8(buffer.clone()).unwrap();
+        assert!(text.starts_with("# YPBank changeset\n@@ REMOVED\nTX_ID: "));
+
+        assert_eq!(
+            ChangeSet::read_text_from(&mut buffer.as_slice()).unwrap(),
+            changeset
+        );
+    }
+
+    #[test]
+    fn test_bin_round_trip() {
+        let (_, new, mut changeset) = get_changeset();
+        changeset.order = new.iter().rev().map(Record::tx_id).collect();
+
+        let mut buffer = vec![];
+        changeset.write_bin_to(&mut buffer).unwrap();
+
+        assert_eq!(
+            ChangeSet::read_bin_from(&mut buffer.as_slice()).unwrap(),
+            changeset
+        );
+    }
+
+    #[rstest]
+    #[case::bin(true)]
+    #[case::text(false)]
+    fn test_read_detected(#[case] bin: bool) {
+        let (_, _, changeset) = get_changeset();
+
+        let mut buffer = vec![];
+        if bin {
+            changeset.write_bin_to(&mut buffer).unwrap();
+        } else {
+            changeset.write_text_to(&mut buffer).unwrap();
+        }
+
+        assert_eq!(
+            ChangeSet::read_from(&mut buffer.as_slice()).unwrap(),
+            changeset
+        );
+    }
+
+    #[rstest]
+    #[case(b"TX_ID: 1\n", "Invalid changeset: unexpected line `TX_ID: 1`")]
+    #[case(
+        b"@@ REMOVED\nTX_ID: x\n",
+        "Invalid changeset: invalid removed TX_ID `TX_ID: x`"
+    )]
+    #[case(
+        b"@@ ADDED\n@@ ADDED\n",
+        "Invalid changeset: duplicate section `@@ ADDED`"
+    )]
+    #[case(
+        b"@@ ADDED POSITIONS\nPOSITION: 1\n",
+        "Invalid changeset: 1 added positions for 0 added records"
+    )]
+    fn test_read_text_invalid(#[case] data: &[u8], #[case] expected: &str) {
+        let result = ChangeSet::read_text_from(&mut &data[..]).unwrap_err();
+
+        assert_eq!(result.to_string(), expected);
+    }
+
+    #[test]
+    fn test_read_bin_invalid_magic() {
+        let result = ChangeSet::read_bin_from(&mut &b"YPBN\0\0\0\0\0\0\0\0"[..]).unwrap_err();
+
+        assert_eq!(
+            result.to_string(),
+            "Invalid changeset: invalid magic number"
+        );
+    }
+}
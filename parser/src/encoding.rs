@@ -0,0 +1,436 @@
+//! Модуль перекодирования текстовых данных, записанных не в UTF-8.
+//!
+//! Все текстовые форматы разбираются и записываются в UTF-8. Для чтения и записи файлов в других
+//! кодировках (например, Windows-1251, в которой выгружают данные многие российские банки,
+//! или UTF-16) источник оборачивается в [`DecodeReader`], а назначение — в [`EncodeWriter`].
+//!
+//! Поддержка включается признаком крейта `encoding`.
+
+use super::errors::FormatError;
+use std::io::{self, Read, Write};
+
+/// Кодировка текстовых данных.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Encoding(&'static encoding_rs::Encoding);
+
+/// Реализация трейта [`Default`] для [`Encoding`].
+impl Default for Encoding {
+    /// Кодировка по умолчанию — UTF-8.
+    fn default() -> Self {
+        Self(encoding_rs::UTF_8)
+    }
+}
+
+/// Реализация трейта [`TryFrom<&str>`] для [`Encoding`].
+impl TryFrom<&str> for Encoding {
+    /// Ошибка определения кодировки.
+    type Error = FormatError;
+
+    /// Реализация метода [`TryFrom<&str>::try_from`] для [`Encoding`].
+    ///
+    /// Принимаются названия кодировок по стандарту WHATWG Encoding (`utf-8`, `windows-1251`,
+    /// `cp1251`, `utf-16le`, `utf-16be`, `koi8-r` и др.) без учета регистра.
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        encoding_rs::Encoding::for_label(s.as_bytes())
+            .filter(|&encoding| encoding != encoding_rs::REPLACEMENT)
+            .map(Self)
+            .ok_or_else(|| FormatError::UnsupportedEncoding(s.to_string()))
+    }
+}
+
+impl Encoding {
+    /// Каноническое название кодировки.
+    pub fn name(&self) -> &'static str {
+        self.0.name()
+    }
+
+    /// Признак кодировки UTF-8, не требующей перекодирования.
+    pub fn is_utf8(&self) -> bool {
+        self.0 == encoding_rs::UTF_8
+    }
+
+    /// Признак кодировки UTF-16.
+    pub fn is_utf16(&self) -> bool {
+        self.0 == encoding_rs::UTF_16LE || self.0 == encoding_rs::UTF_16BE
+    }
+
+    /// Обернуть источник, перекодирующий считываемые данные из данной кодировки в UTF-8.
+    pub fn decoder<R: Read>(self, r: R) -> DecodeReader<R> {
+        DecodeReader::new(r, self)
+    }
+
+    /// Обернуть назначение, перекодирующее записываемые данные из UTF-8 в данную кодировку.
+    pub fn encoder<W: Write>(self, w: W) -> EncodeWriter<W> {
+        EncodeWriter::new(w, self)
+    }
+}
+
+/// Источник, перекодирующий считываемые данные в UTF-8.
+///
+/// Метка порядка байтов UTF-8 или UTF-16 в начале данных определяет их кодировку вместо
+/// заданной и не передается в считываемые данные. Некорректные для кодировки данные приводят
+/// к ошибке чтения.
+pub struct DecodeReader<R: Read> {
+    /// Исходный источник данных.
+    inner: R,
+
+    /// Декодер данных.
+    decoder: encoding_rs::Decoder,
+
+    /// Буфер считанных из источника данных.
+    input: Box<[u8]>,
+
+    /// Начало необработанных данных в буфере считанных данных.
+    input_start: usize,
+
+    /// Конец считанных данных в буфере считанных данных.
+    input_end: usize,
+
+    /// Буфер перекодированных данных.
+    output: Box<[u8]>,
+
+    /// Начало невыданных данных в буфере перекодированных данных.
+    output_start: usize,
+
+    /// Конец перекодированных данных в буфере перекодированных данных.
+    output_end: usize,
+
+    /// Признак исчерпания источника.
+    eof: bool,
+
+    /// Признак завершения перекодирования.
+    finished: bool,
+}
+
+impl<R: Read> DecodeReader<R> {
+    /// Размер буферов.
+    const CAPACITY: usize = 8 * 1024;
+
+    /// Создание источника, перекодирующего данные из указанной кодировки.
+    pub fn new(r: R, encoding: Encoding) -> Self {
+        Self {
+            inner: r,
+            decoder: encoding.0.new_decoder(),
+            input: vec![0; Self::CAPACITY].into_boxed_slice(),
+            input_start: 0,
+            input_end: 0,
+            output: vec![0; Self::CAPACITY].into_boxed_slice(),
+            output_start: 0,
+            output_end: 0,
+            eof: false,
+            finished: false,
+        }
+    }
+
+    /// Перекодировать очередную порцию данных источника в буфер перекодированных данных.
+    fn decode(&mut self) -> io::Result<()> {
+        if self.input_start == self.input_end && !self.eof {
+            self.input_start = 0;
+            self.input_end = loop {
+                match self.inner.read(&mut self.input) {
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    result => break result?,
+                }
+            };
+            self.eof = self.input_end == 0;
+        }
+
+        let (result, read, written) = self.decoder.decode_to_utf8_without_replacement(
+            &self.input[self.input_start..self.input_end],
+            &mut self.output,
+            self.eof,
+        );
+
+        self.input_start += read;
+        self.output_start = 0;
+        self.output_end = written;
+
+        match result {
+            encoding_rs::DecoderResult::InputEmpty => self.finished = self.eof,
+            encoding_rs::DecoderResult::OutputFull => {}
+            encoding_rs::DecoderResult::Malformed(..) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "stream did not contain valid {}",
+                        self.decoder.encoding().name()
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Реализация трейта [`Read`] для [`DecodeReader`].
+impl<R: Read> Read for DecodeReader<R> {
+    /// Реализация метода [`Read::read`] для [`DecodeReader`].
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.output_start == self.output_end {
+            if self.finished {
+                return Ok(0);
+            }
+
+            self.decode()?;
+        }
+
+        let count = (self.output_end - self.output_start).min(buf.len());
+        buf[..count].copy_from_slice(&self.output[self.output_start..self.output_start + count]);
+        self.output_start += count;
+
+        Ok(count)
+    }
+}
+
+/// Назначение, перекодирующее записываемые данные из UTF-8.
+///
+/// Символы, непредставимые в кодировке, приводят к ошибке записи. Для записи данных,
+/// завершающих последовательность в кодировках с состоянием, необходимо вызвать
+/// [`EncodeWriter::finish`].
+pub struct EncodeWriter<W: Write> {
+    /// Исходное назначение.
+    inner: W,
+
+    /// Кодировка назначения.
+    encoding: Encoding,
+
+    /// Кодировщик данных.
+    encoder: encoding_rs::Encoder,
+
+    /// Незавершенный многобайтовый символ UTF-8 в конце записанных данных.
+    pending: Vec<u8>,
+
+    /// Буфер перекодированных данных.
+    output: Box<[u8]>,
+}
+
+impl<W: Write> EncodeWriter<W> {
+    /// Размер буфера перекодированных данных.
+    const CAPACITY: usize = 8 * 1024;
+
+    /// Создание назначения, перекодирующего данные в указанную кодировку.
+    pub fn new(w: W, encoding: Encoding) -> Self {
+        Self {
+            inner: w,
+            encoding,
+            encoder: encoding.0.new_encoder(),
+            pending: vec![],
+            output: vec![0; Self::CAPACITY].into_boxed_slice(),
+        }
+    }
+
+    /// Завершить перекодирование и вернуть исходное назначение.
+    pub fn finish(mut self) -> io::Result<W> {
+        if !self.pending.is_empty() {
+            return Err(invalid_utf8());
+        }
+
+        self.encode("", true)?;
+        self.inner.flush()?;
+
+        Ok(self.inner)
+    }
+
+    /// Перекодировать и записать в назначение указанный текст.
+    fn encode(&mut self, text: &str, last: bool) -> io::Result<()> {
+        // Кодировщики encoding_rs для UTF-16 по стандарту WHATWG записывают UTF-8.
+        if self.encoding.is_utf16() {
+            let bytes = text
+                .encode_utf16()
+                .flat_map(|unit| {
+                    if self.encoding.0 == encoding_rs::UTF_16LE {
+                        unit.to_le_bytes()
+                    } else {
+                        unit.to_be_bytes()
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            return self.inner.write_all(&bytes);
+        }
+
+        let mut text = text;
+
+        loop {
+            let (result, read, written) =
+                self.encoder
+                    .encode_from_utf8_without_replacement(text, &mut self.output, last);
+
+            self.inner.write_all(&self.output[..written])?;
+            text = &text[read..];
+
+            match result {
+                encoding_rs::EncoderResult::InputEmpty => return Ok(()),
+                encoding_rs::EncoderResult::OutputFull => {}
+                encoding_rs::EncoderResult::Unmappable(c) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "character {c:?} cannot be encoded in {}",
+                            self.encoding.name()
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Сформировать ошибку записи данных, не являющихся корректным UTF-8.
+fn invalid_utf8() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, "data is not valid UTF-8")
+}
+
+/// Реализация трейта [`Write`] для [`EncodeWriter`].
+impl<W: Write> Write for EncodeWriter<W> {
+    /// Реализация метода [`Write::write`] для [`EncodeWriter`].
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.encoding.is_utf8() {
+            return self.inner.write(buf);
+        }
+
+        let mut data = std::mem::take(&mut self.pending);
+        data.extend_from_slice(buf);
+
+        // Данные могут обрываться посреди многобайтового символа.
+        let valid = match std::str::from_utf8(&data) {
+            Ok(text) => text.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => return Err(invalid_utf8()),
+        };
+
+        let text = std::str::from_utf8(&data[..valid]).map_err(|_| invalid_utf8())?;
+        self.encode(text, false)?;
+
+        self.pending = data[valid..].to_vec();
+
+        Ok(buf.len())
+    }
+
+    /// Реализация метода [`Write::flush`] для [`EncodeWriter`].
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::{Status, TxType};
+    use crate::{Record, YPBankImpl};
+    use rstest::rstest;
+
+    fn records() -> Vec<Record> {
+        vec![Record::new(
+            1,
+            TxType::Deposit,
+            0,
+            2,
+            300,
+            1000,
+            Status::Success,
+            "Пополнение счета".to_string(),
+        )]
+    }
+
+    fn write(format: YPBankImpl, encoding: Encoding) -> Vec<u8> {
+        let mut writer = encoding.encoder(vec![]);
+        format.write_to(records(), &mut writer).unwrap();
+
+        writer.finish().unwrap()
+    }
+
+    #[rstest]
+    fn test_roundtrip(
+        #[values("text", "csv", "json", "jsonl")] format: &str,
+        #[values("utf-8", "cp1251", "koi8-r", "utf-16le", "utf-16be")] encoding: &str,
+    ) {
+        let format = YPBankImpl::try_from(format).unwrap();
+        let encoding = Encoding::try_from(encoding).unwrap();
+
+        let data = write(format, encoding);
+
+        assert_eq!(
+            format
+                .read_from(&mut encoding.decoder(data.as_slice()))
+                .unwrap(),
+            records()
+        );
+    }
+
+    #[test]
+    fn test_write_cp1251() {
+        let data = write(YPBankImpl::Csv, Encoding::try_from("windows-1251").unwrap());
+
+        assert!(
+            data.ends_with(b",\"\xCF\xEE\xEF\xEE\xEB\xED\xE5\xED\xE8\xE5 \xF1\xF7\xE5\xF2\xE0\"\n")
+        );
+    }
+
+    #[test]
+    fn test_write_split_character() {
+        let mut writer = Encoding::try_from("cp1251").unwrap().encoder(vec![]);
+        let text = "Счет".as_bytes();
+
+        writer.write_all(&text[..3]).unwrap();
+        writer.write_all(&text[3..]).unwrap();
+
+        assert_eq!(writer.finish().unwrap(), b"\xD1\xF7\xE5\xF2");
+    }
+
+    #[test]
+    fn test_write_unmappable() {
+        let mut writer = Encoding::try_from("cp1251").unwrap().encoder(vec![]);
+
+        let result = writer.write_all("日本".as_bytes()).unwrap_err();
+
+        assert_eq!(
+            result.to_string(),
+            "character '日' cannot be encoded in windows-1251"
+        );
+    }
+
+    #[test]
+    fn test_read_utf8_bom_overrides_encoding() {
+        let data = "\u{feff}Счет".as_bytes();
+        let mut result = String::new();
+
+        Encoding::try_from("cp1251")
+            .unwrap()
+            .decoder(data)
+            .read_to_string(&mut result)
+            .unwrap();
+
+        assert_eq!(result, "Счет");
+    }
+
+    #[test]
+    fn test_read_malformed() {
+        let mut result = String::new();
+
+        let error = Encoding::try_from("utf-16le")
+            .unwrap()
+            .decoder([0x00, 0xD8, 0x41, 0x00].as_slice())
+            .read_to_string(&mut result)
+            .unwrap_err();
+
+        assert_eq!(error.to_string(), "stream did not contain valid UTF-16LE");
+    }
+
+    #[rstest]
+    #[case("cp1251", "windows-1251")]
+    #[case("UTF-16", "UTF-16LE")]
+    #[case("utf8", "UTF-8")]
+    fn test_try_from(#[case] label: &str, #[case] name: &str) {
+        assert_eq!(Encoding::try_from(label).unwrap().name(), name);
+    }
+
+    #[test]
+    fn test_try_from_unsupported() {
+        assert_eq!(
+            Encoding::try_from("klingon").unwrap_err().to_string(),
+            "Unsupported encoding: klingon"
+        );
+    }
+}
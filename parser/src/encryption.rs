@@ -0,0 +1,264 @@
+//! Модуль зашифрованного контейнера бинарного формата.
+//!
+//! Выгрузки содержат персональные данные, поэтому при передаче их можно зашифровать алгоритмом
+//! AES-256-GCM. Зашифрованный контейнер имеет следующую структуру (числа в порядке big-endian):
+//!
+//! MAGIC (4 байта, `YPBE`) | VERSION (4 байта) | NONCE (12 байт) | CIPHERTEXT | TAG (16 байт)
+//!
+//! Расшифрованные данные представляют собой записи в бинарном формате (с заголовком контейнера
+//! [`YPBankBin::write_container_to`] или без него). Заголовок зашифрованного контейнера
+//! защищен от изменения вместе с данными. Ключ длиной 32 байта задается шестнадцатеричной
+//! строкой, например, созданной командой `openssl rand -hex 32`.
+//!
+//! Поддержка включается признаком крейта `encryption`.
+
+use super::YPBank;
+use super::bin_format::YPBankBin;
+use super::errors::EncryptionError;
+use super::fingerprint::from_hex;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use std::fmt;
+use std::io::{Read, Write};
+
+/// MAGIC зашифрованного контейнера.
+const MAGIC: [u8; 4] = [0x59, 0x50, 0x42, 0x45];
+
+/// Поддерживаемая версия зашифрованного контейнера.
+const VERSION: u32 = 1;
+
+/// Размер одноразового числа (nonce) в байтах.
+const NONCE_LEN: usize = 12;
+
+/// Ключ шифрования AES-256.
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey([u8; 32]);
+
+impl EncryptionKey {
+    /// Считать ключ из переменной окружения с указанным именем.
+    pub fn from_env(name: &str) -> Result<Self, EncryptionError> {
+        let value =
+            std::env::var(name).map_err(|_| EncryptionError::MissingKey(name.to_string()))?;
+
+        Self::try_from(value.as_str())
+    }
+
+    /// Объект шифрования данных этим ключом.
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(&self.0.into())
+    }
+}
+
+/// Реализация трейта [`TryFrom<&str>`] для [`EncryptionKey`].
+impl TryFrom<&str> for EncryptionKey {
+    /// Ошибка разбора ключа.
+    type Error = EncryptionError;
+
+    /// Реализация метода [`TryFrom<&str>::try_from`] для [`EncryptionKey`].
+    ///
+    /// Ключ задается шестнадцатеричной строкой из 64 символов; пробельные символы по краям
+    /// строки игнорируются.
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        from_hex(s.trim()).map(Self).ok_or_else(|| {
+            EncryptionError::InvalidKey("expected 64 hexadecimal digits".to_string())
+        })
+    }
+}
+
+/// Реализация трейта [`fmt::Debug`] для [`EncryptionKey`].
+impl fmt::Debug for EncryptionKey {
+    /// Реализация метода [`fmt::Debug::fmt`] для [`EncryptionKey`].
+    ///
+    /// Значение ключа не выводится.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptionKey(..)")
+    }
+}
+
+/// Заголовок зашифрованного контейнера с указанным одноразовым числом.
+fn header(nonce: &[u8]) -> Vec<u8> {
+    let mut header = Vec::with_capacity(MAGIC.len() + 4 + NONCE_LEN);
+    header.extend(MAGIC);
+    header.extend(VERSION.to_be_bytes());
+    header.extend(nonce);
+
+    header
+}
+
+/// Зашифровать данные и записать их в назначение в виде зашифрованного контейнера.
+pub fn encrypt<W: Write>(
+    plaintext: &[u8],
+    w: &mut W,
+    key: &EncryptionKey,
+) -> Result<(), EncryptionError> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let header = header(&nonce);
+
+    let ciphertext = key
+        .cipher()
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad: &header,
+            },
+        )
+        .map_err(|_| EncryptionError::EncryptionFailed)?;
+
+    w.write_all(&header)?;
+    w.write_all(&ciphertext)?;
+
+    Ok(())
+}
+
+/// Считать зашифрованный контейнер из источника и расшифровать его данные.
+pub fn decrypt<R: Read>(r: &mut R, key: &EncryptionKey) -> Result<Vec<u8>, EncryptionError> {
+    let mut data = vec![];
+    r.read_to_end(&mut data)?;
+
+    let header_len = MAGIC.len() + 4 + NONCE_LEN;
+
+    if !data.starts_with(&MAGIC) {
+        return Err(EncryptionError::NotEncrypted);
+    }
+
+    if data.len() < header_len {
+        return Err(EncryptionError::DecryptionFailed);
+    }
+
+    let (header, ciphertext) = data.split_at(header_len);
+
+    let version = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+    if version != VERSION {
+        return Err(EncryptionError::UnsupportedVersion(version));
+    }
+
+    key.cipher()
+        .decrypt(
+            Nonce::from_slice(&header[MAGIC.len() + 4..]),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map_err(|_| EncryptionError::DecryptionFailed)
+}
+
+/// Считать данные о банковских операциях из зашифрованного контейнера бинарного формата.
+pub fn read_from_bin_encrypted<R: Read>(
+    r: &mut R,
+    key: &EncryptionKey,
+) -> Result<YPBankBin, EncryptionError> {
+    let plaintext = decrypt(r, key)?;
+
+    Ok(YPBankBin::read_from(&mut plaintext.as_slice())?)
+}
+
+/// Записать данные о банковских операциях в бинарном формате (с заголовком контейнера)
+/// в зашифрованный контейнер.
+pub fn write_to_bin_encrypted<W: Write>(
+    data: &YPBankBin,
+    w: &mut W,
+    key: &EncryptionKey,
+) -> Result<(), EncryptionError> {
+    let mut plaintext = vec![];
+    data.write_container_to(&mut plaintext)?;
+
+    encrypt(&plaintext, w, key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    fn encrypted() -> Vec<u8> {
+        let data = YPBankBin::from_records(crate::tests::get_data_to_write());
+
+        let mut bytes = vec![];
+        write_to_bin_encrypted(&data, &mut bytes, &EncryptionKey::try_from(KEY).unwrap()).unwrap();
+
+        bytes
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let bytes = encrypted();
+
+        assert_eq!(bytes[..8], [0x59, 0x50, 0x42, 0x45, 0x00, 0x00, 0x00, 0x01]);
+
+        let data = read_from_bin_encrypted(
+            &mut bytes.as_slice(),
+            &EncryptionKey::try_from(KEY).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(data.records, crate::tests::get_data_to_write());
+    }
+
+    #[test]
+    fn test_nonce_is_random() {
+        assert_ne!(encrypted(), encrypted());
+    }
+
+    #[test]
+    fn test_decrypt_wrong_key() {
+        let key = EncryptionKey::try_from(KEY.replace('0', "f").as_str()).unwrap();
+
+        let result = read_from_bin_encrypted(&mut encrypted().as_slice(), &key);
+
+        assert!(matches!(
+            result.unwrap_err(),
+            EncryptionError::DecryptionFailed
+        ));
+    }
+
+    #[test]
+    fn test_decrypt_tampered() {
+        let key = EncryptionKey::try_from(KEY).unwrap();
+
+        for position in [7, 10, 40] {
+            let mut bytes = encrypted();
+            bytes[position] ^= 1;
+
+            let result = decrypt(&mut bytes.as_slice(), &key).unwrap_err();
+
+            assert!(matches!(
+                result,
+                EncryptionError::DecryptionFailed | EncryptionError::UnsupportedVersion(_)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_decrypt_not_encrypted() {
+        let mut bytes = vec![];
+        YPBankBin::from_records(crate::tests::get_data_to_write())
+            .write_to(&mut bytes)
+            .unwrap();
+
+        let result = decrypt(
+            &mut bytes.as_slice(),
+            &EncryptionKey::try_from(KEY).unwrap(),
+        );
+
+        assert!(matches!(result.unwrap_err(), EncryptionError::NotEncrypted));
+    }
+
+    #[test]
+    fn test_key_invalid() {
+        assert_eq!(
+            EncryptionKey::try_from("abc").unwrap_err().to_string(),
+            "Invalid encryption key: expected 64 hexadecimal digits"
+        );
+    }
+
+    #[test]
+    fn test_key_debug_hides_value() {
+        assert_eq!(
+            format!("{:?}", EncryptionKey::try_from(KEY).unwrap()),
+            "EncryptionKey(..)"
+        );
+    }
+}
@@ -1,8 +1,43 @@
+use super::dedup::Duplicate;
+use super::record::RecordKey;
 use super::record::errors::{
-    ParseRecordFromBinError, ParseRecordFromCsvError, ParseRecordFromTxtError,
+    ParseKeyError, ParseRecordFromBinError, ParseRecordFromCsvError,
+    ParseRecordFromFixedWidthError, ParseRecordFromTxtError, ParseTxTypeError,
 };
+use super::schema::SchemaVersion;
+use std::fmt;
+use std::path::PathBuf;
 use thiserror::Error;
 
+/// Позиция записи в источнике данных.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    /// Порядковый номер записи (начиная с 1).
+    pub record: usize,
+
+    /// Номер строки, с которой начинается запись (начиная с 1).
+    ///
+    /// Не указывается для бинарных форматов.
+    pub line: Option<u64>,
+
+    /// Смещение начала записи в байтах от начала источника.
+    pub offset: u64,
+}
+
+/// Реализация трейта [`fmt::Display`] для [`Position`].
+impl fmt::Display for Position {
+    /// Реализация метода [`fmt::Display::fmt`] для [`Position`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "record {}", self.record)?;
+
+        if let Some(line) = self.line {
+            write!(f, ", line {line}")?;
+        }
+
+        write!(f, ", byte {}", self.offset)
+    }
+}
+
 /// Ошибка чтения данных из источника.
 #[derive(Debug, Error)]
 pub enum ReadError {
@@ -18,9 +53,182 @@ pub enum ReadError {
     #[error("Binary format parsing error: {0}")]
     FromBin(#[from] ParseRecordFromBinError),
 
+    /// Ошибка чтения данных из JSON источника.
+    #[error("JSON format parsing error: {0}")]
+    FromJson(#[from] serde_json::Error),
+
+    /// Ошибка чтения данных из источника в формате Protocol Buffers.
+    #[cfg(feature = "protobuf")]
+    #[error("Protobuf format parsing error: {0}")]
+    FromProto(#[from] super::record::errors::ParseRecordFromProtoError),
+
+    /// Ошибка чтения данных из источника в формате с фиксированной шириной полей.
+    #[error("Fixed-width format parsing error: {0}")]
+    FromFixedWidth(#[from] ParseRecordFromFixedWidthError),
+
+    /// Ошибка чтения данных из источника в формате MessagePack.
+    #[cfg(feature = "msgpack")]
+    #[error("MessagePack format parsing error: {0}")]
+    FromMsgPack(#[from] rmp_serde::decode::Error),
+
+    /// Источник оборвался раньше объявленного в заголовке формата конца данных.
+    #[error(
+        "Data is truncated: expected {expected_records} records ({expected_len} bytes), found {actual_len} bytes"
+    )]
+    Truncated {
+        /// Объявленное количество записей.
+        expected_records: u64,
+
+        /// Объявленный размер данных записей в байтах.
+        expected_len: u64,
+
+        /// Фактический размер данных записей в байтах.
+        actual_len: u64,
+    },
+
+    /// Некорректный или не соответствующий источнику индекс записей.
+    #[error("Invalid index: {0}")]
+    InvalidIndex(String),
+
+    /// Запись источника слияния нарушает порядок сортировки (см. [`crate::MergeSorted`]).
+    #[error("Input {input} is not sorted: TX_ID {tx_id} is out of order after TX_ID {previous}")]
+    Unsorted {
+        /// Порядковый номер источника (начиная с 1).
+        input: usize,
+
+        /// TX_ID записи, нарушившей порядок.
+        tx_id: u64,
+
+        /// TX_ID предыдущей записи того же источника.
+        previous: u64,
+    },
+
+    /// Некорректный набор изменений записей.
+    #[error("Invalid changeset: {0}")]
+    InvalidChangeSet(String),
+
+    /// Не удалось определить формат данных по содержимому источника.
+    #[error("Could not detect data format")]
+    UnknownFormat,
+
     /// Ошибка чтения данных, не связанная с его типом.
     #[error("Read data error: {0}")]
     Io(#[from] std::io::Error),
+
+    /// Ошибка чтения данных в формате Apache Parquet.
+    #[cfg(feature = "parquet")]
+    #[error("Parquet format error: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+
+    /// Ошибка чтения данных из базы данных SQLite.
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    /// Ошибка чтения данных с указанием позиции записи в источнике.
+    #[error("{source} (at {position})")]
+    At {
+        /// Позиция записи, при чтении которой произошла ошибка.
+        position: Position,
+
+        /// Исходная ошибка чтения.
+        source: Box<ReadError>,
+    },
+}
+
+impl ReadError {
+    /// Получить позицию записи в источнике, при чтении которой произошла ошибка, если она известна.
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            Self::At { position, .. } => Some(*position),
+            _ => None,
+        }
+    }
+
+    /// Получить исходную ошибку чтения без информации о позиции.
+    pub fn inner(&self) -> &ReadError {
+        match self {
+            Self::At { source, .. } => source.inner(),
+            e => e,
+        }
+    }
+
+    /// Признак того, что источник оборвался раньше конца данных.
+    ///
+    /// Позволяет отличить неполные (например, не до конца скачанные) данные, которые стоит
+    /// получить заново, от поврежденных.
+    pub fn is_truncated(&self) -> bool {
+        matches!(
+            self.inner(),
+            Self::Truncated { .. } | Self::FromBin(ParseRecordFromBinError::UnexpectedEof { .. })
+        )
+    }
+}
+
+/// Ошибка устранения записей с повторяющимися TX_ID.
+#[derive(Debug, Error, PartialEq)]
+pub enum DedupError {
+    /// Найдены записи с повторяющимся TX_ID.
+    #[error("Duplicate TX_ID found: {0}")]
+    DuplicateTxId(Duplicate),
+}
+
+/// Ошибка вычисления набора изменений записей о транзакциях.
+#[derive(Debug, Error, PartialEq)]
+pub enum DiffError {
+    /// Исходные записи содержат повторяющиеся TX_ID.
+    #[error("Duplicate TX_ID found in the old transactions: {0}")]
+    DuplicateInOld(Duplicate),
+
+    /// Новые записи содержат повторяющиеся TX_ID.
+    #[error("Duplicate TX_ID found in the new transactions: {0}")]
+    DuplicateInNew(Duplicate),
+}
+
+/// Ошибка применения набора изменений к записям о транзакциях.
+#[derive(Debug, Error, PartialEq)]
+pub enum ApplyError {
+    /// Записи, к которым применяется набор изменений, содержат повторяющиеся TX_ID.
+    #[error("Duplicate TX_ID found in the base transactions: {0}")]
+    DuplicateInBase(Duplicate),
+
+    /// Запись с добавляемым TX_ID уже существует.
+    #[error("Cannot add transaction {0}: TX_ID already exists")]
+    DuplicateTxId(u64),
+
+    /// Запись с удаляемым или изменяемым TX_ID отсутствует.
+    #[error("Cannot remove or modify transaction {0}: TX_ID not found")]
+    MissingTxId(u64),
+
+    /// Запись изменяется набором изменений более одного раза.
+    #[error("Cannot modify transaction {0}: it is modified more than once")]
+    RepeatedChange(u64),
+
+    /// Позиция добавляемой записи выходит за пределы полученных записей.
+    #[error("Cannot add transaction {tx_id} at position {position}: out of range")]
+    InvalidPosition {
+        /// TX_ID добавляемой записи.
+        tx_id: u64,
+
+        /// Порядковый номер добавляемой записи (начиная с 0).
+        position: usize,
+    },
+
+    /// Порядок записей набора изменений не соответствует полученным записям.
+    #[error("Transaction order of the changeset does not match the transactions")]
+    InvalidOrder,
+
+    /// Отпечаток полученных записей отличается от отпечатка набора изменений.
+    #[error("Restored transactions do not match the changeset fingerprint")]
+    FingerprintMismatch,
+}
+
+/// Ошибка удаления значений полей записей о транзакциях.
+#[derive(Debug, Error, PartialEq)]
+pub enum RedactError {
+    /// Значение поля не может быть удалено без нарушения структуры записи.
+    #[error("Field {0} cannot be redacted")]
+    UnsupportedField(RecordKey),
 }
 
 /// Ошибка записи данных.
@@ -34,13 +242,296 @@ pub enum WriteError {
     #[error("Unexpected error: {0}")]
     UnexpectedError(String),
 
+    /// Значение поля записи не может быть записано в формате с фиксированной шириной полей.
+    #[error("Cannot write {key} value `{value}` as a fixed-width field: {reason}")]
+    FixedWidthValue {
+        /// Ключ поля записи.
+        key: RecordKey,
+
+        /// Значение поля записи.
+        value: String,
+
+        /// Причина ошибки.
+        reason: String,
+    },
+
+    /// Валюта записи не может быть записана в CSV-таблицу без столбца валюты (столбец
+    /// добавляется, только если валюта указана в первой записи).
+    #[error(
+        "Cannot write CURRENCY of transaction {0}: the CSV table has no CURRENCY column, as the first record has no currency"
+    )]
+    MissingCurrencyColumn(u64),
+
+    /// Запись требует более новой версии схемы, чем используется при записи
+    /// (см. [`crate::RecordWriter::schema_version`]).
+    #[error(
+        "Transaction {tx_id} requires schema version {required} (AMOUNT {amount}), but schema version {used} is used"
+    )]
+    SchemaVersionRequired {
+        /// Идентификатор транзакции.
+        tx_id: u64,
+
+        /// Сумма транзакции.
+        amount: i128,
+
+        /// Требуемая версия схемы.
+        required: SchemaVersion,
+
+        /// Используемая версия схемы.
+        used: SchemaVersion,
+    },
+
     /// Ошибка записи данных, не связанная с его типом.
     #[error("Read data error: {0}")]
     Io(#[from] std::io::Error),
+
+    /// Ошибка записи данных в формате MessagePack.
+    #[cfg(feature = "msgpack")]
+    #[error("MessagePack format error: {0}")]
+    ToMsgPack(#[from] rmp_serde::encode::Error),
+
+    /// Ошибка записи данных в формате Apache Parquet.
+    #[cfg(feature = "parquet")]
+    #[error("Parquet format error: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+
+    /// Ошибка записи данных в базу данных SQLite.
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+}
+
+/// Ошибка описания разметки полей формата с фиксированной шириной полей.
+#[derive(Debug, Error, PartialEq)]
+pub enum FixedWidthLayoutError {
+    /// Некорректное описание поля.
+    #[error("Invalid field spec `{0}`: expected KEY:WIDTH[:ALIGN[:PADDING]]")]
+    InvalidFieldSpec(String),
+
+    /// Некорректный ключ поля.
+    #[error(transparent)]
+    InvalidKey(#[from] ParseKeyError),
+
+    /// Некорректное выравнивание значения поля.
+    #[error("Invalid alignment: {0}")]
+    InvalidAlignment(String),
+
+    /// Нулевая ширина поля.
+    #[error("Field {0} must have a non-zero width")]
+    ZeroWidth(RecordKey),
+
+    /// Символ-заполнитель неотличим от значения поля.
+    #[error("Field {0} cannot be padded with a digit unless it is a right-aligned number")]
+    AmbiguousPadding(RecordKey),
+
+    /// Поле указано в разметке несколько раз.
+    #[error("Duplicate field: {0}")]
+    DuplicateField(RecordKey),
+
+    /// Поле отсутствует в разметке.
+    #[error("Missing field: {0}")]
+    MissingField(RecordKey),
+}
+
+/// Ошибка описания соответствия имен столбцов CSV-таблицы полям записи.
+#[derive(Debug, Error, PartialEq)]
+pub enum CsvColumnMappingError {
+    /// Некорректная строка описания соответствия.
+    #[error("Invalid mapping at line {line}: `{content}`: expected COLUMN=KEY")]
+    InvalidLine {
+        /// Номер строки (начиная с 1).
+        line: usize,
+
+        /// Содержимое строки.
+        content: String,
+    },
+
+    /// Некорректный ключ поля.
+    #[error(transparent)]
+    InvalidKey(#[from] ParseKeyError),
+}
+
+/// Ошибка описания правил присвоения категорий записям о транзакциях.
+#[derive(Debug, Error)]
+pub enum TagRulesError {
+    /// Некорректная строка описания правила.
+    #[error("Invalid rule at line {line}: `{content}`: expected CATEGORY: CONDITION; ...")]
+    InvalidLine {
+        /// Номер строки (начиная с 1).
+        line: usize,
+
+        /// Содержимое строки.
+        content: String,
+    },
+
+    /// Некорректное условие правила.
+    #[error(
+        "Invalid condition at line {line}: `{content}`: expected description=REGEX, tx_type=TYPE or amount=MIN..MAX"
+    )]
+    InvalidCondition {
+        /// Номер строки (начиная с 1).
+        line: usize,
+
+        /// Условие.
+        content: String,
+    },
+
+    /// Некорректное регулярное выражение для описания транзакции.
+    #[error("Invalid regular expression at line {line}: {source}")]
+    InvalidRegex {
+        /// Номер строки (начиная с 1).
+        line: usize,
+
+        /// Ошибка разбора регулярного выражения.
+        source: regex::Error,
+    },
+
+    /// Некорректный тип транзакции.
+    #[error("Invalid TX_TYPE at line {line}: {source}")]
+    InvalidTxType {
+        /// Номер строки (начиная с 1).
+        line: usize,
+
+        /// Ошибка парсинга типа транзакции.
+        source: ParseTxTypeError,
+    },
+}
+
+/// Структурная ошибка данных в бинарном формате, обнаруженная при разборе по полям.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+#[error("Structural error at byte {offset}: {message}")]
+pub struct BinStructureError {
+    /// Смещение ошибки в байтах от начала данных.
+    pub offset: u64,
+
+    /// Описание ошибки.
+    pub message: String,
+}
+
+/// Ошибка параметров генерации записей о транзакциях.
+#[derive(Debug, Error, PartialEq)]
+pub enum GenerateError {
+    /// Пустой диапазон идентификаторов участников либо диапазон, содержащий 0.
+    #[error("Invalid user ID range: {0}..={1}")]
+    InvalidUserIds(u64, u64),
+
+    /// Пустой диапазон сумм либо диапазон, содержащий 0.
+    #[error("Invalid amount range: {0}..={1}")]
+    InvalidAmount(u64, u64),
+
+    /// Пустой интервал меток времени либо интервал вне допустимого диапазона.
+    #[error("Invalid time window: {0}..{1}")]
+    InvalidTimeWindow(u64, u64),
 }
 
 #[derive(Error, Debug)]
 pub enum FormatError {
     #[error("Invalid data format: {0}")]
     InvalidFormat(String),
+
+    #[error("Unsupported compression: {0}")]
+    UnsupportedCompression(String),
+
+    #[error("Unsupported SQL dialect: {0}")]
+    UnsupportedSqlDialect(String),
+
+    #[error("Unsupported line ending: {0}")]
+    UnsupportedLineEnding(String),
+
+    #[error("Unsupported description policy: {0}")]
+    UnsupportedDescriptionPolicy(String),
+
+    #[error("Unsupported schema version: {0}")]
+    UnsupportedSchemaVersion(String),
+
+    #[error("Unsupported partitioning: {0}")]
+    UnsupportedPartitioning(String),
+
+    #[error("Invalid statement period: {0}: expected YYYY-MM or SINCE..UNTIL")]
+    InvalidPeriod(String),
+
+    #[error("Invalid page token: {0}")]
+    InvalidPageToken(String),
+
+    #[cfg(feature = "encoding")]
+    #[error("Unsupported encoding: {0}")]
+    UnsupportedEncoding(String),
+}
+
+/// Ошибка подписи или проверки подписи данных.
+#[cfg(feature = "signing")]
+#[derive(Error, Debug)]
+pub enum SigningError {
+    #[error("Invalid key: {0}")]
+    InvalidKey(String),
+
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+
+    #[error("Signature verification failed: data were modified or signed with another key")]
+    VerificationFailed,
+
+    #[error(transparent)]
+    Read(#[from] ReadError),
+}
+
+/// Ошибка шифрования или расшифровки данных.
+#[cfg(feature = "encryption")]
+#[derive(Error, Debug)]
+pub enum EncryptionError {
+    #[error("Invalid encryption key: {0}")]
+    InvalidKey(String),
+
+    #[error("Environment variable {0} with the encryption key is not set")]
+    MissingKey(String),
+
+    #[error("Data is not an encrypted container")]
+    NotEncrypted,
+
+    #[error("Unsupported encrypted container version: {0}")]
+    UnsupportedVersion(u32),
+
+    #[error("Decryption failed: data were modified or encrypted with another key")]
+    DecryptionFailed,
+
+    #[error("Encryption failed")]
+    EncryptionFailed,
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Read(#[from] ReadError),
+
+    #[error(transparent)]
+    Write(#[from] WriteError),
+}
+
+/// Ошибка дозаписи записей о транзакциях в архив файлов в бинарном формате.
+#[derive(Error, Debug)]
+pub enum ArchiveError {
+    #[error("Archive file error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Cannot append to {}: {source}", path.display())]
+    CorruptedTail { path: PathBuf, source: ReadError },
+
+    #[error("Cannot append to {}: the file starts with a container header", .0.display())]
+    Container(PathBuf),
+
+    #[error(transparent)]
+    Write(#[from] WriteError),
+}
+
+/// Ошибка внешней сортировки записей о транзакциях.
+#[derive(Error, Debug)]
+pub enum SortError {
+    #[error("Temporary file error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Read(#[from] ReadError),
+
+    #[error(transparent)]
+    Write(#[from] WriteError),
 }
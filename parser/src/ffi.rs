@@ -0,0 +1,434 @@
+//! Модуль интерфейса C для встраивания библиотеки в существующие системы.
+//!
+//! Функции экспортируются из динамической библиотеки крейта `ypbank` под именами с префиксом
+//! `ypbank_` и описаны в заголовочном файле `include/ypbank.h`. Форматы указываются так же,
+//! как в конвертере (`text`, `csv`, `bin`, `json`, `jsonl`).
+//!
+//! Функции, завершившиеся ошибкой, возвращают `NULL` или `-1`, а текст ошибки доступен через
+//! [`ypbank_last_error`] в том же потоке. Паника внутри библиотеки не пересекает границу FFI
+//! и сообщается как ошибка.
+
+use super::YPBankImpl;
+use super::record::Record;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+thread_local! {
+    /// Текст последней ошибки в текущем потоке.
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Запись о транзакции в представлении C.
+///
+/// Строки заканчиваются нулевым символом и действительны до освобождения набора записей
+/// функцией [`ypbank_records_free`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct YPBankRecord {
+    /// Идентификатор транзакции.
+    pub tx_id: u64,
+
+    /// Тип транзакции (например, `DEPOSIT`).
+    pub tx_type: *const c_char,
+
+    /// Идентификатор отправителя.
+    pub from_user_id: u64,
+
+    /// Идентификатор получателя.
+    pub to_user_id: u64,
+
+    /// Младшие 64 бита суммы транзакции (128-битного целого со знаком).
+    pub amount_lo: u64,
+
+    /// Старшие 64 бита суммы транзакции; 0 для сумм в пределах [`u64`].
+    pub amount_hi: i64,
+
+    /// Unix epoch timestamp в миллисекундах.
+    pub timestamp: u64,
+
+    /// Состояние транзакции (например, `SUCCESS`).
+    pub status: *const c_char,
+
+    /// Описание транзакции.
+    pub description: *const c_char,
+
+    /// Длина описания транзакции в байтах без завершающего нулевого символа.
+    ///
+    /// Описание может содержать нулевые символы, поэтому его длину следует брать из этого поля.
+    pub description_len: usize,
+
+    /// Код валюты транзакции или `NULL`, если валюта не указана.
+    pub currency: *const c_char,
+}
+
+/// Набор записей о транзакциях, считанный функцией [`ypbank_parse`].
+#[derive(Debug)]
+pub struct YPBankRecords {
+    /// Записи в представлении C.
+    records: Vec<YPBankRecord>,
+
+    /// Строки, на которые ссылаются записи; хранятся, пока существует набор.
+    _strings: Vec<Box<[u8]>>,
+}
+
+impl YPBankRecords {
+    /// Подготовить представление C указанных записей.
+    fn new(records: &[Record]) -> Self {
+        let mut strings = vec![];
+
+        let mut string = |value: &str| {
+            let mut bytes = Vec::with_capacity(value.len() + 1);
+            bytes.extend_from_slice(value.as_bytes());
+            bytes.push(0);
+
+            let bytes = bytes.into_boxed_slice();
+            let ptr = bytes.as_ptr().cast();
+            strings.push(bytes);
+
+            ptr
+        };
+
+        let records = records
+            .iter()
+            .map(|record| YPBankRecord {
+                tx_id: record.tx_id(),
+                tx_type: string(&record.tx_type().to_string()),
+                from_user_id: record.from_user_id(),
+                to_user_id: record.to_user_id(),
+                amount_lo: record.wide_amount() as u64,
+                amount_hi: (record.wide_amount() >> 64) as i64,
+                timestamp: record.timestamp(),
+                status: string(&record.status().to_string()),
+                description: string(record.description()),
+                description_len: record.description().len(),
+                currency: record
+                    .currency()
+                    .map_or(ptr::null(), |currency| string(currency.as_str())),
+            })
+            .collect();
+
+        Self {
+            records,
+            _strings: strings,
+        }
+    }
+}
+
+/// Выполнить функцию, сохранив текст ошибки или паники для [`ypbank_last_error`].
+///
+/// Возвращает `None` при ошибке.
+fn catch<T>(f: impl FnOnce() -> Result<T, String>) -> Option<T> {
+    let result = panic::catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|_| Err("Internal error: parser panicked".to_string()));
+
+    let (value, error) = match result {
+        Ok(value) => (Some(value), None),
+        Err(error) => (None, Some(error)),
+    };
+
+    LAST_ERROR.with_borrow_mut(|last_error| {
+        *last_error = error.map(|error| CString::new(error.replace('\0', " ")).unwrap_or_default());
+    });
+
+    value
+}
+
+/// Получить формат данных по его названию в строке C.
+///
+/// # Safety
+///
+/// `format` должен быть `NULL` или указывать на строку, заканчивающуюся нулевым символом.
+unsafe fn format_from(format: *const c_char) -> Result<YPBankImpl, String> {
+    if format.is_null() {
+        return Err("Data format is NULL".to_string());
+    }
+
+    // SAFETY: по контракту функции строка заканчивается нулевым символом.
+    let format = unsafe { CStr::from_ptr(format) }.to_string_lossy();
+
+    YPBankImpl::try_from(format.as_ref()).map_err(|e| e.to_string())
+}
+
+/// Получить данные по указателю и длине.
+///
+/// # Safety
+///
+/// `data` должен указывать на `len` байтов или быть `NULL` при нулевой длине.
+unsafe fn data_from<'a>(data: *const u8, len: usize) -> Result<&'a [u8], String> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+
+    if data.is_null() {
+        return Err("Data is NULL".to_string());
+    }
+
+    // SAFETY: по контракту функции указатель указывает на `len` байтов.
+    Ok(unsafe { slice::from_raw_parts(data, len) })
+}
+
+/// Считать записи о транзакциях в указанном формате из `len` байтов данных.
+///
+/// Возвращает набор записей, который необходимо освободить функцией [`ypbank_records_free`],
+/// или `NULL` при ошибке (см. [`ypbank_last_error`]).
+///
+/// # Safety
+///
+/// `data` должен указывать на `len` байтов (или быть `NULL` при нулевой длине), а `format` —
+/// на строку, заканчивающуюся нулевым символом.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ypbank_parse(
+    data: *const u8,
+    len: usize,
+    format: *const c_char,
+) -> *mut YPBankRecords {
+    catch(|| {
+        // SAFETY: контракт передается вызывающей стороне.
+        let (data, format) = unsafe { (data_from(data, len)?, format_from(format)?) };
+
+        let records = format
+            .read_from(&mut &data[..])
+            .map_err(|e| e.to_string())?;
+
+        Ok(Box::into_raw(Box::new(YPBankRecords::new(&records))))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Получить количество записей в наборе.
+///
+/// # Safety
+///
+/// `records` должен быть получен от [`ypbank_parse`] и еще не освобожден.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ypbank_records_len(records: *const YPBankRecords) -> usize {
+    // SAFETY: по контракту функции указатель получен от `ypbank_parse`.
+    unsafe { records.as_ref() }.map_or(0, |records| records.records.len())
+}
+
+/// Получить запись набора с указанным номером (начиная с 0).
+///
+/// Возвращает `NULL`, если в наборе меньше записей. Запись действительна до освобождения набора.
+///
+/// # Safety
+///
+/// `records` должен быть получен от [`ypbank_parse`] и еще не освобожден.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ypbank_records_get(
+    records: *const YPBankRecords,
+    index: usize,
+) -> *const YPBankRecord {
+    // SAFETY: по контракту функции указатель получен от `ypbank_parse`.
+    unsafe { records.as_ref() }
+        .and_then(|records| records.records.get(index))
+        .map_or(ptr::null(), ptr::from_ref)
+}
+
+/// Освободить набор записей, полученный от [`ypbank_parse`].
+///
+/// # Safety
+///
+/// `records` должен быть `NULL` или получен от [`ypbank_parse`] и еще не освобожден.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ypbank_records_free(records: *mut YPBankRecords) {
+    if !records.is_null() {
+        // SAFETY: по контракту функции указатель получен от `Box::into_raw`.
+        drop(unsafe { Box::from_raw(records) });
+    }
+}
+
+/// Конвертировать `len` байтов данных о транзакциях из формата `from` в формат `to`.
+///
+/// При успехе записывает в `output` и `output_len` указатель на данные в целевом формате и их
+/// длину и возвращает 0; данные необходимо освободить функцией [`ypbank_free`]. При ошибке
+/// возвращает -1 (см. [`ypbank_last_error`]).
+///
+/// # Safety
+///
+/// `data` должен указывать на `len` байтов (или быть `NULL` при нулевой длине), `from` и `to` —
+/// на строки, заканчивающиеся нулевым символом, а `output` и `output_len` — на доступные для
+/// записи значения.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ypbank_convert(
+    data: *const u8,
+    len: usize,
+    from: *const c_char,
+    to: *const c_char,
+    output: *mut *mut u8,
+    output_len: *mut usize,
+) -> i32 {
+    let converted = catch(|| {
+        if output.is_null() || output_len.is_null() {
+            return Err("Output pointer is NULL".to_string());
+        }
+
+        // SAFETY: контракт передается вызывающей стороне.
+        let (data, from, to) =
+            unsafe { (data_from(data, len)?, format_from(from)?, format_from(to)?) };
+
+        let records = from.read_from(&mut &data[..]).map_err(|e| e.to_string())?;
+
+        let mut converted = vec![];
+        to.write_to(records, &mut converted)
+            .map_err(|e| e.to_string())?;
+
+        Ok(converted.into_boxed_slice())
+    });
+
+    let Some(converted) = converted else {
+        return -1;
+    };
+
+    // SAFETY: указатели проверены на `NULL`, а по контракту функции доступны для записи.
+    unsafe {
+        *output_len = converted.len();
+        *output = Box::into_raw(converted).cast();
+    }
+
+    0
+}
+
+/// Освободить данные длиной `len`, полученные от [`ypbank_convert`].
+///
+/// # Safety
+///
+/// `data` должен быть `NULL` или получен от [`ypbank_convert`] вместе с длиной `len`
+/// и еще не освобожден.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ypbank_free(data: *mut u8, len: usize) {
+    if !data.is_null() {
+        // SAFETY: по контракту функции данные получены от `Box::into_raw` с длиной `len`.
+        drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(data, len)) });
+    }
+}
+
+/// Получить текст ошибки последнего вызова функции библиотеки в текущем потоке.
+///
+/// Возвращает `NULL`, если последний вызов завершился успешно. Строка действительна до
+/// следующего вызова функции библиотеки в том же потоке.
+#[unsafe(no_mangle)]
+pub extern "C" fn ypbank_last_error() -> *const c_char {
+    LAST_ERROR.with_borrow(|last_error| last_error.as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_data(format: YPBankImpl) -> Vec<u8> {
+        let mut data = vec![];
+        format
+            .write_to(crate::tests::get_data_to_write(), &mut data)
+            .unwrap();
+
+        data
+    }
+
+    fn last_error() -> Option<String> {
+        let error = ypbank_last_error();
+
+        (!error.is_null()).then(|| {
+            unsafe { CStr::from_ptr(error) }
+                .to_string_lossy()
+                .to_string()
+        })
+    }
+
+    #[test]
+    fn test_parse() {
+        let data = get_data(YPBankImpl::Bin);
+        let expected = crate::tests::get_data_to_write();
+
+        unsafe {
+            let records = ypbank_parse(data.as_ptr(), data.len(), c"bin".as_ptr());
+
+            assert!(!records.is_null());
+            assert_eq!(last_error(), None);
+            assert_eq!(ypbank_records_len(records), expected.len());
+            assert!(ypbank_records_get(records, expected.len()).is_null());
+
+            let record = &*ypbank_records_get(records, 1);
+
+            assert_eq!(record.tx_id, expected[1].tx_id());
+            assert_eq!(CStr::from_ptr(record.tx_type).to_str(), Ok("TRANSFER"));
+            assert_eq!(record.amount_lo, expected[1].wide_amount() as u64);
+            assert_eq!(record.amount_hi, 0);
+            assert_eq!(
+                slice::from_raw_parts(record.description.cast::<u8>(), record.description_len),
+                expected[1].description().as_bytes()
+            );
+            assert!(record.currency.is_null());
+
+            ypbank_records_free(records);
+        }
+    }
+
+    #[test]
+    fn test_parse_wide_amount() {
+        let mut record = crate::tests::get_data_to_write().remove(0);
+        record.set_wide_amount(-2);
+
+        let records = YPBankRecords::new(&[record]);
+
+        assert_eq!(records.records[0].amount_lo, u64::MAX - 1);
+        assert_eq!(records.records[0].amount_hi, -1);
+    }
+
+    #[test]
+    fn test_convert() {
+        let data = get_data(YPBankImpl::Text);
+        let mut output = ptr::null_mut();
+        let mut output_len = 0;
+
+        unsafe {
+            let result = ypbank_convert(
+                data.as_ptr(),
+                data.len(),
+                c"text".as_ptr(),
+                c"csv".as_ptr(),
+                &mut output,
+                &mut output_len,
+            );
+
+            assert_eq!(result, 0);
+            assert_eq!(
+                slice::from_raw_parts(output, output_len),
+                get_data(YPBankImpl::Csv)
+            );
+
+            ypbank_free(output, output_len);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let data = get_data(YPBankImpl::Text);
+
+        unsafe {
+            let records = ypbank_parse(data.as_ptr(), data.len(), c"xml".as_ptr());
+
+            assert!(records.is_null());
+            assert_eq!(last_error().unwrap(), "Invalid data format: xml");
+
+            let records = ypbank_parse(data.as_ptr(), data.len(), c"bin".as_ptr());
+
+            assert!(records.is_null());
+            assert!(last_error().is_some());
+
+            let result = ypbank_convert(
+                data.as_ptr(),
+                data.len(),
+                c"text".as_ptr(),
+                c"csv".as_ptr(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            );
+
+            assert_eq!(result, -1);
+            assert_eq!(last_error().unwrap(), "Output pointer is NULL");
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! Модуль отбора записей о транзакциях по значениям полей.
+
+use super::record::{Record, Status, TxType};
+
+/// Условия отбора записей о транзакциях.
+///
+/// Запись удовлетворяет фильтру, если она удовлетворяет всем заданным условиям.
+/// Фильтр по умолчанию не содержит условий и пропускает все записи.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Filter {
+    /// Состояние транзакции.
+    pub status: Option<Status>,
+
+    /// Тип транзакции.
+    pub tx_type: Option<TxType>,
+
+    /// Идентификатор отправителя.
+    pub from_user_id: Option<u64>,
+
+    /// Идентификатор получателя.
+    pub to_user_id: Option<u64>,
+
+    /// Минимальная сумма транзакции (включительно).
+    pub min_amount: Option<u64>,
+
+    /// Минимальная метка времени (включительно).
+    pub since: Option<u64>,
+
+    /// Максимальная метка времени (не включительно).
+    pub until: Option<u64>,
+}
+
+impl Filter {
+    /// Признак отсутствия условий отбора.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Проверить, удовлетворяет ли запись о транзакции всем условиям отбора.
+    pub fn matches(&self, record: &Record) -> bool {
+        self.status
+            .as_ref()
+            .is_none_or(|status| record.status() == status)
+            && self
+                .tx_type
+                .as_ref()
+                .is_none_or(|tx_type| record.tx_type() == tx_type)
+            && self
+                .from_user_id
+                .is_none_or(|from_user_id| record.from_user_id() == from_user_id)
+            && self
+                .to_user_id
+                .is_none_or(|to_user_id| record.to_user_id() == to_user_id)
+            && self
+                .min_amount
+                .is_none_or(|min_amount| record.wide_amount() >= i128::from(min_amount))
+            && self.since.is_none_or(|since| record.timestamp() >= since)
+            && self.until.is_none_or(|until| record.timestamp() < until)
+    }
+}
+
+/// Оставить только записи, удовлетворяющие фильтру, сохранив их порядок.
+///
+/// Возвращает количество удаленных записей.
+pub(crate) fn retain(records: &mut Vec<Record>, filter: &Filter) -> usize {
+    let count = records.len();
+
+    records.retain(|record| filter.matches(record));
+
+    count - records.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn get_records() -> Vec<Record> {
+        vec![
+            Record::new(
+                1,
+                TxType::Deposit,
+                0,
+                10,
+                100,
+                1000,
+                Status::Success,
+                "".to_string(),
+            ),
+            Record::new(
+                2,
+                TxType::Transfer,
+                10,
+                20,
+                50,
+                2000,
+                Status::Failure,
+                "".to_string(),
+            ),
+            Record::new(
+                3,
+                TxType::Withdrawal,
+                20,
+                0,
+                500,
+                3000,
+                Status::Success,
+                "".to_string(),
+            ),
+        ]
+    }
+
+    #[rstest]
+    #[case(Filter::default(), vec![1, 2, 3])]
+    #[case(Filter { status: Some(Status::Success), ..Default::default() }, vec![1, 3])]
+    #[case(Filter { tx_type: Some(TxType::Transfer), ..Default::default() }, vec![2])]
+    #[case(Filter { from_user_id: Some(20), ..Default::default() }, vec![3])]
+    #[case(Filter { to_user_id: Some(10), ..Default::default() }, vec![1])]
+    #[case(Filter { min_amount: Some(100), ..Default::default() }, vec![1, 3])]
+    #[case(Filter { since: Some(2000), until: Some(3000), ..Default::default() }, vec![2])]
+    #[case(
+        Filter { status: Some(Status::Success), min_amount: Some(200), ..Default::default() },
+        vec![3]
+    )]
+    fn test_filter(#[case] filter: Filter, #[case] tx_ids: Vec<u64>) {
+        let mut records = get_records();
+
+        let removed = retain(&mut records, &filter);
+
+        assert_eq!(removed, 3 - tx_ids.len());
+        assert_eq!(
+            records.iter().map(Record::tx_id).collect::<Vec<_>>(),
+            tx_ids
+        );
+    }
+
+    #[test]
+    fn test_is_empty() {
+        assert!(Filter::default().is_empty());
+        assert!(
+            !Filter {
+                until: Some(0),
+                ..Default::default()
+            }
+            .is_empty()
+        );
+    }
+}
@@ -0,0 +1,147 @@
+//! Модуль вычисления отпечатка набора записей о транзакциях.
+
+use super::record::Record;
+use sha2::{Digest, Sha256};
+
+/// Отпечаток набора записей о транзакциях — хеш SHA-256 канонических представлений записей
+/// (см. [`Record::hash_stable`]) в порядке их следования.
+///
+/// Отпечаток не зависит от формата, из которого были считаны записи, поэтому большие выгрузки
+/// можно сравнить, обменявшись только отпечатками. Записи добавляются по одной, так что отпечаток
+/// вычисляется и при потоковом чтении. Для сравнения наборов независимо от порядка записей
+/// их следует предварительно отсортировать (см. [`crate::YPBank::sort_by_keys`]).
+#[derive(Debug, Clone, Default)]
+pub struct Fingerprint {
+    /// Состояние вычисления хеша.
+    hasher: Sha256,
+
+    /// Буфер канонического представления очередной записи.
+    buffer: Vec<u8>,
+}
+
+impl Fingerprint {
+    /// Начать вычисление отпечатка пустого набора записей.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Добавить очередную запись о транзакции.
+    pub fn update(&mut self, record: &Record) {
+        self.buffer.clear();
+        record.write_canonical(&mut self.buffer);
+
+        self.hasher.update(&self.buffer);
+    }
+
+    /// Завершить вычисление и вернуть отпечаток.
+    pub fn finish(self) -> [u8; 32] {
+        self.hasher.finalize().into()
+    }
+}
+
+/// Реализация трейта [`Extend<&Record>`] для [`Fingerprint`].
+impl<'a> Extend<&'a Record> for Fingerprint {
+    /// Реализация метода [`Extend<&Record>::extend`] для [`Fingerprint`].
+    fn extend<I: IntoIterator<Item = &'a Record>>(&mut self, iter: I) {
+        for record in iter {
+            self.update(record);
+        }
+    }
+}
+
+/// Представить отпечаток, хеш или подпись в виде шестнадцатеричной строки.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Разобрать шестнадцатеричную строку из `2 * N` цифр (например, отпечаток или ключ).
+pub fn from_hex<const N: usize>(s: &str) -> Option<[u8; N]> {
+    if s.len() != 2 * N || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let mut bytes = [0u8; N];
+    for (byte, i) in bytes.iter_mut().zip((0..s.len()).step_by(2)) {
+        *byte = u8::from_str_radix(&s[i..i + 2], 16).ok()?;
+    }
+
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RecordKey, SortOrder, YPBank, YPBankBin, YPBankImpl, YPBankText};
+    use rstest::rstest;
+
+    #[test]
+    fn test_hash_stable() {
+        let record = &crate::tests::get_data_to_write()[0];
+
+        assert_eq!(
+            to_hex(&record.hash_stable()),
+            "dde138ebaec3c92af05181b4e077e8f90c724dfe8f8be1d5e756ead47bc7800b"
+        );
+    }
+
+    #[rstest]
+    #[case("00ff7a", Some([0x00, 0xff, 0x7a]))]
+    #[case("00FF7A", Some([0x00, 0xff, 0x7a]))]
+    #[case("00ff7", None)]
+    #[case("00ff7g", None)]
+    #[case("+0ff7a", None)]
+    fn test_from_hex(#[case] s: &str, #[case] expected: Option<[u8; 3]>) {
+        assert_eq!(from_hex(s), expected);
+    }
+
+    #[test]
+    fn test_fingerprint_empty() {
+        assert_eq!(
+            to_hex(&Fingerprint::new().finish()),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_single_record() {
+        let record = &crate::tests::get_data_to_write()[0];
+
+        let mut fingerprint = Fingerprint::new();
+        fingerprint.update(record);
+
+        assert_eq!(fingerprint.finish(), record.hash_stable());
+    }
+
+    #[rstest]
+    fn test_fingerprint_independent_of_format(
+        #[values("text", "csv", "bin", "json", "jsonl")] format: &str,
+    ) {
+        let records = crate::tests::get_data_to_write();
+        let expected = YPBankText::from_records(records.clone()).fingerprint();
+
+        let format = YPBankImpl::try_from(format).unwrap();
+        let mut data = vec![];
+        format.write_to(records, &mut data).unwrap();
+
+        let mut fingerprint = Fingerprint::new();
+        for record in format.iter_from(data.as_slice()) {
+            fingerprint.update(&record.unwrap());
+        }
+
+        assert_eq!(fingerprint.finish(), expected);
+    }
+
+    #[test]
+    fn test_fingerprint_depends_on_order_and_values() {
+        let records = crate::tests::get_data_to_write();
+        let expected = YPBankBin::from_records(records.clone()).fingerprint();
+
+        let mut reversed = YPBankBin::from_records(records.clone());
+        reversed.sort_by(RecordKey::TxId, SortOrder::Descending);
+        assert_ne!(reversed.fingerprint(), expected);
+
+        let mut changed = YPBankBin::from_records(records);
+        changed.records_mut()[0].set_amount(1);
+        assert_ne!(changed.fingerprint(), expected);
+    }
+}
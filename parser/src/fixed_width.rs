@@ -0,0 +1,586 @@
+//! Модуль чтения и записи данных о транзакциях в формате с фиксированной шириной полей.
+//!
+//! Каждая запись занимает одну строку, поля которой расположены на фиксированных позициях
+//! согласно разметке [`FixedWidthLayout`] и дополнены до своей ширины символом-заполнителем.
+
+use super::errors::{FixedWidthLayoutError, ReadError, WriteError};
+use super::iter::{RecordIter, recover_nothing};
+use super::options::ParseContext;
+use super::record::errors::ParseRecordFromFixedWidthError;
+use super::record::{Record, RecordKey};
+use super::writer::RecordWriter;
+use std::collections::HashSet;
+use std::io::{BufRead, BufWriter, Read, Write};
+
+/// Выравнивание значения внутри поля.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    /// По левому краю (заполнитель добавляется справа).
+    Left,
+
+    /// По правому краю (заполнитель добавляется слева).
+    Right,
+}
+
+/// Реализация трейта [`TryFrom<&str>`] для [`Alignment`].
+impl TryFrom<&str> for Alignment {
+    /// Ошибка описания разметки полей.
+    type Error = FixedWidthLayoutError;
+
+    /// Реализация метода [`TryFrom<&str>::try_from`] для [`Alignment`].
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "left" | "l" => Ok(Self::Left),
+            "right" | "r" => Ok(Self::Right),
+            _ => Err(FixedWidthLayoutError::InvalidAlignment(s.to_string())),
+        }
+    }
+}
+
+/// Описание поля в формате с фиксированной шириной полей.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedWidthField {
+    /// Ключ поля записи.
+    pub key: RecordKey,
+
+    /// Ширина поля в символах.
+    pub width: usize,
+
+    /// Выравнивание значения внутри поля.
+    pub align: Alignment,
+
+    /// Символ-заполнитель.
+    pub padding: char,
+}
+
+impl FixedWidthField {
+    /// Создание описания поля указанной ширины.
+    ///
+    /// Числовые поля по умолчанию выравниваются по правому краю и дополняются нулями,
+    /// остальные — по левому краю и дополняются пробелами.
+    pub fn new(key: RecordKey, width: usize) -> Self {
+        if Self::is_numeric(key) {
+            Self {
+                key,
+                width,
+                align: Alignment::Right,
+                padding: '0',
+            }
+        } else {
+            Self {
+                key,
+                width,
+                align: Alignment::Left,
+                padding: ' ',
+            }
+        }
+    }
+
+    /// Указать выравнивание значения внутри поля.
+    pub fn align(self, align: Alignment) -> Self {
+        Self { align, ..self }
+    }
+
+    /// Указать символ-заполнитель.
+    pub fn padding(self, padding: char) -> Self {
+        Self { padding, ..self }
+    }
+
+    /// Признак числового поля записи.
+    fn is_numeric(key: RecordKey) -> bool {
+        matches!(
+            key,
+            RecordKey::TxId
+                | RecordKey::FromUserId
+                | RecordKey::ToUserId
+                | RecordKey::Amount
+                | RecordKey::Timestamp
+        )
+    }
+
+    /// Проверить корректность описания поля.
+    ///
+    /// Заполнитель-цифра допускается только для числовых полей с выравниванием по правому краю
+    /// и только `0`, иначе его невозможно отличить от значения.
+    fn validate(&self) -> Result<(), FixedWidthLayoutError> {
+        if self.width == 0 {
+            return Err(FixedWidthLayoutError::ZeroWidth(self.key));
+        }
+
+        if self.padding.is_ascii_digit()
+            && !(self.padding == '0'
+                && self.align == Alignment::Right
+                && Self::is_numeric(self.key))
+        {
+            return Err(FixedWidthLayoutError::AmbiguousPadding(self.key));
+        }
+
+        Ok(())
+    }
+
+    /// Получить значение поля без символов-заполнителей.
+    fn trim<'a>(&self, value: &'a str) -> &'a str {
+        let trimmed = match self.align {
+            Alignment::Left => value.trim_end_matches(self.padding),
+            Alignment::Right => value.trim_start_matches(self.padding),
+        };
+
+        // Нулевое значение числового поля, дополненного нулями, состоит из одних заполнителей.
+        if trimmed.is_empty() && self.padding == '0' {
+            &value[value.len() - 1..]
+        } else {
+            trimmed
+        }
+    }
+
+    /// Записать значение поля записи, дополнив его до ширины поля.
+    fn format(&self, record: &Record) -> Result<String, WriteError> {
+        let value = match self.key {
+            RecordKey::Description => record.description().to_string(),
+            key => record.field_to_string(key),
+        };
+
+        let error = |reason: String| WriteError::FixedWidthValue {
+            key: self.key,
+            value: value.clone(),
+            reason,
+        };
+
+        if value.contains(['\n', '\r']) {
+            return Err(error("line breaks are not allowed".to_string()));
+        }
+
+        let len = value.chars().count();
+
+        if len > self.width {
+            return Err(error(format!("longer than {} characters", self.width)));
+        }
+
+        let padding = self.padding.to_string().repeat(self.width - len);
+
+        Ok(match self.align {
+            Alignment::Left => value + &padding,
+            // Знак отрицательной суммы предшествует нулям-заполнителям, как в `-0042`.
+            Alignment::Right if self.padding == '0' && value.starts_with('-') => {
+                format!("-{padding}{}", &value[1..])
+            }
+            Alignment::Right => padding + &value,
+        })
+    }
+}
+
+/// Реализация трейта [`TryFrom<&str>`] для [`FixedWidthField`].
+impl TryFrom<&str> for FixedWidthField {
+    /// Ошибка описания разметки полей.
+    type Error = FixedWidthLayoutError;
+
+    /// Реализация метода [`TryFrom<&str>::try_from`] для [`FixedWidthField`].
+    ///
+    /// Поле описывается строкой вида `KEY:WIDTH[:ALIGN[:PADDING]]`, где `ALIGN` — `left`
+    /// или `right`, а `PADDING` — один символ.
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        let invalid = || FixedWidthLayoutError::InvalidFieldSpec(s.to_string());
+
+        let mut parts = s.splitn(4, ':');
+
+        let key = RecordKey::try_from(parts.next().ok_or_else(invalid)?.trim())?;
+        let width = parts
+            .next()
+            .ok_or_else(invalid)?
+            .trim()
+            .parse()
+            .map_err(|_| invalid())?;
+
+        let mut field = Self::new(key, width);
+
+        if let Some(align) = parts.next() {
+            field = field.align(Alignment::try_from(align.trim())?);
+        }
+
+        if let Some(padding) = parts.next() {
+            let mut chars = padding.chars();
+
+            field = match (chars.next(), chars.next()) {
+                (Some(padding), None) => field.padding(padding),
+                _ => return Err(invalid()),
+            };
+        }
+
+        Ok(field)
+    }
+}
+
+/// Разметка полей формата с фиксированной шириной полей.
+///
+/// Определяет порядок, ширину, выравнивание и символ-заполнитель каждого поля записи;
+/// каждое поле записи должно быть указано ровно один раз. Ширина измеряется в символах.
+///
+/// При чтении символы-заполнители отбрасываются со стороны, противоположной выравниванию,
+/// поэтому значения, начинающиеся или заканчивающиеся таким символом с этой стороны,
+/// не сохраняются без изменений.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedWidthLayout {
+    /// Описания полей в порядке их следования в строке.
+    fields: Vec<FixedWidthField>,
+}
+
+impl FixedWidthLayout {
+    /// Создание разметки с проверкой описаний полей.
+    pub fn new(fields: Vec<FixedWidthField>) -> Result<Self, FixedWidthLayoutError> {
+        let mut keys = HashSet::new();
+
+        for field in &fields {
+            field.validate()?;
+
+            if !keys.insert(field.key) {
+                return Err(FixedWidthLayoutError::DuplicateField(field.key));
+            }
+        }
+
+        if let Some(key) = Record::EXPECTED_KEYS
+            .into_iter()
+            .find(|key| !keys.contains(key))
+        {
+            return Err(FixedWidthLayoutError::MissingField(key));
+        }
+
+        Ok(Self { fields })
+    }
+
+    /// Описания полей в порядке их следования в строке.
+    pub fn fields(&self) -> &[FixedWidthField] {
+        &self.fields
+    }
+
+    /// Длина строки записи в символах.
+    pub fn line_width(&self) -> usize {
+        self.fields.iter().map(|field| field.width).sum()
+    }
+
+    /// Разобрать строку записи (без символов перевода строки) с указанными параметрами разбора.
+    fn parse_line(
+        &self,
+        line: &str,
+        context: &ParseContext,
+    ) -> Result<Record, ParseRecordFromFixedWidthError> {
+        let actual = line.chars().count();
+
+        if actual != self.line_width() {
+            return Err(ParseRecordFromFixedWidthError::InvalidLineLength {
+                expected: self.line_width(),
+                actual,
+            });
+        }
+
+        let mut record = Record::default();
+        let mut chars = line.chars();
+
+        for field in &self.fields {
+            let value = chars.by_ref().take(field.width).collect::<String>();
+
+            record.validate_and_set_unquoted_value_by_key(
+                field.key,
+                field.trim(&value),
+                context,
+            )?;
+        }
+
+        Ok(record)
+    }
+
+    /// Считать очередную запись о банковской операции в формате с фиксированной шириной полей.
+    ///
+    /// Пустые строки пропускаются. Строка считывается из источника целиком до ее разбора,
+    /// поэтому после ошибки чтение может быть продолжено со следующей строки.
+    fn read_record<B: BufRead>(
+        &self,
+        reader: &mut B,
+        context: &ParseContext,
+    ) -> Result<Option<Record>, ReadError> {
+        let mut line = String::new();
+
+        while reader.read_line(&mut line)? > 0 {
+            let content = line.trim_end_matches(['\r', '\n']);
+
+            if !content.is_empty() {
+                return Ok(Some(self.parse_line(content, context)?));
+            }
+
+            line.clear();
+        }
+
+        Ok(None)
+    }
+
+    /// Записать очередную запись о банковской операции в формате с фиксированной шириной полей.
+    fn write_record<W: Write>(
+        &self,
+        writer: &mut BufWriter<W>,
+        record: &Record,
+    ) -> Result<(), WriteError> {
+        let line = self
+            .fields
+            .iter()
+            .map(|field| field.format(record))
+            .collect::<Result<String, _>>()?;
+
+        writeln!(writer, "{line}")?;
+
+        Ok(())
+    }
+
+    /// Считать записи о транзакциях согласно разметке.
+    pub fn read_from<R: Read>(&self, r: &mut R) -> Result<Vec<Record>, ReadError> {
+        self.iter_from(r).read_all()
+    }
+
+    /// Последовательно считывать записи о транзакциях согласно разметке.
+    pub fn iter_from<R: Read>(&self, r: R) -> RecordIter<R> {
+        let layout = self.clone();
+
+        RecordIter::new_with(r, move |reader, _index, context| {
+            layout.read_record(reader, context)
+        })
+        .with_recovery(recover_nothing)
+    }
+
+    /// Записать записи о транзакциях согласно разметке.
+    pub fn write_to<W: Write>(&self, records: &[Record], w: &mut W) -> Result<(), WriteError> {
+        let mut writer = self.writer_to(w);
+
+        for record in records {
+            writer.write_record(record)?;
+        }
+
+        writer.finish()?;
+
+        Ok(())
+    }
+
+    /// Последовательно записывать записи о транзакциях согласно разметке.
+    pub fn writer_to<W: Write>(&self, w: W) -> RecordWriter<W> {
+        let layout = self.clone();
+
+        RecordWriter::new_with(
+            w,
+            move |writer, record, _index| layout.write_record(writer, record),
+            |_writer, _count| Ok(()),
+        )
+    }
+}
+
+/// Реализация трейта [`Default`] для [`FixedWidthLayout`].
+impl Default for FixedWidthLayout {
+    /// Разметка по умолчанию: поля в порядке их следования в записи, ширина числовых полей
+    /// достаточна для любого значения (20 символов, для TIMESTAMP — 13).
+    fn default() -> Self {
+        Self {
+            fields: vec![
+                FixedWidthField::new(RecordKey::TxId, 20),
+                FixedWidthField::new(RecordKey::TxType, 10),
+                FixedWidthField::new(RecordKey::FromUserId, 20),
+                FixedWidthField::new(RecordKey::ToUserId, 20),
+                FixedWidthField::new(RecordKey::Amount, 20),
+                FixedWidthField::new(RecordKey::Timestamp, 13),
+                FixedWidthField::new(RecordKey::Status, 7),
+                FixedWidthField::new(RecordKey::Description, 64),
+            ],
+        }
+    }
+}
+
+/// Реализация трейта [`TryFrom<&str>`] для [`FixedWidthLayout`].
+impl TryFrom<&str> for FixedWidthLayout {
+    /// Ошибка описания разметки полей.
+    type Error = FixedWidthLayoutError;
+
+    /// Реализация метода [`TryFrom<&str>::try_from`] для [`FixedWidthLayout`].
+    ///
+    /// Разметка описывается перечислением описаний полей через запятую
+    /// (см. [`FixedWidthField::try_from`]), например `TX_ID:10,TX_TYPE:10,...`.
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        let fields = s
+            .split(',')
+            .map(FixedWidthField::try_from)
+            .collect::<Result<_, _>>()?;
+
+        Self::new(fields)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SchemaVersion;
+    use crate::line_ending::{LineEnding, LineEndingWriter};
+    use crate::record::{Status, TxType};
+    use rstest::rstest;
+    use std::io::Cursor;
+
+    const LAYOUT: &str = "TX_ID:6,TX_TYPE:10,FROM_USER_ID:4,TO_USER_ID:4:right: ,AMOUNT:8,\
+                          TIMESTAMP:5,STATUS:8:right,DESCRIPTION:12";
+
+    #[test]
+    fn test_write_to_fixed_width() {
+        let layout = FixedWidthLayout::try_from(LAYOUT).unwrap();
+        let records = vec![
+            Record::new(
+                1001,
+                TxType::Deposit,
+                0,
+                501,
+                50000,
+                12345,
+                Status::Success,
+                "Funding".to_string(),
+            ),
+            Record::new(
+                1002,
+                TxType::Transfer,
+                501,
+                502,
+                150,
+                12346,
+                Status::Pending,
+                "".to_string(),
+            ),
+        ];
+
+        let mut buffer = vec![];
+        layout.write_to(&records, &mut buffer).unwrap();
+
+        assert_eq!(
+            String::from_utf8(buffer.clone()).unwrap(),
+            "001001DEPOSIT   0000 5010005000012345 SUCCESSFunding     \n\
+             001002TRANSFER  0501 5020000015012346 PENDING            \n"
+        );
+
+        assert_eq!(layout.read_from(&mut buffer.as_slice()).unwrap(), records);
+    }
+
+    #[test]
+    fn test_roundtrip_default_layout() {
+        let layout = FixedWidthLayout::default();
+        let records = crate::tests::get_data_to_write();
+
+        let mut buffer = vec![];
+        layout.write_to(&records, &mut buffer).unwrap();
+
+        let lines = String::from_utf8(buffer.clone()).unwrap();
+        assert!(
+            lines
+                .lines()
+                .all(|line| line.chars().count() == layout.line_width())
+        );
+
+        assert_eq!(layout.read_from(&mut buffer.as_slice()).unwrap(), records);
+    }
+
+    #[test]
+    fn test_roundtrip_wide_amounts() {
+        let layout = FixedWidthLayout::default();
+        let records = crate::tests::get_wide_data_to_write()[..2].to_vec();
+
+        let mut writer = layout.writer_to(vec![]).schema_version(SchemaVersion::V2);
+        for record in &records {
+            writer.write_record(record).unwrap();
+        }
+        let buffer = writer.finish().unwrap();
+
+        let lines = String::from_utf8(buffer.clone()).unwrap();
+        assert!(lines.contains("-00000000000000025001633036800000"));
+        assert!(lines.contains("184467440737095516161633036800000"));
+
+        assert_eq!(layout.read_from(&mut buffer.as_slice()).unwrap(), records);
+    }
+
+    #[test]
+    fn test_roundtrip_bom_crlf() {
+        let layout = FixedWidthLayout::default();
+        let records = crate::tests::get_data_to_write();
+
+        let mut writer = LineEndingWriter::new(vec![], LineEnding::CrLf, true);
+        layout.write_to(&records, &mut writer).unwrap();
+        let buffer = writer.into_inner().unwrap();
+
+        assert_eq!(layout.read_from(&mut buffer.as_slice()).unwrap(), records);
+    }
+
+    #[test]
+    fn test_read_from_fixed_width_invalid_line_length() {
+        let layout = FixedWidthLayout::try_from(LAYOUT).unwrap();
+        let mut cursor = Cursor::new("001001DEPOSIT\n");
+
+        let result = layout.read_from(&mut cursor).unwrap_err();
+
+        assert!(matches!(
+            result.inner(),
+            ReadError::FromFixedWidth(ParseRecordFromFixedWidthError::InvalidLineLength {
+                expected: 57,
+                actual: 13
+            })
+        ));
+        assert_eq!(
+            result.to_string(),
+            "Fixed-width format parsing error: Invalid line length: expected 57 characters, got 13 (at record 1, line 1, byte 0)"
+        );
+    }
+
+    #[test]
+    fn test_read_from_fixed_width_invalid_value() {
+        let layout = FixedWidthLayout::try_from(LAYOUT).unwrap();
+        let mut cursor = Cursor::new("00100XDEPOSIT   0000 5010005000012345 SUCCESSFunding     \n");
+
+        let result = layout.read_from(&mut cursor).unwrap_err();
+
+        assert_eq!(
+            result.to_string(),
+            "Fixed-width format parsing error: Invalid value: 100X (TX_ID is not a number) (at record 1, line 1, byte 0)"
+        );
+    }
+
+    #[test]
+    fn test_write_to_fixed_width_value_too_long() {
+        let layout = FixedWidthLayout::try_from(LAYOUT).unwrap();
+        let record = Record::new(
+            1001,
+            TxType::Deposit,
+            0,
+            501,
+            50000,
+            12345,
+            Status::Success,
+            "Initial account funding".to_string(),
+        );
+
+        let result = layout.write_to(&[record], &mut vec![]).unwrap_err();
+
+        assert_eq!(
+            result.to_string(),
+            "Cannot write DESCRIPTION value `Initial account funding` as a fixed-width field: longer than 12 characters"
+        );
+    }
+
+    #[rstest]
+    #[case("TX_ID", FixedWidthLayoutError::InvalidFieldSpec("TX_ID".to_string()))]
+    #[case("TX_ID:x", FixedWidthLayoutError::InvalidFieldSpec("TX_ID:x".to_string()))]
+    #[case("TX_ID:1:center", FixedWidthLayoutError::InvalidAlignment("center".to_string()))]
+    #[case("TX_ID:1:left:ab", FixedWidthLayoutError::InvalidFieldSpec("TX_ID:1:left:ab".to_string()))]
+    #[case("TX_ID:0", FixedWidthLayoutError::ZeroWidth(RecordKey::TxId))]
+    #[case(
+        "TX_ID:1:left",
+        FixedWidthLayoutError::AmbiguousPadding(RecordKey::TxId)
+    )]
+    #[case(
+        "STATUS:7:right:0",
+        FixedWidthLayoutError::AmbiguousPadding(RecordKey::Status)
+    )]
+    #[case(
+        "TX_ID:1,TX_ID:2",
+        FixedWidthLayoutError::DuplicateField(RecordKey::TxId)
+    )]
+    #[case("TX_ID:1", FixedWidthLayoutError::MissingField(RecordKey::TxType))]
+    fn test_layout_invalid(#[case] spec: &str, #[case] expected: FixedWidthLayoutError) {
+        assert_eq!(FixedWidthLayout::try_from(spec).unwrap_err(), expected);
+    }
+}
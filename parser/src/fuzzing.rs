@@ -0,0 +1,625 @@
+//! Модуль генерации произвольных записей о транзакциях для тестирования на основе свойств
+//! и фаззинга.
+//!
+//! Генерируются корректные записи, которые записываются и считываются без потерь во всех
+//! форматах при версии схемы по умолчанию, а также нестандартные записи ([`AdversarialRecord`])
+//! с граничными значениями полей, неизвестными типами и состояниями, широкими суммами,
+//! управляющими символами в описании и дополнительными полями. Нестандартную запись формат
+//! может отказаться записать, но не должен искажать ее при записи или чтении.
+//!
+//! Поддержка [`arbitrary`](https://docs.rs/arbitrary) включается признаком крейта `arbitrary`
+//! (реализации трейта `Arbitrary` для [`Record`] и [`AdversarialRecord`]), а стратегии
+//! [`proptest`](https://docs.rs/proptest) — признаком крейта `proptest` ([`record`],
+//! [`records`], [`adversarial_record`] и [`adversarial_records`]).
+//!
+//! Функции [`fuzz_from_text`], [`fuzz_from_csv`] и [`fuzz_from_bin`] — цели фаззинга разбора
+//! записей, используемые крейтом `fuzz` (`cargo fuzz run from_text`) и тестами на наборах
+//! данных `fuzz/regressions`, ранее выявлявших ошибки разбора.
+
+use super::CsvOptions;
+use super::options::{ParseContext, Projection, SizeLimits};
+use super::record::{Currency, Record, RecordBuilder, Status, TxType};
+use super::schema::SchemaVersion;
+use std::collections::HashMap;
+use std::io;
+
+/// Запись о транзакции с граничными и нестандартными значениями полей.
+///
+/// Запись может не проходить проверок [`RecordBuilder`] и не записываться в некоторых
+/// форматах или версиях схемы.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdversarialRecord(pub Record);
+
+/// Набор корректных записей о транзакциях, записываемый и считываемый без потерь во всех
+/// форматах.
+///
+/// Валюта указана либо у всех записей набора, либо ни у одной, так как столбец валюты
+/// в CSV формате общий для всех записей.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordBatch(pub Vec<Record>);
+
+/// Максимальная длина (в символах) описания генерируемой корректной записи.
+const MAX_DESCRIPTION_LEN: usize = 64;
+
+/// Максимальная длина (в символах) описания генерируемой нестандартной записи.
+const MAX_ADVERSARIAL_DESCRIPTION_LEN: usize = 512;
+
+/// Максимальное количество дополнительных полей генерируемой нестандартной записи.
+const MAX_ADVERSARIAL_METADATA: usize = 4;
+
+/// Построить корректную запись о транзакции из сгенерированных значений полей.
+///
+/// Отправитель депозита и получатель обналичивания обнуляются, а нулевая сумма заменяется
+/// единичной, чтобы запись проходила проверки [`RecordBuilder`].
+#[allow(clippy::too_many_arguments)]
+fn valid_record(
+    tx_id: u64,
+    tx_type: usize,
+    from_user_id: u64,
+    to_user_id: u64,
+    amount: u64,
+    timestamp: u64,
+    status: usize,
+    description: String,
+    currency: Option<[u8; 3]>,
+) -> Record {
+    let tx_type = TxType::KNOWN[tx_type % TxType::KNOWN.len()].clone();
+    let (from_user_id, to_user_id) = match tx_type {
+        TxType::Deposit => (0, to_user_id),
+        TxType::Withdrawal => (from_user_id, 0),
+        _ => (from_user_id, to_user_id),
+    };
+
+    let mut record = Record::new(
+        tx_id,
+        tx_type,
+        from_user_id,
+        to_user_id,
+        amount.max(1),
+        timestamp,
+        Status::KNOWN[status % Status::KNOWN.len()].clone(),
+        description,
+    );
+    record.set_currency(currency.and_then(|code| Currency::try_from(&code[..]).ok()));
+
+    record
+}
+
+/// Построить набор корректных записей из сгенерированных записей: валюта указанного кода
+/// устанавливается записям без валюты, а при отсутствии кода валюта удаляется у всех записей.
+fn valid_batch(mut records: Vec<Record>, currency: Option<[u8; 3]>) -> RecordBatch {
+    let currency = currency.and_then(|code| Currency::try_from(&code[..]).ok());
+
+    for record in &mut records {
+        record.set_currency(currency.and(record.currency()).or(currency));
+    }
+
+    RecordBatch(records)
+}
+
+/// Привести сгенерированное неизвестное значение перечисления с указанными известными
+/// значениями к виду, в котором оно считывается при нестрогом чтении.
+///
+/// Числовые коды известных значений заменяются первым неизвестным кодом, а совпадающие
+/// с названиями известных значений — дополняются префиксом, чтобы значение оставалось
+/// неизвестным после записи и чтения.
+fn unknown_value<T: ToString>(value: String, known: &[T]) -> String {
+    match value.parse::<u8>() {
+        Ok(code) => code.max(known.len() as u8).to_string(),
+        Err(_) if known.iter().any(|known| known.to_string() == value) => format!("X-{value}"),
+        Err(_) => value,
+    }
+}
+
+/// Построить нестандартную запись о транзакции из сгенерированных значений полей.
+#[allow(clippy::too_many_arguments)]
+fn adversarial_record_from(
+    tx_id: u64,
+    tx_type: TxType,
+    from_user_id: u64,
+    to_user_id: u64,
+    amount: i128,
+    timestamp: u64,
+    status: Status,
+    description: String,
+    currency: Option<[u8; 3]>,
+    metadata: HashMap<String, String>,
+) -> AdversarialRecord {
+    let mut record = Record::new_wide(
+        tx_id,
+        tx_type,
+        from_user_id,
+        to_user_id,
+        amount,
+        timestamp,
+        status,
+        description,
+    );
+    record.set_currency(currency.and_then(|code| Currency::try_from(&code[..]).ok()));
+    *record.metadata_mut() = metadata;
+
+    AdversarialRecord(record)
+}
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impl {
+    use super::*;
+    use arbitrary::{Arbitrary, Result, Unstructured};
+
+    /// Сгенерировать необязательный код валюты из заглавных латинских букв.
+    fn currency(u: &mut Unstructured<'_>) -> Result<Option<[u8; 3]>> {
+        if !u.arbitrary::<bool>()? {
+            return Ok(None);
+        }
+
+        let mut code = [0; 3];
+
+        for c in &mut code {
+            *c = u.int_in_range(b'A'..=b'Z')?;
+        }
+
+        Ok(Some(code))
+    }
+
+    /// Сгенерировать строку не длиннее указанного количества символов.
+    fn string(u: &mut Unstructured<'_>, max_len: usize) -> Result<String> {
+        let s = u.arbitrary::<&str>()?;
+
+        Ok(s.chars().take(max_len).collect())
+    }
+
+    /// Реализация трейта [`Arbitrary`] для [`Record`].
+    ///
+    /// Генерирует корректные записи (см. [`crate::fuzzing`]).
+    impl<'a> Arbitrary<'a> for Record {
+        /// Реализация метода [`Arbitrary::arbitrary`] для [`Record`].
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            let description = string(u, MAX_DESCRIPTION_LEN)?
+                .chars()
+                .filter(|c| !c.is_control())
+                .collect();
+
+            Ok(valid_record(
+                u.arbitrary()?,
+                u.choose_index(TxType::KNOWN.len())?,
+                u.arbitrary()?,
+                u.arbitrary()?,
+                u.arbitrary()?,
+                u.int_in_range(RecordBuilder::MIN_TIMESTAMP..=RecordBuilder::MAX_TIMESTAMP - 1)?,
+                u.choose_index(Status::KNOWN.len())?,
+                description,
+                currency(u)?,
+            ))
+        }
+    }
+
+    /// Реализация трейта [`Arbitrary`] для [`RecordBatch`].
+    impl<'a> Arbitrary<'a> for RecordBatch {
+        /// Реализация метода [`Arbitrary::arbitrary`] для [`RecordBatch`].
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            Ok(valid_batch(u.arbitrary()?, currency(u)?))
+        }
+    }
+
+    /// Реализация трейта [`Arbitrary`] для [`AdversarialRecord`].
+    impl<'a> Arbitrary<'a> for AdversarialRecord {
+        /// Реализация метода [`Arbitrary::arbitrary`] для [`AdversarialRecord`].
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            let tx_type = if u.ratio(1, 8)? {
+                TxType::Unknown(unknown_value(
+                    string(u, MAX_DESCRIPTION_LEN)?,
+                    &TxType::KNOWN,
+                ))
+            } else {
+                u.choose(&TxType::KNOWN)?.clone()
+            };
+            let status = if u.ratio(1, 8)? {
+                Status::Other(unknown_value(
+                    string(u, MAX_DESCRIPTION_LEN)?,
+                    &Status::KNOWN,
+                ))
+            } else {
+                u.choose(&Status::KNOWN)?.clone()
+            };
+
+            let mut metadata = HashMap::new();
+
+            for _ in 0..u.int_in_range(0..=MAX_ADVERSARIAL_METADATA)? {
+                metadata.insert(
+                    string(u, MAX_DESCRIPTION_LEN)?,
+                    string(u, MAX_DESCRIPTION_LEN)?,
+                );
+            }
+
+            Ok(adversarial_record_from(
+                u.arbitrary()?,
+                tx_type,
+                u.arbitrary()?,
+                u.arbitrary()?,
+                u.arbitrary()?,
+                u.arbitrary()?,
+                status,
+                string(u, MAX_ADVERSARIAL_DESCRIPTION_LEN)?,
+                currency(u)?,
+                metadata,
+            ))
+        }
+    }
+}
+
+#[cfg(any(feature = "proptest", test))]
+mod proptest_impl {
+    use super::*;
+    use proptest::collection::{SizeRange, hash_map, vec};
+    use proptest::prelude::*;
+    use proptest::string::string_regex;
+
+    /// Стратегия генерации необязательного кода валюты.
+    fn currency() -> impl Strategy<Value = Option<[u8; 3]>> {
+        proptest::option::of(proptest::array::uniform3(b'A'..=b'Z'))
+    }
+
+    /// Стратегия генерации граничных и произвольных значений идентификатора.
+    fn adversarial_id() -> impl Strategy<Value = u64> {
+        prop_oneof![Just(0), Just(u64::MAX), any::<u64>()]
+    }
+
+    /// Стратегия генерации граничных и произвольных сумм, включая отрицательные и широкие.
+    fn adversarial_amount() -> impl Strategy<Value = i128> {
+        prop_oneof![
+            Just(0),
+            Just(-1),
+            Just(u64::MAX.into()),
+            Just(i128::from(u64::MAX) + 1),
+            Just(i128::MIN),
+            Just(i128::MAX),
+            any::<u64>().prop_map(i128::from),
+            any::<i128>(),
+        ]
+    }
+
+    /// Стратегия генерации описаний с кавычками, разделителями, переводами строк,
+    /// управляющими и не-ASCII символами.
+    fn adversarial_description() -> impl Strategy<Value = String> {
+        let max_len = MAX_ADVERSARIAL_DESCRIPTION_LEN;
+
+        prop_oneof![
+            Just(String::new()),
+            "[\"\\\\,:\r\n\t \\x00]{1,8}",
+            string_regex(&format!("[\"\\\\,\\PC\r\n\t]{{0,{max_len}}}")).unwrap(),
+            any::<String>(),
+        ]
+    }
+
+    /// Стратегия генерации корректной записи о транзакции (см. [`crate::fuzzing`]).
+    pub fn record() -> impl Strategy<Value = Record> {
+        (
+            (
+                any::<u64>(),
+                0..TxType::KNOWN.len(),
+                any::<u64>(),
+                any::<u64>(),
+            ),
+            (
+                any::<u64>(),
+                RecordBuilder::MIN_TIMESTAMP..RecordBuilder::MAX_TIMESTAMP,
+                0..Status::KNOWN.len(),
+                string_regex(&format!("\\PC{{0,{MAX_DESCRIPTION_LEN}}}")).unwrap(),
+                currency(),
+            ),
+        )
+            .prop_map(
+                |(
+                    (tx_id, tx_type, from_user_id, to_user_id),
+                    (amount, timestamp, status, description, currency),
+                )| {
+                    valid_record(
+                        tx_id,
+                        tx_type,
+                        from_user_id,
+                        to_user_id,
+                        amount,
+                        timestamp,
+                        status,
+                        description,
+                        currency,
+                    )
+                },
+            )
+    }
+
+    /// Стратегия генерации набора корректных записей о транзакциях указанного размера
+    /// (см. [`RecordBatch`]).
+    pub fn records(size: impl Into<SizeRange>) -> impl Strategy<Value = Vec<Record>> {
+        (vec(record(), size), currency())
+            .prop_map(|(records, currency)| valid_batch(records, currency).0)
+    }
+
+    /// Стратегия генерации нестандартной записи о транзакции (см. [`AdversarialRecord`]).
+    pub fn adversarial_record() -> impl Strategy<Value = AdversarialRecord> {
+        let tx_type = prop_oneof![
+            4 => proptest::sample::select(TxType::KNOWN.to_vec()),
+            1 => prop_oneof!["[A-Z][A-Z_]{0,15}", any::<String>(), (0..=u8::MAX).prop_map(|code| code.to_string())]
+                .prop_map(|value| TxType::Unknown(unknown_value(value, &TxType::KNOWN))),
+        ];
+        let status = prop_oneof![
+            4 => proptest::sample::select(Status::KNOWN.to_vec()),
+            1 => prop_oneof!["[A-Z][A-Z_]{0,15}", any::<String>(), (0..=u8::MAX).prop_map(|code| code.to_string())]
+                .prop_map(|value| Status::Other(unknown_value(value, &Status::KNOWN))),
+        ];
+        let metadata = hash_map(
+            prop_oneof!["[A-Za-z0-9_.-]{1,16}", any::<String>()],
+            prop_oneof!["\\PC{0,32}", any::<String>()],
+            0..=MAX_ADVERSARIAL_METADATA,
+        );
+
+        (
+            (
+                adversarial_id(),
+                tx_type,
+                adversarial_id(),
+                adversarial_id(),
+            ),
+            (
+                adversarial_amount(),
+                prop_oneof![Just(0), Just(u64::MAX), any::<u64>()],
+                status,
+                adversarial_description(),
+                currency(),
+                metadata,
+            ),
+        )
+            .prop_map(
+                |(
+                    (tx_id, tx_type, from_user_id, to_user_id),
+                    (amount, timestamp, status, description, currency, metadata),
+                )| {
+                    adversarial_record_from(
+                        tx_id,
+                        tx_type,
+                        from_user_id,
+                        to_user_id,
+                        amount,
+                        timestamp,
+                        status,
+                        description,
+                        currency,
+                        metadata,
+                    )
+                },
+            )
+    }
+
+    /// Стратегия генерации набора нестандартных записей о транзакциях указанного размера.
+    pub fn adversarial_records(
+        size: impl Into<SizeRange>,
+    ) -> impl Strategy<Value = Vec<AdversarialRecord>> {
+        vec(adversarial_record(), size)
+    }
+
+    /// Реализация трейта [`Arbitrary`] для [`Record`].
+    ///
+    /// Генерирует корректные записи (см. [`record`]).
+    impl Arbitrary for Record {
+        /// Параметры генерации (не используются).
+        type Parameters = ();
+
+        /// Стратегия генерации.
+        type Strategy = BoxedStrategy<Self>;
+
+        /// Реализация метода [`Arbitrary::arbitrary_with`] для [`Record`].
+        fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+            record().boxed()
+        }
+    }
+
+    /// Реализация трейта [`Arbitrary`] для [`RecordBatch`].
+    impl Arbitrary for RecordBatch {
+        /// Допустимые размеры набора.
+        type Parameters = SizeRange;
+
+        /// Стратегия генерации.
+        type Strategy = BoxedStrategy<Self>;
+
+        /// Реализация метода [`Arbitrary::arbitrary_with`] для [`RecordBatch`].
+        fn arbitrary_with(size: Self::Parameters) -> Self::Strategy {
+            records(size).prop_map(RecordBatch).boxed()
+        }
+    }
+
+    /// Реализация трейта [`Arbitrary`] для [`AdversarialRecord`].
+    impl Arbitrary for AdversarialRecord {
+        /// Параметры генерации (не используются).
+        type Parameters = ();
+
+        /// Стратегия генерации.
+        type Strategy = BoxedStrategy<Self>;
+
+        /// Реализация метода [`Arbitrary::arbitrary_with`] для [`AdversarialRecord`].
+        fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+            adversarial_record().boxed()
+        }
+    }
+}
+
+#[cfg(any(feature = "proptest", test))]
+pub use proptest_impl::{adversarial_record, adversarial_records, record, records};
+
+/// Параметры разбора записей при фаззинге: ограничения размеров записей меньше ограничений
+/// по умолчанию, чтобы данные небольшого размера достигали их.
+const FUZZ_CONTEXT: ParseContext = ParseContext {
+    lenient: false,
+    limits: SizeLimits {
+        max_record_size: 1024,
+        max_description_len: 256,
+    },
+    projection: Projection::ALL,
+};
+
+/// Последовательно разобрать записи из произвольных данных и проверить, что каждая успешно
+/// разобранная запись записывается и считывается повторно без изменений.
+///
+/// Разбор прекращается на первой ошибке или если очередная попытка не продвинулась по данным.
+fn check_records<E>(
+    mut data: &[u8],
+    read: impl Fn(&mut &[u8]) -> Result<Record, E>,
+    write: impl Fn(&Record, &mut Vec<u8>) -> io::Result<()>,
+) {
+    while !data.is_empty() {
+        let len = data.len();
+
+        let Ok(record) = read(&mut data) else {
+            break;
+        };
+
+        let mut written = vec![];
+
+        // Запись может быть непредставима в формате (например, дополнительное поле
+        // с переводом строки в текстовом формате), но записанная не должна искажаться.
+        if write(&record, &mut written).is_ok() {
+            let reread = read(&mut written.as_slice())
+                .unwrap_or_else(|_| panic!("written record cannot be read: {written:?}"));
+
+            assert_eq!(reread, record, "record changed after write: {written:?}");
+        }
+
+        if data.len() == len {
+            break;
+        }
+    }
+}
+
+/// Цель фаззинга разбора записей в текстовом формате ([`Record::from_text`]).
+///
+/// Паникует, если успешно разобранная запись изменяется после записи и повторного чтения.
+pub fn fuzz_from_text(data: &[u8]) {
+    check_records(
+        data,
+        |r| Record::from_text_with_buffer(r, &mut String::new(), &FUZZ_CONTEXT),
+        |record, w| record.to_text(w),
+    );
+}
+
+/// Цель фаззинга разбора записей в CSV формате ([`Record::from_csv`]).
+///
+/// Паникует, если успешно разобранная запись изменяется после записи и повторного чтения.
+pub fn fuzz_from_csv(data: &[u8]) {
+    check_records(
+        data,
+        |r| Record::from_csv_with_context(r, &CsvOptions::default(), &FUZZ_CONTEXT),
+        |record, w| record.to_csv(w),
+    );
+}
+
+/// Цель фаззинга разбора записей в бинарном формате ([`Record::from_bin`]).
+///
+/// Паникует, если успешно разобранная запись изменяется после записи и повторного чтения.
+pub fn fuzz_from_bin(data: &[u8]) {
+    check_records(
+        data,
+        |r| Record::from_bin_at(r, 1, &FUZZ_CONTEXT),
+        |record, w| record.to_bin_with_schema(w, SchemaVersion::V2),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParseOptions, SchemaVersion, YPBankImpl};
+    use proptest::prelude::*;
+    use rstest::rstest;
+    use std::fs;
+    use std::path::Path;
+
+    /// Форматы, в которых записи сохраняются без потерь.
+    const FORMATS: [YPBankImpl; 5] = [
+        YPBankImpl::Text,
+        YPBankImpl::Csv,
+        YPBankImpl::Bin,
+        YPBankImpl::Json,
+        YPBankImpl::Jsonl,
+    ];
+
+    fn roundtrip(format: YPBankImpl, records: &[Record]) -> Vec<Record> {
+        let mut data = vec![];
+        format.write_to(records.to_vec(), &mut data).unwrap();
+
+        format.read_from(&mut data.as_slice()).unwrap()
+    }
+
+    #[rstest]
+    #[case("from_text", fuzz_from_text)]
+    #[case("from_csv", fuzz_from_csv)]
+    #[case("from_bin", fuzz_from_bin)]
+    fn test_fuzz_regressions(#[case] target: &str, #[case] fuzz: fn(&[u8])) {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fuzz/regressions")
+            .join(target);
+
+        for entry in fs::read_dir(dir).unwrap() {
+            fuzz(&fs::read(entry.unwrap().path()).unwrap());
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_valid_records(records in records(0..8)) {
+            for record in &records {
+                let mut builder = Record::builder()
+                    .tx_id(record.tx_id())
+                    .tx_type(record.tx_type().clone())
+                    .from_user_id(record.from_user_id())
+                    .to_user_id(record.to_user_id())
+                    .wide_amount(record.wide_amount())
+                    .timestamp(record.timestamp())
+                    .status(record.status().clone())
+                    .description(record.description());
+
+                if let Some(currency) = record.currency() {
+                    builder = builder.currency(currency);
+                }
+
+                prop_assert_eq!(&builder.build().unwrap(), record);
+            }
+        }
+
+        #[test]
+        fn test_roundtrip(records in records(0..8)) {
+            for format in FORMATS {
+                prop_assert_eq!(roundtrip(format, &records), records.clone(), "{:?}", format);
+            }
+        }
+
+        #[test]
+        fn test_adversarial_roundtrip(AdversarialRecord(record) in any::<AdversarialRecord>()) {
+            for format in FORMATS {
+                let mut writer = format.writer_to(vec![]).schema_version(SchemaVersion::V2);
+
+                // Формат может отказаться записать запись, но не исказить ее.
+                if writer.write_record(&record).is_err() {
+                    continue;
+                }
+
+                // Дополнительные поля записываются в CSV формате только в указанные столбцы.
+                let mut expected = record.clone();
+
+                if format == YPBankImpl::Csv {
+                    expected.metadata_mut().clear();
+                }
+
+                let data = writer.finish().unwrap();
+                let options = ParseOptions {
+                    lenient: true,
+                    ..ParseOptions::default()
+                };
+                let read = format
+                    .read_with_options(&mut data.as_slice(), options)
+                    .map(|report| report.records);
+
+                if let Ok(read) = read {
+                    prop_assert_eq!(read, vec![expected], "{:?}", format);
+                }
+            }
+        }
+    }
+}
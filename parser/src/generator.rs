@@ -0,0 +1,347 @@
+//! Модуль генерации псевдослучайных корректных записей о транзакциях, например, для нагрузочного
+//! тестирования потребителей данных.
+
+use super::errors::GenerateError;
+use super::record::{Record, RecordBuilder, Status, TxType};
+use std::ops::RangeInclusive;
+
+/// Распределение сумм генерируемых транзакций.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmountDistribution {
+    /// Равномерное распределение в диапазоне от `min` до `max` включительно.
+    Uniform {
+        /// Минимальная сумма.
+        min: u64,
+
+        /// Максимальная сумма.
+        max: u64,
+    },
+
+    /// Логарифмически равномерное распределение в диапазоне от `min` до `max` включительно:
+    /// небольшие суммы встречаются чаще крупных.
+    LogUniform {
+        /// Минимальная сумма.
+        min: u64,
+
+        /// Максимальная сумма.
+        max: u64,
+    },
+}
+
+impl AmountDistribution {
+    /// Минимальная и максимальная суммы.
+    fn bounds(&self) -> (u64, u64) {
+        match *self {
+            Self::Uniform { min, max } | Self::LogUniform { min, max } => (min, max),
+        }
+    }
+}
+
+/// Параметры генерации записей о транзакциях.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratorConfig {
+    /// Начальное значение генератора псевдослучайных чисел: одинаковые параметры дают
+    /// одинаковые записи.
+    pub seed: u64,
+
+    /// TX_ID первой записи; TX_ID следующих записей последовательно увеличиваются.
+    pub first_tx_id: u64,
+
+    /// Диапазон идентификаторов участников транзакций (без 0).
+    pub user_ids: RangeInclusive<u64>,
+
+    /// Распределение сумм транзакций.
+    pub amount: AmountDistribution,
+
+    /// Минимальная метка времени (включительно).
+    pub since: u64,
+
+    /// Максимальная метка времени (не включительно).
+    pub until: u64,
+}
+
+/// Реализация трейта [`Default`] для [`GeneratorConfig`].
+impl Default for GeneratorConfig {
+    /// Реализация метода [`Default::default`] для [`GeneratorConfig`].
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            first_tx_id: 1,
+            user_ids: 1..=1000,
+            amount: AmountDistribution::LogUniform {
+                min: 1,
+                max: 1_000_000,
+            },
+            // 2024-01-01T00:00:00Z .. 2025-01-01T00:00:00Z.
+            since: 1_704_067_200_000,
+            until: 1_735_689_600_000,
+        }
+    }
+}
+
+/// Бесконечный итератор псевдослучайных корректных записей о транзакциях.
+///
+/// Генерируемые записи не содержат нарушений, проверяемых [`crate::Validator`]: TX_ID
+/// уникальны, у депозита нет отправителя, у обналичивания — получателя, отправитель перевода
+/// отличается от получателя, а метки времени находятся в допустимом диапазоне.
+#[derive(Debug, Clone)]
+pub struct Generator {
+    /// Параметры генерации.
+    config: GeneratorConfig,
+
+    /// Состояние генератора псевдослучайных чисел (SplitMix64).
+    state: u64,
+
+    /// TX_ID очередной записи (отсутствует, если TX_ID исчерпаны).
+    next_tx_id: Option<u64>,
+}
+
+impl Generator {
+    /// Создание генератора записей с указанными параметрами.
+    pub fn new(config: GeneratorConfig) -> Result<Self, GenerateError> {
+        let (start, end) = (*config.user_ids.start(), *config.user_ids.end());
+        if start == 0 || start > end {
+            return Err(GenerateError::InvalidUserIds(start, end));
+        }
+
+        let (min, max) = config.amount.bounds();
+        if min == 0 || min > max {
+            return Err(GenerateError::InvalidAmount(min, max));
+        }
+
+        if config.since >= config.until
+            || config.since < RecordBuilder::MIN_TIMESTAMP
+            || config.until > RecordBuilder::MAX_TIMESTAMP
+        {
+            return Err(GenerateError::InvalidTimeWindow(config.since, config.until));
+        }
+
+        Ok(Self {
+            state: config.seed,
+            next_tx_id: Some(config.first_tx_id),
+            config,
+        })
+    }
+
+    /// Очередное псевдослучайное число.
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+
+        z ^ (z >> 31)
+    }
+
+    /// Псевдослучайное число от 0 (включительно) до `n` (не включительно).
+    fn below(&mut self, n: u64) -> u64 {
+        ((u128::from(self.next_u64()) * u128::from(n)) >> 64) as u64
+    }
+
+    /// Псевдослучайное число от `min` до `max` включительно.
+    fn in_range(&mut self, min: u64, max: u64) -> u64 {
+        match (max - min).checked_add(1) {
+            Some(len) => min + self.below(len),
+            None => self.next_u64(),
+        }
+    }
+
+    /// Псевдослучайное число от 0 (включительно) до 1 (не включительно).
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Псевдослучайная сумма транзакции.
+    fn amount(&mut self) -> u64 {
+        match self.config.amount {
+            AmountDistribution::Uniform { min, max } => self.in_range(min, max),
+            AmountDistribution::LogUniform { min, max } => {
+                let (low, high) = ((min as f64).ln(), (max as f64 + 1.0).ln());
+                let amount = (low + self.next_f64() * (high - low)).exp() as u64;
+
+                amount.clamp(min, max)
+            }
+        }
+    }
+
+    /// Псевдослучайные отправитель и получатель транзакции указанного типа.
+    fn participants(&mut self, tx_type: &TxType) -> (u64, u64) {
+        let (start, end) = (*self.config.user_ids.start(), *self.config.user_ids.end());
+        let user = self.in_range(start, end);
+
+        match tx_type {
+            TxType::Deposit => (0, user),
+            TxType::Withdrawal => (user, 0),
+            _ => {
+                // Получатель выбирается среди остальных участников.
+                let others = end - start;
+                let to = start + (user - start + 1 + self.below(others)) % (others + 1);
+
+                (user, to)
+            }
+        }
+    }
+}
+
+/// Реализация трейта [`Iterator`] для [`Generator`].
+impl Iterator for Generator {
+    type Item = Record;
+
+    /// Реализация метода [`Iterator::next`] для [`Generator`].
+    fn next(&mut self) -> Option<Self::Item> {
+        let tx_id = self.next_tx_id?;
+        self.next_tx_id = tx_id.checked_add(1);
+
+        // Перевод невозможен, если участник всего один.
+        let single_user = self.config.user_ids.start() == self.config.user_ids.end();
+        let tx_type = match self.below(if single_user { 2 } else { 3 }) {
+            0 => TxType::Deposit,
+            1 => TxType::Withdrawal,
+            _ => TxType::Transfer,
+        };
+
+        let (from_user_id, to_user_id) = self.participants(&tx_type);
+        let amount = self.amount();
+        let timestamp = self.in_range(self.config.since, self.config.until - 1);
+
+        let status = match self.below(100) {
+            0..90 => Status::Success,
+            90..95 => Status::Failure,
+            _ => Status::Pending,
+        };
+
+        let description = format!("Generated {}", tx_type.to_string().to_lowercase());
+
+        Some(Record::new(
+            tx_id,
+            tx_type,
+            from_user_id,
+            to_user_id,
+            amount,
+            timestamp,
+            status,
+            description,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ValidationConfig, validation::validate};
+    use rstest::rstest;
+
+    fn generate(config: GeneratorConfig, count: usize) -> Vec<Record> {
+        Generator::new(config).unwrap().take(count).collect()
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let config = GeneratorConfig {
+            seed: 42,
+            ..Default::default()
+        };
+
+        assert_eq!(generate(config.clone(), 100), generate(config, 100));
+        assert_ne!(
+            generate(GeneratorConfig::default(), 100),
+            generate(
+                GeneratorConfig {
+                    seed: 1,
+                    ..Default::default()
+                },
+                100
+            )
+        );
+    }
+
+    #[rstest]
+    #[case(AmountDistribution::Uniform { min: 10, max: 20 })]
+    #[case(AmountDistribution::LogUniform { min: 1, max: 1_000_000_000 })]
+    #[case(AmountDistribution::Uniform { min: 1, max: u64::MAX })]
+    fn test_valid_records(#[case] amount: AmountDistribution) {
+        let config = GeneratorConfig {
+            first_tx_id: 100,
+            user_ids: 5..=7,
+            amount,
+            since: 1_700_000_000_000,
+            until: 1_700_000_001_000,
+            ..Default::default()
+        };
+
+        let records = generate(config.clone(), 1000);
+        let (min, max) = amount.bounds();
+
+        assert!(
+            validate(&records, &ValidationConfig::default())
+                .issues
+                .is_empty()
+        );
+        assert_eq!(records[0].tx_id(), 100);
+        assert_eq!(records[999].tx_id(), 1099);
+
+        for record in &records {
+            assert!((i128::from(min)..=i128::from(max)).contains(&record.wide_amount()));
+            assert!((config.since..config.until).contains(&record.timestamp()));
+
+            for user_id in [record.from_user_id(), record.to_user_id()] {
+                assert!(user_id == 0 || config.user_ids.contains(&user_id));
+            }
+        }
+
+        for tx_type in [TxType::Deposit, TxType::Transfer, TxType::Withdrawal] {
+            assert!(records.iter().any(|record| *record.tx_type() == tx_type));
+        }
+    }
+
+    #[test]
+    fn test_single_user() {
+        let records = generate(
+            GeneratorConfig {
+                user_ids: 3..=3,
+                ..Default::default()
+            },
+            100,
+        );
+
+        assert!(
+            records
+                .iter()
+                .all(|record| *record.tx_type() != TxType::Transfer)
+        );
+    }
+
+    #[test]
+    fn test_tx_id_exhausted() {
+        let records = generate(
+            GeneratorConfig {
+                first_tx_id: u64::MAX - 1,
+                ..Default::default()
+            },
+            10,
+        );
+
+        assert_eq!(records.len(), 2);
+    }
+
+    #[rstest]
+    #[case(GeneratorConfig { user_ids: 0..=10, ..Default::default() }, "Invalid user ID range: 0..=10")]
+    #[case(GeneratorConfig { user_ids: RangeInclusive::new(10, 1), ..Default::default() }, "Invalid user ID range: 10..=1")]
+    #[case(
+        GeneratorConfig { amount: AmountDistribution::Uniform { min: 0, max: 10 }, ..Default::default() },
+        "Invalid amount range: 0..=10"
+    )]
+    #[case(
+        GeneratorConfig { amount: AmountDistribution::LogUniform { min: 20, max: 10 }, ..Default::default() },
+        "Invalid amount range: 20..=10"
+    )]
+    #[case(
+        GeneratorConfig { since: 1_700_000_000_000, until: 1_700_000_000_000, ..Default::default() },
+        "Invalid time window: 1700000000000..1700000000000"
+    )]
+    #[case(GeneratorConfig { since: 0, ..Default::default() }, "Invalid time window: 0..1735689600000")]
+    fn test_invalid_config(#[case] config: GeneratorConfig, #[case] message: &str) {
+        assert_eq!(Generator::new(config).unwrap_err().to_string(), message);
+    }
+}
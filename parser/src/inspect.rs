@@ -0,0 +1,598 @@
+//! Модуль разбора данных в бинарном формате по полям для поиска повреждений.
+//!
+//! [`BinInspector`] последовательно разбирает заголовок контейнера и записи, сообщая для каждого
+//! поля его название, смещение, исходные байты и считанное значение, и останавливается на первой
+//! структурной ошибке (неверный MAGIC, некорректный RECORD_SIZE, обрыв данных и т. п.)
+//! с указанием ее смещения. Некорректные значения полей (например, неизвестный код TX_TYPE)
+//! не нарушают структуру данных и отображаются в значении поля.
+
+use super::YPBankBin;
+use super::errors::BinStructureError;
+use super::record::{Record, Status, TxType};
+use std::fmt;
+
+/// Максимальное количество байт значения поля, отображаемых в шестнадцатеричном виде.
+const MAX_DUMP_BYTES: usize = 16;
+
+/// Поле данных в бинарном формате.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InspectedField {
+    /// Название поля.
+    pub name: String,
+
+    /// Смещение начала поля в байтах от начала данных.
+    pub offset: u64,
+
+    /// Исходные байты поля.
+    pub bytes: Vec<u8>,
+
+    /// Считанное значение поля.
+    pub value: String,
+}
+
+/// Реализация трейта [`fmt::Display`] для [`InspectedField`].
+///
+/// Поле выводится строкой: смещение, не более 16 первых байт в шестнадцатеричном виде, название
+/// и значение поля.
+impl fmt::Display for InspectedField {
+    /// Реализация метода [`fmt::Display::fmt`] для [`InspectedField`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut hex = self
+            .bytes
+            .iter()
+            .take(MAX_DUMP_BYTES)
+            .map(|byte| format!("{byte:02X}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        if self.bytes.len() > MAX_DUMP_BYTES {
+            hex.push_str(&format!(" ... ({} bytes)", self.bytes.len()));
+        }
+
+        write!(
+            f,
+            "{:08X}  {hex:<47}  {}: {}",
+            self.offset, self.name, self.value
+        )
+    }
+}
+
+/// Разобранная часть данных в бинарном формате.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InspectedItem {
+    /// Заголовок контейнера.
+    ContainerHeader {
+        /// Поля заголовка.
+        fields: Vec<InspectedField>,
+    },
+
+    /// Запись о транзакции.
+    Record {
+        /// Порядковый номер записи (начиная с 1).
+        index: usize,
+
+        /// Смещение начала записи в байтах от начала данных.
+        offset: u64,
+
+        /// Поля записи, включая расширения.
+        fields: Vec<InspectedField>,
+    },
+}
+
+impl InspectedItem {
+    /// Поля разобранной части данных.
+    pub fn fields(&self) -> &[InspectedField] {
+        match self {
+            Self::ContainerHeader { fields } | Self::Record { fields, .. } => fields,
+        }
+    }
+}
+
+/// Реализация трейта [`fmt::Display`] для [`InspectedItem`].
+impl fmt::Display for InspectedItem {
+    /// Реализация метода [`fmt::Display::fmt`] для [`InspectedItem`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ContainerHeader { .. } => writeln!(f, "Container header at byte 0")?,
+            Self::Record { index, offset, .. } => {
+                writeln!(f, "Record {index} at byte {offset}")?;
+            }
+        }
+
+        for field in self.fields() {
+            writeln!(f, "    {field}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Последовательный разбор данных в бинарном формате по полям.
+///
+/// Итератор возвращает заголовок контейнера (если он есть) и записи, а после первой
+/// структурной ошибки завершается. Поля записи, разобранные до ошибки, возвращаются
+/// перед ней.
+#[derive(Debug, Clone)]
+pub struct BinInspector<'a> {
+    /// Разбираемые данные.
+    data: &'a [u8],
+
+    /// Смещение следующей разбираемой части данных.
+    offset: usize,
+
+    /// Количество разобранных записей.
+    records: usize,
+
+    /// Структурная ошибка, возвращаемая после разобранных до нее полей.
+    error: Option<BinStructureError>,
+
+    /// Признак завершения разбора.
+    done: bool,
+}
+
+impl<'a> BinInspector<'a> {
+    /// Создание разбора указанных данных в бинарном формате.
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            offset: 0,
+            records: 0,
+            error: None,
+            done: false,
+        }
+    }
+
+    /// Разобрать заголовок контейнера.
+    fn inspect_header(fields: &mut Fields) -> Result<(), BinStructureError> {
+        fields.push("MAGIC", YPBankBin::CONTAINER_MAGIC.len(), |bytes| {
+            String::from_utf8_lossy(bytes).to_string()
+        })?;
+        fields.push_u32("VERSION")?;
+        fields.push_u64("RECORD_COUNT")?;
+        fields.push_u64("PAYLOAD_LEN")?;
+
+        Ok(())
+    }
+
+    /// Разобрать очередную запись о транзакции.
+    ///
+    /// Если запись обрывается концом данных, поля разбираются до конца данных.
+    fn inspect_record(fields: &mut Fields) -> Result<(), BinStructureError> {
+        let start = fields.offset;
+
+        let magic = fields.push("MAGIC", Record::BINARY_MAGIC.len(), |bytes| {
+            String::from_utf8_lossy(bytes).to_string()
+        })?;
+
+        if magic != Record::BINARY_MAGIC {
+            return Err(BinStructureError {
+                offset: start as u64,
+                message: format!(
+                    "invalid record MAGIC {}, expected 59 50 42 4E (`YPBN`)",
+                    to_hex(magic)
+                ),
+            });
+        }
+
+        let record_size = u32::from_be_bytes(fields.push_u32("RECORD_SIZE")?);
+
+        if record_size < Record::BINARY_MIN_RECORD_SIZE {
+            return Err(BinStructureError {
+                offset: (start + Record::BINARY_MAGIC.len()) as u64,
+                message: format!(
+                    "RECORD_SIZE ({record_size}) is less than the minimum record size ({})",
+                    Record::BINARY_MIN_RECORD_SIZE
+                ),
+            });
+        }
+
+        let end = fields.offset + record_size as usize;
+        fields.end = end.min(fields.end);
+
+        fields.push_u64("TX_ID")?;
+        fields.push("TX_TYPE", 1, |bytes| match TxType::try_from(bytes[0]) {
+            Ok(tx_type) => tx_type.to_string(),
+            Err(e) => format!("invalid ({e})"),
+        })?;
+        fields.push_u64("FROM_USER_ID")?;
+        fields.push_u64("TO_USER_ID")?;
+        fields.push_u64("AMOUNT")?;
+        fields.push_u64("TIMESTAMP")?;
+        fields.push("STATUS", 1, |bytes| match Status::try_from(bytes[0]) {
+            Ok(status) => status.to_string(),
+            Err(e) => format!("invalid ({e})"),
+        })?;
+
+        let description_offset = fields.offset;
+        let description_size = u32::from_be_bytes(fields.push_u32("DESCRIPTION_SIZE")?);
+        let max_description_size = record_size - Record::BINARY_MIN_RECORD_SIZE;
+
+        if description_size > max_description_size {
+            return Err(BinStructureError {
+                offset: description_offset as u64,
+                message: format!(
+                    "DESCRIPTION_SIZE ({description_size}) exceeds the space left in the record \
+                     ({max_description_size} bytes)"
+                ),
+            });
+        }
+
+        fields.push("DESCRIPTION", description_size as usize, decode_text)?;
+
+        while fields.offset < end {
+            let tag = fields.push("EXTENSION_TAG", 1, |bytes| {
+                match bytes[0] {
+                    Record::BINARY_EXTENSION_CURRENCY => "1 (CURRENCY)",
+                    Record::BINARY_EXTENSION_METADATA => "2 (METADATA)",
+                    Record::BINARY_EXTENSION_AMOUNT => "3 (wide AMOUNT)",
+                    _ => "unknown (skipped)",
+                }
+                .to_string()
+            })?[0];
+
+            let len_offset = fields.offset;
+            let len = u16::from_be_bytes(fields.push_u16("EXTENSION_LENGTH")?) as usize;
+
+            if fields.offset + len > end {
+                return Err(BinStructureError {
+                    offset: len_offset as u64,
+                    message: format!(
+                        "EXTENSION_LENGTH ({len}) exceeds the end of the record at byte {end}"
+                    ),
+                });
+            }
+
+            fields.push("EXTENSION_VALUE", len, |bytes| match tag {
+                Record::BINARY_EXTENSION_AMOUNT => <[u8; 16]>::try_from(bytes).map_or_else(
+                    |_| "invalid length".to_string(),
+                    |amount| i128::from_be_bytes(amount).to_string(),
+                ),
+                Record::BINARY_EXTENSION_METADATA if bytes.len() >= 2 => {
+                    let key_len = u16::from_be_bytes([bytes[0], bytes[1]]) as usize;
+
+                    match bytes[2..].split_at_checked(key_len) {
+                        Some((key, value)) => {
+                            format!("{} = {}", decode_text(key), decode_text(value))
+                        }
+                        None => "invalid key length".to_string(),
+                    }
+                }
+                _ => decode_text(bytes),
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Реализация трейта [`Iterator`] для [`BinInspector`].
+impl Iterator for BinInspector<'_> {
+    type Item = Result<InspectedItem, BinStructureError>;
+
+    /// Реализация метода [`Iterator::next`] для [`BinInspector`].
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(error) = self.error.take() {
+            self.done = true;
+
+            return Some(Err(error));
+        }
+
+        if self.done || self.offset >= self.data.len() {
+            return None;
+        }
+
+        let start = self.offset;
+        let header = start == 0 && self.data.starts_with(&YPBankBin::CONTAINER_MAGIC);
+        let mut fields = Fields::new(self.data, start, self.data.len());
+
+        let result = if header {
+            Self::inspect_header(&mut fields)
+        } else {
+            Self::inspect_record(&mut fields)
+        };
+
+        // Поля, разобранные до структурной ошибки, возвращаются перед ней.
+        match result {
+            Ok(()) => self.offset = fields.offset,
+            Err(error) if fields.fields.is_empty() => {
+                self.done = true;
+
+                return Some(Err(error));
+            }
+            Err(error) => self.error = Some(error),
+        }
+
+        let item = if header {
+            InspectedItem::ContainerHeader {
+                fields: fields.fields,
+            }
+        } else {
+            self.records += 1;
+
+            InspectedItem::Record {
+                index: self.records,
+                offset: start as u64,
+                fields: fields.fields,
+            }
+        };
+
+        Some(Ok(item))
+    }
+}
+
+/// Поля разбираемой части данных.
+struct Fields<'a> {
+    /// Разбираемые данные.
+    data: &'a [u8],
+
+    /// Смещение следующего поля.
+    offset: usize,
+
+    /// Смещение конца разбираемой части данных.
+    end: usize,
+
+    /// Разобранные поля.
+    fields: Vec<InspectedField>,
+}
+
+impl<'a> Fields<'a> {
+    /// Создание разбора части данных с указанными смещениями начала и конца.
+    fn new(data: &'a [u8], offset: usize, end: usize) -> Self {
+        Self {
+            data,
+            offset,
+            end,
+            fields: vec![],
+        }
+    }
+
+    /// Разобрать поле указанной длины и вернуть его байты.
+    fn push(
+        &mut self,
+        name: &str,
+        len: usize,
+        decode: impl FnOnce(&[u8]) -> String,
+    ) -> Result<&'a [u8], BinStructureError> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + len)
+            .filter(|_| self.offset + len <= self.end)
+            .ok_or_else(|| BinStructureError {
+                offset: self.end.min(self.data.len()) as u64,
+                message: format!(
+                    "unexpected end of {} while reading {name} ({len} bytes at byte {})",
+                    if self.end < self.data.len() {
+                        "record"
+                    } else {
+                        "data"
+                    },
+                    self.offset
+                ),
+            })?;
+
+        self.fields.push(InspectedField {
+            name: name.to_string(),
+            offset: self.offset as u64,
+            bytes: bytes.to_vec(),
+            value: decode(bytes),
+        });
+        self.offset += len;
+
+        Ok(bytes)
+    }
+
+    /// Разобрать двухбайтовое целое число (big-endian).
+    fn push_u16(&mut self, name: &str) -> Result<[u8; 2], BinStructureError> {
+        let bytes = self.push(name, 2, |bytes| {
+            u16::from_be_bytes(bytes.try_into().unwrap()).to_string()
+        })?;
+
+        Ok(bytes.try_into().unwrap())
+    }
+
+    /// Разобрать четырехбайтовое целое число (big-endian).
+    fn push_u32(&mut self, name: &str) -> Result<[u8; 4], BinStructureError> {
+        let bytes = self.push(name, 4, |bytes| {
+            u32::from_be_bytes(bytes.try_into().unwrap()).to_string()
+        })?;
+
+        Ok(bytes.try_into().unwrap())
+    }
+
+    /// Разобрать восьмибайтовое целое число (big-endian).
+    fn push_u64(&mut self, name: &str) -> Result<[u8; 8], BinStructureError> {
+        let bytes = self.push(name, 8, |bytes| {
+            u64::from_be_bytes(bytes.try_into().unwrap()).to_string()
+        })?;
+
+        Ok(bytes.try_into().unwrap())
+    }
+}
+
+/// Байты в шестнадцатеричном виде через пробел.
+fn to_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Текстовое значение поля с экранированными управляющими символами.
+fn decode_text(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text
+            .chars()
+            .map(|c| {
+                if c.is_control() {
+                    c.escape_debug().to_string()
+                } else {
+                    c.to_string()
+                }
+            })
+            .collect(),
+        Err(e) => format!("invalid UTF-8 ({e})"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::YPBank;
+
+    fn get_data() -> Vec<u8> {
+        let mut data = vec![];
+        YPBankBin::from_records(crate::tests::get_data_to_write())
+            .write_to(&mut data)
+            .unwrap();
+
+        data
+    }
+
+    #[test]
+    fn test_inspect() {
+        let data = get_data();
+
+        let items = BinInspector::new(&data)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(items.len(), 3);
+
+        let fields = items[0].fields();
+        assert_eq!(
+            fields
+                .iter()
+                .map(|field| (field.name.as_str(), field.offset, field.value.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                ("MAGIC", 0, "YPBN"),
+                ("RECORD_SIZE", 4, "64"),
+                ("TX_ID", 8, "1234567890123456"),
+                ("TX_TYPE", 16, "DEPOSIT"),
+                ("FROM_USER_ID", 17, "0"),
+                ("TO_USER_ID", 25, "9876543210987654"),
+                ("AMOUNT", 33, "10000"),
+                ("TIMESTAMP", 41, "1633036800000"),
+                ("STATUS", 49, "SUCCESS"),
+                ("DESCRIPTION_SIZE", 50, "18"),
+                ("DESCRIPTION", 54, "\"Terminal deposit\""),
+            ]
+        );
+        assert!(matches!(
+            items[1],
+            InspectedItem::Record {
+                index: 2,
+                offset: 72,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_inspect_container_and_extensions() {
+        let mut record = crate::tests::get_data_to_write().remove(0);
+        record.set_currency("USD".try_into().ok());
+        let mut data = vec![];
+        YPBankBin::from_records(vec![record])
+            .write_container_to(&mut data)
+            .unwrap();
+
+        let items = BinInspector::new(&data)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].fields()[0].value, "YPBC");
+        assert_eq!(
+            items[1]
+                .fields()
+                .iter()
+                .rev()
+                .take(3)
+                .map(|field| field.value.as_str())
+                .collect::<Vec<_>>(),
+            ["USD", "3", "1 (CURRENCY)"]
+        );
+    }
+
+    #[test]
+    fn test_inspect_stops_at_invalid_magic() {
+        let mut data = get_data();
+        data[72] = b'X';
+
+        let items = BinInspector::new(&data).collect::<Vec<_>>();
+
+        assert_eq!(items.len(), 3);
+        assert!(items[0].is_ok());
+        assert_eq!(items[1].as_ref().unwrap().fields().len(), 1);
+        assert_eq!(
+            items[2].as_ref().unwrap_err().to_string(),
+            "Structural error at byte 72: invalid record MAGIC 58 50 42 4E, expected 59 50 42 4E (`YPBN`)"
+        );
+    }
+
+    #[test]
+    fn test_inspect_truncated() {
+        let data = get_data();
+
+        let items = BinInspector::new(&data[..100]).collect::<Vec<_>>();
+
+        assert_eq!(items.len(), 3);
+        assert_eq!(
+            items[1]
+                .as_ref()
+                .unwrap()
+                .fields()
+                .iter()
+                .map(|field| field.name.as_str())
+                .collect::<Vec<_>>(),
+            ["MAGIC", "RECORD_SIZE", "TX_ID", "TX_TYPE", "FROM_USER_ID"]
+        );
+        assert_eq!(
+            items[2].as_ref().unwrap_err().to_string(),
+            "Structural error at byte 100: unexpected end of data while reading TO_USER_ID (8 bytes at byte 97)"
+        );
+    }
+
+    #[test]
+    fn test_inspect_invalid_description_size() {
+        let mut data = get_data();
+        data[50..54].copy_from_slice(&100u32.to_be_bytes());
+
+        let error = BinInspector::new(&data).find_map(Result::err).unwrap();
+
+        assert_eq!(error.offset, 50);
+        assert_eq!(
+            error.message,
+            "DESCRIPTION_SIZE (100) exceeds the space left in the record (18 bytes)"
+        );
+    }
+
+    #[test]
+    fn test_inspect_invalid_value() {
+        let mut data = get_data();
+        data[16] = 42;
+
+        let items = BinInspector::new(&data)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(items[0].fields()[3].value, "invalid (Invalid TX_TYPE: 42)");
+    }
+
+    #[test]
+    fn test_display_field() {
+        let field = InspectedField {
+            name: "TX_ID".to_string(),
+            offset: 8,
+            bytes: vec![0, 0, 0, 0, 0, 0, 0, 1],
+            value: "1".to_string(),
+        };
+
+        assert_eq!(
+            field.to_string(),
+            "00000008  00 00 00 00 00 00 00 01                          TX_ID: 1"
+        );
+    }
+}
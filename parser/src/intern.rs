@@ -0,0 +1,82 @@
+//! Модуль разделения одинаковых описаний транзакций между записями.
+
+use super::record::Description;
+use std::collections::HashSet;
+
+/// Набор описаний транзакций, позволяющий записям с одинаковыми описаниями разделять одну строку
+/// вместо хранения собственных копий (см. [`crate::Record::intern_description`]).
+///
+/// Полезен для больших наборов записей с повторяющимися описаниями (например, «Terminal deposit»).
+/// Короткие описания хранятся в самих записях без выделения памяти в куче (см. [`Description`]),
+/// поэтому в набор не добавляются. Набор не ограничен по размеру и хранит все добавленные
+/// описания, пока существует.
+#[derive(Debug, Clone, Default)]
+pub struct DescriptionPool {
+    /// Добавленные описания.
+    descriptions: HashSet<Description>,
+}
+
+impl DescriptionPool {
+    /// Создание пустого набора описаний.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Получить описание, равное указанному, разделяющее строку с ранее добавленным описанием.
+    ///
+    /// Если такого описания в наборе еще нет, оно добавляется.
+    pub fn intern(&mut self, description: &str) -> Description {
+        if let Some(interned) = self.descriptions.get(description) {
+            return interned.clone();
+        }
+
+        let description = Description::new(description);
+
+        if description.is_heap_allocated() {
+            self.descriptions.insert(description.clone());
+        }
+
+        description
+    }
+
+    /// Количество добавленных описаний.
+    pub fn len(&self) -> usize {
+        self.descriptions.len()
+    }
+
+    /// Признак отсутствия добавленных описаний.
+    pub fn is_empty(&self) -> bool {
+        self.descriptions.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern_shares_long_descriptions() {
+        let mut pool = DescriptionPool::new();
+        let text = "Monthly subscription payment for premium services";
+
+        let copy = String::from(text);
+
+        let first = pool.intern(text);
+        let second = pool.intern(&copy);
+
+        assert_eq!(first, text);
+        assert_eq!(first.as_ptr(), second.as_ptr());
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn test_intern_keeps_short_descriptions_inline() {
+        let mut pool = DescriptionPool::new();
+
+        let description = pool.intern("Terminal deposit");
+
+        assert_eq!(description, "Terminal deposit");
+        assert!(!description.is_heap_allocated());
+        assert!(pool.is_empty());
+    }
+}
@@ -0,0 +1,1601 @@
+//! Модуль потокового чтения записей о транзакциях.
+
+use super::errors::{Position, ReadError};
+use super::intern::DescriptionPool;
+use super::line_ending::UTF8_BOM;
+use super::metrics::Metrics;
+use super::options::{OnError, ParseContext, ParseOptions, ParseReport, Projection, SizeLimits};
+use super::record::Record;
+use std::io::{BufRead, Read};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Функции буфера источника, который уже буферизован вызывающей стороной.
+struct InnerBuffer<R> {
+    /// Реализация [`BufRead::fill_buf`] для источника.
+    fill_buf: fn(&mut R) -> std::io::Result<&[u8]>,
+
+    /// Реализация [`BufRead::consume`] для источника.
+    consume: fn(&mut R, usize),
+}
+
+/// Буферизованный источник данных, отслеживающий текущую позицию чтения.
+///
+/// Если источник уже буферизован (см. [`RecordIter::buffered`]), данные считываются
+/// непосредственно из его буфера, а собственный буфер используется только для получения
+/// непрерывных участков данных, не помещающихся в буфер источника (см. [`PositionReader::peek`]).
+pub(crate) struct PositionReader<R: Read> {
+    /// Исходный источник данных.
+    inner: R,
+
+    /// Функции буфера источника, если он уже буферизован.
+    inner_buffer: Option<InnerBuffer<R>>,
+
+    /// Буфер считанных, но еще не обработанных данных.
+    buffer: Box<[u8]>,
+
+    /// Начало необработанных данных в буфере.
+    start: usize,
+
+    /// Конец считанных данных в буфере.
+    end: usize,
+
+    /// Количество считанных байт.
+    offset: u64,
+
+    /// Количество считанных символов перевода строки.
+    newlines: u64,
+
+    /// Данные, обработанные с начала текущей записи, если их требуется сохранять
+    /// (см. [`PositionReader::rewind`]).
+    retained: Option<Vec<u8>>,
+}
+
+impl<R: Read> PositionReader<R> {
+    /// Размер буфера чтения.
+    const CAPACITY: usize = 8 * 1024;
+
+    /// Создание источника данных с отслеживанием позиции.
+    fn new(r: R) -> Self {
+        Self {
+            inner: r,
+            inner_buffer: None,
+            buffer: vec![0; Self::CAPACITY].into_boxed_slice(),
+            start: 0,
+            end: 0,
+            offset: 0,
+            newlines: 0,
+            retained: None,
+        }
+    }
+
+    /// Начать сохранять обрабатываемые данные, отбросив сохраненные ранее.
+    fn retain(&mut self) {
+        self.retained.get_or_insert_default().clear();
+    }
+
+    /// Вернуть в источник данные, обработанные с начала сохранения (см. [`PositionReader::retain`]),
+    /// кроме первых `skip` байт, чтобы обработать их повторно.
+    fn rewind(&mut self, skip: usize) {
+        let Some(retained) = self.retained.as_mut() else {
+            return;
+        };
+
+        let returned = std::mem::take(retained);
+        let returned = returned.get(skip..).unwrap_or_default();
+
+        // Собственный буфер обрабатывается раньше буфера источника, а запас в его конце
+        // позволяет дочитывать данные в [`PositionReader::peek`].
+        let mut buffer =
+            Vec::with_capacity(returned.len() + self.end - self.start + Self::CAPACITY);
+        buffer.extend_from_slice(returned);
+        buffer.extend_from_slice(&self.buffer[self.start..self.end]);
+
+        self.end = buffer.len();
+        self.start = 0;
+        buffer.resize(self.end + Self::CAPACITY, 0);
+        self.buffer = buffer.into_boxed_slice();
+
+        self.newlines -= returned.iter().filter(|&&b| b == b'\n').count() as u64;
+        self.offset -= returned.len() as u64;
+    }
+
+    /// Получить не менее `len` необработанных байт, не извлекая их из источника.
+    ///
+    /// Возвращает меньшее количество байт, только если источник исчерпан.
+    pub(crate) fn peek(&mut self, len: usize) -> std::io::Result<&[u8]> {
+        let len = len.min(Self::CAPACITY);
+
+        if let Some(inner_buffer) = &self.inner_buffer
+            && self.start == self.end
+            && (inner_buffer.fill_buf)(&mut self.inner)?.len() >= len
+        {
+            return (inner_buffer.fill_buf)(&mut self.inner);
+        }
+
+        if self.buffer.is_empty() {
+            self.buffer = vec![0; Self::CAPACITY].into_boxed_slice();
+        }
+
+        while self.end - self.start < len {
+            if self.start > 0 {
+                self.buffer.copy_within(self.start..self.end, 0);
+                self.end -= self.start;
+                self.start = 0;
+            }
+
+            match self.read_inner() {
+                Ok(0) => break,
+                Ok(count) => self.end += count,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(&self.buffer[self.start..self.end])
+    }
+
+    /// Дописать данные из источника в конец собственного буфера.
+    ///
+    /// Возвращает количество дописанных байт.
+    fn read_inner(&mut self) -> std::io::Result<usize> {
+        let Some(inner_buffer) = &self.inner_buffer else {
+            return self.inner.read(&mut self.buffer[self.end..]);
+        };
+
+        let available = (inner_buffer.fill_buf)(&mut self.inner)?;
+        let count = available.len().min(self.buffer.len() - self.end);
+
+        self.buffer[self.end..self.end + count].copy_from_slice(&available[..count]);
+        (inner_buffer.consume)(&mut self.inner, count);
+
+        Ok(count)
+    }
+}
+
+impl<R: BufRead> PositionReader<R> {
+    /// Считывать данные непосредственно из буфера источника, не буферизуя их повторно.
+    ///
+    /// Данные, уже считанные в собственный буфер, обрабатываются до данных источника.
+    fn use_inner_buffer(&mut self) {
+        if self.start == self.end {
+            self.buffer = Box::default();
+            self.start = 0;
+            self.end = 0;
+        }
+
+        self.inner_buffer = Some(InnerBuffer {
+            fill_buf: R::fill_buf,
+            consume: R::consume,
+        });
+    }
+}
+
+/// Реализация трейта [`Read`] для [`PositionReader`].
+impl<R: Read> Read for PositionReader<R> {
+    /// Реализация метода [`Read::read`] для [`PositionReader`].
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let available = self.fill_buf()?;
+        let count = available.len().min(buf.len());
+
+        buf[..count].copy_from_slice(&available[..count]);
+        self.consume(count);
+
+        Ok(count)
+    }
+}
+
+/// Реализация трейта [`BufRead`] для [`PositionReader`].
+impl<R: Read> BufRead for PositionReader<R> {
+    /// Реализация метода [`BufRead::fill_buf`] для [`PositionReader`].
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        if self.start == self.end {
+            if let Some(inner_buffer) = &self.inner_buffer {
+                return (inner_buffer.fill_buf)(&mut self.inner);
+            }
+
+            self.start = 0;
+            self.end = self.inner.read(&mut self.buffer)?;
+        }
+
+        Ok(&self.buffer[self.start..self.end])
+    }
+
+    /// Реализация метода [`BufRead::consume`] для [`PositionReader`].
+    fn consume(&mut self, amount: usize) {
+        if self.start == self.end
+            && let Some(inner_buffer) = &self.inner_buffer
+        {
+            // Данные, возвращенные последним вызовом fill_buf, остаются в буфере источника,
+            // поэтому их повторное получение не обращается к источнику.
+            let Ok(available) = (inner_buffer.fill_buf)(&mut self.inner) else {
+                return;
+            };
+            let amount = amount.min(available.len());
+
+            self.newlines += available[..amount].iter().filter(|&&b| b == b'\n').count() as u64;
+            self.offset += amount as u64;
+
+            if let Some(retained) = self.retained.as_mut() {
+                retained.extend_from_slice(&available[..amount]);
+            }
+
+            (inner_buffer.consume)(&mut self.inner, amount);
+            return;
+        }
+
+        let amount = amount.min(self.end - self.start);
+        let consumed = &self.buffer[self.start..self.start + amount];
+
+        self.newlines += consumed.iter().filter(|&&b| b == b'\n').count() as u64;
+        self.offset += amount as u64;
+
+        if let Some(retained) = self.retained.as_mut() {
+            retained.extend_from_slice(consumed);
+        }
+
+        self.start += amount;
+    }
+}
+
+/// Функция считывания очередной записи о транзакции из источника.
+///
+/// Принимает порядковый номер считываемой записи (начиная с 0) и параметры ее разбора
+/// и возвращает `None` при достижении конца данных.
+pub(crate) type ReadRecordFn<R> =
+    fn(&mut PositionReader<R>, usize, &ParseContext) -> Result<Option<Record>, ReadError>;
+
+/// Функция считывания очередной записи о транзакции, использующая параметры формата
+/// (например, разметку полей, см. [`crate::FixedWidthLayout`]).
+type ReadRecordWithFn<R> = Box<
+    dyn FnMut(&mut PositionReader<R>, usize, &ParseContext) -> Result<Option<Record>, ReadError>
+        + Send,
+>;
+
+/// Способ считывания очередной записи о транзакции.
+enum RecordReader<R: Read> {
+    /// Функция формата, не имеющего параметров.
+    Fn(ReadRecordFn<R>),
+
+    /// Функция формата, использующая его параметры.
+    With(ReadRecordWithFn<R>),
+}
+
+impl<R: Read> RecordReader<R> {
+    /// Считать очередную запись о транзакции.
+    fn read(
+        &mut self,
+        reader: &mut PositionReader<R>,
+        index: usize,
+        context: &ParseContext,
+    ) -> Result<Option<Record>, ReadError> {
+        match self {
+            Self::Fn(read_record) => read_record(reader, index, context),
+            Self::With(read_record) => read_record(reader, index, context),
+        }
+    }
+}
+
+/// Сведения о содержимом источника, объявленные в заголовке формата.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DeclaredContent {
+    /// Количество записей о транзакциях.
+    pub(crate) records: u64,
+
+    /// Размер данных записей в байтах.
+    pub(crate) payload_len: u64,
+}
+
+/// Функция считывания заголовка формата, предшествующего записям о транзакциях.
+///
+/// Возвращает сведения о содержимом источника, если они объявлены в заголовке.
+pub(crate) type ReadHeaderFn<R> =
+    fn(&mut PositionReader<R>) -> Result<Option<DeclaredContent>, ReadError>;
+
+/// Функция считывания заголовка формата, использующая параметры формата
+/// (например, разделитель полей, см. [`crate::CsvOptions`]).
+type ReadHeaderWithFn<R> =
+    Box<dyn FnOnce(&mut PositionReader<R>) -> Result<Option<DeclaredContent>, ReadError> + Send>;
+
+/// Способ считывания заголовка формата.
+enum HeaderReader<R: Read> {
+    /// Функция формата, не имеющего параметров.
+    Fn(ReadHeaderFn<R>),
+
+    /// Функция формата, использующая его параметры.
+    With(ReadHeaderWithFn<R>),
+}
+
+impl<R: Read> HeaderReader<R> {
+    /// Считать заголовок формата.
+    fn read(self, reader: &mut PositionReader<R>) -> Result<Option<DeclaredContent>, ReadError> {
+        match self {
+            Self::Fn(read_header) => read_header(reader),
+            Self::With(read_header) => read_header(reader),
+        }
+    }
+}
+
+/// Функция восстановления после ошибки чтения записи о транзакции.
+///
+/// Перемещает позицию чтения к началу следующей записи.
+pub(crate) type RecoverFn<R> = fn(&mut PositionReader<R>) -> Result<(), ReadError>;
+
+/// Функция восстановления после ошибки чтения для форматов, в которых считывание записи
+/// всегда завершается на границе следующей записи.
+pub(crate) fn recover_nothing<R: Read>(_reader: &mut PositionReader<R>) -> Result<(), ReadError> {
+    Ok(())
+}
+
+/// Сведения о ходе чтения источника.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// Количество обработанных записей (включая некорректные).
+    pub records: usize,
+
+    /// Количество байт источника, обработанных при чтении записей.
+    pub bytes: u64,
+
+    /// Признак завершения чтения источника.
+    pub finished: bool,
+}
+
+/// Максимальное количество записей, под которое заранее выделяется память по сведениям
+/// из заголовка формата или ожидаемому количеству записей.
+pub(crate) const MAX_PREALLOCATED_RECORDS: usize = 1 << 20;
+
+/// Сведения для трассировки чтения источника при помощи `tracing`.
+#[cfg(feature = "tracing")]
+struct Trace {
+    /// Область трассировки, в которой считываются записи (см. [`RecordIter::instrument`]).
+    span: tracing::Span,
+
+    /// Момент считывания первой записи.
+    started: Option<Instant>,
+
+    /// Количество некорректных записей.
+    errors: usize,
+}
+
+#[cfg(feature = "tracing")]
+impl Trace {
+    /// Войти в область трассировки перед считыванием очередной записи.
+    fn enter(&mut self) -> tracing::span::EnteredSpan {
+        self.started.get_or_insert_with(Instant::now);
+        self.span.clone().entered()
+    }
+
+    /// Сообщить об ошибке чтения записи и, по завершении чтения источника, о количестве
+    /// считанных записей и скорости чтения.
+    fn record(&mut self, item: Option<&Result<Record, ReadError>>, progress: Progress) {
+        if let Some(Err(e)) = item {
+            let position = e.position();
+
+            // Ошибки считывания заголовка формата не относятся к записям.
+            if position.is_some() {
+                self.errors += 1;
+            }
+
+            tracing::warn!(
+                record = position.map(|position| position.record),
+                line = position.and_then(|position| position.line),
+                offset = position.map(|position| position.offset),
+                error = %e,
+                "failed to read record"
+            );
+        }
+
+        if progress.finished {
+            let elapsed = self
+                .started
+                .map(|started| started.elapsed())
+                .unwrap_or_default();
+            let records = progress.records - self.errors;
+
+            tracing::info!(
+                records,
+                errors = self.errors,
+                bytes = progress.bytes,
+                elapsed_ms = elapsed.as_millis() as u64,
+                records_per_sec = records as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+                "finished reading"
+            );
+        }
+    }
+}
+
+/// Функция, вызываемая после обработки каждой записи и по достижении конца источника.
+type ProgressFn = Box<dyn FnMut(Progress) + Send>;
+
+/// Итератор записей о транзакциях, считываемых из источника по одной.
+///
+/// Ошибки чтения дополняются позицией записи в источнике (см. [`ReadError::At`]).
+/// После первой ошибки итерация прекращается, если не указано обратное
+/// (см. [`RecordIter::continue_on_error`]).
+pub struct RecordIter<R: Read> {
+    /// Буферизованный источник данных с отслеживанием позиции.
+    reader: PositionReader<R>,
+
+    /// Функция считывания заголовка формата, если он еще не считан.
+    read_header: Option<HeaderReader<R>>,
+
+    /// Сведения о содержимом источника, объявленные в заголовке формата.
+    declared: Option<DeclaredContent>,
+
+    /// Смещение начала данных записей (сразу после заголовка формата).
+    payload_start: u64,
+
+    /// Функция считывания очередной записи в заданном формате.
+    read_record: RecordReader<R>,
+
+    /// Функция восстановления после ошибки чтения записи, если формат это допускает.
+    recover: Option<RecoverFn<R>>,
+
+    /// Признак продолжения итерации после ошибки чтения записи.
+    continue_on_error: bool,
+
+    /// Признак поиска следующей записи после ошибки со второго байта некорректной записи,
+    /// а не с места, где прервалось ее чтение (см. [`RecordIter::with_rescan`]).
+    rescan: bool,
+
+    /// Параметры разбора записей: нестрогое чтение, ограничения размеров и считываемые поля
+    /// (см. [`ParseOptions`]).
+    context: ParseContext,
+
+    /// Ожидаемое количество записей (см. [`ParseOptions::capacity_hint`]).
+    capacity_hint: Option<usize>,
+
+    /// Набор описаний, разделяемых считанными записями (см. [`ParseOptions::intern_descriptions`]).
+    descriptions: Option<DescriptionPool>,
+
+    /// Признак построчного формата, для которого в позиции ошибки указывается номер строки.
+    line_based: bool,
+
+    /// Признак того, что метку порядка байтов UTF-8 в начале источника построчного формата
+    /// еще предстоит пропустить.
+    skip_bom: bool,
+
+    /// Количество уже считанных записей.
+    index: usize,
+
+    /// Признак завершения итерации.
+    finished: bool,
+
+    /// Функция уведомления о ходе чтения.
+    progress: Option<ProgressFn>,
+
+    /// Получатель метрик чтения (см. [`RecordIter::metrics`]).
+    metrics: Option<Arc<dyn Metrics>>,
+
+    /// Сведения для трассировки чтения.
+    #[cfg(feature = "tracing")]
+    trace: Trace,
+}
+
+impl<R: Read> RecordIter<R> {
+    /// Создание итератора записей над указанным источником данных в построчном формате.
+    pub(crate) fn new(r: R, read_record: ReadRecordFn<R>) -> Self {
+        Self::with_reader(r, RecordReader::Fn(read_record))
+    }
+
+    /// Создание итератора записей над указанным источником данных в построчном формате,
+    /// функция считывания записей которого использует параметры формата.
+    pub(crate) fn new_with(
+        r: R,
+        read_record: impl FnMut(
+            &mut PositionReader<R>,
+            usize,
+            &ParseContext,
+        ) -> Result<Option<Record>, ReadError>
+        + Send
+        + 'static,
+    ) -> Self {
+        Self::with_reader(r, RecordReader::With(Box::new(read_record)))
+    }
+
+    /// Создание итератора записей над указанным источником данных в построчном формате.
+    fn with_reader(r: R, read_record: RecordReader<R>) -> Self {
+        Self {
+            reader: PositionReader::new(r),
+            read_header: None,
+            declared: None,
+            payload_start: 0,
+            read_record,
+            recover: None,
+            continue_on_error: false,
+            rescan: false,
+            context: ParseContext::default(),
+            capacity_hint: None,
+            descriptions: None,
+            line_based: true,
+            skip_bom: true,
+            index: 0,
+            finished: false,
+            progress: None,
+            metrics: None,
+            #[cfg(feature = "tracing")]
+            trace: Trace {
+                span: tracing::Span::none(),
+                started: None,
+                errors: 0,
+            },
+        }
+    }
+
+    /// Создание итератора записей над указанным источником данных в бинарном формате.
+    pub(crate) fn new_binary(r: R, read_record: ReadRecordFn<R>) -> Self {
+        Self {
+            line_based: false,
+            skip_bom: false,
+            ..Self::new(r, read_record)
+        }
+    }
+
+    /// Указать функцию считывания заголовка формата, вызываемую перед считыванием первой записи.
+    ///
+    /// Ошибки считывания заголовка не дополняются позицией в источнике.
+    pub(crate) fn with_header(self, read_header: ReadHeaderFn<R>) -> Self {
+        Self {
+            read_header: Some(HeaderReader::Fn(read_header)),
+            ..self
+        }
+    }
+
+    /// Указать функцию считывания заголовка формата, использующую параметры формата.
+    ///
+    /// См. [`RecordIter::with_header`].
+    pub(crate) fn with_header_with(
+        self,
+        read_header: impl FnOnce(&mut PositionReader<R>) -> Result<Option<DeclaredContent>, ReadError>
+        + Send
+        + 'static,
+    ) -> Self {
+        Self {
+            read_header: Some(HeaderReader::With(Box::new(read_header))),
+            ..self
+        }
+    }
+
+    /// Указать функцию восстановления после ошибки чтения записи.
+    pub(crate) fn with_recovery(self, recover: RecoverFn<R>) -> Self {
+        Self {
+            recover: Some(recover),
+            ..self
+        }
+    }
+
+    /// Возобновлять поиск следующей записи после ошибки со второго байта некорректной записи.
+    ///
+    /// Некорректная запись (например, оборванная) может захватить начало следующих за ней
+    /// целых записей, которые иначе были бы пропущены при восстановлении. Для этого данные
+    /// каждой записи сохраняются до ее успешного считывания.
+    pub(crate) fn with_rescan(self) -> Self {
+        Self {
+            rescan: true,
+            ..self
+        }
+    }
+
+    /// Продолжать итерацию после ошибки чтения записи со следующей записи.
+    ///
+    /// Если формат не допускает восстановления после ошибки (например, JSON-массив),
+    /// либо ошибка произошла при чтении заголовка, итерация все равно прекращается.
+    pub fn continue_on_error(self) -> Self {
+        Self {
+            continue_on_error: true,
+            ..self
+        }
+    }
+
+    /// Считывать неизвестные значения перечислений (например, типы транзакций) без ошибки
+    /// (см. [`ParseOptions::lenient`]).
+    pub fn lenient(mut self) -> Self {
+        self.context.lenient = true;
+        self
+    }
+
+    /// Указать ограничения размеров считываемых записей (см. [`ParseOptions::limits`]).
+    pub fn limits(mut self, limits: SizeLimits) -> Self {
+        self.context.limits = limits;
+        self
+    }
+
+    /// Указать ожидаемое количество записей, под которое заранее выделяется память при чтении
+    /// всех записей (см. [`ParseOptions::capacity_hint`]).
+    pub fn capacity_hint(self, records: usize) -> Self {
+        Self {
+            capacity_hint: Some(records),
+            ..self
+        }
+    }
+
+    /// Разделять одинаковые описания транзакций между считанными записями
+    /// (см. [`ParseOptions::intern_descriptions`]).
+    pub fn intern_descriptions(self) -> Self {
+        Self {
+            descriptions: Some(DescriptionPool::new()),
+            ..self
+        }
+    }
+
+    /// Считывать из источника только значения указанных полей
+    /// (см. [`ParseOptions::projection`]).
+    pub fn projection(mut self, projection: Projection) -> Self {
+        self.context.projection = projection;
+        self
+    }
+
+    /// Указать функцию, вызываемую после обработки каждой записи и по достижении конца источника
+    /// со сведениями о ходе чтения, например, для отображения прогресса.
+    pub fn on_progress(self, progress: impl FnMut(Progress) + Send + 'static) -> Self {
+        Self {
+            progress: Some(Box::new(progress)),
+            ..self
+        }
+    }
+
+    /// Сообщать указанному получателю метрик о каждой считанной записи (размер и время чтения)
+    /// и о каждой ошибке чтения.
+    pub fn metrics(self, metrics: Arc<dyn Metrics>) -> Self {
+        Self {
+            metrics: Some(metrics),
+            ..self
+        }
+    }
+
+    /// Считывать записи в указанной области трассировки `tracing`, например, с путем и форматом
+    /// файла, чтобы события чтения записей были связаны с источником.
+    ///
+    /// Об ошибках чтения записей сообщается событиями уровня `WARN`, а по завершении чтения —
+    /// событием уровня `INFO` с количеством записей, временем и скоростью чтения.
+    #[cfg(feature = "tracing")]
+    pub fn instrument(mut self, span: tracing::Span) -> Self {
+        self.trace.span = span;
+        self
+    }
+
+    /// Уведомить о ходе чтения, если указана соответствующая функция.
+    fn report_progress(&mut self) {
+        if let Some(progress) = self.progress.as_mut() {
+            progress(Progress {
+                records: self.index,
+                bytes: self.reader.offset,
+                finished: self.finished,
+            });
+        }
+    }
+
+    /// Считать очередную запись о транзакции вместе с позицией ее начала в источнике,
+    /// например, для указания номера строки при сообщении о нарушениях в записи.
+    pub fn next_with_position(&mut self) -> Option<Result<(Position, Record), ReadError>> {
+        if let Err(e) = self.read_header() {
+            return Some(Err(e));
+        }
+
+        let position = self.current_position();
+
+        self.next()
+            .map(|result| result.map(|record| (position, record)))
+    }
+
+    /// Считать очередную запись о транзакции вместе со смещением ее начала в источнике.
+    pub(crate) fn next_with_offset(&mut self) -> Option<Result<(u64, Record), ReadError>> {
+        self.next_with_position()
+            .map(|result| result.map(|(position, record)| (position.offset, record)))
+    }
+
+    /// Пропустить метку порядка байтов UTF-8 в начале источника, если она есть.
+    fn skip_bom(&mut self) -> Result<(), ReadError> {
+        if self.reader.peek(UTF8_BOM.len())?.starts_with(&UTF8_BOM) {
+            self.reader.consume(UTF8_BOM.len());
+        }
+
+        Ok(())
+    }
+
+    /// Считать заголовок формата, если он еще не считан.
+    ///
+    /// Перед заголовком построчного формата пропускается метка порядка байтов UTF-8.
+    fn read_header(&mut self) -> Result<(), ReadError> {
+        if std::mem::take(&mut self.skip_bom) {
+            self.skip_bom().inspect_err(|_| self.finished = true)?;
+        }
+
+        if let Some(read_header) = self.read_header.take() {
+            self.declared = read_header
+                .read(&mut self.reader)
+                .inspect_err(|_| self.finished = true)?;
+            self.payload_start = self.reader.offset;
+        }
+
+        Ok(())
+    }
+
+    /// Количество байт источника, обработанных при чтении.
+    pub(crate) fn offset(&self) -> u64 {
+        self.reader.offset
+    }
+
+    /// Пропустить оставшиеся данные источника, прекратив итерацию.
+    ///
+    /// Возвращает смещение конца источника (или места, где чтение из него завершилось ошибкой).
+    pub(crate) fn skip_rest(&mut self) -> u64 {
+        self.finished = true;
+
+        while let Ok(buffer) = self.reader.fill_buf() {
+            if buffer.is_empty() {
+                break;
+            }
+
+            let len = buffer.len();
+            self.reader.consume(len);
+        }
+
+        self.reader.offset
+    }
+
+    /// Ожидаемое количество записей для предварительного выделения памяти.
+    ///
+    /// Количество записей, объявленное в заголовке формата, имеет приоритет перед ожидаемым.
+    fn expected_capacity(&self) -> usize {
+        let records = match (self.declared, self.capacity_hint) {
+            (Some(declared), _) => declared.records,
+            (None, Some(hint)) => hint as u64,
+            (None, None) => 0,
+        };
+
+        records.min(MAX_PREALLOCATED_RECORDS as u64) as usize
+    }
+
+    /// Считать все записи о транзакциях, прерывая чтение при первой ошибке.
+    ///
+    /// Если количество записей объявлено в заголовке формата, память под них выделяется заранее.
+    pub(crate) fn read_all(mut self) -> Result<Vec<Record>, ReadError> {
+        self.read_header()?;
+
+        let mut records = Vec::with_capacity(self.expected_capacity());
+
+        for result in self {
+            records.push(result?);
+        }
+
+        Ok(records)
+    }
+
+    /// Считать все записи о транзакциях с учетом параметров обработки ошибок.
+    ///
+    /// Ошибки, после которых продолжение чтения невозможно, возвращаются независимо
+    /// от параметров.
+    pub(crate) fn read_report(self, options: ParseOptions) -> Result<ParseReport, ReadError> {
+        let mut iter = match options.on_error {
+            OnError::Fail => self,
+            OnError::Skip | OnError::Collect => self.continue_on_error(),
+        };
+        iter.context.lenient |= options.lenient;
+        iter.context.limits = options.limits;
+        iter.capacity_hint = options.capacity_hint.or(iter.capacity_hint);
+        iter.context.projection = options.projection;
+
+        if options.intern_descriptions {
+            iter = iter.intern_descriptions();
+        }
+
+        iter.read_header()?;
+
+        let mut report = ParseReport {
+            records: Vec::with_capacity(iter.expected_capacity()),
+            ..Default::default()
+        };
+
+        while let Some(result) = iter.next() {
+            match result {
+                Ok(record) => report.records.push(record),
+                Err(e) => {
+                    let Some(position) = e.position().filter(|_| !iter.finished) else {
+                        return Err(e);
+                    };
+
+                    report.skipped.push(position.record);
+
+                    if options.on_error == OnError::Collect {
+                        report.errors.push(e);
+                    }
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Проверить, не оборвался ли источник раньше объявленного в заголовке формата конца данных.
+    ///
+    /// Возвращает ошибку, если источник исчерпан, а считано меньше объявленного количества записей.
+    fn check_truncated(&mut self) -> Result<(), ReadError> {
+        let Some(declared) = self.declared else {
+            return Ok(());
+        };
+
+        if (self.index as u64) < declared.records && self.reader.peek(1)?.is_empty() {
+            return Err(ReadError::Truncated {
+                expected_records: declared.records,
+                expected_len: declared.payload_len,
+                actual_len: self.reader.offset - self.payload_start,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Текущая позиция чтения в источнике.
+    fn current_position(&self) -> Position {
+        Position {
+            record: self.index + 1,
+            line: self.line_based.then_some(self.reader.newlines + 1),
+            offset: self.reader.offset,
+        }
+    }
+}
+
+/// Реализация трейта [`Iterator`] для [`RecordIter`].
+impl<R: Read> Iterator for RecordIter<R> {
+    type Item = Result<Record, ReadError>;
+
+    /// Реализация метода [`Iterator::next`] для [`RecordIter`].
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        #[cfg(feature = "tracing")]
+        let _entered = self.trace.enter();
+
+        let started = self
+            .metrics
+            .as_ref()
+            .map(|_| (Instant::now(), self.reader.offset));
+
+        let item = self.next_record();
+
+        if let Some(metrics) = &self.metrics
+            && let Some((started, offset)) = started
+        {
+            match &item {
+                Some(Ok(_)) => metrics.record_read(self.reader.offset - offset, started.elapsed()),
+                Some(Err(_)) => metrics.read_error(),
+                None => {}
+            }
+        }
+
+        #[cfg(feature = "tracing")]
+        self.trace.record(
+            item.as_ref(),
+            Progress {
+                records: self.index,
+                bytes: self.reader.offset,
+                finished: self.finished,
+            },
+        );
+
+        if item.is_some() || self.finished {
+            self.report_progress();
+        }
+
+        item
+    }
+}
+
+impl<R: Read> RecordIter<R> {
+    /// Считать очередную запись о транзакции, дополнив ошибку чтения позицией в источнике.
+    fn next_record(&mut self) -> Option<Result<Record, ReadError>> {
+        if let Err(e) = self.read_header() {
+            return Some(Err(e));
+        }
+
+        if let Some(declared) = self.declared
+            && self.index as u64 >= declared.records
+        {
+            self.finished = true;
+            return None;
+        }
+
+        let position = self.current_position();
+
+        let rescan = self.rescan && self.continue_on_error && self.recover.is_some();
+
+        if rescan {
+            self.reader.retain();
+        }
+
+        // Обрыв источника раньше объявленного в заголовке конца данных сообщается
+        // вместо ошибки чтения неполной записи.
+        let result = match self
+            .read_record
+            .read(&mut self.reader, self.index, &self.context)
+        {
+            Ok(Some(record)) => Ok(Some(record)),
+            result => self.check_truncated().and(result),
+        };
+
+        match result {
+            Ok(Some(mut record)) => {
+                if !self.context.projection.is_all() {
+                    record.project(self.context.projection);
+                }
+
+                if let Some(descriptions) = self.descriptions.as_mut() {
+                    record.intern_description(descriptions);
+                }
+
+                self.index += 1;
+                Some(Ok(record))
+            }
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(e) => {
+                self.index += 1;
+
+                // Если при ошибке не было считано ни одного байта, продолжение чтения
+                // привело бы к повторению той же ошибки.
+                self.finished = match self.recover {
+                    Some(recover)
+                        if self.continue_on_error && self.reader.offset > position.offset =>
+                    {
+                        if rescan {
+                            self.reader.rewind(1);
+                        }
+
+                        recover(&mut self.reader).is_err()
+                    }
+                    _ => true,
+                };
+
+                Some(Err(ReadError::At {
+                    position,
+                    source: Box::new(e),
+                }))
+            }
+        }
+    }
+}
+
+impl<R: BufRead> RecordIter<R> {
+    /// Считывать данные непосредственно из буфера источника, уже буферизованного вызывающей
+    /// стороной (например, [`std::io::BufReader`]), не копируя их в буфер итератора.
+    pub fn buffered(mut self) -> Self {
+        self.reader.use_inner_buffer();
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::{Position, ReadError};
+    use crate::options::{OnError, ParseOptions, Projection, SizeLimits};
+    use crate::record::RecordKey;
+    use crate::record::errors::{ParseRecordFromBinError, ParseRecordFromTxtError};
+    use crate::record::{Record, Status, TxType};
+    use crate::{RecordIter, YPBank, YPBankImpl, YPBankText};
+    use rstest::rstest;
+    use std::io::Cursor;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_iter_stops_after_error() {
+        let data = "TX_ID: 1\nUNEXPECTED\n\nTX_ID: 2\n";
+
+        let mut iter = YPBankText::iter_from(Cursor::new(data));
+
+        let result = iter.next().unwrap().unwrap_err();
+        assert!(matches!(
+            result.inner(),
+            ReadError::FromText(ParseRecordFromTxtError::UnexpectedError(_))
+        ));
+        assert!(iter.next().is_none());
+    }
+
+    #[rstest]
+    #[case(
+        "text",
+        "TX_ID: 1\nTX_TYPE: DEPOSIT\nFROM_USER_ID: 0\nTO_USER_ID: 2\nAMOUNT: 100\nTIMESTAMP: 1\nSTATUS: SUCCESS\nDESCRIPTION: \"\"\n\nTX_ID: ABC\n",
+        Position { record: 2, line: Some(10), offset: 114 }
+    )]
+    #[case(
+        "csv",
+        "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n1,DEPOSIT,0,2,100,1,SUCCESS,\"\"\n1,ABC\n",
+        Position { record: 2, line: Some(3), offset: 105 }
+    )]
+    fn test_iter_error_position(
+        #[case] format: &str,
+        #[case] data: &str,
+        #[case] expected: Position,
+    ) {
+        let format = YPBankImpl::try_from(format).unwrap();
+
+        let result = format.read_from(&mut data.as_bytes()).unwrap_err();
+
+        assert_eq!(result.position(), Some(expected));
+    }
+
+    #[rstest]
+    fn test_iter_buffered(
+        #[values("text", "csv", "bin", "json", "jsonl")] format: &str,
+        #[values(1, 3, 8192)] capacity: usize,
+    ) {
+        let format = YPBankImpl::try_from(format).unwrap();
+        let records = crate::tests::get_data_to_write();
+
+        let mut data = crate::line_ending::UTF8_BOM.to_vec();
+        if format == YPBankImpl::Bin {
+            data.clear();
+        }
+        format.write_to(records.clone(), &mut data).unwrap();
+
+        let reader = std::io::BufReader::with_capacity(capacity, data.as_slice());
+
+        assert_eq!(format.read_from_buffered(reader).unwrap(), records);
+    }
+
+    #[rstest]
+    fn test_iter_buffered_resync(#[values(1, 3, 16)] capacity: usize) {
+        let mut data = vec![];
+        let mut record = vec![];
+        for (index, record_to_write) in crate::tests::get_data_to_write().iter().enumerate() {
+            record.clear();
+            record_to_write.to_bin(&mut record).unwrap();
+
+            data.extend_from_slice(&record);
+            if index == 0 {
+                data.extend_from_slice(b"garbage YP");
+            }
+        }
+
+        fn collect<R: std::io::Read>(iter: RecordIter<R>) -> Vec<Result<Record, String>> {
+            iter.continue_on_error()
+                .map(|result| result.map_err(|e| e.to_string()))
+                .collect()
+        }
+
+        let expected = collect(YPBankImpl::Bin.iter_from(data.as_slice()));
+        let reader = std::io::BufReader::with_capacity(capacity, data.as_slice());
+
+        assert_eq!(expected.iter().filter(|result| result.is_ok()).count(), 3);
+        assert_eq!(
+            collect(YPBankImpl::Bin.iter_from_buffered(reader)),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case(
+        "text",
+        "TX_ID: 1\nTX_TYPE: DEPOSIT\nFROM_USER_ID: 0\nTO_USER_ID: 2\nAMOUNT: 100\nTIMESTAMP: 1\nSTATUS: SUCCESS\nDESCRIPTION: \"\"\n\nTX_ID: ABC\n",
+        Position { record: 2, line: Some(10), offset: 114 }
+    )]
+    #[case(
+        "csv",
+        "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n1,DEPOSIT,0,2,100,1,SUCCESS,\"\"\n1,ABC\n",
+        Position { record: 2, line: Some(3), offset: 105 }
+    )]
+    fn test_iter_buffered_error_position(
+        #[case] format: &str,
+        #[case] data: &str,
+        #[case] expected: Position,
+    ) {
+        let format = YPBankImpl::try_from(format).unwrap();
+        let reader = std::io::BufReader::with_capacity(16, data.as_bytes());
+
+        let result = format.read_from_buffered(reader).unwrap_err();
+
+        assert_eq!(result.position(), Some(expected));
+    }
+
+    #[test]
+    fn test_next_with_position() {
+        let data = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n1,DEPOSIT,0,2,100,1,SUCCESS,\"\"\n2,DEPOSIT,0,2,100,1,SUCCESS,\"\"\n";
+
+        let mut iter = YPBankImpl::Csv.iter_from(data.as_bytes());
+
+        let positions = std::iter::from_fn(|| iter.next_with_position())
+            .map(|result| result.unwrap().0)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            positions,
+            vec![
+                Position {
+                    record: 1,
+                    line: Some(2),
+                    offset: 74
+                },
+                Position {
+                    record: 2,
+                    line: Some(3),
+                    offset: 105
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_iter_error_position_bin() {
+        let mut buffer = vec![];
+        YPBankImpl::Bin
+            .write_to(crate::tests::get_data_to_write(), &mut buffer)
+            .unwrap();
+        buffer.extend([0x59, 0x51, 0x42, 0x4E]);
+
+        let result = YPBankImpl::Bin
+            .read_from(&mut buffer.as_slice())
+            .unwrap_err();
+
+        assert_eq!(
+            result.to_string(),
+            "Binary format parsing error: Invalid magic number (at record 4, byte 212)"
+        );
+    }
+
+    #[test]
+    fn test_iter_from_every_format() {
+        let records = crate::tests::get_data_to_write();
+
+        for format in ["text", "csv", "bin", "json", "jsonl"] {
+            let format = YPBankImpl::try_from(format).unwrap();
+
+            let mut buffer = vec![];
+            format.write_to(records.clone(), &mut buffer).unwrap();
+
+            let result = format
+                .iter_from(Cursor::new(buffer))
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+
+            assert_eq!(result, records);
+        }
+    }
+
+    #[rstest]
+    #[case("text")]
+    #[case("csv")]
+    #[case("bin")]
+    #[case("json")]
+    #[case("jsonl")]
+    fn test_iter_progress(#[case] format: &str) {
+        let format = YPBankImpl::try_from(format).unwrap();
+
+        let mut buffer = vec![];
+        format
+            .write_to(crate::tests::get_data_to_write(), &mut buffer)
+            .unwrap();
+        let len = buffer.len() as u64;
+
+        let reports = Arc::new(Mutex::new(vec![]));
+        let sink = reports.clone();
+
+        let count = format
+            .iter_from(Cursor::new(buffer))
+            .on_progress(move |progress| sink.lock().unwrap().push(progress))
+            .count();
+
+        let reports = reports.lock().unwrap();
+
+        assert_eq!(count, 3);
+        assert_eq!(
+            reports.iter().map(|p| p.records).collect::<Vec<_>>(),
+            vec![1, 2, 3, 3]
+        );
+        assert!(reports.windows(2).all(|w| w[0].bytes <= w[1].bytes));
+        assert_eq!(
+            reports.iter().map(|p| p.finished).collect::<Vec<_>>(),
+            vec![false, false, false, true]
+        );
+        assert!(reports[2].bytes <= len);
+    }
+
+    /// Записать тестовые записи в заданном формате, повредив тип второй записи.
+    fn get_corrupted_data(format: YPBankImpl) -> Vec<u8> {
+        let mut buffer = vec![];
+        format
+            .write_to(crate::tests::get_data_to_write(), &mut buffer)
+            .unwrap();
+
+        String::from_utf8(buffer)
+            .unwrap()
+            .replacen("TRANSFER", "ABC", 1)
+            .into_bytes()
+    }
+
+    #[rstest]
+    #[case("text")]
+    #[case("csv")]
+    #[case("jsonl")]
+    fn test_read_with_options_skip(#[case] format: &str) {
+        let format = YPBankImpl::try_from(format).unwrap();
+        let data = get_corrupted_data(format);
+
+        let options = ParseOptions {
+            on_error: OnError::Skip,
+            ..Default::default()
+        };
+        let report = format
+            .read_with_options(&mut data.as_slice(), options)
+            .unwrap();
+
+        let mut expected = crate::tests::get_data_to_write();
+        expected.remove(1);
+
+        assert_eq!(report.records, expected);
+        assert_eq!(report.skipped, vec![2]);
+        assert!(report.errors.is_empty());
+    }
+
+    /// Назначение журнала, сохраняющее записанные данные в общий буфер.
+    #[cfg(feature = "tracing")]
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+    #[cfg(feature = "tracing")]
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_iter_tracing() {
+        let data = get_corrupted_data(YPBankImpl::Csv);
+        let logs = LogBuffer::default();
+
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .flatten_event(true)
+            .with_writer({
+                let logs = logs.clone();
+                move || logs.clone()
+            })
+            .finish();
+
+        tracing::subscriber::with_default(subscriber, || {
+            let report = YPBankImpl::Csv
+                .iter_from(data.as_slice())
+                .instrument(tracing::info_span!("file", path = "data.csv"))
+                .read_report(ParseOptions {
+                    on_error: OnError::Skip,
+                    ..Default::default()
+                })
+                .unwrap();
+
+            assert_eq!(report.skipped, vec![2]);
+        });
+
+        let logs = logs.0.lock().unwrap();
+        let events = logs
+            .split(|&b| b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(events.len(), 2);
+
+        assert_eq!(events[0]["level"], "WARN");
+        assert_eq!(events[0]["record"], 2);
+        assert_eq!(events[0]["span"]["path"], "data.csv");
+
+        assert_eq!(events[1]["message"], "finished reading");
+        assert_eq!(events[1]["records"], 2);
+        assert_eq!(events[1]["errors"], 1);
+        assert_eq!(events[1]["span"]["path"], "data.csv");
+    }
+
+    #[test]
+    fn test_read_with_options_collect() {
+        let data = get_corrupted_data(YPBankImpl::Csv);
+
+        let options = ParseOptions {
+            on_error: OnError::Collect,
+            ..Default::default()
+        };
+        let report = YPBankImpl::Csv
+            .read_with_options(&mut data.as_slice(), options)
+            .unwrap();
+
+        assert_eq!(report.records.len(), 2);
+        assert_eq!(report.skipped, vec![2]);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(
+            report.errors[0].to_string(),
+            "CSV format parsing error: Invalid value: ABC (Invalid TX_TYPE: ABC) (at record 2, line 3, byte 165)"
+        );
+    }
+
+    #[test]
+    fn test_read_with_options_skip_bin() {
+        let mut data = vec![];
+        YPBankImpl::Bin
+            .write_to(crate::tests::get_data_to_write(), &mut data)
+            .unwrap();
+
+        // Повредить MAGIC второй записи.
+        let second = data[1..]
+            .windows(4)
+            .position(|w| w == crate::record::Record::BINARY_MAGIC)
+            .unwrap()
+            + 1;
+        data[second] = 0;
+
+        let options = ParseOptions {
+            on_error: OnError::Collect,
+            ..Default::default()
+        };
+        let report = YPBankImpl::Bin
+            .read_with_options(&mut data.as_slice(), options)
+            .unwrap();
+
+        let mut expected = crate::tests::get_data_to_write();
+        expected.remove(1);
+
+        assert_eq!(report.records, expected);
+        assert_eq!(report.skipped, vec![2]);
+        assert_eq!(
+            report.errors[0].to_string(),
+            format!(
+                "Binary format parsing error: Invalid magic number (at record 2, byte {second})"
+            )
+        );
+    }
+
+    #[test]
+    fn test_read_with_options_limits_bin() {
+        let mut data = vec![];
+        YPBankImpl::Bin
+            .write_to(crate::tests::get_data_to_write(), &mut data)
+            .unwrap();
+
+        let options = ParseOptions {
+            on_error: OnError::Collect,
+            limits: SizeLimits {
+                max_description_len: 16,
+                ..SizeLimits::default()
+            },
+            ..Default::default()
+        };
+        let report = YPBankImpl::Bin
+            .read_with_options(&mut data.as_slice(), options)
+            .unwrap();
+
+        assert_eq!(report.records, crate::tests::get_data_to_write()[1..2]);
+        assert_eq!(report.skipped, vec![1, 3]);
+        assert!(report.errors.iter().all(|e| {
+            matches!(
+                e.inner(),
+                ReadError::FromBin(ParseRecordFromBinError::DescriptionTooLong { max: 16, .. })
+            )
+        }));
+    }
+
+    #[rstest]
+    #[case(None, 3)]
+    #[case(Some(100), 100)]
+    #[case(Some(usize::MAX), crate::iter::MAX_PREALLOCATED_RECORDS)]
+    fn test_read_with_options_capacity_hint(
+        #[case] capacity_hint: Option<usize>,
+        #[case] expected: usize,
+    ) {
+        let mut data = vec![];
+        YPBankImpl::Csv
+            .write_to(crate::tests::get_data_to_write(), &mut data)
+            .unwrap();
+
+        let options = ParseOptions {
+            capacity_hint,
+            ..Default::default()
+        };
+        let report = YPBankImpl::Csv
+            .read_with_options(&mut data.as_slice(), options)
+            .unwrap();
+
+        assert_eq!(report.records, crate::tests::get_data_to_write());
+        assert!(report.records.capacity() >= expected);
+    }
+
+    #[rstest]
+    fn test_read_with_options_intern_descriptions(#[values(false, true)] intern: bool) {
+        let mut records = crate::tests::get_data_to_write();
+        for record in &mut records {
+            record.set_description("Monthly subscription payment for premium services");
+        }
+
+        let mut data = vec![];
+        YPBankImpl::Csv
+            .write_to(records.clone(), &mut data)
+            .unwrap();
+
+        let options = ParseOptions {
+            intern_descriptions: intern,
+            ..Default::default()
+        };
+        let report = YPBankImpl::Csv
+            .read_with_options(&mut data.as_slice(), options)
+            .unwrap();
+
+        assert_eq!(report.records, records);
+
+        let first = report.records[0].description().as_ptr();
+        let shared = report.records[1..]
+            .iter()
+            .all(|record| record.description().as_ptr() == first);
+        assert_eq!(shared, intern);
+    }
+
+    #[rstest]
+    fn test_read_with_options_projection(
+        #[values(YPBankImpl::Bin, YPBankImpl::Csv, YPBankImpl::Json)] format: YPBankImpl,
+    ) {
+        let mut records = crate::tests::get_data_to_write();
+        records[0]
+            .metadata_mut()
+            .insert("channel".to_string(), "atm".to_string());
+
+        let mut data = vec![];
+        format.write_to(records.clone(), &mut data).unwrap();
+
+        let projection =
+            Projection::new(&[RecordKey::TxId, RecordKey::Amount, RecordKey::Timestamp]);
+        let options = ParseOptions {
+            projection,
+            ..Default::default()
+        };
+        let report = format
+            .read_with_options(&mut data.as_slice(), options)
+            .unwrap();
+
+        let record = &report.records[0];
+        assert_eq!(record.tx_id(), records[0].tx_id());
+        assert_eq!(record.amount(), records[0].amount());
+        assert_eq!(record.timestamp(), records[0].timestamp());
+        assert_eq!(record.to_user_id(), 0);
+        assert_eq!(record.description(), "");
+        assert!(record.metadata().is_empty());
+
+        for record in &mut records {
+            record.project(projection);
+        }
+        assert_eq!(report.records, records);
+    }
+
+    #[test]
+    fn test_projection_skips_bin_description_decoding() {
+        let mut data = vec![];
+        YPBankImpl::Bin
+            .write_to(crate::tests::get_data_to_write(), &mut data)
+            .unwrap();
+
+        // Некорректный UTF-8 в описании первой записи.
+        data[55] = 0xFF;
+
+        assert!(YPBankImpl::Bin.read_from(&mut data.as_slice()).is_err());
+
+        let records = YPBankImpl::Bin
+            .iter_from(data.as_slice())
+            .projection(Projection::new(&[RecordKey::Amount]))
+            .read_all()
+            .unwrap();
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].amount(), Some(10000));
+    }
+
+    #[test]
+    fn test_capacity_hint_declared_records_take_priority() {
+        let mut data = vec![];
+        crate::YPBankBin {
+            records: crate::tests::get_data_to_write(),
+        }
+        .write_container_to(&mut data)
+        .unwrap();
+
+        let mut iter = YPBankImpl::Bin
+            .iter_from(data.as_slice())
+            .capacity_hint(1000);
+        iter.read_header().unwrap();
+
+        assert_eq!(iter.expected_capacity(), 3);
+    }
+
+    #[rstest]
+    #[case(OnError::Fail, "csv")]
+    #[case(OnError::Skip, "json")]
+    #[case(OnError::Collect, "json")]
+    fn test_read_with_options_fail(#[case] on_error: OnError, #[case] format: &str) {
+        let format = YPBankImpl::try_from(format).unwrap();
+        let data = get_corrupted_data(format);
+
+        let result = format.read_with_options(
+            &mut data.as_slice(),
+            ParseOptions {
+                on_error,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(result.unwrap_err().position().unwrap().record, 2);
+    }
+
+    #[rstest]
+    #[case("text", "CHARGEBACK", "ON_HOLD")]
+    #[case("csv", "CHARGEBACK", "ON_HOLD")]
+    #[case("json", "CHARGEBACK", "ON_HOLD")]
+    #[case("jsonl", "7", "9")]
+    #[case("bin", "7", "9")]
+    fn test_iter_lenient(#[case] format: &str, #[case] tx_type: &str, #[case] status: &str) {
+        let format = YPBankImpl::try_from(format).unwrap();
+        let mut records = crate::tests::get_data_to_write();
+        records[1].set_tx_type(TxType::Unknown(tx_type.to_string()));
+        records[2].set_status(Status::Other(status.to_string()));
+
+        let mut data = vec![];
+        format.write_to(records.clone(), &mut data).unwrap();
+
+        let result = format.read_from(&mut data.as_slice()).unwrap_err();
+        assert_eq!(result.position().unwrap().record, 2);
+        assert!(
+            result
+                .to_string()
+                .contains(&format!("Invalid TX_TYPE: {tx_type}"))
+        );
+
+        let result = format
+            .iter_from(data.as_slice())
+            .lenient()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(result, records);
+    }
+
+    #[test]
+    fn test_read_with_options_lenient() {
+        let data = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n1,REFUND,2,1,100,1,SUCCESS,\"\"\n2,CHARGEBACK,2,1,100,1,ON_HOLD,\"\"\n3,NEW TYPE,2,1,100,1,SUCCESS,\"\"\n";
+        let options = ParseOptions {
+            on_error: OnError::Skip,
+            lenient: true,
+            ..Default::default()
+        };
+
+        let report = YPBankImpl::Csv
+            .read_with_options(&mut data.as_bytes(), options)
+            .unwrap();
+
+        assert_eq!(
+            report
+                .records
+                .iter()
+                .map(|record| (record.tx_type().clone(), record.status().clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (TxType::Refund, Status::Success),
+                (
+                    TxType::Unknown("CHARGEBACK".to_string()),
+                    Status::Other("ON_HOLD".to_string())
+                )
+            ]
+        );
+        assert_eq!(report.skipped, vec![3]);
+    }
+
+    #[test]
+    fn test_read_with_options_invalid_header() {
+        let options = ParseOptions {
+            on_error: OnError::Skip,
+            ..Default::default()
+        };
+        let result = YPBankImpl::Csv.read_with_options(&mut "TX_ID\n".as_bytes(), options);
+
+        assert!(matches!(result, Err(ReadError::FromCsv(_))));
+    }
+}
@@ -0,0 +1,312 @@
+use super::YPBank;
+use super::errors::{ReadError, WriteError};
+use super::iter::{DeclaredContent, RecordIter};
+use super::options::ParseContext;
+use super::record::Record;
+use super::writer::RecordWriter;
+use serde::de::Error as _;
+use std::io::{BufRead, BufWriter, Read, Write};
+
+#[derive(Debug)]
+pub struct YPBankJson {
+    /// Записи о банковских операциях.
+    pub records: Vec<Record>,
+}
+
+impl YPBankJson {
+    /// Пропустить пробельные символы и вернуть следующий за ними байт, не извлекая его из источника.
+    fn peek_non_whitespace<R: BufRead>(reader: &mut R) -> Result<Option<u8>, ReadError> {
+        loop {
+            let buffer = reader.fill_buf()?;
+
+            if buffer.is_empty() {
+                return Ok(None);
+            }
+
+            match buffer.iter().position(|b| !b.is_ascii_whitespace()) {
+                Some(pos) => {
+                    let byte = buffer[pos];
+                    reader.consume(pos);
+                    return Ok(Some(byte));
+                }
+                None => {
+                    let len = buffer.len();
+                    reader.consume(len);
+                }
+            }
+        }
+    }
+
+    /// Сформировать ошибку нарушения структуры JSON-массива.
+    fn syntax_error(description: &str) -> ReadError {
+        ReadError::FromJson(serde_json::Error::custom(description))
+    }
+
+    /// Считать открывающую скобку JSON-массива объектов.
+    fn read_header<B: BufRead>(reader: &mut B) -> Result<Option<DeclaredContent>, ReadError> {
+        match Self::peek_non_whitespace(reader)? {
+            Some(b'[') => {
+                reader.consume(1);
+                Ok(None)
+            }
+            Some(_) => Err(Self::syntax_error("expected `[`")),
+            None => Err(Self::syntax_error("unexpected end of JSON array")),
+        }
+    }
+
+    /// Считать очередную запись о банковской операции из JSON-массива объектов.
+    ///
+    /// Перед каждой записью, кроме первой, ожидается запятая. После закрывающей скобки массива
+    /// допускаются только пробельные символы.
+    fn read_record<B: BufRead>(
+        reader: &mut B,
+        index: usize,
+        context: &ParseContext,
+    ) -> Result<Option<Record>, ReadError> {
+        match Self::peek_non_whitespace(reader)? {
+            Some(b']') => {
+                reader.consume(1);
+
+                match Self::peek_non_whitespace(reader)? {
+                    None => Ok(None),
+                    Some(_) => Err(Self::syntax_error("trailing characters after JSON array")),
+                }
+            }
+            Some(b',') if index > 0 => {
+                reader.consume(1);
+                Self::deserialize_record(reader, context)
+            }
+            Some(_) if index == 0 => Self::deserialize_record(reader, context),
+            Some(_) => Err(Self::syntax_error("expected `,` or `]`")),
+            None => Err(Self::syntax_error("unexpected end of JSON array")),
+        }
+    }
+
+    /// Десериализовать очередной JSON-объект записи о банковской операции.
+    fn deserialize_record<B: BufRead>(
+        reader: &mut B,
+        context: &ParseContext,
+    ) -> Result<Option<Record>, ReadError> {
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+
+        Ok(Some(Record::deserialize_with(&mut deserializer, context)?))
+    }
+
+    /// Записать очередную запись о банковской операции в JSON-массив объектов.
+    ///
+    /// Каждая запись размещается на отдельной строке внутри массива.
+    fn write_record<W: Write>(
+        writer: &mut BufWriter<W>,
+        record: &Record,
+        index: usize,
+    ) -> Result<(), WriteError> {
+        writer.write_all(if index == 0 { b"[\n  " } else { b",\n  " })?;
+        record.to_json(writer)?;
+
+        Ok(())
+    }
+
+    /// Завершить JSON-массив объектов закрывающей скобкой.
+    fn finish<W: Write>(writer: &mut BufWriter<W>, count: usize) -> Result<(), WriteError> {
+        writer.write_all(if count == 0 { b"[]\n" } else { b"\n]\n" })?;
+
+        Ok(())
+    }
+}
+
+impl YPBank for YPBankJson {
+    /// Считанные записи о банковских операциях.
+    fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    /// Изменяемый список записей о банковских операциях.
+    fn records_mut(&mut self) -> &mut Vec<Record> {
+        &mut self.records
+    }
+
+    /// Создание набора из указанных записей о банковских операциях.
+    fn from_records(records: Vec<Record>) -> Self {
+        Self { records }
+    }
+
+    /// Считать данные о банковских операциях в формате JSON-массива объектов.
+    fn read_from<R: Read>(r: &mut R) -> Result<Self, ReadError> {
+        let records = Self::iter_from(r).collect::<Result<_, _>>()?;
+
+        Ok(Self { records })
+    }
+
+    /// Последовательно считывать данные о банковских операциях из JSON-массива объектов.
+    ///
+    /// После ошибки разбора объекта граница следующей записи неизвестна, поэтому продолжение
+    /// чтения после ошибки не поддерживается.
+    fn iter_from<R: Read>(r: R) -> RecordIter<R> {
+        RecordIter::new(r, Self::read_record).with_header(Self::read_header)
+    }
+
+    /// Записать данные о банковских операциях в формате JSON-массива объектов.
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<(), WriteError> {
+        let mut writer = Self::writer_to(w);
+
+        for record in &self.records {
+            writer.write_record(record)?;
+        }
+
+        writer.finish()?;
+
+        Ok(())
+    }
+
+    /// Последовательно записывать данные о банковских операциях в формате JSON-массива объектов.
+    fn writer_to<W: Write>(w: W) -> RecordWriter<W> {
+        RecordWriter::new(w, Self::write_record, Self::finish)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::status::Status;
+    use crate::record::tx_type::TxType;
+    use rstest::rstest;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_read_from_json_data_specification() {
+        let data = r#"[
+  {
+    "TX_ID": 1001,
+    "TX_TYPE": "DEPOSIT",
+    "FROM_USER_ID": 0,
+    "TO_USER_ID": 501,
+    "AMOUNT": 50000,
+    "TIMESTAMP": 1672531200000,
+    "STATUS": "SUCCESS",
+    "DESCRIPTION": "Initial account funding"
+  },
+  {
+    "DESCRIPTION": "Payment for services, invoice #123",
+    "TX_ID": 1002,
+    "TX_TYPE": "TRANSFER",
+    "FROM_USER_ID": 501,
+    "TO_USER_ID": 502,
+    "AMOUNT": 15000,
+    "TIMESTAMP": 1672534800000,
+    "STATUS": "FAILURE"
+  }
+]"#;
+        let mut cursor = Cursor::new(data.as_bytes());
+        let result = YPBankJson::read_from(&mut cursor);
+
+        let data = result.unwrap();
+
+        let expected_records = vec![
+            Record::new(
+                1001,
+                TxType::Deposit,
+                0,
+                501,
+                50000,
+                1672531200000,
+                Status::Success,
+                "Initial account funding".to_string(),
+            ),
+            Record::new(
+                1002,
+                TxType::Transfer,
+                501,
+                502,
+                15000,
+                1672534800000,
+                Status::Failure,
+                "Payment for services, invoice #123".to_string(),
+            ),
+        ];
+
+        assert_eq!(data.records, expected_records);
+    }
+
+    #[rstest]
+    #[case("", "unexpected end of JSON array")]
+    #[case(r#"{"TX_ID": 1}"#, "expected `[`")]
+    #[case(r#"[{"TX_ID": 1}]"#, "missing field `TX_TYPE` at line 1 column 12")]
+    #[case(
+        r#"[{"TX_ID": 1, "TX_TYPE": "ABC"}]"#,
+        "Invalid TX_TYPE: ABC at line 1 column 30"
+    )]
+    #[case(
+        r#"[{"UNEXPECTED_KEY": 1}]"#,
+        "unknown field `UNEXPECTED_KEY`, expected one of `TX_ID`, `TX_TYPE`, `FROM_USER_ID`, `TO_USER_ID`, `AMOUNT`, `TIMESTAMP`, `STATUS`, `DESCRIPTION`, `CURRENCY`, `METADATA` at line 1 column 18"
+    )]
+    #[case("[] []", "trailing characters after JSON array")]
+    #[case(
+        "[1 2]",
+        "invalid type: integer `1`, expected struct Record at line 1 column 2"
+    )]
+    fn test_read_from_json_invalid_data(#[case] data: &str, #[case] description: &str) {
+        let mut cursor = Cursor::new(data.as_bytes());
+        let result = YPBankJson::read_from(&mut cursor);
+
+        let result = result.unwrap_err();
+        assert!(matches!(result.inner(), ReadError::FromJson(_)));
+        assert_eq!(
+            result.inner().to_string(),
+            format!("JSON format parsing error: {description}")
+        );
+    }
+
+    #[rstest]
+    #[case("[]")]
+    #[case(" [ \n ] \n")]
+    fn test_read_from_json_empty_array(#[case] data: &str) {
+        let mut cursor = Cursor::new(data.as_bytes());
+        let data = YPBankJson::read_from(&mut cursor).unwrap();
+
+        assert!(data.records.is_empty());
+    }
+
+    #[test]
+    fn test_write_to_json_empty_record() {
+        let data = YPBankJson { records: vec![] };
+        let mut cursor = Cursor::new(vec![]);
+        data.write_to(&mut cursor).unwrap();
+        assert_eq!(cursor.into_inner(), b"[]\n");
+    }
+
+    #[test]
+    fn test_write_to_json() {
+        let records = crate::tests::get_data_to_write();
+
+        let data = YPBankJson { records };
+        let mut cursor = Cursor::new(vec![]);
+        data.write_to(&mut cursor).unwrap();
+
+        assert_eq!(
+            cursor.into_inner(),
+            br#"[
+  {"TX_ID":1234567890123456,"TX_TYPE":"DEPOSIT","FROM_USER_ID":0,"TO_USER_ID":9876543210987654,"AMOUNT":10000,"TIMESTAMP":1633036800000,"STATUS":"SUCCESS","DESCRIPTION":"Terminal deposit"},
+  {"TX_ID":2312321321321321,"TX_TYPE":"TRANSFER","FROM_USER_ID":1231231231231231,"TO_USER_ID":9876543210987654,"AMOUNT":1000,"TIMESTAMP":1633056800000,"STATUS":"FAILURE","DESCRIPTION":"User transfer"},
+  {"TX_ID":3213213213213213,"TX_TYPE":"WITHDRAWAL","FROM_USER_ID":9876543210987654,"TO_USER_ID":0,"AMOUNT":100,"TIMESTAMP":1633066800000,"STATUS":"SUCCESS","DESCRIPTION":"User withdrawal"}
+]
+"#
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let records = crate::tests::get_data_to_write();
+
+        let mut cursor = Cursor::new(vec![]);
+        YPBankJson {
+            records: records.clone(),
+        }
+        .write_to(&mut cursor)
+        .unwrap();
+
+        cursor.set_position(0);
+        let data = YPBankJson::read_from(&mut cursor).unwrap();
+
+        assert_eq!(data.records, records);
+    }
+}
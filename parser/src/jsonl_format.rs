@@ -0,0 +1,183 @@
+use super::YPBank;
+use super::errors::{ReadError, WriteError};
+use super::iter::{RecordIter, recover_nothing};
+use super::options::ParseContext;
+use super::record::Record;
+use super::writer::{RecordWriter, finish_nothing};
+use std::io::{BufRead, BufWriter, Read, Write};
+
+#[derive(Debug)]
+pub struct YPBankJsonl {
+    /// Записи о банковских операциях.
+    pub records: Vec<Record>,
+}
+
+impl YPBankJsonl {
+    /// Считать очередную запись о банковской операции в формате JSON Lines.
+    ///
+    /// Пустые строки пропускаются. Строка считывается из источника целиком до ее разбора,
+    /// поэтому после ошибки чтение может быть продолжено со следующей строки.
+    fn read_record<B: BufRead>(
+        reader: &mut B,
+        _index: usize,
+        context: &ParseContext,
+    ) -> Result<Option<Record>, ReadError> {
+        let mut line = String::new();
+
+        while reader.read_line(&mut line)? > 0 {
+            if !line.trim().is_empty() {
+                let mut deserializer = serde_json::Deserializer::from_str(&line);
+                let record = Record::deserialize_with(&mut deserializer, context)?;
+                deserializer.end()?;
+
+                return Ok(Some(record));
+            }
+
+            line.clear();
+        }
+
+        Ok(None)
+    }
+
+    /// Записать очередную запись о банковской операции в формате JSON Lines.
+    fn write_record<W: Write>(
+        writer: &mut BufWriter<W>,
+        record: &Record,
+        _index: usize,
+    ) -> Result<(), WriteError> {
+        record.to_json(writer)?;
+        writer.write_all(b"\n")?;
+
+        Ok(())
+    }
+}
+
+impl YPBank for YPBankJsonl {
+    /// Считанные записи о банковских операциях.
+    fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    /// Изменяемый список записей о банковских операциях.
+    fn records_mut(&mut self) -> &mut Vec<Record> {
+        &mut self.records
+    }
+
+    /// Создание набора из указанных записей о банковских операциях.
+    fn from_records(records: Vec<Record>) -> Self {
+        Self { records }
+    }
+
+    /// Считать данные о банковских операциях в формате JSON Lines (один JSON-объект на строку).
+    fn read_from<R: Read>(r: &mut R) -> Result<Self, ReadError> {
+        let records = Self::iter_from(r).collect::<Result<_, _>>()?;
+
+        Ok(Self { records })
+    }
+
+    /// Последовательно считывать данные о банковских операциях в формате JSON Lines.
+    fn iter_from<R: Read>(r: R) -> RecordIter<R> {
+        RecordIter::new(r, Self::read_record).with_recovery(recover_nothing)
+    }
+
+    /// Записать данные о банковских операциях в формате JSON Lines (один JSON-объект на строку).
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<(), WriteError> {
+        let mut writer = Self::writer_to(w);
+
+        for record in &self.records {
+            writer.write_record(record)?;
+        }
+
+        writer.finish()?;
+
+        Ok(())
+    }
+
+    /// Последовательно записывать данные о банковских операциях в формате JSON Lines.
+    fn writer_to<W: Write>(w: W) -> RecordWriter<W> {
+        RecordWriter::new(w, Self::write_record, finish_nothing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::status::Status;
+    use crate::record::tx_type::TxType;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_read_from_jsonl_data_specification() {
+        let data = r#"{"TX_ID":1001,"TX_TYPE":"DEPOSIT","FROM_USER_ID":0,"TO_USER_ID":501,"AMOUNT":50000,"TIMESTAMP":1672531200000,"STATUS":"SUCCESS","DESCRIPTION":"Initial account funding"}
+
+{"TX_ID":1003,"TX_TYPE":"WITHDRAWAL","FROM_USER_ID":502,"TO_USER_ID":0,"AMOUNT":1000,"TIMESTAMP":1672538400000,"STATUS":"PENDING","DESCRIPTION":"ATM withdrawal"}
+"#;
+        let mut cursor = Cursor::new(data.as_bytes());
+        let result = YPBankJsonl::read_from(&mut cursor);
+
+        let data = result.unwrap();
+
+        let expected_records = vec![
+            Record::new(
+                1001,
+                TxType::Deposit,
+                0,
+                501,
+                50000,
+                1672531200000,
+                Status::Success,
+                "Initial account funding".to_string(),
+            ),
+            Record::new(
+                1003,
+                TxType::Withdrawal,
+                502,
+                0,
+                1000,
+                1672538400000,
+                Status::Pending,
+                "ATM withdrawal".to_string(),
+            ),
+        ];
+
+        assert_eq!(data.records, expected_records);
+    }
+
+    #[test]
+    fn test_read_from_jsonl_invalid_record() {
+        let mut cursor = Cursor::new(br#"{"TX_ID": 1}"#);
+        let result = YPBankJsonl::read_from(&mut cursor);
+
+        let result = result.unwrap_err();
+        assert!(matches!(result.inner(), ReadError::FromJson(_)));
+        assert_eq!(
+            result.to_string(),
+            "JSON format parsing error: missing field `TX_TYPE` at line 1 column 12 (at record 1, line 1, byte 0)"
+        );
+    }
+
+    #[test]
+    fn test_write_to_jsonl_empty_record() {
+        let data = YPBankJsonl { records: vec![] };
+        let mut cursor = Cursor::new(vec![]);
+        data.write_to(&mut cursor).unwrap();
+        assert_eq!(cursor.into_inner(), b"");
+    }
+
+    #[test]
+    fn test_write_to_jsonl() {
+        let records = crate::tests::get_data_to_write();
+
+        let data = YPBankJsonl { records };
+        let mut cursor = Cursor::new(vec![]);
+        data.write_to(&mut cursor).unwrap();
+
+        assert_eq!(
+            cursor.into_inner(),
+            br#"{"TX_ID":1234567890123456,"TX_TYPE":"DEPOSIT","FROM_USER_ID":0,"TO_USER_ID":9876543210987654,"AMOUNT":10000,"TIMESTAMP":1633036800000,"STATUS":"SUCCESS","DESCRIPTION":"Terminal deposit"}
+{"TX_ID":2312321321321321,"TX_TYPE":"TRANSFER","FROM_USER_ID":1231231231231231,"TO_USER_ID":9876543210987654,"AMOUNT":1000,"TIMESTAMP":1633056800000,"STATUS":"FAILURE","DESCRIPTION":"User transfer"}
+{"TX_ID":3213213213213213,"TX_TYPE":"WITHDRAWAL","FROM_USER_ID":9876543210987654,"TO_USER_ID":0,"AMOUNT":100,"TIMESTAMP":1633066800000,"STATUS":"SUCCESS","DESCRIPTION":"User withdrawal"}
+"#
+        );
+    }
+}
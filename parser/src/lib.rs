@@ -5,42 +5,475 @@
 //!
 //! 2. текстовый формат описания списка операций;
 //!
-//! 3. бинарное предоставление списка операций.
+//! 3. бинарное предоставление списка операций;
+//!
+//! 4. JSON-массив объектов с именами полей, совпадающими с ключами записи;
+//!
+//! 5. JSON Lines (NDJSON) — по одному JSON-объекту записи на строку.
 //!
 //! Чтение из источника данных, реализующего трейт [`Read`], производится при помощи
 //! методов [`read_from_text`], [`read_from_csv`], [`read_from_bin`] для соответствующих форматов данных.
 //!
+//! Для обработки больших источников без загрузки их в память целиком предусмотрено потоковое
+//! чтение по одной записи при помощи метода [`YPBank::iter_from`], возвращающего [`RecordIter`].
+//!
+//! Долго работающие процессы-сборщики дописывают записи в архив файлов в бинарном формате
+//! с ротацией по размеру при помощи [`BinArchive`].
+//!
+//! Для быстрого поиска записей в бинарном формате по TX_ID без просмотра источника целиком
+//! предусмотрен индекс [`YPBankBinIndex`], а для выборки и постраничного просмотра записей
+//! по их порядковому номеру — [`BinReader`], пропускающий записи без разбора их полей.
+//! Постраничное чтение записей в любом формате с маркером продолжения выполняется функцией
+//! [`read_page`].
+//!
+//! Смысловая корректность считанных записей проверяется методом [`YPBank::validate`],
+//! возвращающим [`ValidationReport`] с ошибками и предупреждениями.
+//! При потоковом чтении записи проверяются по одной при помощи [`Validator`].
+//!
+//! Сверка записей из двух источников по TX_ID (и, при необходимости, нечетко по сумме и метке
+//! времени) производится функциями модуля [`reconcile`]. Записи сравниваются по полям
+//! при помощи [`RecordComparator`] с исключением полей и допусками сумм и меток времени.
+//!
+//! Наборы изменений между выгрузками (добавленные, удаленные и измененные по TX_ID записи)
+//! вычисляются, применяются и сериализуются в текстовом и бинарном форматах при помощи модуля
+//! [`diff`], что позволяет передавать ежедневные изменения вместо полных выгрузок.
+//!
+//! Выписка по счету участника за период (входящий и исходящий остатки, транзакции
+//! в хронологическом порядке, итоги) формируется функциями модуля [`statement`].
+//!
+//! Поврежденные данные в бинарном формате разбираются по полям (смещение, исходные байты,
+//! считанное значение) до первой структурной ошибки при помощи [`inspect::BinInspector`].
+//!
+//! Сводная статистика (количество и суммы транзакций по типам и состояниям, участники
+//! с наибольшим объемом транзакций, диапазон меток времени) собирается при помощи [`Stats`].
+//!
+//! Отпечаток набора записей ([`YPBank::fingerprint`], [`Fingerprint`]) и хеш отдельной записи
+//! ([`Record::hash_stable`]) не зависят от формата данных и позволяют сравнивать большие выгрузки,
+//! обмениваясь только хешами.
+//!
+//! Отпечаток выгрузки подписывается и проверяется алгоритмом Ed25519 при помощи модуля `signing`
+//! (поддержка включается признаком крейта `signing`).
+//!
+//! Выгрузки в бинарном формате шифруются алгоритмом AES-256-GCM функциями
+//! `encryption::write_to_bin_encrypted` и `encryption::read_from_bin_encrypted` (поддержка
+//! включается признаком крейта `encryption`).
+//!
+//! Для передачи данных аналитикам и в тестовые среды записи обезличиваются методом
+//! [`YPBank::anonymize`] или, при потоковой обработке, при помощи [`Anonymizer`].
+//!
+//! Отбор записей по состоянию, типу, участникам, сумме и метке времени производится
+//! при помощи фильтра [`Filter`].
+//!
+//! Произвольные преобразования записей при потоковой обработке (маскирование описаний, сдвиг
+//! меток времени, замена идентификаторов участников и др.) задаются трейтом [`Transform`]
+//! и применяются к потоку записей функцией [`transform_records`].
+//!
+//! Категории транзакций присваиваются по правилам (описание, тип и сумма транзакции) при помощи
+//! [`Tagger`] и сохраняются в дополнительном поле [`CATEGORY_KEY`]; [`Stats`] собирает итоги
+//! по категориям.
+//!
+//! Псевдослучайные корректные записи для нагрузочного тестирования генерируются итератором
+//! [`generator::Generator`].
+//!
+//! Для чтения поврежденных источников предусмотрен метод [`YPBank::read_with_options`],
+//! позволяющий пропускать некорректные записи (см. [`ParseOptions`]) и возвращающий
+//! [`ParseReport`] со считанными записями и номерами пропущенных.
+//!
+//! Сжатые gzip или zstd источники прозрачно распаковываются функцией [`compression::decompress`],
+//! а при записи данные сжимаются при помощи [`compression::Compression::encoder`]
+//! (поддержка включается признаками крейта `gzip` и `zstd`).
+//!
+//! Наборы записей в каждом из форматов можно собирать программно: [`YPBank::new`], [`YPBank::push`],
+//! а также трейты [`FromIterator`], [`Extend`] и [`IntoIterator`].
+//!
+//! Основные типы записи ([`Record`], [`TxType`], [`Status`], [`RecordKey`] и др.) доступны
+//! из корня крейта, а наиболее часто используемые типы и трейты импортируются одной строкой
+//! `use parser::prelude::*;` (см. [`prelude`]).
+//!
+//! Построчные форматы при чтении допускают окончания строк `\r\n` и метку порядка байтов UTF-8
+//! в начале данных, а записать данные в таком виде позволяет [`line_ending::LineEndingWriter`].
+//!
+//! Текстовые данные в кодировках, отличных от UTF-8 (например, Windows-1251 или UTF-16),
+//! перекодируются при помощи модуля `encoding` (поддержка включается признаком крейта
+//! `encoding`).
+//!
+//! Для аналитических систем записи о транзакциях могут записываться и считываться в формате
+//! Apache Parquet при помощи `YPBankParquet` (поддержка включается признаком крейта `parquet`).
+//!
+//! Большие архивы считываются из файлов, отображенных в память, функцией
+//! `mmap::read_from_path_mmap` (поддержка включается признаком крейта `mmap`).
+//!
+//! Записи о транзакциях могут сохраняться в таблицу базы данных SQLite и считываться из нее
+//! с отбором по [`Filter`] при помощи `YPBankSqlite` (поддержка включается признаком крейта
+//! `sqlite`).
+//!
+//! Для вставки фрагментов данных в описания задач и страницы вики записи записываются таблицей
+//! Markdown с выбранными столбцами и ограничением количества строк (см. [`MarkdownOptions`]).
+//!
+//! Записи распределяются по нескольким назначениям (разделам), например, по месяцу метки времени
+//! или группе пользователей, при помощи [`PartitionedWriter`] (см. [`PartitionBy`]).
+//!
+//! Для встраивания в существующие системы библиотека предоставляет интерфейс C (модуль `ffi`,
+//! заголовочный файл `include/ypbank.h`; поддержка включается признаком крейта `ffi`).
+//!
+//! Загруженные в браузере файлы считываются и конвертируются без обращения к серверу при помощи
+//! привязок WebAssembly из модуля `wasm` (поддержка включается признаком крейта `wasm`).
+//!
+//! Сам крейт собирается только как библиотека Rust: динамическая библиотека с интерфейсом C
+//! и привязками WebAssembly собирается из крейта `ypbank`.
+//!
+//! Метрики потокового чтения и записи (количество записей и ошибок, размер записей, время
+//! разбора и записи) передаются реализации трейта [`Metrics`], указанной методами
+//! [`RecordIter::metrics`] и [`RecordWriter::metrics`]; [`MetricsRegistry`] накапливает их
+//! и выводит в текстовом формате Prometheus.
+//!
+//! Чтение и запись записей инструментируются событиями крейта `tracing` (ошибки чтения записей,
+//! количество и скорость чтения и записи); области трассировки файлов задаются методом
+//! `RecordIter::instrument` (поддержка включается признаком крейта `tracing`).
+//!
+//! Для тестирования на основе свойств и фаззинга корректные и нестандартные записи генерируются
+//! при помощи модуля `fuzzing` (поддержка включается признаками крейта `arbitrary` и `proptest`).
+//!
 //! Запись производится в назначение, реализующее трейт [`Write`], при помощи
 //! методов ['write_to_text'], ['write_to_csv'], ['write_to_bin'] для соответствующих форматов данных.
 
 #![deny(unreachable_pub)]
 
+mod anonymize;
+mod archive;
 mod bin_format;
+mod bin_index;
+mod bin_reader;
+pub mod compression;
 mod csv_format;
+mod dedup;
+pub mod diff;
+#[cfg(feature = "encoding")]
+pub mod encoding;
+#[cfg(feature = "encryption")]
+pub mod encryption;
 pub mod errors;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod filter;
+pub mod fingerprint;
+mod fixed_width;
+#[cfg(any(feature = "arbitrary", feature = "proptest", test))]
+pub mod fuzzing;
+pub mod generator;
+pub mod inspect;
+mod intern;
+mod iter;
+mod json_format;
+mod jsonl_format;
+pub mod line_ending;
+mod markdown;
+mod metrics;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+#[cfg(feature = "msgpack")]
+mod msgpack_format;
+mod options;
+mod pagination;
+#[cfg(feature = "parquet")]
+mod parquet_format;
+mod partition;
+pub mod prelude;
+#[cfg(feature = "protobuf")]
+mod proto_format;
+pub mod reconcile;
 pub mod record;
+pub mod schema;
+#[cfg(feature = "signing")]
+pub mod signing;
+mod sort;
+mod sql_dump;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+pub mod statement;
+mod stats;
+mod tagging;
 mod text_format;
+mod transform;
+mod validation;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+mod writer;
 
-use crate::record::Record;
-pub use bin_format::YPBankBin;
-pub use csv_format::YPBankCsv;
-use errors::{FormatError, ReadError, WriteError};
-use std::io::{Read, Write};
+pub use anonymize::{AnonymizePolicy, Anonymizer, DescriptionPolicy, UserIdPolicy};
+pub use archive::{BinArchive, BinArchiveWriter};
+pub use bin_format::{RecoveryGap, YPBankBin};
+pub use bin_index::YPBankBinIndex;
+pub use bin_reader::BinReader;
+pub use csv_format::{CsvColumnMapping, CsvOptions, YPBankCsv};
+pub use dedup::{DedupStrategy, Duplicate};
+use errors::{DedupError, FormatError, ReadError, RedactError, WriteError};
+pub use filter::Filter;
+pub use fingerprint::Fingerprint;
+pub use fixed_width::{Alignment, FixedWidthField, FixedWidthLayout};
+pub use intern::DescriptionPool;
+pub use iter::{Progress, RecordIter};
+pub use json_format::YPBankJson;
+pub use jsonl_format::YPBankJsonl;
+pub use markdown::MarkdownOptions;
+pub use metrics::{Metrics, MetricsRegistry};
+#[cfg(feature = "msgpack")]
+pub use msgpack_format::YPBankMsgPack;
+pub use options::{OnError, ParseOptions, ParseReport, Projection, SizeLimits, WriteOptions};
+pub use pagination::{Page, PageToken, read_page};
+#[cfg(feature = "parquet")]
+pub use parquet_format::{ParquetWriter, YPBankParquet};
+pub use partition::{PartitionBy, PartitionedWriter};
+#[cfg(feature = "protobuf")]
+pub use proto_format::YPBankProto;
+pub use record::{
+    Currency, Description, FieldDiff, Record, RecordBuilder, RecordComparator, RecordKey,
+    RecordMask, RecordRef, Status, TxType,
+};
+pub use schema::SchemaVersion;
+pub use sort::{ExternalSort, MergeSorted, SortOrder, merge_sorted, sort_external};
+pub use sql_dump::SqlDialect;
+#[cfg(feature = "sqlite")]
+pub use sqlite::{SqliteWriter, YPBankSqlite};
+pub use stats::{Stats, StatsReport, TimeRange, Totals, TotalsReport, UserReport};
+use std::collections::HashSet;
+use std::io::{BufRead, Chain, Cursor, Read, Seek, SeekFrom, Write};
+pub use tagging::{CATEGORY_KEY, TagRule, Tagger};
 pub use text_format::YPBankText;
+pub use transform::{
+    MaskDescriptions, RemapUserIds, ShiftTimestamps, Transform, Transformed, transform_records,
+};
+pub use validation::{
+    IssueKind, Severity, TimestampCheck, ValidationConfig, ValidationIssue, ValidationReport,
+    Validator,
+};
+pub use writer::RecordWriter;
 
 /// Трейт для парсинга и хранения данных о банковских операциях.
 pub trait YPBank: Sized {
+    /// Считанные записи о банковских операциях.
+    fn records(&self) -> &[Record];
+
+    /// Изменяемый список записей о банковских операциях.
+    fn records_mut(&mut self) -> &mut Vec<Record>;
+
+    /// Создание набора из указанных записей о банковских операциях.
+    fn from_records(records: Vec<Record>) -> Self;
+
+    /// Создание пустого набора записей о банковских операциях.
+    fn new() -> Self {
+        Self::from_records(vec![])
+    }
+
+    /// Добавить запись о банковской операции в конец набора.
+    fn push(&mut self, record: Record) {
+        self.records_mut().push(record);
+    }
+
+    /// Количество записей о банковских операциях.
+    fn len(&self) -> usize {
+        self.records().len()
+    }
+
+    /// Признак отсутствия записей о банковских операциях.
+    fn is_empty(&self) -> bool {
+        self.records().is_empty()
+    }
+
+    /// Итератор по записям о банковских операциях.
+    fn iter(&self) -> std::slice::Iter<'_, Record> {
+        self.records().iter()
+    }
+
     /// Считать данные о банковских операциях.
     fn read_from<R: Read>(r: &mut R) -> Result<Self, ReadError>;
 
+    /// Последовательно считывать данные о банковских операциях по одной записи,
+    /// не загружая источник в память целиком.
+    fn iter_from<R: Read>(r: R) -> RecordIter<R>;
+
+    /// Считать данные о банковских операциях из источника, уже буферизованного вызывающей
+    /// стороной, не буферизуя его повторно (см. [`RecordIter::buffered`]).
+    fn read_from_buffered<R: BufRead>(r: R) -> Result<Self, ReadError> {
+        Ok(Self::from_records(
+            Self::iter_from(r).buffered().read_all()?,
+        ))
+    }
+
+    /// Считать записи о банковских операциях с учетом параметров обработки ошибок.
+    ///
+    /// В зависимости от [`ParseOptions::on_error`] некорректные записи могут быть пропущены,
+    /// а их номера (и ошибки чтения) сохранены в [`ParseReport`].
+    fn read_with_options<R: Read>(
+        r: &mut R,
+        options: ParseOptions,
+    ) -> Result<ParseReport, ReadError> {
+        Self::iter_from(r).read_report(options)
+    }
+
+    /// Проверить смысловую корректность записей о банковских операциях, включая
+    /// взаимосвязи между записями (например, повторяющиеся TX_ID).
+    fn validate(&self, rules: &ValidationConfig) -> ValidationReport {
+        validation::validate(self.records(), rules)
+    }
+
+    /// Найти группы записей с одинаковым TX_ID.
+    fn find_duplicates(&self) -> Vec<Duplicate> {
+        dedup::find_duplicates(self.records())
+    }
+
+    /// Удалить записи с повторяющимися TX_ID согласно указанному способу, например,
+    /// перед записью объединенных выгрузок из нескольких систем.
+    ///
+    /// Возвращает количество удаленных записей.
+    fn dedup_by_tx_id(&mut self, strategy: DedupStrategy) -> Result<usize, DedupError> {
+        dedup::dedup_by_tx_id(self.records_mut(), strategy)
+    }
+
+    /// Удалить значения указанных полей у записей с указанными TX_ID, например, для исполнения
+    /// запросов на удаление персональных данных из архивов (см. [`Record::redact`]).
+    ///
+    /// Возвращает количество измененных записей.
+    fn redact(
+        &mut self,
+        tx_ids: &HashSet<u64>,
+        fields: &[RecordKey],
+    ) -> Result<usize, RedactError> {
+        Record::check_redactable(fields)?;
+
+        let mut count = 0;
+
+        for record in self.records_mut() {
+            if tx_ids.contains(&record.tx_id()) {
+                record.redact(fields)?;
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Оставить только записи о банковских операциях, удовлетворяющие фильтру.
+    ///
+    /// Возвращает количество удаленных записей.
+    fn retain_matching(&mut self, filter: &Filter) -> usize {
+        filter::retain(self.records_mut(), filter)
+    }
+
+    /// Обезличить записи о банковских операциях: согласованно заменить идентификаторы участников
+    /// псевдонимами и удалить или замаскировать описания (см. [`AnonymizePolicy`]).
+    fn anonymize(&mut self, policy: &AnonymizePolicy) {
+        anonymize::anonymize(self.records_mut(), policy);
+    }
+
+    /// Собрать сводную статистику по записям о банковских операциях.
+    fn stats(&self) -> Stats {
+        self.records().iter().collect()
+    }
+
+    /// Отсортировать записи о банковских операциях по значению поля с указанным ключом.
+    fn sort_by(&mut self, key: RecordKey, order: SortOrder) {
+        self.sort_by_keys(&[(key, order)]);
+    }
+
+    /// Устойчиво отсортировать записи о банковских операциях последовательно по нескольким
+    /// ключам (например, по TIMESTAMP, а при равенстве — по TX_ID), чтобы получить
+    /// канонический порядок для сравнения и архивирования.
+    fn sort_by_keys(&mut self, keys: &[(RecordKey, SortOrder)]) {
+        sort::sort_by_keys(self.records_mut(), keys);
+    }
+
+    /// Вычислить отпечаток набора записей о банковских операциях (хеш SHA-256 их канонических
+    /// представлений), не зависящий от формата данных (см. [`Fingerprint`]).
+    fn fingerprint(&self) -> [u8; 32] {
+        let mut fingerprint = Fingerprint::new();
+        fingerprint.extend(self.records());
+
+        fingerprint.finish()
+    }
+
     /// Записать данные о банковских операциях.
     fn write_to<W: Write>(&self, w: &mut W) -> Result<(), WriteError>;
+
+    /// Последовательно записывать данные о банковских операциях по одной записи.
+    fn writer_to<W: Write>(w: W) -> RecordWriter<W>;
+
+    /// Записать данные о банковских операциях в назначение, уже буферизованное вызывающей
+    /// стороной, не буферизуя их повторно (см. [`RecordWriter::buffered`]).
+    ///
+    /// Назначение сбрасывается по окончании записи и возвращается.
+    fn write_to_buffered<W: Write>(&self, w: W) -> Result<W, WriteError> {
+        let mut writer = Self::writer_to(w).buffered();
+
+        for record in self.records() {
+            writer.write_record(record)?;
+        }
+
+        writer.finish()
+    }
 }
 
+/// Реализация трейтов коллекции записей ([`FromIterator`], [`Extend`], [`IntoIterator`])
+/// для указанных реализаций [`YPBank`].
+macro_rules! impl_record_collection {
+    ($($bank:ty),+) => {$(
+        /// Реализация трейта [`FromIterator<Record>`] для набора записей.
+        impl FromIterator<Record> for $bank {
+            /// Реализация метода [`FromIterator<Record>::from_iter`] для набора записей.
+            fn from_iter<I: IntoIterator<Item = Record>>(iter: I) -> Self {
+                Self::from_records(iter.into_iter().collect())
+            }
+        }
+
+        /// Реализация трейта [`Extend<Record>`] для набора записей.
+        impl Extend<Record> for $bank {
+            /// Реализация метода [`Extend<Record>::extend`] для набора записей.
+            fn extend<I: IntoIterator<Item = Record>>(&mut self, iter: I) {
+                self.records_mut().extend(iter);
+            }
+        }
+
+        /// Реализация трейта [`IntoIterator`] для набора записей.
+        impl IntoIterator for $bank {
+            type Item = Record;
+            type IntoIter = std::vec::IntoIter<Record>;
+
+            /// Реализация метода [`IntoIterator::into_iter`] для набора записей.
+            fn into_iter(self) -> Self::IntoIter {
+                self.records.into_iter()
+            }
+        }
+
+        /// Реализация трейта [`IntoIterator`] для ссылки на набор записей.
+        impl<'a> IntoIterator for &'a $bank {
+            type Item = &'a Record;
+            type IntoIter = std::slice::Iter<'a, Record>;
+
+            /// Реализация метода [`IntoIterator::into_iter`] для ссылки на набор записей.
+            fn into_iter(self) -> Self::IntoIter {
+                self.records.iter()
+            }
+        }
+    )+};
+}
+
+impl_record_collection!(YPBankText, YPBankCsv, YPBankBin, YPBankJson, YPBankJsonl);
+#[cfg(feature = "msgpack")]
+impl_record_collection!(YPBankMsgPack);
+#[cfg(feature = "protobuf")]
+impl_record_collection!(YPBankProto);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum YPBankImpl {
     Text,
     Csv,
     Bin,
+    Json,
+    Jsonl,
+    #[cfg(feature = "msgpack")]
+    MsgPack,
+    #[cfg(feature = "protobuf")]
+    Proto,
 }
 
 impl TryFrom<&str> for YPBankImpl {
@@ -51,36 +484,222 @@ impl TryFrom<&str> for YPBankImpl {
             "text" => Ok(YPBankImpl::Text),
             "csv" => Ok(YPBankImpl::Csv),
             "bin" => Ok(YPBankImpl::Bin),
+            "json" => Ok(YPBankImpl::Json),
+            "jsonl" | "ndjson" => Ok(YPBankImpl::Jsonl),
+            #[cfg(feature = "msgpack")]
+            "msgpack" => Ok(YPBankImpl::MsgPack),
+            #[cfg(feature = "protobuf")]
+            "proto" | "protobuf" => Ok(YPBankImpl::Proto),
             _ => Err(FormatError::InvalidFormat(s.to_string())),
         }
     }
 }
 
+/// Источник, выдающий сначала уже считанный начальный фрагмент данных, а затем оставшиеся
+/// данные исходного источника (см. [`YPBankImpl::detect_format_from_stream`]).
+pub type PrefixedReader<R> = Chain<Cursor<Vec<u8>>, R>;
+
 impl YPBankImpl {
+    /// Размер начального фрагмента источника, анализируемого при определении формата данных.
+    const DETECT_FORMAT_PREFIX_LEN: u64 = 1024;
+
+    /// Определить формат данных по содержимому источника.
+    ///
+    /// Анализируется начальный фрагмент источника: MAGIC бинарного формата или его контейнера,
+    /// открывающий символ JSON-массива или объекта, заголовок CSV-формата либо строки вида
+    /// `KEY: value` (и комментарии) текстового формата. После анализа позиция источника
+    /// восстанавливается.
+    pub fn detect_format<R: Read + Seek>(r: &mut R) -> Result<Self, ReadError> {
+        let start = r.stream_position()?;
+
+        let mut prefix = vec![];
+        r.by_ref()
+            .take(Self::DETECT_FORMAT_PREFIX_LEN)
+            .read_to_end(&mut prefix)?;
+
+        r.seek(SeekFrom::Start(start))?;
+
+        Self::detect_format_from_prefix(&prefix).ok_or(ReadError::UnknownFormat)
+    }
+
+    /// Определить формат данных по содержимому источника без возможности перемещения позиции
+    /// (например, stdin).
+    ///
+    /// Возвращает формат и источник, выдающий сначала проанализированный начальный фрагмент,
+    /// а затем оставшиеся данные исходного источника.
+    pub fn detect_format_from_stream<R: Read>(
+        mut r: R,
+    ) -> Result<(Self, PrefixedReader<R>), ReadError> {
+        let mut prefix = vec![];
+        r.by_ref()
+            .take(Self::DETECT_FORMAT_PREFIX_LEN)
+            .read_to_end(&mut prefix)?;
+
+        let format = Self::detect_format_from_prefix(&prefix).ok_or(ReadError::UnknownFormat)?;
+
+        Ok((format, Cursor::new(prefix).chain(r)))
+    }
+
+    /// Определить формат данных по начальному фрагменту источника.
+    fn detect_format_from_prefix(prefix: &[u8]) -> Option<Self> {
+        if prefix.starts_with(&Record::BINARY_MAGIC)
+            || prefix.starts_with(&YPBankBin::CONTAINER_MAGIC)
+        {
+            return Some(YPBankImpl::Bin);
+        }
+
+        // Фрагмент может обрываться посреди многобайтового символа.
+        let text = match std::str::from_utf8(prefix) {
+            Ok(text) => text,
+            Err(e) if e.error_len().is_none() => {
+                std::str::from_utf8(&prefix[..e.valid_up_to()]).ok()?
+            }
+            Err(_) => return None,
+        };
+
+        let text = text.trim_start_matches('\u{feff}').trim_start();
+
+        match text.chars().next()? {
+            '[' => return Some(YPBankImpl::Json),
+            '{' => return Some(YPBankImpl::Jsonl),
+            '#' => return Some(YPBankImpl::Text),
+            _ => {}
+        }
+
+        let first_line = text.lines().next()?.trim_end();
+
+        if YPBankCsv::is_header(first_line) {
+            return Some(YPBankImpl::Csv);
+        }
+
+        match first_line.split_once(':') {
+            Some((key, _)) if RecordKey::try_from(key).is_ok() => Some(YPBankImpl::Text),
+            _ => None,
+        }
+    }
+
     pub fn read_from<R: Read>(&self, r: &mut R) -> Result<Vec<Record>, ReadError> {
-        Ok(match self {
-            YPBankImpl::Text => YPBankText::read_from(r)?.records,
-            YPBankImpl::Csv => YPBankCsv::read_from(r)?.records,
-            YPBankImpl::Bin => YPBankBin::read_from(r)?.records,
-        })
+        self.iter_from(r).read_all()
     }
 
-    pub fn write_to<W: Write>(&self, records: Vec<Record>, w: &mut W) -> Result<(), WriteError> {
+    /// Считать записи о транзакциях в заданном формате с учетом параметров обработки ошибок.
+    pub fn read_with_options<R: Read>(
+        &self,
+        r: &mut R,
+        options: ParseOptions,
+    ) -> Result<ParseReport, ReadError> {
+        self.iter_from(r).read_report(options)
+    }
+
+    /// Считать записи о транзакциях в заданном формате из источника, уже буферизованного
+    /// вызывающей стороной, не буферизуя его повторно.
+    pub fn read_from_buffered<R: BufRead>(&self, r: R) -> Result<Vec<Record>, ReadError> {
+        self.iter_from_buffered(r).read_all()
+    }
+
+    /// Последовательно считывать записи о транзакциях в заданном формате из источника,
+    /// уже буферизованного вызывающей стороной (см. [`RecordIter::buffered`]).
+    pub fn iter_from_buffered<R: BufRead>(&self, r: R) -> RecordIter<R> {
+        self.iter_from(r).buffered()
+    }
+
+    /// Последовательно считывать записи о транзакциях в заданном формате.
+    pub fn iter_from<R: Read>(&self, r: R) -> RecordIter<R> {
         match self {
-            YPBankImpl::Text => YPBankText { records }.write_to(w)?,
-            YPBankImpl::Csv => YPBankCsv { records }.write_to(w)?,
-            YPBankImpl::Bin => YPBankBin { records }.write_to(w)?,
-        };
+            YPBankImpl::Text => YPBankText::iter_from(r),
+            YPBankImpl::Csv => YPBankCsv::iter_from(r),
+            YPBankImpl::Bin => YPBankBin::iter_from(r),
+            YPBankImpl::Json => YPBankJson::iter_from(r),
+            YPBankImpl::Jsonl => YPBankJsonl::iter_from(r),
+            #[cfg(feature = "msgpack")]
+            YPBankImpl::MsgPack => YPBankMsgPack::iter_from(r),
+            #[cfg(feature = "protobuf")]
+            YPBankImpl::Proto => YPBankProto::iter_from(r),
+        }
+    }
+
+    /// Средний размер записи о транзакции в заданном формате в байтах.
+    ///
+    /// Соответствует записи с шестнадцатизначными идентификаторами и коротким описанием
+    /// и используется для оценки количества записей по размеру данных.
+    pub fn average_record_size(&self) -> u64 {
+        match self {
+            YPBankImpl::Text => 180,
+            YPBankImpl::Csv => 90,
+            YPBankImpl::Bin => 72,
+            YPBankImpl::Json | YPBankImpl::Jsonl => 170,
+            #[cfg(feature = "msgpack")]
+            YPBankImpl::MsgPack => 120,
+            #[cfg(feature = "protobuf")]
+            YPBankImpl::Proto => 60,
+        }
+    }
+
+    /// Оценить количество записей о транзакциях в заданном формате по общему размеру данных,
+    /// например, по длине файла, чтобы заранее выделить под них память
+    /// (см. [`ParseOptions::capacity_hint`]).
+    pub fn estimate_record_count(&self, total_bytes: u64) -> usize {
+        usize::try_from(total_bytes / self.average_record_size()).unwrap_or(usize::MAX)
+    }
+
+    pub fn write_to<W: Write>(&self, records: Vec<Record>, w: &mut W) -> Result<(), WriteError> {
+        let mut writer = self.writer_to(w);
+
+        for record in &records {
+            writer.write_record(record)?;
+        }
+
+        writer.finish()?;
 
         Ok(())
     }
+
+    /// Записать записи о транзакциях в заданном формате в назначение, уже буферизованное
+    /// вызывающей стороной, не буферизуя их повторно.
+    ///
+    /// Назначение сбрасывается по окончании записи и возвращается.
+    pub fn write_to_buffered<W: Write>(&self, records: &[Record], w: W) -> Result<W, WriteError> {
+        let mut writer = self.writer_to_buffered(w);
+
+        for record in records {
+            writer.write_record(record)?;
+        }
+
+        writer.finish()
+    }
+
+    /// Последовательно записывать записи о транзакциях в заданном формате в назначение,
+    /// уже буферизованное вызывающей стороной (см. [`RecordWriter::buffered`]).
+    pub fn writer_to_buffered<W: Write>(&self, w: W) -> RecordWriter<W> {
+        self.writer_to(w).buffered()
+    }
+
+    /// Последовательно записывать записи о транзакциях в заданном формате.
+    pub fn writer_to<W: Write>(&self, w: W) -> RecordWriter<W> {
+        match self {
+            YPBankImpl::Text => YPBankText::writer_to(w),
+            YPBankImpl::Csv => YPBankCsv::writer_to(w),
+            YPBankImpl::Bin => YPBankBin::writer_to(w),
+            YPBankImpl::Json => YPBankJson::writer_to(w),
+            YPBankImpl::Jsonl => YPBankJsonl::writer_to(w),
+            #[cfg(feature = "msgpack")]
+            YPBankImpl::MsgPack => YPBankMsgPack::writer_to(w),
+            #[cfg(feature = "protobuf")]
+            YPBankImpl::Proto => YPBankProto::writer_to(w),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::record::Record;
+    use super::errors::{ReadError, RedactError};
     use super::record::status::Status;
     use super::record::tx_type::TxType;
+    use super::record::{Record, RecordKey};
+    use super::{YPBank, YPBankBin, YPBankCsv, YPBankImpl, YPBankJson, YPBankJsonl, YPBankText};
+    use rstest::rstest;
+    use std::collections::HashSet;
+    use std::io::{Cursor, Seek};
 
     pub(super) fn get_data_to_write() -> Vec<Record> {
         vec![
@@ -116,4 +735,182 @@ mod tests {
             ),
         ]
     }
+
+    /// Записи с широкими суммами (см. [`crate::SchemaVersion::V2`]) и обычной суммой.
+    pub(super) fn get_wide_data_to_write() -> Vec<Record> {
+        [-2500, i128::from(u64::MAX) + 1, i128::MIN, 100]
+            .into_iter()
+            .zip(1..)
+            .map(|(amount, tx_id)| {
+                Record::new_wide(
+                    tx_id,
+                    TxType::Reversal,
+                    1,
+                    2,
+                    amount,
+                    1633036800000,
+                    Status::Success,
+                    "Correction".to_string(),
+                )
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case("text")]
+    #[case("csv")]
+    #[case("bin")]
+    #[case("json")]
+    #[case("jsonl")]
+    fn test_detect_format(#[case] format: &str) {
+        let format = YPBankImpl::try_from(format).unwrap();
+
+        let mut buffer = vec![];
+        format.write_to(get_data_to_write(), &mut buffer).unwrap();
+
+        let mut cursor = Cursor::new(buffer);
+
+        assert_eq!(YPBankImpl::detect_format(&mut cursor).unwrap(), format);
+        assert_eq!(cursor.stream_position().unwrap(), 0);
+    }
+
+    #[rstest]
+    #[case("text")]
+    #[case("csv")]
+    #[case("bin")]
+    #[case("json")]
+    #[case("jsonl")]
+    fn test_detect_format_from_stream(#[case] format: &str) {
+        let format = YPBankImpl::try_from(format).unwrap();
+
+        let mut buffer = vec![];
+        format.write_to(get_data_to_write(), &mut buffer).unwrap();
+
+        let (detected, mut r) = YPBankImpl::detect_format_from_stream(buffer.as_slice()).unwrap();
+
+        assert_eq!(detected, format);
+        assert_eq!(detected.read_from(&mut r).unwrap(), get_data_to_write());
+    }
+
+    #[rstest]
+    #[case("# Record 1\nTX_ID: 1\n", YPBankImpl::Text)]
+    #[case("\n  TX_TYPE: DEPOSIT\n", YPBankImpl::Text)]
+    #[case(" \n[]", YPBankImpl::Json)]
+    #[case("YPBC\0\0\0\x01", YPBankImpl::Bin)]
+    #[case(
+        "\u{feff}TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\r\n",
+        YPBankImpl::Csv
+    )]
+    #[case("\u{feff}TX_ID: 1\r\n", YPBankImpl::Text)]
+    fn test_detect_format_specific(#[case] data: &str, #[case] expected: YPBankImpl) {
+        let mut cursor = Cursor::new(data.as_bytes());
+
+        assert_eq!(YPBankImpl::detect_format(&mut cursor).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case(b"")]
+    #[case(b"  \n")]
+    #[case(b"UNKNOWN: 1")]
+    #[case(b"TX_ID;TX_TYPE")]
+    #[case(&[0xff, 0xfe, 0x00])]
+    fn test_detect_format_unknown(#[case] data: &[u8]) {
+        let mut cursor = Cursor::new(data);
+
+        let result = YPBankImpl::detect_format(&mut cursor).unwrap_err();
+        assert!(matches!(result, ReadError::UnknownFormat));
+        assert_eq!(result.to_string(), "Could not detect data format");
+    }
+
+    #[rstest]
+    fn test_estimate_record_count(#[values("text", "csv", "bin", "json", "jsonl")] format: &str) {
+        let format = YPBankImpl::try_from(format).unwrap();
+
+        let mut data = vec![];
+        format
+            .write_to(
+                get_data_to_write().into_iter().cycle().take(300).collect(),
+                &mut data,
+            )
+            .unwrap();
+
+        let estimate = format.estimate_record_count(data.len() as u64);
+
+        assert!((240..=360).contains(&estimate), "{format:?}: {estimate}");
+    }
+
+    fn check_collection<T>()
+    where
+        T: YPBank + FromIterator<Record> + Extend<Record> + IntoIterator<Item = Record>,
+        for<'a> &'a T: IntoIterator<Item = &'a Record>,
+    {
+        let records = get_data_to_write();
+
+        let mut bank = T::new();
+        assert!(bank.is_empty());
+
+        bank.push(records[0].clone());
+        bank.extend(records[1..].iter().cloned());
+
+        assert_eq!(bank.len(), 3);
+        assert!(bank.iter().eq(&records));
+        assert!((&bank).into_iter().eq(&records));
+        assert_eq!(bank.into_iter().collect::<Vec<_>>(), records);
+
+        let bank = records.iter().cloned().collect::<T>();
+        assert_eq!(bank.records(), records);
+    }
+
+    #[test]
+    fn test_collection() {
+        check_collection::<YPBankText>();
+        check_collection::<YPBankCsv>();
+        check_collection::<YPBankBin>();
+        check_collection::<YPBankJson>();
+        check_collection::<YPBankJsonl>();
+    }
+
+    #[test]
+    fn test_redact() {
+        let records = get_data_to_write();
+        let mut bank = YPBankCsv::from_records(records.clone());
+
+        let count = bank
+            .redact(
+                &HashSet::from([records[1].tx_id(), 1]),
+                &[RecordKey::FromUserId, RecordKey::Description],
+            )
+            .unwrap();
+
+        assert_eq!(count, 1);
+        assert_eq!(bank.records[0], records[0]);
+        assert_eq!(bank.records[1].from_user_id(), 0);
+        assert_eq!(bank.records[1].to_user_id(), records[1].to_user_id());
+        assert_eq!(bank.records[1].description(), "");
+
+        // Записи с удаленными значениями полей остаются структурно корректными.
+        let mut data = vec![];
+        bank.write_to(&mut data).unwrap();
+        assert_eq!(
+            YPBankCsv::read_from(&mut data.as_slice()).unwrap().records,
+            bank.records
+        );
+    }
+
+    #[test]
+    fn test_redact_unsupported_field() {
+        let records = get_data_to_write();
+        let mut bank = YPBankCsv::from_records(records.clone());
+
+        let result = bank.redact(
+            &HashSet::from([records[0].tx_id()]),
+            &[RecordKey::Description, RecordKey::Status],
+        );
+
+        assert_eq!(
+            result.unwrap_err(),
+            RedactError::UnsupportedField(RecordKey::Status)
+        );
+        assert_eq!(bank.records, records);
+    }
 }
@@ -0,0 +1,181 @@
+//! Модуль окончаний строк и метки порядка байтов UTF-8 в текстовых форматах.
+//!
+//! Построчные форматы при чтении допускают окончания строк `\r\n` и метку порядка байтов UTF-8
+//! в начале данных (так сохраняют файлы Windows-приложения, например, Excel). Для записи данных
+//! в таком же виде записываемые данные оборачиваются в [`LineEndingWriter`].
+
+use super::errors::FormatError;
+use std::io::{self, Write};
+
+/// Метка порядка байтов (BOM) в кодировке UTF-8.
+pub(crate) const UTF8_BOM: [u8; 3] = [0xEF, 0xBB, 0xBF];
+
+/// Окончание строк записываемых данных.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    /// `\n` (Unix).
+    #[default]
+    Lf,
+
+    /// `\r\n` (Windows).
+    CrLf,
+}
+
+/// Реализация трейта [`TryFrom<&str>`] для [`LineEnding`].
+impl TryFrom<&str> for LineEnding {
+    /// Ошибка определения окончания строк.
+    type Error = FormatError;
+
+    /// Реализация метода [`TryFrom<&str>::try_from`] для [`LineEnding`].
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "lf" | "unix" => Ok(Self::Lf),
+            "crlf" | "windows" => Ok(Self::CrLf),
+            _ => Err(FormatError::UnsupportedLineEnding(s.to_string())),
+        }
+    }
+}
+
+/// Назначение, заменяющее окончания строк записываемых данных и, при необходимости,
+/// записывающее метку порядка байтов UTF-8 перед ними.
+///
+/// Предполагается, что записываемые данные используют окончания строк `\n`.
+pub struct LineEndingWriter<W: Write> {
+    /// Исходное назначение.
+    inner: W,
+
+    /// Окончание строк.
+    line_ending: LineEnding,
+
+    /// Признак того, что метку порядка байтов еще предстоит записать.
+    pending_bom: bool,
+}
+
+impl<W: Write> LineEndingWriter<W> {
+    /// Обернуть назначение с указанным окончанием строк и признаком записи метки порядка байтов.
+    pub fn new(w: W, line_ending: LineEnding, bom: bool) -> Self {
+        Self {
+            inner: w,
+            line_ending,
+            pending_bom: bom,
+        }
+    }
+
+    /// Вернуть исходное назначение.
+    ///
+    /// Метка порядка байтов записывается, даже если данные не записывались.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.write_bom()?;
+
+        Ok(self.inner)
+    }
+
+    /// Записать метку порядка байтов, если она еще не записана.
+    fn write_bom(&mut self) -> io::Result<()> {
+        if self.pending_bom {
+            self.inner.write_all(&UTF8_BOM)?;
+            self.pending_bom = false;
+        }
+
+        Ok(())
+    }
+}
+
+/// Реализация трейта [`Write`] для [`LineEndingWriter`].
+impl<W: Write> Write for LineEndingWriter<W> {
+    /// Реализация метода [`Write::write`] для [`LineEndingWriter`].
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_bom()?;
+
+        match self.line_ending {
+            LineEnding::Lf => self.inner.write(buf),
+            LineEnding::CrLf => {
+                let mut lines = buf.split(|&b| b == b'\n').peekable();
+
+                while let Some(line) = lines.next() {
+                    self.inner.write_all(line)?;
+
+                    if lines.peek().is_some() {
+                        self.inner.write_all(b"\r\n")?;
+                    }
+                }
+
+                Ok(buf.len())
+            }
+        }
+    }
+
+    /// Реализация метода [`Write::flush`] для [`LineEndingWriter`].
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_bom()?;
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::YPBankImpl;
+    use rstest::rstest;
+
+    fn write(format: YPBankImpl, line_ending: LineEnding, bom: bool) -> Vec<u8> {
+        let mut writer = LineEndingWriter::new(vec![], line_ending, bom);
+        format
+            .write_to(crate::tests::get_data_to_write(), &mut writer)
+            .unwrap();
+
+        writer.into_inner().unwrap()
+    }
+
+    #[rstest]
+    fn test_roundtrip(
+        #[values("text", "csv", "json", "jsonl")] format: &str,
+        #[values(LineEnding::Lf, LineEnding::CrLf)] line_ending: LineEnding,
+        #[values(false, true)] bom: bool,
+    ) {
+        let format = YPBankImpl::try_from(format).unwrap();
+        let data = write(format, line_ending, bom);
+
+        assert_eq!(data.starts_with(&UTF8_BOM), bom);
+        assert_eq!(
+            data.windows(2).any(|w| w == b"\r\n"),
+            line_ending == LineEnding::CrLf
+        );
+        assert_eq!(
+            format.read_from(&mut data.as_slice()).unwrap(),
+            crate::tests::get_data_to_write()
+        );
+    }
+
+    #[test]
+    fn test_write_crlf() {
+        let mut writer = LineEndingWriter::new(vec![], LineEnding::CrLf, true);
+        writer.write_all(b"a\nb\n").unwrap();
+        writer.write_all(b"\nc").unwrap();
+
+        assert_eq!(writer.into_inner().unwrap(), b"\xEF\xBB\xBFa\r\nb\r\n\r\nc");
+    }
+
+    #[test]
+    fn test_write_bom_empty() {
+        let writer = LineEndingWriter::new(vec![], LineEnding::Lf, true);
+
+        assert_eq!(writer.into_inner().unwrap(), UTF8_BOM);
+    }
+
+    #[rstest]
+    #[case("lf", LineEnding::Lf)]
+    #[case("crlf", LineEnding::CrLf)]
+    #[case("windows", LineEnding::CrLf)]
+    fn test_try_from(#[case] value: &str, #[case] expected: LineEnding) {
+        assert_eq!(LineEnding::try_from(value).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_try_from_unsupported() {
+        assert_eq!(
+            LineEnding::try_from("cr").unwrap_err().to_string(),
+            "Unsupported line ending: cr"
+        );
+    }
+}
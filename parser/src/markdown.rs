@@ -0,0 +1,200 @@
+//! Модуль записи данных о транзакциях в виде таблицы Markdown (GitHub Flavored Markdown).
+//!
+//! Таблицу можно вставить в описание задачи или страницу вики без преобразования. Формат
+//! предназначен только для записи.
+
+use super::errors::WriteError;
+use super::record::{Record, RecordKey};
+use super::writer::RecordWriter;
+use std::io::{BufWriter, Write};
+
+/// Параметры записи таблицы Markdown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownOptions {
+    /// Поля записи, записываемые столбцами таблицы, в порядке следования столбцов.
+    pub columns: Vec<RecordKey>,
+
+    /// Максимальное количество строк таблицы. Если записей больше, после таблицы
+    /// указывается количество незаписанных записей.
+    pub max_rows: Option<usize>,
+}
+
+/// Реализация трейта [`Default`] для [`MarkdownOptions`].
+impl Default for MarkdownOptions {
+    /// Параметры по умолчанию: все обязательные поля записи без ограничения количества строк.
+    fn default() -> Self {
+        Self {
+            columns: Record::EXPECTED_KEYS.to_vec(),
+            max_rows: None,
+        }
+    }
+}
+
+impl MarkdownOptions {
+    /// Последовательно записывать записи о транзакциях строками таблицы Markdown.
+    ///
+    /// Заголовок таблицы записывается и при отсутствии записей.
+    pub fn writer_to<W: Write>(&self, w: W) -> RecordWriter<W> {
+        let options = self.clone();
+        let finish_options = self.clone();
+
+        RecordWriter::new_with(
+            w,
+            move |writer, record, index| options.write_record(writer, record, index),
+            move |writer, count| finish_options.finish(writer, count),
+        )
+    }
+
+    /// Записать строку таблицы с очередной записью о транзакции.
+    ///
+    /// Перед первой записью записывается заголовок таблицы, а записи сверх
+    /// [`MarkdownOptions::max_rows`] пропускаются.
+    fn write_record<W: Write>(
+        &self,
+        writer: &mut BufWriter<W>,
+        record: &Record,
+        index: usize,
+    ) -> Result<(), WriteError> {
+        if index == 0 {
+            self.write_header(writer)?;
+        }
+
+        if self.max_rows.is_some_and(|max_rows| index >= max_rows) {
+            return Ok(());
+        }
+
+        let cells = self
+            .columns
+            .iter()
+            .map(|&key| match key {
+                RecordKey::Description => escape_cell(record.description()),
+                key => record.field_to_string(key),
+            })
+            .collect::<Vec<_>>();
+
+        writeln!(writer, "| {} |", cells.join(" | "))?;
+
+        Ok(())
+    }
+
+    /// Записать заголовок таблицы с выравниванием числовых столбцов по правому краю.
+    fn write_header<W: Write>(&self, writer: &mut BufWriter<W>) -> Result<(), WriteError> {
+        let names = self
+            .columns
+            .iter()
+            .map(RecordKey::to_string)
+            .collect::<Vec<_>>();
+        let delimiters = self
+            .columns
+            .iter()
+            .map(|key| match key {
+                RecordKey::TxId
+                | RecordKey::FromUserId
+                | RecordKey::ToUserId
+                | RecordKey::Amount
+                | RecordKey::Timestamp => "---:",
+                _ => "---",
+            })
+            .collect::<Vec<_>>();
+
+        writeln!(writer, "| {} |", names.join(" | "))?;
+        writeln!(writer, "| {} |", delimiters.join(" | "))?;
+
+        Ok(())
+    }
+
+    /// Завершить таблицу: записать заголовок, если записей не было, либо количество
+    /// записей, не поместившихся в таблицу.
+    fn finish<W: Write>(&self, writer: &mut BufWriter<W>, count: usize) -> Result<(), WriteError> {
+        if count == 0 {
+            return self.write_header(writer);
+        }
+
+        if let Some(max_rows) = self.max_rows.filter(|&max_rows| count > max_rows) {
+            writeln!(writer)?;
+            writeln!(writer, "_{} more records not shown_", count - max_rows)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Экранировать значение ячейки таблицы: символ `|` и обратная косая черта экранируются
+/// обратной косой чертой, а переводы строк заменяются тегом `<br>`.
+fn escape_cell(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace(['\n', '\r'], "<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(options: &MarkdownOptions, records: &[Record]) -> String {
+        let mut writer = options.writer_to(vec![]);
+
+        for record in records {
+            writer.write_record(record).unwrap();
+        }
+
+        String::from_utf8(writer.finish().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_write() {
+        assert_eq!(
+            write(
+                &MarkdownOptions::default(),
+                &crate::tests::get_data_to_write()[..2]
+            ),
+            "| TX_ID | TX_TYPE | FROM_USER_ID | TO_USER_ID | AMOUNT | TIMESTAMP | STATUS | DESCRIPTION |
+| ---: | --- | ---: | ---: | ---: | ---: | --- | --- |
+| 1234567890123456 | DEPOSIT | 0 | 9876543210987654 | 10000 | 1633036800000 | SUCCESS | Terminal deposit |
+| 2312321321321321 | TRANSFER | 1231231231231231 | 9876543210987654 | 1000 | 1633056800000 | FAILURE | User transfer |
+"
+        );
+    }
+
+    #[test]
+    fn test_write_columns_and_max_rows() {
+        let options = MarkdownOptions {
+            columns: vec![RecordKey::Amount, RecordKey::TxType],
+            max_rows: Some(1),
+        };
+
+        assert_eq!(
+            write(&options, &crate::tests::get_data_to_write()),
+            "| AMOUNT | TX_TYPE |
+| ---: | --- |
+| 10000 | DEPOSIT |
+
+_2 more records not shown_
+"
+        );
+    }
+
+    #[test]
+    fn test_write_escaped_description() {
+        let mut record = crate::tests::get_data_to_write().remove(0);
+        record.set_description("a|b\\c\nd");
+        let options = MarkdownOptions {
+            columns: vec![RecordKey::Description],
+            max_rows: None,
+        };
+
+        assert!(write(&options, &[record]).ends_with("| a\\|b\\\\c<br>d |\n"));
+    }
+
+    #[test]
+    fn test_write_empty() {
+        assert_eq!(
+            write(&MarkdownOptions::default(), &[]),
+            "| TX_ID | TX_TYPE | FROM_USER_ID | TO_USER_ID | AMOUNT | TIMESTAMP | STATUS | DESCRIPTION |
+| ---: | --- | ---: | ---: | ---: | ---: | --- | --- |
+"
+        );
+    }
+}
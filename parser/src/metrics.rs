@@ -0,0 +1,356 @@
+//! Модуль сбора метрик потокового чтения и записи данных о транзакциях.
+//!
+//! [`RecordIter`](crate::RecordIter) и [`RecordWriter`](crate::RecordWriter) сообщают о каждой
+//! считанной и записанной записи и о каждой ошибке реализации трейта [`Metrics`], указанной
+//! методом `metrics`. [`MetricsRegistry`] накапливает счетчики и гистограммы и выводит их
+//! в текстовом формате Prometheus, поэтому сервис может отдавать их по `/metrics`.
+
+use std::fmt::{self, Write as _};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Получатель метрик потокового чтения и записи записей о транзакциях.
+///
+/// Методы вызываются из потока, считывающего или записывающего записи, поэтому должны
+/// выполняться быстро. Реализации по умолчанию ничего не делают.
+pub trait Metrics: Send + Sync {
+    /// Запись о транзакции считана: `size` — количество байт источника, занятых записью,
+    /// `latency` — время считывания и разбора записи.
+    fn record_read(&self, size: u64, latency: Duration) {
+        let _ = (size, latency);
+    }
+
+    /// Запись о транзакции не удалось считать.
+    fn read_error(&self) {}
+
+    /// Запись о транзакции записана за время `latency`.
+    fn record_written(&self, latency: Duration) {
+        let _ = latency;
+    }
+
+    /// Запись о транзакции не удалось записать.
+    fn write_error(&self) {}
+}
+
+/// Границы корзин гистограммы размеров записей в байтах.
+const RECORD_SIZE_BUCKETS: [f64; 8] = [64.0, 128.0, 256.0, 512.0, 1024.0, 4096.0, 16384.0, 65536.0];
+
+/// Границы корзин гистограмм времени считывания и записи записей в секундах.
+const LATENCY_BUCKETS: [f64; 7] = [1e-6, 1e-5, 1e-4, 1e-3, 1e-2, 0.1, 1.0];
+
+/// Гистограмма с фиксированными границами корзин.
+struct Histogram<const N: usize> {
+    /// Верхние границы корзин (включительно).
+    bounds: [f64; N],
+
+    /// Количество значений в каждой корзине и за последней границей.
+    counts: [AtomicU64; N],
+
+    /// Количество значений, превышающих последнюю границу.
+    overflow: AtomicU64,
+
+    /// Сумма значений в единицах `unit`.
+    sum: AtomicU64,
+
+    /// Величина единицы суммы значений (например, `1e-9` для наносекунд).
+    unit: f64,
+}
+
+impl<const N: usize> Histogram<N> {
+    /// Создание пустой гистограммы.
+    const fn new(bounds: [f64; N], unit: f64) -> Self {
+        Self {
+            bounds,
+            counts: [const { AtomicU64::new(0) }; N],
+            overflow: AtomicU64::new(0),
+            sum: AtomicU64::new(0),
+            unit,
+        }
+    }
+
+    /// Учесть значение, выраженное в единицах `unit`.
+    fn observe(&self, value: u64) {
+        let scaled = value as f64 * self.unit;
+
+        match self.bounds.iter().position(|&bound| scaled <= bound) {
+            Some(bucket) => self.counts[bucket].fetch_add(1, Ordering::Relaxed),
+            None => self.overflow.fetch_add(1, Ordering::Relaxed),
+        };
+        self.sum.fetch_add(value, Ordering::Relaxed);
+    }
+
+    /// Вывести гистограмму в текстовом формате Prometheus.
+    fn render(&self, out: &mut String, name: &str, help: &str) -> fmt::Result {
+        writeln!(out, "# HELP {name} {help}")?;
+        writeln!(out, "# TYPE {name} histogram")?;
+
+        let mut count = 0;
+        for (bound, bucket) in self.bounds.iter().zip(&self.counts) {
+            count += bucket.load(Ordering::Relaxed);
+            writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {count}")?;
+        }
+        count += self.overflow.load(Ordering::Relaxed);
+
+        writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {count}")?;
+        writeln!(
+            out,
+            "{name}_sum {}",
+            self.sum.load(Ordering::Relaxed) as f64 * self.unit
+        )?;
+        writeln!(out, "{name}_count {count}")
+    }
+}
+
+/// Накопитель метрик чтения и записи, выводимых в текстовом формате Prometheus.
+///
+/// Один накопитель разделяется между потоками и итераторами при помощи [`std::sync::Arc`]:
+///
+/// ```
+/// use parser::{MetricsRegistry, YPBankImpl};
+/// use std::sync::Arc;
+///
+/// let metrics = Arc::new(MetricsRegistry::new());
+///
+/// let records = YPBankImpl::Csv
+///     .iter_from("TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n".as_bytes())
+///     .metrics(metrics.clone())
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+///
+/// assert!(records.is_empty());
+/// assert!(metrics.to_prometheus().contains("ypbank_records_read_total 0"));
+/// ```
+pub struct MetricsRegistry {
+    /// Количество считанных записей.
+    records_read: AtomicU64,
+
+    /// Количество ошибок чтения записей.
+    read_errors: AtomicU64,
+
+    /// Количество записанных записей.
+    records_written: AtomicU64,
+
+    /// Количество ошибок записи записей.
+    write_errors: AtomicU64,
+
+    /// Размеры считанных записей в байтах.
+    record_size: Histogram<8>,
+
+    /// Время считывания и разбора записей в наносекундах.
+    parse_latency: Histogram<7>,
+
+    /// Время записи записей в наносекундах.
+    write_latency: Histogram<7>,
+}
+
+impl MetricsRegistry {
+    /// Создание накопителя с нулевыми значениями метрик.
+    pub const fn new() -> Self {
+        Self {
+            records_read: AtomicU64::new(0),
+            read_errors: AtomicU64::new(0),
+            records_written: AtomicU64::new(0),
+            write_errors: AtomicU64::new(0),
+            record_size: Histogram::new(RECORD_SIZE_BUCKETS, 1.0),
+            parse_latency: Histogram::new(LATENCY_BUCKETS, 1e-9),
+            write_latency: Histogram::new(LATENCY_BUCKETS, 1e-9),
+        }
+    }
+
+    /// Количество считанных записей.
+    pub fn records_read(&self) -> u64 {
+        self.records_read.load(Ordering::Relaxed)
+    }
+
+    /// Количество ошибок чтения записей.
+    pub fn read_errors(&self) -> u64 {
+        self.read_errors.load(Ordering::Relaxed)
+    }
+
+    /// Количество записанных записей.
+    pub fn records_written(&self) -> u64 {
+        self.records_written.load(Ordering::Relaxed)
+    }
+
+    /// Количество ошибок записи записей.
+    pub fn write_errors(&self) -> u64 {
+        self.write_errors.load(Ordering::Relaxed)
+    }
+
+    /// Вывести метрики в текстовом формате Prometheus (`text/plain; version=0.0.4`).
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+
+        self.render(&mut out)
+            .expect("writing to a string never fails");
+
+        out
+    }
+
+    /// Вывести метрики в текстовом формате Prometheus.
+    fn render(&self, out: &mut String) -> fmt::Result {
+        let counters = [
+            (
+                "ypbank_records_read_total",
+                "Records read",
+                &self.records_read,
+            ),
+            (
+                "ypbank_read_errors_total",
+                "Records that failed to be read",
+                &self.read_errors,
+            ),
+            (
+                "ypbank_records_written_total",
+                "Records written",
+                &self.records_written,
+            ),
+            (
+                "ypbank_write_errors_total",
+                "Records that failed to be written",
+                &self.write_errors,
+            ),
+        ];
+
+        for (name, help, counter) in counters {
+            writeln!(out, "# HELP {name} {help}")?;
+            writeln!(out, "# TYPE {name} counter")?;
+            writeln!(out, "{name} {}", counter.load(Ordering::Relaxed))?;
+        }
+
+        self.record_size.render(
+            out,
+            "ypbank_record_size_bytes",
+            "Size of read records in bytes",
+        )?;
+        self.parse_latency.render(
+            out,
+            "ypbank_parse_latency_seconds",
+            "Time to read and parse a record",
+        )?;
+        self.write_latency.render(
+            out,
+            "ypbank_write_latency_seconds",
+            "Time to write a record",
+        )
+    }
+}
+
+/// Реализация трейта [`Default`] для [`MetricsRegistry`].
+impl Default for MetricsRegistry {
+    /// Реализация метода [`Default::default`] для [`MetricsRegistry`].
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Реализация трейта [`Metrics`] для [`MetricsRegistry`].
+impl Metrics for MetricsRegistry {
+    /// Реализация метода [`Metrics::record_read`] для [`MetricsRegistry`].
+    fn record_read(&self, size: u64, latency: Duration) {
+        self.records_read.fetch_add(1, Ordering::Relaxed);
+        self.record_size.observe(size);
+        self.parse_latency.observe(latency.as_nanos() as u64);
+    }
+
+    /// Реализация метода [`Metrics::read_error`] для [`MetricsRegistry`].
+    fn read_error(&self) {
+        self.read_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Реализация метода [`Metrics::record_written`] для [`MetricsRegistry`].
+    fn record_written(&self, latency: Duration) {
+        self.records_written.fetch_add(1, Ordering::Relaxed);
+        self.write_latency.observe(latency.as_nanos() as u64);
+    }
+
+    /// Реализация метода [`Metrics::write_error`] для [`MetricsRegistry`].
+    fn write_error(&self) {
+        self.write_errors.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::YPBankImpl;
+    use rstest::rstest;
+    use std::sync::Arc;
+
+    #[rstest]
+    #[case(YPBankImpl::Text, b"\nUNEXPECTED\n")]
+    #[case(YPBankImpl::Csv, b"UNEXPECTED\n")]
+    #[case(YPBankImpl::Bin, b"\xff\xff\xff\xff\xff\xff\xff\xff\n")]
+    fn test_metrics_read_write(#[case] format: YPBankImpl, #[case] corrupted: &[u8]) {
+        let records = crate::tests::get_data_to_write();
+        let metrics = Arc::new(MetricsRegistry::new());
+
+        let mut writer = format.writer_to(vec![]).metrics(metrics.clone());
+        for record in &records {
+            writer.write_record(record).unwrap();
+        }
+        let mut data = writer.finish().unwrap();
+
+        // Поврежденный хвост источника приводит к ошибке чтения.
+        data.extend_from_slice(corrupted);
+
+        let read = format
+            .iter_from(data.as_slice())
+            .metrics(metrics.clone())
+            .collect::<Vec<_>>();
+
+        assert_eq!(read.len(), records.len() + 1);
+        assert_eq!(metrics.records_written(), records.len() as u64);
+        assert_eq!(metrics.write_errors(), 0);
+        assert_eq!(metrics.records_read(), records.len() as u64);
+        assert_eq!(metrics.read_errors(), 1);
+
+        let sizes = metrics.record_size.sum.load(Ordering::Relaxed);
+        assert!(sizes > 0 && sizes < data.len() as u64);
+    }
+
+    #[test]
+    fn test_metrics_write_error() {
+        let metrics = Arc::new(MetricsRegistry::new());
+        let record = crate::record::Record::builder()
+            .tx_id(1)
+            .tx_type(crate::TxType::Deposit)
+            .from_user_id(0)
+            .to_user_id(1)
+            .wide_amount(-1)
+            .timestamp(1_700_000_000_000)
+            .status(crate::Status::Success)
+            .build()
+            .unwrap();
+
+        let mut writer = YPBankImpl::Csv.writer_to(vec![]).metrics(metrics.clone());
+
+        assert!(writer.write_record(&record).is_err());
+        assert_eq!(metrics.write_errors(), 1);
+        assert_eq!(metrics.records_written(), 0);
+    }
+
+    #[test]
+    fn test_to_prometheus() {
+        let metrics = MetricsRegistry::new();
+        metrics.record_read(100, Duration::from_micros(5));
+        metrics.record_read(10_000_000, Duration::from_secs(2));
+        metrics.read_error();
+
+        let text = metrics.to_prometheus();
+
+        assert!(
+            text.contains(
+                "# TYPE ypbank_records_read_total counter\nypbank_records_read_total 2\n"
+            )
+        );
+        assert!(text.contains("ypbank_read_errors_total 1\n"));
+        assert!(text.contains("ypbank_record_size_bytes_bucket{le=\"64\"} 0\n"));
+        assert!(text.contains("ypbank_record_size_bytes_bucket{le=\"128\"} 1\n"));
+        assert!(text.contains("ypbank_record_size_bytes_bucket{le=\"+Inf\"} 2\n"));
+        assert!(text.contains("ypbank_record_size_bytes_sum 10000100\n"));
+        assert!(text.contains("ypbank_parse_latency_seconds_bucket{le=\"0.00001\"} 1\n"));
+        assert!(text.contains("ypbank_parse_latency_seconds_count 2\n"));
+        assert!(text.contains("ypbank_write_latency_seconds_count 0\n"));
+    }
+}
@@ -0,0 +1,127 @@
+//! Модуль чтения записей о транзакциях из файлов, отображенных в память.
+//!
+//! Отображение в память избавляет от копирования данных через буфер чтения, что ускоряет
+//! чтение больших архивов, не изменяемых во время чтения. Записи в бинарном формате
+//! разбираются непосредственно из отображенных данных (см. [`YPBankBin::parse_slice`]),
+//! а записи в остальных форматах (например, CSV) считываются из них как из среза байтов.
+//!
+//! Поддержка включается признаком крейта `mmap`.
+
+use super::YPBankImpl;
+use super::bin_format::YPBankBin;
+use super::errors::ReadError;
+use super::iter::MAX_PREALLOCATED_RECORDS;
+use super::record::Record;
+use memmap2::Mmap;
+use std::fs::File;
+use std::path::Path;
+
+/// Считать записи о транзакциях в заданном формате из файла, отображенного в память.
+///
+/// Файл не должен изменяться или усекаться другими процессами во время чтения. Сжатые файлы
+/// не распаковываются.
+pub fn read_from_path_mmap<P: AsRef<Path>>(
+    path: P,
+    format: YPBankImpl,
+) -> Result<Vec<Record>, ReadError> {
+    let file = File::open(path)?;
+
+    // SAFETY: отображение используется только для чтения и не переживает функцию;
+    // неизменность файла во время чтения требуется от вызывающей стороны.
+    let map = unsafe { Mmap::map(&file)? };
+
+    let capacity_hint = format.estimate_record_count(map.len() as u64);
+
+    match format {
+        YPBankImpl::Bin => {
+            let mut records = Vec::with_capacity(capacity_hint.min(MAX_PREALLOCATED_RECORDS));
+
+            for record in YPBankBin::parse_slice(&map) {
+                records.push(record?.into());
+            }
+
+            Ok(records)
+        }
+        format => format
+            .iter_from(&map[..])
+            .capacity_hint(capacity_hint)
+            .read_all(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use std::path::PathBuf;
+
+    /// Временный файл, удаляемый по окончании теста.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, data: &[u8]) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("parser-mmap-{}-{name}", std::process::id()));
+            std::fs::write(&path, data).unwrap();
+
+            Self(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[rstest]
+    fn test_read_from_path_mmap(#[values("bin", "csv", "text")] format: &str) {
+        let format = YPBankImpl::try_from(format).unwrap();
+
+        let mut data = vec![];
+        format
+            .write_to(crate::tests::get_data_to_write(), &mut data)
+            .unwrap();
+        let file = TempFile::new(&format!("{format:?}"), &data);
+
+        assert_eq!(
+            read_from_path_mmap(&file.0, format).unwrap(),
+            crate::tests::get_data_to_write()
+        );
+    }
+
+    #[test]
+    fn test_read_from_path_mmap_empty() {
+        let file = TempFile::new("empty", &[]);
+
+        assert!(
+            read_from_path_mmap(&file.0, YPBankImpl::Bin)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_read_from_path_mmap_truncated() {
+        let mut data = vec![];
+        YPBankImpl::Bin
+            .write_to(crate::tests::get_data_to_write(), &mut data)
+            .unwrap();
+        data.truncate(data.len() - 1);
+        let file = TempFile::new("truncated", &data);
+
+        assert!(
+            read_from_path_mmap(&file.0, YPBankImpl::Bin)
+                .unwrap_err()
+                .is_truncated()
+        );
+    }
+
+    #[test]
+    fn test_read_from_path_mmap_missing() {
+        assert!(matches!(
+            read_from_path_mmap("/nonexistent/parser-mmap.bin", YPBankImpl::Bin),
+            Err(ReadError::Io(_))
+        ));
+    }
+}
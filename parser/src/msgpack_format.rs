@@ -0,0 +1,220 @@
+//! Модуль чтения и записи данных о транзакциях в формате MessagePack.
+//!
+//! Поддержка включается признаком крейта `msgpack`.
+
+use super::YPBank;
+use super::errors::{ReadError, WriteError};
+use super::iter::RecordIter;
+use super::options::ParseContext;
+use super::record::Record;
+use super::writer::{RecordWriter, finish_nothing};
+use rmp_serde::Serializer;
+use serde::Serialize;
+use std::io::{BufRead, BufWriter, Read, Write};
+
+/// Записи о транзакциях в формате MessagePack.
+///
+/// Данные представляют собой последовательность MessagePack-значений, по одному на запись.
+/// Записываемая запись кодируется словарем с ключами полей (`TX_ID`, `TX_TYPE`, ...) и теми же
+/// значениями, что и в JSON-формате; при чтении допускается также массив значений полей в порядке
+/// их объявления.
+#[derive(Debug)]
+pub struct YPBankMsgPack {
+    /// Записи о банковских операциях.
+    pub records: Vec<Record>,
+}
+
+impl YPBankMsgPack {
+    /// Считать очередную запись о банковской операции в формате MessagePack.
+    ///
+    /// Границы значений не отмечены в потоке данных, поэтому после ошибки чтение
+    /// не может быть продолжено.
+    fn read_record<B: BufRead>(
+        reader: &mut B,
+        _index: usize,
+        context: &ParseContext,
+    ) -> Result<Option<Record>, ReadError> {
+        if reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+
+        let mut deserializer = rmp_serde::Deserializer::new(reader);
+
+        Ok(Some(Record::deserialize_with(&mut deserializer, context)?))
+    }
+
+    /// Записать очередную запись о банковской операции в формате MessagePack.
+    fn write_record<W: Write>(
+        writer: &mut BufWriter<W>,
+        record: &Record,
+        _index: usize,
+    ) -> Result<(), WriteError> {
+        record.serialize(&mut Serializer::new(writer).with_struct_map())?;
+
+        Ok(())
+    }
+}
+
+impl YPBank for YPBankMsgPack {
+    /// Считанные записи о банковских операциях.
+    fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    /// Изменяемый список записей о банковских операциях.
+    fn records_mut(&mut self) -> &mut Vec<Record> {
+        &mut self.records
+    }
+
+    /// Создание набора из указанных записей о банковских операциях.
+    fn from_records(records: Vec<Record>) -> Self {
+        Self { records }
+    }
+
+    /// Считать данные о банковских операциях в формате MessagePack.
+    fn read_from<R: Read>(r: &mut R) -> Result<Self, ReadError> {
+        let records = Self::iter_from(r).collect::<Result<_, _>>()?;
+
+        Ok(Self { records })
+    }
+
+    /// Последовательно считывать данные о банковских операциях в формате MessagePack.
+    fn iter_from<R: Read>(r: R) -> RecordIter<R> {
+        RecordIter::new_binary(r, Self::read_record)
+    }
+
+    /// Записать данные о банковских операциях в формате MessagePack.
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<(), WriteError> {
+        let mut writer = Self::writer_to(w);
+
+        for record in &self.records {
+            writer.write_record(record)?;
+        }
+
+        writer.finish()?;
+
+        Ok(())
+    }
+
+    /// Последовательно записывать данные о банковских операциях в формате MessagePack.
+    fn writer_to<W: Write>(w: W) -> RecordWriter<W> {
+        RecordWriter::new(w, Self::write_record, finish_nothing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SchemaVersion;
+    use crate::record::{Status, TxType};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_roundtrip() {
+        let records = crate::tests::get_data_to_write();
+
+        let data = YPBankMsgPack {
+            records: records.clone(),
+        };
+        let mut cursor = Cursor::new(vec![]);
+        data.write_to(&mut cursor).unwrap();
+
+        let mut cursor = Cursor::new(cursor.into_inner());
+        let data = YPBankMsgPack::read_from(&mut cursor).unwrap();
+
+        assert_eq!(data.records, records);
+    }
+
+    #[test]
+    fn test_roundtrip_wide_amounts() {
+        let records = crate::tests::get_wide_data_to_write();
+
+        let mut writer = YPBankMsgPack::writer_to(vec![]).schema_version(SchemaVersion::V2);
+        for record in &records {
+            writer.write_record(record).unwrap();
+        }
+        let buffer = writer.finish().unwrap();
+
+        let data = YPBankMsgPack::read_from(&mut buffer.as_slice()).unwrap();
+
+        assert_eq!(data.records, records);
+    }
+
+    #[test]
+    fn test_write_to_msgpack_map() {
+        let data = YPBankMsgPack {
+            records: vec![Record::new(
+                1,
+                TxType::Deposit,
+                0,
+                2,
+                100,
+                1000,
+                Status::Success,
+                "a".to_string(),
+            )],
+        };
+        let mut cursor = Cursor::new(vec![]);
+        data.write_to(&mut cursor).unwrap();
+
+        let bytes = cursor.into_inner();
+
+        // Словарь из 8 элементов, первый ключ — строка "TX_ID" со значением 1.
+        assert_eq!(bytes[..8], [0x88, 0xa5, b'T', b'X', b'_', b'I', b'D', 1]);
+    }
+
+    #[test]
+    fn test_read_from_msgpack_array() {
+        let value = (
+            7u64,
+            "TRANSFER",
+            1u64,
+            2u64,
+            300u64,
+            1000u64,
+            "PENDING",
+            "Array record",
+        );
+        let data = rmp_serde::to_vec(&value).unwrap();
+
+        let result = YPBankMsgPack::read_from(&mut data.as_slice()).unwrap();
+
+        assert_eq!(
+            result.records,
+            vec![Record::new(
+                7,
+                TxType::Transfer,
+                1,
+                2,
+                300,
+                1000,
+                Status::Pending,
+                "Array record".to_string(),
+            )]
+        );
+    }
+
+    #[test]
+    fn test_write_to_msgpack_empty_record() {
+        let data = YPBankMsgPack { records: vec![] };
+        let mut cursor = Cursor::new(vec![]);
+        data.write_to(&mut cursor).unwrap();
+        assert_eq!(cursor.into_inner(), b"");
+    }
+
+    #[test]
+    fn test_read_from_msgpack_truncated() {
+        let mut data = vec![];
+        YPBankMsgPack {
+            records: crate::tests::get_data_to_write(),
+        }
+        .write_to(&mut data)
+        .unwrap();
+        data.truncate(data.len() - 5);
+
+        let result = YPBankMsgPack::read_from(&mut data.as_slice()).unwrap_err();
+
+        assert!(matches!(result.inner(), ReadError::FromMsgPack(_)));
+        assert_eq!(result.position().unwrap().record, 3);
+    }
+}
@@ -0,0 +1,200 @@
+//! Модуль параметров чтения данных о транзакциях.
+
+use super::errors::ReadError;
+use super::record::{Record, RecordKey};
+use super::schema::SchemaVersion;
+
+/// Способ обработки ошибок чтения отдельных записей.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnError {
+    /// Прервать чтение при первой ошибке.
+    #[default]
+    Fail,
+
+    /// Пропустить поврежденную запись, сохранив только ее порядковый номер.
+    Skip,
+
+    /// Пропустить поврежденную запись, сохранив ее порядковый номер и ошибку чтения.
+    Collect,
+}
+
+/// Параметры чтения данных о транзакциях.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Способ обработки ошибок чтения отдельных записей.
+    pub on_error: OnError,
+
+    /// Признак нестрогого чтения значений: неизвестные типы и состояния транзакций (например,
+    /// появившиеся в более новых выгрузках) считываются как [`crate::TxType::Unknown`]
+    /// и [`crate::Status::Other`], а не приводят к ошибке чтения записи.
+    pub lenient: bool,
+
+    /// Ограничения размеров считываемых записей.
+    pub limits: SizeLimits,
+
+    /// Ожидаемое количество записей, под которое заранее выделяется память, если оно
+    /// не объявлено в заголовке формата (см. [`crate::YPBankImpl::estimate_record_count`]).
+    pub capacity_hint: Option<usize>,
+
+    /// Признак разделения одинаковых описаний транзакций между считанными записями
+    /// (см. [`crate::DescriptionPool`]), уменьшающего объем памяти для наборов записей
+    /// с повторяющимися описаниями.
+    pub intern_descriptions: bool,
+
+    /// Поля, значения которых считываются из источника (см. [`Projection`]).
+    pub projection: Projection,
+}
+
+/// Параметры записи данных о транзакциях (см. [`crate::RecordWriter::with_options`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// Емкость буфера записи в байтах.
+    ///
+    /// Больший буфер сокращает количество системных вызовов при записи множества небольших
+    /// записей, например, в канал.
+    pub buffer_capacity: usize,
+
+    /// Версия схемы записываемых записей (см. [`crate::RecordWriter::schema_version`]).
+    pub schema_version: SchemaVersion,
+}
+
+impl WriteOptions {
+    /// Емкость буфера записи по умолчанию (8 КиБ).
+    pub const DEFAULT_BUFFER_CAPACITY: usize = 8 * 1024;
+}
+
+/// Реализация трейта [`Default`] для [`WriteOptions`].
+impl Default for WriteOptions {
+    /// Реализация метода [`Default::default`] для [`WriteOptions`].
+    fn default() -> Self {
+        Self {
+            buffer_capacity: Self::DEFAULT_BUFFER_CAPACITY,
+            schema_version: SchemaVersion::default(),
+        }
+    }
+}
+
+/// Ограничения размеров считываемых записей.
+///
+/// Размеры в бинарном формате задаются самим источником, а строки текстового и CSV форматов
+/// могут не содержать перевода строки или закрывающей кавычки, поэтому ограничения защищают
+/// от специально сформированных данных, вынуждающих выделить чрезмерный объем памяти.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeLimits {
+    /// Максимальный размер тела записи (RECORD_SIZE) в бинарном формате или суммарный размер
+    /// строк записи в текстовом и CSV форматах в байтах.
+    pub max_record_size: u32,
+
+    /// Максимальная длина описания транзакции (DESCRIPTION_SIZE) в байтах.
+    pub max_description_len: u32,
+}
+
+impl SizeLimits {
+    /// Ограничения по умолчанию: 1 МиБ на запись и 64 КиБ на описание транзакции.
+    pub const DEFAULT: Self = Self {
+        max_record_size: 1024 * 1024,
+        max_description_len: 64 * 1024,
+    };
+}
+
+/// Реализация трейта [`Default`] для [`SizeLimits`].
+impl Default for SizeLimits {
+    /// Реализация метода [`Default::default`] для [`SizeLimits`].
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Набор полей записи, значения которых считываются из источника (см. [`Record::project`]).
+///
+/// Поля, не входящие в набор, получают пустые значения: числовые поля — 0, описание —
+/// пустую строку, валюта и дополнительные поля отсутствуют. При чтении из бинарного формата
+/// описание и расширения таких полей не декодируются, что сокращает объем памяти
+/// и время чтения, например, когда записи считываются только для подсчета итогов.
+/// TX_ID, TX_TYPE и STATUS не имеют пустого значения и считываются всегда.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Projection {
+    /// Битовая маска ключей полей (бит с номером ключа в [`RecordKey`]).
+    keys: u16,
+
+    /// Признак считывания дополнительных полей записи (см. [`Record::metadata`]).
+    pub metadata: bool,
+}
+
+impl Projection {
+    /// Все поля записи, включая дополнительные.
+    pub const ALL: Self = Self {
+        keys: u16::MAX,
+        metadata: true,
+    };
+
+    /// Набор из указанных полей записи без дополнительных полей.
+    pub fn new(keys: &[RecordKey]) -> Self {
+        keys.iter().copied().collect()
+    }
+
+    /// Признак вхождения поля с указанным ключом в набор.
+    pub fn contains(&self, key: RecordKey) -> bool {
+        self.keys & (1 << key as u16) != 0
+    }
+
+    /// Признак набора из всех полей записи, включая дополнительные.
+    pub fn is_all(&self) -> bool {
+        *self == Self::ALL
+    }
+}
+
+/// Реализация трейта [`Default`] для [`Projection`].
+impl Default for Projection {
+    /// Реализация метода [`Default::default`] для [`Projection`].
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+/// Реализация трейта [`FromIterator<RecordKey>`] для [`Projection`].
+impl FromIterator<RecordKey> for Projection {
+    /// Реализация метода [`FromIterator::from_iter`] для [`Projection`].
+    fn from_iter<I: IntoIterator<Item = RecordKey>>(iter: I) -> Self {
+        Self {
+            keys: iter.into_iter().fold(0, |keys, key| keys | 1 << key as u16),
+            metadata: false,
+        }
+    }
+}
+
+/// Параметры разбора отдельной записи, передаваемые функциям чтения форматов
+/// (см. [`crate::RecordIter`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct ParseContext {
+    /// Признак нестрогого чтения значений (см. [`ParseOptions::lenient`]).
+    pub(crate) lenient: bool,
+
+    /// Ограничения размеров считываемых записей (см. [`ParseOptions::limits`]).
+    pub(crate) limits: SizeLimits,
+
+    /// Поля, значения которых считываются из источника (см. [`ParseOptions::projection`]).
+    pub(crate) projection: Projection,
+}
+
+/// Проверить, может ли значение быть считано как неизвестное значение перечисления при нестрогом
+/// чтении: оно должно быть непустым и состоять из латинских букв, цифр, `_`, `-` и `.`.
+pub(crate) fn is_lenient_value(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-' | b'.'))
+}
+
+/// Результат чтения данных о транзакциях с учетом параметров [`ParseOptions`].
+#[derive(Debug, Default)]
+pub struct ParseReport {
+    /// Успешно считанные записи о транзакциях.
+    pub records: Vec<Record>,
+
+    /// Порядковые номера пропущенных поврежденных записей (начиная с 1).
+    pub skipped: Vec<usize>,
+
+    /// Ошибки чтения пропущенных записей (только для [`OnError::Collect`]).
+    pub errors: Vec<ReadError>,
+}
@@ -0,0 +1,225 @@
+//! Модуль постраничного чтения записей о транзакциях.
+//!
+//! [`read_page`] считывает страницу записей, начиная с указанного порядкового номера или маркера
+//! продолжения ([`PageToken`]), полученного вместе с предыдущей страницей. Источник не загружается
+//! в память целиком: предшествующие записи пропускаются при чтении, а для бинарного формата
+//! маркер содержит смещение следующей записи, поэтому каждая следующая страница считывается
+//! без просмотра предыдущих.
+
+use super::YPBankImpl;
+use super::bin_reader::BinReader;
+use super::errors::{FormatError, ReadError};
+use super::record::Record;
+use std::fmt;
+use std::io::{Read, Seek, SeekFrom};
+
+/// Маркер продолжения постраничного чтения: положение первой записи страницы.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PageToken {
+    /// Порядковый номер (начиная с 0) первой записи страницы.
+    pub offset: usize,
+
+    /// Смещение первой записи страницы в байтах от начала источника в бинарном формате.
+    byte_offset: Option<u64>,
+}
+
+/// Реализация трейта [`From<usize>`] для [`PageToken`].
+impl From<usize> for PageToken {
+    /// Маркер страницы, начинающейся с записи с указанным порядковым номером (начиная с 0).
+    fn from(offset: usize) -> Self {
+        Self {
+            offset,
+            byte_offset: None,
+        }
+    }
+}
+
+/// Реализация трейта [`fmt::Display`] для [`PageToken`].
+///
+/// Маркер записывается в виде `OFFSET` или `OFFSET@BYTE_OFFSET`, пригодном для передачи
+/// клиенту и обратного разбора при помощи [`TryFrom<&str>`].
+impl fmt::Display for PageToken {
+    /// Реализация метода [`fmt::Display::fmt`] для [`PageToken`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.offset)?;
+
+        if let Some(byte_offset) = self.byte_offset {
+            write!(f, "@{byte_offset}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Реализация трейта [`TryFrom<&str>`] для [`PageToken`].
+impl TryFrom<&str> for PageToken {
+    /// Ошибка разбора маркера.
+    type Error = FormatError;
+
+    /// Реализация метода [`TryFrom<&str>::try_from`] для [`PageToken`].
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        let invalid_token = || FormatError::InvalidPageToken(s.to_string());
+
+        let (offset, byte_offset) = match s.split_once('@') {
+            Some((offset, byte_offset)) => (
+                offset,
+                Some(byte_offset.parse().map_err(|_| invalid_token())?),
+            ),
+            None => (s, None),
+        };
+
+        Ok(Self {
+            offset: offset.parse().map_err(|_| invalid_token())?,
+            byte_offset,
+        })
+    }
+}
+
+/// Страница записей о транзакциях.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    /// Записи страницы.
+    pub records: Vec<Record>,
+
+    /// Маркер следующей страницы или `None`, если страница последняя.
+    pub next: Option<PageToken>,
+}
+
+/// Считать страницу из не более чем `limit` записей о транзакциях в заданном формате,
+/// начиная с записи с указанным порядковым номером (начиная с 0) или маркером продолжения.
+///
+/// Записи в бинарном формате, предшествующие странице, пропускаются по их RECORD_SIZE без
+/// разбора полей (см. [`BinReader`]), а при чтении по маркеру — не просматриваются вовсе.
+/// Записи в остальных форматах, предшествующие странице, считываются и отбрасываются.
+pub fn read_page<R: Read + Seek>(
+    reader: &mut R,
+    format: YPBankImpl,
+    offset: impl Into<PageToken>,
+    limit: usize,
+) -> Result<Page, ReadError> {
+    let token = offset.into();
+
+    if format == YPBankImpl::Bin {
+        return read_bin_page(reader, token, limit);
+    }
+
+    reader.seek(SeekFrom::Start(0))?;
+
+    let mut iter = format.iter_from(reader);
+
+    for _ in 0..token.offset {
+        if iter.next().transpose()?.is_none() {
+            return Ok(Page {
+                records: vec![],
+                next: None,
+            });
+        }
+    }
+
+    let records = iter.by_ref().take(limit).collect::<Result<Vec<_>, _>>()?;
+
+    // Ошибка в первой записи следующей страницы сообщается при ее чтении.
+    let next = (records.len() == limit && iter.next().is_some())
+        .then(|| PageToken::from(token.offset + limit));
+
+    Ok(Page { records, next })
+}
+
+/// Считать страницу записей в бинарном формате.
+fn read_bin_page<R: Read + Seek>(
+    reader: &mut R,
+    token: PageToken,
+    limit: usize,
+) -> Result<Page, ReadError> {
+    let mut reader = BinReader::new(reader)?;
+
+    let start = match token.byte_offset {
+        Some(byte_offset) => Some(byte_offset).filter(|&byte_offset| byte_offset < reader.end()),
+        None => reader.seek_to_record(token.offset)?,
+    };
+
+    let Some(start) = start else {
+        return Ok(Page {
+            records: vec![],
+            next: None,
+        });
+    };
+
+    let (records, next_offset) = reader.read_records_at(token.offset, start, limit)?;
+    let next = (next_offset < reader.end()).then(|| PageToken {
+        offset: token.offset + records.len(),
+        byte_offset: Some(next_offset),
+    });
+
+    Ok(Page { records, next })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use std::io::Cursor;
+
+    fn get_data(format: YPBankImpl) -> Cursor<Vec<u8>> {
+        let mut data = vec![];
+        format
+            .write_to(crate::tests::get_data_to_write(), &mut data)
+            .unwrap();
+
+        Cursor::new(data)
+    }
+
+    #[rstest]
+    #[case(YPBankImpl::Bin)]
+    #[case(YPBankImpl::Csv)]
+    #[case(YPBankImpl::Text)]
+    #[case(YPBankImpl::Json)]
+    fn test_read_pages(#[case] format: YPBankImpl) {
+        let mut data = get_data(format);
+        let records = crate::tests::get_data_to_write();
+
+        let first = read_page(&mut data, format, 0, 2).unwrap();
+
+        assert_eq!(first.records, records[..2]);
+        assert_eq!(first.next.unwrap().offset, 2);
+
+        let token = PageToken::try_from(first.next.unwrap().to_string().as_str()).unwrap();
+        let second = read_page(&mut data, format, token, 2).unwrap();
+
+        assert_eq!(second.records, records[2..]);
+        assert_eq!(second.next, None);
+    }
+
+    #[rstest]
+    #[case(YPBankImpl::Bin)]
+    #[case(YPBankImpl::Csv)]
+    fn test_read_page_exact_and_past_end(#[case] format: YPBankImpl) {
+        let mut data = get_data(format);
+
+        let page = read_page(&mut data, format, 1, 2).unwrap();
+        assert_eq!(page.records.len(), 2);
+        assert_eq!(page.next, None);
+
+        let page = read_page(&mut data, format, 5, 2).unwrap();
+        assert!(page.records.is_empty());
+        assert_eq!(page.next, None);
+    }
+
+    #[test]
+    fn test_bin_page_token() {
+        let mut data = get_data(YPBankImpl::Bin);
+
+        let page = read_page(&mut data, YPBankImpl::Bin, 0, 1).unwrap();
+
+        assert_eq!(page.next.unwrap().to_string(), "1@72");
+    }
+
+    #[rstest]
+    #[case("12", Ok(PageToken::from(12)))]
+    #[case("3@144", Ok(PageToken { offset: 3, byte_offset: Some(144) }))]
+    #[case("x", Err("Invalid page token: x".to_string()))]
+    #[case("1@", Err("Invalid page token: 1@".to_string()))]
+    fn test_parse_page_token(#[case] s: &str, #[case] expected: Result<PageToken, String>) {
+        assert_eq!(PageToken::try_from(s).map_err(|e| e.to_string()), expected);
+    }
+}
@@ -0,0 +1,298 @@
+//! Модуль чтения и записи данных о транзакциях в формате Apache Parquet.
+//!
+//! Поддержка включается признаком крейта `parquet`.
+
+use super::errors::{ReadError, WriteError};
+use super::record::{Record, RecordKey, Status, TxType};
+use super::schema::SchemaVersion;
+use bytes::Bytes;
+use parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use parquet::file::reader::FileReader;
+use parquet::file::serialized_reader::SerializedFileReader;
+use parquet::file::writer::{SerializedColumnWriter, SerializedFileWriter};
+use parquet::record::RowAccessor;
+use parquet::schema::parser::parse_message_type;
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+/// Записи о транзакциях в формате Apache Parquet.
+///
+/// Каждому полю записи соответствует столбец с именем ключа поля: идентификаторы и сумма
+/// хранятся как беззнаковые 64-битные целые, метка времени — как метка времени в миллисекундах
+/// (UTC), остальные поля — как строки UTF-8.
+///
+/// Метаданные формата расположены в конце файла, поэтому при чтении источник считывается
+/// в память целиком.
+#[derive(Debug)]
+pub struct YPBankParquet {
+    /// Записи о банковских операциях.
+    pub records: Vec<Record>,
+}
+
+impl YPBankParquet {
+    /// Схема записи о транзакции.
+    const SCHEMA: &str = "message transaction {
+        REQUIRED INT64 TX_ID (INTEGER(64,false));
+        REQUIRED BYTE_ARRAY TX_TYPE (UTF8);
+        REQUIRED INT64 FROM_USER_ID (INTEGER(64,false));
+        REQUIRED INT64 TO_USER_ID (INTEGER(64,false));
+        REQUIRED INT64 AMOUNT (INTEGER(64,false));
+        REQUIRED INT64 TIMESTAMP (TIMESTAMP(MILLIS,true));
+        REQUIRED BYTE_ARRAY STATUS (UTF8);
+        REQUIRED BYTE_ARRAY DESCRIPTION (UTF8);
+    }";
+
+    /// Считать данные о банковских операциях в формате Apache Parquet.
+    pub fn read_from<R: Read>(r: &mut R) -> Result<Self, ReadError> {
+        let mut buffer = vec![];
+        r.read_to_end(&mut buffer)?;
+
+        let reader = SerializedFileReader::new(Bytes::from(buffer))?;
+
+        let records = reader
+            .get_row_iter(None)?
+            .map(|row| {
+                let row = row?;
+
+                Ok(Record::new(
+                    row.get_ulong(0)?,
+                    TxType::try_from(row.get_string(1)?.as_str()).map_err(Self::invalid_value)?,
+                    row.get_ulong(2)?,
+                    row.get_ulong(3)?,
+                    row.get_ulong(4)?,
+                    row.get_timestamp_millis(5)? as u64,
+                    Status::try_from(row.get_string(6)?.as_str()).map_err(Self::invalid_value)?,
+                    row.get_string(7)?.clone(),
+                ))
+            })
+            .collect::<Result<_, ParquetError>>()?;
+
+        Ok(Self { records })
+    }
+
+    /// Ошибка некорректного значения поля записи.
+    fn invalid_value(e: impl std::error::Error + Send + Sync + 'static) -> ParquetError {
+        ParquetError::External(Box::new(e))
+    }
+
+    /// Записать данные о банковских операциях в формате Apache Parquet.
+    pub fn write_to<W: Write + Send>(&self, w: W) -> Result<W, WriteError> {
+        let mut writer = Self::writer_to(w)?;
+
+        for record in &self.records {
+            writer.write_record(record)?;
+        }
+
+        writer.finish()
+    }
+
+    /// Последовательно записывать данные о банковских операциях в формате Apache Parquet.
+    pub fn writer_to<W: Write + Send>(w: W) -> Result<ParquetWriter<W>, WriteError> {
+        let schema = Arc::new(parse_message_type(Self::SCHEMA)?);
+        let properties = Arc::new(WriterProperties::builder().build());
+
+        Ok(ParquetWriter {
+            writer: SerializedFileWriter::new(w, schema, properties)?,
+            buffer: vec![],
+        })
+    }
+}
+
+/// Потоковая запись данных о транзакциях в формате Apache Parquet.
+///
+/// Записи накапливаются в памяти и записываются группами строк по
+/// [`ParquetWriter::ROW_GROUP_SIZE`] записей. Для записи метаданных формата необходимо
+/// вызвать [`ParquetWriter::finish`].
+pub struct ParquetWriter<W: Write + Send> {
+    /// Назначение записи.
+    writer: SerializedFileWriter<W>,
+
+    /// Записи очередной группы строк.
+    buffer: Vec<Record>,
+}
+
+impl<W: Write + Send> ParquetWriter<W> {
+    /// Количество записей в группе строк.
+    pub const ROW_GROUP_SIZE: usize = 65_536;
+
+    /// Записать очередную запись о транзакции.
+    ///
+    /// Схема формата не вмещает широкие суммы (см. [`SchemaVersion::V2`]).
+    pub fn write_record(&mut self, record: &Record) -> Result<(), WriteError> {
+        if record.schema_version() > SchemaVersion::V1 {
+            return Err(WriteError::SchemaVersionRequired {
+                tx_id: record.tx_id(),
+                amount: record.wide_amount(),
+                required: record.schema_version(),
+                used: SchemaVersion::V1,
+            });
+        }
+
+        self.buffer.push(record.clone());
+
+        if self.buffer.len() >= Self::ROW_GROUP_SIZE {
+            self.write_row_group()?;
+        }
+
+        Ok(())
+    }
+
+    /// Завершить запись данных и вернуть исходное назначение.
+    pub fn finish(mut self) -> Result<W, WriteError> {
+        if !self.buffer.is_empty() {
+            self.write_row_group()?;
+        }
+
+        Ok(self.writer.into_inner()?)
+    }
+
+    /// Записать накопленные записи в виде группы строк.
+    fn write_row_group(&mut self) -> Result<(), WriteError> {
+        let records = std::mem::take(&mut self.buffer);
+        let mut row_group = self.writer.next_row_group()?;
+
+        for key in Record::EXPECTED_KEYS {
+            let Some(mut column) = row_group.next_column()? else {
+                return Err(WriteError::UnexpectedError(format!(
+                    "missing column {key} in schema"
+                )));
+            };
+
+            let field = |f: fn(&Record) -> u64| records.iter().map(f);
+
+            match key {
+                RecordKey::TxId => write_int64(&mut column, field(Record::tx_id))?,
+                RecordKey::TxType => write_string(
+                    &mut column,
+                    records.iter().map(|record| record.tx_type().to_string()),
+                )?,
+                RecordKey::FromUserId => write_int64(&mut column, field(Record::from_user_id))?,
+                RecordKey::ToUserId => write_int64(&mut column, field(Record::to_user_id))?,
+                RecordKey::Amount => write_int64(&mut column, field(Record::narrow_amount))?,
+                RecordKey::Timestamp => write_int64(&mut column, field(Record::timestamp))?,
+                RecordKey::Status => write_string(
+                    &mut column,
+                    records.iter().map(|record| record.status().to_string()),
+                )?,
+                RecordKey::Description => write_string(
+                    &mut column,
+                    records
+                        .iter()
+                        .map(|record| record.description().to_string()),
+                )?,
+                RecordKey::Currency => unreachable!("only required fields are written"),
+            }
+
+            column.close()?;
+        }
+
+        row_group.close()?;
+
+        Ok(())
+    }
+}
+
+/// Записать значения столбца целых чисел (с сохранением двоичного представления).
+fn write_int64(
+    column: &mut SerializedColumnWriter<'_>,
+    values: impl Iterator<Item = u64>,
+) -> Result<(), ParquetError> {
+    let values = values.map(|value| value as i64).collect::<Vec<_>>();
+    column
+        .typed::<Int64Type>()
+        .write_batch(&values, None, None)?;
+
+    Ok(())
+}
+
+/// Записать значения строкового столбца.
+fn write_string(
+    column: &mut SerializedColumnWriter<'_>,
+    values: impl Iterator<Item = String>,
+) -> Result<(), ParquetError> {
+    let values = values
+        .map(|value| ByteArray::from(value.into_bytes()))
+        .collect::<Vec<_>>();
+    column
+        .typed::<ByteArrayType>()
+        .write_batch(&values, None, None)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let records = crate::tests::get_data_to_write();
+
+        let data = YPBankParquet {
+            records: records.clone(),
+        };
+        let buffer = data.write_to(vec![]).unwrap();
+
+        assert!(buffer.starts_with(b"PAR1"));
+        assert_eq!(
+            YPBankParquet::read_from(&mut buffer.as_slice())
+                .unwrap()
+                .records,
+            records
+        );
+    }
+
+    #[test]
+    fn test_roundtrip_several_row_groups() {
+        let record = crate::tests::get_data_to_write().remove(0);
+        let count = ParquetWriter::<Vec<u8>>::ROW_GROUP_SIZE + 10;
+
+        let mut writer = YPBankParquet::writer_to(vec![]).unwrap();
+        for _ in 0..count {
+            writer.write_record(&record).unwrap();
+        }
+        let buffer = writer.finish().unwrap();
+
+        let result = YPBankParquet::read_from(&mut buffer.as_slice()).unwrap();
+
+        assert_eq!(result.records.len(), count);
+        assert!(result.records.iter().all(|r| *r == record));
+    }
+
+    #[test]
+    fn test_write_wide_amount() {
+        let record = crate::tests::get_wide_data_to_write().remove(0);
+
+        let mut writer = YPBankParquet::writer_to(vec![]).unwrap();
+
+        assert!(matches!(
+            writer.write_record(&record).unwrap_err(),
+            WriteError::SchemaVersionRequired {
+                required: SchemaVersion::V2,
+                used: SchemaVersion::V1,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_empty() {
+        let buffer = YPBankParquet { records: vec![] }.write_to(vec![]).unwrap();
+
+        assert!(
+            YPBankParquet::read_from(&mut buffer.as_slice())
+                .unwrap()
+                .records
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_read_invalid() {
+        let result = YPBankParquet::read_from(&mut b"TX_ID: 1".as_slice()).unwrap_err();
+
+        assert!(matches!(result, ReadError::Parquet(_)));
+    }
+}
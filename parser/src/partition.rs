@@ -0,0 +1,414 @@
+//! Модуль записи данных о транзакциях в несколько назначений (разделов) по ключу записи.
+
+use super::errors::{FormatError, WriteError};
+use super::record::{Record, TxType};
+use super::writer::RecordWriter;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io::Write;
+
+/// Количество миллисекунд в сутках.
+const MS_PER_DAY: u64 = 86_400_000;
+
+/// Способ определения раздела записи о транзакции.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartitionBy {
+    /// По дням (UTC) метки времени записи: ключ раздела `YYYY-MM-DD`.
+    Day,
+
+    /// По месяцам (UTC) метки времени записи: ключ раздела `YYYY-MM`.
+    Month,
+
+    /// По остатку от деления идентификатора пользователя на указанное количество групп:
+    /// ключ раздела `user-N`.
+    ///
+    /// Используется идентификатор отправителя, а для депозитов, не имеющих отправителя, —
+    /// идентификатор получателя.
+    UserBucket(u64),
+}
+
+impl PartitionBy {
+    /// Ключ раздела, в который попадает запись.
+    pub fn key(&self, record: &Record) -> String {
+        match self {
+            Self::Day => {
+                let (year, month, day) = date_from_timestamp(record.timestamp());
+                format!("{year:04}-{month:02}-{day:02}")
+            }
+            Self::Month => {
+                let (year, month, _) = date_from_timestamp(record.timestamp());
+                format!("{year:04}-{month:02}")
+            }
+            Self::UserBucket(buckets) => {
+                let user_id = match record.tx_type() {
+                    TxType::Deposit => record.to_user_id(),
+                    _ => record.from_user_id(),
+                };
+                let width = (buckets - 1).max(1).ilog10() as usize + 1;
+
+                format!("user-{:0width$}", user_id % buckets)
+            }
+        }
+    }
+}
+
+/// Реализация трейта [`TryFrom<&str>`] для [`PartitionBy`].
+impl TryFrom<&str> for PartitionBy {
+    /// Ошибка определения способа разбиения на разделы.
+    type Error = FormatError;
+
+    /// Реализация метода [`TryFrom<&str>::try_from`] для [`PartitionBy`].
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "day" => Ok(Self::Day),
+            "month" => Ok(Self::Month),
+            _ => match s.strip_prefix("user:").map(str::parse::<u64>) {
+                Some(Ok(buckets)) if buckets > 0 => Ok(Self::UserBucket(buckets)),
+                _ => Err(FormatError::UnsupportedPartitioning(s.to_string())),
+            },
+        }
+    }
+}
+
+/// Получить дату (год, месяц, день) по Unix epoch timestamp в миллисекундах.
+pub(crate) fn date_from_timestamp(timestamp: u64) -> (u64, u64, u64) {
+    // Преобразование количества дней от начала эпохи в дату григорианского календаря,
+    // в котором год условно начинается 1 марта.
+    let days = timestamp / MS_PER_DAY + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+
+    (year, month, day)
+}
+
+/// Получить Unix epoch timestamp в миллисекундах начала указанной даты (UTC).
+pub(crate) fn timestamp_from_date(year: u64, month: u64, day: u64) -> u64 {
+    // Преобразование, обратное `date_from_timestamp`.
+    let year = year - u64::from(month <= 2);
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let month_from_march = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    (era * 146_097 + day_of_era - 719_468) * MS_PER_DAY
+}
+
+/// Функция определения ключа раздела записи.
+type PartitionKeyFn<'a, K> = Box<dyn FnMut(&Record) -> K + 'a>;
+
+/// Функция открытия назначения записи нового раздела.
+type OpenPartitionFn<'a, K, W> = Box<dyn FnMut(&K) -> Result<RecordWriter<W>, WriteError> + 'a>;
+
+/// Потоковая запись данных о транзакциях в несколько назначений (разделов) по ключу записи,
+/// например, по месяцу метки времени (см. [`PartitionBy`]).
+///
+/// Назначение раздела открывается при записи в него первой записи, поэтому каждый раздел
+/// содержит собственные заголовок и завершение формата. Для корректного завершения разделов
+/// необходимо вызвать [`PartitionedWriter::finish`].
+pub struct PartitionedWriter<'a, K, W: Write> {
+    /// Функция определения ключа раздела записи.
+    key: PartitionKeyFn<'a, K>,
+
+    /// Функция открытия назначения записи нового раздела.
+    open: OpenPartitionFn<'a, K, W>,
+
+    /// Разделы в порядке их открытия (назначение отсутствует у уже завершенного раздела).
+    partitions: Vec<(K, Option<RecordWriter<W>>)>,
+
+    /// Индексы разделов по их ключам.
+    index: HashMap<K, usize>,
+
+    /// Количество уже записанных записей.
+    count: usize,
+}
+
+impl<'a, K: Clone + Eq + Hash, W: Write> PartitionedWriter<'a, K, W> {
+    /// Создание объекта записи по разделам с указанными функциями определения ключа раздела
+    /// записи и открытия назначения записи нового раздела.
+    pub fn new(
+        key: impl FnMut(&Record) -> K + 'a,
+        open: impl FnMut(&K) -> Result<RecordWriter<W>, WriteError> + 'a,
+    ) -> Self {
+        Self {
+            key: Box::new(key),
+            open: Box::new(open),
+            partitions: vec![],
+            index: HashMap::new(),
+            count: 0,
+        }
+    }
+
+    /// Записать очередную запись о транзакции в ее раздел, открыв его при необходимости.
+    ///
+    /// Запись в уже завершенный раздел (см. [`PartitionedWriter::finish_partition`])
+    /// не производится, а возвращается ошибка.
+    pub fn write_record(&mut self, record: &Record) -> Result<(), WriteError> {
+        let key = (self.key)(record);
+
+        let index = match self.index.get(&key) {
+            Some(&index) => index,
+            None => {
+                let writer = (self.open)(&key)?;
+
+                self.partitions.push((key.clone(), Some(writer)));
+                self.index.insert(key, self.partitions.len() - 1);
+
+                self.partitions.len() - 1
+            }
+        };
+
+        let writer = self.partitions[index].1.as_mut().ok_or_else(|| {
+            WriteError::UnexpectedError(format!(
+                "transaction {} belongs to an already finished partition",
+                record.tx_id()
+            ))
+        })?;
+
+        writer.write_record(record)?;
+        self.count += 1;
+
+        Ok(())
+    }
+
+    /// Завершить запись раздела с указанным ключом и вернуть его назначение.
+    ///
+    /// Возвращает `None`, если раздел не открывался или уже завершен.
+    pub fn finish_partition(&mut self, key: &K) -> Result<Option<W>, WriteError> {
+        let Some(&index) = self.index.get(key) else {
+            return Ok(None);
+        };
+
+        self.partitions[index]
+            .1
+            .take()
+            .map(RecordWriter::finish)
+            .transpose()
+    }
+
+    /// Ключи открытых разделов, в том числе уже завершенных, в порядке их открытия.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.partitions.iter().map(|(key, _)| key)
+    }
+
+    /// Количество открытых разделов, в том числе уже завершенных.
+    pub fn len(&self) -> usize {
+        self.partitions.len()
+    }
+
+    /// Признак отсутствия открытых разделов.
+    pub fn is_empty(&self) -> bool {
+        self.partitions.is_empty()
+    }
+
+    /// Количество уже записанных записей во всех разделах.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Завершить запись всех еще не завершенных разделов и вернуть их ключи и назначения
+    /// в порядке открытия разделов.
+    pub fn finish(self) -> Result<Vec<(K, W)>, WriteError> {
+        self.partitions
+            .into_iter()
+            .filter_map(|(key, writer)| writer.map(|writer| (key, writer)))
+            .map(|(key, writer)| Ok((key, writer.finish()?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Status, YPBankImpl};
+    use rstest::rstest;
+
+    fn record(tx_type: TxType, from_user_id: u64, to_user_id: u64, timestamp: u64) -> Record {
+        Record::new(
+            1,
+            tx_type,
+            from_user_id,
+            to_user_id,
+            100,
+            timestamp,
+            Status::Success,
+            "Test".to_string(),
+        )
+    }
+
+    #[rstest]
+    #[case(PartitionBy::Day, 0, "1970-01-01")]
+    #[case(PartitionBy::Day, 1633036800000, "2021-09-30")]
+    #[case(PartitionBy::Day, 1709164800000, "2024-02-29")]
+    #[case(PartitionBy::Month, 1633036800000, "2021-09")]
+    #[case(PartitionBy::Month, 1704067199999, "2023-12")]
+    #[case(PartitionBy::Month, 1704067200000, "2024-01")]
+    fn test_key_by_date(
+        #[case] partition_by: PartitionBy,
+        #[case] timestamp: u64,
+        #[case] expected: &str,
+    ) {
+        let record = record(TxType::Transfer, 1, 2, timestamp);
+
+        assert_eq!(partition_by.key(&record), expected);
+    }
+
+    #[rstest]
+    #[case((1970, 1, 1), 0)]
+    #[case((2021, 9, 30), 1632960000000)]
+    #[case((2024, 2, 29), 1709164800000)]
+    #[case((2024, 3, 1), 1709251200000)]
+    fn test_timestamp_from_date(#[case] date: (u64, u64, u64), #[case] timestamp: u64) {
+        assert_eq!(timestamp_from_date(date.0, date.1, date.2), timestamp);
+        assert_eq!(date_from_timestamp(timestamp), date);
+    }
+
+    #[rstest]
+    #[case(TxType::Transfer, 1, "user-0")]
+    #[case(TxType::Transfer, 10, "user-7")]
+    #[case(TxType::Deposit, 10, "user-4")]
+    #[case(TxType::Transfer, 16, "user-01")]
+    #[case(TxType::Withdrawal, 100, "user-17")]
+    fn test_key_by_user_bucket(
+        #[case] tx_type: TxType,
+        #[case] buckets: u64,
+        #[case] expected: &str,
+    ) {
+        let record = record(tx_type, 17, 4, 0);
+
+        assert_eq!(PartitionBy::UserBucket(buckets).key(&record), expected);
+    }
+
+    #[rstest]
+    #[case("day", PartitionBy::Day)]
+    #[case("month", PartitionBy::Month)]
+    #[case("user:16", PartitionBy::UserBucket(16))]
+    fn test_try_from(#[case] value: &str, #[case] expected: PartitionBy) {
+        assert_eq!(PartitionBy::try_from(value).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case("year")]
+    #[case("user:0")]
+    #[case("user:x")]
+    fn test_try_from_unsupported(#[case] value: &str) {
+        assert_eq!(
+            PartitionBy::try_from(value).unwrap_err().to_string(),
+            format!("Unsupported partitioning: {value}")
+        );
+    }
+
+    #[test]
+    fn test_write_partitions() {
+        let format = YPBankImpl::Csv;
+        let records = [
+            record(TxType::Transfer, 1, 2, 1633036800000),
+            record(TxType::Transfer, 1, 2, 1635724800000),
+            record(TxType::Transfer, 1, 2, 1633040000000),
+        ];
+
+        let mut opened = vec![];
+        let mut writer = PartitionedWriter::new(
+            |record| PartitionBy::Month.key(record),
+            |key: &String| {
+                opened.push(key.clone());
+                Ok(format.writer_to(vec![]))
+            },
+        );
+
+        for record in &records {
+            writer.write_record(record).unwrap();
+        }
+
+        assert_eq!(writer.count(), 3);
+        assert_eq!(writer.len(), 2);
+        assert_eq!(writer.keys().collect::<Vec<_>>(), ["2021-09", "2021-11"]);
+
+        let partitions = writer.finish().unwrap();
+
+        assert_eq!(opened, ["2021-09", "2021-11"]);
+        assert_eq!(partitions.len(), 2);
+        assert_eq!(
+            format.read_from(&mut partitions[0].1.as_slice()).unwrap(),
+            [records[0].clone(), records[2].clone()]
+        );
+        assert_eq!(
+            format.read_from(&mut partitions[1].1.as_slice()).unwrap(),
+            [records[1].clone()]
+        );
+    }
+
+    #[test]
+    fn test_finish_partition() {
+        let format = YPBankImpl::Jsonl;
+        let records = [
+            record(TxType::Transfer, 1, 2, 0),
+            record(TxType::Transfer, 2, 2, 0),
+            record(TxType::Transfer, 1, 2, 0),
+        ];
+
+        let mut writer = PartitionedWriter::new(
+            |record| record.from_user_id(),
+            |_: &u64| Ok(format.writer_to(vec![])),
+        );
+
+        writer.write_record(&records[0]).unwrap();
+
+        let finished = writer.finish_partition(&1).unwrap().unwrap();
+
+        assert_eq!(format.read_from(&mut finished.as_slice()).unwrap().len(), 1);
+        assert!(writer.finish_partition(&1).unwrap().is_none());
+        assert!(writer.finish_partition(&3).unwrap().is_none());
+
+        writer.write_record(&records[1]).unwrap();
+
+        assert_eq!(
+            writer.write_record(&records[2]).unwrap_err().to_string(),
+            "Unexpected error: transaction 1 belongs to an already finished partition"
+        );
+
+        let partitions = writer.finish().unwrap();
+
+        assert_eq!(partitions.len(), 1);
+        assert_eq!(partitions[0].0, 2);
+    }
+
+    #[test]
+    fn test_no_records() {
+        let writer = PartitionedWriter::new(
+            |record| record.tx_id(),
+            |_: &u64| Ok(YPBankImpl::Csv.writer_to(vec![])),
+        );
+
+        assert!(writer.is_empty());
+        assert!(writer.finish().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_open_error() {
+        let mut writer = PartitionedWriter::<u64, Vec<u8>>::new(
+            |record| record.tx_id(),
+            |_| Err(WriteError::Io(std::io::Error::other("denied"))),
+        );
+
+        assert!(
+            writer
+                .write_record(&record(TxType::Deposit, 0, 1, 0))
+                .is_err()
+        );
+        assert!(writer.is_empty());
+        assert_eq!(writer.count(), 0);
+    }
+}
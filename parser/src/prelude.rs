@@ -0,0 +1,23 @@
+//! Наиболее часто используемые типы и трейты крейта для импорта одной строкой:
+//!
+//! ```
+//! use parser::prelude::*;
+//!
+//! let record = Record::builder()
+//!     .tx_id(1)
+//!     .tx_type(TxType::Deposit)
+//!     .from_user_id(0)
+//!     .to_user_id(2)
+//!     .amount(100)
+//!     .timestamp(1_700_000_000_000)
+//!     .status(Status::Success)
+//!     .build()
+//!     .unwrap();
+//!
+//! let mut buffer = vec![];
+//! YPBankImpl::Csv.write_to(vec![record], &mut buffer).unwrap();
+//! ```
+
+pub use crate::errors::{ReadError, WriteError};
+pub use crate::record::{Currency, Record, RecordBuilder, RecordKey, Status, TxType};
+pub use crate::{Filter, RecordIter, RecordWriter, YPBank, YPBankImpl};
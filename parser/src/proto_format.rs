@@ -0,0 +1,420 @@
+//! Модуль чтения и записи данных о транзакциях в формате Protocol Buffers.
+//!
+//! Поддержка включается признаком крейта `protobuf`. Схема сообщения описана в файле
+//! `proto/ypbank.proto`.
+
+use super::YPBank;
+use super::errors::{ReadError, WriteError};
+use super::iter::{RecordIter, recover_nothing};
+use super::options::ParseContext;
+use super::record::errors::{ParseRecordFromProtoError, ParseValueError};
+use super::record::{Record, RecordKey, Status, TxType};
+use super::schema::SchemaVersion;
+use super::writer::{RecordWriter, finish_nothing};
+use prost::Message;
+use std::io::{self, BufRead, BufWriter, Read, Write};
+
+/// Сообщение `ypbank.Transaction` (см. `proto/ypbank.proto`).
+///
+/// Поля-перечисления хранятся как `int32`, что совместимо с `enum` на уровне кодирования.
+#[derive(Clone, PartialEq, Message)]
+struct Transaction {
+    #[prost(uint64, tag = "1")]
+    tx_id: u64,
+
+    #[prost(int32, tag = "2")]
+    tx_type: i32,
+
+    #[prost(uint64, tag = "3")]
+    from_user_id: u64,
+
+    #[prost(uint64, tag = "4")]
+    to_user_id: u64,
+
+    #[prost(uint64, tag = "5")]
+    amount: u64,
+
+    #[prost(uint64, tag = "6")]
+    timestamp: u64,
+
+    #[prost(int32, tag = "7")]
+    status: i32,
+
+    #[prost(string, tag = "8")]
+    description: String,
+
+    #[prost(bytes = "vec", tag = "9")]
+    wide_amount: Vec<u8>,
+}
+
+/// Реализация трейта [`TryFrom<&Record>`] для [`Transaction`].
+impl TryFrom<&Record> for Transaction {
+    /// Ошибка преобразования типа или состояния транзакции, не имеющего кода.
+    type Error = io::Error;
+
+    /// Реализация метода [`TryFrom<&Record>::try_from`] для [`Transaction`].
+    fn try_from(record: &Record) -> Result<Self, Self::Error> {
+        let uncoded = |key: RecordKey, value: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{key} `{value}` cannot be written in the Protocol Buffers format"),
+            )
+        };
+
+        Ok(Self {
+            tx_id: record.tx_id(),
+            tx_type: u8::try_from(record.tx_type())
+                .map_err(|_| uncoded(RecordKey::TxType, record.tx_type().to_string()))?
+                .into(),
+            from_user_id: record.from_user_id(),
+            to_user_id: record.to_user_id(),
+            amount: record.narrow_amount(),
+            timestamp: record.timestamp(),
+            status: u8::try_from(record.status())
+                .map_err(|_| uncoded(RecordKey::Status, record.status().to_string()))?
+                .into(),
+            description: record.description().to_string(),
+            wide_amount: if record.schema_version() > SchemaVersion::V1 {
+                record.wide_amount().to_be_bytes().to_vec()
+            } else {
+                vec![]
+            },
+        })
+    }
+}
+
+/// Реализация трейта [`TryFrom<Transaction>`] для [`Record`].
+impl TryFrom<Transaction> for Record {
+    /// Ошибка парсинга сообщения.
+    type Error = ParseRecordFromProtoError;
+
+    /// Реализация метода [`TryFrom<Transaction>::try_from`] для [`Record`].
+    fn try_from(message: Transaction) -> Result<Self, Self::Error> {
+        Self::from_message(message, &ParseContext::default())
+    }
+}
+
+impl Record {
+    /// Преобразовать сообщение Protocol Buffers в запись о транзакции с указанными параметрами
+    /// разбора.
+    fn from_message(
+        message: Transaction,
+        context: &ParseContext,
+    ) -> Result<Self, ParseRecordFromProtoError> {
+        let tx_type = u8::try_from(message.tx_type)
+            .ok()
+            .and_then(|value| TxType::from_code(value, context.lenient).ok())
+            .ok_or_else(|| ParseValueError::InvalidValue {
+                value: message.tx_type.to_string(),
+                description: "unknown TX_TYPE enum value".to_string(),
+            })?;
+
+        let status = u8::try_from(message.status)
+            .ok()
+            .and_then(|value| Status::from_code(value, context.lenient).ok())
+            .ok_or_else(|| ParseValueError::InvalidValue {
+                value: message.status.to_string(),
+                description: "unknown STATUS enum value".to_string(),
+            })?;
+
+        let amount = if message.wide_amount.is_empty() {
+            i128::from(message.amount)
+        } else {
+            let bytes = <[u8; 16]>::try_from(message.wide_amount.as_slice()).map_err(|_| {
+                ParseValueError::InvalidValue {
+                    value: format!("{:?}", message.wide_amount),
+                    description: "wide AMOUNT must have 16 bytes".to_string(),
+                }
+            })?;
+
+            i128::from_be_bytes(bytes)
+        };
+
+        Ok(Record::new_wide(
+            message.tx_id,
+            tx_type,
+            message.from_user_id,
+            message.to_user_id,
+            amount,
+            message.timestamp,
+            status,
+            message.description,
+        ))
+    }
+}
+
+/// Записи о транзакциях в формате Protocol Buffers.
+///
+/// Каждая запись кодируется сообщением `ypbank.Transaction`, которому предшествует его размер
+/// в байтах в кодировке varint — так же, как при записи `writeDelimitedTo` и чтении
+/// `parseDelimitedFrom` в стандартных реализациях Protocol Buffers.
+#[derive(Debug)]
+pub struct YPBankProto {
+    /// Записи о банковских операциях.
+    pub records: Vec<Record>,
+}
+
+impl YPBankProto {
+    /// Максимальный допустимый размер одного сообщения в байтах.
+    const MAX_MESSAGE_SIZE: u64 = 16 * 1024 * 1024;
+
+    /// Считать размер очередного сообщения в кодировке varint.
+    ///
+    /// Возвращает `None`, если источник закончился до начала сообщения.
+    fn read_message_size<B: BufRead>(
+        reader: &mut B,
+    ) -> Result<Option<u64>, ParseRecordFromProtoError> {
+        let mut size = 0u64;
+
+        for shift in (0..64).step_by(7) {
+            let byte = match reader.fill_buf()?.first() {
+                Some(&byte) => byte,
+                None if shift == 0 => return Ok(None),
+                None => {
+                    return Err(ParseRecordFromProtoError::UnexpectedError(
+                        "unexpected end of data in message size".to_string(),
+                    ));
+                }
+            };
+            reader.consume(1);
+
+            size |= u64::from(byte & 0x7f) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(Some(size));
+            }
+        }
+
+        Err(ParseRecordFromProtoError::InvalidMessageSize(size))
+    }
+
+    /// Считать очередную запись о банковской операции в формате Protocol Buffers.
+    ///
+    /// Сообщение считывается из источника целиком до его декодирования, поэтому после ошибки
+    /// в содержимом сообщения чтение может быть продолжено со следующего сообщения.
+    fn read_record<B: BufRead>(
+        reader: &mut B,
+        _index: usize,
+        context: &ParseContext,
+    ) -> Result<Option<Record>, ReadError> {
+        let Some(size) = Self::read_message_size(reader)? else {
+            return Ok(None);
+        };
+
+        if size > Self::MAX_MESSAGE_SIZE {
+            return Err(ParseRecordFromProtoError::InvalidMessageSize(size).into());
+        }
+
+        let mut buffer = vec![0u8; size as usize];
+        reader
+            .read_exact(&mut buffer)
+            .map_err(ParseRecordFromProtoError::from)?;
+
+        let message =
+            Transaction::decode(buffer.as_slice()).map_err(ParseRecordFromProtoError::from)?;
+
+        Ok(Some(Record::from_message(message, context)?))
+    }
+
+    /// Записать очередную запись о банковской операции в формате Protocol Buffers.
+    fn write_record<W: Write>(
+        writer: &mut BufWriter<W>,
+        record: &Record,
+        _index: usize,
+    ) -> Result<(), WriteError> {
+        writer.write_all(&Transaction::try_from(record)?.encode_length_delimited_to_vec())?;
+
+        Ok(())
+    }
+}
+
+impl YPBank for YPBankProto {
+    /// Считанные записи о банковских операциях.
+    fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    /// Изменяемый список записей о банковских операциях.
+    fn records_mut(&mut self) -> &mut Vec<Record> {
+        &mut self.records
+    }
+
+    /// Создание набора из указанных записей о банковских операциях.
+    fn from_records(records: Vec<Record>) -> Self {
+        Self { records }
+    }
+
+    /// Считать данные о банковских операциях в формате Protocol Buffers.
+    fn read_from<R: Read>(r: &mut R) -> Result<Self, ReadError> {
+        let records = Self::iter_from(r).collect::<Result<_, _>>()?;
+
+        Ok(Self { records })
+    }
+
+    /// Последовательно считывать данные о банковских операциях в формате Protocol Buffers.
+    fn iter_from<R: Read>(r: R) -> RecordIter<R> {
+        RecordIter::new_binary(r, Self::read_record).with_recovery(recover_nothing)
+    }
+
+    /// Записать данные о банковских операциях в формате Protocol Buffers.
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<(), WriteError> {
+        let mut writer = Self::writer_to(w);
+
+        for record in &self.records {
+            writer.write_record(record)?;
+        }
+
+        writer.finish()?;
+
+        Ok(())
+    }
+
+    /// Последовательно записывать данные о банковских операциях в формате Protocol Buffers.
+    fn writer_to<W: Write>(w: W) -> RecordWriter<W> {
+        RecordWriter::new(w, Self::write_record, finish_nothing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OnError, ParseOptions};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_roundtrip() {
+        let records = crate::tests::get_data_to_write();
+
+        let data = YPBankProto {
+            records: records.clone(),
+        };
+        let mut cursor = Cursor::new(vec![]);
+        data.write_to(&mut cursor).unwrap();
+
+        let mut cursor = Cursor::new(cursor.into_inner());
+        let data = YPBankProto::read_from(&mut cursor).unwrap();
+
+        assert_eq!(data.records, records);
+    }
+
+    #[test]
+    fn test_roundtrip_wide_amounts() {
+        let records = crate::tests::get_wide_data_to_write();
+
+        let mut writer = YPBankProto::writer_to(vec![]).schema_version(SchemaVersion::V2);
+        for record in &records {
+            writer.write_record(record).unwrap();
+        }
+        let buffer = writer.finish().unwrap();
+
+        let data = YPBankProto::read_from(&mut buffer.as_slice()).unwrap();
+
+        assert_eq!(data.records, records);
+    }
+
+    #[test]
+    fn test_read_from_proto_invalid_wide_amount() {
+        let message = Transaction {
+            wide_amount: vec![0xFF; 3],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Record::try_from(message).unwrap_err().to_string(),
+            "Invalid value: [255, 255, 255] (wide AMOUNT must have 16 bytes)"
+        );
+    }
+
+    #[test]
+    fn test_write_to_proto_wire_format() {
+        let data = YPBankProto {
+            records: vec![Record::new(
+                1,
+                TxType::Transfer,
+                2,
+                3,
+                100,
+                1000,
+                Status::Success,
+                "a".to_string(),
+            )],
+        };
+        let mut cursor = Cursor::new(vec![]);
+        data.write_to(&mut cursor).unwrap();
+
+        // STATUS равен значению по умолчанию и потому не кодируется.
+        assert_eq!(
+            cursor.into_inner(),
+            [
+                16, 0x08, 1, 0x10, 1, 0x18, 2, 0x20, 3, 0x28, 100, 0x30, 0xe8, 0x07, 0x42, 1, b'a'
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_to_proto_empty_record() {
+        let data = YPBankProto { records: vec![] };
+        let mut cursor = Cursor::new(vec![]);
+        data.write_to(&mut cursor).unwrap();
+        assert_eq!(cursor.into_inner(), b"");
+    }
+
+    #[test]
+    fn test_read_from_proto_invalid_tx_type() {
+        let mut cursor = Cursor::new([4u8, 0x08, 1, 0x10, 7]);
+        let result = YPBankProto::read_from(&mut cursor);
+
+        let result = result.unwrap_err();
+        assert!(matches!(
+            result.inner(),
+            ReadError::FromProto(ParseRecordFromProtoError::InvalidValue(_))
+        ));
+        assert_eq!(
+            result.to_string(),
+            "Protobuf format parsing error: Invalid value: 7 (unknown TX_TYPE enum value) (at record 1, byte 0)"
+        );
+    }
+
+    #[test]
+    fn test_read_from_proto_truncated() {
+        let mut cursor = Cursor::new([10u8, 0x08, 1]);
+        let result = YPBankProto::read_from(&mut cursor);
+
+        assert!(matches!(
+            result.unwrap_err().inner(),
+            ReadError::FromProto(ParseRecordFromProtoError::UnexpectedError(_))
+        ));
+    }
+
+    #[test]
+    fn test_read_from_proto_too_large_message() {
+        let mut cursor = Cursor::new([0xff, 0xff, 0xff, 0xff, 0x0f]);
+        let result = YPBankProto::read_from(&mut cursor);
+
+        assert!(matches!(
+            result.unwrap_err().inner(),
+            ReadError::FromProto(ParseRecordFromProtoError::InvalidMessageSize(4294967295))
+        ));
+    }
+
+    #[test]
+    fn test_read_from_proto_continue_after_invalid_message() {
+        let mut data = vec![2u8, 0x10, 9];
+        YPBankProto {
+            records: crate::tests::get_data_to_write(),
+        }
+        .write_to(&mut data)
+        .unwrap();
+
+        let report = YPBankProto::iter_from(Cursor::new(data))
+            .read_report(ParseOptions {
+                on_error: OnError::Collect,
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert_eq!(report.records, crate::tests::get_data_to_write());
+        assert_eq!(report.skipped, vec![1]);
+        assert_eq!(report.errors.len(), 1);
+    }
+}
@@ -0,0 +1,323 @@
+//! Модуль сверки записей о транзакциях из двух источников.
+
+use super::YPBank;
+use super::record::{FieldDiff, Record, RecordComparator};
+use std::collections::HashMap;
+
+/// Допуски нечеткого сопоставления записей с различающимися TX_ID.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// Допустимая разница сумм транзакций.
+    pub amount_tolerance: u64,
+
+    /// Допустимая разница меток времени в миллисекундах.
+    pub timestamp_tolerance: u64,
+}
+
+impl FuzzyMatch {
+    /// Проверить, можно ли считать записи одной и той же транзакцией.
+    fn matches(&self, left: &Record, right: &Record) -> bool {
+        left.wide_amount().abs_diff(right.wide_amount()) <= u128::from(self.amount_tolerance)
+            && left.timestamp().abs_diff(right.timestamp()) <= self.timestamp_tolerance
+    }
+}
+
+/// Параметры сверки записей о транзакциях.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReconcileOptions {
+    /// Допуски нечеткого сопоставления записей, оставшихся без пары после сопоставления
+    /// по TX_ID. Если не указаны, нечеткое сопоставление не производится.
+    pub fuzzy: Option<FuzzyMatch>,
+
+    /// Сравнение записей с одинаковым TX_ID: исключаемые поля и допуски сумм и меток времени.
+    pub comparator: RecordComparator,
+}
+
+/// Способ сопоставления пары записей.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchKind {
+    /// Записи с одинаковым TX_ID и совпадающими полями.
+    TxId,
+
+    /// Записи с различающимися TX_ID, совпадающие по сумме и метке времени с учетом допусков.
+    Fuzzy,
+}
+
+/// Пара сопоставленных записей.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchedPair {
+    /// Порядковый номер записи в первом источнике (начиная с 1).
+    pub left: usize,
+
+    /// Порядковый номер записи во втором источнике (начиная с 1).
+    pub right: usize,
+
+    /// Способ сопоставления.
+    pub kind: MatchKind,
+}
+
+/// Пара записей с одинаковым TX_ID, различающихся значениями полей.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMismatch {
+    /// Идентификатор транзакции.
+    pub tx_id: u64,
+
+    /// Порядковый номер записи в первом источнике (начиная с 1).
+    pub left: usize,
+
+    /// Порядковый номер записи во втором источнике (начиная с 1).
+    pub right: usize,
+
+    /// Различия значений полей.
+    pub fields: Vec<FieldDiff>,
+}
+
+/// Результат сверки записей о транзакциях из двух источников.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Reconciliation {
+    /// Сопоставленные записи.
+    pub matched: Vec<MatchedPair>,
+
+    /// Записи с одинаковым TX_ID, различающиеся значениями полей.
+    pub mismatched: Vec<FieldMismatch>,
+
+    /// Порядковые номера записей, присутствующих только в первом источнике.
+    pub only_in_left: Vec<usize>,
+
+    /// Порядковые номера записей, присутствующих только во втором источнике.
+    pub only_in_right: Vec<usize>,
+}
+
+impl Reconciliation {
+    /// Признак полного совпадения источников.
+    pub fn is_clean(&self) -> bool {
+        self.mismatched.is_empty() && self.only_in_left.is_empty() && self.only_in_right.is_empty()
+    }
+}
+
+/// Сверить записи о транзакциях из двух источников.
+///
+/// Записи сопоставляются по TX_ID (при повторяющихся TX_ID — в порядке следования), а оставшиеся
+/// без пары записи — нечетко по сумме и метке времени, если это указано в параметрах.
+pub fn reconcile_records(
+    left: &[Record],
+    right: &[Record],
+    options: &ReconcileOptions,
+) -> Reconciliation {
+    let mut result = Reconciliation::default();
+
+    let mut right_by_tx_id: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (index, record) in right.iter().enumerate().rev() {
+        right_by_tx_id
+            .entry(record.tx_id())
+            .or_default()
+            .push(index);
+    }
+
+    let mut right_matched = vec![false; right.len()];
+    let mut left_unmatched = vec![];
+
+    for (left_index, left_record) in left.iter().enumerate() {
+        let Some(right_index) = right_by_tx_id
+            .get_mut(&left_record.tx_id())
+            .and_then(Vec::pop)
+        else {
+            left_unmatched.push(left_index);
+            continue;
+        };
+
+        right_matched[right_index] = true;
+
+        let fields = options.comparator.diff(left_record, &right[right_index]);
+
+        if fields.is_empty() {
+            result.matched.push(MatchedPair {
+                left: left_index + 1,
+                right: right_index + 1,
+                kind: MatchKind::TxId,
+            });
+        } else {
+            result.mismatched.push(FieldMismatch {
+                tx_id: left_record.tx_id(),
+                left: left_index + 1,
+                right: right_index + 1,
+                fields,
+            });
+        }
+    }
+
+    for left_index in left_unmatched {
+        let right_index = options.fuzzy.and_then(|fuzzy| {
+            (0..right.len()).find(|&index| {
+                !right_matched[index] && fuzzy.matches(&left[left_index], &right[index])
+            })
+        });
+
+        match right_index {
+            Some(right_index) => {
+                right_matched[right_index] = true;
+                result.matched.push(MatchedPair {
+                    left: left_index + 1,
+                    right: right_index + 1,
+                    kind: MatchKind::Fuzzy,
+                });
+            }
+            None => result.only_in_left.push(left_index + 1),
+        }
+    }
+
+    result.only_in_right = (0..right.len())
+        .filter(|&index| !right_matched[index])
+        .map(|index| index + 1)
+        .collect();
+
+    result
+}
+
+/// Сверить записи о транзакциях из двух источников (см. [`reconcile_records`]).
+pub fn reconcile<L: YPBank, R: YPBank>(
+    left: &L,
+    right: &R,
+    options: &ReconcileOptions,
+) -> Reconciliation {
+    reconcile_records(left.records(), right.records(), options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::{RecordKey, RecordMask, Status, TxType};
+    use crate::{YPBankCsv, YPBankText};
+
+    fn get_record(tx_id: u64, amount: i128, timestamp: u64) -> Record {
+        Record::new_wide(
+            tx_id,
+            TxType::Transfer,
+            1,
+            2,
+            amount,
+            timestamp,
+            Status::Success,
+            "".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_reconcile_identical() {
+        let records = crate::tests::get_data_to_write();
+
+        let left = YPBankText {
+            records: records.clone(),
+        };
+        let mut reversed = records.clone();
+        reversed.reverse();
+        let right = YPBankCsv { records: reversed };
+
+        let result = reconcile(&left, &right, &ReconcileOptions::default());
+
+        assert!(result.is_clean());
+        assert_eq!(
+            result
+                .matched
+                .iter()
+                .map(|p| (p.left, p.right))
+                .collect::<Vec<_>>(),
+            vec![(1, 3), (2, 2), (3, 1)]
+        );
+    }
+
+    #[test]
+    fn test_reconcile_by_tx_id() {
+        let left = vec![
+            get_record(1, 100, 1000),
+            get_record(2, 200, 2000),
+            get_record(3, 300, 3000),
+        ];
+        let right = vec![
+            get_record(4, 400, 4000),
+            get_record(2, 250, 2000),
+            get_record(1, 100, 1000),
+        ];
+
+        let result = reconcile_records(&left, &right, &ReconcileOptions::default());
+
+        assert_eq!(
+            result,
+            Reconciliation {
+                matched: vec![MatchedPair {
+                    left: 1,
+                    right: 3,
+                    kind: MatchKind::TxId
+                }],
+                mismatched: vec![FieldMismatch {
+                    tx_id: 2,
+                    left: 2,
+                    right: 2,
+                    fields: vec![FieldDiff {
+                        key: RecordKey::Amount,
+                        expected: "200".to_string(),
+                        actual: "250".to_string(),
+                    }]
+                }],
+                only_in_left: vec![3],
+                only_in_right: vec![1],
+            }
+        );
+        assert!(!result.is_clean());
+    }
+
+    #[test]
+    fn test_reconcile_fuzzy() {
+        let left = vec![get_record(1, 100, 1000), get_record(2, 500, 5000)];
+        let right = vec![get_record(11, 101, 1500), get_record(12, 500, 9000)];
+
+        let options = ReconcileOptions {
+            fuzzy: Some(FuzzyMatch {
+                amount_tolerance: 1,
+                timestamp_tolerance: 500,
+            }),
+            ..Default::default()
+        };
+        let result = reconcile_records(&left, &right, &options);
+
+        assert_eq!(
+            result.matched,
+            vec![MatchedPair {
+                left: 1,
+                right: 1,
+                kind: MatchKind::Fuzzy
+            }]
+        );
+        assert_eq!(result.only_in_left, vec![2]);
+        assert_eq!(result.only_in_right, vec![2]);
+    }
+
+    #[test]
+    fn test_reconcile_duplicate_tx_id() {
+        let left = vec![get_record(1, 100, 1000), get_record(1, 200, 2000)];
+        let right = vec![get_record(1, 100, 1000)];
+
+        let result = reconcile_records(&left, &right, &ReconcileOptions::default());
+
+        assert_eq!(result.matched.len(), 1);
+        assert_eq!(result.only_in_left, vec![2]);
+        assert!(result.only_in_right.is_empty());
+    }
+
+    #[test]
+    fn test_reconcile_masked() {
+        let left = vec![get_record(1, 100, 1000), get_record(2, 200, 2000)];
+        let right = vec![get_record(1, 100, 1500), get_record(2, 250, 2500)];
+
+        let options = ReconcileOptions {
+            comparator: RecordMask::new().ignore(RecordKey::Timestamp).into(),
+            ..Default::default()
+        };
+        let result = reconcile_records(&left, &right, &options);
+
+        assert_eq!(result.matched.len(), 1);
+        assert_eq!(result.mismatched.len(), 1);
+        assert_eq!(result.mismatched[0].fields[0].key, RecordKey::Amount);
+        assert_eq!(result.mismatched[0].fields.len(), 1);
+    }
+}
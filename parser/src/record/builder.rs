@@ -0,0 +1,229 @@
+//! Модуль построения записи о транзакции с проверкой ее смысловой корректности.
+
+use super::Record;
+use super::currency::Currency;
+use super::errors::BuildRecordError;
+use super::keys::RecordKey;
+use super::status::Status;
+use super::tx_type::TxType;
+use std::collections::HashMap;
+
+/// Макрос установки заданного поля строителя записи о транзакции.
+macro_rules! builder_setter {
+    ($field:ident, $type:ty) => {
+        pub fn $field(mut self, $field: $type) -> Self {
+            self.$field = Some($field);
+            self
+        }
+    };
+}
+
+/// Строитель записи о транзакции.
+///
+/// В отличие от [`Record::new`], проверяет смысловую корректность записи при вызове
+/// [`RecordBuilder::build`]: отсутствие отправителя у депозита, отсутствие получателя
+/// у обналичивания, ненулевую сумму и допустимую метку времени.
+#[derive(Debug, Clone, Default)]
+pub struct RecordBuilder {
+    /// Идентификатор транзакции.
+    tx_id: Option<u64>,
+
+    /// Тип транзакции.
+    tx_type: Option<TxType>,
+
+    /// Идентификатор отправителя.
+    from_user_id: Option<u64>,
+
+    /// Идентификатор получателя.
+    to_user_id: Option<u64>,
+
+    /// Сумма транзакции.
+    amount: Option<i128>,
+
+    /// Unix epoch timestamp в миллисекундах.
+    timestamp: Option<u64>,
+
+    /// Состояние транзакции.
+    status: Option<Status>,
+
+    /// Произвольное текстовое описание.
+    description: Option<String>,
+
+    /// Необязательная валюта транзакции.
+    currency: Option<Currency>,
+
+    /// Дополнительные поля записи.
+    metadata: HashMap<String, String>,
+}
+
+impl RecordBuilder {
+    /// Минимальная допустимая метка времени (2000-01-01T00:00:00Z).
+    pub const MIN_TIMESTAMP: u64 = 946_684_800_000;
+
+    /// Максимальная допустимая метка времени (2100-01-01T00:00:00Z).
+    pub const MAX_TIMESTAMP: u64 = 4_102_444_800_000;
+
+    /// Создание строителя записи без заполненных полей.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    builder_setter!(tx_id, u64);
+    builder_setter!(tx_type, TxType);
+    builder_setter!(from_user_id, u64);
+    builder_setter!(to_user_id, u64);
+    builder_setter!(timestamp, u64);
+    builder_setter!(status, Status);
+    builder_setter!(currency, Currency);
+
+    /// Установить сумму транзакции.
+    pub fn amount(mut self, amount: u64) -> Self {
+        self.amount = Some(i128::from(amount));
+        self
+    }
+
+    /// Установить широкую сумму транзакции (см. [`Record::wide_amount`]).
+    pub fn wide_amount(mut self, amount: i128) -> Self {
+        self.amount = Some(amount);
+        self
+    }
+
+    /// Установить описание транзакции (по умолчанию пустое).
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Добавить дополнительное поле записи с указанным ключом.
+    pub fn metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    /// Построить запись о транзакции, проверив заполненность обязательных полей
+    /// и смысловую корректность их значений.
+    pub fn build(self) -> Result<Record, BuildRecordError> {
+        let mut record = Record::new_wide(
+            self.tx_id
+                .ok_or(BuildRecordError::MissingField(RecordKey::TxId))?,
+            self.tx_type
+                .ok_or(BuildRecordError::MissingField(RecordKey::TxType))?,
+            self.from_user_id
+                .ok_or(BuildRecordError::MissingField(RecordKey::FromUserId))?,
+            self.to_user_id
+                .ok_or(BuildRecordError::MissingField(RecordKey::ToUserId))?,
+            self.amount
+                .ok_or(BuildRecordError::MissingField(RecordKey::Amount))?,
+            self.timestamp
+                .ok_or(BuildRecordError::MissingField(RecordKey::Timestamp))?,
+            self.status
+                .ok_or(BuildRecordError::MissingField(RecordKey::Status))?,
+            self.description.unwrap_or_default(),
+        );
+        record.set_currency(self.currency);
+        *record.metadata_mut() = self.metadata;
+
+        Self::validate(&record)?;
+
+        Ok(record)
+    }
+
+    /// Проверить смысловую корректность записи о транзакции.
+    fn validate(record: &Record) -> Result<(), BuildRecordError> {
+        match record.tx_type() {
+            TxType::Deposit if record.from_user_id() != 0 => {
+                return Err(BuildRecordError::DepositWithSender(record.from_user_id()));
+            }
+            TxType::Withdrawal if record.to_user_id() != 0 => {
+                return Err(BuildRecordError::WithdrawalWithReceiver(
+                    record.to_user_id(),
+                ));
+            }
+            _ => {}
+        }
+
+        if record.wide_amount() == 0 {
+            return Err(BuildRecordError::ZeroAmount);
+        }
+
+        if !(Self::MIN_TIMESTAMP..Self::MAX_TIMESTAMP).contains(&record.timestamp()) {
+            return Err(BuildRecordError::TimestampOutOfRange(record.timestamp()));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn get_builder(tx_type: TxType, from_user_id: u64, to_user_id: u64) -> RecordBuilder {
+        RecordBuilder::new()
+            .tx_id(1)
+            .tx_type(tx_type)
+            .from_user_id(from_user_id)
+            .to_user_id(to_user_id)
+            .amount(100)
+            .timestamp(1633036800000)
+            .status(Status::Success)
+    }
+
+    #[rstest]
+    #[case(TxType::Deposit, 0, 2)]
+    #[case(TxType::Transfer, 1, 2)]
+    #[case(TxType::Withdrawal, 1, 0)]
+    fn test_build_record(#[case] tx_type: TxType, #[case] from: u64, #[case] to: u64) {
+        let record = get_builder(tx_type.clone(), from, to)
+            .description("Terminal deposit")
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            record,
+            Record::new(
+                1,
+                tx_type,
+                from,
+                to,
+                100,
+                1633036800000,
+                Status::Success,
+                "Terminal deposit".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_build_record_missing_field() {
+        let result = RecordBuilder::new().tx_id(1).build().unwrap_err();
+
+        assert_eq!(result, BuildRecordError::MissingField(RecordKey::TxType));
+        assert_eq!(result.to_string(), "Missing field: TX_TYPE");
+    }
+
+    #[rstest]
+    #[case(
+        get_builder(TxType::Deposit, 1, 2),
+        "DEPOSIT must have FROM_USER_ID equal to 0, got 1"
+    )]
+    #[case(
+        get_builder(TxType::Withdrawal, 1, 2),
+        "WITHDRAWAL must have TO_USER_ID equal to 0, got 2"
+    )]
+    #[case(get_builder(TxType::Transfer, 1, 2).amount(0), "AMOUNT must be non-zero")]
+    #[case(
+        get_builder(TxType::Transfer, 1, 2).timestamp(0),
+        "TIMESTAMP is out of range: 0"
+    )]
+    #[case(
+        get_builder(TxType::Transfer, 1, 2).timestamp(RecordBuilder::MAX_TIMESTAMP),
+        "TIMESTAMP is out of range: 4102444800000"
+    )]
+    fn test_build_invalid_record(#[case] builder: RecordBuilder, #[case] error: &str) {
+        let result = builder.build().unwrap_err();
+
+        assert_eq!(result.to_string(), error);
+    }
+}
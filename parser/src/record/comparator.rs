@@ -0,0 +1,166 @@
+//! Модуль настраиваемого сравнения записей о транзакциях.
+
+use super::Record;
+use super::diff::FieldDiff;
+use super::keys::RecordKey;
+use super::mask::RecordMask;
+
+/// Настраиваемое сравнение записей о транзакциях по полям.
+///
+/// Поля, исключенные маской, не сравниваются, а суммы и метки времени, различающиеся не более
+/// чем на допуск, считаются совпадающими — например, чтобы расхождение часов систем, выгрузивших
+/// записи, не считалось различием. По умолчанию сравниваются все поля без допусков.
+///
+/// ```
+/// use parser::{Record, RecordComparator, Status, TxType};
+///
+/// let record = Record::new(1, TxType::Deposit, 0, 2, 100, 1_700_000_000_000, Status::Success, String::new());
+/// let mut skewed = record.clone();
+/// skewed.set_timestamp(1_700_000_000_250);
+///
+/// assert!(!RecordComparator::new().matches(&record, &skewed));
+/// assert!(RecordComparator::new().timestamp_tolerance(500).matches(&record, &skewed));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RecordComparator {
+    /// Поля, исключаемые из сравнения.
+    pub mask: RecordMask,
+
+    /// Допустимая разница сумм транзакций.
+    pub amount_tolerance: u64,
+
+    /// Допустимая разница меток времени в миллисекундах.
+    pub timestamp_tolerance: u64,
+}
+
+impl RecordComparator {
+    /// Создание сравнения всех полей без допусков.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Исключить поля маски из сравнения.
+    pub fn mask(self, mask: RecordMask) -> Self {
+        Self { mask, ..self }
+    }
+
+    /// Считать совпадающими суммы, различающиеся не более чем на `tolerance`.
+    pub fn amount_tolerance(self, tolerance: u64) -> Self {
+        Self {
+            amount_tolerance: tolerance,
+            ..self
+        }
+    }
+
+    /// Считать совпадающими метки времени, различающиеся не более чем на `tolerance` миллисекунд.
+    pub fn timestamp_tolerance(self, tolerance: u64) -> Self {
+        Self {
+            timestamp_tolerance: tolerance,
+            ..self
+        }
+    }
+
+    /// Признак совпадения значений поля с указанным ключом с учетом допусков.
+    pub fn field_matches(&self, expected: &Record, actual: &Record, key: RecordKey) -> bool {
+        match key {
+            RecordKey::Amount => {
+                expected.wide_amount().abs_diff(actual.wide_amount())
+                    <= u128::from(self.amount_tolerance)
+            }
+            RecordKey::Timestamp => {
+                expected.timestamp().abs_diff(actual.timestamp()) <= self.timestamp_tolerance
+            }
+            key => expected.compare_by(actual, key).is_eq(),
+        }
+    }
+
+    /// Признак совпадения записей по всем сравниваемым полям.
+    pub fn matches(&self, expected: &Record, actual: &Record) -> bool {
+        self.mask
+            .compared_keys()
+            .all(|key| self.field_matches(expected, actual, key))
+    }
+
+    /// Сравнить запись (ожидаемую) с другой записью (фактической) по всем сравниваемым полям.
+    ///
+    /// Возвращает различия в порядке следования полей в записи.
+    pub fn diff(&self, expected: &Record, actual: &Record) -> Vec<FieldDiff> {
+        self.mask
+            .compared_keys()
+            .filter(|&key| !self.field_matches(expected, actual, key))
+            .map(|key| FieldDiff {
+                key,
+                expected: expected.field_to_string(key),
+                actual: actual.field_to_string(key),
+            })
+            .collect()
+    }
+}
+
+/// Реализация трейта [`From<RecordMask>`] для [`RecordComparator`].
+impl From<RecordMask> for RecordComparator {
+    /// Сравнение полей, не исключенных маской, без допусков.
+    fn from(mask: RecordMask) -> Self {
+        Self::new().mask(mask)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(0, 0, vec![RecordKey::Amount, RecordKey::Timestamp])]
+    #[case(5, 0, vec![RecordKey::Timestamp])]
+    #[case(4, 1000, vec![RecordKey::Amount])]
+    #[case(5, 1000, vec![])]
+    fn test_diff_with_tolerances(
+        #[case] amount_tolerance: u64,
+        #[case] timestamp_tolerance: u64,
+        #[case] expected: Vec<RecordKey>,
+    ) {
+        let record = crate::tests::get_data_to_write().remove(0);
+        let mut other = record.clone();
+        other
+            .set_amount(record.amount().unwrap() - 5)
+            .set_timestamp(record.timestamp() + 1000);
+
+        let comparator = RecordComparator::new()
+            .amount_tolerance(amount_tolerance)
+            .timestamp_tolerance(timestamp_tolerance);
+
+        assert_eq!(
+            comparator
+                .diff(&record, &other)
+                .into_iter()
+                .map(|diff| diff.key)
+                .collect::<Vec<_>>(),
+            expected
+        );
+        assert_eq!(comparator.matches(&record, &other), expected.is_empty());
+        assert_eq!(comparator.matches(&other, &record), expected.is_empty());
+    }
+
+    #[test]
+    fn test_diff_masked_with_tolerance() {
+        let record = crate::tests::get_data_to_write().remove(0);
+        let mut other = record.clone();
+        other
+            .set_timestamp(record.timestamp() + 10)
+            .set_description("Other".to_string());
+
+        let comparator = RecordComparator::from(RecordMask::new().ignore(RecordKey::Description))
+            .timestamp_tolerance(10);
+
+        assert!(comparator.diff(&record, &other).is_empty());
+        assert_eq!(
+            comparator.timestamp_tolerance(9).diff(&record, &other),
+            vec![FieldDiff {
+                key: RecordKey::Timestamp,
+                expected: record.timestamp().to_string(),
+                actual: other.timestamp().to_string(),
+            }]
+        );
+    }
+}
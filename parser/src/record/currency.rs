@@ -0,0 +1,111 @@
+//! Модуль описания валюты транзакции.
+
+use super::errors::ParseCurrencyError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Валюта транзакции — трехбуквенный код ISO 4217 в верхнем регистре (например, `USD`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Currency([u8; 3]);
+
+impl Currency {
+    /// Получить код валюты.
+    pub fn as_str(&self) -> &str {
+        // Код состоит только из заглавных латинских букв, проверенных при создании.
+        std::str::from_utf8(&self.0).unwrap_or_default()
+    }
+
+    /// Получить код валюты в виде байтов ASCII.
+    pub(crate) fn as_bytes(&self) -> &[u8; 3] {
+        &self.0
+    }
+}
+
+/// Реализация трейта [`fmt::Display`] для [`Currency`].
+impl fmt::Display for Currency {
+    /// Реализация метода [`fmt::Display::fmt`] для [`Currency`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Реализация трейта [`TryFrom<&[u8]>`] для [`Currency`].
+impl TryFrom<&[u8]> for Currency {
+    /// Ошибка парсинга валюты.
+    type Error = ParseCurrencyError;
+
+    /// Реализация метода [`TryFrom<&[u8]>::try_from`] для [`Currency`].
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        match <[u8; 3]>::try_from(value) {
+            Ok(code) if code.iter().all(u8::is_ascii_uppercase) => Ok(Self(code)),
+            _ => Err(ParseCurrencyError::InvalidCurrency(
+                String::from_utf8_lossy(value).to_string(),
+            )),
+        }
+    }
+}
+
+/// Реализация трейта [`TryFrom<&str>`] для [`Currency`].
+impl TryFrom<&str> for Currency {
+    /// Ошибка парсинга валюты.
+    type Error = ParseCurrencyError;
+
+    /// Реализация метода [`TryFrom<&str>::try_from`] для [`Currency`].
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_bytes())
+    }
+}
+
+/// Реализация трейта [`TryFrom<String>`] для [`Currency`].
+impl TryFrom<String> for Currency {
+    /// Ошибка парсинга валюты.
+    type Error = ParseCurrencyError;
+
+    /// Реализация метода [`TryFrom<String>::try_from`] для [`Currency`].
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_str())
+    }
+}
+
+/// Реализация трейта [`From<Currency>`] для [`String`].
+impl From<Currency> for String {
+    /// Реализация метода [`From<Currency>::from`] для [`String`].
+    fn from(value: Currency) -> Self {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Currency::try_from("USD").unwrap().to_string(), "USD");
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("usd")]
+    #[case("US")]
+    #[case("USDT")]
+    #[case("U$D")]
+    #[case("ЕВР")]
+    fn test_try_from_invalid(#[case] value: &str) {
+        assert_eq!(
+            Currency::try_from(value).unwrap_err().to_string(),
+            format!("Invalid CURRENCY: {value}")
+        );
+    }
+
+    #[test]
+    fn test_try_from_bytes() {
+        assert_eq!(
+            Currency::try_from(&b"EUR"[..]).unwrap(),
+            Currency::try_from("EUR").unwrap()
+        );
+        assert!(Currency::try_from(&[0xff, 0x41, 0x41][..]).is_err());
+    }
+}
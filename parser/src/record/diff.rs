@@ -0,0 +1,121 @@
+//! Модуль сравнения записей о транзакциях по отдельным полям.
+
+use super::Record;
+use super::comparator::RecordComparator;
+use super::keys::RecordKey;
+use super::mask::RecordMask;
+use std::fmt;
+
+/// Различие значений поля двух записей о транзакциях.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDiff {
+    /// Ключ различающегося поля.
+    pub key: RecordKey,
+
+    /// Значение поля в ожидаемой записи.
+    pub expected: String,
+
+    /// Значение поля в фактической записи.
+    pub actual: String,
+}
+
+/// Реализация трейта [`fmt::Display`] для [`FieldDiff`].
+impl fmt::Display for FieldDiff {
+    /// Реализация метода [`fmt::Display::fmt`] для [`FieldDiff`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: expected {}, actual {}",
+            self.key, self.expected, self.actual
+        )
+    }
+}
+
+impl Record {
+    /// Получить значение поля с указанным ключом в текстовом представлении.
+    ///
+    /// Описание заключается в кавычки, отсутствующая валюта представляется пустой строкой.
+    pub fn field_to_string(&self, key: RecordKey) -> String {
+        match key {
+            RecordKey::TxId => self.tx_id.to_string(),
+            RecordKey::TxType => self.tx_type.to_string(),
+            RecordKey::FromUserId => self.from_user_id.to_string(),
+            RecordKey::ToUserId => self.to_user_id.to_string(),
+            RecordKey::Amount => self.amount.to_string(),
+            RecordKey::Timestamp => self.timestamp.to_string(),
+            RecordKey::Status => self.status.to_string(),
+            RecordKey::Description => format!("\"{}\"", self.description),
+            RecordKey::Currency => self.currency.map(String::from).unwrap_or_default(),
+        }
+    }
+
+    /// Сравнить запись (ожидаемую) с другой записью (фактической) по всем полям.
+    ///
+    /// Возвращает различия в порядке следования полей в записи.
+    pub fn diff(&self, other: &Self) -> Vec<FieldDiff> {
+        self.diff_masked(other, &RecordMask::default())
+    }
+
+    /// Сравнить запись (ожидаемую) с другой записью (фактической) по всем полям,
+    /// кроме исключенных маской.
+    ///
+    /// Для сравнения с допусками сумм и меток времени см. [`RecordComparator`].
+    pub fn diff_masked(&self, other: &Self, mask: &RecordMask) -> Vec<FieldDiff> {
+        RecordComparator::from(*mask).diff(self, other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::{Status, TxType};
+
+    #[test]
+    fn test_diff() {
+        let records = crate::tests::get_data_to_write();
+
+        assert!(records[0].diff(&records[0]).is_empty());
+
+        let mut other = records[0].clone();
+        other
+            .set_tx_type(TxType::Transfer)
+            .set_amount(1)
+            .set_status(Status::Pending)
+            .set_description("Other".to_string());
+
+        let diff = records[0].diff(&other);
+
+        assert_eq!(
+            diff.iter().map(|d| d.to_string()).collect::<Vec<_>>(),
+            vec![
+                "TX_TYPE: expected DEPOSIT, actual TRANSFER",
+                "AMOUNT: expected 10000, actual 1",
+                "STATUS: expected SUCCESS, actual PENDING",
+                "DESCRIPTION: expected \"Terminal deposit\", actual \"Other\"",
+            ]
+        );
+        assert_eq!(diff[1].key, RecordKey::Amount);
+    }
+
+    #[test]
+    fn test_diff_masked() {
+        let records = crate::tests::get_data_to_write();
+
+        let mut other = records[0].clone();
+        other.set_timestamp(1).set_description("Other".to_string());
+
+        let mask = RecordMask::new()
+            .ignore(RecordKey::Timestamp)
+            .ignore(RecordKey::Description);
+
+        assert!(records[0].diff_masked(&other, &mask).is_empty());
+        assert_eq!(
+            records[0]
+                .diff_masked(&other, &RecordMask::new().ignore(RecordKey::Timestamp))
+                .iter()
+                .map(|d| d.key)
+                .collect::<Vec<_>>(),
+            vec![RecordKey::Description]
+        );
+    }
+}
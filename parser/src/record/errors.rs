@@ -1,6 +1,7 @@
 //! Модуль описания ошибок парсинга ключа поля, значения поля (включая возможный тип, состояние),
 //! а также записи целиком о транзакции.
 
+use super::keys::RecordKey;
 use thiserror::Error;
 
 /// Ошибка парсинга типа транзакции.
@@ -19,6 +20,14 @@ pub enum ParseStatusError {
     InvalidStatus(String),
 }
 
+/// Ошибка парсинга валюты транзакции.
+#[derive(Debug, Error)]
+pub enum ParseCurrencyError {
+    /// Некорректное значение поля валюты транзакции CURRENCY.
+    #[error("Invalid CURRENCY: {0}")]
+    InvalidCurrency(String),
+}
+
 /// Ошибка парсинга ключа поля транзакции.
 #[derive(Debug, Error, PartialEq)]
 pub enum ParseKeyError {
@@ -67,6 +76,19 @@ impl From<ParseStatusError> for ParseValueError {
     }
 }
 
+/// Реализация трейта [`From<ParseCurrencyError>`] для [`ParseValueError`].
+impl From<ParseCurrencyError> for ParseValueError {
+    /// Реализация метода [`From<ParseCurrencyError>::from`] для [`ParseValueError`].
+    fn from(e: ParseCurrencyError) -> Self {
+        match e {
+            ParseCurrencyError::InvalidCurrency(ref value) => ParseValueError::InvalidValue {
+                value: value.clone(),
+                description: e.to_string(),
+            },
+        }
+    }
+}
+
 /// Ошибка парсинга текстового представления операции.
 #[derive(Debug, Error, PartialEq)]
 pub enum ParseRecordFromTxtError {
@@ -86,6 +108,10 @@ pub enum ParseRecordFromTxtError {
     #[error("{0}")]
     InvalidValue(ParseValueError),
 
+    /// Размер строк записи превышает ограничение (см. [`crate::SizeLimits::max_record_size`]).
+    #[error("Record exceeds the limit of {0} bytes")]
+    RecordTooLarge(u32),
+
     /// Неожиданная ошибка парсинга данных.
     #[error("Unexpected error: {0}")]
     UnexpectedError(String),
@@ -122,10 +148,30 @@ pub enum ParseRecordFromCsvError {
     #[error("Invalid count of columns: {0}")]
     InvalidCountOfColumns(usize),
 
+    /// Нарушение правил использования кавычек в полях записи (RFC 4180).
+    #[error("Invalid quoting: {0}")]
+    InvalidQuoting(String),
+
+    /// Столбец заголовка не соответствует ни одному полю записи.
+    #[error("Unknown column: {0}")]
+    UnknownColumn(String),
+
+    /// Поле записи (или дополнительное поле) соответствует нескольким столбцам заголовка.
+    #[error("Duplicate column for {0}")]
+    DuplicateColumn(String),
+
+    /// Поле записи не соответствует ни одному столбцу заголовка.
+    #[error("Missing column for {0}")]
+    MissingColumn(RecordKey),
+
     /// Некорректное значение поля записи.
     #[error("{0}")]
     InvalidValue(ParseValueError),
 
+    /// Размер строк записи превышает ограничение (см. [`crate::SizeLimits::max_record_size`]).
+    #[error("Record exceeds the limit of {0} bytes")]
+    RecordTooLarge(u32),
+
     /// Неожиданная ошибка парсинга данных.
     #[error("Unexpected error: {0}")]
     UnexpectedError(String),
@@ -158,6 +204,64 @@ pub enum ParseRecordFromBinError {
     #[error("Invalid record size: {0}")]
     InvalidRecordSize(u32),
 
+    /// Неподдерживаемая версия заголовка контейнера.
+    #[error("Unsupported container version: {0}")]
+    UnsupportedContainerVersion(u32),
+
+    /// Размер тела записи меньше суммы размеров фиксированных полей и описания транзакции.
+    #[error(
+        "Record size {record_size} is less than the size of fixed fields ({fixed_len}) plus DESCRIPTION_SIZE ({description_len})"
+    )]
+    RecordSizeMismatch {
+        /// Размер тела записи, указанный в источнике.
+        record_size: u32,
+
+        /// Суммарный размер фиксированных полей тела записи.
+        fixed_len: u32,
+
+        /// Длина описания, указанная в источнике.
+        description_len: u32,
+    },
+
+    /// Размер тела записи превышает ограничение (см. [`crate::SizeLimits::max_record_size`]).
+    #[error("Record size {size} exceeds the limit of {max} bytes")]
+    RecordTooLarge {
+        /// Размер тела записи, указанный в источнике.
+        size: u32,
+
+        /// Максимальный допустимый размер тела записи.
+        max: u32,
+    },
+
+    /// Длина описания транзакции превышает ограничение
+    /// (см. [`crate::SizeLimits::max_description_len`]).
+    #[error("Description length {len} exceeds the limit of {max} bytes")]
+    DescriptionTooLong {
+        /// Длина описания, указанная в источнике.
+        len: u32,
+
+        /// Максимальная допустимая длина описания.
+        max: u32,
+    },
+
+    /// Источник оборвался посреди записи.
+    ///
+    /// В отличие от остальных ошибок, указывает на неполные (например, не до конца скачанные),
+    /// а не на поврежденные данные.
+    #[error(
+        "Unexpected end of data in record {record_index}: expected {expected} bytes, got {got} (the data is likely truncated)"
+    )]
+    UnexpectedEof {
+        /// Порядковый номер записи (начиная с 1).
+        record_index: usize,
+
+        /// Ожидаемый размер записи в байтах (только заголовка, если он считан не полностью).
+        expected: usize,
+
+        /// Количество считанных байтов записи.
+        got: usize,
+    },
+
     /// Некорректное значение поля записи.
     #[error(transparent)]
     InvalidValue(#[from] ParseValueError),
@@ -174,3 +278,87 @@ impl From<std::io::Error> for ParseRecordFromBinError {
         Self::UnexpectedError(e.to_string())
     }
 }
+
+/// Ошибка парсинга представления операции в формате с фиксированной шириной полей.
+#[derive(Debug, Error, PartialEq)]
+pub enum ParseRecordFromFixedWidthError {
+    /// Длина строки не соответствует разметке полей.
+    #[error("Invalid line length: expected {expected} characters, got {actual}")]
+    InvalidLineLength {
+        /// Длина строки согласно разметке полей.
+        expected: usize,
+
+        /// Фактическая длина строки.
+        actual: usize,
+    },
+
+    /// Некорректное значение поля записи.
+    #[error(transparent)]
+    InvalidValue(#[from] ParseValueError),
+
+    /// Неожиданная ошибка парсинга данных.
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+}
+
+/// Реализация трейта [`From<std::io::Error>`] для [`ParseRecordFromFixedWidthError`].
+impl From<std::io::Error> for ParseRecordFromFixedWidthError {
+    /// Реализация метода [`From<std::io::Error>::from`] для [`ParseRecordFromFixedWidthError`].
+    fn from(e: std::io::Error) -> Self {
+        Self::UnexpectedError(e.to_string())
+    }
+}
+
+/// Ошибка парсинга представления операции в формате Protocol Buffers.
+#[cfg(feature = "protobuf")]
+#[derive(Debug, Error, PartialEq)]
+pub enum ParseRecordFromProtoError {
+    /// Некорректное значение размера сообщения.
+    #[error("Invalid message size: {0}")]
+    InvalidMessageSize(u64),
+
+    /// Ошибка декодирования сообщения.
+    #[error("Invalid message: {0}")]
+    Decode(#[from] prost::DecodeError),
+
+    /// Некорректное значение поля записи.
+    #[error(transparent)]
+    InvalidValue(#[from] ParseValueError),
+
+    /// Неожиданная ошибка парсинга данных.
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+}
+
+/// Реализация трейта [`From<std::io::Error>`] для [`ParseRecordFromProtoError`].
+#[cfg(feature = "protobuf")]
+impl From<std::io::Error> for ParseRecordFromProtoError {
+    /// Реализация метода [`From<std::io::Error>::from`] для [`ParseRecordFromProtoError`].
+    fn from(e: std::io::Error) -> Self {
+        Self::UnexpectedError(e.to_string())
+    }
+}
+
+/// Ошибка построения записи о транзакции при помощи [`super::RecordBuilder`].
+#[derive(Debug, Error, PartialEq)]
+pub enum BuildRecordError {
+    /// Не заполнено обязательное поле записи.
+    #[error("Missing field: {0}")]
+    MissingField(RecordKey),
+
+    /// У депозита указан отправитель.
+    #[error("DEPOSIT must have FROM_USER_ID equal to 0, got {0}")]
+    DepositWithSender(u64),
+
+    /// У обналичивания указан получатель.
+    #[error("WITHDRAWAL must have TO_USER_ID equal to 0, got {0}")]
+    WithdrawalWithReceiver(u64),
+
+    /// Нулевая сумма транзакции.
+    #[error("AMOUNT must be non-zero")]
+    ZeroAmount,
+
+    /// Метка времени вне допустимого диапазона.
+    #[error("TIMESTAMP is out of range: {0}")]
+    TimestampOutOfRange(u64),
+}
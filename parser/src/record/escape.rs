@@ -0,0 +1,108 @@
+//! Модуль экранирования описания транзакции в текстовом формате.
+//!
+//! Описание в текстовом формате записывается в одну строку между символами `"`, поэтому
+//! кавычки, обратная косая черта и управляющие символы переводов строк и табуляции внутри
+//! описания экранируются обратной косой чертой: `\"`, `\\`, `\n`, `\r`, `\t`.
+
+use super::errors::ParseValueError;
+use std::io::{self, Write};
+
+/// Записать экранированное описание транзакции в текстовом формате в указанное место.
+///
+/// Участки описания, не требующие экранирования, записываются целиком без промежуточного
+/// копирования.
+pub(crate) fn write_escaped_description<W: Write>(w: &mut W, value: &str) -> io::Result<()> {
+    let mut start = 0;
+
+    for (index, c) in value.char_indices() {
+        let escaped: &[u8] = match c {
+            '"' => b"\\\"",
+            '\\' => b"\\\\",
+            '\n' => b"\\n",
+            '\r' => b"\\r",
+            '\t' => b"\\t",
+            _ => continue,
+        };
+
+        w.write_all(&value.as_bytes()[start..index])?;
+        w.write_all(escaped)?;
+        start = index + c.len_utf8();
+    }
+
+    w.write_all(&value.as_bytes()[start..])
+}
+
+/// Восстановить описание транзакции, экранированное при записи в текстовом формате.
+///
+/// Неэкранированные кавычки, неизвестные escape-последовательности и обратная косая черта
+/// в конце описания считаются ошибкой.
+pub(crate) fn unescape_description(value: &str) -> Result<String, ParseValueError> {
+    let invalid = |description: String| ParseValueError::InvalidValue {
+        value: value.to_string(),
+        description,
+    };
+
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('"') => result.push('"'),
+                Some('\\') => result.push('\\'),
+                Some('n') => result.push('\n'),
+                Some('r') => result.push('\r'),
+                Some('t') => result.push('\t'),
+                Some(c) => {
+                    return Err(invalid(format!(
+                        "DESCRIPTION contains unknown escape sequence \\{c}"
+                    )));
+                }
+                None => {
+                    return Err(invalid(
+                        "DESCRIPTION must not end with unescaped symbol \\".to_string(),
+                    ));
+                }
+            },
+            '"' => {
+                return Err(invalid(
+                    "DESCRIPTION must not contain unescaped symbol \"".to_string(),
+                ));
+            }
+            c => result.push(c),
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("Terminal deposit", "Terminal deposit")]
+    #[case("Payment \"42\"", r#"Payment \"42\""#)]
+    #[case("Multi\nline\r\n", r"Multi\nline\r\n")]
+    #[case("C:\\tmp\tdir", r"C:\\tmp\tdir")]
+    #[case("", "")]
+    fn test_escape_roundtrip(#[case] value: &str, #[case] escaped: &str) {
+        let mut result = vec![];
+        write_escaped_description(&mut result, value).unwrap();
+
+        assert_eq!(String::from_utf8(result).unwrap(), escaped);
+        assert_eq!(unescape_description(escaped).unwrap(), value);
+    }
+
+    #[rstest]
+    #[case(r"C:\tmp\x", "DESCRIPTION contains unknown escape sequence \\x")]
+    #[case(r"ends with \", "DESCRIPTION must not end with unescaped symbol \\")]
+    #[case(r#"say "hi""#, "DESCRIPTION must not contain unescaped symbol \"")]
+    fn test_unescape_invalid(#[case] value: &str, #[case] description: &str) {
+        assert_eq!(
+            unescape_description(value).unwrap_err().to_string(),
+            format!("Invalid value: {value} ({description})")
+        );
+    }
+}
@@ -31,6 +31,9 @@ pub enum RecordKey {
 
     /// Произвольное текстовое описание.
     Description,
+
+    /// Необязательная валюта транзакции (код ISO 4217).
+    Currency,
 }
 
 /// Реализация трейта [`fmt::Display`] для [`RecordKey`].
@@ -46,6 +49,7 @@ impl fmt::Display for RecordKey {
             Self::Timestamp => "TIMESTAMP",
             Self::Status => "STATUS",
             Self::Description => "DESCRIPTION",
+            Self::Currency => "CURRENCY",
         };
 
         write!(f, "{s}")
@@ -68,6 +72,7 @@ impl TryFrom<&str> for RecordKey {
             "TIMESTAMP" => Ok(Self::Timestamp),
             "STATUS" => Ok(Self::Status),
             "DESCRIPTION" => Ok(Self::Description),
+            "CURRENCY" => Ok(Self::Currency),
             _ => Err(ParseKeyError::InvalidKey(s.to_string())),
         }
     }
@@ -88,6 +93,7 @@ mod tests {
         assert_eq!(RecordKey::Timestamp.to_string(), "TIMESTAMP");
         assert_eq!(RecordKey::Status.to_string(), "STATUS");
         assert_eq!(RecordKey::Description.to_string(), "DESCRIPTION");
+        assert_eq!(RecordKey::Currency.to_string(), "CURRENCY");
     }
 
     #[test]
@@ -112,6 +118,10 @@ mod tests {
             RecordKey::try_from("DESCRIPTION").unwrap(),
             RecordKey::Description
         );
+        assert_eq!(
+            RecordKey::try_from("CURRENCY").unwrap(),
+            RecordKey::Currency
+        );
 
         assert!(RecordKey::try_from("").is_err_and(|e| e.to_string() == "Invalid key: "));
         assert!(
@@ -0,0 +1,108 @@
+//! Модуль описания набора полей записи о транзакции, исключаемых из сравнения.
+
+use super::Record;
+use super::errors::ParseKeyError;
+use super::keys::RecordKey;
+
+/// Набор полей записи о транзакции, исключаемых из сравнения.
+///
+/// По умолчанию сравниваются все поля.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RecordMask {
+    /// Битовая маска исключаемых полей (бит с номером `key as u8` для каждого ключа).
+    ignored: u16,
+}
+
+impl RecordMask {
+    /// Создание маски, не исключающей ни одного поля.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Исключить поле с указанным ключом из сравнения.
+    pub fn ignore(mut self, key: RecordKey) -> Self {
+        self.ignored |= 1 << key as u8;
+        self
+    }
+
+    /// Признак исключения поля с указанным ключом из сравнения.
+    pub fn is_ignored(&self, key: RecordKey) -> bool {
+        self.ignored & (1 << key as u8) != 0
+    }
+
+    /// Ключи сравниваемых полей в порядке следования полей в записи.
+    pub fn compared_keys(&self) -> impl Iterator<Item = RecordKey> {
+        Record::EXPECTED_KEYS
+            .into_iter()
+            .chain(Record::OPTIONAL_KEYS)
+            .filter(|&key| !self.is_ignored(key))
+    }
+}
+
+/// Реализация трейта [`TryFrom<&str>`] для [`RecordMask`].
+///
+/// Принимает перечисленные через запятую ключи полей без учета регистра,
+/// например `timestamp,description`.
+impl TryFrom<&str> for RecordMask {
+    /// Ошибка парсинга ключа поля записи о транзакции.
+    type Error = ParseKeyError;
+
+    /// Реализация метода [`TryFrom<&str>::try_from`] для [`RecordMask`].
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .try_fold(Self::new(), |mask, key| {
+                Ok(mask.ignore(RecordKey::try_from(key.to_uppercase().as_str())?))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn test_ignore() {
+        let mask = RecordMask::new()
+            .ignore(RecordKey::Timestamp)
+            .ignore(RecordKey::Description);
+
+        assert!(mask.is_ignored(RecordKey::Timestamp));
+        assert!(mask.is_ignored(RecordKey::Description));
+        assert!(!mask.is_ignored(RecordKey::TxId));
+        assert_eq!(
+            mask.compared_keys().collect::<Vec<_>>(),
+            vec![
+                RecordKey::TxId,
+                RecordKey::TxType,
+                RecordKey::FromUserId,
+                RecordKey::ToUserId,
+                RecordKey::Amount,
+                RecordKey::Status,
+                RecordKey::Currency,
+            ]
+        );
+        assert_eq!(RecordMask::new().compared_keys().count(), 9);
+    }
+
+    #[rstest]
+    #[case("", RecordMask::new())]
+    #[case("timestamp", RecordMask::new().ignore(RecordKey::Timestamp))]
+    #[case(
+        "TIMESTAMP, description",
+        RecordMask::new().ignore(RecordKey::Timestamp).ignore(RecordKey::Description)
+    )]
+    #[case("to_user_id,", RecordMask::new().ignore(RecordKey::ToUserId))]
+    fn test_try_from(#[case] s: &str, #[case] expected: RecordMask) {
+        assert_eq!(RecordMask::try_from(s).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_try_from_invalid_key() {
+        let result = RecordMask::try_from("timestamp,date").unwrap_err();
+
+        assert_eq!(result.to_string(), "Invalid key: DATE");
+    }
+}
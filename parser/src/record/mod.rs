@@ -1,25 +1,59 @@
 //! Модуль описания записи о транзакции.
 
-use std::collections::HashSet;
-use std::io::{BufRead, Write};
-
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{self, BufRead, IoSlice, Read, Write};
+
+mod builder;
+mod comparator;
+pub(crate) mod currency;
+mod diff;
 pub(crate) mod errors;
+mod escape;
 pub(crate) mod keys;
+mod mask;
 pub(crate) mod status;
 pub(crate) mod tx_type;
+mod view;
+
+pub use builder::RecordBuilder;
+pub use comparator::RecordComparator;
+pub use currency::Currency;
+pub use diff::FieldDiff;
+pub use mask::RecordMask;
+pub use view::RecordRef;
 
 use errors::{
-    ParseRecordFromBinError, ParseRecordFromCsvError, ParseRecordFromTxtError, ParseStatusError,
-    ParseTxTypeError, ParseValueError,
+    ParseRecordFromBinError, ParseRecordFromCsvError, ParseRecordFromTxtError, ParseValueError,
 };
-use keys::RecordKey;
-use status::Status;
-use tx_type::TxType;
-
-use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use escape::{unescape_description, write_escaped_description};
+pub use keys::RecordKey;
+pub use status::Status;
+pub use tx_type::TxType;
+
+use crate::CsvOptions;
+use crate::DescriptionPool;
+use crate::csv_format::CsvColumn;
+use crate::errors::{RedactError, WriteError};
+use crate::options::{ParseContext, Projection, SizeLimits};
+use crate::schema::SchemaVersion;
+use byteorder::{BigEndian, WriteBytesExt};
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use smol_str::SmolStr;
+
+/// Описание транзакции.
+///
+/// Короткие описания (до 23 байт) хранятся в самом значении без выделения памяти в куче,
+/// а длинные — в общей неизменяемой строке, разделяемой копиями значения (в том числе
+/// одинаковыми описаниями разных записей, см. [`crate::DescriptionPool`]).
+pub type Description = SmolStr;
 
 /// Структура хранения данных записи о транзакции.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub struct Record {
     /// Неотрицательное целое число, идентифицирующее транзакцию.
     tx_id: u64,
@@ -35,8 +69,12 @@ pub struct Record {
     /// (0 для типа транзакции `Withdrawal`).
     to_user_id: u64,
 
-    /// Неотрицательное целое число, представляющее сумму в наименьшей единице валюты.
-    amount: u64,
+    /// Целое число, представляющее сумму в наименьшей единице валюты.
+    ///
+    /// Отрицательные суммы (корректировки) и суммы, превышающие [`u64::MAX`], требуют
+    /// версии схемы [`SchemaVersion::V2`].
+    #[serde(serialize_with = "serialize_amount")]
+    amount: i128,
 
     /// Unix epoch timestamp в миллисекундах.
     timestamp: u64,
@@ -45,7 +83,194 @@ pub struct Record {
     status: Status,
 
     /// Произвольное текстовое описание.
-    description: String,
+    description: Description,
+
+    /// Необязательная валюта транзакции (код ISO 4217).
+    ///
+    /// Записи, считанные из данных без валюты, ее не имеют.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    currency: Option<Currency>,
+
+    /// Дополнительные поля записи (например, специфичные для поставщика данных) по их ключам.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    metadata: HashMap<String, String>,
+}
+
+/// Сериализовать сумму транзакции наименьшим подходящим целочисленным типом.
+///
+/// Так суммы в пределах [`u64`] сериализуются так же, как до появления широких сумм,
+/// а форматы без поддержки 128-битных чисел (например, MessagePack) записывают
+/// широкие суммы в пределах [`i64`] обычными числами.
+fn serialize_amount<S: Serializer>(amount: &i128, serializer: S) -> Result<S::Ok, S::Error> {
+    if let Ok(amount) = u64::try_from(*amount) {
+        serializer.serialize_u64(amount)
+    } else if let Ok(amount) = i64::try_from(*amount) {
+        serializer.serialize_i64(amount)
+    } else {
+        serializer.serialize_i128(*amount)
+    }
+}
+
+/// Имена полей записи о транзакции при сериализации в порядке их объявления.
+const SERDE_FIELDS: &[&str] = &[
+    "TX_ID",
+    "TX_TYPE",
+    "FROM_USER_ID",
+    "TO_USER_ID",
+    "AMOUNT",
+    "TIMESTAMP",
+    "STATUS",
+    "DESCRIPTION",
+    "CURRENCY",
+    "METADATA",
+];
+
+/// Посетитель десериализации записи о транзакции с указанными параметрами разбора
+/// (см. [`Record::deserialize_with`]).
+struct RecordVisitor<'a> {
+    /// Параметры разбора записи.
+    context: &'a ParseContext,
+}
+
+impl RecordVisitor<'_> {
+    /// Разобрать тип транзакции с учетом параметров разбора.
+    fn tx_type<E: de::Error>(&self, value: &str) -> Result<TxType, E> {
+        TxType::from_name(value, self.context.lenient).map_err(E::custom)
+    }
+
+    /// Разобрать состояние транзакции с учетом параметров разбора.
+    fn status<E: de::Error>(&self, value: &str) -> Result<Status, E> {
+        Status::from_name(value, self.context.lenient).map_err(E::custom)
+    }
+}
+
+/// Реализация трейта [`Visitor`] для [`RecordVisitor`].
+impl<'de> Visitor<'de> for RecordVisitor<'_> {
+    /// Десериализуемая запись о транзакции.
+    type Value = Record;
+
+    /// Реализация метода [`Visitor::expecting`] для [`RecordVisitor`].
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("struct Record")
+    }
+
+    /// Реализация метода [`Visitor::visit_seq`] для [`RecordVisitor`].
+    ///
+    /// Поля следуют в порядке объявления, валюта и дополнительные поля необязательны.
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Record, A::Error> {
+        macro_rules! next {
+            ($index:literal) => {
+                seq.next_element()?
+                    .ok_or_else(|| de::Error::invalid_length($index, &self))?
+            };
+        }
+
+        let tx_id = next!(0);
+        let tx_type: String = next!(1);
+        let tx_type = self.tx_type(&tx_type)?;
+        let from_user_id = next!(2);
+        let to_user_id = next!(3);
+        let amount = next!(4);
+        let timestamp = next!(5);
+        let status: String = next!(6);
+        let status = self.status(&status)?;
+        let description = next!(7);
+
+        Ok(Record {
+            tx_id,
+            tx_type,
+            from_user_id,
+            to_user_id,
+            amount,
+            timestamp,
+            status,
+            description,
+            currency: seq.next_element()?.flatten(),
+            metadata: seq.next_element()?.unwrap_or_default(),
+        })
+    }
+
+    /// Реализация метода [`Visitor::visit_map`] для [`RecordVisitor`].
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Record, A::Error> {
+        let mut tx_id = None;
+        let mut tx_type = None;
+        let mut from_user_id = None;
+        let mut to_user_id = None;
+        let mut amount = None;
+        let mut timestamp = None;
+        let mut status = None;
+        let mut description = None;
+        let mut currency = None;
+        let mut metadata = None;
+
+        macro_rules! set {
+            ($field:ident, $name:literal) => {{
+                if $field.is_some() {
+                    return Err(de::Error::duplicate_field($name));
+                }
+                $field = Some(map.next_value()?);
+            }};
+        }
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "TX_ID" => set!(tx_id, "TX_ID"),
+                "TX_TYPE" => {
+                    if tx_type.is_some() {
+                        return Err(de::Error::duplicate_field("TX_TYPE"));
+                    }
+                    tx_type = Some(self.tx_type(&map.next_value::<String>()?)?);
+                }
+                "FROM_USER_ID" => set!(from_user_id, "FROM_USER_ID"),
+                "TO_USER_ID" => set!(to_user_id, "TO_USER_ID"),
+                "AMOUNT" => set!(amount, "AMOUNT"),
+                "TIMESTAMP" => set!(timestamp, "TIMESTAMP"),
+                "STATUS" => {
+                    if status.is_some() {
+                        return Err(de::Error::duplicate_field("STATUS"));
+                    }
+                    status = Some(self.status(&map.next_value::<String>()?)?);
+                }
+                "DESCRIPTION" => set!(description, "DESCRIPTION"),
+                "CURRENCY" => set!(currency, "CURRENCY"),
+                "METADATA" => set!(metadata, "METADATA"),
+                key => return Err(de::Error::unknown_field(key, SERDE_FIELDS)),
+            }
+        }
+
+        Ok(Record {
+            tx_id: tx_id.ok_or_else(|| de::Error::missing_field("TX_ID"))?,
+            tx_type: tx_type.ok_or_else(|| de::Error::missing_field("TX_TYPE"))?,
+            from_user_id: from_user_id.ok_or_else(|| de::Error::missing_field("FROM_USER_ID"))?,
+            to_user_id: to_user_id.ok_or_else(|| de::Error::missing_field("TO_USER_ID"))?,
+            amount: amount.ok_or_else(|| de::Error::missing_field("AMOUNT"))?,
+            timestamp: timestamp.ok_or_else(|| de::Error::missing_field("TIMESTAMP"))?,
+            status: status.ok_or_else(|| de::Error::missing_field("STATUS"))?,
+            description: description.ok_or_else(|| de::Error::missing_field("DESCRIPTION"))?,
+            currency: currency.flatten(),
+            metadata: metadata.unwrap_or_default(),
+        })
+    }
+}
+
+/// Реализация трейта [`Deserialize`] для [`Record`].
+impl<'de> Deserialize<'de> for Record {
+    /// Реализация метода [`Deserialize::deserialize`] для [`Record`].
+    ///
+    /// Неизвестные тип и состояние транзакции приводят к ошибке.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::deserialize_with(deserializer, &ParseContext::default())
+    }
+}
+
+/// Поле записи в CSV формате, полученное при разборе строки таблицы.
+#[derive(Debug, PartialEq)]
+pub(crate) struct CsvField {
+    /// Значение поля без обрамляющих кавычек и с раскрытым экранированием.
+    pub(crate) value: String,
+
+    /// Признак того, что значение поля было заключено в кавычки.
+    quoted: bool,
 }
 
 /// Макрос установки заданного поля записи о транзакции.
@@ -58,6 +283,15 @@ macro_rules! setter {
     };
 }
 
+/// Макрос получения значения заданного поля записи о транзакции.
+macro_rules! getter {
+    ($field:ident, $type:ty) => {
+        pub fn $field(&self) -> $type {
+            self.$field
+        }
+    };
+}
+
 /// Реализаций трейта [`Default`] для [`Record`].
 impl Default for Record {
     /// Реализация метода [`Default::default`] для [`Record`].
@@ -70,7 +304,9 @@ impl Default for Record {
             amount: 0,
             timestamp: 0,
             status: Status::Success,
-            description: "".to_string(),
+            description: Description::default(),
+            currency: None,
+            metadata: HashMap::new(),
         }
     }
 }
@@ -88,7 +324,17 @@ impl Record {
         RecordKey::Description,
     ];
 
+    /// Ключи необязательных полей записи транзакции.
+    pub const OPTIONAL_KEYS: [RecordKey; 1] = [RecordKey::Currency];
+
+    /// Создание строителя записи о транзакции с проверкой ее смысловой корректности.
+    pub fn builder() -> RecordBuilder {
+        RecordBuilder::new()
+    }
+
     /// Создание нового объекта записи о транзакции на основе переданных данных.
+    ///
+    /// Необязательные поля не заполняются.
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         tx_id: u64,
@@ -98,7 +344,34 @@ impl Record {
         amount: u64,
         timestamp: u64,
         status: Status,
-        description: String,
+        description: impl Into<Description>,
+    ) -> Self {
+        Self::new_wide(
+            tx_id,
+            tx_type,
+            from_user_id,
+            to_user_id,
+            i128::from(amount),
+            timestamp,
+            status,
+            description,
+        )
+    }
+
+    /// Создание нового объекта записи о транзакции с широкой суммой
+    /// (см. [`Record::wide_amount`]).
+    ///
+    /// Необязательные поля не заполняются.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_wide(
+        tx_id: u64,
+        tx_type: TxType,
+        from_user_id: u64,
+        to_user_id: u64,
+        amount: i128,
+        timestamp: u64,
+        status: Status,
+        description: impl Into<Description>,
     ) -> Self {
         Self {
             tx_id,
@@ -108,7 +381,9 @@ impl Record {
             amount,
             timestamp,
             status,
-            description,
+            description: description.into(),
+            currency: None,
+            metadata: HashMap::new(),
         }
     }
 
@@ -116,10 +391,202 @@ impl Record {
     setter!(set_tx_type, tx_type, TxType);
     setter!(set_from_user_id, from_user_id, u64);
     setter!(set_to_user_id, to_user_id, u64);
-    setter!(set_amount, amount, u64);
     setter!(set_timestamp, timestamp, u64);
     setter!(set_status, status, Status);
-    setter!(set_description, description, String);
+    setter!(set_currency, currency, Option<Currency>);
+
+    getter!(tx_id, u64);
+    getter!(from_user_id, u64);
+    getter!(to_user_id, u64);
+    getter!(timestamp, u64);
+    getter!(currency, Option<Currency>);
+
+    /// Получить тип транзакции.
+    pub fn tx_type(&self) -> &TxType {
+        &self.tx_type
+    }
+
+    /// Получить состояние транзакции.
+    pub fn status(&self) -> &Status {
+        &self.status
+    }
+
+    /// Минимальная версия схемы, в которой может быть записана запись о транзакции.
+    ///
+    /// Версия [`SchemaVersion::V2`] требуется для отрицательных сумм и сумм,
+    /// превышающих [`u64::MAX`].
+    pub fn schema_version(&self) -> SchemaVersion {
+        if self.is_wide() {
+            SchemaVersion::V2
+        } else {
+            SchemaVersion::V1
+        }
+    }
+
+    /// Получить сумму транзакции.
+    ///
+    /// Для широкой суммы (см. [`Record::is_wide`]) возвращается [`None`]; сама сумма доступна
+    /// через [`Record::wide_amount`].
+    pub fn amount(&self) -> Option<u64> {
+        u64::try_from(self.amount).ok()
+    }
+
+    /// Получить сумму транзакции для поля суммы фиксированной ширины форматов.
+    ///
+    /// Широкая сумма записывается в это поле нулем — так ее считывают читатели версии схемы
+    /// [`SchemaVersion::V1`], — а сама сумма записывается отдельно.
+    pub(crate) fn narrow_amount(&self) -> u64 {
+        self.amount().unwrap_or(0)
+    }
+
+    /// Установить сумму транзакции.
+    pub fn set_amount(&mut self, amount: u64) -> &mut Self {
+        self.amount = i128::from(amount);
+        self
+    }
+
+    /// Получить сумму транзакции с учетом широких сумм: отрицательных и превышающих
+    /// [`u64::MAX`].
+    pub fn wide_amount(&self) -> i128 {
+        self.amount
+    }
+
+    /// Установить сумму транзакции, которая может быть широкой (см. [`Record::is_wide`]).
+    pub fn set_wide_amount(&mut self, amount: i128) -> &mut Self {
+        self.amount = amount;
+        self
+    }
+
+    /// Признак широкой суммы транзакции: отрицательной или превышающей [`u64::MAX`].
+    ///
+    /// Такая запись требует версии схемы [`SchemaVersion::V2`] (см. [`Record::schema_version`]).
+    pub fn is_wide(&self) -> bool {
+        u64::try_from(self.amount).is_err()
+    }
+
+    /// Получить описание транзакции.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Установить описание транзакции.
+    pub fn set_description(&mut self, description: impl Into<Description>) -> &mut Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Заменить описание транзакции одинаковым описанием из указанного набора, чтобы записи
+    /// с повторяющимися описаниями разделяли одну строку.
+    pub fn intern_description(&mut self, pool: &mut DescriptionPool) -> &mut Self {
+        self.description = pool.intern(&self.description);
+        self
+    }
+
+    /// Получить дополнительные поля записи.
+    pub fn metadata(&self) -> &HashMap<String, String> {
+        &self.metadata
+    }
+
+    /// Получить изменяемые дополнительные поля записи.
+    pub fn metadata_mut(&mut self) -> &mut HashMap<String, String> {
+        &mut self.metadata
+    }
+
+    /// Проверить, может ли строка быть ключом дополнительного поля записи.
+    ///
+    /// Ключ должен быть непустым, состоять из латинских букв, цифр и символов `_`, `-`, `.`
+    /// и не совпадать с ключами полей записи.
+    pub fn is_metadata_key(key: &str) -> bool {
+        !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+            && RecordKey::try_from(key).is_err()
+    }
+
+    /// Получить дополнительные поля записи, упорядоченные по ключу.
+    fn sorted_metadata(&self) -> Vec<(&String, &String)> {
+        let mut metadata = self.metadata.iter().collect::<Vec<_>>();
+        metadata.sort();
+
+        metadata
+    }
+
+    /// Проверить, что значения полей с указанными ключами могут быть удалены
+    /// (см. [`Record::redact`]).
+    pub fn check_redactable(fields: &[RecordKey]) -> Result<(), RedactError> {
+        match fields
+            .iter()
+            .find(|key| matches!(key, RecordKey::TxId | RecordKey::TxType | RecordKey::Status))
+        {
+            Some(&key) => Err(RedactError::UnsupportedField(key)),
+            None => Ok(()),
+        }
+    }
+
+    /// Удалить значения полей с указанными ключами, сохранив структурную корректность записи:
+    /// числовые поля обнуляются, описание очищается, а валюта удаляется.
+    ///
+    /// TX_ID, TX_TYPE и STATUS не имеют пустого значения и не могут быть удалены.
+    pub fn redact(&mut self, fields: &[RecordKey]) -> Result<(), RedactError> {
+        Self::check_redactable(fields)?;
+
+        for key in fields {
+            match key {
+                RecordKey::FromUserId => self.from_user_id = 0,
+                RecordKey::ToUserId => self.to_user_id = 0,
+                RecordKey::Amount => self.amount = 0,
+                RecordKey::Timestamp => self.timestamp = 0,
+                RecordKey::Description => self.description = Description::default(),
+                RecordKey::Currency => self.currency = None,
+                RecordKey::TxId | RecordKey::TxType | RecordKey::Status => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Оставить значения только указанных полей, заменив значения остальных пустыми,
+    /// как при [`Record::redact`]; дополнительные поля удаляются, если они не входят в набор.
+    ///
+    /// TX_ID, TX_TYPE и STATUS не имеют пустого значения и сохраняются всегда.
+    pub fn project(&mut self, projection: Projection) {
+        let keys = Self::EXPECTED_KEYS.into_iter().chain(Self::OPTIONAL_KEYS);
+
+        for key in keys.filter(|&key| !projection.contains(key)) {
+            match key {
+                RecordKey::FromUserId => self.from_user_id = 0,
+                RecordKey::ToUserId => self.to_user_id = 0,
+                RecordKey::Amount => self.amount = 0,
+                RecordKey::Timestamp => self.timestamp = 0,
+                RecordKey::Description => self.description = Description::default(),
+                RecordKey::Currency => self.currency = None,
+                RecordKey::TxId | RecordKey::TxType | RecordKey::Status => {}
+            }
+        }
+
+        if !projection.metadata {
+            self.metadata.clear();
+        }
+    }
+
+    /// Сравнить записи о транзакциях по значению поля с указанным ключом.
+    ///
+    /// Типы и состояния транзакций сравниваются по их коду в бинарном формате
+    /// (неизвестные типы и состояния следуют за известными).
+    pub fn compare_by(&self, other: &Self, key: RecordKey) -> Ordering {
+        match key {
+            RecordKey::TxId => self.tx_id.cmp(&other.tx_id),
+            RecordKey::TxType => self.tx_type.cmp(&other.tx_type),
+            RecordKey::FromUserId => self.from_user_id.cmp(&other.from_user_id),
+            RecordKey::ToUserId => self.to_user_id.cmp(&other.to_user_id),
+            RecordKey::Amount => self.amount.cmp(&other.amount),
+            RecordKey::Timestamp => self.timestamp.cmp(&other.timestamp),
+            RecordKey::Status => self.status.cmp(&other.status),
+            RecordKey::Description => self.description.cmp(&other.description),
+            RecordKey::Currency => self.currency.cmp(&other.currency),
+        }
+    }
 
     /// Валидация и установка значения идентификатора транзакции.
     fn validate_and_set_tx_id(&mut self, value: &str) -> Result<(), ParseValueError> {
@@ -136,8 +603,12 @@ impl Record {
     }
 
     /// Валидация и установка значения типа транзакции.
-    fn validate_and_set_tx_type(&mut self, value: &str) -> Result<(), ParseValueError> {
-        let tx_type = value.try_into()?;
+    fn validate_and_set_tx_type(
+        &mut self,
+        value: &str,
+        lenient: bool,
+    ) -> Result<(), ParseValueError> {
+        let tx_type = TxType::from_name(value, lenient)?;
 
         self.set_tx_type(tx_type);
 
@@ -175,13 +646,13 @@ impl Record {
     /// Валидация и установка значения суммы транзакции.
     fn validate_and_set_amount(&mut self, value: &str) -> Result<(), ParseValueError> {
         let amount = value
-            .parse::<u64>()
+            .parse::<i128>()
             .map_err(|_| ParseValueError::InvalidValue {
                 value: value.to_string(),
                 description: "AMOUNT is not a number".to_string(),
             })?;
 
-        self.set_amount(amount);
+        self.set_wide_amount(amount);
 
         Ok(())
     }
@@ -201,24 +672,50 @@ impl Record {
     }
 
     /// Валидация и установка значения состояния транзакции.
-    fn validate_and_set_status(&mut self, value: &str) -> Result<(), ParseValueError> {
-        let status = value.try_into()?;
+    fn validate_and_set_status(
+        &mut self,
+        value: &str,
+        lenient: bool,
+    ) -> Result<(), ParseValueError> {
+        let status = Status::from_name(value, lenient)?;
 
         self.set_status(status);
 
         Ok(())
     }
 
-    /// Валидация и установка значения произвольного текстового описания транзакции.
-    fn validate_and_set_description(&mut self, value: &str) -> Result<(), ParseValueError> {
-        if !value.starts_with('"') || !value.ends_with('"') {
+    /// Валидация и установка значения валюты транзакции.
+    ///
+    /// Пустое значение означает отсутствие валюты.
+    fn validate_and_set_currency(&mut self, value: &str) -> Result<(), ParseValueError> {
+        let currency = match value {
+            "" => None,
+            value => Some(Currency::try_from(value)?),
+        };
+
+        self.set_currency(currency);
+
+        Ok(())
+    }
+
+    /// Проверить, что описание транзакции заключено в кавычки, и вернуть его без них.
+    fn unquote_description(value: &str) -> Result<&str, ParseValueError> {
+        if value.len() < 2 || !value.starts_with('"') || !value.ends_with('"') {
             return Err(ParseValueError::InvalidValue {
                 value: value.to_string(),
                 description: "DESCRIPTION must start and end with symbol \"".to_string(),
             });
         }
 
-        self.set_description(value[1..value.len() - 1].to_string());
+        Ok(&value[1..value.len() - 1])
+    }
+
+    /// Валидация и установка значения произвольного текстового описания транзакции,
+    /// экранированного при записи в текстовом формате.
+    fn validate_and_set_escaped_description(&mut self, value: &str) -> Result<(), ParseValueError> {
+        let description = unescape_description(Self::unquote_description(value)?)?;
+
+        self.set_description(description);
 
         Ok(())
     }
@@ -228,35 +725,70 @@ impl Record {
         &mut self,
         key: RecordKey,
         value: &str,
+        context: &ParseContext,
     ) -> Result<(), ParseValueError> {
         match key {
             RecordKey::TxId => self.validate_and_set_tx_id(value),
-            RecordKey::TxType => self.validate_and_set_tx_type(value),
+            RecordKey::TxType => self.validate_and_set_tx_type(value, context.lenient),
             RecordKey::FromUserId => self.validate_and_set_from_user_id(value),
             RecordKey::ToUserId => self.validate_and_set_to_user_id(value),
             RecordKey::Amount => self.validate_and_set_amount(value),
             RecordKey::Timestamp => self.validate_and_set_timestamp(value),
-            RecordKey::Status => self.validate_and_set_status(value),
-            RecordKey::Description => self.validate_and_set_description(value),
+            RecordKey::Status => self.validate_and_set_status(value, context.lenient),
+            RecordKey::Description => self.validate_and_set_escaped_description(value),
+            RecordKey::Currency => self.validate_and_set_currency(value),
+        }
+    }
+
+    /// Валидация и установка значения поля записи транзакции по его ключу, если описание
+    /// указано без кавычек.
+    pub(crate) fn validate_and_set_unquoted_value_by_key(
+        &mut self,
+        key: RecordKey,
+        value: &str,
+        context: &ParseContext,
+    ) -> Result<(), ParseValueError> {
+        match key {
+            RecordKey::Description => {
+                self.set_description(value);
+                Ok(())
+            }
+            key => self.validate_and_set_value_by_key(key, value, context),
         }
     }
 
     /// Считать данные о транзакции из указанного источника, имеющего текстовый формат записи.
     pub fn from_text<R: BufRead>(r: &mut R) -> Result<Self, ParseRecordFromTxtError> {
+        Self::from_text_with_buffer(r, &mut String::new(), &ParseContext::default())
+    }
+
+    /// Считать данные о транзакции из указанного источника, имеющего текстовый формат записи,
+    /// с указанными параметрами разбора, используя указанный буфер для считываемых строк.
+    ///
+    /// Буфер может быть переиспользован для чтения следующих записей, чтобы не выделять память
+    /// под каждую строку.
+    pub(crate) fn from_text_with_buffer<R: BufRead>(
+        r: &mut R,
+        line: &mut String,
+        context: &ParseContext,
+    ) -> Result<Self, ParseRecordFromTxtError> {
+        let max_record_size = context.limits.max_record_size;
         let mut result = Self::default();
 
         let mut expected_keys = HashSet::from(Self::EXPECTED_KEYS);
+        let mut read = 0;
 
         loop {
-            let mut line = String::new();
+            line.clear();
 
-            let bytes_count = r.read_line(&mut line)?;
+            let bytes_count = Self::read_record_line(r, line, &mut read, max_record_size)?
+                .ok_or(ParseRecordFromTxtError::RecordTooLarge(max_record_size))?;
 
-            if bytes_count == 0 || line == "\n" {
+            if bytes_count == 0 || line == "\n" || line == "\r\n" {
                 break;
             }
 
-            line = line.trim_end_matches(['\r', '\n']).to_string();
+            let line = line.trim_end_matches(['\r', '\n']);
 
             if line.starts_with('#') {
                 continue;
@@ -273,9 +805,18 @@ impl Record {
                 return Err(ParseRecordFromTxtError::ColonNotFound(key.to_string()));
             }
 
-            let key = RecordKey::try_from(&key[..key.len() - 1])?;
+            let key = &key[..key.len() - 1];
+
+            let key = match RecordKey::try_from(key) {
+                Ok(key) => key,
+                Err(_) if Self::is_metadata_key(key) => {
+                    result.metadata.insert(key.to_string(), value.to_string());
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
 
-            result.validate_and_set_value_by_key(key, value)?;
+            result.validate_and_set_value_by_key(key, value, context)?;
             expected_keys.remove(&key);
         }
 
@@ -293,180 +834,699 @@ impl Record {
     }
 
     /// Записать данные о транзакции в указанное место в текстовом формате.
+    ///
+    /// Описание заключается в кавычки, кавычки, обратная косая черта и переводы строк внутри
+    /// описания экранируются обратной косой чертой (`\"`, `\\`, `\n`). Валюта записывается
+    /// строкой `CURRENCY`, только если она указана, а за ней следуют дополнительные поля
+    /// в порядке их ключей.
+    ///
+    /// Дополнительные поля с ключами, не удовлетворяющими [`Record::is_metadata_key`],
+    /// или значениями, содержащими переводы строк, не могут быть записаны.
     pub fn to_text<W: Write>(&self, w: &mut W) -> Result<(), std::io::Error> {
-        w.write_all(
-            format!(
-                r#"TX_ID: {}
+        write!(
+            w,
+            r#"TX_ID: {}
 TX_TYPE: {}
 FROM_USER_ID: {}
 TO_USER_ID: {}
 AMOUNT: {}
 TIMESTAMP: {}
 STATUS: {}
-DESCRIPTION: "{}""#,
-                self.tx_id,
-                self.tx_type,
-                self.from_user_id,
-                self.to_user_id,
-                self.amount,
-                self.timestamp,
-                self.status,
-                self.description
-            )
-            .as_bytes(),
+DESCRIPTION: ""#,
+            self.tx_id,
+            self.tx_type,
+            self.from_user_id,
+            self.to_user_id,
+            self.amount,
+            self.timestamp,
+            self.status,
         )?;
+        write_escaped_description(w, &self.description)?;
+        w.write_all(b"\"")?;
+
+        if let Some(currency) = self.currency {
+            write!(w, "\nCURRENCY: {currency}")?;
+        }
+
+        for (key, value) in self.sorted_metadata() {
+            if !Self::is_metadata_key(key) || value.contains(['\n', '\r']) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("metadata field `{key}` cannot be written in the text format"),
+                ));
+            }
+
+            write!(w, "\n{key}: {value}")?;
+        }
+
         w.write_all("\n".as_bytes())
     }
 
+    /// Считать очередную строку записи в текстовом или CSV формате в указанный пустой буфер
+    /// с учетом `read` байтов строк записи, считанных ранее.
+    ///
+    /// Строка считывается не далее ограничения размера записи `max_record_size`
+    /// (см. [`crate::SizeLimits::max_record_size`]), поэтому источник без переводов строк
+    /// или с незакрытой кавычкой не вынуждает выделить чрезмерный объем памяти. Возвращает
+    /// количество считанных байтов или `None`, если запись превышает ограничение.
+    fn read_record_line<R: BufRead>(
+        r: &mut R,
+        line: &mut String,
+        read: &mut u64,
+        max_record_size: u32,
+    ) -> io::Result<Option<usize>> {
+        let max_record_size = u64::from(max_record_size);
+
+        let mut bytes = std::mem::take(line).into_bytes();
+        let bytes_count = r
+            .by_ref()
+            .take(max_record_size.saturating_sub(*read) + 1)
+            .read_until(b'\n', &mut bytes)?;
+
+        *read += bytes_count as u64;
+
+        if *read > max_record_size {
+            return Ok(None);
+        }
+
+        *line = String::from_utf8(bytes).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "stream did not contain valid UTF-8",
+            )
+        })?;
+
+        Ok(Some(bytes_count))
+    }
+
+    /// Считать поля очередной записи CSV-таблицы в соответствии с RFC 4180.
+    ///
+    /// Поля, заключенные в кавычки, могут содержать разделители, переводы строк и кавычки,
+    /// экранированные удвоением (`""`). Разделитель полей и символ кавычки задаются параметрами
+    /// формата, а размер записи ограничен ограничением по умолчанию. Возвращает `None`,
+    /// если источник исчерпан.
+    pub(crate) fn read_csv_fields<R: BufRead>(
+        r: &mut R,
+        options: &CsvOptions,
+    ) -> Result<Option<Vec<CsvField>>, ParseRecordFromCsvError> {
+        Self::read_csv_fields_with_buffer(
+            r,
+            options,
+            &mut String::new(),
+            SizeLimits::DEFAULT.max_record_size,
+        )
+    }
+
+    /// Считать поля очередной записи CSV-таблицы размером не более `max_record_size` байтов,
+    /// используя указанный буфер для считываемых строк (см. [`Record::read_csv_fields`]).
+    fn read_csv_fields_with_buffer<R: BufRead>(
+        r: &mut R,
+        options: &CsvOptions,
+        line: &mut String,
+        max_record_size: u32,
+    ) -> Result<Option<Vec<CsvField>>, ParseRecordFromCsvError> {
+        let CsvOptions {
+            delimiter,
+            quote_char,
+            ..
+        } = *options;
+
+        let mut fields = vec![];
+
+        let mut value = String::new();
+        let mut quoted = false;
+        let mut in_quotes = false;
+        let mut closed = false;
+        let mut read = 0;
+
+        loop {
+            line.clear();
+
+            let bytes_count = Self::read_record_line(r, line, &mut read, max_record_size)?
+                .ok_or(ParseRecordFromCsvError::RecordTooLarge(max_record_size))?;
+
+            if bytes_count == 0 {
+                if fields.is_empty() && !quoted && value.is_empty() {
+                    return Ok(None);
+                }
+
+                return Err(ParseRecordFromCsvError::InvalidQuoting(
+                    "unterminated quoted field".to_string(),
+                ));
+            }
+
+            let mut chars = line.chars().peekable();
+
+            while let Some(c) = chars.next() {
+                if in_quotes {
+                    if c != quote_char {
+                        value.push(c);
+                    } else if chars.next_if_eq(&quote_char).is_some() {
+                        value.push(quote_char);
+                    } else {
+                        in_quotes = false;
+                        closed = true;
+                    }
+
+                    continue;
+                }
+
+                match c {
+                    _ if c == delimiter => {
+                        fields.push(CsvField {
+                            value: std::mem::take(&mut value),
+                            quoted,
+                        });
+                        quoted = false;
+                        closed = false;
+                    }
+                    '\n' => break,
+                    '\r' if matches!(chars.peek(), None | Some('\n')) => {}
+                    _ if c == quote_char && !quoted && value.is_empty() => {
+                        quoted = true;
+                        in_quotes = true;
+                    }
+                    _ if closed => {
+                        return Err(ParseRecordFromCsvError::InvalidQuoting(format!(
+                            "unexpected character after closing quote: {c:?}"
+                        )));
+                    }
+                    _ => value.push(c),
+                }
+            }
+
+            if !in_quotes {
+                fields.push(CsvField { value, quoted });
+                return Ok(Some(fields));
+            }
+        }
+    }
+
     /// Считать данные о транзакции из указанного источника, имеющего CSV формат записи.
     pub fn from_csv<R: BufRead>(r: &mut R) -> Result<Self, ParseRecordFromCsvError> {
-        let mut result = Self::default();
+        Self::from_csv_with(r, &CsvOptions::default())
+    }
 
-        let mut line = String::new();
+    /// Считать данные о транзакции из указанного источника, имеющего CSV формат записи
+    /// с заданными параметрами.
+    ///
+    /// Девятый столбец, если он есть, содержит валюту транзакции.
+    pub fn from_csv_with<R: BufRead>(
+        r: &mut R,
+        options: &CsvOptions,
+    ) -> Result<Self, ParseRecordFromCsvError> {
+        Self::from_csv_with_context(r, options, &ParseContext::default())
+    }
 
-        let bytes_count = r.read_line(&mut line)?;
+    /// Считать данные о транзакции из указанного источника, имеющего CSV формат записи
+    /// с заданными параметрами, с указанными параметрами разбора (см. [`Record::from_csv_with`]).
+    pub(crate) fn from_csv_with_context<R: BufRead>(
+        r: &mut R,
+        options: &CsvOptions,
+        context: &ParseContext,
+    ) -> Result<Self, ParseRecordFromCsvError> {
+        let fields = Self::read_csv_fields_with_buffer(
+            r,
+            options,
+            &mut String::new(),
+            context.limits.max_record_size,
+        )?
+        .ok_or_else(|| ParseRecordFromCsvError::UnexpectedError("EOF is reached".to_string()))?;
+
+        let currency = fields.len() > Self::EXPECTED_KEYS.len() + options.metadata_columns.len();
+
+        Self::from_csv_fields(fields, &options.columns(currency), context)
+    }
 
-        if bytes_count == 0 {
-            return Err(ParseRecordFromCsvError::UnexpectedError(
-                "EOF is reached".to_string(),
-            ));
-        }
+    /// Считать данные о транзакции из указанного источника, имеющего CSV формат записи
+    /// с заданными параметрами, в котором столбцы соответствуют указанным полям, с указанными
+    /// параметрами разбора.
+    ///
+    /// Указанный буфер для считываемых строк может быть переиспользован для чтения следующих
+    /// записей.
+    pub(crate) fn from_csv_columns<R: BufRead>(
+        r: &mut R,
+        options: &CsvOptions,
+        columns: &[CsvColumn],
+        line: &mut String,
+        context: &ParseContext,
+    ) -> Result<Self, ParseRecordFromCsvError> {
+        let fields =
+            Self::read_csv_fields_with_buffer(r, options, line, context.limits.max_record_size)?
+                .ok_or_else(|| {
+                    ParseRecordFromCsvError::UnexpectedError("EOF is reached".to_string())
+                })?;
 
-        line = line.trim_end_matches(['\r', '\n']).to_string();
+        Self::from_csv_fields(fields, columns, context)
+    }
 
-        let values = line
-            .splitn(Self::EXPECTED_KEYS.len(), ',')
-            .collect::<Vec<_>>();
+    /// Разобрать поля записи CSV-таблицы, столбцы которой соответствуют указанным полям.
+    ///
+    /// Пустое значение дополнительного поля без кавычек означает его отсутствие.
+    fn from_csv_fields(
+        fields: Vec<CsvField>,
+        columns: &[CsvColumn],
+        context: &ParseContext,
+    ) -> Result<Self, ParseRecordFromCsvError> {
+        let mut result = Self::default();
 
-        if Self::EXPECTED_KEYS.len() != values.len() {
-            return Err(ParseRecordFromCsvError::InvalidCountOfColumns(values.len()));
+        if columns.len() != fields.len() {
+            return Err(ParseRecordFromCsvError::InvalidCountOfColumns(fields.len()));
         }
 
-        for (&key, value) in Self::EXPECTED_KEYS.iter().zip(values.iter()) {
-            result.validate_and_set_value_by_key(key, value)?;
+        for (column, field) in columns.iter().zip(fields) {
+            match column {
+                CsvColumn::Field(RecordKey::Description) if field.quoted => {
+                    result.set_description(field.value);
+                }
+                CsvColumn::Field(RecordKey::Description) => {
+                    return Err(ParseValueError::InvalidValue {
+                        value: field.value,
+                        description: "DESCRIPTION must start and end with symbol \"".to_string(),
+                    })?;
+                }
+                &CsvColumn::Field(key) => {
+                    result.validate_and_set_value_by_key(key, &field.value, context)?
+                }
+                CsvColumn::Metadata(key) => {
+                    if field.quoted || !field.value.is_empty() {
+                        result.metadata.insert(key.clone(), field.value);
+                    }
+                }
+            }
         }
 
         Ok(result)
     }
 
     /// Записать данные о транзакции в указанное место в CSV формате.
+    ///
+    /// Описание заключается в кавычки, кавычки внутри описания экранируются удвоением (RFC 4180).
     pub fn to_csv<W: Write>(&self, w: &mut W) -> Result<(), std::io::Error> {
-        w.write_all(
-            format!(
-                "{},{},{},{},{},{},{},\"{}\"\n",
-                self.tx_id,
-                self.tx_type,
-                self.from_user_id,
-                self.to_user_id,
-                self.amount,
-                self.timestamp,
-                self.status,
-                self.description
-            )
-            .as_bytes(),
-        )
+        self.to_csv_with(w, &CsvOptions::default())
+    }
+
+    /// Записать данные о транзакции в указанное место в CSV формате с заданными параметрами.
+    ///
+    /// Описание заключается в символы кавычки, которые внутри описания экранируются удвоением.
+    /// Валюта, если она указана, записывается девятым столбцом, а за ней следуют столбцы
+    /// дополнительных полей из [`CsvOptions::metadata_columns`].
+    pub fn to_csv_with<W: Write>(
+        &self,
+        w: &mut W,
+        options: &CsvOptions,
+    ) -> Result<(), std::io::Error> {
+        self.to_csv_row(w, options, self.currency.is_some())
+    }
+
+    /// Записать данные о транзакции в виде строки CSV-таблицы с заданными параметрами,
+    /// добавив столбец валюты, если это требуется (пустой для записи без валюты), и столбцы
+    /// дополнительных полей (значения заключаются в кавычки, отсутствующие поля пусты).
+    pub(crate) fn to_csv_row<W: Write>(
+        &self,
+        w: &mut W,
+        options: &CsvOptions,
+        currency_column: bool,
+    ) -> Result<(), std::io::Error> {
+        let delimiter = options.delimiter;
+
+        let fields: [&dyn std::fmt::Display; 7] = [
+            &self.tx_id,
+            &self.tx_type,
+            &self.from_user_id,
+            &self.to_user_id,
+            &self.amount,
+            &self.timestamp,
+            &self.status,
+        ];
+
+        for field in fields {
+            write!(w, "{field}{delimiter}")?;
+        }
+
+        Self::write_csv_quoted(w, &self.description, options.quote_char)?;
+
+        if currency_column {
+            write!(w, "{delimiter}")?;
+
+            if let Some(currency) = self.currency {
+                write!(w, "{currency}")?;
+            }
+        }
+
+        for key in &options.metadata_columns {
+            write!(w, "{delimiter}")?;
+
+            if let Some(value) = self.metadata.get(key) {
+                Self::write_csv_quoted(w, value, options.quote_char)?;
+            }
+        }
+
+        w.write_all(b"\n")
     }
 
-    const BINARY_MAGIC: [u8; 4] = [0x59, 0x50, 0x42, 0x4E];
-    const BINARY_MIN_RECORD_SIZE: u32 = 46;
+    /// Записать значение поля CSV-таблицы, заключив его в символы кавычки и экранировав
+    /// их внутри значения удвоением.
+    fn write_csv_quoted<W: Write>(w: &mut W, value: &str, quote_char: char) -> io::Result<()> {
+        let mut quote = [0; 4];
+        let quote = quote_char.encode_utf8(&mut quote).as_bytes();
+
+        w.write_all(quote)?;
+
+        for (index, part) in value.split(quote_char).enumerate() {
+            if index > 0 {
+                w.write_all(quote)?;
+                w.write_all(quote)?;
+            }
+
+            w.write_all(part.as_bytes())?;
+        }
+
+        w.write_all(quote)
+    }
+
+    pub(crate) const BINARY_MAGIC: [u8; 4] = [0x59, 0x50, 0x42, 0x4E];
+    pub(crate) const BINARY_MIN_RECORD_SIZE: u32 = 46;
+
+    /// Размер заголовка записи бинарного формата: MAGIC и RECORD_SIZE.
+    pub(crate) const BINARY_HEADER_SIZE: usize = 8;
+
+    /// Тег расширения бинарного формата, содержащего валюту транзакции.
+    pub(crate) const BINARY_EXTENSION_CURRENCY: u8 = 1;
+
+    /// Тег расширения бинарного формата, содержащего дополнительное поле записи.
+    pub(crate) const BINARY_EXTENSION_METADATA: u8 = 2;
+
+    /// Тег расширения бинарного формата, содержащего широкую сумму транзакции.
+    pub(crate) const BINARY_EXTENSION_AMOUNT: u8 = 3;
 
     /// Считать данные о транзакции из указанного источника, имеющего бинарный формат записи.
     pub fn from_bin<R: BufRead>(r: &mut R) -> Result<Self, ParseRecordFromBinError> {
-        let mut result = Self::default();
+        Self::from_bin_at(r, 1, &ParseContext::default())
+    }
 
-        let mut magic = [0u8; 4];
+    /// Считать данные о транзакции с указанным порядковым номером (начиная с 1) из источника,
+    /// имеющего бинарный формат записи, с указанными параметрами разбора.
+    ///
+    /// Запись считывается целиком до разбора ее полей, поэтому обрыв источника посреди записи
+    /// сообщается как [`ParseRecordFromBinError::UnexpectedEof`], а не как ошибка значения поля.
+    pub(crate) fn from_bin_at<R: BufRead>(
+        r: &mut R,
+        record_index: usize,
+        context: &ParseContext,
+    ) -> Result<Self, ParseRecordFromBinError> {
+        let mut header = Vec::with_capacity(Self::BINARY_HEADER_SIZE);
+
+        // MAGIC считывается отдельно, чтобы при его несовпадении не захватить начало
+        // следующей записи.
+        r.by_ref()
+            .take(Self::BINARY_MAGIC.len() as u64)
+            .read_to_end(&mut header)?;
+
+        if !Self::BINARY_MAGIC.starts_with(&header) {
+            return Err(ParseRecordFromBinError::InvalidMagicNumber);
+        }
 
-        r.read_exact(&mut magic)?;
+        r.by_ref()
+            .take((Self::BINARY_HEADER_SIZE - header.len()) as u64)
+            .read_to_end(&mut header)?;
+
+        let record_size =
+            Self::check_bin_header(&header, record_index, context.limits.max_record_size)?;
+
+        // Тело записи считывается целиком согласно RECORD_SIZE, поэтому ошибка в ее полях
+        // (например, некорректный DESCRIPTION_SIZE) не сдвигает чтение следующей записи.
+        // Буфер растет по мере чтения, поэтому поврежденный RECORD_SIZE не приводит
+        // к выделению лишней памяти.
+        let mut body = vec![];
+        r.by_ref()
+            .take(u64::from(record_size))
+            .read_to_end(&mut body)?;
+
+        if body.len() < record_size as usize {
+            return Err(ParseRecordFromBinError::UnexpectedEof {
+                record_index,
+                expected: Self::BINARY_HEADER_SIZE + record_size as usize,
+                got: Self::BINARY_HEADER_SIZE + body.len(),
+            });
+        }
+
+        Ok(RecordRef::from_bin_body(&body, context)?.into())
+    }
 
-        if magic != Self::BINARY_MAGIC {
+    /// Проверить заголовок записи бинарного формата (MAGIC и RECORD_SIZE) и получить размер
+    /// тела записи, не превышающий `max_record_size` байтов.
+    ///
+    /// Неполный заголовок считается обрывом источника, только если его байты совпадают
+    /// с началом MAGIC.
+    pub(crate) fn check_bin_header(
+        header: &[u8],
+        record_index: usize,
+        max_record_size: u32,
+    ) -> Result<u32, ParseRecordFromBinError> {
+        let magic = &header[..header.len().min(Self::BINARY_MAGIC.len())];
+
+        if !Self::BINARY_MAGIC.starts_with(magic) {
             return Err(ParseRecordFromBinError::InvalidMagicNumber);
         }
 
-        let record_size = r.read_u32::<BigEndian>()?;
+        let Some(size) = header.get(Self::BINARY_MAGIC.len()..Self::BINARY_HEADER_SIZE) else {
+            return Err(ParseRecordFromBinError::UnexpectedEof {
+                record_index,
+                expected: Self::BINARY_HEADER_SIZE,
+                got: header.len(),
+            });
+        };
+
+        let record_size = u32::from_be_bytes(size.try_into().unwrap());
 
         if record_size < Self::BINARY_MIN_RECORD_SIZE {
             return Err(ParseRecordFromBinError::InvalidRecordSize(record_size));
         }
 
-        let tx_id = r.read_u64::<BigEndian>()?;
-        result.set_tx_id(tx_id);
+        if record_size > max_record_size {
+            return Err(ParseRecordFromBinError::RecordTooLarge {
+                size: record_size,
+                max: max_record_size,
+            });
+        }
 
-        let tx_type_raw = r.read_u8()?;
-        let tx_type = tx_type_raw.try_into().map_err(|e: ParseTxTypeError| {
-            ParseValueError::InvalidValue {
-                value: tx_type_raw.to_string(),
-                description: e.to_string(),
-            }
-        })?;
-        result.set_tx_type(tx_type);
+        Ok(record_size)
+    }
 
-        let from_user_id = r.read_u64::<BigEndian>()?;
-        result.set_from_user_id(from_user_id);
+    /// Подготовить расширения бинарного формата для необязательных полей записи.
+    ///
+    /// Дополнительные поля записываются в порядке их ключей, а широкая сумма — последней.
+    fn bin_extensions(&self) -> Result<Vec<u8>, io::Error> {
+        let mut extensions = vec![];
+
+        let mut write_extension = |tag: u8, value: &[u8]| {
+            let len = u16::try_from(value.len()).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("binary extension {tag} is too long ({} bytes)", value.len()),
+                )
+            })?;
 
-        let to_user_id = r.read_u64::<BigEndian>()?;
-        result.set_to_user_id(to_user_id);
+            extensions.push(tag);
+            extensions.extend_from_slice(&len.to_be_bytes());
+            extensions.extend_from_slice(value);
 
-        let amount = r.read_u64::<BigEndian>()?;
-        result.set_amount(amount);
+            Ok::<_, io::Error>(())
+        };
 
-        let timestamp = r.read_u64::<BigEndian>()?;
-        result.set_timestamp(timestamp);
+        if let Some(currency) = self.currency {
+            write_extension(Self::BINARY_EXTENSION_CURRENCY, currency.as_bytes())?;
+        }
 
-        let status_raw = r.read_u8()?;
-        let status =
-            status_raw
-                .try_into()
-                .map_err(|e: ParseStatusError| ParseValueError::InvalidValue {
-                    value: status_raw.to_string(),
-                    description: e.to_string(),
-                })?;
-        result.set_status(status);
+        for (key, value) in self.sorted_metadata() {
+            let key_len = u16::try_from(key.len()).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("metadata key is too long ({} bytes)", key.len()),
+                )
+            })?;
 
-        let desc_len = r.read_u32::<BigEndian>()?;
+            let mut entry = key_len.to_be_bytes().to_vec();
+            entry.extend_from_slice(key.as_bytes());
+            entry.extend_from_slice(value.as_bytes());
 
-        if record_size != Self::BINARY_MIN_RECORD_SIZE + desc_len {
-            return Err(ParseRecordFromBinError::UnexpectedError(format!(
-                "true record size is not equal to expected (record size({}) != static length ({}) + description length ({}))",
-                record_size,
-                Self::BINARY_MIN_RECORD_SIZE,
-                desc_len
-            )));
+            write_extension(Self::BINARY_EXTENSION_METADATA, &entry)?;
         }
 
-        if desc_len > 0 {
-            let mut buffer = vec![0u8; desc_len as usize];
-            r.read_exact(&mut buffer)?;
-
-            result.validate_and_set_description(
-                String::from_utf8(buffer.clone())
-                    .map_err(|e| ParseValueError::InvalidValue {
-                        value: String::from_utf8_lossy(&buffer).to_string(),
-                        description: e.to_string(),
-                    })?
-                    .as_str(),
-            )?;
+        if self.schema_version() > SchemaVersion::V1 {
+            write_extension(Self::BINARY_EXTENSION_AMOUNT, &self.amount.to_be_bytes())?;
         }
 
-        Ok(result)
+        Ok(extensions)
     }
 
     /// Записать данные о транзакции в указанное место в бинарном формате.
+    ///
+    /// Необязательные и дополнительные поля записываются расширениями после описания транзакции.
+    /// Запись с широкой суммой не записывается, так как читатели версии схемы
+    /// [`SchemaVersion::V1`] считали бы вместо нее 0 (см. [`Record::to_bin_with_schema`]).
+    ///
+    /// Заголовок и фиксированные поля, описание и расширения передаются назначению одним вызовом
+    /// [`Write::write_vectored`], если оно принимает их целиком.
     pub fn to_bin<W: Write>(&self, w: &mut W) -> Result<(), std::io::Error> {
-        w.write_all(&Self::BINARY_MAGIC)?;
+        self.to_bin_with_schema(w, SchemaVersion::V1)
+    }
+
+    /// Записать данные о транзакции в указанное место в бинарном формате с указанной версией
+    /// схемы (см. [`Record::to_bin`]).
+    ///
+    /// При версии [`SchemaVersion::V2`] широкая сумма записывается расширением, а поле AMOUNT
+    /// в этом случае содержит 0. Запись, требующая более новой версии схемы, не записывается.
+    pub fn to_bin_with_schema<W: Write>(
+        &self,
+        w: &mut W,
+        schema_version: SchemaVersion,
+    ) -> Result<(), std::io::Error> {
+        if self.schema_version() > schema_version {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                WriteError::SchemaVersionRequired {
+                    tx_id: self.tx_id,
+                    amount: self.amount,
+                    required: self.schema_version(),
+                    used: schema_version,
+                }
+                .to_string(),
+            ));
+        }
+
+        let extensions = self.bin_extensions()?;
 
         let description_len = self.description.len() as u32 + 2;
-        let record_size = Self::BINARY_MIN_RECORD_SIZE + description_len;
-        w.write_u32::<BigEndian>(record_size)?;
-
-        w.write_u64::<BigEndian>(self.tx_id)?;
-        w.write_u8(self.tx_type as u8)?;
-        w.write_u64::<BigEndian>(self.from_user_id)?;
-        w.write_u64::<BigEndian>(self.to_user_id)?;
-        w.write_u64::<BigEndian>(self.amount)?;
-        w.write_u64::<BigEndian>(self.timestamp)?;
-        w.write_u8(self.status as u8)?;
-        w.write_u32::<BigEndian>(description_len)?;
-        w.write_all(format!("\"{}\"", self.description).as_bytes())
+        let record_size = Self::BINARY_MIN_RECORD_SIZE + description_len + extensions.len() as u32;
+
+        // Заголовок, фиксированные поля и открывающая кавычка описания.
+        let mut fixed = [0; Self::BINARY_HEADER_SIZE + Self::BINARY_MIN_RECORD_SIZE as usize + 1];
+        let mut f = &mut fixed[..];
+
+        f.write_all(&Self::BINARY_MAGIC)?;
+        f.write_u32::<BigEndian>(record_size)?;
+        f.write_u64::<BigEndian>(self.tx_id)?;
+        f.write_u8(u8::try_from(&self.tx_type).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "TX_TYPE `{}` cannot be written in the binary format",
+                    self.tx_type
+                ),
+            )
+        })?)?;
+        f.write_u64::<BigEndian>(self.from_user_id)?;
+        f.write_u64::<BigEndian>(self.to_user_id)?;
+        f.write_u64::<BigEndian>(self.narrow_amount())?;
+        f.write_u64::<BigEndian>(self.timestamp)?;
+        f.write_u8(u8::try_from(&self.status).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "STATUS `{}` cannot be written in the binary format",
+                    self.status
+                ),
+            )
+        })?)?;
+        f.write_u32::<BigEndian>(description_len)?;
+        f.write_all(b"\"")?;
+
+        write_all_vectored(
+            w,
+            &mut [
+                IoSlice::new(&fixed),
+                IoSlice::new(self.description.as_bytes()),
+                IoSlice::new(b"\""),
+                IoSlice::new(&extensions),
+            ],
+        )
+    }
+
+    /// Десериализовать данные о транзакции с указанными параметрами разбора.
+    ///
+    /// В отличие от [`Deserialize`], допускает неизвестные тип и состояние транзакции
+    /// при нестрогом чтении (см. [`crate::ParseOptions::lenient`]).
+    /// Поля записи могут следовать как в объекте, так и в массиве в порядке объявления.
+    pub(crate) fn deserialize_with<'de, D: Deserializer<'de>>(
+        deserializer: D,
+        context: &ParseContext,
+    ) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("Record", SERDE_FIELDS, RecordVisitor { context })
+    }
+
+    /// Записать данные о транзакции в указанное место в формате JSON-объекта.
+    pub fn to_json<W: Write>(&self, w: &mut W) -> Result<(), std::io::Error> {
+        serde_json::to_writer(w, self).map_err(std::io::Error::from)
+    }
+
+    /// Записать каноническое представление данных о транзакции, не зависящее от формата,
+    /// из которого они были считаны.
+    ///
+    /// Поля записываются в порядке объявления: числовые значения — в порядке байтов от старшего
+    /// к младшему, TX_TYPE и STATUS — кодами бинарного формата (неизвестные тип или состояние
+    /// без кода — байтом 255, длиной (4 байта) и байтами названия), описание — длиной в байтах
+    /// (4 байта) и байтами UTF-8 без кавычек. Валюта, если она указана, записывается последней:
+    /// байтом 1 и кодом ISO 4217, а за ней дополнительные поля в порядке ключей: байтом 2,
+    /// длинами (4 байта) и байтами ключа и значения. Так представление записей без валюты
+    /// и дополнительных полей не изменилось с их появлением. Широкая сумма записывается вместо
+    /// суммы нулем, а в конце — байтом 3 и 16 байтами суммы.
+    pub(crate) fn write_canonical(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.tx_id.to_be_bytes());
+        match u8::try_from(&self.tx_type) {
+            Ok(code) => out.push(code),
+            Err(_) => {
+                let name = self.tx_type.to_string();
+                out.push(u8::MAX);
+                out.extend_from_slice(&(name.len() as u32).to_be_bytes());
+                out.extend_from_slice(name.as_bytes());
+            }
+        }
+        out.extend_from_slice(&self.from_user_id.to_be_bytes());
+        out.extend_from_slice(&self.to_user_id.to_be_bytes());
+        out.extend_from_slice(&self.narrow_amount().to_be_bytes());
+        out.extend_from_slice(&self.timestamp.to_be_bytes());
+        match u8::try_from(&self.status) {
+            Ok(code) => out.push(code),
+            Err(_) => {
+                let name = self.status.to_string();
+                out.push(u8::MAX);
+                out.extend_from_slice(&(name.len() as u32).to_be_bytes());
+                out.extend_from_slice(name.as_bytes());
+            }
+        }
+        out.extend_from_slice(&(self.description.len() as u32).to_be_bytes());
+        out.extend_from_slice(self.description.as_bytes());
+
+        if let Some(currency) = self.currency {
+            out.push(1);
+            out.extend_from_slice(currency.as_bytes());
+        }
+
+        for (key, value) in self.sorted_metadata() {
+            out.push(2);
+            out.extend_from_slice(&(key.len() as u32).to_be_bytes());
+            out.extend_from_slice(key.as_bytes());
+            out.extend_from_slice(&(value.len() as u32).to_be_bytes());
+            out.extend_from_slice(value.as_bytes());
+        }
+
+        if self.schema_version() > SchemaVersion::V1 {
+            out.push(3);
+            out.extend_from_slice(&self.amount.to_be_bytes());
+        }
+    }
+
+    /// Вычислить хеш SHA-256 канонического представления данных о транзакции.
+    ///
+    /// Хеш не зависит от формата, из которого была считана запись, и не меняется между
+    /// версиями библиотеки.
+    pub fn hash_stable(&self) -> [u8; 32] {
+        let mut data = vec![];
+        self.write_canonical(&mut data);
+
+        Sha256::digest(&data).into()
     }
 }
 
@@ -489,10 +1549,34 @@ DESCRIPTION: "{}""#,
 //     }
 // }
 
+/// Записать данные нескольких буферов целиком, по возможности одним вызовом
+/// [`Write::write_vectored`].
+fn write_all_vectored<W: Write>(w: &mut W, mut bufs: &mut [IoSlice<'_>]) -> io::Result<()> {
+    IoSlice::advance_slices(&mut bufs, 0);
+
+    while !bufs.is_empty() {
+        match w.write_vectored(bufs) {
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write whole buffer",
+                ));
+            }
+            Ok(count) => IoSlice::advance_slices(&mut bufs, count),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::errors::ParseKeyError;
     use super::*;
+    use crate::YPBankImpl;
+    use crate::options::SizeLimits;
     use rstest::rstest;
     use std::io::{BufReader, Cursor};
 
@@ -530,6 +1614,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_read_from_text_with_buffer_reused() {
+        let records = crate::tests::get_data_to_write();
+
+        let mut data = vec![];
+        for record in &records {
+            record.to_text(&mut data).unwrap();
+            data.push(b'\n');
+        }
+
+        let mut reader = data.as_slice();
+        let mut line = "stale content".to_string();
+
+        for record in &records {
+            assert_eq!(
+                &Record::from_text_with_buffer(&mut reader, &mut line, &ParseContext::default())
+                    .unwrap(),
+                record
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_from_csv_columns_with_buffer_reused() {
+        let records = crate::tests::get_data_to_write();
+        let options = CsvOptions::default();
+
+        let mut data = vec![];
+        for record in &records {
+            record.to_csv(&mut data).unwrap();
+        }
+
+        let mut reader = data.as_slice();
+        let mut line = "stale content".to_string();
+
+        for record in &records {
+            assert_eq!(
+                &Record::from_csv_columns(
+                    &mut reader,
+                    &options,
+                    &options.columns(false),
+                    &mut line,
+                    &ParseContext::default(),
+                )
+                .unwrap(),
+                record
+            );
+        }
+    }
+
     #[test]
     fn test_read_from_text_correct_record_with_comments() {
         let mut reader = BufReader::new(Cursor::new(
@@ -568,6 +1702,48 @@ DESCRIPTION: "Terminal deposit"
         );
     }
 
+    #[rstest]
+    #[case("Payment \"42\"")]
+    #[case("Multi\nline\r\ndescription")]
+    #[case("C:\\tmp\\n")]
+    #[case("\"")]
+    fn test_text_roundtrip_escaped_description(#[case] description: &str) {
+        let mut record = Record::default();
+        record.set_description(description.to_string());
+
+        let mut data = vec![];
+        record.to_text(&mut data).unwrap();
+
+        assert_eq!(data.iter().filter(|&&b| b == b'\n').count(), 8);
+        assert_eq!(Record::from_text(&mut data.as_slice()).unwrap(), record);
+    }
+
+    #[test]
+    fn test_read_from_text_escaped_description() {
+        let data = "TX_ID: 1\nTX_TYPE: DEPOSIT\nFROM_USER_ID: 0\nTO_USER_ID: 2\nAMOUNT: 100\nTIMESTAMP: 1\nSTATUS: SUCCESS\nDESCRIPTION: \"Say \\\"hi\\\"\\nC:\\\\tmp\"\n";
+
+        let result = Record::from_text(&mut data.as_bytes()).unwrap();
+
+        assert_eq!(result.description(), "Say \"hi\"\nC:\\tmp");
+    }
+
+    #[rstest]
+    #[case("\"")]
+    #[case("\"say \"hi\"\"")]
+    #[case("\"C:\\xyz\"")]
+    fn test_read_from_text_invalid_description(#[case] value: &str) {
+        let data = format!(
+            "TX_ID: 1\nTX_TYPE: DEPOSIT\nFROM_USER_ID: 0\nTO_USER_ID: 2\nAMOUNT: 100\nTIMESTAMP: 1\nSTATUS: SUCCESS\nDESCRIPTION: {value}\n"
+        );
+
+        let result = Record::from_text(&mut data.as_bytes());
+
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseRecordFromTxtError::InvalidValue(_)
+        ));
+    }
+
     #[test]
     fn test_read_from_text_incorrect_symbol() {
         let mut reader = BufReader::new(Cursor::new(vec![0xff, 0xff]));
@@ -684,7 +1860,7 @@ DESCRIPTION: "Terminal deposit"
                 "TIMESTAMP: 1623228800",
                 "STATUS: SUCCESS",
                 "DESCRIPTION: \"Terminal deposit\"",
-                "UNEXPECTED_KEY: 1",
+                "UNEXPECTED/KEY: 1",
             ]
             .join("\n"),
         ));
@@ -696,7 +1872,7 @@ DESCRIPTION: "Terminal deposit"
             result,
             ParseRecordFromTxtError::InvalidKey(ParseKeyError::InvalidKey(_))
         ));
-        assert_eq!(result.to_string(), "Invalid key: UNEXPECTED_KEY");
+        assert_eq!(result.to_string(), "Invalid key: UNEXPECTED/KEY");
     }
 
     #[test]
@@ -756,25 +1932,73 @@ DESCRIPTION: "Initial account funding"
     fn test_read_from_csv_correct_record(#[case] line: &str) {
         let mut reader = BufReader::new(Cursor::new(line));
 
-        let result = Record::from_csv(&mut reader);
+        let result = Record::from_csv(&mut reader);
+
+        let record = result.unwrap();
+
+        assert_eq!(
+            record,
+            Record::new(
+                1001,
+                TxType::Deposit,
+                0,
+                501,
+                50000,
+                1672531200000,
+                Status::Success,
+                "Initial account funding".to_string()
+            )
+        );
+    }
+
+    #[rstest]
+    #[case(
+        "1,TRANSFER,501,502,15000,1672534800000,SUCCESS,\"Payment, ref \"\"42\"\"\"",
+        "Payment, ref \"42\""
+    )]
+    #[case(
+        "1,TRANSFER,501,502,15000,1672534800000,SUCCESS,\"Multi\nline, description\"\n",
+        "Multi\nline, description"
+    )]
+    #[case("\"1\",TRANSFER,\"501\",502,15000,1672534800000,SUCCESS,\"\"\r\n", "")]
+    fn test_read_from_csv_quoted_fields(#[case] line: &str, #[case] description: &str) {
+        let mut reader = BufReader::new(Cursor::new(line));
 
-        let record = result.unwrap();
+        let record = Record::from_csv(&mut reader).unwrap();
 
         assert_eq!(
             record,
             Record::new(
-                1001,
-                TxType::Deposit,
-                0,
+                1,
+                TxType::Transfer,
                 501,
-                50000,
-                1672531200000,
+                502,
+                15000,
+                1672534800000,
                 Status::Success,
-                "Initial account funding".to_string()
+                description.to_string()
             )
         );
     }
 
+    #[rstest]
+    #[case(
+        "1,TRANSFER,501,502,15000,1672534800000,SUCCESS,\"Unterminated",
+        "Invalid quoting: unterminated quoted field"
+    )]
+    #[case(
+        "1,TRANSFER,501,502,15000,1672534800000,SUCCESS,\"Closed\" twice\"",
+        "Invalid quoting: unexpected character after closing quote: ' '"
+    )]
+    fn test_read_from_csv_invalid_quoting(#[case] line: &str, #[case] error: &str) {
+        let mut reader = BufReader::new(Cursor::new(line));
+
+        let result = Record::from_csv(&mut reader).unwrap_err();
+
+        assert!(matches!(result, ParseRecordFromCsvError::InvalidQuoting(_)));
+        assert_eq!(result.to_string(), error);
+    }
+
     #[test]
     fn test_read_from_csv_eof() {
         let mut reader = BufReader::new(Cursor::new(vec![]));
@@ -907,6 +2131,57 @@ DESCRIPTION: "Initial account funding"
         )
     }
 
+    #[test]
+    fn test_write_to_csv_escaped_quotes() {
+        let mut record = Record::default();
+        record.set_description("Payment, ref \"42\"".to_string());
+
+        let mut cursor = Cursor::new(Vec::new());
+        assert!(record.to_csv(&mut cursor).is_ok());
+        assert_eq!(
+            cursor.into_inner(),
+            b"0,DEPOSIT,0,0,0,0,SUCCESS,\"Payment, ref \"\"42\"\"\"\n"
+        )
+    }
+
+    #[rstest]
+    #[case("Payment, ref \"42\"")]
+    #[case("Multi\nline\r\ndescription")]
+    #[case("C:\\tmp\\n")]
+    fn test_csv_and_bin_roundtrip_escaped_description(#[case] description: &str) {
+        let mut record = Record::default();
+        record.set_description(description.to_string());
+
+        let mut data = vec![];
+        record.to_csv(&mut data).unwrap();
+        assert_eq!(Record::from_csv(&mut data.as_slice()).unwrap(), record);
+
+        let mut data = vec![];
+        record.to_bin(&mut data).unwrap();
+        assert_eq!(Record::from_bin(&mut data.as_slice()).unwrap(), record);
+    }
+
+    #[test]
+    fn test_write_to_json() {
+        let record = Record::new(
+            1001,
+            TxType::Deposit,
+            0,
+            501,
+            50000,
+            1672531200000,
+            Status::Success,
+            "Initial account funding".to_string(),
+        );
+
+        let mut cursor = Cursor::new(Vec::new());
+        assert!(record.to_json(&mut cursor).is_ok());
+        assert_eq!(
+            cursor.into_inner(),
+            br#"{"TX_ID":1001,"TX_TYPE":"DEPOSIT","FROM_USER_ID":0,"TO_USER_ID":501,"AMOUNT":50000,"TIMESTAMP":1672531200000,"STATUS":"SUCCESS","DESCRIPTION":"Initial account funding"}"#
+        )
+    }
+
     #[test]
     fn test_read_from_bin_correct_record() {
         let mut reader = BufReader::new(Cursor::new(vec![
@@ -986,19 +2261,42 @@ DESCRIPTION: "Initial account funding"
         let result = result.unwrap_err();
         assert!(matches!(
             result,
-            ParseRecordFromBinError::UnexpectedError(_)
+            ParseRecordFromBinError::UnexpectedEof {
+                record_index: 1,
+                expected: 8,
+                got: 0
+            }
         ));
         assert_eq!(
             result.to_string(),
-            "Unexpected error: failed to fill whole buffer"
+            "Unexpected end of data in record 1: expected 8 bytes, got 0 (the data is likely truncated)"
         );
     }
 
+    #[rstest]
+    #[case(&[0x59, 0x50], Ok(2))]
+    #[case(&[0x59, 0x50, 0x42, 0x4E, 0x00, 0x00], Ok(6))]
+    #[case(&[0x59, 0x51], Err("Invalid magic number"))]
+    fn test_read_from_bin_truncated_header(
+        #[case] data: &[u8],
+        #[case] expected: Result<usize, &str>,
+    ) {
+        let result = Record::from_bin(&mut &data[..]).unwrap_err();
+
+        match expected {
+            Ok(got) => assert!(matches!(
+                result,
+                ParseRecordFromBinError::UnexpectedEof { expected: 8, got: actual, .. } if actual == got
+            )),
+            Err(error) => assert_eq!(result.to_string(), error),
+        }
+    }
+
     #[test]
     fn test_read_from_bin_invalid_desc_len() {
         let mut reader = BufReader::new(Cursor::new(vec![
             0x59, 0x50, 0x42, 0x4E, // MAGIC
-            0x00, 0x00, 0x00, 0x3f, // RECORD_SIZE
+            0x00, 0x00, 0x00, 0x3d, // RECORD_SIZE
             0x00, 0x03, 0x8d, 0x7e, 0xa4, 0xc6, 0x80, 0x00, // TX_ID
             0x00, // TX_TYPE
             0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // FROM_USER_ID
@@ -1014,13 +2312,17 @@ DESCRIPTION: "Initial account funding"
         let result = Record::from_bin(&mut reader);
 
         let result = result.unwrap_err();
-        assert!(matches!(
+        assert_eq!(
             result,
-            ParseRecordFromBinError::UnexpectedError(_)
-        ));
+            ParseRecordFromBinError::RecordSizeMismatch {
+                record_size: 61,
+                fixed_len: 46,
+                description_len: 16
+            }
+        );
         assert_eq!(
             result.to_string(),
-            "Unexpected error: true record size is not equal to expected (record size(63) != static length (46) + description length (16))"
+            "Record size 61 is less than the size of fixed fields (46) plus DESCRIPTION_SIZE (16)"
         );
     }
 
@@ -1054,6 +2356,88 @@ DESCRIPTION: "Initial account funding"
         assert_eq!(result.to_string(), "Invalid record size: 32");
     }
 
+    #[test]
+    fn test_read_from_bin_record_too_large() {
+        let data = [0x59, 0x50, 0x42, 0x4E, 0xff, 0xff, 0xff, 0xff];
+
+        let result = Record::from_bin(&mut data.as_slice()).unwrap_err();
+
+        assert_eq!(
+            result,
+            ParseRecordFromBinError::RecordTooLarge {
+                size: u32::MAX,
+                max: 1024 * 1024
+            }
+        );
+        assert_eq!(
+            result.to_string(),
+            "Record size 4294967295 exceeds the limit of 1048576 bytes"
+        );
+    }
+
+    #[rstest]
+    #[case(SizeLimits { max_record_size: 61, ..SizeLimits::DEFAULT }, Ok(()))]
+    #[case(
+        SizeLimits { max_record_size: 60, ..SizeLimits::DEFAULT },
+        Err("Record size 61 exceeds the limit of 60 bytes")
+    )]
+    #[case(SizeLimits { max_description_len: 15, ..SizeLimits::DEFAULT }, Ok(()))]
+    #[case(
+        SizeLimits { max_description_len: 14, ..SizeLimits::DEFAULT },
+        Err("Description length 15 exceeds the limit of 14 bytes")
+    )]
+    fn test_read_from_bin_limits(#[case] limits: SizeLimits, #[case] expected: Result<(), &str>) {
+        let record = Record {
+            description: "Record number".into(),
+            ..Record::default()
+        };
+        let mut data = vec![];
+        record.to_bin(&mut data).unwrap();
+
+        let context = ParseContext {
+            limits,
+            ..ParseContext::default()
+        };
+
+        let result = Record::from_bin_at(&mut data.as_slice(), 1, &context);
+
+        assert_eq!(
+            result.map_err(|e| e.to_string()),
+            expected.map(|_| record).map_err(str::to_string)
+        );
+    }
+
+    #[rstest]
+    #[case(YPBankImpl::Text, format!("TX_ID: 1\nDESCRIPTION: \"{}", "x".repeat(1000)))]
+    #[case(YPBankImpl::Csv, format!("1,DEPOSIT,0,2,100,1,SUCCESS,\"{}", "x\n".repeat(500)))]
+    #[case(YPBankImpl::Csv, ",".repeat(1000))]
+    fn test_read_record_lines_limit(#[case] format: YPBankImpl, #[case] data: String) {
+        let context = ParseContext {
+            limits: SizeLimits {
+                max_record_size: 64,
+                ..SizeLimits::DEFAULT
+            },
+            ..ParseContext::default()
+        };
+        let mut reader = data.as_bytes();
+
+        let result = match format {
+            YPBankImpl::Text => {
+                Record::from_text_with_buffer(&mut reader, &mut String::new(), &context)
+                    .map_err(|e| e.to_string())
+            }
+            _ => Record::from_csv_with_context(&mut reader, &CsvOptions::default(), &context)
+                .map_err(|e| e.to_string()),
+        };
+
+        assert_eq!(
+            result.unwrap_err(),
+            "Record exceeds the limit of 64 bytes".to_string()
+        );
+        // Строки записи не считываются далее ограничения.
+        assert!(data.len() - reader.len() <= 65);
+    }
+
     #[test]
     fn test_read_from_bin_invalid_description_size() {
         let mut reader = BufReader::new(Cursor::new(vec![
@@ -1076,11 +2460,15 @@ DESCRIPTION: "Initial account funding"
         let result = result.unwrap_err();
         assert!(matches!(
             result,
-            ParseRecordFromBinError::UnexpectedError(_)
+            ParseRecordFromBinError::UnexpectedEof {
+                record_index: 1,
+                expected: 71,
+                got: 69
+            }
         ));
         assert_eq!(
             result.to_string(),
-            "Unexpected error: failed to fill whole buffer"
+            "Unexpected end of data in record 1: expected 71 bytes, got 69 (the data is likely truncated)"
         );
     }
 
@@ -1212,4 +2600,436 @@ DESCRIPTION: "Initial account funding"
             ]
         )
     }
+
+    /// Назначение, принимающее за один вызов не более `max_write` байт и подсчитывающее вызовы.
+    struct ChunkedWriter {
+        data: Vec<u8>,
+        max_write: usize,
+        calls: usize,
+    }
+
+    impl Write for ChunkedWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.write_vectored(&[IoSlice::new(buf)])
+        }
+
+        fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+            self.calls += 1;
+
+            let mut written = 0;
+            for buf in bufs {
+                let count = buf.len().min(self.max_write - written);
+                self.data.extend_from_slice(&buf[..count]);
+                written += count;
+            }
+
+            Ok(written)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[rstest]
+    #[case(usize::MAX, 1)]
+    #[case(10, 8)]
+    fn test_write_to_bin_vectored(#[case] max_write: usize, #[case] calls: usize) {
+        let record = record_with_currency();
+        let mut expected = vec![];
+        record.to_bin(&mut expected).unwrap();
+
+        let mut writer = ChunkedWriter {
+            data: vec![],
+            max_write,
+            calls: 0,
+        };
+        record.to_bin(&mut writer).unwrap();
+
+        assert_eq!(writer.data, expected);
+        assert_eq!(writer.calls, calls);
+    }
+
+    fn record_with_currency() -> Record {
+        let mut record = Record::new(
+            1,
+            TxType::Transfer,
+            2,
+            3,
+            100,
+            1633036860000,
+            Status::Success,
+            "Invoice #7".to_string(),
+        );
+        record.set_currency(Some(Currency::try_from("EUR").unwrap()));
+
+        record
+    }
+
+    #[test]
+    fn test_currency_roundtrip() {
+        let record = record_with_currency();
+
+        let mut data = vec![];
+        record.to_text(&mut data).unwrap();
+        assert!(data.ends_with(b"DESCRIPTION: \"Invoice #7\"\nCURRENCY: EUR\n"));
+        assert_eq!(Record::from_text(&mut data.as_slice()).unwrap(), record);
+
+        let mut data = vec![];
+        record.to_csv(&mut data).unwrap();
+        assert_eq!(
+            data,
+            b"1,TRANSFER,2,3,100,1633036860000,SUCCESS,\"Invoice #7\",EUR\n"
+        );
+        assert_eq!(Record::from_csv(&mut data.as_slice()).unwrap(), record);
+
+        let mut data = vec![];
+        record.to_bin(&mut data).unwrap();
+        assert!(data.ends_with(&[0x01, 0x00, 0x03, b'E', b'U', b'R']));
+        assert_eq!(Record::from_bin(&mut data.as_slice()).unwrap(), record);
+    }
+
+    #[rstest]
+    #[case("1,DEPOSIT,0,2,100,1,SUCCESS,\"a\",", None)]
+    #[case("1,DEPOSIT,0,2,100,1,SUCCESS,\"a\",USD", Some("USD"))]
+    fn test_read_from_csv_currency(#[case] line: &str, #[case] currency: Option<&str>) {
+        let record = Record::from_csv(&mut line.as_bytes()).unwrap();
+
+        assert_eq!(
+            record.currency(),
+            currency.map(|code| Currency::try_from(code).unwrap())
+        );
+    }
+
+    #[rstest]
+    #[case("CURRENCY: usd")]
+    #[case("CURRENCY: DOLLAR")]
+    fn test_read_from_text_invalid_currency(#[case] line: &str) {
+        let data = format!(
+            "TX_ID: 1\nTX_TYPE: DEPOSIT\nFROM_USER_ID: 0\nTO_USER_ID: 2\nAMOUNT: 100\nTIMESTAMP: 1\nSTATUS: SUCCESS\nDESCRIPTION: \"\"\n{line}\n"
+        );
+
+        let result = Record::from_text(&mut data.as_bytes()).unwrap_err();
+
+        assert!(
+            result
+                .to_string()
+                .ends_with(&format!("(Invalid CURRENCY: {})", &line[10..]))
+        );
+    }
+
+    #[test]
+    fn test_read_from_bin_unknown_extension() {
+        let record = record_with_currency();
+
+        let mut data = vec![];
+        record.to_bin(&mut data).unwrap();
+
+        // Расширение с неизвестным тегом 0xff и длиной 2 перед расширением валюты.
+        let record_size = u32::from_be_bytes(data[4..8].try_into().unwrap()) + 5;
+        data[4..8].copy_from_slice(&record_size.to_be_bytes());
+        let position = data.len() - 6;
+        data.splice(position..position, [0xff, 0x00, 0x02, 0xab, 0xcd]);
+
+        assert_eq!(Record::from_bin(&mut data.as_slice()).unwrap(), record);
+    }
+
+    #[rstest]
+    #[case(&[0x01, 0x00, 0x03, b'e', b'u', b'r'], "Invalid value: eur (Invalid CURRENCY: eur)")]
+    #[case(
+        &[0x01, 0x00, 0x04, b'E', b'U', b'R'],
+        "Unexpected error: extension 1 length (4) exceeds the record size"
+    )]
+    #[case(&[0x01, 0x00], "Unexpected error: failed to fill whole buffer")]
+    fn test_read_from_bin_invalid_extension(#[case] extension: &[u8], #[case] error: &str) {
+        let mut data = vec![];
+        Record::default().to_bin(&mut data).unwrap();
+
+        let record_size =
+            u32::from_be_bytes(data[4..8].try_into().unwrap()) + extension.len() as u32;
+        data[4..8].copy_from_slice(&record_size.to_be_bytes());
+        data.extend_from_slice(extension);
+
+        assert_eq!(
+            Record::from_bin(&mut data.as_slice())
+                .unwrap_err()
+                .to_string(),
+            error
+        );
+    }
+
+    #[test]
+    fn test_hash_stable_currency() {
+        let record = record_with_currency();
+
+        let mut without_currency = record.clone();
+        without_currency.set_currency(None);
+
+        let mut canonical = vec![];
+        record.write_canonical(&mut canonical);
+
+        let mut prefix = vec![];
+        without_currency.write_canonical(&mut prefix);
+
+        assert_eq!(canonical, [prefix, vec![1, b'E', b'U', b'R']].concat());
+        assert_ne!(record.hash_stable(), without_currency.hash_stable());
+    }
+
+    fn record_with_metadata() -> Record {
+        let mut record = record_with_currency();
+        record.metadata_mut().extend(
+            [("VENDOR_ID", "42"), ("mcc", "5411, grocery")]
+                .map(|(k, v)| (k.to_string(), v.to_string())),
+        );
+
+        record
+    }
+
+    #[test]
+    fn test_metadata_roundtrip() {
+        let record = record_with_metadata();
+
+        let mut data = vec![];
+        record.to_text(&mut data).unwrap();
+        assert!(data.ends_with(b"CURRENCY: EUR\nVENDOR_ID: 42\nmcc: 5411, grocery\n"));
+        assert_eq!(Record::from_text(&mut data.as_slice()).unwrap(), record);
+
+        let mut data = vec![];
+        record.to_bin(&mut data).unwrap();
+        assert_eq!(Record::from_bin(&mut data.as_slice()).unwrap(), record);
+
+        let mut data = vec![];
+        record.to_json(&mut data).unwrap();
+        assert!(
+            String::from_utf8(data.clone())
+                .unwrap()
+                .contains("\"METADATA\":{")
+        );
+        assert_eq!(serde_json::from_slice::<Record>(&data).unwrap(), record);
+    }
+
+    #[rstest]
+    #[case(false, Err("Invalid TX_TYPE: CHARGEBACK at line 1 column 36".to_string()))]
+    #[case(true, Ok(TxType::Unknown("CHARGEBACK".to_string())))]
+    fn test_deserialize_with_lenient(
+        #[case] lenient: bool,
+        #[case] expected: Result<TxType, String>,
+    ) {
+        let data = r#"{"TX_ID": 1, "TX_TYPE": "CHARGEBACK", "FROM_USER_ID": 0, "TO_USER_ID": 1,
+            "AMOUNT": 10, "TIMESTAMP": 0, "STATUS": "SUCCESS", "DESCRIPTION": ""}"#;
+        let context = ParseContext {
+            lenient,
+            ..ParseContext::default()
+        };
+
+        let result =
+            Record::deserialize_with(&mut serde_json::Deserializer::from_str(data), &context);
+
+        assert_eq!(
+            result
+                .map(|record| record.tx_type().clone())
+                .map_err(|e| e.to_string()),
+            expected
+        );
+        // Нестрогое чтение не влияет на разбор типа транзакции вне контекста.
+        assert!(TxType::try_from("CHARGEBACK").is_err());
+    }
+
+    #[test]
+    fn test_read_from_text_metadata_lines() {
+        let data = "TX_ID: 1\nX-Vendor.Ref: abc: def\nTX_TYPE: DEPOSIT\nFROM_USER_ID: 0\nTO_USER_ID: 2\nAMOUNT: 100\nTIMESTAMP: 1\nSTATUS: SUCCESS\nDESCRIPTION: \"\"\n";
+
+        let record = Record::from_text(&mut data.as_bytes()).unwrap();
+
+        assert_eq!(
+            record.metadata(),
+            &HashMap::from([("X-Vendor.Ref".to_string(), "abc: def".to_string())])
+        );
+    }
+
+    #[rstest]
+    #[case("bad key", "1")]
+    #[case("AMOUNT", "1")]
+    #[case("NOTE", "multi\nline")]
+    fn test_write_to_text_invalid_metadata(#[case] key: &str, #[case] value: &str) {
+        let mut record = Record::default();
+        record
+            .metadata_mut()
+            .insert(key.to_string(), value.to_string());
+
+        let result = record.to_text(&mut vec![]).unwrap_err();
+
+        assert_eq!(
+            result.to_string(),
+            format!("metadata field `{key}` cannot be written in the text format")
+        );
+    }
+
+    #[test]
+    fn test_write_to_bin_unknown_tx_type() {
+        let mut record = Record::default();
+        record.set_tx_type(TxType::Unknown("CHARGEBACK".to_string()));
+
+        let result = record.to_bin(&mut vec![]).unwrap_err();
+
+        assert_eq!(
+            result.to_string(),
+            "TX_TYPE `CHARGEBACK` cannot be written in the binary format"
+        );
+    }
+
+    #[test]
+    fn test_write_to_bin_other_status() {
+        let mut record = Record::default();
+        record.set_status(Status::Other("ON_HOLD".to_string()));
+
+        let result = record.to_bin(&mut vec![]).unwrap_err();
+
+        assert_eq!(
+            result.to_string(),
+            "STATUS `ON_HOLD` cannot be written in the binary format"
+        );
+    }
+
+    #[test]
+    fn test_hash_stable_unknown_tx_type() {
+        let mut coded = Record::default();
+        coded.set_tx_type(TxType::Unknown("7".to_string()));
+        let mut named = Record::default();
+        named.set_tx_type(TxType::Unknown("CHARGEBACK".to_string()));
+
+        assert_ne!(coded.hash_stable(), Record::default().hash_stable());
+        assert_ne!(coded.hash_stable(), named.hash_stable());
+    }
+
+    #[test]
+    fn test_write_to_csv_metadata_columns() {
+        let record = record_with_metadata();
+        let options = CsvOptions {
+            metadata_columns: vec!["mcc".to_string(), "MISSING".to_string()],
+            ..CsvOptions::default()
+        };
+
+        let mut data = vec![];
+        record.to_csv_with(&mut data, &options).unwrap();
+
+        assert_eq!(
+            data,
+            b"1,TRANSFER,2,3,100,1633036860000,SUCCESS,\"Invoice #7\",EUR,\"5411, grocery\",\n"
+        );
+
+        let result = Record::from_csv_with(&mut data.as_slice(), &options).unwrap();
+        assert_eq!(
+            result.metadata(),
+            &HashMap::from([("mcc".to_string(), "5411, grocery".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_read_from_bin_invalid_metadata() {
+        let mut data = vec![];
+        Record::default().to_bin(&mut data).unwrap();
+
+        let extension = [0x02, 0x00, 0x03, 0x00, 0x05, b'K'];
+        let record_size =
+            u32::from_be_bytes(data[4..8].try_into().unwrap()) + extension.len() as u32;
+        data[4..8].copy_from_slice(&record_size.to_be_bytes());
+        data.extend_from_slice(&extension);
+
+        assert_eq!(
+            Record::from_bin(&mut data.as_slice())
+                .unwrap_err()
+                .to_string(),
+            "Unexpected error: metadata key length (5) exceeds the extension size"
+        );
+    }
+
+    #[rstest]
+    #[case(-2500, "-2500")]
+    #[case(i128::from(u64::MAX) + 1, "18446744073709551616")]
+    #[case(i128::MIN, "-170141183460469231731687303715884105728")]
+    fn test_wide_amount_roundtrip(#[case] amount: i128, #[case] text: &str) {
+        let mut record = record_with_currency();
+        record.set_wide_amount(amount);
+        assert_eq!(record.schema_version(), SchemaVersion::V2);
+
+        let mut data = vec![];
+        record.to_text(&mut data).unwrap();
+        assert!(String::from_utf8_lossy(&data).contains(&format!("AMOUNT: {text}\n")));
+        assert_eq!(Record::from_text(&mut data.as_slice()).unwrap(), record);
+
+        let mut data = vec![];
+        record.to_csv(&mut data).unwrap();
+        assert!(String::from_utf8_lossy(&data).contains(&format!(",{text},")));
+        assert_eq!(Record::from_csv(&mut data.as_slice()).unwrap(), record);
+
+        let mut data = vec![];
+        record.to_json(&mut data).unwrap();
+        assert!(String::from_utf8_lossy(&data).contains(&format!("\"AMOUNT\":{text},")));
+
+        assert!(record.to_bin(&mut vec![]).is_err());
+
+        let mut data = vec![];
+        record
+            .to_bin_with_schema(&mut data, SchemaVersion::V2)
+            .unwrap();
+        assert_eq!(&data[33..41], &[0; 8]);
+        let mut extension = vec![0x03, 0x00, 0x10];
+        extension.extend_from_slice(&amount.to_be_bytes());
+        assert!(data.ends_with(&extension));
+        assert_eq!(Record::from_bin(&mut data.as_slice()).unwrap(), record);
+    }
+
+    #[test]
+    fn test_schema_version() {
+        let mut record = Record::default();
+        assert_eq!(record.schema_version(), SchemaVersion::V1);
+
+        record.set_wide_amount(i128::from(u64::MAX));
+        assert_eq!(record.schema_version(), SchemaVersion::V1);
+
+        record.set_wide_amount(-1);
+        assert_eq!(record.schema_version(), SchemaVersion::V2);
+    }
+
+    #[test]
+    fn test_read_from_bin_invalid_wide_amount() {
+        let mut data = vec![];
+        Record::default().to_bin(&mut data).unwrap();
+
+        let extension = [0x03, 0x00, 0x02, 0xFF, 0xFF];
+        let record_size =
+            u32::from_be_bytes(data[4..8].try_into().unwrap()) + extension.len() as u32;
+        data[4..8].copy_from_slice(&record_size.to_be_bytes());
+        data.extend_from_slice(&extension);
+
+        assert_eq!(
+            Record::from_bin(&mut data.as_slice())
+                .unwrap_err()
+                .to_string(),
+            "Unexpected error: wide AMOUNT extension has invalid length (2)"
+        );
+    }
+
+    #[test]
+    fn test_hash_stable_wide_amount() {
+        let mut record = Record::default();
+        record.set_wide_amount(-1);
+
+        let mut wide = record.clone();
+        wide.set_wide_amount(i128::from(u64::MAX) + 1);
+
+        assert_ne!(record.hash_stable(), Record::default().hash_stable());
+        assert_ne!(record.hash_stable(), wide.hash_stable());
+    }
+
+    #[test]
+    fn test_write_to_bin_metadata_too_long() {
+        let mut record = Record::default();
+        record
+            .metadata_mut()
+            .insert("NOTE".to_string(), "x".repeat(u16::MAX as usize));
+
+        assert!(record.to_bin(&mut vec![]).is_err());
+    }
 }
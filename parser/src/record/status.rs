@@ -1,10 +1,16 @@
 //! Модуль описания возможных состояний транзакции.
 
 use super::errors::ParseStatusError;
+use crate::options::is_lenient_value;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
 /// Состояние транзакции.
-#[derive(Debug, Clone, Copy, PartialEq)]
+///
+/// Порядок состояний соответствует их кодам в бинарном формате; неизвестные состояния следуют
+/// за ними.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub enum Status {
     /// Успех.
     Success,
@@ -14,6 +20,64 @@ pub enum Status {
 
     /// В процессе.
     Pending,
+
+    /// Отменена.
+    Cancelled,
+
+    /// Сторнирована.
+    Reversed,
+
+    /// Неизвестное состояние, считанное при нестрогом чтении (см. [`crate::ParseOptions::lenient`]).
+    ///
+    /// Содержит значение из источника: название в текстовых форматах или десятичный код
+    /// в бинарных форматах. Записать в бинарные форматы можно только состояние с числовым кодом.
+    Other(String),
+}
+
+impl Status {
+    /// Известные состояния транзакций в порядке их кодов в бинарном формате.
+    pub const KNOWN: [Status; 5] = [
+        Self::Success,
+        Self::Failure,
+        Self::Pending,
+        Self::Cancelled,
+        Self::Reversed,
+    ];
+
+    /// Получить состояние транзакции по его названию.
+    ///
+    /// При нестрогом чтении (см. [`crate::ParseOptions::lenient`]) неизвестное название
+    /// считывается как [`Status::Other`], а десятичный код — так же, как в бинарном формате.
+    pub(crate) fn from_name(s: &str, lenient: bool) -> Result<Self, ParseStatusError> {
+        match s {
+            "SUCCESS" => Ok(Self::Success),
+            "FAILURE" => Ok(Self::Failure),
+            "PENDING" => Ok(Self::Pending),
+            "CANCELLED" => Ok(Self::Cancelled),
+            "REVERSED" => Ok(Self::Reversed),
+            _ if lenient => match s.parse::<u8>() {
+                Ok(code) => Self::from_code(code, lenient),
+                Err(_) if is_lenient_value(s) => Ok(Self::Other(s.to_string())),
+                Err(_) => Err(ParseStatusError::InvalidStatus(s.to_string())),
+            },
+            _ => Err(ParseStatusError::InvalidStatus(s.to_string())),
+        }
+    }
+
+    /// Получить состояние транзакции по его коду в бинарном формате.
+    ///
+    /// При нестрогом чтении неизвестный код считывается как [`Status::Other`].
+    pub(crate) fn from_code(value: u8, lenient: bool) -> Result<Self, ParseStatusError> {
+        match value {
+            0 => Ok(Self::Success),
+            1 => Ok(Self::Failure),
+            2 => Ok(Self::Pending),
+            3 => Ok(Self::Cancelled),
+            4 => Ok(Self::Reversed),
+            _ if lenient => Ok(Self::Other(value.to_string())),
+            _ => Err(ParseStatusError::InvalidStatus(value.to_string())),
+        }
+    }
 }
 
 /// Реализация трейта [`fmt::Display`] для [`Status`].
@@ -24,6 +88,9 @@ impl fmt::Display for Status {
             Self::Success => "SUCCESS",
             Self::Failure => "FAILURE",
             Self::Pending => "PENDING",
+            Self::Cancelled => "CANCELLED",
+            Self::Reversed => "REVERSED",
+            Self::Other(value) => value,
         };
 
         write!(f, "{s}")
@@ -31,45 +98,68 @@ impl fmt::Display for Status {
 }
 
 /// Реализация трейта [`TryFrom<&str>`] для [`Status`].
+///
+/// Допускаются только известные состояния транзакций.
 impl TryFrom<&str> for Status {
     /// Ошибка парсинга состояния транзакции.
     type Error = ParseStatusError;
 
     /// Реализация метода [`TryFrom<&str>::try_from`] для [`Status`].
     fn try_from(s: &str) -> Result<Self, Self::Error> {
-        match s {
-            "SUCCESS" => Ok(Self::Success),
-            "FAILURE" => Ok(Self::Failure),
-            "PENDING" => Ok(Self::Pending),
-            _ => Err(ParseStatusError::InvalidStatus(s.to_string())),
-        }
+        Self::from_name(s, false)
+    }
+}
+
+/// Реализация трейта [`TryFrom<String>`] для [`Status`].
+impl TryFrom<String> for Status {
+    /// Ошибка парсинга состояния транзакции.
+    type Error = ParseStatusError;
+
+    /// Реализация метода [`TryFrom<String>::try_from`] для [`Status`].
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_str())
+    }
+}
+
+/// Реализация трейта [`From<Status>`] для [`String`].
+impl From<Status> for String {
+    /// Реализация метода [`From<Status>::from`] для [`String`].
+    fn from(value: Status) -> Self {
+        value.to_string()
     }
 }
 
 /// Реализация трейта [`TryFrom<u8>`] для [`Status`].
+///
+/// Допускаются только коды известных состояний транзакций.
 impl TryFrom<u8> for Status {
     /// Ошибка парсинга состояния транзакции.
     type Error = ParseStatusError;
 
     /// Реализация метода [`TryFrom<u8>::try_from`] для [`Status`].
     fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            0 => Ok(Self::Success),
-            1 => Ok(Self::Failure),
-            2 => Ok(Self::Pending),
-            _ => Err(ParseStatusError::InvalidStatus(value.to_string())),
-        }
+        Self::from_code(value, false)
     }
 }
 
-/// Реализация трейта [`From<Status>`] для [`u8`].
-impl From<Status> for u8 {
-    /// Реализация метода [`From<Status>::from`] для [`u8`].
-    fn from(value: Status) -> Self {
+/// Реализация трейта [`TryFrom<&Status>`] для [`u8`].
+///
+/// Неизвестное состояние транзакции имеет код, только если оно было считано из бинарного формата.
+impl TryFrom<&Status> for u8 {
+    /// Ошибка парсинга состояния транзакции.
+    type Error = ParseStatusError;
+
+    /// Реализация метода [`TryFrom<&Status>::try_from`] для [`u8`].
+    fn try_from(value: &Status) -> Result<Self, Self::Error> {
         match value {
-            Status::Success => 0,
-            Status::Failure => 1,
-            Status::Pending => 2,
+            Status::Success => Ok(0),
+            Status::Failure => Ok(1),
+            Status::Pending => Ok(2),
+            Status::Cancelled => Ok(3),
+            Status::Reversed => Ok(4),
+            Status::Other(value) => value
+                .parse()
+                .map_err(|_| ParseStatusError::InvalidStatus(value.clone())),
         }
     }
 }
@@ -77,12 +167,16 @@ impl From<Status> for u8 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rstest::rstest;
 
     #[test]
     fn test_display() {
         assert_eq!(Status::Success.to_string(), "SUCCESS");
         assert_eq!(Status::Failure.to_string(), "FAILURE");
         assert_eq!(Status::Pending.to_string(), "PENDING");
+        assert_eq!(Status::Cancelled.to_string(), "CANCELLED");
+        assert_eq!(Status::Reversed.to_string(), "REVERSED");
+        assert_eq!(Status::Other("ON_HOLD".to_string()).to_string(), "ON_HOLD");
     }
 
     #[test]
@@ -90,6 +184,8 @@ mod tests {
         assert_eq!(Status::try_from("SUCCESS").unwrap(), Status::Success);
         assert_eq!(Status::try_from("FAILURE").unwrap(), Status::Failure);
         assert_eq!(Status::try_from("PENDING").unwrap(), Status::Pending);
+        assert_eq!(Status::try_from("CANCELLED").unwrap(), Status::Cancelled);
+        assert_eq!(Status::try_from("REVERSED").unwrap(), Status::Reversed);
         assert!(Status::try_from("").is_err_and(|e| e.to_string() == "Invalid STATUS: "));
         assert!(
             Status::try_from("INVALID").is_err_and(|e| e.to_string() == "Invalid STATUS: INVALID")
@@ -101,14 +197,44 @@ mod tests {
         assert_eq!(Status::try_from(0).unwrap(), Status::Success);
         assert_eq!(Status::try_from(1).unwrap(), Status::Failure);
         assert_eq!(Status::try_from(2).unwrap(), Status::Pending);
+        assert_eq!(Status::try_from(3).unwrap(), Status::Cancelled);
+        assert_eq!(Status::try_from(4).unwrap(), Status::Reversed);
 
-        assert!(Status::try_from(3).is_err_and(|e| e.to_string() == "Invalid STATUS: 3"));
+        assert!(Status::try_from(5).is_err_and(|e| e.to_string() == "Invalid STATUS: 5"));
     }
 
     #[test]
     fn test_into_u8() {
-        assert_eq!(u8::from(Status::Success), 0);
-        assert_eq!(u8::from(Status::Failure), 1);
-        assert_eq!(u8::from(Status::Pending), 2);
+        for (code, status) in Status::KNOWN.iter().enumerate() {
+            assert_eq!(u8::try_from(status).unwrap(), code as u8);
+        }
+
+        assert_eq!(u8::try_from(&Status::Other("9".to_string())).unwrap(), 9);
+        assert!(
+            u8::try_from(&Status::Other("ON_HOLD".to_string()))
+                .is_err_and(|e| e.to_string() == "Invalid STATUS: ON_HOLD")
+        );
+    }
+
+    #[rstest]
+    #[case("ON_HOLD", Ok(Status::Other("ON_HOLD".to_string())))]
+    #[case("9", Ok(Status::Other("9".to_string())))]
+    #[case("3", Ok(Status::Cancelled))]
+    #[case("REVERSED", Ok(Status::Reversed))]
+    #[case("", Err("Invalid STATUS: "))]
+    #[case("ON HOLD", Err("Invalid STATUS: ON HOLD"))]
+    fn test_try_from_string_lenient(#[case] value: &str, #[case] expected: Result<Status, &str>) {
+        let result = Status::from_name(value, true).map_err(|e| e.to_string());
+
+        assert_eq!(result, expected.map_err(str::to_string));
+    }
+
+    #[test]
+    fn test_try_from_u8_lenient() {
+        assert_eq!(
+            Status::from_code(200, true).unwrap(),
+            Status::Other("200".to_string())
+        );
+        assert!(Status::try_from(200).is_err());
     }
 }
@@ -1,10 +1,15 @@
 //! Модуль описания возможных типов транзакции.
 
 use super::errors::ParseTxTypeError;
+use crate::options::is_lenient_value;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
 /// Тип транзакции.
-#[derive(Debug, Clone, Copy, PartialEq)]
+///
+/// Порядок типов соответствует их кодам в бинарном формате; неизвестные типы следуют за ними.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub enum TxType {
     /// Депозит.
     Deposit,
@@ -14,6 +19,70 @@ pub enum TxType {
 
     /// Обналичивание.
     Withdrawal,
+
+    /// Возврат средств.
+    Refund,
+
+    /// Комиссия.
+    Fee,
+
+    /// Сторнирование.
+    Reversal,
+
+    /// Неизвестный тип, считанный при нестрогом чтении (см. [`crate::ParseOptions::lenient`]).
+    ///
+    /// Содержит значение из источника: название в текстовых форматах или десятичный код
+    /// в бинарных форматах. Записать в бинарные форматы можно только тип с числовым кодом.
+    Unknown(String),
+}
+
+impl TxType {
+    /// Известные типы транзакций в порядке их кодов в бинарном формате.
+    pub const KNOWN: [TxType; 6] = [
+        Self::Deposit,
+        Self::Transfer,
+        Self::Withdrawal,
+        Self::Refund,
+        Self::Fee,
+        Self::Reversal,
+    ];
+
+    /// Получить тип транзакции по его названию.
+    ///
+    /// При нестрогом чтении (см. [`crate::ParseOptions::lenient`]) неизвестное название
+    /// считывается как [`TxType::Unknown`], а десятичный код — так же, как в бинарном формате.
+    pub(crate) fn from_name(value: &str, lenient: bool) -> Result<Self, ParseTxTypeError> {
+        match value {
+            "DEPOSIT" => Ok(Self::Deposit),
+            "TRANSFER" => Ok(Self::Transfer),
+            "WITHDRAWAL" => Ok(Self::Withdrawal),
+            "REFUND" => Ok(Self::Refund),
+            "FEE" => Ok(Self::Fee),
+            "REVERSAL" => Ok(Self::Reversal),
+            _ if lenient => match value.parse::<u8>() {
+                Ok(code) => Self::from_code(code, lenient),
+                Err(_) if is_lenient_value(value) => Ok(Self::Unknown(value.to_string())),
+                Err(_) => Err(ParseTxTypeError::InvalidTxType(value.to_string())),
+            },
+            _ => Err(ParseTxTypeError::InvalidTxType(value.to_string())),
+        }
+    }
+
+    /// Получить тип транзакции по его коду в бинарном формате.
+    ///
+    /// При нестрогом чтении неизвестный код считывается как [`TxType::Unknown`].
+    pub(crate) fn from_code(value: u8, lenient: bool) -> Result<Self, ParseTxTypeError> {
+        match value {
+            0 => Ok(Self::Deposit),
+            1 => Ok(Self::Transfer),
+            2 => Ok(Self::Withdrawal),
+            3 => Ok(Self::Refund),
+            4 => Ok(Self::Fee),
+            5 => Ok(Self::Reversal),
+            _ if lenient => Ok(Self::Unknown(value.to_string())),
+            _ => Err(ParseTxTypeError::InvalidTxType(value.to_string())),
+        }
+    }
 }
 
 /// Реализация трейта [`fmt::Display`] для [`TxType`].
@@ -24,6 +93,10 @@ impl fmt::Display for TxType {
             Self::Deposit => "DEPOSIT",
             Self::Transfer => "TRANSFER",
             Self::Withdrawal => "WITHDRAWAL",
+            Self::Refund => "REFUND",
+            Self::Fee => "FEE",
+            Self::Reversal => "REVERSAL",
+            Self::Unknown(value) => value,
         };
 
         write!(f, "{s}")
@@ -31,45 +104,69 @@ impl fmt::Display for TxType {
 }
 
 /// Реализация трейта [`TryFrom<&str>`] для [`TxType`].
+///
+/// Допускаются только известные типы транзакций.
 impl TryFrom<&str> for TxType {
     /// Ошибка парсинга типа транзакции.
     type Error = ParseTxTypeError;
 
     /// Реализация метода [`TryFrom<&str>::try_from`] для [`TxType`].
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        match value {
-            "DEPOSIT" => Ok(Self::Deposit),
-            "TRANSFER" => Ok(Self::Transfer),
-            "WITHDRAWAL" => Ok(Self::Withdrawal),
-            _ => Err(ParseTxTypeError::InvalidTxType(value.to_string())),
-        }
+        Self::from_name(value, false)
+    }
+}
+
+/// Реализация трейта [`TryFrom<String>`] для [`TxType`].
+impl TryFrom<String> for TxType {
+    /// Ошибка парсинга типа транзакции.
+    type Error = ParseTxTypeError;
+
+    /// Реализация метода [`TryFrom<String>::try_from`] для [`TxType`].
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_str())
+    }
+}
+
+/// Реализация трейта [`From<TxType>`] для [`String`].
+impl From<TxType> for String {
+    /// Реализация метода [`From<TxType>::from`] для [`String`].
+    fn from(value: TxType) -> Self {
+        value.to_string()
     }
 }
 
 /// Реализация трейта [`TryFrom<u8>`] для [`TxType`].
+///
+/// Допускаются только коды известных типов транзакций.
 impl TryFrom<u8> for TxType {
     /// Ошибка парсинга типа транзакции.
     type Error = ParseTxTypeError;
 
     /// Реализация метода [`TryFrom<u8>::try_from`] для [`TxType`].
     fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            0 => Ok(Self::Deposit),
-            1 => Ok(Self::Transfer),
-            2 => Ok(Self::Withdrawal),
-            _ => Err(ParseTxTypeError::InvalidTxType(value.to_string())),
-        }
+        Self::from_code(value, false)
     }
 }
 
-/// Реализация трейта [`From<TxType>`] для [`u8`].
-impl From<TxType> for u8 {
-    /// Реализация метода [`From<TxType>::from`] для [`u8`].
-    fn from(value: TxType) -> Self {
+/// Реализация трейта [`TryFrom<&TxType>`] для [`u8`].
+///
+/// Неизвестный тип транзакции имеет код, только если он был считан из бинарного формата.
+impl TryFrom<&TxType> for u8 {
+    /// Ошибка парсинга типа транзакции.
+    type Error = ParseTxTypeError;
+
+    /// Реализация метода [`TryFrom<&TxType>::try_from`] для [`u8`].
+    fn try_from(value: &TxType) -> Result<Self, Self::Error> {
         match value {
-            TxType::Deposit => 0,
-            TxType::Transfer => 1,
-            TxType::Withdrawal => 2,
+            TxType::Deposit => Ok(0),
+            TxType::Transfer => Ok(1),
+            TxType::Withdrawal => Ok(2),
+            TxType::Refund => Ok(3),
+            TxType::Fee => Ok(4),
+            TxType::Reversal => Ok(5),
+            TxType::Unknown(value) => value
+                .parse()
+                .map_err(|_| ParseTxTypeError::InvalidTxType(value.clone())),
         }
     }
 }
@@ -77,12 +174,20 @@ impl From<TxType> for u8 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rstest::rstest;
 
     #[test]
     fn test_display() {
         assert_eq!(TxType::Deposit.to_string(), "DEPOSIT");
         assert_eq!(TxType::Transfer.to_string(), "TRANSFER");
         assert_eq!(TxType::Withdrawal.to_string(), "WITHDRAWAL");
+        assert_eq!(TxType::Refund.to_string(), "REFUND");
+        assert_eq!(TxType::Fee.to_string(), "FEE");
+        assert_eq!(TxType::Reversal.to_string(), "REVERSAL");
+        assert_eq!(
+            TxType::Unknown("CHARGEBACK".to_string()).to_string(),
+            "CHARGEBACK"
+        );
     }
 
     #[test]
@@ -90,11 +195,15 @@ mod tests {
         assert_eq!(TxType::try_from("DEPOSIT").unwrap(), TxType::Deposit);
         assert_eq!(TxType::try_from("TRANSFER").unwrap(), TxType::Transfer);
         assert_eq!(TxType::try_from("WITHDRAWAL").unwrap(), TxType::Withdrawal);
+        assert_eq!(TxType::try_from("REFUND").unwrap(), TxType::Refund);
+        assert_eq!(TxType::try_from("FEE").unwrap(), TxType::Fee);
+        assert_eq!(TxType::try_from("REVERSAL").unwrap(), TxType::Reversal);
 
         assert!(TxType::try_from("").is_err_and(|e| e.to_string() == "Invalid TX_TYPE: "));
         assert!(
             TxType::try_from("INVALID").is_err_and(|e| e.to_string() == "Invalid TX_TYPE: INVALID")
         );
+        assert!(TxType::try_from("7").is_err_and(|e| e.to_string() == "Invalid TX_TYPE: 7"));
     }
 
     #[test]
@@ -102,14 +211,45 @@ mod tests {
         assert_eq!(TxType::try_from(0).unwrap(), TxType::Deposit);
         assert_eq!(TxType::try_from(1).unwrap(), TxType::Transfer);
         assert_eq!(TxType::try_from(2).unwrap(), TxType::Withdrawal);
+        assert_eq!(TxType::try_from(3).unwrap(), TxType::Refund);
+        assert_eq!(TxType::try_from(4).unwrap(), TxType::Fee);
+        assert_eq!(TxType::try_from(5).unwrap(), TxType::Reversal);
 
-        assert!(TxType::try_from(3).is_err_and(|e| e.to_string() == "Invalid TX_TYPE: 3"));
+        assert!(TxType::try_from(6).is_err_and(|e| e.to_string() == "Invalid TX_TYPE: 6"));
     }
 
     #[test]
     fn test_into_u8() {
-        assert_eq!(u8::from(TxType::Deposit), 0);
-        assert_eq!(u8::from(TxType::Transfer), 1);
-        assert_eq!(u8::from(TxType::Withdrawal), 2);
+        for (code, tx_type) in TxType::KNOWN.iter().enumerate() {
+            assert_eq!(u8::try_from(tx_type).unwrap(), code as u8);
+        }
+
+        assert_eq!(u8::try_from(&TxType::Unknown("7".to_string())).unwrap(), 7);
+        assert!(
+            u8::try_from(&TxType::Unknown("CHARGEBACK".to_string()))
+                .is_err_and(|e| e.to_string() == "Invalid TX_TYPE: CHARGEBACK")
+        );
+    }
+
+    #[rstest]
+    #[case("CHARGEBACK", Ok(TxType::Unknown("CHARGEBACK".to_string())))]
+    #[case("7", Ok(TxType::Unknown("7".to_string())))]
+    #[case("3", Ok(TxType::Refund))]
+    #[case("FEE", Ok(TxType::Fee))]
+    #[case("", Err("Invalid TX_TYPE: "))]
+    #[case("NEW TYPE", Err("Invalid TX_TYPE: NEW TYPE"))]
+    fn test_try_from_string_lenient(#[case] value: &str, #[case] expected: Result<TxType, &str>) {
+        let result = TxType::from_name(value, true).map_err(|e| e.to_string());
+
+        assert_eq!(result, expected.map_err(str::to_string));
+    }
+
+    #[test]
+    fn test_try_from_u8_lenient() {
+        assert_eq!(
+            TxType::from_code(200, true).unwrap(),
+            TxType::Unknown("200".to_string())
+        );
+        assert!(TxType::try_from(200).is_err());
     }
 }
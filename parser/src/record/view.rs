@@ -0,0 +1,401 @@
+//! Модуль заимствующего представления записи о транзакции, разбираемой из данных
+//! в бинарном формате без копирования строковых полей.
+
+use super::errors::{ParseRecordFromBinError, ParseStatusError, ParseTxTypeError, ParseValueError};
+use super::{Currency, Record, RecordKey, Status, TxType};
+use crate::options::{ParseContext, Projection};
+use byteorder::{BigEndian, ReadBytesExt};
+
+/// Представление записи о транзакции, заимствующее описание и дополнительные поля из данных
+/// в бинарном формате.
+///
+/// В отличие от [`Record`], не выделяет память под строковые поля, поэтому подходит
+/// для просмотра больших объемов данных, например, отображенных в память архивов.
+/// Полноценная запись получается преобразованием в [`Record`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordRef<'a> {
+    /// Идентификатор транзакции.
+    tx_id: u64,
+
+    /// Тип транзакции.
+    tx_type: TxType,
+
+    /// Идентификатор отправителя.
+    from_user_id: u64,
+
+    /// Идентификатор получателя.
+    to_user_id: u64,
+
+    /// Сумма транзакции в наименьшей единице валюты.
+    amount: i128,
+
+    /// Unix epoch timestamp в миллисекундах.
+    timestamp: u64,
+
+    /// Состояние транзакции.
+    status: Status,
+
+    /// Описание транзакции без кавычек.
+    description: &'a str,
+
+    /// Необязательная валюта транзакции.
+    currency: Option<Currency>,
+
+    /// Дополнительные поля записи в порядке их следования в данных.
+    metadata: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> RecordRef<'a> {
+    /// Разобрать запись о транзакции в начале указанных данных в бинарном формате.
+    ///
+    /// Возвращает представление записи и размер занимаемых ею данных в байтах.
+    pub fn from_bin(data: &'a [u8]) -> Result<(Self, usize), ParseRecordFromBinError> {
+        Self::from_bin_at(data, 1, &ParseContext::default())
+    }
+
+    /// Разобрать запись о транзакции с указанным порядковым номером (начиная с 1) в начале
+    /// указанных данных в бинарном формате с указанными параметрами разбора.
+    pub(crate) fn from_bin_at(
+        data: &'a [u8],
+        record_index: usize,
+        context: &ParseContext,
+    ) -> Result<(Self, usize), ParseRecordFromBinError> {
+        let header = &data[..data.len().min(Record::BINARY_HEADER_SIZE)];
+        let record_size =
+            Record::check_bin_header(header, record_index, context.limits.max_record_size)?;
+
+        let len = Record::BINARY_HEADER_SIZE + record_size as usize;
+        let body = data.get(Record::BINARY_HEADER_SIZE..len).ok_or(
+            ParseRecordFromBinError::UnexpectedEof {
+                record_index,
+                expected: len,
+                got: data.len(),
+            },
+        )?;
+
+        Ok((Self::from_bin_body(body, context)?, len))
+    }
+
+    /// Разобрать тело записи бинарного формата, следующее за ее заголовком, с указанными
+    /// параметрами разбора.
+    pub(super) fn from_bin_body(
+        mut body: &'a [u8],
+        context: &ParseContext,
+    ) -> Result<Self, ParseRecordFromBinError> {
+        let record_size = body.len() as u32;
+        let r = &mut body;
+
+        let tx_id = r.read_u64::<BigEndian>()?;
+
+        let tx_type_raw = r.read_u8()?;
+        let tx_type =
+            TxType::from_code(tx_type_raw, context.lenient).map_err(|e: ParseTxTypeError| {
+                ParseValueError::InvalidValue {
+                    value: tx_type_raw.to_string(),
+                    description: e.to_string(),
+                }
+            })?;
+
+        let from_user_id = r.read_u64::<BigEndian>()?;
+        let to_user_id = r.read_u64::<BigEndian>()?;
+        let amount = r.read_u64::<BigEndian>()?;
+        let timestamp = r.read_u64::<BigEndian>()?;
+
+        let status_raw = r.read_u8()?;
+        let status =
+            Status::from_code(status_raw, context.lenient).map_err(|e: ParseStatusError| {
+                ParseValueError::InvalidValue {
+                    value: status_raw.to_string(),
+                    description: e.to_string(),
+                }
+            })?;
+
+        let desc_len = r.read_u32::<BigEndian>()?;
+        let max_description_len = context.limits.max_description_len;
+
+        if desc_len > max_description_len {
+            return Err(ParseRecordFromBinError::DescriptionTooLong {
+                len: desc_len,
+                max: max_description_len,
+            });
+        }
+
+        if record_size - Record::BINARY_MIN_RECORD_SIZE < desc_len {
+            return Err(ParseRecordFromBinError::RecordSizeMismatch {
+                record_size,
+                fixed_len: Record::BINARY_MIN_RECORD_SIZE,
+                description_len: desc_len,
+            });
+        }
+
+        let (description, extensions) = r.split_at(desc_len as usize);
+        let projection = context.projection;
+
+        // Описание, не входящее в набор считываемых полей, не декодируется.
+        let description = if description.is_empty() || !projection.contains(RecordKey::Description)
+        {
+            ""
+        } else {
+            Record::unquote_description(to_str(description)?)?
+        };
+
+        let mut result = Self {
+            tx_id,
+            tx_type,
+            from_user_id,
+            to_user_id,
+            amount: i128::from(amount),
+            timestamp,
+            status,
+            description,
+            currency: None,
+            metadata: vec![],
+        };
+
+        result.read_bin_extensions(extensions, projection)?;
+
+        Ok(result)
+    }
+
+    /// Считать расширения бинарного формата, следующие за описанием транзакции.
+    ///
+    /// Каждое расширение имеет структуру TAG (1 байт) | LENGTH (2 байта, big-endian) | VALUE.
+    /// Расширения с неизвестными тегами пропускаются, чтобы данные, записанные более новыми
+    /// версиями формата, могли быть прочитаны. Значения расширений полей, не входящих в набор
+    /// считываемых полей, не декодируются.
+    fn read_bin_extensions(
+        &mut self,
+        mut data: &'a [u8],
+        projection: Projection,
+    ) -> Result<(), ParseRecordFromBinError> {
+        while !data.is_empty() {
+            let tag = data.read_u8()?;
+            let len = data.read_u16::<BigEndian>()? as usize;
+
+            if data.len() < len {
+                return Err(ParseRecordFromBinError::UnexpectedError(format!(
+                    "extension {tag} length ({len}) exceeds the record size"
+                )));
+            }
+
+            let (value, rest) = data.split_at(len);
+
+            match tag {
+                Record::BINARY_EXTENSION_CURRENCY if !projection.contains(RecordKey::Currency) => {}
+                Record::BINARY_EXTENSION_METADATA if !projection.metadata => {}
+                Record::BINARY_EXTENSION_CURRENCY => {
+                    let currency = Currency::try_from(value).map_err(ParseValueError::from)?;
+                    self.currency = Some(currency);
+                }
+                Record::BINARY_EXTENSION_METADATA => {
+                    self.metadata.push(Self::read_bin_metadata(value)?);
+                }
+                Record::BINARY_EXTENSION_AMOUNT => {
+                    let amount = <[u8; 16]>::try_from(value).map_err(|_| {
+                        ParseRecordFromBinError::UnexpectedError(format!(
+                            "wide AMOUNT extension has invalid length ({len})"
+                        ))
+                    })?;
+                    self.amount = i128::from_be_bytes(amount);
+                }
+                _ => {}
+            }
+
+            data = rest;
+        }
+
+        Ok(())
+    }
+
+    /// Считать дополнительное поле записи из значения расширения бинарного формата.
+    ///
+    /// Значение имеет структуру KEY_LENGTH (2 байта, big-endian) | KEY | VALUE (UTF-8).
+    fn read_bin_metadata(
+        mut data: &'a [u8],
+    ) -> Result<(&'a str, &'a str), ParseRecordFromBinError> {
+        let key_len = data.read_u16::<BigEndian>()? as usize;
+
+        if data.len() < key_len {
+            return Err(ParseRecordFromBinError::UnexpectedError(format!(
+                "metadata key length ({key_len}) exceeds the extension size"
+            )));
+        }
+
+        let (key, value) = data.split_at(key_len);
+
+        Ok((to_str(key)?, to_str(value)?))
+    }
+
+    /// Получить идентификатор транзакции.
+    pub fn tx_id(&self) -> u64 {
+        self.tx_id
+    }
+
+    /// Получить тип транзакции.
+    pub fn tx_type(&self) -> &TxType {
+        &self.tx_type
+    }
+
+    /// Получить идентификатор отправителя.
+    pub fn from_user_id(&self) -> u64 {
+        self.from_user_id
+    }
+
+    /// Получить идентификатор получателя.
+    pub fn to_user_id(&self) -> u64 {
+        self.to_user_id
+    }
+
+    /// Получить сумму транзакции ([`None`] для широкой суммы, см. [`Record::amount`]).
+    pub fn amount(&self) -> Option<u64> {
+        u64::try_from(self.amount).ok()
+    }
+
+    /// Получить сумму транзакции с учетом широких сумм (см. [`Record::wide_amount`]).
+    pub fn wide_amount(&self) -> i128 {
+        self.amount
+    }
+
+    /// Получить метку времени транзакции.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Получить состояние транзакции.
+    pub fn status(&self) -> &Status {
+        &self.status
+    }
+
+    /// Получить описание транзакции.
+    pub fn description(&self) -> &'a str {
+        self.description
+    }
+
+    /// Получить валюту транзакции.
+    pub fn currency(&self) -> Option<Currency> {
+        self.currency
+    }
+
+    /// Получить дополнительные поля записи в порядке их следования в данных.
+    pub fn metadata(&self) -> &[(&'a str, &'a str)] {
+        &self.metadata
+    }
+}
+
+/// Преобразовать байты строкового поля в строку UTF-8 без копирования.
+fn to_str(bytes: &[u8]) -> Result<&str, ParseValueError> {
+    std::str::from_utf8(bytes).map_err(|e| ParseValueError::InvalidValue {
+        value: String::from_utf8_lossy(bytes).to_string(),
+        description: e.to_string(),
+    })
+}
+
+/// Реализация трейта [`From<RecordRef>`] для [`Record`].
+impl From<RecordRef<'_>> for Record {
+    /// Реализация метода [`From<RecordRef>::from`] для [`Record`].
+    fn from(value: RecordRef<'_>) -> Self {
+        let mut record = Record::new_wide(
+            value.tx_id,
+            value.tx_type,
+            value.from_user_id,
+            value.to_user_id,
+            value.amount,
+            value.timestamp,
+            value.status,
+            value.description,
+        );
+        record.set_currency(value.currency);
+        record.metadata.extend(
+            value
+                .metadata
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.to_string())),
+        );
+
+        record
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::SchemaVersion;
+    use rstest::rstest;
+
+    fn get_bin_data() -> Vec<u8> {
+        let mut data = vec![];
+
+        for record in crate::tests::get_data_to_write() {
+            record.to_bin(&mut data).unwrap();
+        }
+
+        data
+    }
+
+    #[test]
+    fn test_from_bin() {
+        let data = get_bin_data();
+        let expected = crate::tests::get_data_to_write();
+
+        let (record, len) = RecordRef::from_bin(&data).unwrap();
+
+        assert_eq!(len, 8 + 46 + 18);
+        assert_eq!(record.tx_id(), expected[0].tx_id());
+        assert_eq!(record.description(), "Terminal deposit");
+        assert_eq!(Record::from(record), expected[0]);
+
+        let (record, _) = RecordRef::from_bin(&data[len..]).unwrap();
+
+        assert_eq!(Record::from(record), expected[1]);
+    }
+
+    #[test]
+    fn test_from_bin_borrows_description() {
+        let data = get_bin_data();
+
+        let (record, _) = RecordRef::from_bin(&data).unwrap();
+
+        assert!(data.as_ptr_range().contains(&record.description().as_ptr()));
+    }
+
+    #[test]
+    fn test_from_bin_extensions() {
+        let mut record = crate::tests::get_wide_data_to_write().remove(1);
+        record.set_currency(Some(Currency::try_from("EUR").unwrap()));
+        record
+            .metadata_mut()
+            .insert("branch".to_string(), "042".to_string());
+
+        let mut data = vec![];
+        record
+            .to_bin_with_schema(&mut data, SchemaVersion::V2)
+            .unwrap();
+
+        let (view, len) = RecordRef::from_bin(&data).unwrap();
+
+        assert_eq!(len, data.len());
+        assert_eq!(view.amount(), None);
+        assert_eq!(view.wide_amount(), record.wide_amount());
+        assert_eq!(view.currency(), record.currency());
+        assert_eq!(view.metadata(), [("branch", "042")]);
+        assert_eq!(Record::from(view), record);
+    }
+
+    #[rstest]
+    #[case(
+        0,
+        "Unexpected end of data in record 1: expected 8 bytes, got 0 (the data is likely truncated)"
+    )]
+    #[case(
+        60,
+        "Unexpected end of data in record 1: expected 72 bytes, got 60 (the data is likely truncated)"
+    )]
+    fn test_from_bin_truncated(#[case] len: usize, #[case] error: &str) {
+        let data = get_bin_data();
+
+        assert_eq!(
+            RecordRef::from_bin(&data[..len]).unwrap_err().to_string(),
+            error
+        );
+    }
+}
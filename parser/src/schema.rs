@@ -0,0 +1,86 @@
+//! Модуль версий схемы записей о транзакциях.
+//!
+//! Версия 1 — исходная схема, в которой сумма транзакции является неотрицательным числом,
+//! не превышающим [`u64::MAX`]. Версия 2 допускает «широкие» суммы: отрицательные
+//! (корректировки) и превышающие [`u64::MAX`], в пределах [`i128`].
+//!
+//! Широкие суммы в форматах представляются так:
+//!
+//! - в текстовом, CSV, JSON и SQL форматах — десятичным числом со знаком `-` для отрицательных;
+//! - в бинарном формате поле AMOUNT содержит 0, а сумма записывается расширением
+//!   (16 байт, big-endian, дополнительный код); заголовок контейнера имеет версию 2;
+//! - в Protocol Buffers поле `amount` содержит 0, а сумма записывается полем `wide_amount`
+//!   так же, как в бинарном формате.
+//!
+//! Чтение всегда допускает широкие суммы, а запись — только если версия схемы записи
+//! (см. [`crate::RecordWriter::schema_version`]) не ниже версии схемы записи о транзакции
+//! (см. [`crate::Record::schema_version`]).
+
+use super::errors::FormatError;
+use std::fmt;
+
+/// Версия схемы записей о транзакциях.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SchemaVersion {
+    /// Суммы транзакций в пределах [`u64`].
+    #[default]
+    V1,
+
+    /// Широкие суммы транзакций в пределах [`i128`].
+    V2,
+}
+
+/// Реализация трейта [`fmt::Display`] для [`SchemaVersion`].
+impl fmt::Display for SchemaVersion {
+    /// Реализация метода [`fmt::Display::fmt`] для [`SchemaVersion`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::V1 => "1",
+            Self::V2 => "2",
+        };
+
+        write!(f, "{s}")
+    }
+}
+
+/// Реализация трейта [`TryFrom<&str>`] для [`SchemaVersion`].
+impl TryFrom<&str> for SchemaVersion {
+    /// Ошибка определения версии схемы.
+    type Error = FormatError;
+
+    /// Реализация метода [`TryFrom<&str>::try_from`] для [`SchemaVersion`].
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "1" | "v1" => Ok(Self::V1),
+            "2" | "v2" => Ok(Self::V2),
+            _ => Err(FormatError::UnsupportedSchemaVersion(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("1", SchemaVersion::V1)]
+    #[case("v1", SchemaVersion::V1)]
+    #[case("2", SchemaVersion::V2)]
+    #[case("v2", SchemaVersion::V2)]
+    fn test_try_from(#[case] value: &str, #[case] expected: SchemaVersion) {
+        assert_eq!(SchemaVersion::try_from(value).unwrap(), expected);
+        assert_eq!(
+            SchemaVersion::try_from(expected.to_string().as_str()).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_try_from_unsupported() {
+        assert_eq!(
+            SchemaVersion::try_from("3").unwrap_err().to_string(),
+            "Unsupported schema version: 3"
+        );
+    }
+}
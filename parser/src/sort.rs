@@ -0,0 +1,766 @@
+//! Модуль сортировки записей о транзакциях по значениям полей.
+
+use super::YPBankImpl;
+use super::errors::{ReadError, SortError};
+use super::iter::{MAX_PREALLOCATED_RECORDS, RecordIter};
+use super::record::{Record, RecordKey};
+use super::schema::SchemaVersion;
+use super::writer::RecordWriter;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{self, AtomicUsize};
+
+/// Направление сортировки.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    /// По возрастанию.
+    #[default]
+    Ascending,
+
+    /// По убыванию.
+    Descending,
+}
+
+/// Сравнить записи о транзакциях последовательно по указанным ключам.
+///
+/// Каждый следующий ключ учитывается только при равенстве значений по предыдущим.
+pub(crate) fn compare(a: &Record, b: &Record, keys: &[(RecordKey, SortOrder)]) -> Ordering {
+    keys.iter()
+        .map(|&(key, order)| match order {
+            SortOrder::Ascending => a.compare_by(b, key),
+            SortOrder::Descending => b.compare_by(a, key),
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Устойчиво отсортировать записи о транзакциях последовательно по указанным ключам.
+pub(crate) fn sort_by_keys(records: &mut [Record], keys: &[(RecordKey, SortOrder)]) {
+    records.sort_by(|a, b| compare(a, b, keys));
+}
+
+/// Формат временных файлов внешней сортировки, без потерь представляющий любые записи.
+const SPILL_FORMAT: YPBankImpl = YPBankImpl::Jsonl;
+
+/// Внешняя сортировка записей о транзакциях, объем которых превышает доступную память.
+///
+/// Записи считываются частями по [`ExternalSort::chunk_records`] записей; каждая часть
+/// сортируется в памяти и сохраняется во временный файл, после чего временные файлы сливаются
+/// в назначение (k-way merge), не более чем по [`ExternalSort::MAX_MERGE_WIDTH`] файлов за раз.
+/// Поэтому в памяти находится не более одной части записей, а на диске требуется место
+/// примерно для двух копий данных.
+///
+/// Сортировка устойчива: записи с равными значениями ключей следуют в порядке источника.
+/// Временные файлы удаляются по окончании сортировки, в том числе при ошибке.
+#[derive(Debug, Clone)]
+pub struct ExternalSort {
+    /// Ключи сортировки с направлениями.
+    keys: Vec<(RecordKey, SortOrder)>,
+
+    /// Каталог временных файлов.
+    tmp_dir: PathBuf,
+
+    /// Количество записей в сортируемой в памяти части.
+    chunk_records: usize,
+}
+
+impl ExternalSort {
+    /// Количество записей в сортируемой в памяти части по умолчанию.
+    pub const DEFAULT_CHUNK_RECORDS: usize = 1_000_000;
+
+    /// Максимальное количество временных файлов, одновременно открытых при слиянии.
+    pub const MAX_MERGE_WIDTH: usize = 256;
+
+    /// Создание внешней сортировки по указанным ключам с временными файлами в указанном каталоге.
+    pub fn new(keys: &[(RecordKey, SortOrder)], tmp_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            keys: keys.to_vec(),
+            tmp_dir: tmp_dir.into(),
+            chunk_records: Self::DEFAULT_CHUNK_RECORDS,
+        }
+    }
+
+    /// Установить количество записей в сортируемой в памяти части (не менее 1).
+    pub fn chunk_records(self, records: usize) -> Self {
+        Self {
+            chunk_records: records.max(1),
+            ..self
+        }
+    }
+
+    /// Отсортировать считываемые записи и записать их в указанное назначение.
+    ///
+    /// Если все записи умещаются в одну часть, временные файлы не создаются.
+    /// Возвращает назначение после завершения записи.
+    pub fn sort<W: Write>(
+        &self,
+        records: impl IntoIterator<Item = Result<Record, ReadError>>,
+        mut writer: RecordWriter<W>,
+    ) -> Result<W, SortError> {
+        let mut records = records.into_iter();
+        let mut spills = SpillFiles::new(&self.tmp_dir);
+        let mut pending = vec![];
+        let mut chunk = Vec::with_capacity(self.chunk_records.min(MAX_PREALLOCATED_RECORDS));
+
+        loop {
+            for record in records.by_ref().take(self.chunk_records) {
+                chunk.push(record?);
+            }
+
+            let exhausted = chunk.len() < self.chunk_records;
+            sort_by_keys(&mut chunk, &self.keys);
+
+            if exhausted && pending.is_empty() {
+                for record in &chunk {
+                    writer.write_record(record)?;
+                }
+
+                return Ok(writer.finish()?);
+            }
+
+            if !chunk.is_empty() {
+                pending.push(spills.write(&chunk)?);
+                chunk.clear();
+            }
+
+            if exhausted {
+                break;
+            }
+        }
+
+        drop(chunk);
+
+        // Слияние соседних файлов сохраняет порядок частей и, следовательно, устойчивость.
+        while pending.len() > Self::MAX_MERGE_WIDTH {
+            let mut merged = Vec::with_capacity(pending.len().div_ceil(Self::MAX_MERGE_WIDTH));
+
+            for group in pending.chunks(Self::MAX_MERGE_WIDTH) {
+                let (path, file) = spills.create()?;
+                let mut spill_writer = spill_writer(file);
+
+                self.merge(group, &mut spill_writer)?;
+                spill_writer.finish()?;
+                spills.remove(group);
+                merged.push(path);
+            }
+
+            pending = merged;
+        }
+
+        self.merge(&pending, &mut writer)?;
+
+        Ok(writer.finish()?)
+    }
+
+    /// Слить отсортированные временные файлы в указанное назначение.
+    fn merge<W: Write>(
+        &self,
+        paths: &[PathBuf],
+        writer: &mut RecordWriter<W>,
+    ) -> Result<(), SortError> {
+        let sources = paths
+            .iter()
+            .map(|path| Ok(spill_reader(File::open(path)?)))
+            .collect::<Result<Vec<_>, SortError>>()?;
+
+        for record in MergeSorted::new(sources, &self.keys) {
+            writer.write_record(&record?)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Отсортировать записи о транзакциях в заданном формате, объем которых может превышать
+/// доступную память, последовательно по указанным ключам (см. [`ExternalSort`]).
+///
+/// Записи считываются из источника и записываются в назначение в одном формате как есть,
+/// в том числе с широкими суммами. Временные файлы создаются в каталоге `tmp_dir`.
+pub fn sort_external<R: Read, W: Write>(
+    input: R,
+    format: YPBankImpl,
+    keys: &[(RecordKey, SortOrder)],
+    tmp_dir: &Path,
+    output: W,
+) -> Result<W, SortError> {
+    ExternalSort::new(keys, tmp_dir).sort(
+        format.iter_from(input),
+        format.writer_to(output).schema_version(SchemaVersion::V2),
+    )
+}
+
+/// Создание объекта записи во временный файл внешней сортировки.
+fn spill_writer(file: File) -> RecordWriter<File> {
+    SPILL_FORMAT
+        .writer_to(file)
+        .schema_version(SchemaVersion::V2)
+}
+
+/// Создание итератора чтения временного файла внешней сортировки.
+///
+/// Файл читается нестрого, чтобы неизвестные типы и состояния транзакций считывались как есть.
+fn spill_reader(file: File) -> RecordIter<File> {
+    SPILL_FORMAT.iter_from(file).lenient()
+}
+
+/// Слить записи о транзакциях из источников в заданных форматах, каждый из которых уже
+/// отсортирован по указанным ключам, в один отсортированный поток (см. [`MergeSorted`]).
+///
+/// Например, позволяет собрать помесячный архив из отсортированных ежедневных выгрузок,
+/// не загружая их в память.
+pub fn merge_sorted<R: Read>(
+    inputs: Vec<(R, YPBankImpl)>,
+    keys: &[(RecordKey, SortOrder)],
+) -> MergeSorted<RecordIter<R>> {
+    MergeSorted::new(
+        inputs
+            .into_iter()
+            .map(|(input, format)| format.iter_from(input)),
+        keys,
+    )
+}
+
+/// Итератор слияния записей о транзакциях из нескольких источников, каждый из которых уже
+/// отсортирован по одним и тем же ключам (k-way merge).
+///
+/// В памяти одновременно находится не более одной записи каждого источника. Слияние устойчиво:
+/// при равенстве значений ключей первой следует запись из источника, указанного раньше.
+/// Каждая запись источника сравнивается с предыдущей записью того же источника: запись,
+/// нарушающая порядок, возвращается ошибкой [`ReadError::Unsorted`] вместо того, чтобы
+/// молча нарушить порядок результата. Ошибка чтения источника возвращается как очередной
+/// элемент; после нее слияние может быть продолжено, если источник допускает продолжение
+/// чтения (записи, нарушившие порядок, при этом сливаются как есть).
+pub struct MergeSorted<I> {
+    /// Источники записей.
+    sources: Vec<I>,
+
+    /// Очередные записи источников, упорядоченные для извлечения наименьшей.
+    heap: BinaryHeap<MergeEntry>,
+
+    /// Источники, очередная запись которых еще не считана.
+    refill: Vec<usize>,
+
+    /// Последние извлеченные записи источников для проверки их порядка.
+    last: Vec<Option<Record>>,
+
+    /// Ключи сортировки с направлениями.
+    keys: Arc<[(RecordKey, SortOrder)]>,
+}
+
+impl<I: Iterator<Item = Result<Record, ReadError>>> MergeSorted<I> {
+    /// Создание итератора слияния указанных источников, отсортированных по указанным ключам.
+    pub fn new(sources: impl IntoIterator<Item = I>, keys: &[(RecordKey, SortOrder)]) -> Self {
+        let sources = sources.into_iter().collect::<Vec<_>>();
+
+        Self {
+            heap: BinaryHeap::with_capacity(sources.len()),
+            refill: (0..sources.len()).rev().collect(),
+            last: vec![None; sources.len()],
+            sources,
+            keys: keys.into(),
+        }
+    }
+}
+
+/// Реализация трейта [`Iterator`] для [`MergeSorted`].
+impl<I: Iterator<Item = Result<Record, ReadError>>> Iterator for MergeSorted<I> {
+    type Item = Result<Record, ReadError>;
+
+    /// Реализация метода [`Iterator::next`] для [`MergeSorted`].
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(&source) = self.refill.last() {
+            match self.sources[source].next() {
+                Some(Ok(record)) => {
+                    self.refill.pop();
+
+                    let unsorted = self.last[source]
+                        .as_ref()
+                        .filter(|last| compare(last, &record, &self.keys) == Ordering::Greater);
+                    let error = unsorted.map(|last| ReadError::Unsorted {
+                        input: source + 1,
+                        tx_id: record.tx_id(),
+                        previous: last.tx_id(),
+                    });
+
+                    self.heap.push(MergeEntry {
+                        record,
+                        source,
+                        keys: self.keys.clone(),
+                    });
+
+                    if let Some(error) = error {
+                        return Some(Err(error));
+                    }
+                }
+                // Источник остается в очереди, чтобы продолжить его чтение при следующем вызове.
+                Some(Err(e)) => return Some(Err(e)),
+                None => {
+                    self.refill.pop();
+                }
+            }
+        }
+
+        let MergeEntry { record, source, .. } = self.heap.pop()?;
+        self.refill.push(source);
+        self.last[source] = Some(record.clone());
+
+        Some(Ok(record))
+    }
+}
+
+/// Очередная запись источника при слиянии.
+struct MergeEntry {
+    /// Запись о транзакции.
+    record: Record,
+
+    /// Порядковый номер источника.
+    source: usize,
+
+    /// Ключи сортировки с направлениями.
+    keys: Arc<[(RecordKey, SortOrder)]>,
+}
+
+/// Реализация трейта [`Ord`] для [`MergeEntry`].
+///
+/// [`BinaryHeap`] извлекает наибольший элемент, поэтому порядок обращен; при равенстве ключей
+/// первой извлекается запись из источника, указанного раньше.
+impl Ord for MergeEntry {
+    /// Реализация метода [`Ord::cmp`] для [`MergeEntry`].
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare(&other.record, &self.record, &self.keys).then(other.source.cmp(&self.source))
+    }
+}
+
+/// Реализация трейта [`PartialOrd`] для [`MergeEntry`].
+impl PartialOrd for MergeEntry {
+    /// Реализация метода [`PartialOrd::partial_cmp`] для [`MergeEntry`].
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Реализация трейта [`PartialEq`] для [`MergeEntry`].
+impl PartialEq for MergeEntry {
+    /// Реализация метода [`PartialEq::eq`] для [`MergeEntry`].
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+/// Реализация трейта [`Eq`] для [`MergeEntry`].
+impl Eq for MergeEntry {}
+
+/// Временные файлы внешней сортировки, удаляемые при уничтожении набора.
+struct SpillFiles<'a> {
+    /// Каталог временных файлов.
+    dir: &'a Path,
+
+    /// Пути к созданным и еще не удаленным файлам.
+    paths: Vec<PathBuf>,
+}
+
+impl<'a> SpillFiles<'a> {
+    /// Создание пустого набора временных файлов в указанном каталоге.
+    fn new(dir: &'a Path) -> Self {
+        Self { dir, paths: vec![] }
+    }
+
+    /// Создать новый временный файл с уникальным именем.
+    fn create(&mut self) -> std::io::Result<(PathBuf, File)> {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+        loop {
+            let id = NEXT_ID.fetch_add(1, atomic::Ordering::Relaxed);
+            let path = self
+                .dir
+                .join(format!("ypbank-sort-{}-{id}.jsonl", std::process::id()));
+
+            match File::create_new(&path) {
+                Ok(file) => {
+                    self.paths.push(path.clone());
+
+                    return Ok((path, file));
+                }
+                // Файл мог остаться от аварийно завершенного процесса с тем же идентификатором.
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Записать отсортированную часть записей в новый временный файл.
+    fn write(&mut self, records: &[Record]) -> Result<PathBuf, SortError> {
+        let (path, file) = self.create()?;
+        let mut writer = spill_writer(file);
+
+        for record in records {
+            writer.write_record(record)?;
+        }
+
+        writer.finish()?;
+
+        Ok(path)
+    }
+
+    /// Удалить указанные временные файлы, которые больше не нужны.
+    fn remove(&mut self, paths: &[PathBuf]) {
+        for path in paths {
+            let _ = std::fs::remove_file(path);
+        }
+
+        self.paths.retain(|path| !paths.contains(path));
+    }
+}
+
+/// Реализация трейта [`Drop`] для [`SpillFiles`].
+impl Drop for SpillFiles<'_> {
+    /// Реализация метода [`Drop::drop`] для [`SpillFiles`].
+    fn drop(&mut self) {
+        for path in &self.paths {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::{Currency, Status, TxType};
+    use rstest::rstest;
+
+    /// Временный каталог, удаляемый по окончании теста.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("parser-sort-{}-{name}", std::process::id()));
+            std::fs::create_dir_all(&path).unwrap();
+
+            Self(path)
+        }
+
+        fn is_empty(&self) -> bool {
+            std::fs::read_dir(&self.0).unwrap().next().is_none()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn get_record(tx_id: u64, tx_type: TxType, timestamp: u64) -> Record {
+        Record::new(
+            tx_id,
+            tx_type,
+            0,
+            0,
+            100,
+            timestamp,
+            Status::Success,
+            format!("Record {tx_id}"),
+        )
+    }
+
+    fn get_records() -> Vec<Record> {
+        vec![
+            get_record(3, TxType::Withdrawal, 200),
+            get_record(1, TxType::Transfer, 300),
+            get_record(4, TxType::Deposit, 100),
+            get_record(2, TxType::Deposit, 200),
+        ]
+    }
+
+    #[rstest]
+    #[case(&[(RecordKey::TxId, SortOrder::Ascending)], vec![1, 2, 3, 4])]
+    #[case(&[(RecordKey::TxId, SortOrder::Descending)], vec![4, 3, 2, 1])]
+    #[case(&[(RecordKey::TxType, SortOrder::Ascending)], vec![4, 2, 1, 3])]
+    #[case(&[(RecordKey::Timestamp, SortOrder::Ascending)], vec![4, 3, 2, 1])]
+    #[case(
+        &[(RecordKey::Timestamp, SortOrder::Ascending), (RecordKey::TxId, SortOrder::Ascending)],
+        vec![4, 2, 3, 1]
+    )]
+    #[case(
+        &[(RecordKey::Timestamp, SortOrder::Descending), (RecordKey::TxId, SortOrder::Descending)],
+        vec![1, 3, 2, 4]
+    )]
+    #[case(&[], vec![3, 1, 4, 2])]
+    fn test_sort_by_keys(#[case] keys: &[(RecordKey, SortOrder)], #[case] expected: Vec<u64>) {
+        let mut records = get_records();
+
+        sort_by_keys(&mut records, keys);
+
+        assert_eq!(
+            records.iter().map(Record::tx_id).collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    fn get_many_records(count: u64) -> Vec<Record> {
+        (1..=count)
+            .map(|tx_id| get_record(tx_id, TxType::Deposit, tx_id * 7919 % 13))
+            .collect()
+    }
+
+    #[rstest]
+    fn test_external_sort_matches_sort_by_keys(
+        #[values(1, 2, 3, 7, 100)] chunk_records: usize,
+        #[values(SortOrder::Ascending, SortOrder::Descending)] order: SortOrder,
+    ) {
+        let dir = TempDir::new(&format!("chunks-{chunk_records}-{order:?}"));
+        let keys = [(RecordKey::Timestamp, order)];
+        let records = get_many_records(50);
+
+        let mut expected = records.clone();
+        sort_by_keys(&mut expected, &keys);
+
+        let output = ExternalSort::new(&keys, &dir.0)
+            .chunk_records(chunk_records)
+            .sort(
+                records.into_iter().map(Ok),
+                YPBankImpl::Jsonl.writer_to(vec![]),
+            )
+            .unwrap();
+
+        assert_eq!(
+            YPBankImpl::Jsonl.iter_from(&output[..]).read_all().unwrap(),
+            expected
+        );
+        assert!(dir.is_empty());
+    }
+
+    #[test]
+    fn test_external_sort_multiple_merge_passes() {
+        let dir = TempDir::new("passes");
+        let keys = [(RecordKey::Timestamp, SortOrder::Ascending)];
+        let records = get_many_records(ExternalSort::MAX_MERGE_WIDTH as u64 * 2 + 1);
+
+        let mut expected = records.clone();
+        sort_by_keys(&mut expected, &keys);
+
+        let output = ExternalSort::new(&keys, &dir.0)
+            .chunk_records(1)
+            .sort(
+                records.into_iter().map(Ok),
+                YPBankImpl::Bin.writer_to(vec![]),
+            )
+            .unwrap();
+
+        assert_eq!(
+            YPBankImpl::Bin.iter_from(&output[..]).read_all().unwrap(),
+            expected
+        );
+        assert!(dir.is_empty());
+    }
+
+    #[test]
+    fn test_external_sort_preserves_records() {
+        let dir = TempDir::new("preserves");
+        let keys = [(RecordKey::TxId, SortOrder::Descending)];
+
+        let mut records = crate::tests::get_wide_data_to_write();
+        records[0].set_currency(Some(Currency::try_from("EUR").unwrap()));
+        records[1]
+            .metadata_mut()
+            .insert("branch".to_string(), "042".to_string());
+        records[2].set_status(Status::Other("ON_HOLD".to_string()));
+
+        let mut expected = records.clone();
+        sort_by_keys(&mut expected, &keys);
+
+        let output = ExternalSort::new(&keys, &dir.0)
+            .chunk_records(1)
+            .sort(
+                records.into_iter().map(Ok),
+                YPBankImpl::Jsonl
+                    .writer_to(vec![])
+                    .schema_version(SchemaVersion::V2),
+            )
+            .unwrap();
+
+        assert_eq!(
+            YPBankImpl::Jsonl
+                .iter_from(&output[..])
+                .lenient()
+                .read_all()
+                .unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_sort_external() {
+        let dir = TempDir::new("bin");
+        let mut writer = YPBankImpl::Bin
+            .writer_to(vec![])
+            .schema_version(SchemaVersion::V2);
+        for record in crate::tests::get_wide_data_to_write() {
+            writer.write_record(&record).unwrap();
+        }
+        let data = writer.finish().unwrap();
+
+        let keys = [(RecordKey::Amount, SortOrder::Ascending)];
+        let output = sort_external(&data[..], YPBankImpl::Bin, &keys, &dir.0, vec![]).unwrap();
+
+        assert_eq!(
+            YPBankImpl::Bin
+                .iter_from(&output[..])
+                .map(|record| record.unwrap().tx_id())
+                .collect::<Vec<_>>(),
+            vec![3, 1, 4, 2]
+        );
+    }
+
+    #[test]
+    fn test_external_sort_read_error_removes_temp_files() {
+        let dir = TempDir::new("error");
+        let keys = [(RecordKey::Timestamp, SortOrder::Ascending)];
+        let records = get_many_records(5)
+            .into_iter()
+            .map(Ok)
+            .chain([Err(ReadError::Io(std::io::Error::other("broken")))]);
+
+        let result = ExternalSort::new(&keys, &dir.0)
+            .chunk_records(2)
+            .sort(records, YPBankImpl::Jsonl.writer_to(vec![]));
+
+        assert!(matches!(result, Err(SortError::Read(_))));
+        assert!(dir.is_empty());
+    }
+
+    fn write_sorted(format: YPBankImpl, records: &[Record]) -> Vec<u8> {
+        let mut writer = format.writer_to(vec![]);
+        for record in records {
+            writer.write_record(record).unwrap();
+        }
+
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn test_merge_sorted() {
+        let keys = [(RecordKey::Timestamp, SortOrder::Ascending)];
+        let days = [
+            vec![
+                get_record(1, TxType::Deposit, 100),
+                get_record(2, TxType::Deposit, 300),
+            ],
+            vec![
+                get_record(3, TxType::Deposit, 100),
+                get_record(4, TxType::Deposit, 200),
+            ],
+            vec![
+                get_record(5, TxType::Deposit, 50),
+                get_record(6, TxType::Deposit, 300),
+            ],
+        ];
+        let formats = [YPBankImpl::Text, YPBankImpl::Csv, YPBankImpl::Bin];
+
+        let data = days
+            .iter()
+            .zip(formats)
+            .map(|(records, format)| (write_sorted(format, records), format))
+            .collect::<Vec<_>>();
+        let inputs = data
+            .iter()
+            .map(|(data, format)| (&data[..], *format))
+            .collect();
+
+        assert_eq!(
+            merge_sorted(inputs, &keys)
+                .map(|record| record.unwrap().tx_id())
+                .collect::<Vec<_>>(),
+            vec![5, 1, 3, 4, 2, 6]
+        );
+    }
+
+    #[test]
+    fn test_merge_sorted_empty() {
+        let inputs: Vec<(&[u8], YPBankImpl)> =
+            vec![(&[], YPBankImpl::Bin), (&[], YPBankImpl::Jsonl)];
+
+        assert_eq!(merge_sorted(inputs, &[]).count(), 0);
+        assert_eq!(merge_sorted(Vec::<(&[u8], _)>::new(), &[]).count(), 0);
+    }
+
+    #[test]
+    fn test_merge_sorted_continues_after_error() {
+        let keys = [(RecordKey::TxId, SortOrder::Ascending)];
+        let first = vec![
+            Ok(get_record(1, TxType::Deposit, 0)),
+            Err(ReadError::Io(std::io::Error::other("broken"))),
+            Ok(get_record(4, TxType::Deposit, 0)),
+        ];
+        let second = vec![
+            Ok(get_record(2, TxType::Deposit, 0)),
+            Ok(get_record(3, TxType::Deposit, 0)),
+        ];
+
+        let merged = MergeSorted::new([first.into_iter(), second.into_iter()], &keys)
+            .map(|record| {
+                record
+                    .map(|record| record.tx_id())
+                    .map_err(|e| e.to_string())
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            merged,
+            vec![
+                Ok(1),
+                Err("Read data error: broken".to_string()),
+                Ok(2),
+                Ok(3),
+                Ok(4)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_sorted_unsorted_input() {
+        let keys = [(RecordKey::Timestamp, SortOrder::Ascending)];
+        let first = write_sorted(
+            YPBankImpl::Csv,
+            &[
+                get_record(1, TxType::Deposit, 100),
+                get_record(2, TxType::Deposit, 300),
+            ],
+        );
+        let second = write_sorted(
+            YPBankImpl::Bin,
+            &[
+                get_record(3, TxType::Deposit, 200),
+                get_record(4, TxType::Deposit, 100),
+            ],
+        );
+
+        let result = merge_sorted(
+            vec![
+                (&first[..], YPBankImpl::Csv),
+                (&second[..], YPBankImpl::Bin),
+            ],
+            &keys,
+        )
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_err();
+
+        assert!(matches!(
+            result,
+            ReadError::Unsorted {
+                input: 2,
+                tx_id: 4,
+                previous: 3
+            }
+        ));
+        assert_eq!(
+            result.to_string(),
+            "Input 2 is not sorted: TX_ID 4 is out of order after TX_ID 3"
+        );
+    }
+}
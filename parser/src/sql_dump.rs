@@ -0,0 +1,263 @@
+//! Модуль записи данных о транзакциях в виде SQL-запросов `INSERT`.
+
+use super::errors::{FormatError, WriteError};
+use super::record::Record;
+use super::writer::RecordWriter;
+use std::io::{BufWriter, Write};
+
+/// Диалект SQL, для которого формируются запросы добавления записей.
+///
+/// Записи добавляются в таблицу `transactions` со столбцами, соответствующими полям записи
+/// (см. [`crate::YPBankSqlite`]), в одной транзакции базы данных.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SqlDialect {
+    /// PostgreSQL.
+    #[default]
+    Postgres,
+
+    /// MySQL (MariaDB).
+    Mysql,
+
+    /// SQLite.
+    Sqlite,
+}
+
+/// Реализация трейта [`TryFrom<&str>`] для [`SqlDialect`].
+impl TryFrom<&str> for SqlDialect {
+    /// Ошибка определения диалекта SQL.
+    type Error = FormatError;
+
+    /// Реализация метода [`TryFrom<&str>::try_from`] для [`SqlDialect`].
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "postgres" | "postgresql" => Ok(Self::Postgres),
+            "mysql" | "mariadb" => Ok(Self::Mysql),
+            "sqlite" => Ok(Self::Sqlite),
+            _ => Err(FormatError::UnsupportedSqlDialect(s.to_string())),
+        }
+    }
+}
+
+impl SqlDialect {
+    /// Столбцы таблицы в порядке следования полей в записи.
+    const COLUMNS: [&str; 8] = [
+        "tx_id",
+        "tx_type",
+        "from_user_id",
+        "to_user_id",
+        "amount",
+        "timestamp",
+        "status",
+        "description",
+    ];
+
+    /// Последовательно записывать запросы добавления записей о транзакциях в данном диалекте.
+    pub fn writer_to<W: Write>(&self, w: W) -> RecordWriter<W> {
+        match self {
+            Self::Postgres => RecordWriter::new(w, write_record_postgres, finish),
+            Self::Mysql => RecordWriter::new(w, write_record_mysql, finish),
+            Self::Sqlite => RecordWriter::new(w, write_record_sqlite, finish),
+        }
+    }
+
+    /// Экранировать имя таблицы или столбца.
+    fn quote_identifier(&self, name: &str) -> String {
+        match self {
+            Self::Mysql => format!("`{name}`"),
+            Self::Postgres | Self::Sqlite => format!("\"{name}\""),
+        }
+    }
+
+    /// Экранировать строковое значение.
+    ///
+    /// MySQL по умолчанию интерпретирует обратную косую черту в строках как начало
+    /// escape-последовательности, поэтому для него она также экранируется.
+    fn quote_string(&self, value: &str) -> String {
+        let value = value.replace('\'', "''");
+
+        match self {
+            Self::Mysql => format!("'{}'", value.replace('\\', "\\\\")),
+            Self::Postgres | Self::Sqlite => format!("'{value}'"),
+        }
+    }
+
+    /// Записать запрос добавления очередной записи о транзакции.
+    ///
+    /// Перед первой записью начинается транзакция базы данных.
+    fn write_record<W: Write>(
+        &self,
+        writer: &mut BufWriter<W>,
+        record: &Record,
+        index: usize,
+    ) -> Result<(), WriteError> {
+        if index == 0 {
+            writeln!(writer, "BEGIN;")?;
+        }
+
+        let columns = Self::COLUMNS
+            .iter()
+            .map(|column| self.quote_identifier(column))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        writeln!(
+            writer,
+            "INSERT INTO {} ({columns}) VALUES ({}, {}, {}, {}, {}, {}, {}, {});",
+            self.quote_identifier("transactions"),
+            record.tx_id(),
+            self.quote_string(&record.tx_type().to_string()),
+            record.from_user_id(),
+            record.to_user_id(),
+            record.wide_amount(),
+            record.timestamp(),
+            self.quote_string(&record.status().to_string()),
+            self.quote_string(record.description()),
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Записать запрос добавления очередной записи о транзакции в диалекте PostgreSQL.
+fn write_record_postgres<W: Write>(
+    writer: &mut BufWriter<W>,
+    record: &Record,
+    index: usize,
+) -> Result<(), WriteError> {
+    SqlDialect::Postgres.write_record(writer, record, index)
+}
+
+/// Записать запрос добавления очередной записи о транзакции в диалекте MySQL.
+fn write_record_mysql<W: Write>(
+    writer: &mut BufWriter<W>,
+    record: &Record,
+    index: usize,
+) -> Result<(), WriteError> {
+    SqlDialect::Mysql.write_record(writer, record, index)
+}
+
+/// Записать запрос добавления очередной записи о транзакции в диалекте SQLite.
+fn write_record_sqlite<W: Write>(
+    writer: &mut BufWriter<W>,
+    record: &Record,
+    index: usize,
+) -> Result<(), WriteError> {
+    SqlDialect::Sqlite.write_record(writer, record, index)
+}
+
+/// Завершить транзакцию базы данных, если была записана хотя бы одна запись.
+fn finish<W: Write>(writer: &mut BufWriter<W>, count: usize) -> Result<(), WriteError> {
+    if count > 0 {
+        writeln!(writer, "COMMIT;")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::{Status, TxType};
+    use rstest::rstest;
+
+    fn write(dialect: SqlDialect, records: &[Record]) -> String {
+        let mut writer = dialect.writer_to(vec![]);
+
+        for record in records {
+            writer.write_record(record).unwrap();
+        }
+
+        String::from_utf8(writer.finish().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_write_postgres() {
+        assert_eq!(
+            write(
+                SqlDialect::Postgres,
+                &crate::tests::get_data_to_write()[..2]
+            ),
+            r#"BEGIN;
+INSERT INTO "transactions" ("tx_id", "tx_type", "from_user_id", "to_user_id", "amount", "timestamp", "status", "description") VALUES (1234567890123456, 'DEPOSIT', 0, 9876543210987654, 10000, 1633036800000, 'SUCCESS', 'Terminal deposit');
+INSERT INTO "transactions" ("tx_id", "tx_type", "from_user_id", "to_user_id", "amount", "timestamp", "status", "description") VALUES (2312321321321321, 'TRANSFER', 1231231231231231, 9876543210987654, 1000, 1633056800000, 'FAILURE', 'User transfer');
+COMMIT;
+"#
+        );
+    }
+
+    #[test]
+    fn test_write_mysql() {
+        assert_eq!(
+            write(SqlDialect::Mysql, &crate::tests::get_data_to_write()[..1]),
+            "BEGIN;
+INSERT INTO `transactions` (`tx_id`, `tx_type`, `from_user_id`, `to_user_id`, `amount`, `timestamp`, `status`, `description`) VALUES (1234567890123456, 'DEPOSIT', 0, 9876543210987654, 10000, 1633036800000, 'SUCCESS', 'Terminal deposit');
+COMMIT;
+"
+        );
+    }
+
+    #[rstest]
+    #[case(SqlDialect::Postgres, r"'It''s C:\tmp'")]
+    #[case(SqlDialect::Sqlite, r"'It''s C:\tmp'")]
+    #[case(SqlDialect::Mysql, r"'It''s C:\\tmp'")]
+    fn test_write_escaped_description(#[case] dialect: SqlDialect, #[case] expected: &str) {
+        let record = Record::new(
+            1,
+            TxType::Deposit,
+            0,
+            1,
+            1,
+            1,
+            Status::Success,
+            r"It's C:\tmp".to_string(),
+        );
+
+        let result = write(dialect, &[record]);
+
+        assert!(result.contains(&format!("'SUCCESS', {expected});\n")));
+    }
+
+    #[test]
+    fn test_write_empty() {
+        assert_eq!(write(SqlDialect::Sqlite, &[]), "");
+    }
+
+    #[rstest]
+    #[case("postgres", SqlDialect::Postgres)]
+    #[case("postgresql", SqlDialect::Postgres)]
+    #[case("mysql", SqlDialect::Mysql)]
+    #[case("sqlite", SqlDialect::Sqlite)]
+    fn test_try_from(#[case] value: &str, #[case] expected: SqlDialect) {
+        assert_eq!(SqlDialect::try_from(value).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_try_from_unsupported() {
+        assert_eq!(
+            SqlDialect::try_from("oracle").unwrap_err().to_string(),
+            "Unsupported SQL dialect: oracle"
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_write_sqlite_executes() {
+        let records = crate::tests::get_data_to_write();
+        let script = write(SqlDialect::Sqlite, &records);
+
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE transactions (tx_id INTEGER, tx_type TEXT, from_user_id INTEGER,
+                    to_user_id INTEGER, amount INTEGER, timestamp INTEGER, status TEXT,
+                    description TEXT)",
+            )
+            .unwrap();
+        connection.execute_batch(&script).unwrap();
+
+        let count: usize = connection
+            .query_row("SELECT COUNT(*) FROM transactions", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, records.len());
+    }
+}
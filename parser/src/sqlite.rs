@@ -0,0 +1,284 @@
+//! Модуль чтения и записи данных о транзакциях в базу данных SQLite.
+//!
+//! Поддержка включается признаком крейта `sqlite`.
+
+use super::Filter;
+use super::errors::{ReadError, WriteError};
+use super::record::{Record, Status, TxType};
+use rusqlite::types::{ToSql, Type};
+use rusqlite::{Connection, Row, Transaction, params, params_from_iter};
+use std::path::Path;
+
+/// База данных SQLite с таблицей `transactions`, содержащей записи о транзакциях.
+///
+/// Значения полей записи, превышающие [`i64::MAX`], не могут быть сохранены в базе данных.
+pub struct YPBankSqlite {
+    /// Соединение с базой данных.
+    connection: Connection,
+}
+
+impl YPBankSqlite {
+    /// Запрос создания таблицы записей о транзакциях, если она еще не создана.
+    const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS transactions (
+        tx_id INTEGER NOT NULL,
+        tx_type TEXT NOT NULL,
+        from_user_id INTEGER NOT NULL,
+        to_user_id INTEGER NOT NULL,
+        amount INTEGER NOT NULL,
+        timestamp INTEGER NOT NULL,
+        status TEXT NOT NULL,
+        description TEXT NOT NULL
+    )";
+
+    /// Столбцы таблицы в порядке следования полей в записи.
+    const COLUMNS: &str =
+        "tx_id, tx_type, from_user_id, to_user_id, amount, timestamp, status, description";
+
+    /// Открыть (или создать) базу данных в указанном файле, создав в ней таблицу записей
+    /// о транзакциях, если она отсутствует.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, rusqlite::Error> {
+        Self::init(Connection::open(path)?)
+    }
+
+    /// Создать базу данных в памяти.
+    pub fn open_in_memory() -> Result<Self, rusqlite::Error> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    /// Создать таблицу записей о транзакциях, если она отсутствует.
+    fn init(connection: Connection) -> Result<Self, rusqlite::Error> {
+        connection.execute_batch(Self::CREATE_TABLE)?;
+
+        Ok(Self { connection })
+    }
+
+    /// Начать добавление записей о транзакциях в таблицу.
+    ///
+    /// Все записи добавляются в одной транзакции базы данных, которая фиксируется вызовом
+    /// [`SqliteWriter::finish`]; если он не был вызван, добавленные записи отменяются.
+    pub fn writer(&mut self) -> Result<SqliteWriter<'_>, WriteError> {
+        Ok(SqliteWriter {
+            transaction: self.connection.transaction()?,
+            written: 0,
+        })
+    }
+
+    /// Добавить записи о транзакциях в таблицу в одной транзакции базы данных.
+    pub fn insert(&mut self, records: &[Record]) -> Result<(), WriteError> {
+        let mut writer = self.writer()?;
+
+        for record in records {
+            writer.write_record(record)?;
+        }
+
+        writer.finish()?;
+
+        Ok(())
+    }
+
+    /// Считать записи о транзакциях, удовлетворяющие фильтру, в порядке их добавления.
+    ///
+    /// Условия фильтра проверяются базой данных.
+    pub fn read(&self, filter: &Filter) -> Result<Vec<Record>, ReadError> {
+        let mut conditions = vec![];
+        let mut values: Vec<Box<dyn ToSql>> = vec![];
+
+        let mut condition = |condition: &'static str, value: Box<dyn ToSql>| {
+            conditions.push(condition);
+            values.push(value);
+        };
+
+        if let Some(status) = &filter.status {
+            condition("status = ?", Box::new(status.to_string()));
+        }
+        if let Some(tx_type) = &filter.tx_type {
+            condition("tx_type = ?", Box::new(tx_type.to_string()));
+        }
+        if let Some(from_user_id) = filter.from_user_id {
+            condition("from_user_id = ?", Box::new(from_user_id));
+        }
+        if let Some(to_user_id) = filter.to_user_id {
+            condition("to_user_id = ?", Box::new(to_user_id));
+        }
+        if let Some(min_amount) = filter.min_amount {
+            condition("amount >= ?", Box::new(min_amount));
+        }
+        if let Some(since) = filter.since {
+            condition("timestamp >= ?", Box::new(since));
+        }
+        if let Some(until) = filter.until {
+            condition("timestamp < ?", Box::new(until));
+        }
+
+        let mut query = format!("SELECT {} FROM transactions", Self::COLUMNS);
+        if !conditions.is_empty() {
+            query += &format!(" WHERE {}", conditions.join(" AND "));
+        }
+        query += " ORDER BY rowid";
+
+        let mut statement = self.connection.prepare(&query)?;
+        let records = statement
+            .query_map(params_from_iter(values), Self::record_from_row)?
+            .collect::<Result<_, _>>()?;
+
+        Ok(records)
+    }
+
+    /// Ошибка преобразования текстового значения столбца с указанным номером.
+    fn conversion_error(
+        index: usize,
+        e: impl std::error::Error + Send + Sync + 'static,
+    ) -> rusqlite::Error {
+        rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(e))
+    }
+
+    /// Построить запись о транзакции по строке таблицы.
+    fn record_from_row(row: &Row) -> Result<Record, rusqlite::Error> {
+        let tx_type: String = row.get(1)?;
+        let status: String = row.get(6)?;
+
+        Ok(Record::new_wide(
+            row.get(0)?,
+            TxType::try_from(tx_type.as_str()).map_err(|e| Self::conversion_error(1, e))?,
+            row.get(2)?,
+            row.get(3)?,
+            i128::from(row.get::<_, i64>(4)?),
+            row.get(5)?,
+            Status::try_from(status.as_str()).map_err(|e| Self::conversion_error(6, e))?,
+            row.get::<_, String>(7)?,
+        ))
+    }
+}
+
+/// Последовательное добавление записей о транзакциях в таблицу в одной транзакции базы данных
+/// (см. [`YPBankSqlite::writer`]).
+pub struct SqliteWriter<'a> {
+    /// Транзакция базы данных.
+    transaction: Transaction<'a>,
+
+    /// Количество добавленных записей.
+    written: usize,
+}
+
+impl SqliteWriter<'_> {
+    /// Добавить очередную запись о транзакции.
+    pub fn write_record(&mut self, record: &Record) -> Result<(), WriteError> {
+        let query = format!(
+            "INSERT INTO transactions ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            YPBankSqlite::COLUMNS
+        );
+
+        self.transaction.prepare_cached(&query)?.execute(params![
+            record.tx_id(),
+            record.tx_type().to_string(),
+            record.from_user_id(),
+            record.to_user_id(),
+            i64::try_from(record.wide_amount())
+                .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?,
+            record.timestamp(),
+            record.status().to_string(),
+            record.description(),
+        ])?;
+
+        self.written += 1;
+
+        Ok(())
+    }
+
+    /// Зафиксировать добавленные записи и вернуть их количество.
+    pub fn finish(self) -> Result<usize, WriteError> {
+        self.transaction.commit()?;
+
+        Ok(self.written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn test_roundtrip() {
+        let records = crate::tests::get_data_to_write();
+
+        let mut database = YPBankSqlite::open_in_memory().unwrap();
+        database.insert(&records).unwrap();
+        database.insert(&records[..1]).unwrap();
+
+        let mut expected = records.clone();
+        expected.push(records[0].clone());
+
+        assert_eq!(database.read(&Filter::default()).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case(Filter { status: Some(Status::Success), ..Default::default() }, vec![0, 2])]
+    #[case(Filter { tx_type: Some(TxType::Transfer), ..Default::default() }, vec![1])]
+    #[case(Filter { to_user_id: Some(9876543210987654), min_amount: Some(5000), ..Default::default() }, vec![0])]
+    #[case(Filter { since: Some(1633056800000), until: Some(1633066800000), ..Default::default() }, vec![1])]
+    fn test_read_filter(#[case] filter: Filter, #[case] indices: Vec<usize>) {
+        let records = crate::tests::get_data_to_write();
+
+        let mut database = YPBankSqlite::open_in_memory().unwrap();
+        database.insert(&records).unwrap();
+
+        assert_eq!(
+            database.read(&filter).unwrap(),
+            indices
+                .into_iter()
+                .map(|i| records[i].clone())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_writer_rollback() {
+        let records = crate::tests::get_data_to_write();
+
+        let mut database = YPBankSqlite::open_in_memory().unwrap();
+
+        let mut writer = database.writer().unwrap();
+        writer.write_record(&records[0]).unwrap();
+        drop(writer);
+
+        let mut writer = database.writer().unwrap();
+        writer.write_record(&records[1]).unwrap();
+        assert_eq!(writer.finish().unwrap(), 1);
+
+        assert_eq!(
+            database.read(&Filter::default()).unwrap(),
+            vec![records[1].clone()]
+        );
+    }
+
+    #[test]
+    fn test_value_out_of_range() {
+        let record = Record::new(
+            u64::MAX,
+            TxType::Deposit,
+            0,
+            1,
+            100,
+            1633036800000,
+            Status::Success,
+            "".to_string(),
+        );
+
+        let mut database = YPBankSqlite::open_in_memory().unwrap();
+
+        assert!(database.insert(&[record]).is_err());
+        assert!(database.read(&Filter::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_roundtrip_negative_amount() {
+        let records = crate::tests::get_wide_data_to_write();
+
+        let mut database = YPBankSqlite::open_in_memory().unwrap();
+        database.insert(&records[..1]).unwrap();
+
+        assert_eq!(database.read(&Filter::default()).unwrap(), records[..1]);
+        assert!(database.insert(&records[1..2]).is_err());
+    }
+}
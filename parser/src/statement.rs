@@ -0,0 +1,577 @@
+//! Модуль формирования выписок по счету участника за период.
+//!
+//! Выписка ([`Statement`]) содержит входящий и исходящий остатки, записи о транзакциях
+//! участника за период в хронологическом порядке и итоги поступлений и списаний.
+//! Остаток изменяют только успешные транзакции: участник-получатель получает сумму транзакции,
+//! а участник-отправитель ее отдает (отправитель депозита и получатель обналичивания не
+//! учитываются, как и в [`crate::Stats`]).
+
+use super::YPBank;
+use super::errors::FormatError;
+use super::partition::{date_from_timestamp, timestamp_from_date};
+use super::record::{Record, Status, TxType};
+use super::stats::Totals;
+use std::io::{self, Write};
+
+/// Период выписки: интервал меток времени в миллисекундах.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Period {
+    /// Начало периода (включительно).
+    pub since: u64,
+
+    /// Конец периода (не включительно).
+    pub until: u64,
+}
+
+impl Period {
+    /// Создание периода с указанными началом (включительно) и концом (не включительно).
+    pub fn new(since: u64, until: u64) -> Self {
+        Self { since, until }
+    }
+
+    /// Календарный месяц (UTC).
+    ///
+    /// Возвращает `None`, если номер месяца не от 1 до 12.
+    pub fn month(year: u64, month: u64) -> Option<Self> {
+        if !(1..=12).contains(&month) {
+            return None;
+        }
+
+        let (next_year, next_month) = if month == 12 {
+            (year + 1, 1)
+        } else {
+            (year, month + 1)
+        };
+
+        Some(Self::new(
+            timestamp_from_date(year, month, 1),
+            timestamp_from_date(next_year, next_month, 1),
+        ))
+    }
+
+    /// Признак принадлежности метки времени периоду.
+    pub fn contains(&self, timestamp: u64) -> bool {
+        (self.since..self.until).contains(&timestamp)
+    }
+}
+
+/// Реализация трейта [`TryFrom<&str>`] для [`Period`].
+///
+/// Период задается календарным месяцем `YYYY-MM` либо интервалом меток времени
+/// в миллисекундах `SINCE..UNTIL`.
+impl TryFrom<&str> for Period {
+    /// Ошибка определения периода.
+    type Error = FormatError;
+
+    /// Реализация метода [`TryFrom<&str>::try_from`] для [`Period`].
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        let period = if let Some((since, until)) = s.split_once("..") {
+            since
+                .parse()
+                .ok()
+                .zip(until.parse().ok())
+                .map(|(since, until)| Self::new(since, until))
+                .filter(|period| period.since < period.until)
+        } else {
+            s.split_once('-')
+                .filter(|(year, month)| year.len() == 4 && month.len() == 2)
+                .and_then(|(year, month)| Some((year.parse().ok()?, month.parse().ok()?)))
+                .and_then(|(year, month)| Self::month(year, month))
+        };
+
+        period.ok_or_else(|| FormatError::InvalidPeriod(s.to_string()))
+    }
+}
+
+/// Запись выписки: транзакция участника и ее влияние на остаток.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatementEntry {
+    /// Запись о транзакции.
+    pub record: Record,
+
+    /// Изменение остатка: положительное для поступлений, отрицательное для списаний
+    /// и нулевое для неуспешных транзакций и переводов самому себе.
+    pub change: i128,
+
+    /// Остаток после транзакции.
+    pub balance: i128,
+}
+
+/// Выписка по счету участника за период.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    /// Идентификатор участника.
+    pub user_id: u64,
+
+    /// Период выписки.
+    pub period: Period,
+
+    /// Остаток на начало периода.
+    pub opening_balance: i128,
+
+    /// Остаток на конец периода.
+    pub closing_balance: i128,
+
+    /// Транзакции участника за период в хронологическом порядке.
+    pub entries: Vec<StatementEntry>,
+
+    /// Итоги поступлений за период.
+    pub credits: Totals,
+
+    /// Итоги списаний за период (сумма положительна).
+    pub debits: Totals,
+}
+
+/// Формат вывода выписки.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatementFormat {
+    /// Текст для чтения человеком.
+    Text,
+
+    /// CSV-таблица записей выписки.
+    Csv,
+
+    /// HTML-страница.
+    Html,
+}
+
+impl Statement {
+    /// Записать выписку в указанном формате.
+    pub fn write_to<W: Write>(&self, w: &mut W, format: StatementFormat) -> io::Result<()> {
+        match format {
+            StatementFormat::Text => self.write_text(w),
+            StatementFormat::Csv => self.write_csv(w),
+            StatementFormat::Html => self.write_html(w),
+        }
+    }
+
+    /// Записать выписку в виде текста.
+    fn write_text<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(
+            w,
+            "Statement for user {} from {} to {}",
+            self.user_id,
+            format_date(self.period.since),
+            format_date(self.period.until)
+        )?;
+        writeln!(w, "Opening balance: {}", self.opening_balance)?;
+
+        for entry in &self.entries {
+            let record = &entry.record;
+
+            writeln!(
+                w,
+                "    {} {} {} {:+} {} {} ({} -> {}) \"{}\"",
+                record.timestamp(),
+                record.tx_id(),
+                record.tx_type(),
+                entry.change,
+                entry.balance,
+                record.status(),
+                record.from_user_id(),
+                record.to_user_id(),
+                record.description()
+            )?;
+        }
+
+        writeln!(
+            w,
+            "Credits: {} (amount {})",
+            self.credits.count, self.credits.amount
+        )?;
+        writeln!(
+            w,
+            "Debits: {} (amount {})",
+            self.debits.count, self.debits.amount
+        )?;
+        writeln!(w, "Closing balance: {}", self.closing_balance)
+    }
+
+    /// Записать записи выписки в виде CSV-таблицы.
+    ///
+    /// Входящий и исходящий остатки содержатся в первой и последней строках с пустым TX_ID.
+    fn write_csv<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(
+            w,
+            "TIMESTAMP,TX_ID,TX_TYPE,STATUS,FROM_USER_ID,TO_USER_ID,AMOUNT,CHANGE,BALANCE,DESCRIPTION"
+        )?;
+        writeln!(
+            w,
+            "{},,,,,,,,{},\"Opening balance\"",
+            self.period.since, self.opening_balance
+        )?;
+
+        for entry in &self.entries {
+            let record = &entry.record;
+
+            writeln!(
+                w,
+                "{},{},{},{},{},{},{},{},{},\"{}\"",
+                record.timestamp(),
+                record.tx_id(),
+                record.tx_type(),
+                record.status(),
+                record.from_user_id(),
+                record.to_user_id(),
+                record.wide_amount(),
+                entry.change,
+                entry.balance,
+                record.description().replace('"', "\"\"")
+            )?;
+        }
+
+        writeln!(
+            w,
+            "{},,,,,,,,{},\"Closing balance\"",
+            self.period.until, self.closing_balance
+        )
+    }
+
+    /// Записать выписку в виде HTML-страницы.
+    fn write_html<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let title = format!(
+            "Statement for user {} from {} to {}",
+            self.user_id,
+            format_date(self.period.since),
+            format_date(self.period.until)
+        );
+
+        writeln!(w, "<!DOCTYPE html>")?;
+        writeln!(w, "<html>")?;
+        writeln!(
+            w,
+            "<head><meta charset=\"utf-8\"><title>{title}</title></head>"
+        )?;
+        writeln!(w, "<body>")?;
+        writeln!(w, "<h1>{title}</h1>")?;
+        writeln!(w, "<p>Opening balance: {}</p>", self.opening_balance)?;
+        writeln!(w, "<table>")?;
+        writeln!(
+            w,
+            "<tr><th>TIMESTAMP</th><th>TX_ID</th><th>TX_TYPE</th><th>STATUS</th>\
+             <th>FROM_USER_ID</th><th>TO_USER_ID</th><th>CHANGE</th><th>BALANCE</th>\
+             <th>DESCRIPTION</th></tr>"
+        )?;
+
+        for entry in &self.entries {
+            let record = &entry.record;
+
+            writeln!(
+                w,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>\
+                 <td>{:+}</td><td>{}</td><td>{}</td></tr>",
+                record.timestamp(),
+                record.tx_id(),
+                escape_html(&record.tx_type().to_string()),
+                escape_html(&record.status().to_string()),
+                record.from_user_id(),
+                record.to_user_id(),
+                entry.change,
+                entry.balance,
+                escape_html(record.description())
+            )?;
+        }
+
+        writeln!(w, "</table>")?;
+        writeln!(
+            w,
+            "<p>Credits: {} (amount {})</p>",
+            self.credits.count, self.credits.amount
+        )?;
+        writeln!(
+            w,
+            "<p>Debits: {} (amount {})</p>",
+            self.debits.count, self.debits.amount
+        )?;
+        writeln!(w, "<p>Closing balance: {}</p>", self.closing_balance)?;
+        writeln!(w, "</body>")?;
+        writeln!(w, "</html>")
+    }
+}
+
+/// Дата (UTC) метки времени в виде `YYYY-MM-DD`.
+fn format_date(timestamp: u64) -> String {
+    let (year, month, day) = date_from_timestamp(timestamp);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Экранировать специальные символы HTML.
+fn escape_html(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&#39;"),
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+/// Признак участия участника в транзакции: отправитель (кроме депозита) или получатель
+/// (кроме обналичивания).
+pub fn involves(user_id: u64, record: &Record) -> bool {
+    (*record.tx_type() != TxType::Deposit && record.from_user_id() == user_id)
+        || (*record.tx_type() != TxType::Withdrawal && record.to_user_id() == user_id)
+}
+
+/// Изменение остатка участника в результате транзакции.
+fn balance_change(user_id: u64, record: &Record) -> i128 {
+    if *record.status() != Status::Success {
+        return 0;
+    }
+
+    let mut change = 0;
+
+    if *record.tx_type() != TxType::Withdrawal && record.to_user_id() == user_id {
+        change += record.wide_amount();
+    }
+
+    if *record.tx_type() != TxType::Deposit && record.from_user_id() == user_id {
+        change -= record.wide_amount();
+    }
+
+    change
+}
+
+/// Сформировать выписку по счету участника за период по записям о транзакциях в любом порядке.
+///
+/// Входящий остаток вычисляется по транзакциям до начала периода, транзакции после его
+/// окончания не учитываются.
+pub fn generate_from_records<'a, I>(user_id: u64, period: Period, records: I) -> Statement
+where
+    I: IntoIterator<Item = &'a Record>,
+{
+    let mut opening_balance = 0;
+    let mut records_in_period = vec![];
+
+    for record in records {
+        if !involves(user_id, record) {
+            continue;
+        }
+
+        if record.timestamp() < period.since {
+            opening_balance += balance_change(user_id, record);
+        } else if period.contains(record.timestamp()) {
+            records_in_period.push(record);
+        }
+    }
+
+    records_in_period.sort_by_key(|record| record.timestamp());
+
+    let mut balance = opening_balance;
+    let mut credits = Totals::default();
+    let mut debits = Totals::default();
+
+    let entries = records_in_period
+        .into_iter()
+        .map(|record| {
+            let change = balance_change(user_id, record);
+            balance += change;
+
+            if change > 0 {
+                credits.count += 1;
+                credits.amount += change;
+            } else if change < 0 {
+                debits.count += 1;
+                debits.amount -= change;
+            }
+
+            StatementEntry {
+                record: record.clone(),
+                change,
+                balance,
+            }
+        })
+        .collect();
+
+    Statement {
+        user_id,
+        period,
+        opening_balance,
+        closing_balance: balance,
+        entries,
+        credits,
+        debits,
+    }
+}
+
+/// Сформировать выписку по счету участника за период по набору записей о транзакциях.
+pub fn generate<B: YPBank>(user_id: u64, period: Period, bank: &B) -> Statement {
+    generate_from_records(user_id, period, bank.records())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::YPBankText;
+    use rstest::rstest;
+
+    /// 2024-03-01 00:00:00 UTC.
+    const MARCH: u64 = 1_709_251_200_000;
+
+    /// 2024-04-01 00:00:00 UTC.
+    const APRIL: u64 = 1_711_929_600_000;
+
+    fn record(
+        tx_id: u64,
+        tx_type: TxType,
+        from_user_id: u64,
+        to_user_id: u64,
+        amount: i128,
+        timestamp: u64,
+        status: Status,
+    ) -> Record {
+        let mut record = Record::new(
+            tx_id,
+            tx_type,
+            from_user_id,
+            to_user_id,
+            0,
+            timestamp,
+            status,
+            format!("Transaction {tx_id}"),
+        );
+        record.set_wide_amount(amount);
+
+        record
+    }
+
+    fn get_bank() -> YPBankText {
+        YPBankText::from_records(vec![
+            record(5, TxType::Withdrawal, 7, 0, 30, APRIL - 1, Status::Success),
+            record(1, TxType::Deposit, 0, 7, 1000, MARCH - 1, Status::Success),
+            record(2, TxType::Transfer, 7, 8, 200, MARCH + 10, Status::Success),
+            record(3, TxType::Transfer, 9, 7, 50, MARCH + 5, Status::Success),
+            record(4, TxType::Transfer, 7, 9, 400, MARCH + 20, Status::Failure),
+            record(6, TxType::Deposit, 0, 7, 500, APRIL, Status::Success),
+            record(7, TxType::Transfer, 8, 9, 100, MARCH + 1, Status::Success),
+        ])
+    }
+
+    #[test]
+    fn test_generate() {
+        let statement = generate(7, Period::new(MARCH, APRIL), &get_bank());
+
+        assert_eq!(statement.opening_balance, 1000);
+        assert_eq!(statement.closing_balance, 820);
+        assert_eq!(
+            statement
+                .entries
+                .iter()
+                .map(|entry| (entry.record.tx_id(), entry.change, entry.balance))
+                .collect::<Vec<_>>(),
+            [(3, 50, 1050), (2, -200, 850), (4, 0, 850), (5, -30, 820)]
+        );
+        assert_eq!(
+            statement.credits,
+            Totals {
+                count: 1,
+                amount: 50
+            }
+        );
+        assert_eq!(
+            statement.debits,
+            Totals {
+                count: 2,
+                amount: 230
+            }
+        );
+    }
+
+    #[test]
+    fn test_generate_no_transactions() {
+        let statement = generate(42, Period::new(MARCH, APRIL), &get_bank());
+
+        assert_eq!(statement.opening_balance, 0);
+        assert_eq!(statement.closing_balance, 0);
+        assert!(statement.entries.is_empty());
+    }
+
+    #[rstest]
+    #[case("2024-03", Some(Period::new(MARCH, APRIL)))]
+    #[case("2023-12", Some(Period::new(1_701_388_800_000, 1_704_067_200_000)))]
+    #[case("100..200", Some(Period::new(100, 200)))]
+    #[case("2024-13", None)]
+    #[case("2024-3", None)]
+    #[case("200..100", None)]
+    #[case("march", None)]
+    fn test_period_try_from(#[case] value: &str, #[case] expected: Option<Period>) {
+        assert_eq!(Period::try_from(value).ok(), expected);
+    }
+
+    #[test]
+    fn test_write_text() {
+        let statement = generate(9, Period::new(MARCH, APRIL), &get_bank());
+        let mut output = vec![];
+
+        statement
+            .write_to(&mut output, StatementFormat::Text)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!(
+                "Statement for user 9 from 2024-03-01 to 2024-04-01\n\
+                 Opening balance: 0\n    \
+                 {} 7 TRANSFER +100 100 SUCCESS (8 -> 9) \"Transaction 7\"\n    \
+                 {} 3 TRANSFER -50 50 SUCCESS (9 -> 7) \"Transaction 3\"\n    \
+                 {} 4 TRANSFER +0 50 FAILURE (7 -> 9) \"Transaction 4\"\n\
+                 Credits: 1 (amount 100)\n\
+                 Debits: 1 (amount 50)\n\
+                 Closing balance: 50\n",
+                MARCH + 1,
+                MARCH + 5,
+                MARCH + 20
+            )
+        );
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let mut bank = get_bank();
+        bank.records_mut()[2].set_description("Rent \"March\"");
+        let statement = generate(8, Period::new(MARCH, APRIL), &bank);
+        let mut output = vec![];
+
+        statement
+            .write_to(&mut output, StatementFormat::Csv)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!(
+                "TIMESTAMP,TX_ID,TX_TYPE,STATUS,FROM_USER_ID,TO_USER_ID,AMOUNT,CHANGE,BALANCE,DESCRIPTION\n\
+                 {MARCH},,,,,,,,0,\"Opening balance\"\n\
+                 {},7,TRANSFER,SUCCESS,8,9,100,-100,-100,\"Transaction 7\"\n\
+                 {},2,TRANSFER,SUCCESS,7,8,200,200,100,\"Rent \"\"March\"\"\"\n\
+                 {APRIL},,,,,,,,100,\"Closing balance\"\n",
+                MARCH + 1,
+                MARCH + 10
+            )
+        );
+    }
+
+    #[test]
+    fn test_write_html_escapes_description() {
+        let mut bank = get_bank();
+        bank.records_mut()[2].set_description("<b>Rent</b> & \"bills\"");
+        let statement = generate(8, Period::new(MARCH, APRIL), &bank);
+        let mut output = vec![];
+
+        statement
+            .write_to(&mut output, StatementFormat::Html)
+            .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("<!DOCTYPE html>\n"));
+        assert!(output.contains("&lt;b&gt;Rent&lt;/b&gt; &amp; &quot;bills&quot;"));
+        assert!(output.contains("<p>Closing balance: 100</p>"));
+    }
+}
@@ -0,0 +1,525 @@
+//! Модуль сбора сводной статистики по записям о транзакциях.
+
+use super::record::{Record, Status, TxType};
+use super::tagging::CATEGORY_KEY;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Количество и общая сумма транзакций.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Totals {
+    /// Количество транзакций.
+    pub count: usize,
+
+    /// Общая сумма транзакций (с учетом отрицательных сумм корректировок).
+    pub amount: i128,
+}
+
+impl Totals {
+    /// Учесть транзакцию с указанной суммой.
+    fn add(&mut self, amount: i128) {
+        self.count += 1;
+        self.amount += amount;
+    }
+}
+
+/// Сводная статистика по записям о транзакциях.
+///
+/// Накапливается по одной записи методом [`Stats::add`], что позволяет собирать ее
+/// при потоковом чтении источника, не загружая записи в память.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Итоги по всем транзакциям.
+    total: Totals,
+
+    /// Итоги по типам транзакций.
+    by_tx_type: HashMap<TxType, Totals>,
+
+    /// Итоги по состояниям транзакций.
+    by_status: HashMap<Status, Totals>,
+
+    /// Итоги по категориям транзакций (см. [`CATEGORY_KEY`]).
+    by_category: HashMap<String, Totals>,
+
+    /// Итоги по участникам транзакций.
+    by_user: HashMap<u64, Totals>,
+
+    /// Минимальная и максимальная метки времени.
+    time_range: Option<(u64, u64)>,
+}
+
+impl Stats {
+    /// Создание пустой статистики.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Учесть запись о транзакции.
+    ///
+    /// Транзакция учитывается в итогах ее отправителя (кроме депозита) и получателя
+    /// (кроме обналичивания); перевод самому себе учитывается один раз. Транзакция без
+    /// категории не учитывается в итогах по категориям.
+    pub fn add(&mut self, record: &Record) {
+        let amount = record.wide_amount();
+
+        self.total.add(amount);
+        self.by_tx_type
+            .entry(record.tx_type().clone())
+            .or_default()
+            .add(amount);
+        self.by_status
+            .entry(record.status().clone())
+            .or_default()
+            .add(amount);
+
+        if let Some(category) = record.metadata().get(CATEGORY_KEY) {
+            self.by_category
+                .entry(category.clone())
+                .or_default()
+                .add(amount);
+        }
+
+        let from = (*record.tx_type() != TxType::Deposit).then_some(record.from_user_id());
+        let to = (*record.tx_type() != TxType::Withdrawal)
+            .then_some(record.to_user_id())
+            .filter(|&to| from != Some(to));
+
+        for user_id in from.into_iter().chain(to) {
+            self.by_user.entry(user_id).or_default().add(amount);
+        }
+
+        let timestamp = record.timestamp();
+        self.time_range = Some(match self.time_range {
+            Some((min, max)) => (min.min(timestamp), max.max(timestamp)),
+            None => (timestamp, timestamp),
+        });
+    }
+
+    /// Итоги по всем транзакциям.
+    pub fn total(&self) -> Totals {
+        self.total
+    }
+
+    /// Итоги по транзакциям указанного типа.
+    pub fn by_tx_type(&self, tx_type: &TxType) -> Totals {
+        self.by_tx_type.get(tx_type).copied().unwrap_or_default()
+    }
+
+    /// Неизвестные типы транзакций (см. [`TxType::Unknown`]), встретившиеся в записях,
+    /// в порядке возрастания.
+    pub fn unknown_tx_types(&self) -> Vec<&TxType> {
+        let mut tx_types = self
+            .by_tx_type
+            .keys()
+            .filter(|tx_type| matches!(tx_type, TxType::Unknown(_)))
+            .collect::<Vec<_>>();
+
+        tx_types.sort();
+        tx_types
+    }
+
+    /// Итоги по транзакциям в указанном состоянии.
+    pub fn by_status(&self, status: &Status) -> Totals {
+        self.by_status.get(status).copied().unwrap_or_default()
+    }
+
+    /// Неизвестные состояния транзакций (см. [`Status::Other`]), встретившиеся в записях,
+    /// в порядке возрастания.
+    pub fn other_statuses(&self) -> Vec<&Status> {
+        let mut statuses = self
+            .by_status
+            .keys()
+            .filter(|status| matches!(status, Status::Other(_)))
+            .collect::<Vec<_>>();
+
+        statuses.sort();
+        statuses
+    }
+
+    /// Итоги по транзакциям указанной категории.
+    pub fn by_category(&self, category: &str) -> Totals {
+        self.by_category.get(category).copied().unwrap_or_default()
+    }
+
+    /// Категории транзакций, встретившиеся в записях, в порядке возрастания.
+    pub fn categories(&self) -> Vec<&str> {
+        let mut categories = self
+            .by_category
+            .keys()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+
+        categories.sort();
+        categories
+    }
+
+    /// Количество различных участников транзакций.
+    pub fn users(&self) -> usize {
+        self.by_user.len()
+    }
+
+    /// Не более `count` участников с наибольшей общей суммой транзакций в порядке ее убывания
+    /// (при равенстве — в порядке возрастания идентификатора).
+    pub fn top_users(&self, count: usize) -> Vec<(u64, Totals)> {
+        let mut users = self
+            .by_user
+            .iter()
+            .map(|(&user_id, &totals)| (user_id, totals))
+            .collect::<Vec<_>>();
+
+        users.sort_by(|(a_id, a), (b_id, b)| b.amount.cmp(&a.amount).then(a_id.cmp(b_id)));
+        users.truncate(count);
+
+        users
+    }
+
+    /// Минимальная и максимальная метки времени (отсутствуют, если не учтено ни одной записи).
+    pub fn time_range(&self) -> Option<(u64, u64)> {
+        self.time_range
+    }
+}
+
+/// Реализация трейта [`FromIterator<&Record>`] для [`Stats`].
+impl<'a> FromIterator<&'a Record> for Stats {
+    /// Реализация метода [`FromIterator<&Record>::from_iter`] для [`Stats`].
+    fn from_iter<I: IntoIterator<Item = &'a Record>>(iter: I) -> Self {
+        let mut stats = Self::new();
+
+        for record in iter {
+            stats.add(record);
+        }
+
+        stats
+    }
+}
+
+/// Количество и общая сумма транзакций.
+#[derive(Serialize, Debug)]
+pub struct TotalsReport {
+    /// Количество транзакций.
+    pub count: usize,
+
+    /// Общая сумма транзакций.
+    pub amount: i128,
+}
+
+/// Реализация трейта [`From<Totals>`] для [`TotalsReport`].
+impl From<Totals> for TotalsReport {
+    /// Реализация метода [`From<Totals>::from`] для [`TotalsReport`].
+    fn from(totals: Totals) -> Self {
+        Self {
+            count: totals.count,
+            amount: totals.amount,
+        }
+    }
+}
+
+/// Итоги по транзакциям участника.
+#[derive(Serialize, Debug)]
+pub struct UserReport {
+    /// Идентификатор участника.
+    pub user_id: u64,
+
+    /// Количество транзакций участника.
+    pub count: usize,
+
+    /// Общая сумма транзакций участника.
+    pub amount: i128,
+}
+
+/// Диапазон меток времени транзакций.
+#[derive(Serialize, Debug)]
+pub struct TimeRange {
+    /// Минимальная метка времени.
+    pub from: u64,
+
+    /// Максимальная метка времени.
+    pub to: u64,
+}
+
+/// Отчет о сводной статистике по транзакциям.
+#[derive(Serialize, Debug)]
+pub struct StatsReport {
+    /// Итоги по всем транзакциям.
+    pub total: TotalsReport,
+
+    /// Итоги по типам транзакций.
+    pub by_tx_type: BTreeMap<String, TotalsReport>,
+
+    /// Итоги по состояниям транзакций.
+    pub by_status: BTreeMap<String, TotalsReport>,
+
+    /// Итоги по категориям транзакций.
+    pub by_category: BTreeMap<String, TotalsReport>,
+
+    /// Количество различных участников транзакций.
+    pub users: usize,
+
+    /// Участники с наибольшей общей суммой транзакций.
+    pub top_users: Vec<UserReport>,
+
+    /// Диапазон меток времени (отсутствует, если транзакций нет).
+    pub time_range: Option<TimeRange>,
+}
+
+impl StatsReport {
+    /// Построить отчет по статистике, включив в него не более `top` участников
+    /// с наибольшей общей суммой транзакций.
+    pub fn new(stats: &Stats, top: usize) -> Self {
+        Self {
+            total: stats.total().into(),
+            by_tx_type: TxType::KNOWN
+                .iter()
+                .chain(stats.unknown_tx_types())
+                .map(|tx_type| (tx_type.to_string(), stats.by_tx_type(tx_type).into()))
+                .collect(),
+            by_status: Status::KNOWN
+                .iter()
+                .chain(stats.other_statuses())
+                .map(|status| (status.to_string(), stats.by_status(status).into()))
+                .collect(),
+            by_category: stats
+                .categories()
+                .into_iter()
+                .map(|category| (category.to_string(), stats.by_category(category).into()))
+                .collect(),
+            users: stats.users(),
+            top_users: stats
+                .top_users(top)
+                .into_iter()
+                .map(|(user_id, totals)| UserReport {
+                    user_id,
+                    count: totals.count,
+                    amount: totals.amount,
+                })
+                .collect(),
+            time_range: stats.time_range().map(|(from, to)| TimeRange { from, to }),
+        }
+    }
+}
+
+/// Реализация трейта [`fmt::Display`] для [`StatsReport`].
+impl fmt::Display for StatsReport {
+    /// Реализация метода [`fmt::Display::fmt`] для [`StatsReport`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Records: {} (total amount {})",
+            self.total.count, self.total.amount
+        )?;
+
+        for (title, totals) in [
+            ("By TX_TYPE", &self.by_tx_type),
+            ("By STATUS", &self.by_status),
+        ] {
+            writeln!(f, "{title}:")?;
+
+            for (key, totals) in totals {
+                writeln!(f, "    {key}: {} (amount {})", totals.count, totals.amount)?;
+            }
+        }
+
+        if !self.by_category.is_empty() {
+            writeln!(f, "By CATEGORY:")?;
+
+            for (category, totals) in &self.by_category {
+                writeln!(
+                    f,
+                    "    {category}: {} (amount {})",
+                    totals.count, totals.amount
+                )?;
+            }
+        }
+
+        writeln!(f, "Users: {}", self.users)?;
+
+        if !self.top_users.is_empty() {
+            writeln!(f, "Top users by amount:")?;
+
+            for user in &self.top_users {
+                writeln!(
+                    f,
+                    "    {}: {} transactions (amount {})",
+                    user.user_id, user.count, user.amount
+                )?;
+            }
+        }
+
+        match &self.time_range {
+            Some(range) => writeln!(f, "Time range: {} .. {} (ms)", range.from, range.to),
+            None => writeln!(f, "Time range: none"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn get_records() -> Vec<Record> {
+        vec![
+            Record::new(
+                1,
+                TxType::Deposit,
+                0,
+                10,
+                100,
+                3000,
+                Status::Success,
+                "".to_string(),
+            ),
+            Record::new(
+                2,
+                TxType::Transfer,
+                10,
+                20,
+                50,
+                1000,
+                Status::Failure,
+                "".to_string(),
+            ),
+            Record::new(
+                3,
+                TxType::Withdrawal,
+                20,
+                0,
+                500,
+                2000,
+                Status::Success,
+                "".to_string(),
+            ),
+            Record::new(
+                4,
+                TxType::Transfer,
+                30,
+                30,
+                10,
+                4000,
+                Status::Pending,
+                "".to_string(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_empty() {
+        let stats = Stats::new();
+
+        assert_eq!(stats.total(), Totals::default());
+        assert_eq!(stats.users(), 0);
+        assert!(stats.top_users(10).is_empty());
+        assert_eq!(stats.time_range(), None);
+    }
+
+    #[test]
+    fn test_totals() {
+        let stats = get_records().iter().collect::<Stats>();
+
+        assert_eq!(
+            stats.total(),
+            Totals {
+                count: 4,
+                amount: 660
+            }
+        );
+        assert_eq!(stats.time_range(), Some((1000, 4000)));
+    }
+
+    #[rstest]
+    #[case(TxType::Deposit, 1, 100)]
+    #[case(TxType::Transfer, 2, 60)]
+    #[case(TxType::Withdrawal, 1, 500)]
+    fn test_by_tx_type(#[case] tx_type: TxType, #[case] count: usize, #[case] amount: i128) {
+        let stats = get_records().iter().collect::<Stats>();
+
+        assert_eq!(stats.by_tx_type(&tx_type), Totals { count, amount });
+    }
+
+    #[rstest]
+    #[case(Status::Success, 2, 600)]
+    #[case(Status::Failure, 1, 50)]
+    #[case(Status::Pending, 1, 10)]
+    fn test_by_status(#[case] status: Status, #[case] count: usize, #[case] amount: i128) {
+        let stats = get_records().iter().collect::<Stats>();
+
+        assert_eq!(stats.by_status(&status), Totals { count, amount });
+    }
+
+    #[test]
+    fn test_by_category() {
+        let mut records = get_records();
+        for (record, category) in records.iter_mut().zip(["income", "", "cash"]) {
+            if !category.is_empty() {
+                record
+                    .metadata_mut()
+                    .insert(CATEGORY_KEY.to_string(), category.to_string());
+            }
+        }
+
+        let stats = records.iter().collect::<Stats>();
+
+        assert_eq!(stats.categories(), ["cash", "income"]);
+        assert_eq!(
+            stats.by_category("cash"),
+            Totals {
+                count: 1,
+                amount: 500
+            }
+        );
+        assert_eq!(stats.by_category("unknown"), Totals::default());
+    }
+
+    #[test]
+    fn test_top_users() {
+        let stats = get_records().iter().collect::<Stats>();
+
+        assert_eq!(stats.users(), 3);
+        assert_eq!(
+            stats.top_users(2),
+            vec![
+                (
+                    20,
+                    Totals {
+                        count: 2,
+                        amount: 550
+                    }
+                ),
+                (
+                    10,
+                    Totals {
+                        count: 2,
+                        amount: 150
+                    }
+                ),
+            ]
+        );
+        assert_eq!(
+            stats.top_users(10)[2],
+            (
+                30,
+                Totals {
+                    count: 1,
+                    amount: 10
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let stats = get_records().iter().collect::<Stats>();
+        let report = StatsReport::new(&stats, 1);
+
+        assert_eq!(report.total.count, 4);
+        assert_eq!(report.by_tx_type["TRANSFER"].amount, 60);
+        assert_eq!(report.top_users.len(), 1);
+        assert_eq!(report.top_users[0].user_id, 20);
+        assert!(
+            report
+                .to_string()
+                .ends_with("Top users by amount:\n    20: 2 transactions (amount 550)\nTime range: 1000 .. 4000 (ms)\n")
+        );
+    }
+}
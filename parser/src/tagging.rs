@@ -0,0 +1,281 @@
+//! Модуль присвоения записям о транзакциях категорий по правилам.
+//!
+//! Правило ([`TagRule`]) задает категорию и условия на описание (регулярное выражение), тип
+//! и сумму транзакции. [`Tagger`] присваивает записи категорию первого подходящего правила,
+//! сохраняя ее в дополнительном поле [`CATEGORY_KEY`] (см. [`Record::metadata`]), по которому
+//! [`crate::Stats`] собирает итоги по категориям.
+
+use super::errors::TagRulesError;
+use super::record::{Record, TxType};
+use regex::Regex;
+
+/// Ключ дополнительного поля записи, в котором сохраняется категория транзакции.
+pub const CATEGORY_KEY: &str = "category";
+
+/// Правило присвоения категории записям о транзакциях.
+///
+/// Запись удовлетворяет правилу, если она удовлетворяет всем заданным условиям. Правило
+/// без условий подходит для любой записи (например, категория по умолчанию в конце списка).
+#[derive(Debug, Clone)]
+pub struct TagRule {
+    /// Категория, присваиваемая записи.
+    pub category: String,
+
+    /// Регулярное выражение, которому должно удовлетворять описание транзакции.
+    pub description: Option<Regex>,
+
+    /// Тип транзакции.
+    pub tx_type: Option<TxType>,
+
+    /// Минимальная сумма транзакции (включительно).
+    pub min_amount: Option<i128>,
+
+    /// Максимальная сумма транзакции (не включительно).
+    pub max_amount: Option<i128>,
+}
+
+impl TagRule {
+    /// Создание правила без условий, присваивающего указанную категорию.
+    pub fn new(category: impl Into<String>) -> Self {
+        Self {
+            category: category.into(),
+            description: None,
+            tx_type: None,
+            min_amount: None,
+            max_amount: None,
+        }
+    }
+
+    /// Проверить, удовлетворяет ли запись о транзакции всем условиям правила.
+    pub fn matches(&self, record: &Record) -> bool {
+        self.description
+            .as_ref()
+            .is_none_or(|description| description.is_match(record.description()))
+            && self
+                .tx_type
+                .as_ref()
+                .is_none_or(|tx_type| record.tx_type() == tx_type)
+            && self
+                .min_amount
+                .is_none_or(|min_amount| record.wide_amount() >= min_amount)
+            && self
+                .max_amount
+                .is_none_or(|max_amount| record.wide_amount() < max_amount)
+    }
+
+    /// Разобрать правило из строки вида `CATEGORY: CONDITION; CONDITION; ...`.
+    ///
+    /// `line` — номер строки в описании правил для сообщений об ошибках.
+    fn parse(line: usize, s: &str) -> Result<Self, TagRulesError> {
+        let (category, conditions) = s
+            .split_once(':')
+            .map(|(category, conditions)| (category.trim(), conditions))
+            .filter(|(category, _)| !category.is_empty())
+            .ok_or_else(|| TagRulesError::InvalidLine {
+                line,
+                content: s.to_string(),
+            })?;
+
+        let mut rule = Self::new(category);
+
+        for condition in conditions.split(';').map(str::trim) {
+            if condition.is_empty() {
+                continue;
+            }
+
+            let invalid_condition = || TagRulesError::InvalidCondition {
+                line,
+                content: condition.to_string(),
+            };
+
+            let (key, value) = condition.split_once('=').ok_or_else(invalid_condition)?;
+            let value = value.trim();
+
+            match key.trim().to_lowercase().as_str() {
+                "description" => {
+                    rule.description = Some(
+                        Regex::new(value)
+                            .map_err(|source| TagRulesError::InvalidRegex { line, source })?,
+                    );
+                }
+                "tx_type" => {
+                    rule.tx_type = Some(
+                        TxType::try_from(value.to_uppercase().as_str())
+                            .map_err(|source| TagRulesError::InvalidTxType { line, source })?,
+                    );
+                }
+                "amount" => {
+                    let (min, max) = value.split_once("..").ok_or_else(invalid_condition)?;
+                    let parse_bound = |bound: &str| {
+                        let bound = bound.trim();
+
+                        (!bound.is_empty())
+                            .then(|| bound.parse().map_err(|_| invalid_condition()))
+                            .transpose()
+                    };
+
+                    rule.min_amount = parse_bound(min)?;
+                    rule.max_amount = parse_bound(max)?;
+                }
+                _ => return Err(invalid_condition()),
+            }
+        }
+
+        Ok(rule)
+    }
+}
+
+/// Присвоение категорий записям о транзакциях по списку правил.
+///
+/// Правила проверяются по порядку, и записи присваивается категория первого подходящего
+/// правила. Категория записи, не подходящей ни одному правилу, не изменяется.
+#[derive(Debug, Clone, Default)]
+pub struct Tagger {
+    /// Правила в порядке проверки.
+    rules: Vec<TagRule>,
+}
+
+impl Tagger {
+    /// Создание набора с указанными правилами в порядке проверки.
+    pub fn new(rules: Vec<TagRule>) -> Self {
+        Self { rules }
+    }
+
+    /// Правила в порядке проверки.
+    pub fn rules(&self) -> &[TagRule] {
+        &self.rules
+    }
+
+    /// Категория первого правила, которому удовлетворяет запись о транзакции.
+    pub fn category(&self, record: &Record) -> Option<&str> {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(record))
+            .map(|rule| rule.category.as_str())
+    }
+
+    /// Присвоить записи о транзакции категорию первого подходящего правила.
+    ///
+    /// Возвращает присвоенную категорию.
+    pub fn tag(&self, record: &mut Record) -> Option<&str> {
+        let category = self.category(record)?;
+
+        record
+            .metadata_mut()
+            .insert(CATEGORY_KEY.to_string(), category.to_string());
+
+        Some(category)
+    }
+}
+
+/// Реализация трейта [`TryFrom<&str>`] для [`Tagger`].
+///
+/// Каждая непустая строка описывает правило в виде `CATEGORY: CONDITION; CONDITION; ...`,
+/// где условие — `description=REGEX`, `tx_type=TYPE` или `amount=MIN..MAX` (любая из границ
+/// может быть опущена, верхняя не включается), например,
+/// `groceries: description=(?i)market; tx_type=TRANSFER`. Регулярное выражение не может
+/// содержать `;`. Строки, начинающиеся с `#`, игнорируются.
+impl TryFrom<&str> for Tagger {
+    /// Ошибка описания правил.
+    type Error = TagRulesError;
+
+    /// Реализация метода [`TryFrom<&str>::try_from`] для [`Tagger`].
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(line, content)| TagRule::parse(line, content))
+            .collect::<Result<_, _>>()
+            .map(Self::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const RULES: &str = "
+        # Категории транзакций
+        terminal: description=(?i)terminal; tx_type=deposit
+        small: amount=..500
+        large: amount = 5000..
+        other:
+    ";
+
+    #[rstest]
+    #[case(0, Some("terminal"))]
+    #[case(1, Some("other"))]
+    #[case(2, Some("small"))]
+    fn test_category(#[case] index: usize, #[case] expected: Option<&str>) {
+        let tagger = Tagger::try_from(RULES).unwrap();
+        let record = &crate::tests::get_data_to_write()[index];
+
+        assert_eq!(tagger.category(record), expected);
+    }
+
+    #[test]
+    fn test_tag() {
+        let tagger = Tagger::try_from("large: amount=5000..").unwrap();
+        let mut records = crate::tests::get_data_to_write();
+
+        assert_eq!(tagger.tag(&mut records[0]), Some("large"));
+        assert_eq!(tagger.tag(&mut records[1]), None);
+
+        assert_eq!(
+            records[0].metadata().get(CATEGORY_KEY).map(String::as_str),
+            Some("large")
+        );
+        assert!(records[1].metadata().is_empty());
+    }
+
+    #[test]
+    fn test_rule_order() {
+        let tagger = Tagger::new(vec![
+            TagRule {
+                min_amount: Some(1000),
+                ..TagRule::new("first")
+            },
+            TagRule::new("second"),
+        ]);
+
+        assert_eq!(tagger.rules().len(), 2);
+        assert_eq!(
+            crate::tests::get_data_to_write()
+                .iter()
+                .map(|record| tagger.category(record).unwrap())
+                .collect::<Vec<_>>(),
+            ["first", "first", "second"]
+        );
+    }
+
+    #[rstest]
+    #[case(
+        "no category",
+        "Invalid rule at line 1: `no category`: expected CATEGORY: CONDITION; ..."
+    )]
+    #[case(
+        "x: amount=10",
+        "Invalid condition at line 1: `amount=10`: expected description=REGEX, tx_type=TYPE or amount=MIN..MAX"
+    )]
+    #[case(
+        "x: owner=1",
+        "Invalid condition at line 1: `owner=1`: expected description=REGEX, tx_type=TYPE or amount=MIN..MAX"
+    )]
+    #[case(
+        "\nx: tx_type=LOAN",
+        "Invalid TX_TYPE at line 2: Invalid TX_TYPE: LOAN"
+    )]
+    fn test_parse_error(#[case] rules: &str, #[case] expected: &str) {
+        assert_eq!(Tagger::try_from(rules).unwrap_err().to_string(), expected);
+    }
+
+    #[test]
+    fn test_parse_invalid_regex() {
+        assert!(matches!(
+            Tagger::try_from("x: description=(unclosed"),
+            Err(TagRulesError::InvalidRegex { line: 1, .. })
+        ));
+    }
+}
@@ -1,7 +1,10 @@
 use super::YPBank;
 use super::errors::{ReadError, WriteError};
+use super::iter::{RecordIter, recover_nothing};
+use super::options::ParseContext;
 use super::record::Record;
-use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use super::writer::{RecordWriter, finish_nothing};
+use std::io::{BufRead, BufWriter, Read, Write};
 
 #[derive(Debug)]
 pub struct YPBankText {
@@ -9,33 +12,113 @@ pub struct YPBankText {
     pub records: Vec<Record>,
 }
 
+impl YPBankText {
+    /// Считать строки очередной записи вплоть до разделяющей записи пустой строки включительно
+    /// в указанный буфер, очистив его.
+    fn read_block<B: BufRead>(reader: &mut B, block: &mut Vec<u8>) -> Result<(), ReadError> {
+        block.clear();
+
+        loop {
+            let start = block.len();
+
+            if reader.read_until(b'\n', block)? == 0 || matches!(&block[start..], b"\n" | b"\r\n") {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Считать очередную запись о банковской операции в текстовом формате.
+    ///
+    /// Строки записи считываются из источника целиком до ее разбора, поэтому после ошибки
+    /// чтение может быть продолжено со следующей записи. Буферы для строк переиспользуются
+    /// при чтении следующих записей.
+    fn read_record<B: BufRead>(
+        reader: &mut B,
+        block: &mut Vec<u8>,
+        line: &mut String,
+        context: &ParseContext,
+    ) -> Result<Option<Record>, ReadError> {
+        if reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+
+        Self::read_block(reader, block)?;
+
+        Ok(Some(Record::from_text_with_buffer(
+            &mut block.as_slice(),
+            line,
+            context,
+        )?))
+    }
+
+    /// Записать очередную запись о банковской операции в текстовом формате.
+    ///
+    /// Записи отделяются друг от друга пустой строкой.
+    fn write_record<W: Write>(
+        writer: &mut BufWriter<W>,
+        record: &Record,
+        index: usize,
+    ) -> Result<(), WriteError> {
+        if index > 0 {
+            writer.write_all(b"\n")?;
+        }
+        record.to_text(writer)?;
+
+        Ok(())
+    }
+}
+
 impl YPBank for YPBankText {
+    /// Считанные записи о банковских операциях.
+    fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    /// Изменяемый список записей о банковских операциях.
+    fn records_mut(&mut self) -> &mut Vec<Record> {
+        &mut self.records
+    }
+
+    /// Создание набора из указанных записей о банковских операциях.
+    fn from_records(records: Vec<Record>) -> Self {
+        Self { records }
+    }
+
     /// Считать данные о банковских операциях в текстовом формате.
     fn read_from<R: Read>(r: &mut R) -> Result<Self, ReadError> {
-        let mut reader = BufReader::new(r);
+        let records = Self::iter_from(r).collect::<Result<_, _>>()?;
 
-        let mut records: Vec<Record> = vec![];
+        Ok(Self { records })
+    }
 
-        while !reader.fill_buf()?.is_empty() {
-            records.push(Record::from_text(&mut reader)?);
-        }
+    /// Последовательно считывать данные о банковских операциях в текстовом формате.
+    fn iter_from<R: Read>(r: R) -> RecordIter<R> {
+        let mut block = vec![];
+        let mut line = String::new();
 
-        Ok(Self { records })
+        RecordIter::new_with(r, move |reader, _index, context| {
+            Self::read_record(reader, &mut block, &mut line, context)
+        })
+        .with_recovery(recover_nothing)
     }
 
     /// Записать данные о банковских операциях в текстовом формате.
     fn write_to<W: Write>(&self, w: &mut W) -> Result<(), WriteError> {
-        let mut writer = BufWriter::new(w);
+        let mut writer = Self::writer_to(w);
 
-        for (i, record) in self.records.iter().enumerate() {
-            if i > 0 {
-                writer.write_all(b"\n")?;
-            }
-            record.to_text(&mut writer)?;
+        for record in &self.records {
+            writer.write_record(record)?;
         }
 
+        writer.finish()?;
+
         Ok(())
     }
+
+    /// Последовательно записывать данные о банковских операциях в текстовом формате.
+    fn writer_to<W: Write>(w: W) -> RecordWriter<W> {
+        RecordWriter::new(w, Self::write_record, finish_nothing)
+    }
 }
 
 #[cfg(test)]
@@ -44,7 +127,7 @@ mod tests {
     use crate::record::errors::ParseRecordFromTxtError;
     use crate::record::status::Status;
     use crate::record::tx_type::TxType;
-    use std::io::Cursor;
+    use std::io::{BufReader, Cursor};
 
     #[test]
     fn test_read_from_text_data_specification() {
@@ -132,12 +215,12 @@ DESCRIPTION: "User withdrawal"
 
         let result = result.unwrap_err();
         assert!(matches!(
-            result,
+            result.inner(),
             ReadError::FromText(ParseRecordFromTxtError::UnexpectedError(_))
         ));
         assert_eq!(
             result.to_string(),
-            "Text format parsing error: Unexpected error: stream did not contain valid UTF-8"
+            "Text format parsing error: Unexpected error: stream did not contain valid UTF-8 (at record 1, line 1, byte 0)"
         );
     }
 
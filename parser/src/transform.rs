@@ -0,0 +1,340 @@
+//! Модуль преобразования записей о транзакциях при потоковой обработке.
+//!
+//! Преобразование ([`Transform`]) получает очередную запись и возвращает измененную запись
+//! либо `None`, если запись следует отбросить. Преобразования встраиваются в поток записей
+//! функцией [`transform_records`] и объединяются в цепочку при помощи [`Vec`].
+
+use super::anonymize::Anonymizer;
+use super::errors::ReadError;
+use super::filter::Filter;
+use super::record::Record;
+use super::tagging::Tagger;
+use std::collections::HashMap;
+
+/// Преобразование записей о транзакциях.
+pub trait Transform {
+    /// Преобразовать очередную запись о транзакции.
+    ///
+    /// Возвращает `None`, если запись следует отбросить.
+    fn map(&mut self, record: Record) -> Option<Record>;
+}
+
+/// Реализация трейта [`Transform`] для функций.
+impl<F: FnMut(Record) -> Option<Record>> Transform for F {
+    /// Реализация метода [`Transform::map`] для функций.
+    fn map(&mut self, record: Record) -> Option<Record> {
+        self(record)
+    }
+}
+
+/// Реализация трейта [`Transform`] для [`Box<dyn Transform>`].
+impl Transform for Box<dyn Transform + '_> {
+    /// Реализация метода [`Transform::map`] для [`Box<dyn Transform>`].
+    fn map(&mut self, record: Record) -> Option<Record> {
+        (**self).map(record)
+    }
+}
+
+/// Реализация трейта [`Transform`] для [`Box<dyn Transform + Send>`].
+impl Transform for Box<dyn Transform + Send + '_> {
+    /// Реализация метода [`Transform::map`] для [`Box<dyn Transform + Send>`].
+    fn map(&mut self, record: Record) -> Option<Record> {
+        (**self).map(record)
+    }
+}
+
+/// Реализация трейта [`Transform`] для [`Vec<T>`]: цепочка преобразований, применяемых
+/// по порядку, пока запись не будет отброшена.
+impl<T: Transform> Transform for Vec<T> {
+    /// Реализация метода [`Transform::map`] для [`Vec<T>`].
+    fn map(&mut self, record: Record) -> Option<Record> {
+        self.iter_mut()
+            .try_fold(record, |record, transform| transform.map(record))
+    }
+}
+
+/// Реализация трейта [`Transform`] для [`Filter`]: отбрасываются записи, не удовлетворяющие
+/// фильтру.
+impl Transform for Filter {
+    /// Реализация метода [`Transform::map`] для [`Filter`].
+    fn map(&mut self, record: Record) -> Option<Record> {
+        self.matches(&record).then_some(record)
+    }
+}
+
+/// Реализация трейта [`Transform`] для [`Anonymizer`].
+impl Transform for Anonymizer {
+    /// Реализация метода [`Transform::map`] для [`Anonymizer`].
+    fn map(&mut self, mut record: Record) -> Option<Record> {
+        self.anonymize(&mut record);
+
+        Some(record)
+    }
+}
+
+/// Реализация трейта [`Transform`] для [`Tagger`]: записям присваиваются категории.
+impl Transform for Tagger {
+    /// Реализация метода [`Transform::map`] для [`Tagger`].
+    fn map(&mut self, mut record: Record) -> Option<Record> {
+        self.tag(&mut record);
+
+        Some(record)
+    }
+}
+
+/// Заменить цифры в строке (номера счетов, карт, телефонов) символом `*`.
+pub(crate) fn mask_digits(s: &str) -> String {
+    s.chars()
+        .map(|c| if c.is_numeric() { '*' } else { c })
+        .collect()
+}
+
+/// Маскирование цифр в описаниях транзакций символом `*`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MaskDescriptions;
+
+/// Реализация трейта [`Transform`] для [`MaskDescriptions`].
+impl Transform for MaskDescriptions {
+    /// Реализация метода [`Transform::map`] для [`MaskDescriptions`].
+    fn map(&mut self, mut record: Record) -> Option<Record> {
+        if record.description().chars().any(char::is_numeric) {
+            let description = mask_digits(record.description());
+            record.set_description(description);
+        }
+
+        Some(record)
+    }
+}
+
+/// Сдвиг меток времени транзакций на указанное количество миллисекунд (например, для
+/// исправления выгрузок с неверным часовым поясом).
+///
+/// Метки времени, выходящие за пределы допустимых значений, ограничиваются ими.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShiftTimestamps(pub i64);
+
+/// Реализация трейта [`Transform`] для [`ShiftTimestamps`].
+impl Transform for ShiftTimestamps {
+    /// Реализация метода [`Transform::map`] для [`ShiftTimestamps`].
+    fn map(&mut self, mut record: Record) -> Option<Record> {
+        let timestamp = record.timestamp().saturating_add_signed(self.0);
+        record.set_timestamp(timestamp);
+
+        Some(record)
+    }
+}
+
+/// Замена идентификаторов участников (FROM_USER_ID и TO_USER_ID) по таблице соответствия,
+/// например, при слиянии систем с разной нумерацией клиентов.
+///
+/// Идентификаторы, отсутствующие в таблице, не изменяются.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RemapUserIds {
+    /// Новые идентификаторы участников по исходным.
+    mapping: HashMap<u64, u64>,
+}
+
+impl RemapUserIds {
+    /// Создание преобразования с указанной таблицей соответствия идентификаторов.
+    pub fn new(mapping: HashMap<u64, u64>) -> Self {
+        Self { mapping }
+    }
+
+    /// Новый идентификатор участника с указанным исходным идентификатором.
+    fn remap(&self, user_id: u64) -> u64 {
+        self.mapping.get(&user_id).copied().unwrap_or(user_id)
+    }
+}
+
+/// Реализация трейта [`FromIterator<(u64, u64)>`] для [`RemapUserIds`].
+impl FromIterator<(u64, u64)> for RemapUserIds {
+    /// Реализация метода [`FromIterator<(u64, u64)>::from_iter`] для [`RemapUserIds`].
+    fn from_iter<I: IntoIterator<Item = (u64, u64)>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+/// Реализация трейта [`Transform`] для [`RemapUserIds`].
+impl Transform for RemapUserIds {
+    /// Реализация метода [`Transform::map`] для [`RemapUserIds`].
+    fn map(&mut self, mut record: Record) -> Option<Record> {
+        let from_user_id = self.remap(record.from_user_id());
+        let to_user_id = self.remap(record.to_user_id());
+
+        record
+            .set_from_user_id(from_user_id)
+            .set_to_user_id(to_user_id);
+
+        Some(record)
+    }
+}
+
+/// Итератор записей о транзакциях, преобразованных указанным преобразованием
+/// (см. [`transform_records`]).
+#[derive(Debug, Clone)]
+pub struct Transformed<I, T> {
+    /// Исходные записи.
+    records: I,
+
+    /// Преобразование записей.
+    transform: T,
+}
+
+/// Реализация трейта [`Iterator`] для [`Transformed`].
+impl<I, T> Iterator for Transformed<I, T>
+where
+    I: Iterator<Item = Result<Record, ReadError>>,
+    T: Transform,
+{
+    type Item = Result<Record, ReadError>;
+
+    /// Реализация метода [`Iterator::next`] для [`Transformed`].
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.records.next()? {
+                Ok(record) => {
+                    if let Some(record) = self.transform.map(record) {
+                        return Some(Ok(record));
+                    }
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Преобразовать поток записей о транзакциях, например, считываемых [`crate::RecordIter`],
+/// отбрасывая записи, для которых преобразование вернуло `None`.
+///
+/// Ошибки чтения передаются без изменений.
+pub fn transform_records<I, T>(records: I, transform: T) -> Transformed<I::IntoIter, T>
+where
+    I: IntoIterator<Item = Result<Record, ReadError>>,
+    T: Transform,
+{
+    Transformed {
+        records: records.into_iter(),
+        transform,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AnonymizePolicy, Status, TxType};
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("Card 4276 1234", "Card **** ****")]
+    #[case("Terminal deposit", "Terminal deposit")]
+    #[case("", "")]
+    fn test_mask_descriptions(#[case] description: &str, #[case] expected: &str) {
+        let mut record = crate::tests::get_data_to_write().remove(0);
+        record.set_description(description);
+
+        let record = MaskDescriptions.map(record).unwrap();
+
+        assert_eq!(record.description(), expected);
+    }
+
+    #[rstest]
+    #[case(1633036800000, 3_600_000, 1633040400000)]
+    #[case(1633036800000, -3_600_000, 1633033200000)]
+    #[case(1000, -5000, 0)]
+    #[case(u64::MAX - 1, 10, u64::MAX)]
+    fn test_shift_timestamps(#[case] timestamp: u64, #[case] shift: i64, #[case] expected: u64) {
+        let mut record = crate::tests::get_data_to_write().remove(0);
+        record.set_timestamp(timestamp);
+
+        let record = ShiftTimestamps(shift).map(record).unwrap();
+
+        assert_eq!(record.timestamp(), expected);
+    }
+
+    #[test]
+    fn test_remap_user_ids() {
+        let records = crate::tests::get_data_to_write();
+        let mut remap = RemapUserIds::from_iter([(9876543210987654, 42), (5, 6)]);
+
+        let result = records
+            .into_iter()
+            .map(|record| remap.map(record).unwrap())
+            .map(|record| (record.from_user_id(), record.to_user_id()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(result, [(0, 42), (1231231231231231, 42), (42, 0)]);
+    }
+
+    #[test]
+    fn test_chain() {
+        let filter = Filter {
+            status: Some(Status::Success),
+            ..Filter::default()
+        };
+        let mut chain: Vec<Box<dyn Transform>> = vec![
+            Box::new(filter),
+            Box::new(ShiftTimestamps(1)),
+            Box::new(|mut record: Record| {
+                record.set_description("Changed");
+                Some(record)
+            }),
+        ];
+
+        let records = crate::tests::get_data_to_write();
+        let result = records
+            .into_iter()
+            .filter_map(|record| chain.map(record))
+            .collect::<Vec<_>>();
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].timestamp(), 1633036800001);
+        assert!(
+            result
+                .iter()
+                .all(|record| record.description() == "Changed")
+        );
+    }
+
+    #[test]
+    fn test_transform_records() {
+        let records = crate::tests::get_data_to_write();
+        let source = vec![
+            Ok(records[0].clone()),
+            Err(ReadError::Io(std::io::Error::other("broken"))),
+            Ok(records[1].clone()),
+            Ok(records[2].clone()),
+        ];
+
+        let result = transform_records(source, |record: Record| {
+            (*record.tx_type() != TxType::Transfer).then_some(record)
+        })
+        .map(|record| {
+            record
+                .map(|record| record.tx_id())
+                .map_err(|e| e.to_string())
+        })
+        .collect::<Vec<_>>();
+
+        assert_eq!(
+            result,
+            [
+                Ok(records[0].tx_id()),
+                Err("Read data error: broken".to_string()),
+                Ok(records[2].tx_id()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_anonymizer_transform() {
+        let mut anonymizer = Anonymizer::new(AnonymizePolicy::default());
+
+        let record = anonymizer
+            .map(crate::tests::get_data_to_write().remove(1))
+            .unwrap();
+
+        assert_eq!(record.from_user_id(), 1);
+        assert_eq!(record.to_user_id(), 2);
+        assert_eq!(record.description(), "");
+    }
+}
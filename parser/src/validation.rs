@@ -0,0 +1,642 @@
+//! Модуль проверки смысловой корректности считанных записей о транзакциях.
+
+use super::record::{Record, RecordBuilder, TxType};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Важность найденного нарушения.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Подозрительная, но допустимая запись.
+    Warning,
+
+    /// Некорректная запись.
+    Error,
+}
+
+/// Реализация трейта [`fmt::Display`] для [`Severity`].
+impl fmt::Display for Severity {
+    /// Реализация метода [`fmt::Display::fmt`] для [`Severity`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        };
+
+        write!(f, "{s}")
+    }
+}
+
+/// Параметры проверки записей о транзакциях.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationConfig {
+    /// Минимальная допустимая метка времени (включительно).
+    pub min_timestamp: u64,
+
+    /// Максимальная допустимая метка времени (не включительно).
+    pub max_timestamp: u64,
+
+    /// Проверка правдоподобия меток времени, нарушения которой являются ошибками
+    /// (по умолчанию не выполняется).
+    pub timestamp_check: Option<TimestampCheck>,
+
+    /// Важность нарушений смысловых правил операций: перевода самому себе, нулевой суммы,
+    /// депозита с отправителем и обналичивания с получателем.
+    ///
+    /// По умолчанию нулевая сумма не проверяется, а остальные нарушения имеют важность
+    /// [`IssueKind::severity`].
+    pub semantic_rules: Option<Severity>,
+}
+
+/// Параметры проверки правдоподобия меток времени.
+///
+/// Позволяет отвергать записи с заведомо неверными метками времени, например, считанные
+/// из поврежденных файлов бинарного формата.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimestampCheck {
+    /// Допустимое опережение текущего времени в миллисекундах (например, из-за расхождения
+    /// часов источников данных).
+    pub max_future_skew: u64,
+
+    /// Допустимый диапазон меток времени `(начало включительно, конец не включительно)`.
+    pub window: Option<(u64, u64)>,
+}
+
+/// Реализация трейта [`Default`] для [`ValidationConfig`].
+impl Default for ValidationConfig {
+    /// Реализация метода [`Default::default`] для [`ValidationConfig`].
+    fn default() -> Self {
+        Self {
+            min_timestamp: RecordBuilder::MIN_TIMESTAMP,
+            max_timestamp: RecordBuilder::MAX_TIMESTAMP,
+            timestamp_check: None,
+            semantic_rules: None,
+        }
+    }
+}
+
+/// Вид найденного нарушения.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IssueKind {
+    /// TX_ID уже встречался в записи с указанным порядковым номером.
+    DuplicateTxId(usize),
+
+    /// Перевод, у которого отправитель совпадает с получателем.
+    SelfTransfer,
+
+    /// Депозит с указанным отправителем.
+    DepositWithSender(u64),
+
+    /// Обналичивание с указанным получателем.
+    WithdrawalWithReceiver(u64),
+
+    /// Операция с нулевой суммой.
+    ZeroAmount,
+
+    /// Метка времени вне допустимого диапазона.
+    TimestampOutOfRange(u64),
+
+    /// Метка времени, которая при чтении как знаковое 64-битное число (например, другими
+    /// системами) оказывается раньше 1970 года.
+    TimestampBeforeEpoch(u64),
+
+    /// Метка времени позже текущего времени с учетом допустимого опережения.
+    TimestampInFuture {
+        /// Метка времени.
+        timestamp: u64,
+
+        /// Максимальная допустимая метка времени на момент проверки.
+        latest: u64,
+    },
+
+    /// Метка времени вне указанного пользователем диапазона.
+    TimestampOutsideWindow {
+        /// Метка времени.
+        timestamp: u64,
+
+        /// Начало диапазона (включительно).
+        since: u64,
+
+        /// Конец диапазона (не включительно).
+        until: u64,
+    },
+}
+
+impl IssueKind {
+    /// Важность нарушения данного вида по умолчанию.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::SelfTransfer | Self::ZeroAmount | Self::TimestampOutOfRange(_) => {
+                Severity::Warning
+            }
+            Self::DuplicateTxId(_)
+            | Self::DepositWithSender(_)
+            | Self::WithdrawalWithReceiver(_)
+            | Self::TimestampBeforeEpoch(_)
+            | Self::TimestampInFuture { .. }
+            | Self::TimestampOutsideWindow { .. } => Severity::Error,
+        }
+    }
+}
+
+/// Реализация трейта [`fmt::Display`] для [`IssueKind`].
+impl fmt::Display for IssueKind {
+    /// Реализация метода [`fmt::Display::fmt`] для [`IssueKind`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateTxId(first) => {
+                write!(f, "duplicate TX_ID (first seen in record {first})")
+            }
+            Self::SelfTransfer => write!(f, "TRANSFER with FROM_USER_ID equal to TO_USER_ID"),
+            Self::DepositWithSender(from) => write!(f, "DEPOSIT with FROM_USER_ID {from}"),
+            Self::WithdrawalWithReceiver(to) => write!(f, "WITHDRAWAL with TO_USER_ID {to}"),
+            Self::ZeroAmount => write!(f, "zero AMOUNT"),
+            Self::TimestampOutOfRange(timestamp) => {
+                write!(f, "TIMESTAMP {timestamp} is out of range")
+            }
+            Self::TimestampBeforeEpoch(timestamp) => {
+                write!(
+                    f,
+                    "TIMESTAMP {timestamp} is before 1970 when read as a signed 64-bit number"
+                )
+            }
+            Self::TimestampInFuture { timestamp, latest } => {
+                write!(
+                    f,
+                    "TIMESTAMP {timestamp} is in the future (later than {latest})"
+                )
+            }
+            Self::TimestampOutsideWindow {
+                timestamp,
+                since,
+                until,
+            } => {
+                write!(
+                    f,
+                    "TIMESTAMP {timestamp} is outside the window {since}..{until}"
+                )
+            }
+        }
+    }
+}
+
+/// Нарушение, найденное в записи о транзакции.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    /// Порядковый номер записи (начиная с 1).
+    pub record: usize,
+
+    /// Идентификатор транзакции.
+    pub tx_id: u64,
+
+    /// Вид нарушения.
+    pub kind: IssueKind,
+
+    /// Важность нарушения.
+    pub severity: Severity,
+}
+
+impl ValidationIssue {
+    /// Важность нарушения.
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+}
+
+/// Реализация трейта [`fmt::Display`] для [`ValidationIssue`].
+impl fmt::Display for ValidationIssue {
+    /// Реализация метода [`fmt::Display::fmt`] для [`ValidationIssue`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: record {} (TX_ID {}): {}",
+            self.severity(),
+            self.record,
+            self.tx_id,
+            self.kind
+        )
+    }
+}
+
+/// Результат проверки записей о транзакциях.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// Найденные нарушения в порядке следования записей.
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Нарушения, являющиеся ошибками.
+    pub fn errors(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity() == Severity::Error)
+    }
+
+    /// Нарушения, являющиеся предупреждениями.
+    pub fn warnings(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity() == Severity::Warning)
+    }
+
+    /// Признак отсутствия ошибок (предупреждения допускаются).
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+}
+
+/// Реализация трейта [`fmt::Display`] для [`ValidationReport`].
+impl fmt::Display for ValidationReport {
+    /// Реализация метода [`fmt::Display::fmt`] для [`ValidationReport`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in &self.issues {
+            writeln!(f, "{issue}")?;
+        }
+
+        write!(
+            f,
+            "{} errors, {} warnings",
+            self.errors().count(),
+            self.warnings().count()
+        )
+    }
+}
+
+/// Последовательная проверка записей о транзакциях по одной, например, при потоковом чтении
+/// источника без загрузки записей в память.
+///
+/// Для обнаружения повторяющихся TX_ID хранит TX_ID всех проверенных записей.
+#[derive(Debug, Clone, Default)]
+pub struct Validator {
+    /// Параметры проверки.
+    config: ValidationConfig,
+
+    /// Порядковые номера первых записей с каждым из встреченных TX_ID.
+    seen: HashMap<u64, usize>,
+
+    /// Максимальная правдоподобная метка времени: текущее время на момент создания проверки
+    /// с учетом допустимого опережения (если проверка правдоподобия включена).
+    latest_timestamp: Option<u64>,
+}
+
+impl Validator {
+    /// Создание проверки записей с указанными параметрами.
+    pub fn new(config: ValidationConfig) -> Self {
+        let latest_timestamp = config.timestamp_check.map(|check| {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_millis() as u64);
+
+            now.saturating_add(check.max_future_skew)
+        });
+
+        Self {
+            config,
+            seen: HashMap::new(),
+            latest_timestamp,
+        }
+    }
+
+    /// Проверить правдоподобие метки времени записи.
+    fn check_timestamp(&self, timestamp: u64) -> Option<IssueKind> {
+        let check = self.config.timestamp_check?;
+
+        if i64::try_from(timestamp).is_err() {
+            return Some(IssueKind::TimestampBeforeEpoch(timestamp));
+        }
+
+        if let Some(latest) = self.latest_timestamp
+            && timestamp > latest
+        {
+            return Some(IssueKind::TimestampInFuture { timestamp, latest });
+        }
+
+        match check.window {
+            Some((since, until)) if !(since..until).contains(&timestamp) => {
+                Some(IssueKind::TimestampOutsideWindow {
+                    timestamp,
+                    since,
+                    until,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Проверить очередную запись с указанным порядковым номером (начиная с 1).
+    ///
+    /// Возвращает нарушения, найденные в записи, в том числе повторение TX_ID одной из ранее
+    /// проверенных записей.
+    pub fn check(&mut self, number: usize, record: &Record) -> Vec<ValidationIssue> {
+        let mut issues = vec![];
+
+        let semantic_rules = self.config.semantic_rules;
+
+        let mut push = |kind: IssueKind, severity: Option<Severity>| {
+            issues.push(ValidationIssue {
+                record: number,
+                tx_id: record.tx_id(),
+                severity: severity.unwrap_or_else(|| kind.severity()),
+                kind,
+            })
+        };
+
+        if let Some(&first) = self.seen.get(&record.tx_id()) {
+            push(IssueKind::DuplicateTxId(first), None);
+        } else {
+            self.seen.insert(record.tx_id(), number);
+        }
+
+        match record.tx_type() {
+            TxType::Transfer if record.from_user_id() == record.to_user_id() => {
+                push(IssueKind::SelfTransfer, semantic_rules);
+            }
+            TxType::Deposit if record.from_user_id() != 0 => {
+                push(
+                    IssueKind::DepositWithSender(record.from_user_id()),
+                    semantic_rules,
+                );
+            }
+            TxType::Withdrawal if record.to_user_id() != 0 => {
+                push(
+                    IssueKind::WithdrawalWithReceiver(record.to_user_id()),
+                    semantic_rules,
+                );
+            }
+            _ => {}
+        }
+
+        if semantic_rules.is_some() && record.wide_amount() == 0 {
+            push(IssueKind::ZeroAmount, semantic_rules);
+        }
+
+        // Неправдоподобная метка времени не дублируется предупреждением о выходе из диапазона.
+        if let Some(kind) = self.check_timestamp(record.timestamp()) {
+            push(kind, None);
+        } else if !(self.config.min_timestamp..self.config.max_timestamp)
+            .contains(&record.timestamp())
+        {
+            push(IssueKind::TimestampOutOfRange(record.timestamp()), None);
+        }
+
+        issues
+    }
+}
+
+/// Проверить записи о транзакциях с учетом указанных параметров.
+pub(crate) fn validate(records: &[Record], config: &ValidationConfig) -> ValidationReport {
+    let mut validator = Validator::new(config.clone());
+
+    ValidationReport {
+        issues: records
+            .iter()
+            .enumerate()
+            .flat_map(|(index, record)| validator.check(index + 1, record))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::Status;
+    use rstest::rstest;
+
+    fn get_record(tx_id: u64, tx_type: TxType, from: u64, to: u64, timestamp: u64) -> Record {
+        Record::new(
+            tx_id,
+            tx_type,
+            from,
+            to,
+            100,
+            timestamp,
+            Status::Success,
+            "".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_validate_correct_records() {
+        let records = crate::tests::get_data_to_write();
+
+        let report = validate(&records, &ValidationConfig::default());
+
+        assert!(report.issues.is_empty());
+        assert!(report.is_valid());
+        assert_eq!(report.to_string(), "0 errors, 0 warnings");
+    }
+
+    #[test]
+    fn test_validate_issues() {
+        let records = vec![
+            get_record(1, TxType::Deposit, 5, 0, 1633036800000),
+            get_record(2, TxType::Transfer, 7, 7, 1633036800000),
+            get_record(1, TxType::Withdrawal, 3, 4, 1633036800000),
+            get_record(3, TxType::Transfer, 1, 2, 10),
+        ];
+
+        let report = validate(&records, &ValidationConfig::default());
+
+        assert_eq!(
+            report.issues,
+            vec![
+                ValidationIssue {
+                    record: 1,
+                    tx_id: 1,
+                    kind: IssueKind::DepositWithSender(5),
+                    severity: Severity::Error,
+                },
+                ValidationIssue {
+                    record: 2,
+                    tx_id: 2,
+                    kind: IssueKind::SelfTransfer,
+                    severity: Severity::Warning,
+                },
+                ValidationIssue {
+                    record: 3,
+                    tx_id: 1,
+                    kind: IssueKind::DuplicateTxId(1),
+                    severity: Severity::Error,
+                },
+                ValidationIssue {
+                    record: 3,
+                    tx_id: 1,
+                    kind: IssueKind::WithdrawalWithReceiver(4),
+                    severity: Severity::Error,
+                },
+                ValidationIssue {
+                    record: 4,
+                    tx_id: 3,
+                    kind: IssueKind::TimestampOutOfRange(10),
+                    severity: Severity::Warning,
+                },
+            ]
+        );
+        assert!(!report.is_valid());
+        assert_eq!(
+            report.to_string(),
+            "error: record 1 (TX_ID 1): DEPOSIT with FROM_USER_ID 5
+warning: record 2 (TX_ID 2): TRANSFER with FROM_USER_ID equal to TO_USER_ID
+error: record 3 (TX_ID 1): duplicate TX_ID (first seen in record 1)
+error: record 3 (TX_ID 1): WITHDRAWAL with TO_USER_ID 4
+warning: record 4 (TX_ID 3): TIMESTAMP 10 is out of range
+3 errors, 2 warnings"
+        );
+    }
+
+    #[test]
+    fn test_validate_warnings_only() {
+        let records = vec![get_record(1, TxType::Transfer, 1, 2, 10)];
+
+        let config = ValidationConfig {
+            min_timestamp: 100,
+            max_timestamp: 200,
+            ..Default::default()
+        };
+        let report = validate(&records, &config);
+
+        assert!(report.is_valid());
+        assert_eq!(report.warnings().count(), 1);
+    }
+
+    #[test]
+    fn test_validate_timestamp_check() {
+        let records = vec![
+            get_record(1, TxType::Transfer, 1, 2, 1633036800000),
+            get_record(2, TxType::Transfer, 1, 2, u64::MAX),
+            get_record(3, TxType::Transfer, 1, 2, RecordBuilder::MAX_TIMESTAMP * 2),
+            get_record(4, TxType::Transfer, 1, 2, 1500000000000),
+            get_record(5, TxType::Transfer, 1, 2, 10),
+        ];
+
+        let config = ValidationConfig {
+            timestamp_check: Some(TimestampCheck {
+                max_future_skew: 60_000,
+                window: Some((1, 1600000000000)),
+            }),
+            ..Default::default()
+        };
+        let report = validate(&records, &config);
+
+        let kinds = report
+            .issues
+            .iter()
+            .map(|issue| (issue.tx_id, issue.kind.clone()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(kinds.len(), 4);
+        assert_eq!(
+            kinds[0],
+            (
+                1,
+                IssueKind::TimestampOutsideWindow {
+                    timestamp: 1633036800000,
+                    since: 1,
+                    until: 1600000000000
+                }
+            )
+        );
+        assert_eq!(kinds[1], (2, IssueKind::TimestampBeforeEpoch(u64::MAX)));
+        assert!(matches!(
+            kinds[2],
+            (3, IssueKind::TimestampInFuture { timestamp, latest })
+                if timestamp == RecordBuilder::MAX_TIMESTAMP * 2 && latest < timestamp
+        ));
+        assert_eq!(kinds[3], (5, IssueKind::TimestampOutOfRange(10)));
+
+        assert_eq!(report.errors().count(), 3);
+        assert_eq!(
+            report.issues[1].to_string(),
+            "error: record 2 (TX_ID 2): TIMESTAMP 18446744073709551615 is before 1970 when read as a signed 64-bit number"
+        );
+        assert_eq!(
+            report.issues[0].kind.to_string(),
+            "TIMESTAMP 1633036800000 is outside the window 1..1600000000000"
+        );
+    }
+
+    #[test]
+    fn test_validate_timestamp_check_disabled() {
+        let records = vec![get_record(1, TxType::Transfer, 1, 2, u64::MAX)];
+
+        let report = validate(&records, &ValidationConfig::default());
+
+        assert_eq!(
+            report.issues[0].kind,
+            IssueKind::TimestampOutOfRange(u64::MAX)
+        );
+        assert!(report.is_valid());
+    }
+
+    #[rstest]
+    #[case(Severity::Warning, true)]
+    #[case(Severity::Error, false)]
+    fn test_validate_semantic_rules(#[case] severity: Severity, #[case] valid: bool) {
+        let mut zero = get_record(3, TxType::Deposit, 0, 2, 1633036800000);
+        zero.set_amount(0);
+
+        let records = vec![
+            get_record(1, TxType::Transfer, 7, 7, 1633036800000),
+            get_record(2, TxType::Withdrawal, 3, 4, 1633036800000),
+            zero,
+        ];
+
+        let config = ValidationConfig {
+            semantic_rules: Some(severity),
+            ..Default::default()
+        };
+        let report = validate(&records, &config);
+
+        assert_eq!(
+            report
+                .issues
+                .iter()
+                .map(|issue| (issue.kind.clone(), issue.severity()))
+                .collect::<Vec<_>>(),
+            vec![
+                (IssueKind::SelfTransfer, severity),
+                (IssueKind::WithdrawalWithReceiver(4), severity),
+                (IssueKind::ZeroAmount, severity),
+            ]
+        );
+        assert_eq!(report.is_valid(), valid);
+        assert_eq!(
+            report.issues[2].to_string(),
+            format!("{severity}: record 3 (TX_ID 3): zero AMOUNT")
+        );
+    }
+
+    #[test]
+    fn test_validate_zero_amount_not_checked_by_default() {
+        let mut record = get_record(1, TxType::Deposit, 0, 2, 1633036800000);
+        record.set_amount(0);
+
+        assert!(
+            validate(&[record], &ValidationConfig::default())
+                .issues
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_validator() {
+        let mut validator = Validator::new(ValidationConfig::default());
+
+        assert!(
+            validator
+                .check(3, &get_record(1, TxType::Transfer, 1, 2, 1633036800000))
+                .is_empty()
+        );
+        assert_eq!(
+            validator.check(7, &get_record(1, TxType::Deposit, 0, 2, 1633036800000)),
+            vec![ValidationIssue {
+                record: 7,
+                tx_id: 1,
+                kind: IssueKind::DuplicateTxId(3),
+                severity: Severity::Error,
+            }]
+        );
+    }
+}
@@ -0,0 +1,70 @@
+//! Модуль привязок WebAssembly для чтения и конвертации данных о транзакциях в браузере.
+//!
+//! Функции экспортируются в JavaScript при помощи `wasm-bindgen`, поэтому загруженные
+//! пользователем файлы можно просматривать и конвертировать на стороне клиента без обращения
+//! к серверу. Форматы указываются так же, как в конвертере (`text`, `csv`, `bin`, `json`,
+//! `jsonl`), а ошибки чтения и записи передаются в JavaScript исключениями `Error`
+//! с текстом ошибки.
+
+use super::YPBankImpl;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// Считать записи о транзакциях в заданном формате из данных файла.
+///
+/// Возвращает массив объектов с полями записи, как в формате JSON (`TX_ID`, `TX_TYPE`, ...).
+/// Целочисленные поля передаются значениями `BigInt`, так как идентификаторы и суммы могут
+/// превышать `Number.MAX_SAFE_INTEGER`, а дополнительные поля — объектом.
+#[wasm_bindgen]
+pub fn parse(bytes: &[u8], format: &str) -> Result<JsValue, JsError> {
+    let records = YPBankImpl::try_from(format)?.read_from(&mut &bytes[..])?;
+
+    let serializer = serde_wasm_bindgen::Serializer::new()
+        .serialize_maps_as_objects(true)
+        .serialize_large_number_types_as_bigints(true);
+
+    Ok(records.serialize(&serializer)?)
+}
+
+/// Конвертировать данные файла о транзакциях из одного формата в другой.
+///
+/// Возвращает данные в целевом формате (`Uint8Array` в JavaScript).
+#[wasm_bindgen]
+pub fn convert(bytes: &[u8], from: &str, to: &str) -> Result<Vec<u8>, JsError> {
+    let records = YPBankImpl::try_from(from)?.read_from(&mut &bytes[..])?;
+
+    let mut data = vec![];
+    YPBankImpl::try_from(to)?.write_to(records, &mut data)?;
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("text", "bin")]
+    #[case("bin", "csv")]
+    #[case("csv", "jsonl")]
+    fn test_convert(#[case] from: &str, #[case] to: &str) {
+        let records = crate::tests::get_data_to_write();
+
+        let mut data = vec![];
+        YPBankImpl::try_from(from)
+            .unwrap()
+            .write_to(records.clone(), &mut data)
+            .unwrap();
+
+        let converted = convert(&data, from, to).unwrap();
+
+        assert_eq!(
+            YPBankImpl::try_from(to)
+                .unwrap()
+                .read_from(&mut converted.as_slice())
+                .unwrap(),
+            records
+        );
+    }
+}
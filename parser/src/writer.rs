@@ -0,0 +1,389 @@
+//! Модуль потоковой записи данных о транзакциях.
+
+use super::errors::WriteError;
+use super::metrics::Metrics;
+use super::options::WriteOptions;
+use super::record::Record;
+use super::schema::SchemaVersion;
+use std::io::{BufWriter, Write};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Функция записи очередной записи о транзакции в назначение.
+///
+/// Принимает порядковый номер записываемой записи (начиная с 0).
+pub(crate) type WriteRecordFn<W> = fn(&mut BufWriter<W>, &Record, usize) -> Result<(), WriteError>;
+
+/// Функция записи очередной записи о транзакции, использующая параметры формата
+/// (например, разметку полей, см. [`crate::FixedWidthLayout`]).
+type WriteRecordWithFn<W> =
+    Box<dyn FnMut(&mut BufWriter<W>, &Record, usize) -> Result<(), WriteError> + Send>;
+
+/// Функция завершения записи данных, использующая параметры формата.
+type FinishWithFn<W> = Box<dyn FnOnce(&mut BufWriter<W>, usize) -> Result<(), WriteError> + Send>;
+
+/// Способ завершения записи данных.
+enum Finisher<W: Write> {
+    /// Функция формата, не имеющего параметров.
+    Fn(FinishFn<W>),
+
+    /// Функция формата, использующая его параметры.
+    With(FinishWithFn<W>),
+}
+
+/// Способ записи очередной записи о транзакции.
+enum RecordFormatter<W: Write> {
+    /// Функция формата, не имеющего параметров.
+    Fn(WriteRecordFn<W>),
+
+    /// Функция формата, использующая его параметры.
+    With(WriteRecordWithFn<W>),
+}
+
+/// Функция завершения записи данных (например, записи закрывающих символов формата).
+///
+/// Принимает общее количество записанных записей.
+pub(crate) type FinishFn<W> = fn(&mut BufWriter<W>, usize) -> Result<(), WriteError>;
+
+/// Потоковая запись данных о транзакциях в назначение по одной записи.
+///
+/// Для корректного завершения формата и сброса буфера необходимо вызвать [`RecordWriter::finish`].
+pub struct RecordWriter<W: Write> {
+    /// Буферизованное назначение.
+    writer: BufWriter<W>,
+
+    /// Функция записи очередной записи в заданном формате.
+    write_record: RecordFormatter<W>,
+
+    /// Функция завершения записи данных в заданном формате.
+    finish: Finisher<W>,
+
+    /// Количество уже записанных записей.
+    count: usize,
+
+    /// Версия схемы записываемых записей.
+    schema_version: SchemaVersion,
+
+    /// Получатель метрик записи (см. [`RecordWriter::metrics`]).
+    metrics: Option<Arc<dyn Metrics>>,
+
+    /// Момент записи первой записи для трассировки скорости записи.
+    #[cfg(feature = "tracing")]
+    started: Option<Instant>,
+}
+
+impl<W: Write> RecordWriter<W> {
+    /// Создание объекта потоковой записи над указанным назначением.
+    pub(crate) fn new(w: W, write_record: WriteRecordFn<W>, finish: FinishFn<W>) -> Self {
+        Self {
+            writer: BufWriter::with_capacity(WriteOptions::DEFAULT_BUFFER_CAPACITY, w),
+            write_record: RecordFormatter::Fn(write_record),
+            finish: Finisher::Fn(finish),
+            count: 0,
+            schema_version: SchemaVersion::default(),
+            metrics: None,
+            #[cfg(feature = "tracing")]
+            started: None,
+        }
+    }
+
+    /// Создание объекта потоковой записи над указанным назначением, функции записи и завершения
+    /// которого используют параметры формата.
+    pub(crate) fn new_with(
+        w: W,
+        write_record: impl FnMut(&mut BufWriter<W>, &Record, usize) -> Result<(), WriteError>
+        + Send
+        + 'static,
+        finish: impl FnOnce(&mut BufWriter<W>, usize) -> Result<(), WriteError> + Send + 'static,
+    ) -> Self {
+        Self {
+            writer: BufWriter::with_capacity(WriteOptions::DEFAULT_BUFFER_CAPACITY, w),
+            write_record: RecordFormatter::With(Box::new(write_record)),
+            finish: Finisher::With(Box::new(finish)),
+            count: 0,
+            schema_version: SchemaVersion::default(),
+            metrics: None,
+            #[cfg(feature = "tracing")]
+            started: None,
+        }
+    }
+
+    /// Установить версию схемы записываемых записей (по умолчанию [`SchemaVersion::V1`]).
+    ///
+    /// Запись, требующая более новой версии схемы (см. [`Record::schema_version`]),
+    /// не записывается, а возвращается ошибка [`WriteError::SchemaVersionRequired`].
+    pub fn schema_version(mut self, schema_version: SchemaVersion) -> Self {
+        self.schema_version = schema_version;
+        self
+    }
+
+    /// Записывать данные непосредственно в назначение, уже буферизованное вызывающей стороной
+    /// (например, [`BufWriter`]), не буферизуя их повторно.
+    ///
+    /// Должен вызываться до записи первой записи, в противном случае не имеет эффекта.
+    pub fn buffered(mut self) -> Self {
+        if self.count == 0 && self.writer.buffer().is_empty() {
+            let (w, _) = self.writer.into_parts();
+
+            // Буфер нулевой емкости передает данные в назначение без копирования.
+            self.writer = BufWriter::with_capacity(0, w);
+        }
+
+        self
+    }
+
+    /// Установить емкость буфера записи в байтах
+    /// (по умолчанию [`WriteOptions::DEFAULT_BUFFER_CAPACITY`]).
+    ///
+    /// Должен вызываться до записи первой записи, в противном случае не имеет эффекта.
+    pub fn buffer_capacity(mut self, capacity: usize) -> Self {
+        if self.count == 0 && self.writer.buffer().is_empty() {
+            let (w, _) = self.writer.into_parts();
+
+            self.writer = BufWriter::with_capacity(capacity, w);
+        }
+
+        self
+    }
+
+    /// Установить параметры записи: емкость буфера и версию схемы записываемых записей.
+    ///
+    /// Должен вызываться до записи первой записи (см. [`RecordWriter::buffer_capacity`]).
+    pub fn with_options(self, options: WriteOptions) -> Self {
+        self.buffer_capacity(options.buffer_capacity)
+            .schema_version(options.schema_version)
+    }
+
+    /// Сообщать указанному получателю метрик о каждой записанной записи (время записи)
+    /// и о каждой ошибке записи.
+    pub fn metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Записать очередную запись о транзакции.
+    pub fn write_record(&mut self, record: &Record) -> Result<(), WriteError> {
+        #[cfg(feature = "tracing")]
+        self.started.get_or_insert_with(Instant::now);
+
+        let started = self.metrics.as_ref().map(|_| Instant::now());
+        let result = self.format_record(record);
+
+        if let Some(metrics) = &self.metrics
+            && let Some(started) = started
+        {
+            match &result {
+                Ok(()) => metrics.record_written(started.elapsed()),
+                Err(_) => metrics.write_error(),
+            }
+        }
+
+        result
+    }
+
+    /// Записать очередную запись о транзакции в заданном формате.
+    fn format_record(&mut self, record: &Record) -> Result<(), WriteError> {
+        if record.schema_version() > self.schema_version {
+            return Err(WriteError::SchemaVersionRequired {
+                tx_id: record.tx_id(),
+                amount: record.wide_amount(),
+                required: record.schema_version(),
+                used: self.schema_version,
+            });
+        }
+
+        match &mut self.write_record {
+            RecordFormatter::Fn(write_record) => {
+                write_record(&mut self.writer, record, self.count)?
+            }
+            RecordFormatter::With(write_record) => {
+                write_record(&mut self.writer, record, self.count)?
+            }
+        }
+        self.count += 1;
+
+        Ok(())
+    }
+
+    /// Количество уже записанных записей.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Завершить запись данных, сбросить буфер и буфер назначения и вернуть исходное
+    /// назначение.
+    ///
+    /// С признаком крейта `tracing` о завершении записи сообщается событием уровня `INFO`
+    /// с количеством записей, временем и скоростью записи.
+    pub fn finish(mut self) -> Result<W, WriteError> {
+        match self.finish {
+            Finisher::Fn(finish) => finish(&mut self.writer, self.count)?,
+            Finisher::With(finish) => finish(&mut self.writer, self.count)?,
+        }
+
+        let mut w = self
+            .writer
+            .into_inner()
+            .map_err(|e| WriteError::Io(e.into_error()))?;
+        w.flush()?;
+
+        #[cfg(feature = "tracing")]
+        {
+            let elapsed = self
+                .started
+                .map(|started| started.elapsed())
+                .unwrap_or_default();
+
+            tracing::info!(
+                records = self.count,
+                elapsed_ms = elapsed.as_millis() as u64,
+                records_per_sec = self.count as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+                "finished writing"
+            );
+        }
+
+        Ok(w)
+    }
+}
+
+/// Функция завершения записи данных для форматов, не требующих завершающих символов.
+pub(crate) fn finish_nothing<W: Write>(
+    _writer: &mut BufWriter<W>,
+    _count: usize,
+) -> Result<(), WriteError> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::WriteError;
+    use crate::{SchemaVersion, WriteOptions, YPBankImpl};
+    use rstest::rstest;
+    use std::io::BufWriter;
+
+    #[test]
+    fn test_writer_round_trip() {
+        let records = crate::tests::get_data_to_write();
+
+        for format in ["text", "csv", "bin", "json", "jsonl"] {
+            let format = YPBankImpl::try_from(format).unwrap();
+
+            let mut writer = format.writer_to(vec![]);
+            for record in &records {
+                writer.write_record(record).unwrap();
+            }
+            assert_eq!(writer.count(), 3);
+
+            let buffer = writer.finish().unwrap();
+
+            let result = format.read_from(&mut buffer.as_slice()).unwrap();
+            assert_eq!(result, records);
+        }
+    }
+
+    #[rstest]
+    fn test_write_to_buffered(#[values("text", "csv", "bin", "json", "jsonl")] format: &str) {
+        let records = crate::tests::get_data_to_write();
+        let format = YPBankImpl::try_from(format).unwrap();
+
+        let mut expected = vec![];
+        format.write_to(records.clone(), &mut expected).unwrap();
+
+        let writer = format
+            .write_to_buffered(&records, BufWriter::new(vec![]))
+            .unwrap();
+
+        assert!(writer.buffer().is_empty());
+        assert_eq!(writer.get_ref(), &expected);
+    }
+
+    #[test]
+    fn test_writer_buffered_writes_through() {
+        let records = crate::tests::get_data_to_write();
+
+        let mut writer = YPBankImpl::Csv.writer_to(vec![]).buffered();
+        writer.write_record(&records[0]).unwrap();
+
+        assert!(writer.writer.buffer().is_empty());
+        assert!(!writer.writer.get_ref().is_empty());
+    }
+
+    #[test]
+    fn test_writer_options() {
+        let options = WriteOptions {
+            buffer_capacity: 64 * 1024,
+            schema_version: SchemaVersion::V2,
+        };
+
+        let mut writer = YPBankImpl::Bin.writer_to(vec![]).with_options(options);
+
+        assert_eq!(writer.writer.capacity(), 64 * 1024);
+
+        for record in crate::tests::get_wide_data_to_write() {
+            writer.write_record(&record).unwrap();
+        }
+        assert!(writer.writer.get_ref().is_empty());
+
+        // После записи первой записи емкость буфера не изменяется.
+        let writer = writer.buffer_capacity(16);
+        assert_eq!(writer.writer.capacity(), 64 * 1024);
+    }
+
+    #[rstest]
+    fn test_writer_wide_amounts_round_trip(
+        #[values("text", "csv", "bin", "json", "jsonl")] format: &str,
+    ) {
+        let records = crate::tests::get_wide_data_to_write();
+        let format = YPBankImpl::try_from(format).unwrap();
+
+        let mut writer = format.writer_to(vec![]).schema_version(SchemaVersion::V2);
+        for record in &records {
+            writer.write_record(record).unwrap();
+        }
+        let buffer = writer.finish().unwrap();
+
+        assert_eq!(format.read_from(&mut buffer.as_slice()).unwrap(), records);
+    }
+
+    #[test]
+    fn test_writer_schema_version_required() {
+        let records = crate::tests::get_wide_data_to_write();
+
+        let mut writer = YPBankImpl::Csv.writer_to(vec![]);
+        let err = writer.write_record(&records[0]).unwrap_err();
+
+        assert!(matches!(
+            err,
+            WriteError::SchemaVersionRequired {
+                tx_id: 1,
+                amount: -2500,
+                required: SchemaVersion::V2,
+                used: SchemaVersion::V1,
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Transaction 1 requires schema version 2 (AMOUNT -2500), but schema version 1 is used"
+        );
+        assert_eq!(writer.count(), 0);
+
+        writer.write_record(&records[3]).unwrap();
+        assert_eq!(writer.count(), 1);
+    }
+
+    #[test]
+    fn test_writer_empty() {
+        for (format, expected) in [
+            ("text", "".as_bytes()),
+            (
+                "csv",
+                b"TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n",
+            ),
+            ("json", b"[]\n"),
+        ] {
+            let writer = YPBankImpl::try_from(format).unwrap().writer_to(vec![]);
+
+            assert_eq!(writer.finish().unwrap(), expected);
+        }
+    }
+}
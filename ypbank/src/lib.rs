@@ -0,0 +1,10 @@
+//! Динамическая библиотека YPBank для вызова из других языков.
+//!
+//! Крейт `parser` собирается только как библиотека Rust, а интерфейс C (признак `ffi`,
+//! заголовочный файл `parser/include/ypbank.h`) и привязки WebAssembly (признак `wasm`)
+//! экспортируются из этой динамической библиотеки.
+
+#[cfg(feature = "ffi")]
+pub use parser::ffi;
+#[cfg(feature = "wasm")]
+pub use parser::wasm;